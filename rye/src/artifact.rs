@@ -0,0 +1,335 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Error};
+use configparser::ini::Ini;
+use once_cell::sync::Lazy;
+use pep508_rs::Requirement;
+use python_pkginfo::Metadata;
+use regex::Regex;
+use tempfile::tempdir;
+
+use crate::utils::{copy_dir, format_requirement, unpack_archive, CopyDirOptions, IoPathContext};
+
+static EXTRA_MARKER_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"^extra\s*==\s*["']([^"']+)["']$"#).unwrap());
+
+/// Files of an sdist that are replaced by the generated `pyproject.toml`.
+const REPLACED_FILES: &[&str] = &[
+    "PKG-INFO",
+    "setup.py",
+    "setup.cfg",
+    "pyproject.toml",
+    "MANIFEST.in",
+];
+
+/// Folders with tests that are carried over next to the sources.
+const TEST_DIRS: &[&str] = &["tests", "test"];
+
+/// Extensions of compiled modules that have no sources.
+const COMPILED_EXTENSIONS: &[&str] = &["so", "pyd", "dylib", "dll"];
+
+/// What was imported from a wheel or sdist.
+#[derive(Debug, Default)]
+pub struct ArtifactImport {
+    pub name: String,
+    pub version: String,
+    pub description: Option<String>,
+    pub author: Option<(String, String)>,
+    pub license: Option<String>,
+    pub requires_python: Option<String>,
+    pub dependencies: Vec<String>,
+    pub optional_dependencies: BTreeMap<String, Vec<String>>,
+    /// Entry points by group (`console_scripts`, `gui_scripts`, …).
+    pub entry_points: BTreeMap<String, BTreeMap<String, String>>,
+    /// The packages and modules (with their `.py` extension) placed into `src`.
+    pub packages: Vec<String>,
+    /// Things that could not be migrated and need manual attention.
+    pub follow_ups: Vec<String>,
+}
+
+/// Extracts the metadata of a wheel or sdist and unpacks its sources into a
+/// src layout below `dir`.
+pub fn import_artifact(artifact: &Path, dir: &Path) -> Result<ArtifactImport, Error> {
+    let file_name = artifact
+        .file_name()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let is_wheel = file_name.ends_with(".whl");
+    let contents = fs::read(artifact).path_context(artifact, "failed to read artifact")?;
+    let scratch = tempdir()?;
+    // sdists have a single toplevel folder, wheels are flat
+    unpack_archive(&contents, scratch.path(), if is_wheel { 0 } else { 1 })
+        .with_context(|| format!("failed to unpack {}", artifact.display()))?;
+    let root = scratch.path();
+
+    let (metadata_path, egg_info) = if is_wheel {
+        let dist_info = find_dir_with_suffix(root, ".dist-info")
+            .ok_or_else(|| anyhow!("{} has no .dist-info folder", file_name))?;
+        (dist_info.join("METADATA"), Some(dist_info))
+    } else {
+        let egg_info = find_dir_with_suffix(root, ".egg-info")
+            .or_else(|| find_dir_with_suffix(&root.join("src"), ".egg-info"));
+        (root.join("PKG-INFO"), egg_info)
+    };
+    let metadata = Metadata::parse(
+        &fs::read(&metadata_path).path_context(&metadata_path, "failed to read metadata")?,
+    )
+    .with_context(|| format!("invalid metadata in {}", file_name))?;
+
+    let mut rv = ArtifactImport {
+        name: metadata.name.clone(),
+        version: metadata.version.clone(),
+        description: metadata.summary.clone().filter(|x| !x.is_empty()),
+        author: match (metadata.author, metadata.author_email) {
+            (None, None) => None,
+            (author, email) => Some((author.unwrap_or_default(), email.unwrap_or_default())),
+        },
+        license: metadata.license.filter(|x| !x.is_empty()),
+        requires_python: metadata.requires_python,
+        ..ArtifactImport::default()
+    };
+
+    for dep in &metadata.requires_dist {
+        match split_requirement(dep) {
+            Ok((req, None)) => rv.dependencies.push(req),
+            Ok((req, Some(extra))) => rv.optional_dependencies.entry(extra).or_default().push(req),
+            Err(err) => rv
+                .follow_ups
+                .push(format!("requirement '{}': {}", dep, err)),
+        }
+    }
+
+    match egg_info.map(|x| x.join("entry_points.txt")) {
+        Some(path) if path.is_file() => rv.entry_points = read_entry_points(&path)?,
+        _ if !is_wheel && root.join("setup.py").is_file() => rv
+            .follow_ups
+            .push("entry points defined in setup.py were not migrated".into()),
+        _ => {}
+    }
+
+    let src_dir = dir.join("src");
+    if src_dir.exists() {
+        bail!("{} already exists", src_dir.display());
+    }
+    if is_wheel {
+        import_wheel_sources(root, &src_dir, &mut rv)?;
+    } else {
+        import_sdist_sources(root, dir, &src_dir, &mut rv)?;
+    }
+    if rv.packages.is_empty() {
+        rv.follow_ups
+            .push(format!("no Python sources were found in {}", file_name));
+    }
+
+    Ok(rv)
+}
+
+/// Splits a `Requires-Dist` entry into the requirement and the extra it belongs to.
+///
+/// Requirements whose marker combines the extra with other conditions cannot be
+/// expressed as optional dependency and fail.
+fn split_requirement(dep: &str) -> Result<(String, Option<String>), Error> {
+    let mut req = Requirement::from_str(dep).map_err(|err| anyhow!("{}", err))?;
+    let Some(marker) = req.marker.take() else {
+        return Ok((format_requirement(&req).to_string(), None));
+    };
+    let marker_str = marker.to_string();
+    if let Some(m) = EXTRA_MARKER_RE.captures(marker_str.trim()) {
+        return Ok((format_requirement(&req).to_string(), Some(m[1].to_string())));
+    }
+    if marker_str.contains("extra") {
+        bail!("the marker combines an extra with other conditions");
+    }
+    req.marker = Some(marker);
+    Ok((format_requirement(&req).to_string(), None))
+}
+
+/// Reads an `entry_points.txt` file.
+fn read_entry_points(path: &Path) -> Result<BTreeMap<String, BTreeMap<String, String>>, Error> {
+    let mut ini = Ini::new_cs();
+    let config = ini.load(path).map_err(|msg| anyhow!(msg))?;
+    let mut rv = BTreeMap::new();
+    for (group, entries) in config {
+        let entries = entries
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?.trim().to_string())))
+            .collect::<BTreeMap<_, _>>();
+        if !entries.is_empty() {
+            rv.insert(group, entries);
+        }
+    }
+    Ok(rv)
+}
+
+fn find_dir_with_suffix(dir: &Path, suffix: &str) -> Option<PathBuf> {
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .find(|x| x.is_dir() && x.to_string_lossy().ends_with(suffix))
+}
+
+/// Is this a package or module that belongs into `src`?
+fn is_python_source(path: &Path) -> bool {
+    if path.is_dir() {
+        path.join("__init__.py").is_file()
+    } else {
+        path.extension().map_or(false, |x| x == "py")
+    }
+}
+
+fn copy_source(from: &Path, src_dir: &Path, rv: &mut ArtifactImport) -> Result<(), Error> {
+    let name = from.file_name().expect("source has a name");
+    let target = src_dir.join(name);
+    if from.is_dir() {
+        fs::create_dir_all(&target).path_context(&target, "failed to create directory")?;
+        copy_dir(
+            from,
+            target.as_path(),
+            &CopyDirOptions {
+                exclude: Vec::new(),
+            },
+        )?;
+        for entry in walkdir::WalkDir::new(from)
+            .into_iter()
+            .filter_map(|x| x.ok())
+        {
+            if entry
+                .path()
+                .extension()
+                .map_or(false, |x| COMPILED_EXTENSIONS.iter().any(|ext| x == *ext))
+            {
+                rv.follow_ups.push(format!(
+                    "{} is a compiled module without sources",
+                    entry
+                        .path()
+                        .strip_prefix(from.parent().unwrap_or(from))?
+                        .display()
+                ));
+            }
+        }
+    } else {
+        fs::create_dir_all(src_dir).path_context(src_dir, "failed to create directory")?;
+        fs::copy(from, &target).path_context(&target, "failed to copy file")?;
+    }
+    rv.packages.push(name.to_string_lossy().into_owned());
+    Ok(())
+}
+
+fn sorted_entries(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut rv = fs::read_dir(dir)
+        .path_context(dir, "failed to enumerate directory")?
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .collect::<Vec<_>>();
+    rv.sort();
+    Ok(rv)
+}
+
+fn import_wheel_sources(root: &Path, src_dir: &Path, rv: &mut ArtifactImport) -> Result<(), Error> {
+    for path in sorted_entries(root)? {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.ends_with(".dist-info") {
+            continue;
+        } else if name.ends_with(".data") {
+            rv.follow_ups
+                .push(format!("data files in {} were not migrated", name));
+        } else if is_python_source(&path) {
+            copy_source(&path, src_dir, rv)?;
+        } else {
+            rv.follow_ups.push(format!("{} was not migrated", name));
+        }
+    }
+    Ok(())
+}
+
+fn import_sdist_sources(
+    root: &Path,
+    dir: &Path,
+    src_dir: &Path,
+    rv: &mut ArtifactImport,
+) -> Result<(), Error> {
+    // sdists that already use a src layout are taken over as they are
+    let src_root = root.join("src");
+    let has_src_layout = src_root.is_dir();
+    if has_src_layout {
+        for path in sorted_entries(&src_root)? {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            if is_python_source(&path) {
+                copy_source(&path, src_dir, rv)?;
+            } else if !name.ends_with(".egg-info") {
+                rv.follow_ups.push(format!("src/{} was not migrated", name));
+            }
+        }
+    }
+
+    for path in sorted_entries(root)? {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let is_readme_or_license = ["README", "LICENSE", "LICENCE", "COPYING"]
+            .iter()
+            .any(|x| name.to_ascii_uppercase().starts_with(x));
+        if REPLACED_FILES.contains(&&*name) || name.ends_with(".egg-info") {
+            continue;
+        } else if has_src_layout && name == "src" {
+            continue;
+        } else if TEST_DIRS.contains(&&*name) && path.is_dir() {
+            let target = dir.join(&*name);
+            if target.exists() {
+                rv.follow_ups
+                    .push(format!("{} was not migrated as it already exists", name));
+            } else {
+                fs::create_dir_all(&target).path_context(&target, "failed to create directory")?;
+                copy_dir(
+                    &path,
+                    &target,
+                    &CopyDirOptions {
+                        exclude: Vec::new(),
+                    },
+                )?;
+            }
+        } else if is_readme_or_license && path.is_file() {
+            let target = dir.join(&*name);
+            if !target.exists() {
+                fs::copy(&path, &target).path_context(&target, "failed to copy file")?;
+            }
+            if name.to_ascii_uppercase().starts_with("README") && name != "README.md" {
+                rv.follow_ups.push(format!(
+                    "{} was copied but the project uses README.md",
+                    name
+                ));
+            }
+        } else if !has_src_layout && is_python_source(&path) && name != "conftest.py" {
+            copy_source(&path, src_dir, rv)?;
+        } else {
+            rv.follow_ups.push(format!("{} was not migrated", name));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_requirement() {
+        assert_eq!(
+            split_requirement("requests>=2.0").unwrap(),
+            ("requests>=2.0".to_string(), None)
+        );
+        assert_eq!(
+            split_requirement("pytest>=7; extra == \"test\"").unwrap(),
+            ("pytest>=7".to_string(), Some("test".to_string()))
+        );
+        assert_eq!(
+            split_requirement("tomli; python_version < \"3.11\"")
+                .unwrap()
+                .1,
+            None
+        );
+        assert!(split_requirement("colorama; extra == \"cli\" and os_name == \"nt\"").is_err());
+    }
+}
@@ -1,24 +1,36 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::env::consts::EXE_EXTENSION;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{self, AtomicBool};
 use std::{env, fs};
 
 use anyhow::{anyhow, bail, Context, Error};
 use console::style;
-use indicatif::{ProgressBar, ProgressStyle};
+use indicatif::ProgressStyle;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tempfile::tempdir_in;
+use url::Url;
+use walkdir::WalkDir;
 
 use crate::config::Config;
 use crate::platform::{
-    get_app_dir, get_canonical_py_path, get_python_bin_within, get_toolchain_python_bin,
+    get_app_dir, get_local_py_path, get_python_bin_within, get_toolchain_python_bin,
     list_known_toolchains,
 };
+use crate::progress::{emit, progress_enabled, ProgressEvent};
 use crate::pyproject::latest_available_python_version;
-use crate::sources::py::{get_download_url, PythonVersion, PythonVersionRequest};
-use crate::utils::{check_checksum, symlink_file, unpack_archive, CommandOutput, IoPathContext};
+use crate::sources::py::{
+    get_download_url, get_published_checksum, PythonVersion, PythonVersionRequest,
+};
+use crate::tui::progress_bar;
+use crate::utils::{
+    check_checksum, set_curl_proxy, set_curl_tls, symlink_file, unpack_archive, CommandOutput,
+    IoPathContext,
+};
 use crate::uv::UvBuilder;
 
 /// this is the target version that we want to fetch
@@ -54,6 +66,9 @@ virtualenv==20.25.0
 ruff==0.8.2
 "#;
 
+/// The file in a fetched toolchain that records what was unpacked into it.
+pub const INSTALL_RECORD: &str = ".rye-install-record.json";
+
 static FORCED_TO_UPDATE: AtomicBool = AtomicBool::new(false);
 
 fn is_up_to_date() -> bool {
@@ -389,7 +404,7 @@ pub fn fetch(
             }
         }
     }
-    let (version, url, sha256) = match get_download_url(version) {
+    let (version, url, _) = match get_download_url(version) {
         Some(result) => result,
         None => bail!("unknown version {}", version),
     };
@@ -420,17 +435,21 @@ pub fn fetch(
             Cow::Borrowed(target_dir.as_path())
         }
         None => {
-            let target_dir = get_canonical_py_path(&version)?;
+            // downloads never go to the shared toolchain store, a forced
+            // download there shadows the shared toolchain instead.
+            let target_dir = get_local_py_path(&version)?;
             let target_py_bin = get_toolchain_python_bin(&version)?;
             if target_py_bin.is_file() {
                 if !options.force {
                     echo!(if verbose options.output, "Python version already downloaded. Skipping.");
                     return Ok(version);
                 }
-                echo!(if options.output, "Removing the existing Python version");
-                fs::remove_dir_all(&target_dir).with_context(|| {
-                    format!("failed to remove target folder {}", target_dir.display())
-                })?;
+                if target_dir.exists() {
+                    echo!(if options.output, "Removing the existing Python version");
+                    fs::remove_dir_all(&target_dir).with_context(|| {
+                        format!("failed to remove target folder {}", target_dir.display())
+                    })?;
+                }
             }
             echo!(if verbose options.output, "target dir: {}", target_dir.display());
             Cow::Owned(target_dir)
@@ -441,9 +460,9 @@ pub fn fetch(
     echo!(if options.output, "{} {}", style("Downloading").cyan(), version);
     let archive_buffer = download_url(url, options.output)?;
 
-    if let Some(sha256) = sha256 {
+    if let Some(sha256) = get_published_checksum(url)? {
         echo!(if options.output, "{} {}", style("Checking").cyan(), "checksum");
-        check_checksum(&archive_buffer, sha256)
+        check_checksum(&archive_buffer, &sha256)
             .with_context(|| format!("Checksum check of {} failed", &url))?;
     } else {
         echo!(if options.output, "Checksum check skipped (no hash available)");
@@ -485,11 +504,153 @@ pub fn fetch(
     }
     .path_context(&target_dir, "unable to persist download")?;
 
+    write_install_record(&target_dir, url, &archive_buffer)?;
+
     echo!(if options.output, "{} {}", style("Downloaded").green(), version);
 
     Ok(version)
 }
 
+/// Records the checksums of a fetched toolchain.
+#[derive(Serialize, Deserialize, Debug)]
+struct InstallRecord {
+    /// The URL the toolchain was downloaded from.
+    url: String,
+    /// The sha256 checksum of the downloaded archive.
+    sha256: String,
+    /// The sha256 checksums of the unpacked files by relative path.
+    files: BTreeMap<String, String>,
+}
+
+fn hash_file(path: &Path) -> Result<String, io::Error> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn write_install_record(target_dir: &Path, url: &str, archive: &[u8]) -> Result<(), Error> {
+    let mut files = BTreeMap::new();
+    for entry in WalkDir::new(target_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_path = entry
+            .path()
+            .strip_prefix(target_dir)?
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let sha256 = hash_file(entry.path()).path_context(entry.path(), "failed to hash file")?;
+        files.insert(rel_path, sha256);
+    }
+    let record = InstallRecord {
+        url: url.to_string(),
+        sha256: hex::encode(Sha256::digest(archive)),
+        files,
+    };
+    let path = target_dir.join(INSTALL_RECORD);
+    fs::write(&path, serde_json::to_string_pretty(&record)?)
+        .path_context(&path, "failed to write install record")?;
+    Ok(())
+}
+
+fn read_install_record(path: &Path) -> Result<InstallRecord, Error> {
+    serde_json::from_slice(&fs::read(path).path_context(path, "failed to read install record")?)
+        .path_context(path, "invalid install record")
+}
+
+/// Recomputes the checksums of the recorded files and returns the ones that
+/// no longer match as `("modified" | "missing", relative path)`.
+fn changed_files(
+    target_dir: &Path,
+    record: &InstallRecord,
+) -> Result<Vec<(&'static str, String)>, Error> {
+    let mut rv = Vec::new();
+    for (rel_path, sha256) in &record.files {
+        let file_path = target_dir.join(rel_path);
+        match hash_file(&file_path) {
+            Ok(digest) if digest == *sha256 => {}
+            Ok(_) => rv.push(("modified", rel_path.clone())),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                rv.push(("missing", rel_path.clone()))
+            }
+            Err(err) => return Err(err).path_context(&file_path, "failed to hash file"),
+        }
+    }
+    Ok(rv)
+}
+
+/// Recomputes the checksums of the files of a fetched toolchain.
+///
+/// Returns `None` if the toolchain has no install record, for instance because
+/// it was fetched by an older version of rye.
+pub fn find_changed_toolchain_files(
+    target_dir: &Path,
+) -> Result<Option<Vec<(&'static str, String)>>, Error> {
+    let path = target_dir.join(INSTALL_RECORD);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let record = read_install_record(&path)?;
+    changed_files(target_dir, &record).map(Some)
+}
+
+/// Verifies a fetched toolchain without downloading it again.
+///
+/// The checksum of the archive the toolchain was unpacked from is compared
+/// against the published checksum and all unpacked files are compared against
+/// the checksums recorded when the toolchain was fetched.
+pub fn verify_toolchain(target_dir: &Path, output: CommandOutput) -> Result<(), Error> {
+    let path = target_dir.join(INSTALL_RECORD);
+    if !path.is_file() {
+        bail!(
+            "'{}' has no install record and cannot be verified (fetch it again with --force)",
+            target_dir.display()
+        );
+    }
+    let record = read_install_record(&path)?;
+
+    let mut problems = 0;
+    match get_published_checksum(&record.url)? {
+        Some(sha256) if sha256.eq_ignore_ascii_case(&record.sha256) => {
+            echo!(if verbose output, "archive checksum matches {}", sha256);
+        }
+        Some(sha256) => {
+            echo!(
+                if output,
+                "{} archive checksum {} does not match published checksum {}",
+                style("mismatch").red(),
+                record.sha256,
+                sha256
+            );
+            problems += 1;
+        }
+        None => warn!("no published checksum for {}", record.url),
+    }
+
+    for (kind, rel_path) in changed_files(target_dir, &record)? {
+        echo!(if output, "{} {}", style(kind).red(), rel_path);
+        problems += 1;
+    }
+
+    if problems > 0 {
+        bail!(
+            "verification of '{}' failed with {} problem(s)",
+            target_dir.display(),
+            problems
+        );
+    }
+    echo!(
+        if output,
+        "{} {} files",
+        style("Verified").green(),
+        record.files.len()
+    );
+    Ok(())
+}
+
 fn installation_has_build_info(p: &Path) -> bool {
     let mut has_install = false;
     let mut has_build = false;
@@ -512,37 +673,65 @@ pub fn download_url(url: &str, output: CommandOutput) -> Result<Vec<u8>, Error>
     }
 }
 
+/// Presents the client certificate of a configured source with the same host.
+fn set_curl_client_cert(handle: &mut curl::easy::Easy, url: &str) -> Result<(), Error> {
+    let host = Url::parse(url)?.host_str().map(|x| x.to_string());
+    for source in Config::current().sources()? {
+        if source.client_cert.is_none() || source.expand_url()?.host_str() != host.as_deref() {
+            continue;
+        }
+        if let Some(client_cert) = source.expand_client_cert()? {
+            client_cert.configure_curl(handle)?;
+        }
+        break;
+    }
+    Ok(())
+}
+
 pub fn download_url_ignore_404(url: &str, output: CommandOutput) -> Result<Option<Vec<u8>>, Error> {
     // for now we only allow HTTPS downloads.
     if !url.starts_with("https://") {
         bail!("Refusing insecure download");
     }
 
-    let config = Config::current();
     let mut archive_buffer = Vec::new();
     let mut handle = curl::easy::Easy::new();
     handle.url(url)?;
     handle.progress(true)?;
     handle.follow_location(true)?;
+    // the GitHub API refuses requests without a user agent
+    handle.useragent(concat!("rye/", env!("CARGO_PKG_VERSION")))?;
 
     // we only do https requests here, so we always set an https proxy
-    if let Some(proxy) = config.https_proxy_url() {
-        handle.proxy(&proxy)?;
-    }
-
-    // on windows we want to disable revocation checks.  The reason is that MITM proxies
-    // will otherwise not work.  This is a schannel specific behavior anyways.
-    // for more information see https://github.com/curl/curl/issues/264
-    #[cfg(windows)]
-    {
-        handle.ssl_options(curl::easy::SslOpt::new().no_revoke(true))?;
-    }
+    set_curl_proxy(&mut handle, output)?;
+    set_curl_client_cert(&mut handle, url)?;
+    set_curl_tls(&mut handle)?;
 
     let write_archive = &mut archive_buffer;
+    emit(ProgressEvent::DownloadStart { url });
     {
         let mut transfer = handle.transfer();
         let mut pb = None;
+        let report_progress = progress_enabled();
+        let mut last_reported = None;
         transfer.progress_function(move |a, b, _, _| {
+            // only whole percent steps are reported to keep the event stream small
+            if report_progress {
+                let (total, downloaded) = (a as u64, b as u64);
+                let step = match total {
+                    0 => downloaded / (1024 * 1024),
+                    total => downloaded * 100 / total,
+                };
+                if downloaded > 0 && last_reported != Some(step) {
+                    last_reported = Some(step);
+                    emit(ProgressEvent::DownloadProgress {
+                        url,
+                        downloaded,
+                        total: Some(total).filter(|x| *x > 0),
+                    });
+                }
+            }
+
             if output == CommandOutput::Quiet {
                 return true;
             }
@@ -551,7 +740,7 @@ pub fn download_url_ignore_404(url: &str, output: CommandOutput) -> Result<Optio
             if down_len > 0 {
                 if down_pos < down_len {
                     if pb.is_none() {
-                        let pb_config = ProgressBar::new(down_len);
+                        let pb_config = progress_bar(down_len, output);
                         pb_config.set_style(
                             ProgressStyle::with_template("{wide_bar} {bytes:>7}/{total_bytes:7}")
                                 .unwrap(),
@@ -579,12 +768,17 @@ pub fn download_url_ignore_404(url: &str, output: CommandOutput) -> Result<Optio
     } else if !(200..300).contains(&code) {
         bail!("Failed to download: {}", code)
     } else {
+        emit(ProgressEvent::DownloadFinish {
+            url,
+            downloaded: archive_buffer.len() as u64,
+        });
         Ok(Some(archive_buffer))
     }
 }
 
+/// Returns the shared libraries required by a python binary which cannot be found.
 #[cfg(target_os = "linux")]
-fn validate_shared_libraries(py: &Path) -> Result<(), Error> {
+pub fn find_missing_shared_libraries(py: &Path) -> Result<Vec<String>, Error> {
     use std::process::Command;
     let out = Command::new("ldd")
         .arg(py)
@@ -595,17 +789,22 @@ fn validate_shared_libraries(py: &Path) -> Result<(), Error> {
     for line in stdout.lines() {
         let line = line.trim();
         if let Some((before, after)) = line.split_once(" => ") {
-            if after == "not found" && !missing.contains(&before) {
-                missing.push(before);
+            if after == "not found" && !missing.iter().any(|x| x == before) {
+                missing.push(before.to_string());
             }
         }
     }
+    missing.sort();
+    Ok(missing)
+}
 
+#[cfg(target_os = "linux")]
+fn validate_shared_libraries(py: &Path) -> Result<(), Error> {
+    let missing = find_missing_shared_libraries(py)?;
     if missing.is_empty() {
         return Ok(());
     }
 
-    missing.sort();
     echo!(
         "{}: detected missing shared librar{} required by Python:",
         style("error").red(),
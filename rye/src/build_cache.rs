@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use anyhow::Error;
+use sha2::{Digest, Sha256};
+use walkdir::WalkDir;
+
+use crate::platform::get_app_dir;
+use crate::utils::IoPathContext;
+
+/// Folders at the root of a project that hold build outputs.
+///
+/// Only skipped at the root, packages may well contain folders of that name.
+const SKIPPED_ROOT_DIRS: &[&str] = &["build", "dist", "target"];
+
+/// A cache of built distributions keyed by the source tree they were built from.
+///
+/// Every entry is a folder named after the cache key that holds the artifacts
+/// of one build.
+#[derive(Debug)]
+pub struct BuildCache {
+    dir: PathBuf,
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl BuildCache {
+    /// Creates a cache in the given folder.
+    pub fn new(dir: PathBuf) -> BuildCache {
+        BuildCache {
+            dir,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// The shared cache in the rye home folder.
+    pub fn default_dir() -> PathBuf {
+        get_app_dir().join("cache").join("builds")
+    }
+
+    /// Computes the cache key of a project.
+    ///
+    /// The key covers the contents of all files below the given roots (the
+    /// project first, then its local dependencies) and the build
+    /// configuration.  Hidden folders, caches, the build outputs at the top
+    /// of every root and the `out` folder are left out.
+    pub fn key(&self, roots: &[PathBuf], out: &Path, config: &str) -> Result<String, Error> {
+        let mut hasher = Sha256::new();
+        hasher.update(config.as_bytes());
+        for (idx, root) in roots.iter().enumerate() {
+            let mut files = Vec::new();
+            for entry in WalkDir::new(root)
+                .min_depth(1)
+                .into_iter()
+                .filter_entry(|x| {
+                    !x.file_type().is_dir()
+                        || !(is_skipped_dir(x.path(), x.depth()) || x.path() == out)
+                })
+            {
+                let entry = entry?;
+                if entry.file_type().is_file() {
+                    files.push(entry.path().strip_prefix(root)?.to_path_buf());
+                }
+            }
+            files.sort();
+
+            for file in files {
+                let path = root.join(&file);
+                let contents = fs::read(&path).path_context(&path, "failed to read source file")?;
+                // normalize separators so that the key is the same on all platforms
+                hasher.update(format!("{}:", idx).as_bytes());
+                hasher.update(file.to_string_lossy().replace('\\', "/").as_bytes());
+                hasher.update([0]);
+                hasher.update(Sha256::digest(contents));
+            }
+        }
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// Copies the cached artifacts of a key into `out`.
+    ///
+    /// Returns the paths of the copied artifacts or `None` on a cache miss.
+    pub fn restore(&mut self, key: &str, out: &Path) -> Result<Option<Vec<PathBuf>>, Error> {
+        let entry = self.dir.join(key);
+        if !entry.is_dir() {
+            self.misses += 1;
+            return Ok(None);
+        }
+        fs::create_dir_all(out).path_context(out, "failed to create output directory")?;
+        let mut rv = Vec::new();
+        for file in fs::read_dir(&entry).path_context(&entry, "failed to read build cache")? {
+            let file = file?;
+            let target = out.join(file.file_name());
+            fs::copy(file.path(), &target).path_context(&target, "failed to restore artifact")?;
+            rv.push(target);
+        }
+        self.hits += 1;
+        Ok(Some(rv))
+    }
+
+    /// Stores the artifacts of a build under a key.
+    ///
+    /// The entry is written next to its final location first so that
+    /// concurrent builds never see a partial entry.
+    pub fn store(&self, key: &str, artifacts: &[PathBuf]) -> Result<(), Error> {
+        let entry = self.dir.join(key);
+        let tmp = self.dir.join(format!(".{}.{}.tmp", key, process::id()));
+        fs::create_dir_all(&tmp).path_context(&tmp, "failed to create build cache entry")?;
+        for artifact in artifacts {
+            if let Some(name) = artifact.file_name() {
+                let target = tmp.join(name);
+                fs::copy(artifact, &target).path_context(&target, "failed to cache artifact")?;
+            }
+        }
+        if entry.is_dir() || fs::rename(&tmp, &entry).is_err() {
+            // another build stored the same entry in the meantime
+            fs::remove_dir_all(&tmp).ok();
+        }
+        Ok(())
+    }
+}
+
+fn is_skipped_dir(path: &Path, depth: usize) -> bool {
+    path.file_name()
+        .and_then(|x| x.to_str())
+        .map_or(false, |x| {
+            x.starts_with('.')
+                || x.ends_with(".egg-info")
+                || x == "__pycache__"
+                || (depth == 1 && SKIPPED_ROOT_DIRS.contains(&x))
+        })
+}
+
+#[test]
+fn test_build_cache_key() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("project");
+    fs::create_dir_all(root.join("src/pkg")).unwrap();
+    fs::create_dir_all(root.join(".venv")).unwrap();
+    fs::write(root.join("pyproject.toml"), "[project]\nname = \"pkg\"\n").unwrap();
+    fs::write(root.join("src/pkg/__init__.py"), "").unwrap();
+
+    let mut cache = BuildCache::new(dir.path().join("cache"));
+    let roots = vec![root.clone()];
+    let out = root.join("wheels");
+    let key = cache.key(&roots, &out, "wheel").unwrap();
+    assert_eq!(key.len(), 64);
+    assert_ne!(cache.key(&roots, &out, "sdist").unwrap(), key);
+
+    // build outputs and hidden folders do not count
+    fs::write(root.join(".venv/marker"), "x").unwrap();
+    fs::create_dir_all(root.join("dist")).unwrap();
+    fs::write(root.join("dist/pkg-0.1.0.tar.gz"), "x").unwrap();
+    fs::create_dir_all(&out).unwrap();
+    fs::write(out.join("pkg-0.1.0.tar.gz"), "x").unwrap();
+    assert_eq!(cache.key(&roots, &out, "wheel").unwrap(), key);
+
+    fs::write(root.join("src/pkg/__init__.py"), "x = 1").unwrap();
+    assert_ne!(cache.key(&roots, &out, "wheel").unwrap(), key);
+
+    // folders named like build outputs only count below the root
+    fs::create_dir_all(root.join("src/pkg/build")).unwrap();
+    fs::write(root.join("src/pkg/build/__init__.py"), "").unwrap();
+    let key = cache.key(&roots, &out, "wheel").unwrap();
+    fs::write(root.join("src/pkg/build/__init__.py"), "x = 1").unwrap();
+    assert_ne!(cache.key(&roots, &out, "wheel").unwrap(), key);
+
+    // local dependencies are part of the key
+    let key = cache.key(&roots, &out, "wheel").unwrap();
+    let dep = dir.path().join("dep");
+    fs::create_dir_all(&dep).unwrap();
+    fs::write(dep.join("pyproject.toml"), "").unwrap();
+    let roots_with_dep = vec![root.clone(), dep.clone()];
+    let key_with_dep = cache.key(&roots_with_dep, &out, "wheel").unwrap();
+    assert_ne!(key_with_dep, key);
+    fs::write(dep.join("pyproject.toml"), "[project]").unwrap();
+    assert_ne!(
+        cache.key(&roots_with_dep, &out, "wheel").unwrap(),
+        key_with_dep
+    );
+
+    let out = dir.path().join("out");
+    assert!(cache.restore(&key, &out).unwrap().is_none());
+    cache
+        .store(&key, &[root.join("dist/pkg-0.1.0.tar.gz")])
+        .unwrap();
+    let restored = cache.restore(&key, &out).unwrap().unwrap();
+    assert_eq!(restored, vec![out.join("pkg-0.1.0.tar.gz")]);
+    assert_eq!((cache.hits, cache.misses), (1, 1));
+}
@@ -0,0 +1,295 @@
+use std::fs;
+
+use console::style;
+
+/// The package managers install hints are given for.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Platform {
+    /// Debian, Ubuntu and derivatives (apt).
+    Debian,
+    /// Fedora, RHEL, CentOS and derivatives (dnf).
+    Fedora,
+    /// Alpine Linux (apk).
+    Alpine,
+    /// macOS with Homebrew.
+    MacOs,
+    /// Windows (no package manager).
+    Windows,
+    /// A Linux distribution or OS without specific hints.
+    Other,
+}
+
+/// A system dependency that is commonly missing when building packages from source.
+struct SystemDependency {
+    /// What is missing, as shown to the user.
+    name: &'static str,
+    /// Fragments of the build output that indicate that the dependency is missing.
+    patterns: &'static [&'static str],
+    /// How to install the dependency per platform.
+    install: &'static [(Platform, &'static str)],
+    /// An alternative to installing the dependency.
+    note: Option<&'static str>,
+}
+
+const RUSTUP_INSTALL: &str = "curl --proto '=https' --tlsv1.2 -sSf https://sh.rustup.rs | sh";
+
+const SYSTEM_DEPENDENCIES: &[SystemDependency] = &[
+    SystemDependency {
+        name: "the PostgreSQL client library (pg_config)",
+        patterns: &[
+            "pg_config executable not found",
+            "pg_config is required to build psycopg2",
+            "libpq-fe.h: no such file",
+        ],
+        install: &[
+            (Platform::Debian, "sudo apt install libpq-dev"),
+            (Platform::Fedora, "sudo dnf install libpq-devel"),
+            (Platform::Alpine, "apk add postgresql-dev"),
+            (Platform::MacOs, "brew install libpq"),
+            (
+                Platform::Windows,
+                "install PostgreSQL and add its bin folder to PATH",
+            ),
+        ],
+        note: Some("psycopg2-binary (or psycopg[binary]) ships prebuilt wheels instead"),
+    },
+    SystemDependency {
+        name: "the MySQL client library (mysql_config)",
+        patterns: &[
+            "mysql_config not found",
+            "can not find valid pkg-config name",
+            "mysql.h: no such file",
+        ],
+        install: &[
+            (
+                Platform::Debian,
+                "sudo apt install default-libmysqlclient-dev pkg-config",
+            ),
+            (Platform::Fedora, "sudo dnf install mysql-devel pkgconf"),
+            (Platform::Alpine, "apk add mariadb-dev pkgconf"),
+            (Platform::MacOs, "brew install mysql-client pkg-config"),
+        ],
+        note: Some("pymysql is a pure Python alternative"),
+    },
+    SystemDependency {
+        name: "libxml2 and libxslt",
+        patterns: &[
+            "libxml/xmlversion.h: no such file",
+            "libxslt/xsltconfig.h: no such file",
+            "could not find function xmlcheckversion in library libxml2",
+            "is the development package of libxml2 installed?",
+        ],
+        install: &[
+            (
+                Platform::Debian,
+                "sudo apt install libxml2-dev libxslt1-dev",
+            ),
+            (
+                Platform::Fedora,
+                "sudo dnf install libxml2-devel libxslt-devel",
+            ),
+            (Platform::Alpine, "apk add libxml2-dev libxslt-dev"),
+            (Platform::MacOs, "brew install libxml2 libxslt"),
+        ],
+        note: None,
+    },
+    SystemDependency {
+        name: "OpenSSL",
+        patterns: &[
+            "openssl/ssl.h: no such file",
+            "openssl/opensslv.h: no such file",
+            "openssl/err.h: no such file",
+            "could not find directory of openssl installation",
+        ],
+        install: &[
+            (Platform::Debian, "sudo apt install libssl-dev pkg-config"),
+            (Platform::Fedora, "sudo dnf install openssl-devel pkgconf"),
+            (Platform::Alpine, "apk add openssl-dev pkgconf"),
+            (Platform::MacOs, "brew install openssl@3"),
+        ],
+        note: None,
+    },
+    SystemDependency {
+        name: "libffi",
+        patterns: &["ffi.h: no such file"],
+        install: &[
+            (Platform::Debian, "sudo apt install libffi-dev"),
+            (Platform::Fedora, "sudo dnf install libffi-devel"),
+            (Platform::Alpine, "apk add libffi-dev"),
+            (Platform::MacOs, "brew install libffi"),
+        ],
+        note: None,
+    },
+    SystemDependency {
+        name: "a Rust toolchain",
+        patterns: &[
+            "can't find rust compiler",
+            "cargo, the rust package manager, is not installed",
+        ],
+        install: &[
+            (Platform::Alpine, "apk add rust cargo"),
+            (Platform::Windows, "install rustup from https://rustup.rs"),
+            (Platform::Other, RUSTUP_INSTALL),
+        ],
+        note: None,
+    },
+    SystemDependency {
+        name: "a C compiler",
+        patterns: &[
+            "command 'gcc' failed: no such file or directory",
+            "command 'cc' failed: no such file or directory",
+            "unable to execute 'gcc'",
+            "microsoft visual c++ 14.0 or greater is required",
+        ],
+        install: &[
+            (Platform::Debian, "sudo apt install build-essential"),
+            (Platform::Fedora, "sudo dnf install gcc gcc-c++ make"),
+            (Platform::Alpine, "apk add build-base"),
+            (Platform::MacOs, "xcode-select --install"),
+            (
+                Platform::Windows,
+                "install the Microsoft C++ Build Tools from \
+                 https://visualstudio.microsoft.com/visual-cpp-build-tools/",
+            ),
+        ],
+        note: None,
+    },
+];
+
+/// Prints install hints if the output of a failed uv invocation indicates
+/// that building a package from source failed because of a missing system
+/// dependency.
+pub fn print_build_hints(log: &str) {
+    let missing = find_missing_dependencies(log);
+    if missing.is_empty() {
+        return;
+    }
+    let platform = current_platform();
+    elog!();
+    for dep in missing {
+        elog!(
+            "{}: building from source probably failed because {} is missing",
+            style("hint").cyan(),
+            dep.name
+        );
+        match install_hint(dep, platform) {
+            Some(install) => elog!("  install it with: {}", style(install).yellow()),
+            None => elog!("  install it (with its development files) through your package manager"),
+        }
+        if let Some(note) = dep.note {
+            elog!("  alternatively: {}", note);
+        }
+    }
+}
+
+/// Finds the system dependencies that the build output reports as missing.
+fn find_missing_dependencies(log: &str) -> Vec<&'static SystemDependency> {
+    let log = console::strip_ansi_codes(log).to_lowercase();
+    SYSTEM_DEPENDENCIES
+        .iter()
+        .filter(|dep| dep.patterns.iter().any(|x| log.contains(x)))
+        .collect()
+}
+
+fn install_hint(dep: &SystemDependency, platform: Platform) -> Option<&'static str> {
+    let find = |platform| dep.install.iter().find(|x| x.0 == platform).map(|x| x.1);
+    find(platform).or_else(|| {
+        // the generic instructions do not apply to windows
+        if platform == Platform::Windows {
+            None
+        } else {
+            find(Platform::Other)
+        }
+    })
+}
+
+fn current_platform() -> Platform {
+    if cfg!(windows) {
+        Platform::Windows
+    } else if cfg!(target_os = "macos") {
+        Platform::MacOs
+    } else {
+        fs::read_to_string("/etc/os-release")
+            .map(|x| parse_os_release(&x))
+            .unwrap_or(Platform::Other)
+    }
+}
+
+/// Picks the platform from the `ID` and `ID_LIKE` of `/etc/os-release`.
+fn parse_os_release(contents: &str) -> Platform {
+    let ids = contents
+        .lines()
+        .filter_map(|line| {
+            line.strip_prefix("ID=")
+                .or_else(|| line.strip_prefix("ID_LIKE="))
+        })
+        .flat_map(|x| x.trim_matches(['"', '\'']).split_whitespace())
+        .collect::<Vec<_>>();
+    for id in ids {
+        match id {
+            "debian" | "ubuntu" => return Platform::Debian,
+            "fedora" | "rhel" | "centos" => return Platform::Fedora,
+            "alpine" => return Platform::Alpine,
+            _ => {}
+        }
+    }
+    Platform::Other
+}
+
+#[test]
+fn test_find_missing_dependencies() {
+    let log = "\
+  × Failed to build `psycopg2==2.9.9`
+  ╰─▶ Build backend failed to determine extra requires with `build_wheel()` with exit status: 1
+      --- stdout:
+      running egg_info
+
+      Error: pg_config executable not found.
+";
+    let missing = find_missing_dependencies(log);
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0].name, "the PostgreSQL client library (pg_config)");
+
+    let log = "\x1b[31msrc/lxml/includes/etree_defs.h:14:10: fatal error: \
+               libxml/xmlversion.h: No such file or directory\x1b[0m\n\
+               error: can't find Rust compiler";
+    let missing = find_missing_dependencies(log)
+        .into_iter()
+        .map(|x| x.name)
+        .collect::<Vec<_>>();
+    assert_eq!(missing, vec!["libxml2 and libxslt", "a Rust toolchain"]);
+
+    assert!(find_missing_dependencies("error: no matching distribution").is_empty());
+}
+
+#[test]
+fn test_install_hint() {
+    let rust = SYSTEM_DEPENDENCIES
+        .iter()
+        .find(|x| x.name == "a Rust toolchain")
+        .unwrap();
+    assert_eq!(install_hint(rust, Platform::Debian), Some(RUSTUP_INSTALL));
+    assert_eq!(
+        install_hint(rust, Platform::Alpine),
+        Some("apk add rust cargo")
+    );
+    let libffi = SYSTEM_DEPENDENCIES
+        .iter()
+        .find(|x| x.name == "libffi")
+        .unwrap();
+    assert_eq!(install_hint(libffi, Platform::Windows), None);
+}
+
+#[test]
+fn test_parse_os_release() {
+    assert_eq!(
+        parse_os_release("NAME=\"Ubuntu\"\nID=ubuntu\nID_LIKE=debian\n"),
+        Platform::Debian
+    );
+    assert_eq!(
+        parse_os_release("ID=\"rocky\"\nID_LIKE=\"rhel centos fedora\"\n"),
+        Platform::Fedora
+    );
+    assert_eq!(parse_os_release("ID=alpine\n"), Platform::Alpine);
+    assert_eq!(parse_os_release("ID=arch\n"), Platform::Other);
+}
@@ -6,15 +6,18 @@ use anyhow::{anyhow, bail, Context, Error};
 use clap::{Parser, ValueEnum};
 use pep440_rs::{Operator, VersionSpecifier, VersionSpecifiers};
 use pep508_rs::{Requirement, VersionOrUrl};
+use serde::Serialize;
 use url::Url;
 
 use crate::bootstrap::ensure_self_venv;
 use crate::config::Config;
 use crate::lock::KeyringProvider;
-use crate::pyproject::{BuildSystem, DependencyKind, ExpandedSources, PyProject};
+use crate::pyproject::{
+    normalize_package_name, BuildSystem, DependencyKind, ExpandedSources, PyProject,
+};
 use crate::sources::py::PythonVersion;
 use crate::sync::{autosync, sync, SyncOptions};
-use crate::utils::{format_requirement, get_venv_python_bin, CommandOutput};
+use crate::utils::{format_requirement, get_venv_python_bin, pin_url_hash, CommandOutput};
 use crate::uv::UvBuilder;
 
 #[derive(Parser, Debug)]
@@ -48,6 +51,9 @@ pub struct ReqExtras {
     /// Adds a dependency with a specific feature.
     #[arg(long)]
     features: Vec<String>,
+    /// Pin the hash of a URL requirement (e.g. `sha256:<digest>`).
+    #[arg(long, conflicts_with = "git", conflicts_with = "path")]
+    hash: Option<String>,
 }
 
 #[derive(ValueEnum, Copy, Clone, Debug, PartialEq)]
@@ -60,6 +66,65 @@ enum Pin {
     GreaterThanEqual,
 }
 
+#[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
+#[value(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+enum Format {
+    Json,
+}
+
+/// A changed requirement in the machine readable output of `add` and `remove`.
+#[derive(Serialize, Debug)]
+pub struct RequirementChange {
+    pub name: String,
+    pub kind: &'static str,
+    /// The optional dependency group.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// The version constraint (or URL) before the change.
+    pub old: Option<String>,
+    /// The version constraint (or URL) after the change.
+    pub new: Option<String>,
+    pub extras: Vec<String>,
+}
+
+impl RequirementChange {
+    pub fn new(
+        kind: &DependencyKind,
+        old: Option<&Requirement>,
+        new: Option<&Requirement>,
+    ) -> RequirementChange {
+        let (kind, group) = match kind {
+            DependencyKind::Normal => ("normal", None),
+            DependencyKind::Dev => ("dev", None),
+            DependencyKind::Excluded => ("excluded", None),
+            DependencyKind::Optional(section) => ("optional", Some(section.to_string())),
+        };
+        let constraint = |req: &Requirement| match req.version_or_url {
+            Some(VersionOrUrl::VersionSpecifier(ref specs)) => Some(specs.to_string()),
+            Some(VersionOrUrl::Url(ref url)) => Some(url.to_string()),
+            None => None,
+        };
+        let req = new.or(old);
+        RequirementChange {
+            name: req.map(|x| x.name.clone()).unwrap_or_default(),
+            kind,
+            group,
+            old: old.and_then(constraint),
+            new: new.and_then(constraint),
+            extras: req.and_then(|x| x.extras.clone()).unwrap_or_default(),
+        }
+    }
+}
+
+/// The machine readable summary of `add` and `remove`.
+#[derive(Serialize, Debug)]
+pub struct EditSummary {
+    pub changes: Vec<RequirementChange>,
+    /// Whether the virtualenv was synced afterwards.
+    pub autosync: bool,
+}
+
 impl From<Pin> for Operator {
     fn from(value: Pin) -> Self {
         match value {
@@ -74,7 +139,11 @@ impl ReqExtras {
     /// Return true if any path, url, features or similar are set
     /// (anything specific for 1 requirement).
     pub fn has_specifiers(&self) -> bool {
-        self.path.is_some() || self.url.is_some() || self.git.is_some() || !self.features.is_empty()
+        self.path.is_some()
+            || self.url.is_some()
+            || self.git.is_some()
+            || self.hash.is_some()
+            || !self.features.is_empty()
     }
 
     pub fn force_absolute(&mut self) {
@@ -139,6 +208,12 @@ impl ReqExtras {
                 None => Some(VersionOrUrl::Url(file_url)),
             };
         }
+        if let Some(ref hash) = self.hash {
+            match req.version_or_url {
+                Some(VersionOrUrl::Url(ref mut url)) => pin_url_hash(url, hash)?,
+                _ => bail!("--hash can only be used with a URL requirement"),
+            }
+        }
         for feature in self.features.iter().flat_map(|x| x.split(',')) {
             let feature = feature.trim();
             let extras = req.extras.get_or_insert_with(Vec::new);
@@ -182,6 +257,9 @@ pub struct Args {
     /// Turns off all output.
     #[arg(short, long, conflicts_with = "verbose")]
     quiet: bool,
+    /// Print a machine readable summary of the changes instead of the regular output.
+    #[arg(long, alias = "output", conflicts_with = "verbose")]
+    format: Option<Format>,
 
     /// Include pre-releases when finding a package version and automatically syncing the workspace.
     #[arg(long)]
@@ -195,10 +273,17 @@ pub struct Args {
     /// Attempt to use `keyring` for authentication for index URLs.
     #[arg(long, value_enum, default_value_t)]
     keyring_provider: KeyringProvider,
+    /// Extra argument passed to uv as is (unsupported, use at your own risk).
+    #[arg(long = "uv-arg", value_name = "ARG", allow_hyphen_values = true)]
+    uv_args: Vec<String>,
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
-    let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+    let output = if cmd.format.is_some() {
+        CommandOutput::Quiet
+    } else {
+        CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose)
+    };
     ensure_self_venv(output).context("error bootstrapping venv")?;
     let cfg = Config::current();
 
@@ -219,7 +304,7 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     };
 
     if cmd.req_extras.has_specifiers() && cmd.requirements.len() != 1 {
-        bail!("path/url/git/features is not compatible with passing multiple requirements: expected one requirement.")
+        bail!("path/url/git/features/hash is not compatible with passing multiple requirements: expected one requirement.")
     }
 
     let mut requirements = Vec::new();
@@ -230,7 +315,11 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     }
 
     if !cmd.excluded {
-        sync(SyncOptions::python_only().pyproject(None)).context("failed to sync ahead of add")?;
+        sync(SyncOptions {
+            output,
+            ..SyncOptions::python_only()
+        })
+        .context("failed to sync ahead of add")?;
         resolve_requirements_with_uv(
             &pyproject_toml,
             &py_ver,
@@ -239,11 +328,19 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
             output,
             &default_operator,
             cmd.keyring_provider,
+            &cmd.uv_args,
         )?;
     }
 
+    let mut changes = Vec::new();
     for requirement in &requirements {
+        let old = find_dependency(&pyproject_toml, &requirement.name, &dep_kind);
         pyproject_toml.add_dependency(requirement, &dep_kind)?;
+        changes.push(RequirementChange::new(
+            &dep_kind,
+            old.as_ref(),
+            Some(requirement),
+        ));
     }
 
     pyproject_toml.save()?;
@@ -258,7 +355,8 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         }
     }
 
-    if (cfg.autosync() && !cmd.no_sync) || cmd.sync {
+    let with_autosync = (cfg.autosync() && !cmd.no_sync) || cmd.sync;
+    if with_autosync {
         autosync(
             &pyproject_toml,
             output,
@@ -266,12 +364,34 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
             cmd.with_sources,
             cmd.generate_hashes,
             cmd.keyring_provider,
+            cmd.uv_args,
         )?;
     }
 
+    if let Some(Format::Json) = cmd.format {
+        let summary = EditSummary {
+            changes,
+            autosync: with_autosync,
+        };
+        echo!("{}", serde_json::to_string_pretty(&summary)?);
+    }
+
     Ok(())
 }
 
+/// Finds the declared requirement of a package.
+fn find_dependency(
+    pyproject: &PyProject,
+    name: &str,
+    kind: &DependencyKind,
+) -> Option<Requirement> {
+    let name = normalize_package_name(name);
+    pyproject
+        .iter_dependencies(kind.clone())
+        .filter_map(|x| Requirement::from_str(&x.to_string()).ok())
+        .find(|x| normalize_package_name(&x.name) == name)
+}
+
 fn resolve_requirements_with_uv(
     pyproject_toml: &PyProject,
     py_ver: &PythonVersion,
@@ -280,16 +400,23 @@ fn resolve_requirements_with_uv(
     output: CommandOutput,
     default_operator: &Operator,
     keyring_provider: KeyringProvider,
+    uv_args: &[String],
 ) -> Result<(), Error> {
     let venv_path = pyproject_toml.venv_path();
     let py_bin = get_venv_python_bin(&venv_path);
-    let sources = ExpandedSources::from_sources(&pyproject_toml.sources()?)?;
+    let sources = ExpandedSources::from_sources_relative_to(
+        &pyproject_toml.sources()?,
+        &pyproject_toml.workspace_path(),
+    )?;
+    sources.echo_verbose(output);
 
     let uv = UvBuilder::new()
         .with_output(output.quieter())
         .with_sources(sources)
+        .with_uv_args(uv_args)
         .ensure_exists()?
         .venv(&venv_path, &py_bin, py_ver, None)?;
+    let binary_policy = pyproject_toml.binary_policy()?;
 
     for req in requirements {
         let mut new_req = uv.resolve(
@@ -298,6 +425,7 @@ fn resolve_requirements_with_uv(
             pre,
             env::var("__RYE_UV_EXCLUDE_NEWER").ok(),
             keyring_provider,
+            &binary_policy,
         )?;
 
         // if a version or URL is already provided we just use the normalized package name but
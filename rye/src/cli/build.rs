@@ -1,17 +1,39 @@
-use std::fs;
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use std::{env, fs};
 
 use anyhow::{anyhow, bail, Context, Error};
 use clap::Parser;
 use console::style;
+use pep508_rs::VersionOrUrl;
 
 use crate::bootstrap::{fetch, FetchOptions};
+use crate::build_cache::BuildCache;
+use crate::gates::{run_gate, Gate};
 
 use crate::platform::get_toolchain_python_bin;
-use crate::pyproject::{locate_projects, PyProject};
+use crate::provenance::{write_provenance, BuildRecord, ProvenanceFormat};
+use crate::pyproject::{
+    locate_projects, normalize_package_name, DependencyKind, ExpandedSources, PyProject,
+};
+use crate::sources::py::PythonVersion;
+use crate::sync::{sync, SyncOptions};
+use crate::utils::watch::FileWatcher;
 use crate::utils::{get_venv_python_bin, prepend_path_to_path_env, CommandOutput, IoPathContext};
 use crate::uv::UvBuilder;
+use crate::vcs_version::sync_version_from_vcs;
+
+/// How often the source tree is checked for changes in watch mode.
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// File extensions that trigger a rebuild in watch mode.
+const WATCH_EXTENSIONS: &[&str] = &["rs", "py", "pyi", "c", "h"];
+
+/// File names that trigger a rebuild in watch mode.
+const WATCH_FILENAMES: &[&str] = &["Cargo.toml", "Cargo.lock", "pyproject.toml", "build.rs"];
 
 /// Builds a package for distribution.
 #[derive(Parser, Debug)]
@@ -37,6 +59,27 @@ pub struct Args {
     /// Clean the output directory first
     #[arg(short, long)]
     clean: bool,
+    /// Watch the sources and rebuild extension modules into the virtualenv on change
+    #[arg(long, conflicts_with_all = ["sdist", "wheel", "out", "clean"])]
+    watch: bool,
+    /// Record how the artifacts were built in a provenance file next to them
+    #[arg(long, conflicts_with = "watch")]
+    provenance: bool,
+    /// The format of the provenance file
+    #[arg(long, value_enum, default_value_t, requires = "provenance")]
+    provenance_format: ProvenanceFormat,
+    /// Skip the checks configured in `tool.rye.gates.build`
+    #[arg(long, conflicts_with = "watch")]
+    no_verify: bool,
+    /// Reuse the artifacts of unchanged projects from the build cache in the rye home folder
+    #[arg(long, conflicts_with = "watch")]
+    cache: bool,
+    /// Reuse the artifacts of unchanged projects from a build cache in this folder
+    #[arg(long, value_name = "DIR", conflicts_with = "watch")]
+    cache_dir: Option<PathBuf>,
+    /// Always invoke the build backend, even if `tool.rye.build.cache` is enabled
+    #[arg(long, conflicts_with_all = ["cache", "cache_dir"])]
+    no_cache: bool,
     /// Enables verbose diagnostics.
     #[arg(short, long)]
     verbose: bool,
@@ -48,9 +91,16 @@ pub struct Args {
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
     let project = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    if cmd.watch {
+        return watch(project, cmd.all, &cmd.package, cmd.pyproject, output);
+    }
+    if !cmd.no_verify {
+        run_gate(&project, Gate::Build, output)?;
+    }
     let py_ver = project.venv_python_version()?;
 
     let out = match cmd.out {
+        Some(path) if path.is_relative() => env::current_dir()?.join(path),
         Some(path) => path,
         None => project.workspace_path().join("dist"),
     };
@@ -64,6 +114,15 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         }
     }
 
+    let mut cache = match cmd.cache_dir {
+        _ if cmd.no_cache => None,
+        Some(dir) => Some(BuildCache::new(dir)),
+        None if cmd.cache || project.build_cache() => {
+            Some(BuildCache::new(BuildCache::default_dir()))
+        }
+        None => None,
+    };
+
     let projects = locate_projects(project, cmd.all, &cmd.package[..])?;
 
     let all_virtual = projects.iter().all(|p| p.is_virtual());
@@ -89,17 +148,70 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     uv_venv.write_marker()?;
     uv_venv.bootstrap()?;
 
+    // everything besides the sources that changes the produced artifacts
+    let build_config = format!(
+        "rye {}\npython {}\nsdist {}\nwheel {}\nSOURCE_DATE_EPOCH {}\n",
+        env!("CARGO_PKG_VERSION"),
+        py_ver,
+        cmd.sdist,
+        cmd.wheel,
+        env::var("SOURCE_DATE_EPOCH").unwrap_or_default()
+    );
+
     for project in projects {
         // skip over virtual packages on build
         if project.is_virtual() {
             continue;
         }
 
-        echo!(
-            if output,
-            "building {}",
-            style(project.normalized_name()?).cyan()
-        );
+        // the version file is part of the sources, so it is written before
+        // the cache key is computed.
+        let version = if project.version_from_vcs() {
+            Some(sync_version_from_vcs(&project)?.0)
+        } else {
+            None
+        };
+
+        let cache_key = match cache {
+            Some(ref cache) => Some(cache.key(&cache_roots(&project)?, &out, &build_config)?),
+            None => None,
+        };
+        let started_on = SystemTime::now();
+        if let (Some(cache), Some(key)) = (&mut cache, &cache_key) {
+            if let Some(artifacts) = cache.restore(key, &out)? {
+                echo!(
+                    if output,
+                    "using cached build of {}",
+                    style(project.normalized_name()?).cyan()
+                );
+                if cmd.provenance {
+                    record_provenance(
+                        &out,
+                        &project,
+                        &py_ver,
+                        artifacts,
+                        started_on,
+                        cmd.provenance_format,
+                        output,
+                    )?;
+                }
+                continue;
+            }
+        }
+
+        match version {
+            Some(ref version) => echo!(
+                if output,
+                "building {} {}",
+                style(project.normalized_name()?).cyan(),
+                style(version).cyan()
+            ),
+            None => echo!(
+                if output,
+                "building {}",
+                style(project.normalized_name()?).cyan()
+            ),
+        }
 
         let mut build_cmd = Command::new(get_venv_python_bin(venv_dir.path()));
         build_cmd
@@ -137,10 +249,252 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
             build_cmd.stderr(Stdio::null());
         }
 
+        let previous_artifacts = list_artifacts(&out);
         let status = build_cmd.status()?;
         if !status.success() {
             bail!("failed to build dist");
         }
+
+        let artifacts = list_artifacts(&out)
+            .into_iter()
+            .filter(|(path, mtime)| previous_artifacts.get(path) != Some(mtime))
+            .map(|(path, _)| path)
+            .collect::<Vec<_>>();
+        if let (Some(cache), Some(key)) = (&cache, &cache_key) {
+            if !artifacts.is_empty() {
+                cache.store(key, &artifacts)?;
+            }
+        }
+        if cmd.provenance {
+            record_provenance(
+                &out,
+                &project,
+                &py_ver,
+                artifacts,
+                started_on,
+                cmd.provenance_format,
+                output,
+            )?;
+        }
+    }
+
+    if let Some(cache) = cache {
+        echo!(
+            if output,
+            "build cache: {} hit(s), {} miss(es)",
+            cache.hits,
+            cache.misses
+        );
+    }
+    Ok(())
+}
+
+/// Returns the folders whose contents make up the cache key of a project.
+///
+/// These are the project itself followed by its local dependencies: path
+/// dependencies and the workspace members it depends on (transitively).
+fn cache_roots(project: &PyProject) -> Result<Vec<PathBuf>, Error> {
+    let members = match project.workspace() {
+        Some(workspace) => workspace.iter_projects().collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+    let workspace_path = project.workspace_path().to_string_lossy().into_owned();
+
+    let mut rv = vec![project.root_path().to_path_buf()];
+    let mut pending = vec![project];
+    while let Some(current) = pending.pop() {
+        let kinds = current
+            .extras()
+            .into_iter()
+            .map(|x| DependencyKind::Optional(x.into()))
+            .chain(Some(DependencyKind::Normal));
+        for kind in kinds {
+            for dep in current.iter_dependencies(kind) {
+                let Ok(req) = dep.expand(|name| match name {
+                    "PROJECT_ROOT" => Some(workspace_path.clone()),
+                    _ => env::var(name).ok(),
+                }) else {
+                    continue;
+                };
+                if let Some(VersionOrUrl::Url(ref url)) = req.version_or_url {
+                    if let Ok(path) = url.to_file_path() {
+                        if path.is_dir() && !rv.contains(&path) {
+                            rv.push(path);
+                        }
+                    }
+                    continue;
+                }
+                let name = normalize_package_name(&req.name);
+                if let Some(member) = members
+                    .iter()
+                    .find(|x| x.normalized_name().ok().as_deref() == Some(name.as_str()))
+                {
+                    let root = member.root_path().to_path_buf();
+                    if !rv.contains(&root) {
+                        rv.push(root);
+                        pending.push(member);
+                    }
+                }
+            }
+        }
     }
+    rv[1..].sort();
+    Ok(rv)
+}
+
+fn record_provenance(
+    out: &Path,
+    project: &PyProject,
+    py_ver: &PythonVersion,
+    artifacts: Vec<PathBuf>,
+    started_on: SystemTime,
+    format: ProvenanceFormat,
+    output: CommandOutput,
+) -> Result<(), Error> {
+    let path = write_provenance(
+        out,
+        &BuildRecord {
+            project,
+            toolchain: py_ver,
+            artifacts,
+            applied_env: BTreeMap::from([("NO_COLOR".to_string(), "1".to_string())]),
+            started_on,
+            finished_on: SystemTime::now(),
+        },
+        format,
+    )?;
+    echo!(
+        if output,
+        "wrote provenance to {}",
+        style(path.display()).cyan()
+    );
     Ok(())
 }
+
+/// Returns the distributions in the output folder with their modification times.
+fn list_artifacts(out: &Path) -> BTreeMap<PathBuf, SystemTime> {
+    fs::read_dir(out)
+        .into_iter()
+        .flatten()
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .filter(|x| {
+            x.file_name()
+                .and_then(|x| x.to_str())
+                .map_or(false, |x| x.ends_with(".whl") || x.ends_with(".tar.gz"))
+        })
+        .filter_map(|x| Some((x.clone(), fs::metadata(&x).ok()?.modified().ok()?)))
+        .collect()
+}
+
+/// Rebuilds and reinstalls extension modules into the project virtualenv whenever
+/// one of their sources changes.
+fn watch(
+    project: PyProject,
+    all: bool,
+    packages: &[String],
+    pyproject: Option<PathBuf>,
+    output: CommandOutput,
+) -> Result<(), Error> {
+    let venv_path = project.venv_path().to_path_buf();
+    let py_ver = project.venv_python_version()?;
+    let sources =
+        ExpandedSources::from_sources_relative_to(&project.sources()?, &project.workspace_path())?;
+
+    let mut projects = Vec::new();
+    for project in locate_projects(project, all, packages)? {
+        if project.build_backend_name() == Some("maturin") {
+            projects.push((
+                project.normalized_name()?,
+                project.root_path().to_path_buf(),
+            ));
+        }
+    }
+    if projects.is_empty() {
+        bail!("--watch requires at least one project using the maturin build backend");
+    }
+
+    // bring the virtualenv up to date first so that only the extensions need rebuilding
+    sync(SyncOptions {
+        output,
+        dev: true,
+        pyproject,
+        ..SyncOptions::default()
+    })
+    .context("failed to sync ahead of watching")?;
+
+    let uv = UvBuilder::new()
+        .with_output(output)
+        .with_sources(sources)
+        .ensure_exists()?
+        .venv(&venv_path, &get_venv_python_bin(&venv_path), &py_ver, None)?;
+
+    let mut watcher = FileWatcher::new(
+        projects.iter().map(|(_, root)| root.clone()).collect(),
+        WATCH_EXTENSIONS,
+        WATCH_FILENAMES,
+    );
+    for (name, root) in &projects {
+        echo!(
+            if output,
+            "Watching {} in {}",
+            style(name).cyan(),
+            style(root.display()).dim()
+        );
+    }
+    echo!(if output, "Press Ctrl+C to stop");
+
+    loop {
+        thread::sleep(WATCH_POLL_INTERVAL);
+        let changed = watcher.poll();
+        if changed.is_empty() {
+            continue;
+        }
+
+        // editors frequently write files in multiple steps, give them a moment
+        // to settle so that a single save only triggers a single rebuild.
+        thread::sleep(WATCH_POLL_INTERVAL);
+        let mut changed = changed;
+        changed.extend(watcher.poll());
+
+        for (name, root) in &projects {
+            if !changed
+                .iter()
+                .any(|path| owning_project(&projects, path) == Some(root.as_path()))
+            {
+                continue;
+            }
+            echo!(if output, "Rebuilding {}", style(name).cyan());
+            match uv.reinstall_editable(name, root) {
+                Ok(()) => echo!(if output, "Reinstalled {}", style(name).green()),
+                Err(err) => error!("{:?}", err),
+            }
+        }
+    }
+}
+
+/// Returns the root of the innermost project containing the given path.
+fn owning_project<'a>(projects: &'a [(String, PathBuf)], path: &Path) -> Option<&'a Path> {
+    projects
+        .iter()
+        .map(|(_, root)| root.as_path())
+        .filter(|root| path.starts_with(root))
+        .max_by_key(|root| root.components().count())
+}
+
+#[test]
+fn test_owning_project() {
+    let projects = vec![
+        ("ws".to_string(), PathBuf::from("/ws")),
+        ("ext".to_string(), PathBuf::from("/ws/pkgs/ext")),
+    ];
+    assert_eq!(
+        owning_project(&projects, Path::new("/ws/pkgs/ext/src/lib.rs")),
+        Some(Path::new("/ws/pkgs/ext"))
+    );
+    assert_eq!(
+        owning_project(&projects, Path::new("/ws/src/lib.rs")),
+        Some(Path::new("/ws"))
+    );
+    assert_eq!(owning_project(&projects, Path::new("/other/lib.rs")), None);
+}
@@ -0,0 +1,66 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Error};
+use clap::Parser;
+use console::style;
+
+use crate::entry_points::find_broken_entry_points;
+use crate::pyproject::{locate_projects, PyProject};
+use crate::utils::{get_venv_python_bin, CommandOutput};
+
+/// Checks that all entry points of a project are importable.
+///
+/// Every entry in `[project.scripts]` and `[project.gui-scripts]` is resolved
+/// against the installed virtualenv.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// Check all projects in the workspace
+    #[arg(short, long)]
+    all: bool,
+    /// Check a specific package
+    #[arg(short, long)]
+    package: Vec<String>,
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+    /// Enables verbose diagnostics.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Turns off all output.
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+pub fn execute(cmd: Args) -> Result<(), Error> {
+    let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+    let project = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    let venv = project.venv_path().to_path_buf();
+    if !get_venv_python_bin(&venv).is_file() {
+        bail!("project is not synced, no virtualenv found. Run `rye sync`.");
+    }
+
+    let projects = locate_projects(project, cmd.all, &cmd.package[..])?;
+    let projects = projects.iter().collect::<Vec<_>>();
+    let total = projects
+        .iter()
+        .filter(|x| !x.is_virtual())
+        .map(|x| x.entry_points().len())
+        .sum::<usize>();
+
+    let broken = find_broken_entry_points(&venv, &projects)?;
+    for entry_point in &broken {
+        echo!(
+            "{} {} ({}): {}",
+            style("broken").red(),
+            style(&entry_point.name).cyan(),
+            entry_point.target,
+            entry_point.error
+        );
+    }
+    if !broken.is_empty() {
+        bail!("{} of {} entry point(s) are broken", broken.len(), total);
+    }
+
+    echo!(if output, "All {} entry point(s) are importable", total);
+    Ok(())
+}
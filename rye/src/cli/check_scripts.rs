@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Error};
+use clap::Parser;
+use console::style;
+
+use crate::pyproject::{locate_projects, PyProject};
+use crate::utils::CommandOutput;
+
+/// Checks the scripts declared in `tool.rye.scripts`.
+///
+/// Every script must have a valid definition.  If `tool.rye.require-script-help`
+/// is enabled (or `--require-help` is passed) every script also needs a `help`
+/// text.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// Check all projects in the workspace
+    #[arg(short, long)]
+    all: bool,
+    /// Check a specific package
+    #[arg(short, long)]
+    package: Vec<String>,
+    /// Require a help text for all scripts.
+    #[arg(long)]
+    require_help: bool,
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+    /// Enables verbose diagnostics.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Turns off all output.
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+pub fn execute(cmd: Args) -> Result<(), Error> {
+    let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+    let project = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    let require_help = cmd.require_help || project.require_script_help();
+
+    let projects = locate_projects(project, cmd.all, &cmd.package[..])?;
+    let mut total = 0;
+    let mut problems = 0;
+    for project in &projects {
+        let mut scripts = project.declared_scripts();
+        scripts.sort();
+        for name in scripts {
+            total += 1;
+            let problem = if project.get_declared_script(&name).is_none() {
+                Some("invalid script definition")
+            } else if require_help && project.script_help(&name).map_or(true, str::is_empty) {
+                Some("missing help text")
+            } else {
+                None
+            };
+            if let Some(problem) = problem {
+                problems += 1;
+                echo!(
+                    "{} {} ({}): {}",
+                    style("error").red(),
+                    style(&name).cyan(),
+                    project.name().unwrap_or("<unnamed>"),
+                    problem
+                );
+            }
+        }
+    }
+
+    if problems > 0 {
+        bail!("{} of {} script(s) have problems", problems, total);
+    }
+
+    echo!(if output, "All {} script(s) are valid", total);
+    Ok(())
+}
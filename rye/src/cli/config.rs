@@ -1,4 +1,5 @@
 use std::collections::BTreeMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::bail;
@@ -13,6 +14,8 @@ use toml_edit::Table;
 use toml_edit::Value;
 
 use crate::config::Config;
+use crate::pyproject::PyProject;
+use crate::schema::find_setting;
 
 #[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
 #[value(rename_all = "snake_case")]
@@ -27,12 +30,21 @@ enum Format {
 /// of the set options (`--set`, `--set-int`, `--set-bool`, or `--unset`).
 /// Each of the set operations takes a key=value pair. All of these can
 /// be supplied multiple times.
+///
+/// With `--project` the `pyproject.toml` of the current project is used
+/// instead.  Only keys below `tool.rye` can be modified there.
 #[derive(Parser, Debug)]
 #[command(arg_required_else_help(true))]
 pub struct Args {
     /// Print the path to the config.
     #[arg(long)]
     show_path: bool,
+    /// Read or modify the settings of the current project instead.
+    #[arg(long)]
+    project: bool,
+    /// Use this pyproject.toml file.
+    #[arg(long, value_name = "PYPROJECT_TOML", requires = "project")]
+    pyproject: Option<PathBuf>,
 
     #[command(flatten)]
     action: Action,
@@ -63,13 +75,26 @@ pub struct Action {
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let mut config = Config::current();
-    let doc = Arc::make_mut(&mut config).doc_mut();
+    let mut project = if cmd.project {
+        Some(PyProject::load_or_discover(cmd.pyproject.as_deref())?)
+    } else {
+        None
+    };
 
     if cmd.show_path {
-        echo!("{}", config.path().display());
+        match project {
+            Some(ref project) => echo!("{}", project.toml_path().display()),
+            None => echo!("{}", config.path().display()),
+        }
         return Ok(());
     }
 
+    let is_project = project.is_some();
+    let doc = match project {
+        Some(ref mut project) => project.doc_mut(),
+        None => Arc::make_mut(&mut config).doc_mut(),
+    };
+
     let mut read_as_json = BTreeMap::new();
     let mut read_as_string = Vec::new();
     let reads = !cmd.action.get.is_empty();
@@ -136,6 +161,15 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         bail!("cannot mix get and set operations");
     }
 
+    if is_project {
+        for (key, new_value) in &updates {
+            validate_project_setting(key, Some(new_value))?;
+        }
+        for key in &cmd.action.unset {
+            validate_project_setting(key, None)?;
+        }
+    }
+
     for (key, new_value) in updates {
         let mut ptr = doc.as_item_mut();
         for piece in key.split('.') {
@@ -169,7 +203,10 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     }
 
     if modifies {
-        config.save()?;
+        match project {
+            Some(ref project) => project.save()?,
+            None => config.save()?,
+        }
     }
 
     match cmd.action.format {
@@ -186,7 +223,30 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     Ok(())
 }
 
-fn value_to_json(val: Option<&Value>) -> serde_json::Value {
+/// Makes sure that only `tool.rye` settings are modified in a project and
+/// that known settings are set to a value of the right type.
+fn validate_project_setting(key: &str, new_value: Option<&Value>) -> Result<(), Error> {
+    if !key.starts_with("tool.rye.") {
+        bail!(
+            "only settings below tool.rye can be modified in pyproject.toml (got {})",
+            key
+        );
+    }
+    if let (Some(new_value), Some(setting)) = (new_value, find_setting(key)) {
+        let expected = setting.kind.toml_type();
+        if new_value.type_name() != expected {
+            bail!(
+                "invalid value for {}: expected a {}, got a {}",
+                key,
+                expected,
+                new_value.type_name()
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn value_to_json(val: Option<&Value>) -> serde_json::Value {
     match val {
         Some(Value::String(s)) => serde_json::Value::String(s.value().into()),
         Some(Value::Integer(i)) => serde_json::Value::Number((*i.value()).into()),
@@ -1,13 +1,16 @@
 use std::path::PathBuf;
 
-use anyhow::{Context, Error};
+use anyhow::{anyhow, bail, Context, Error};
 use clap::Parser;
+use console::style;
 
-use crate::bootstrap::{fetch, FetchOptions};
+use crate::bootstrap::{fetch, verify_toolchain, FetchOptions};
 use crate::config::Config;
-use crate::platform::get_python_version_request_from_pyenv_pin;
-use crate::pyproject::PyProject;
-use crate::sources::py::PythonVersionRequest;
+use crate::platform::{
+    get_canonical_py_path, get_python_version_request_from_pyenv_pin, read_pin_file,
+};
+use crate::pyproject::{latest_available_python_version, PyProject};
+use crate::sources::py::{get_download_url, PythonVersion, PythonVersionRequest};
 use crate::utils::CommandOutput;
 
 /// Fetches a Python interpreter for the local machine.
@@ -17,6 +20,17 @@ pub struct Args {
     ///
     /// If no version is provided, the requested version from local project or `.python-version` will be fetched.
     version: Option<String>,
+    /// Fetch exactly the interpreter the virtualenv of the current project uses.
+    ///
+    /// Unlike fetching without a version this fails if there is no project.
+    #[arg(long, conflicts_with_all = ["version", "pin_file"])]
+    project: bool,
+    /// Use this pyproject.toml file (with `--project`).
+    #[arg(long, value_name = "PYPROJECT_TOML", requires = "project")]
+    pyproject: Option<PathBuf>,
+    /// Fetch exactly the interpreter requested by this `.python-version` file.
+    #[arg(long, value_name = "PATH", conflicts_with = "version")]
+    pin_file: Option<PathBuf>,
     /// Fetch the Python toolchain even if it is already installed.
     #[arg(short, long)]
     force: bool,
@@ -29,6 +43,10 @@ pub struct Args {
     /// Fetches without build info.
     #[arg(long, conflicts_with = "build_info")]
     no_build_info: bool,
+    /// Verifies an already fetched toolchain against the published checksums
+    /// instead of fetching it.
+    #[arg(long, conflicts_with_all = ["force", "build_info", "no_build_info"])]
+    verify_only: bool,
     /// Enables verbose diagnostics.
     #[arg(short, long)]
     verbose: bool,
@@ -37,23 +55,70 @@ pub struct Args {
     quiet: bool,
 }
 
+impl Args {
+    /// Does the command write a toolchain instead of only verifying one?
+    pub fn is_mutating(&self) -> bool {
+        !self.verify_only
+    }
+}
+
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
 
-    let version: PythonVersionRequest = match cmd.version {
-        Some(version) => version.parse()?,
-        None => {
-            if let Ok(pyproject) = PyProject::discover() {
-                pyproject.venv_python_version()?.into()
-            } else {
-                match get_python_version_request_from_pyenv_pin(&std::env::current_dir()?) {
-                    Some(version) => version,
-                    None => Config::current().default_toolchain()?,
-                }
-            }
+    let version: PythonVersionRequest = if let Some(version) = cmd.version {
+        version.parse()?
+    } else if cmd.project {
+        PyProject::load_or_discover(cmd.pyproject.as_deref())?
+            .venv_python_version()?
+            .into()
+    } else if let Some(ref pin_file) = cmd.pin_file {
+        let requested = read_pin_file(pin_file)?;
+        // resolve the request the same way it is resolved for virtualenvs
+        let version = PythonVersion::try_from(requested.clone())
+            .ok()
+            .or_else(|| latest_available_python_version(&requested))
+            .ok_or_else(|| anyhow!("unable to determine a Python version for {}", requested))?;
+        echo!(
+            if verbose output,
+            "{} resolves to {}",
+            pin_file.display(),
+            style(&version).cyan()
+        );
+        version.into()
+    } else if let Ok(pyproject) = PyProject::discover() {
+        pyproject.venv_python_version()?.into()
+    } else {
+        match get_python_version_request_from_pyenv_pin(&std::env::current_dir()?) {
+            Some(version) => version,
+            None => Config::current().default_toolchain()?,
         }
     };
 
+    if cmd.verify_only {
+        let target_dir = match cmd.target_path {
+            Some(target_dir) => {
+                echo!(if output, "{} '{}'", style("Verifying").cyan(), target_dir.display());
+                target_dir
+            }
+            None => {
+                let version = match PythonVersion::try_from(version.clone()) {
+                    Ok(version) => version,
+                    Err(_) => get_download_url(&version)
+                        .map(|x| x.0)
+                        .ok_or_else(|| anyhow!("unknown version {}", version))?,
+                };
+                let target_dir = get_canonical_py_path(&version)?;
+                if !target_dir.is_dir() {
+                    bail!("{} is not a fetched toolchain", version);
+                }
+                echo!(if output, "{} {}", style("Verifying").cyan(), version);
+                target_dir
+            }
+        };
+        return verify_toolchain(&target_dir, output)
+            .context("error while verifying Python installation");
+    }
+
     fetch(
         &version,
         FetchOptions {
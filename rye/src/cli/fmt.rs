@@ -1,21 +1,40 @@
+use std::path::PathBuf;
+
 use anyhow::Error;
+use clap::builder::{OsStringValueParser, TypedValueParser};
 use clap::Parser;
 
-use crate::utils::ruff;
+use crate::utils::{ruff, QuietExit};
 
 /// Run the code formatter on the project.
 ///
-/// This invokes ruff in format mode.
+/// This invokes ruff in format mode.  With `--pyproject` (without a path) or
+/// `--normalize-pyproject` the dependency arrays in pyproject.toml are sorted
+/// and normalized instead.
 #[derive(Parser, Debug)]
+// an empty path stands for `--pyproject` without a path, which the default
+// parser for paths would reject.
+#[command(mut_arg("pyproject", |arg| {
+    arg.num_args(0..=1)
+        .value_parser(OsStringValueParser::new().map(PathBuf::from))
+        .default_missing_value("")
+        .help("Use this pyproject.toml file, without a path the dependency arrays are normalized")
+}))]
 pub struct Args {
     #[command(flatten)]
     ruff: ruff::RuffArgs,
     /// Run format in check mode
     #[arg(long)]
     check: bool,
+    /// Sort and normalize the dependency arrays in pyproject.toml instead of formatting code
+    #[arg(long, conflicts_with = "paths")]
+    normalize_pyproject: bool,
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
+    if cmd.normalize_pyproject || cmd.ruff.normalize_requested() {
+        return normalize_pyprojects(&cmd.ruff, cmd.check);
+    }
     let mut args = Vec::new();
     args.push("format");
     if cmd.check {
@@ -23,3 +42,24 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     }
     ruff::execute_ruff(cmd.ruff, &args)
 }
+
+fn normalize_pyprojects(args: &ruff::RuffArgs, check: bool) -> Result<(), Error> {
+    let output = args.output();
+    let mut unnormalized = 0;
+    for mut project in args.projects()? {
+        if !project.normalize_dependencies()? {
+            continue;
+        }
+        unnormalized += 1;
+        if check {
+            echo!(if output, "Would normalize {}", project.toml_path().display());
+        } else {
+            project.save()?;
+            echo!(if output, "Normalized {}", project.toml_path().display());
+        }
+    }
+    if check && unnormalized > 0 {
+        return Err(QuietExit(1).into());
+    }
+    Ok(())
+}
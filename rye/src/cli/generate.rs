@@ -0,0 +1,162 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Error};
+use clap::Parser;
+use console::style;
+use minijinja::render;
+
+use crate::lock::LockMode;
+use crate::pyproject::PyProject;
+use crate::sources::uv::{UvDownload, UvRequest};
+use crate::utils::IoPathContext;
+
+/// The template for the Dockerfile.
+const DOCKERFILE_TEMPLATE: &str = include_str!("../templates/docker/Dockerfile.j2");
+
+/// The default .dockerignore file.
+const DOCKERIGNORE: &str = include_str!("../templates/docker/dockerignore");
+
+/// The template for the devcontainer.json.
+const DEVCONTAINER_TEMPLATE: &str = include_str!("../templates/docker/devcontainer.json.j2");
+
+/// Generates files for the project.
+#[derive(Parser, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: SubCommand,
+}
+
+/// Generates a Dockerfile for the project.
+///
+/// The Dockerfile is a multi-stage build that installs the production
+/// lockfile and the project itself (not editable) into a virtualenv which
+/// is then copied into a slim image with the pinned Python version.
+#[derive(Parser, Debug)]
+pub struct DockerCommand {
+    /// Also generate a `.devcontainer/devcontainer.json`.
+    #[arg(long)]
+    devcontainer: bool,
+    /// Overwrite existing files.
+    #[arg(short, long)]
+    force: bool,
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+enum SubCommand {
+    Docker(DockerCommand),
+}
+
+pub fn execute(cmd: Args) -> Result<(), Error> {
+    match cmd.command {
+        SubCommand::Docker(args) => generate_docker(args),
+    }
+}
+
+fn generate_docker(args: DockerCommand) -> Result<(), Error> {
+    let pyproject = PyProject::load_or_discover(args.pyproject.as_deref())?;
+    let root = pyproject.workspace_path();
+    let py_ver = pyproject.venv_python_version()?;
+    if py_ver.name != "cpython" {
+        bail!(
+            "cannot generate Dockerfile for {}, only cpython toolchains are supported",
+            py_ver
+        );
+    }
+    let lockfile = pyproject.lockfile_path(LockMode::Production);
+    if !lockfile.is_file() {
+        bail!("no lockfile found, run `rye lock` first");
+    }
+    let lockfile = lockfile.file_name().unwrap().to_string_lossy();
+
+    // the projects that are installed into the image with their paths
+    // relative to the workspace root, which is the build context.
+    let members;
+    let projects = match pyproject.workspace() {
+        Some(workspace) => {
+            members = workspace.iter_projects().collect::<Result<Vec<_>, _>>()?;
+            members.iter().collect::<Vec<_>>()
+        }
+        None => vec![&pyproject],
+    };
+    let packages = projects
+        .iter()
+        .filter(|x| !x.is_virtual())
+        .map(|x| match x.root_path().strip_prefix(&*root) {
+            Ok(rel) if rel == Path::new("") => ".".to_string(),
+            Ok(rel) => format!("./{}", rel.to_string_lossy().replace('\\', "/")),
+            Err(_) => ".".to_string(),
+        })
+        .collect::<Vec<_>>();
+
+    // prefer running the first script of the project, fall back to the module.
+    let cmd = if pyproject.is_virtual() {
+        vec!["python".to_string()]
+    } else if let Some((script, _)) = pyproject.entry_points().into_iter().next() {
+        vec![script]
+    } else {
+        let module = pyproject.normalized_name()?.replace('-', "_");
+        vec!["python".into(), "-m".into(), module]
+    };
+
+    let python_version = format!("{}.{}.{}", py_ver.major, py_ver.minor, py_ver.patch);
+    let python_major_minor = format!("{}.{}", py_ver.major, py_ver.minor);
+    let uv_version = UvDownload::try_from(UvRequest::default())?.version();
+
+    let mut files = vec![(
+        root.join("Dockerfile"),
+        render!(
+            DOCKERFILE_TEMPLATE,
+            python_version,
+            python_major_minor,
+            uv_version,
+            lockfile,
+            packages,
+            virtual => pyproject.is_virtual(),
+            cmd,
+        ),
+    )];
+    if args.devcontainer {
+        files.push((
+            root.join(".devcontainer").join("devcontainer.json"),
+            render!(
+                DEVCONTAINER_TEMPLATE,
+                name => pyproject.name().unwrap_or("project"),
+                python_version,
+                python_major_minor,
+            ),
+        ));
+    }
+    if !args.force {
+        if let Some((path, _)) = files.iter().find(|(path, _)| path.is_file()) {
+            bail!(
+                "{} already exists, use --force to overwrite it",
+                path.display()
+            );
+        }
+    }
+    // an existing .dockerignore is never touched
+    let dockerignore = root.join(".dockerignore");
+    if !dockerignore.is_file() {
+        files.push((dockerignore, DOCKERIGNORE.to_string()));
+    }
+
+    for (path, contents) in files {
+        write_file(&path, &contents)?;
+    }
+    echo!("Build the image with {}", style("docker build .").cyan());
+    Ok(())
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).path_context(parent, "failed to create directory")?;
+    }
+    fs::write(path, format!("{}\n", contents.trim_end()))
+        .path_context(path, "failed to write file")?;
+    echo!("Generated {}", style(path.display()).cyan());
+    Ok(())
+}
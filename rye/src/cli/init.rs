@@ -12,22 +12,28 @@ use license::License;
 use minijinja::{context, Environment};
 use monotrail_utils::RequirementsTxt;
 use pep440_rs::VersionSpecifier;
-use pep508_rs::Requirement;
+use pep508_rs::{Requirement, VersionOrUrl};
 use serde_json::Value;
 use tempfile::tempdir;
+use toml_edit::{Array, ArrayOfTables, DocumentMut, Item, Table};
 
+use crate::artifact::{import_artifact, ArtifactImport};
 use crate::bootstrap::ensure_self_venv;
 use crate::config::Config;
 use crate::platform::{
-    get_default_author_with_fallback, get_latest_cpython_version, get_pinnable_version,
-    get_python_version_request_from_pyenv_pin,
+    get_app_dir, get_default_author_with_fallback, get_latest_cpython_version,
+    get_pinnable_version, get_python_version_request_from_pyenv_pin,
 };
-use crate::pyproject::BuildSystem;
+use crate::poetry::{convert_pyproject, find_poetry_members, is_poetry_project};
+use crate::pyproject::{normalize_package_name, BuildSystem};
 use crate::sources::py::PythonVersionRequest;
+use crate::tui::ensure_interactive;
+use crate::utils::toml::{iter_tables, reformat_array_multiline};
 use crate::utils::{
     copy_dir, escape_string, format_requirement, get_venv_python_bin, is_inside_git_work_tree,
-    CommandOutput, CopyDirOptions, IoPathContext,
+    pin_url_hash, CommandOutput, CopyDirOptions, IoPathContext,
 };
+use crate::wizard::run_init_wizard;
 
 /// Initialize a new or existing Python project with Rye.
 #[derive(Parser, Debug)]
@@ -71,6 +77,21 @@ pub struct Args {
     /// Python package.  It also cannot be published.
     #[arg(long = "virtual")]
     is_virtual: bool,
+    /// Do not merge the user-level defaults from `new-project-defaults.toml`.
+    #[arg(long)]
+    no_defaults: bool,
+    /// Convert an existing Poetry project in place.
+    #[arg(long)]
+    migrate: bool,
+    /// Convert all Poetry packages below the path into a workspace.
+    #[arg(long, requires = "migrate")]
+    workspace: bool,
+    /// Bootstrap the project from the metadata and sources of a wheel or sdist.
+    #[arg(long, value_name = "ARTIFACT", conflicts_with_all = ["migrate", "is_virtual"])]
+    from: Option<PathBuf>,
+    /// Walk through the options interactively.
+    #[arg(short, long, conflicts_with_all = ["migrate", "from", "quiet"])]
+    interactive: bool,
     /// Requirements files to initialize pyproject.toml with.
     #[arg(short, long, name = "REQUIREMENTS_FILE", conflicts_with = "no_import")]
     requirements: Option<Vec<PathBuf>>,
@@ -85,6 +106,13 @@ pub struct Args {
     quiet: bool,
 }
 
+impl Args {
+    /// The folder the project is created in.
+    pub fn project_dir(&self) -> Result<PathBuf, Error> {
+        Ok(env::current_dir()?.join(&self.path))
+    }
+}
+
 #[derive(Parser, Debug)]
 #[group(multiple = false)]
 struct ArgTemplateChoice {
@@ -150,21 +178,49 @@ if __name__ == "setuptools":
         return getattr(__setuptools, name)
 "#;
 
-pub fn execute(cmd: Args) -> Result<(), Error> {
+pub fn execute(mut cmd: Args) -> Result<(), Error> {
     let cfg = Config::current();
     let mut env = Environment::new();
     env.set_keep_trailing_newline(true);
-    let dir = env::current_dir()?.join(cmd.path);
+    let dir = cmd.project_dir()?;
     let toml = dir.join("pyproject.toml");
     let readme = dir.join("README.md");
     let license_file = dir.join("LICENSE.txt");
     let python_version_file = dir.join(".python-version");
-    let is_virtual = cmd.is_virtual;
+
+    if cmd.migrate {
+        let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+        return migrate_poetry(&dir, cmd.workspace, output);
+    }
 
     if toml.is_file() {
         bail!("pyproject.toml already exists");
     }
 
+    let mut extra_dependencies = Vec::new();
+    if cmd.interactive {
+        ensure_interactive("pass the options as flags instead of --interactive")?;
+        let answers = run_init_wizard(
+            &cmd.name.clone().unwrap_or_else(|| {
+                dir.file_name()
+                    .map(|x| x.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "unknown".into())
+            }),
+            cmd.build_system
+                .or_else(|| cfg.default_build_system())
+                .unwrap_or(BuildSystem::Hatchling),
+            cmd.license.clone().or_else(|| cfg.default_license()),
+        )?;
+        cmd.name = Some(answers.name);
+        cmd.is_virtual = answers.is_virtual;
+        cmd.init_type.script = answers.script;
+        cmd.build_system = Some(answers.build_system);
+        cmd.license = answers.license;
+        cmd.py = Some(answers.python.to_string());
+        extra_dependencies = answers.dependencies;
+    }
+    let is_virtual = cmd.is_virtual;
+
     // fail silently if it already exists or cannot be created.
     fs::create_dir_all(&dir).ok();
 
@@ -203,6 +259,8 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     let author = get_default_author_with_fallback(&dir);
     let license = match cmd.license {
         Some(license) => Some(license),
+        // the wizard already offered the default license
+        None if cmd.interactive => None,
         None => cfg.default_license(),
     };
     if license.is_some() && !license_file.is_file() {
@@ -227,8 +285,34 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     // initialize with no metadata
     let mut metadata = Metadata::new();
 
+    // an artifact provides the complete metadata and the sources.
+    let artifact = match cmd.from {
+        Some(ref path) => {
+            let artifact = import_artifact(path, &dir)
+                .with_context(|| format!("failed to import {}", path.display()))?;
+            echo!(
+                if output,
+                "Imported {} {} from {}",
+                style(&artifact.name).cyan(),
+                artifact.version,
+                path.display()
+            );
+            metadata.name = Some(artifact.name.clone());
+            metadata.version = Some(artifact.version.clone());
+            metadata.description.clone_from(&artifact.description);
+            metadata.author.clone_from(&artifact.author);
+            metadata
+                .requires_python
+                .clone_from(&artifact.requires_python);
+            metadata.license.clone_from(&artifact.license);
+            metadata.dependencies = Some(artifact.dependencies.clone());
+            Some(artifact)
+        }
+        None => None,
+    };
+
     // by default rye attempts to import metadata first.
-    if !cmd.no_import {
+    if !cmd.no_import && artifact.is_none() {
         let options = ImportOptions {
             output,
             requirements: cmd.requirements,
@@ -264,6 +348,9 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     if metadata.dependencies.is_none() {
         metadata.dependencies = Some(Vec::new())
     }
+    if let Some(ref mut dependencies) = metadata.dependencies {
+        dependencies.extend(extra_dependencies);
+    }
 
     // write .python-version
     if !cmd.no_pin && !python_version_file.is_file() {
@@ -378,6 +465,15 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
             private,
         },
     )?;
+    let rv = if cmd.no_defaults {
+        rv
+    } else {
+        apply_new_project_defaults(rv)?
+    };
+    let rv = match artifact {
+        Some(ref artifact) => apply_artifact_import(rv, artifact, build_system)?,
+        None => rv,
+    };
     fs::write(&toml, rv).context("failed to write pyproject.toml")?;
 
     if !is_virtual {
@@ -452,11 +548,373 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         if is_virtual { "virtual " } else { "" },
         dir.display()
     );
+    if let Some(artifact) = artifact.filter(|x| !x.follow_ups.is_empty()) {
+        echo!(if output, "Manual follow-ups:");
+        for msg in &artifact.follow_ups {
+            echo!(if output, "  {}", msg);
+        }
+    }
     echo!(if output, "  Run `rye sync` to get started");
 
     Ok(())
 }
 
+/// Adds what the template cannot express from an imported artifact to the
+/// rendered pyproject.toml: extras, entry points and the packages.
+fn apply_artifact_import(
+    rendered: String,
+    artifact: &ArtifactImport,
+    build_system: BuildSystem,
+) -> Result<String, Error> {
+    let mut doc = rendered
+        .parse::<DocumentMut>()
+        .context("generated invalid pyproject.toml")?;
+    let project = doc["project"]
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("generated pyproject.toml has no [project] table"))?;
+
+    if !artifact.optional_dependencies.is_empty() {
+        let mut extras = Table::new();
+        for (extra, deps) in &artifact.optional_dependencies {
+            let mut array = deps.iter().collect::<Array>();
+            reformat_array_multiline(&mut array);
+            extras.insert(extra, Item::Value(array.into()));
+        }
+        project.insert("optional-dependencies", Item::Table(extras));
+    }
+
+    for (group, entries) in &artifact.entry_points {
+        let mut table = Table::new();
+        for (name, value) in entries {
+            table.insert(name, value.as_str().into());
+        }
+        match group.as_str() {
+            "console_scripts" => project.insert("scripts", Item::Table(table)),
+            "gui_scripts" => project.insert("gui-scripts", Item::Table(table)),
+            _ => {
+                if project.get("entry-points").is_none() {
+                    let mut entry_points = Table::new();
+                    entry_points.set_implicit(true);
+                    project.insert("entry-points", Item::Table(entry_points));
+                }
+                project["entry-points"][group.as_str()] = Item::Table(table);
+                None
+            }
+        };
+    }
+
+    // the imported packages do not necessarily match the project name
+    if build_system == BuildSystem::Hatchling && !artifact.packages.is_empty() {
+        let mut packages = artifact
+            .packages
+            .iter()
+            .map(|x| format!("src/{}", x))
+            .collect::<Array>();
+        if packages.len() > 1 {
+            reformat_array_multiline(&mut packages);
+        }
+        doc["tool"]["hatch"]["build"]["targets"]["wheel"]["packages"] =
+            Item::Value(packages.into());
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Merges the user-level defaults for new projects into the rendered pyproject.toml.
+///
+/// The defaults file mirrors the layout of a `pyproject.toml` and only the
+/// `tool.rye` keys `scripts`, `dev-dependencies` and `sources` are merged.
+/// Values already present in the generated project take precedence.
+fn apply_new_project_defaults(rendered: String) -> Result<String, Error> {
+    let path = get_app_dir().join("new-project-defaults.toml");
+    if !path.is_file() {
+        return Ok(rendered);
+    }
+    let defaults = fs::read_to_string(&path)
+        .path_context(&path, "failed to read new project defaults")?
+        .parse::<DocumentMut>()
+        .with_context(|| format!("failed to parse {}", path.display()))?;
+    let Some(defaults) = defaults
+        .get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.as_table_like())
+    else {
+        return Ok(rendered);
+    };
+
+    let mut doc = rendered
+        .parse::<DocumentMut>()
+        .context("generated invalid pyproject.toml")?;
+    let rye = doc["tool"]["rye"]
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("generated pyproject.toml has no [tool.rye] table"))?;
+
+    for (key, value) in defaults.iter() {
+        match key {
+            "scripts" => {
+                let scripts = value.as_table_like().ok_or_else(|| {
+                    anyhow!("tool.rye.scripts in {} must be a table", path.display())
+                })?;
+                if rye.get("scripts").is_none() {
+                    rye.insert("scripts", Item::Table(Table::new()));
+                }
+                let target = rye["scripts"].as_table_like_mut().ok_or_else(|| {
+                    anyhow!(
+                        "cannot merge tool.rye.scripts from {}: not a table in the new project",
+                        path.display()
+                    )
+                })?;
+                for (name, script) in scripts.iter() {
+                    if target.get(name).is_none() {
+                        target.insert(name, script.clone());
+                    }
+                }
+            }
+            "dev-dependencies" => {
+                let deps = value.as_array().ok_or_else(|| {
+                    anyhow!(
+                        "tool.rye.dev-dependencies in {} must be an array",
+                        path.display()
+                    )
+                })?;
+                if rye.get("dev-dependencies").is_none() {
+                    rye.insert("dev-dependencies", Item::Value(Array::new().into()));
+                }
+                let target = rye["dev-dependencies"].as_array_mut().ok_or_else(|| {
+                    anyhow!(
+                        "cannot merge tool.rye.dev-dependencies from {}: not an array in the new project",
+                        path.display()
+                    )
+                })?;
+                let existing = target
+                    .iter()
+                    .filter_map(|x| x.as_str())
+                    .filter_map(|x| Requirement::from_str(x).ok())
+                    .map(|x| normalize_package_name(&x.name))
+                    .collect::<Vec<_>>();
+                for dep in deps.iter() {
+                    let req = dep
+                        .as_str()
+                        .and_then(|x| Requirement::from_str(x).ok())
+                        .ok_or_else(|| {
+                            anyhow!("invalid dev-dependency {} in {}", dep, path.display())
+                        })?;
+                    if !existing.contains(&normalize_package_name(&req.name)) {
+                        target.push(req.to_string());
+                    }
+                }
+                reformat_array_multiline(target);
+            }
+            "sources" => {
+                let existing = rye
+                    .get("sources")
+                    .map(|x| {
+                        iter_tables(x)
+                            .filter_map(|x| x.ok())
+                            .filter_map(|x| x.get("name").and_then(|x| x.as_str()))
+                            .map(|x| x.to_string())
+                            .collect::<Vec<_>>()
+                    })
+                    .unwrap_or_default();
+                if rye.get("sources").is_none() {
+                    rye.insert("sources", Item::ArrayOfTables(ArrayOfTables::new()));
+                }
+                let target = rye["sources"].as_array_of_tables_mut().ok_or_else(|| {
+                    anyhow!(
+                        "cannot merge tool.rye.sources from {}: not an array of tables in the new project",
+                        path.display()
+                    )
+                })?;
+                for source in iter_tables(value) {
+                    let source = source.with_context(|| {
+                        format!("invalid tool.rye.sources in {}", path.display())
+                    })?;
+                    let name = source.get("name").and_then(|x| x.as_str()).unwrap_or("");
+                    if existing.iter().any(|x| x == name) {
+                        continue;
+                    }
+                    let mut table = Table::new();
+                    for (key, value) in source.iter() {
+                        table.insert(key, value.clone());
+                    }
+                    target.push(table);
+                }
+            }
+            other => {
+                warn!(
+                    category = Config,
+                    "ignoring unsupported key tool.rye.{} in {}",
+                    other,
+                    path.display()
+                );
+            }
+        }
+    }
+
+    Ok(doc.to_string())
+}
+
+/// Converts a Poetry project (or all Poetry projects of a monorepo) in place.
+///
+/// All follow-ups that need manual attention are collected and reported once
+/// at the end.
+fn migrate_poetry(dir: &Path, workspace: bool, output: CommandOutput) -> Result<(), Error> {
+    let toml = dir.join("pyproject.toml");
+    let root_doc = if toml.is_file() {
+        Some(
+            fs::read_to_string(&toml)
+                .path_context(&toml, "failed to read pyproject.toml")?
+                .parse::<DocumentMut>()
+                .path_context(&toml, "failed to parse pyproject.toml")?,
+        )
+    } else {
+        None
+    };
+    let root_is_poetry = root_doc.as_ref().map_or(false, is_poetry_project);
+    if !workspace && !root_is_poetry {
+        bail!("no Poetry project found in {}", dir.display());
+    }
+    if root_doc.is_some() && !root_is_poetry {
+        bail!("pyproject.toml already exists and is not a Poetry project");
+    }
+
+    let members = if workspace {
+        find_poetry_members(dir)?
+    } else {
+        Vec::new()
+    };
+    if workspace && members.is_empty() {
+        bail!("no Poetry packages found below {}", dir.display());
+    }
+
+    // all projects are converted before anything is written so that a project
+    // that fails to convert does not leave a half migrated workspace behind.
+    let mut conversions = Vec::new();
+    let projects = members
+        .iter()
+        .map(|x| x.as_path())
+        .chain(root_is_poetry.then_some(Path::new("")));
+    for rel_path in projects {
+        let project_dir = dir.join(rel_path);
+        let path = project_dir.join("pyproject.toml");
+        let source =
+            fs::read_to_string(&path).path_context(&path, "failed to read pyproject.toml")?;
+        let conversion = convert_pyproject(&source, &project_dir, rel_path, &members)
+            .with_context(|| format!("failed to convert {}", path.display()))?;
+        conversions.push((rel_path, conversion));
+    }
+
+    // the workspace is configured in the converted root project or a new one
+    let mut workspace_root = None;
+    if workspace {
+        let mut doc = match conversions.iter().find(|(x, _)| *x == Path::new("")) {
+            Some((_, conversion)) => conversion
+                .pyproject
+                .parse::<DocumentMut>()
+                .path_context(&toml, "failed to parse pyproject.toml")?,
+            None => new_workspace_root(dir)?,
+        };
+        let mut member_paths = Array::new();
+        for member in &members {
+            member_paths.push(member.to_string_lossy().replace('\\', "/"));
+        }
+        reformat_array_multiline(&mut member_paths);
+        let rye = doc["tool"]["rye"]
+            .as_table_mut()
+            .ok_or_else(|| anyhow!("[tool.rye] in pyproject.toml is malformed"))?;
+        let mut workspace_table = Table::new();
+        workspace_table.insert("members", Item::Value(member_paths.into()));
+        rye.insert("workspace", Item::Table(workspace_table));
+        workspace_root = Some(doc.to_string());
+    }
+
+    let converted = conversions.len();
+    let mut follow_ups = Vec::new();
+    for (rel_path, conversion) in conversions {
+        let project_dir = dir.join(rel_path);
+        let path = project_dir.join("pyproject.toml");
+        let contents = if rel_path == Path::new("") {
+            workspace_root.take().unwrap_or(conversion.pyproject)
+        } else {
+            conversion.pyproject
+        };
+        fs::write(&path, contents).path_context(&path, "failed to write pyproject.toml")?;
+        echo!(
+            if output,
+            "Converted {} ({})",
+            style(&conversion.name).cyan(),
+            style(rel_path.join("pyproject.toml").display()).dim()
+        );
+        follow_ups.extend(
+            conversion
+                .follow_ups
+                .into_iter()
+                .map(|msg| (conversion.name.clone(), msg)),
+        );
+        if project_dir.join("poetry.lock").is_file() {
+            follow_ups.push((
+                conversion.name.clone(),
+                "poetry.lock is no longer used and can be removed".into(),
+            ));
+        }
+    }
+    if let Some(root) = workspace_root {
+        fs::write(&toml, root).path_context(&toml, "failed to write pyproject.toml")?;
+    }
+    if workspace {
+        echo!(
+            if output,
+            "Configured workspace with {} member(s)",
+            members.len()
+        );
+    }
+
+    echo!(
+        if output,
+        "{} Migrated {} Poetry project(s) in {}",
+        style("success:").green(),
+        converted,
+        dir.display()
+    );
+    if !follow_ups.is_empty() {
+        echo!(if output, "Manual follow-ups:");
+        for (name, msg) in &follow_ups {
+            echo!(if output, "  {}: {}", style(name).cyan(), msg);
+        }
+    }
+    echo!(if output, "  Run `rye sync` to get started");
+
+    Ok(())
+}
+
+/// Creates a virtual project to use as root for a migrated workspace.
+fn new_workspace_root(dir: &Path) -> Result<DocumentMut, Error> {
+    let name = slug::slugify(
+        dir.file_name()
+            .map(|x| x.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "workspace".into()),
+    );
+    let mut doc = DocumentMut::new();
+    let mut project = Table::new();
+    project.insert("name", name.as_str().into());
+    project.insert("version", "0.1.0".into());
+    project.insert("dependencies", Item::Value(Array::new().into()));
+    project.insert(
+        "requires-python",
+        Config::current().default_requires_python().as_str().into(),
+    );
+    doc.insert("project", Item::Table(project));
+    let mut tool = Table::new();
+    tool.set_implicit(true);
+    let mut rye = Table::new();
+    rye.insert("managed", true.into());
+    rye.insert("virtual", true.into());
+    rye.insert("dev-dependencies", Item::Value(Array::new().into()));
+    tool.insert("rye", Item::Table(rye));
+    doc.insert("tool", Item::Table(tool));
+    Ok(doc)
+}
+
 #[derive(Default)]
 struct Metadata {
     name: Option<String>,
@@ -698,10 +1156,18 @@ fn import_requirements_file(
         .parent()
         .context("could not establish setup.py parent dir")?;
     let data = RequirementsTxt::parse(path, dir)?;
-    data.requirements.iter().for_each(|x| {
+    for entry in &data.requirements {
+        let mut requirement = entry.requirement.clone();
+        // direct URL requirements retain their hash so that it can be enforced on sync
+        if let Some(VersionOrUrl::Url(ref mut url)) = requirement.version_or_url {
+            if let Some(hash) = entry.hashes.first() {
+                pin_url_hash(url, hash)
+                    .with_context(|| format!("invalid hash for {}", requirement.name))?;
+            }
+        }
         requirements
-            .entry(x.requirement.name.to_string())
-            .or_insert(format_requirement(&x.requirement).to_string());
-    });
+            .entry(requirement.name.to_string())
+            .or_insert(format_requirement(&requirement).to_string());
+    }
     Ok(())
 }
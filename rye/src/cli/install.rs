@@ -1,21 +1,32 @@
 use std::path::Path;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
 
-use anyhow::{Context, Error};
+use anyhow::{bail, Context, Error};
 use clap::Parser;
+use console::style;
+use indicatif::ProgressStyle;
 use pep508_rs::Requirement;
 
+use crate::bootstrap::{ensure_self_venv, fetch, FetchOptions};
 use crate::cli::add::ReqExtras;
 use crate::config::Config;
-use crate::installer::{install, resolve_local_requirement};
+use crate::installer::{install, resolve_local_requirement, ShimConflict};
 use crate::lock::KeyringProvider;
 use crate::sources::py::PythonVersionRequest;
+use crate::tui::{is_interactive, progress_bar};
 use crate::utils::CommandOutput;
 
 /// Installs a package as global tool.
+///
+/// Multiple packages can be passed at once, they are then installed in
+/// parallel.
 #[derive(Parser, Debug)]
 pub struct Args {
-    /// The name of the package to install.
-    requirement: String,
+    /// The name of the package(s) to install.
+    #[arg(required = true)]
+    requirements: Vec<String>,
     #[command(flatten)]
     req_extras: ReqExtras,
     /// Include scripts from a given dependency.
@@ -30,9 +41,24 @@ pub struct Args {
     /// Force install the package even if it's already there.
     #[arg(short, long)]
     force: bool,
+    /// Install the versions recorded in the lockfile of the tool.
+    #[arg(long)]
+    locked: bool,
     /// Attempt to use `keyring` for authentication for index URLs.
     #[arg(long, value_enum, default_value_t)]
     keyring_provider: KeyringProvider,
+    /// Compile Python files to bytecode after installation.
+    #[arg(long)]
+    compile_bytecode: bool,
+    /// Install scripts that conflict with existing shims as `<script>@<tool>`.
+    #[arg(long)]
+    suffix: bool,
+    /// How many tools to install in parallel (defaults to the number of CPUs).
+    #[arg(short, long)]
+    jobs: Option<usize>,
+    /// Extra argument passed to uv as is (unsupported, use at your own risk).
+    #[arg(long = "uv-arg", value_name = "ARG", allow_hyphen_values = true)]
+    uv_args: Vec<String>,
     /// Enables verbose diagnostics.
     #[arg(short, long)]
     verbose: bool,
@@ -43,17 +69,11 @@ pub struct Args {
 
 pub fn execute(mut cmd: Args) -> Result<(), Error> {
     let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
-    let mut extra_requirements = Vec::new();
 
-    // main requirement
-    let mut requirement = handle_requirement(&cmd.requirement, output, true)?;
-    // installations here always use absolute paths for local references
-    // because we do not have a rye workspace to work with.
-    cmd.req_extras.force_absolute();
-    cmd.req_extras.apply_to_requirement(&mut requirement)?;
-
-    for req in cmd.extra_requirement {
-        extra_requirements.push(handle_requirement(&req, output, false)?);
+    if cmd.requirements.len() > 1
+        && (cmd.req_extras.has_specifiers() || !cmd.extra_requirement.is_empty())
+    {
+        bail!("path/url/git/features/hash/extra-requirement is not compatible with passing multiple requirements: expected one requirement.")
     }
 
     let py_ver: PythonVersionRequest = match cmd.python {
@@ -71,18 +91,210 @@ pub fn execute(mut cmd: Args) -> Result<(), Error> {
             }),
     };
 
+    if cmd.requirements.len() > 1 {
+        return install_many(cmd, py_ver, output);
+    }
+
+    let mut extra_requirements = Vec::new();
+
+    // main requirement
+    let mut requirement = handle_requirement(&cmd.requirements[0], output, true)?;
+    // installations here always use absolute paths for local references
+    // because we do not have a rye workspace to work with.
+    cmd.req_extras.force_absolute();
+    cmd.req_extras.apply_to_requirement(&mut requirement)?;
+
+    for req in cmd.extra_requirement {
+        extra_requirements.push(handle_requirement(&req, output, false)?);
+    }
+
+    let on_conflict = if cmd.suffix {
+        ShimConflict::Suffix
+    } else if output != CommandOutput::Quiet && is_interactive() {
+        ShimConflict::Prompt
+    } else {
+        ShimConflict::Fail
+    };
+
     install(
         requirement,
         &py_ver,
         cmd.force,
+        cmd.locked,
         &cmd.include_dep,
         &extra_requirements,
         output,
         cmd.keyring_provider,
+        cmd.compile_bytecode,
+        on_conflict,
+        &cmd.uv_args,
     )?;
     Ok(())
 }
 
+/// Installs multiple tools in parallel and reports a status per tool.
+fn install_many(
+    cmd: Args,
+    py_ver: PythonVersionRequest,
+    output: CommandOutput,
+) -> Result<(), Error> {
+    let mut requirements = Vec::new();
+    for req in &cmd.requirements {
+        requirements.push(handle_requirement(req, output, true)?);
+    }
+
+    // the internal virtualenv and the toolchain are shared by all tools, make
+    // sure they exist before installing in parallel.
+    ensure_self_venv(output)?;
+    let py_ver: PythonVersionRequest = fetch(&py_ver, FetchOptions::with_output(output))?.into();
+
+    // there is no way to prompt while installing in parallel
+    let on_conflict = if cmd.suffix {
+        ShimConflict::Suffix
+    } else {
+        ShimConflict::Fail
+    };
+
+    let requirements = requirements
+        .into_iter()
+        .map(|x| (x.name.to_string(), x))
+        .collect();
+    run_parallel(
+        requirements,
+        cmd.jobs,
+        ToolAction::Install,
+        output,
+        |requirement| {
+            install(
+                requirement,
+                &py_ver,
+                cmd.force,
+                cmd.locked,
+                &cmd.include_dep,
+                &[],
+                CommandOutput::Quiet,
+                cmd.keyring_provider,
+                cmd.compile_bytecode,
+                on_conflict,
+                &cmd.uv_args,
+            )
+        },
+    )
+}
+
+/// What [`run_parallel`] does with the tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolAction {
+    Install,
+    Upgrade,
+}
+
+impl ToolAction {
+    fn verb(self) -> &'static str {
+        match self {
+            ToolAction::Install => "install",
+            ToolAction::Upgrade => "upgrade",
+        }
+    }
+
+    fn progressive(self) -> &'static str {
+        match self {
+            ToolAction::Install => "installing",
+            ToolAction::Upgrade => "upgrading",
+        }
+    }
+
+    fn past(self) -> &'static str {
+        match self {
+            ToolAction::Install => "installed",
+            ToolAction::Upgrade => "upgraded",
+        }
+    }
+}
+
+/// Runs `f` for multiple tools in parallel and reports a status per tool.
+///
+/// The tools are given by name together with what is passed to `f`, which
+/// returns the installed scripts.  At most `jobs` tools are processed at once
+/// (defaults to the number of CPUs).
+pub fn run_parallel<T: Send>(
+    tools: Vec<(String, T)>,
+    jobs: Option<usize>,
+    action: ToolAction,
+    output: CommandOutput,
+    f: impl Fn(T) -> Result<Vec<String>, Error> + Sync,
+) -> Result<(), Error> {
+    let total = tools.len();
+    let jobs = jobs
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |x| x.get()))
+        .clamp(1, total.max(1));
+
+    let pb = progress_bar(total as u64, output);
+    pb.set_style(ProgressStyle::with_template("{spinner} [{pos}/{len}] {wide_msg}").unwrap());
+    pb.enable_steady_tick(Duration::from_millis(100));
+
+    let queue = Mutex::new(tools.into_iter().enumerate());
+    let results = Mutex::new(Vec::new());
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let Some((idx, (name, arg))) = queue.lock().unwrap().next() else {
+                    break;
+                };
+                pb.set_message(format!("{} {}", action.progressive(), name));
+                let result = f(arg);
+                pb.inc(1);
+                results.lock().unwrap().push((idx, name, result));
+            });
+        }
+    });
+    pb.finish_and_clear();
+
+    let mut results = results.into_inner().unwrap();
+    results.sort_by_key(|(idx, _, _)| *idx);
+    let failed = results.iter().filter(|(_, _, x)| x.is_err()).count();
+
+    if output != CommandOutput::Quiet {
+        let width = results
+            .iter()
+            .map(|(_, name, _)| name.len())
+            .max()
+            .unwrap_or(0);
+        for (_, name, result) in &results {
+            match result {
+                Ok(scripts) if scripts.is_empty() => echo!(
+                    "{:<width$}  {}",
+                    style(name).cyan(),
+                    style(format!("{}, no scripts", action.past())).yellow()
+                ),
+                Ok(scripts) => echo!(
+                    "{:<width$}  {} ({})",
+                    style(name).cyan(),
+                    style(action.past()).green(),
+                    scripts.join(", ")
+                ),
+                Err(err) => echo!(
+                    "{:<width$}  {} {}",
+                    style(name).cyan(),
+                    style("failed:").red(),
+                    err
+                ),
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{} of {} tool{} failed to {}",
+            failed,
+            total,
+            if total == 1 { "" } else { "s" },
+            action.verb()
+        );
+    }
+    Ok(())
+}
+
 fn handle_requirement(
     req: &str,
     output: CommandOutput,
@@ -1,10 +1,15 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
 
-use anyhow::Error;
+use anyhow::{anyhow, bail, Error};
 use clap::Parser;
+use pep508_rs::Requirement;
+use python_pkginfo::Metadata;
 
-use crate::pyproject::PyProject;
-use crate::utils::{get_venv_python_bin, CommandOutput};
+use crate::lock::{LockGraph, LockMode};
+use crate::pyproject::{normalize_package_name, PyProject};
+use crate::utils::{get_venv_python_bin, CommandOutput, IoPathContext};
 use crate::uv::{UvBuilder, Venv};
 
 /// Prints the currently installed packages.
@@ -13,10 +18,19 @@ pub struct Args {
     /// Use this pyproject.toml file
     #[arg(long, value_name = "PYPROJECT_TOML")]
     pub(crate) pyproject: Option<PathBuf>,
+    /// Print the dependencies as a tree as recorded in the lockfile.
+    #[arg(long)]
+    tree: bool,
+    /// Only print the subtree of this package.
+    #[arg(short, long, requires = "tree")]
+    package: Option<String>,
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let project = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    if cmd.tree {
+        return print_tree(&project, cmd.package.as_deref());
+    }
     let python = get_venv_python_bin(&project.venv_path());
     if !python.is_file() {
         warn!("Project is not synced, no virtualenv found. Run `rye sync`.");
@@ -28,3 +42,176 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     uv.read_only_venv(&project.venv_path())?.freeze()?;
     Ok(())
 }
+
+/// Prints the dependency tree from the lockfile without touching the network.
+///
+/// The markers of the dependencies are taken from the metadata of the
+/// installed packages if the project was synced.
+fn print_tree(project: &PyProject, package: Option<&str>) -> Result<(), Error> {
+    let lockfile = [LockMode::Dev, LockMode::Production]
+        .into_iter()
+        .map(|x| project.lockfile_path(x))
+        .find(|x| x.is_file())
+        .ok_or_else(|| anyhow!("no lockfile found, run `rye lock` first"))?;
+    let contents =
+        fs::read_to_string(&lockfile).path_context(&lockfile, "failed to read lockfile")?;
+    let graph = LockGraph::parse(&contents);
+
+    let roots = match package {
+        Some(name) if graph.contains(name) => vec![normalize_package_name(name)],
+        Some(name) => bail!("package '{}' is not in {}", name, lockfile.display()),
+        None => graph.roots(),
+    };
+    let installed = installed_requirements(&project.venv_path());
+    for line in render_tree(&graph, &installed, &roots) {
+        echo!("{}", line);
+    }
+    Ok(())
+}
+
+/// Reads the `Requires-Dist` of the packages installed in a virtualenv by
+/// normalized name.
+fn installed_requirements(venv: &Path) -> HashMap<String, Vec<Requirement>> {
+    let site_packages = if cfg!(windows) {
+        vec![venv.join("Lib").join("site-packages")]
+    } else {
+        fs::read_dir(venv.join("lib"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|x| x.path().join("site-packages"))
+            .collect()
+    };
+
+    let mut rv = HashMap::new();
+    for dir in site_packages {
+        for entry in fs::read_dir(&dir).into_iter().flatten().flatten() {
+            if !entry.file_name().to_string_lossy().ends_with(".dist-info") {
+                continue;
+            }
+            let Ok(contents) = fs::read(entry.path().join("METADATA")) else {
+                continue;
+            };
+            let Ok(metadata) = Metadata::parse(&contents) else {
+                continue;
+            };
+            rv.insert(
+                normalize_package_name(&metadata.name),
+                metadata
+                    .requires_dist
+                    .iter()
+                    .filter_map(|x| x.parse().ok())
+                    .collect(),
+            );
+        }
+    }
+    rv
+}
+
+/// Renders the subtrees of the given packages.
+///
+/// Packages that were already expanded are marked with `(*)` instead of
+/// repeating their dependencies, which also breaks cycles.
+fn render_tree(
+    graph: &LockGraph,
+    installed: &HashMap<String, Vec<Requirement>>,
+    roots: &[String],
+) -> Vec<String> {
+    let mut renderer = TreeRenderer {
+        graph,
+        installed,
+        expanded: HashSet::new(),
+        lines: Vec::new(),
+    };
+    for root in roots {
+        renderer.render(root, None, "", "");
+    }
+    renderer.lines
+}
+
+struct TreeRenderer<'a> {
+    graph: &'a LockGraph,
+    installed: &'a HashMap<String, Vec<Requirement>>,
+    expanded: HashSet<String>,
+    lines: Vec<String>,
+}
+
+impl TreeRenderer<'_> {
+    fn render(&mut self, name: &str, marker: Option<String>, prefix: &str, child_prefix: &str) {
+        let mut line = format!("{}{}", prefix, self.graph.requirement(name).unwrap_or(name));
+        if let Some(marker) = marker {
+            line.push_str(&format!(" ({})", marker));
+        }
+        let dependencies = self.graph.dependencies(name);
+        if !dependencies.is_empty() && !self.expanded.insert(name.to_string()) {
+            line.push_str(" (*)");
+            self.lines.push(line);
+            return;
+        }
+        self.lines.push(line);
+
+        for (idx, dependency) in dependencies.iter().enumerate() {
+            let (connector, indent) = if idx + 1 == dependencies.len() {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            let marker = self
+                .installed
+                .get(name)
+                .and_then(|reqs| {
+                    reqs.iter()
+                        .find(|x| normalize_package_name(&x.name) == *dependency)
+                })
+                .and_then(|x| x.marker.as_ref())
+                .map(|x| x.to_string());
+            self.render(
+                dependency,
+                marker,
+                &format!("{}{}", child_prefix, connector),
+                &format!("{}{}", child_prefix, indent),
+            );
+        }
+    }
+}
+
+#[test]
+fn test_render_tree() {
+    let graph = LockGraph::parse(
+        r#"# generated by rye
+-e file:.
+certifi==2024.7.4
+    # via requests
+colorama==0.4.6
+    # via click
+click==8.1.7
+    # via my-project
+idna==3.7
+    # via requests
+requests==2.32.3
+    # via my-project
+"#,
+    );
+    let colorama: Requirement = "colorama; platform_system == 'Windows'".parse().unwrap();
+    let colorama_line = format!(
+        "│   └── colorama==0.4.6 ({})",
+        colorama.marker.as_ref().unwrap()
+    );
+    let installed = HashMap::from([("click".to_string(), vec![colorama])]);
+
+    assert_eq!(
+        render_tree(&graph, &installed, &graph.roots()),
+        vec![
+            "my-project",
+            "├── click==8.1.7",
+            colorama_line.as_str(),
+            "└── requests==2.32.3",
+            "    ├── certifi==2024.7.4",
+            "    └── idna==3.7",
+        ]
+    );
+    assert_eq!(
+        render_tree(&graph, &installed, &["requests".to_string()]),
+        vec!["requests==2.32.3", "├── certifi==2024.7.4", "└── idna==3.7"]
+    );
+}
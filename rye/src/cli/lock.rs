@@ -1,9 +1,14 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Error;
+use anyhow::{bail, Context, Error};
 use clap::Parser;
 
-use crate::lock::{KeyringProvider, LockOptions};
+use crate::bootstrap::{fetch, FetchOptions};
+use crate::config::Config;
+use crate::lock::{update_requirements_lockfile, KeyringProvider, LockMode, LockOptions};
+use crate::platform::get_python_version_request_from_pyenv_pin;
+use crate::pyproject::ExpandedSources;
+use crate::sources::py::PythonVersionRequest;
 use crate::sync::{sync, SyncMode, SyncOptions};
 use crate::utils::CommandOutput;
 
@@ -46,30 +51,142 @@ pub struct Args {
     /// Reset prior lock options.
     #[arg(long)]
     reset: bool,
+    /// Move off yanked versions pinned in the lockfile.
+    #[arg(long)]
+    refresh_yanked: bool,
+    /// Fail if the lockfile contains yanked versions.
+    #[arg(long)]
+    deny_yanked: bool,
+    /// Fail if any of the pins in `tool.rye.pins` has expired.
+    #[arg(long)]
+    deny_expired_pins: bool,
+    /// Also lock the build requirements of all source builds into `build-requirements.lock`.
+    #[arg(long, conflicts_with = "requirements_in")]
+    emit_build_requirements: bool,
     /// Use this pyproject.toml file.
     #[arg(long, value_name = "PYPROJECT_TOML")]
     pyproject: Option<PathBuf>,
+    /// Only regenerate the dev lockfile, leaving the production lockfile untouched.
+    #[arg(long, conflicts_with = "only_prod")]
+    only_dev: bool,
+    /// Only regenerate the production lockfile, leaving the dev lockfile untouched.
+    #[arg(long)]
+    only_prod: bool,
+    /// Lock this requirements file instead of the project (no pyproject.toml needed).
+    #[arg(
+        long,
+        value_name = "REQUIREMENTS_IN",
+        conflicts_with_all = ["pyproject", "features", "all_features", "only_dev", "only_prod"]
+    )]
+    requirements_in: Option<PathBuf>,
+    /// Where to write the lockfile of `--requirements-in` (defaults to the input with a `.txt` extension).
+    #[arg(short, long, requires = "requirements_in")]
+    output: Option<PathBuf>,
+    /// The Python version to lock `--requirements-in` for.
+    #[arg(long, requires = "requirements_in")]
+    python: Option<String>,
+    /// Extra argument passed to uv as is (unsupported, use at your own risk).
+    #[arg(long = "uv-arg", value_name = "ARG", allow_hyphen_values = true)]
+    uv_args: Vec<String>,
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+    let lock_options = LockOptions {
+        update: cmd.update,
+        update_all: cmd.update_all,
+        pre: cmd.pre,
+        features: cmd.features,
+        all_features: cmd.all_features,
+        with_sources: cmd.with_sources,
+        reset: cmd.reset,
+        generate_hashes: cmd.generate_hashes,
+        universal: cmd.universal,
+        refresh_yanked: cmd.refresh_yanked,
+        deny_yanked: cmd.deny_yanked,
+        deny_expired_pins: cmd.deny_expired_pins,
+        emit_build_requirements: cmd.emit_build_requirements,
+        uv_args: cmd.uv_args,
+        ..Default::default()
+    };
+
+    if let Some(requirements_in) = cmd.requirements_in {
+        return lock_requirements_in(
+            &requirements_in,
+            cmd.output,
+            cmd.python,
+            output,
+            &lock_options,
+            cmd.keyring_provider,
+        );
+    }
+
     sync(SyncOptions {
         output,
         mode: SyncMode::LockOnly,
-        lock_options: LockOptions {
-            update: cmd.update,
-            update_all: cmd.update_all,
-            pre: cmd.pre,
-            features: cmd.features,
-            all_features: cmd.all_features,
-            with_sources: cmd.with_sources,
-            reset: cmd.reset,
-            generate_hashes: cmd.generate_hashes,
-            universal: cmd.universal,
-        },
+        lock_options,
         pyproject: cmd.pyproject,
         keyring_provider: cmd.keyring_provider,
+        only: if cmd.only_dev {
+            Some(LockMode::Dev)
+        } else if cmd.only_prod {
+            Some(LockMode::Production)
+        } else {
+            None
+        },
         ..SyncOptions::default()
     })?;
     Ok(())
 }
+
+fn lock_requirements_in(
+    requirements_in: &Path,
+    lockfile: Option<PathBuf>,
+    python: Option<String>,
+    output: CommandOutput,
+    lock_options: &LockOptions,
+    keyring_provider: KeyringProvider,
+) -> Result<(), Error> {
+    let requirements_in = requirements_in.canonicalize().with_context(|| {
+        format!(
+            "requirements file '{}' not found",
+            requirements_in.display()
+        )
+    })?;
+    let lockfile = match lockfile {
+        Some(lockfile) => lockfile,
+        None => requirements_in.with_extension("txt"),
+    };
+    if lockfile == requirements_in {
+        bail!(
+            "refusing to overwrite '{}' with its own lockfile, pass --output",
+            requirements_in.display()
+        );
+    }
+
+    let folder = requirements_in.parent().unwrap_or(Path::new("."));
+    let py_ver: PythonVersionRequest = match python {
+        Some(ref python) => python.parse()?,
+        None => match get_python_version_request_from_pyenv_pin(folder) {
+            Some(version) => version,
+            None => Config::current().default_toolchain()?,
+        },
+    };
+    let py_ver = fetch(&py_ver, FetchOptions::with_output(output))
+        .context("failed fetching toolchain ahead of locking")?;
+
+    // without a project only the sources from the config apply
+    let sources = ExpandedSources::from_sources_relative_to(&Config::current().sources()?, folder)?;
+    sources.echo_verbose(output);
+
+    update_requirements_lockfile(
+        &py_ver,
+        &requirements_in,
+        &lockfile,
+        output,
+        &sources,
+        lock_options,
+        keyring_provider,
+    )
+    .with_context(|| format!("could not write lockfile for {}", requirements_in.display()))
+}
@@ -0,0 +1,117 @@
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Error};
+use clap::Parser;
+use console::style;
+
+use crate::lock::{
+    find_lock_problems, make_relative_url, parse_locked_versions, LockMode, LockOptions,
+};
+use crate::lock_import::{import_lockfile, LockFormat};
+use crate::pyproject::PyProject;
+use crate::utils::{CommandOutput, IoPathContext};
+
+/// Converts a pip-tools, Poetry or PDM lockfile into a Rye lockfile.
+///
+/// The pins (and hashes) of the existing lockfile are kept as they are.  Before
+/// the lockfile is written it is verified that the pins satisfy the
+/// dependencies declared in the `pyproject.toml`.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// The lockfile to convert (a requirements.txt, poetry.lock or pdm.lock).
+    path: PathBuf,
+    /// The format of the lockfile.  By default it's derived from the file name.
+    #[arg(long)]
+    format: Option<LockFormat>,
+    /// Write the dev lockfile and include development dependencies.
+    #[arg(long)]
+    dev: bool,
+    /// Overwrite an existing Rye lockfile.
+    #[arg(short, long)]
+    force: bool,
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+    /// Enables verbose diagnostics.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Turns off all output.
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+pub fn execute(cmd: Args) -> Result<(), Error> {
+    let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+    let pyproject = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    let lock_mode = if cmd.dev {
+        LockMode::Dev
+    } else {
+        LockMode::Production
+    };
+    let lockfile = pyproject.lockfile_path(lock_mode);
+    if lockfile.is_file() && !cmd.force {
+        bail!(
+            "{} already exists, pass --force to overwrite it",
+            lockfile.display()
+        );
+    }
+
+    let format = cmd
+        .format
+        .unwrap_or_else(|| LockFormat::from_path(&cmd.path));
+    let contents =
+        fs::read_to_string(&cmd.path).path_context(&cmd.path, "failed to read lockfile")?;
+    let imported = import_lockfile(&contents, format, cmd.dev)
+        .with_context(|| format!("failed to convert {}", cmd.path.display()))?;
+
+    let members;
+    let projects = match pyproject.workspace() {
+        Some(workspace) => {
+            members = workspace.iter_projects().collect::<Result<Vec<_>, _>>()?;
+            members.iter().collect::<Vec<_>>()
+        }
+        None => vec![&pyproject],
+    };
+
+    let locked = parse_locked_versions(&imported.render_packages());
+    let problems = find_lock_problems(&locked, &projects, lock_mode);
+    if !problems.is_empty() {
+        bail!(
+            "{} does not satisfy the dependencies of the project:\n  - {}",
+            cmd.path.display(),
+            problems.join("\n  - ")
+        );
+    }
+
+    let mut rv = Vec::new();
+    LockOptions {
+        generate_hashes: imported.has_hashes(),
+        ..Default::default()
+    }
+    .write_header(&mut rv)?;
+    for project in &projects {
+        if !project.is_virtual() {
+            let rel_url = make_relative_url(&project.root_path(), &pyproject.workspace_path())?;
+            rv.extend_from_slice(format!("-e {}\n", rel_url).as_bytes());
+        }
+    }
+    rv.extend_from_slice(imported.render_packages().as_bytes());
+    fs::write(&lockfile, rv).path_context(&lockfile, "failed to write lockfile")?;
+
+    echo!(
+        if output,
+        "Converted {} package(s) from {} into {}",
+        imported.packages.len(),
+        cmd.path.display(),
+        style(lockfile.display()).cyan()
+    );
+    if !imported.follow_ups.is_empty() {
+        echo!(if output, "Manual follow-ups:");
+        for follow_up in &imported.follow_ups {
+            echo!(if output, "  {}", follow_up);
+        }
+    }
+
+    Ok(())
+}
@@ -1,41 +1,58 @@
 use std::env;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use anyhow::{bail, Error};
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches, Parser, ValueEnum};
+use same_file::is_same_file;
+use serde::Serialize;
 
 mod add;
 mod build;
+mod check_entry_points;
+mod check_scripts;
 mod config;
 mod fetch;
 mod fmt;
+mod generate;
 mod init;
 mod install;
 mod lint;
 mod list;
 mod lock;
 mod make_req;
+mod migrate_lock;
 mod pin;
+mod pre_commit;
 mod publish;
 mod remove;
 mod run;
 mod rye;
 mod shim;
 mod show;
+mod stats;
 mod sync;
 mod test;
 mod toolchain;
 mod tools;
 mod uninstall;
+mod vendor;
 mod version;
 
-use git_testament::git_testament;
+use git_testament::{git_testament, CommitKind};
 
 use crate::bootstrap::{get_self_venv_status, SELF_PYTHON_TARGET_VERSION};
 use crate::config::Config;
 use crate::platform::symlinks_supported;
-use crate::pyproject::read_venv_marker;
-use crate::utils::IoPathContext;
+use crate::progress::{set_progress_fd, set_progress_file};
+use crate::pyproject::{read_venv_marker, DiscoveryUnsuccessful, PyProject};
+use crate::safety::check_mutating_command;
+use crate::sources::uv::{UvDownload, UvRequest};
+use crate::tui::{
+    check_denied_warnings, set_warning_policy, WarningCategory, WarningFilter, WarningPolicy,
+};
+use crate::utils::load_env_files;
 
 git_testament!(TESTAMENT);
 
@@ -45,33 +62,76 @@ git_testament!(TESTAMENT);
 struct Args {
     #[command(subcommand)]
     command: Option<Command>,
-    /// Load one or more .env files.
+    /// Load one or more .env files (later files override earlier ones).
     #[arg(long)]
     env_file: Vec<PathBuf>,
+    /// Let values from .env files override already set environment variables.
+    #[arg(long, requires = "env_file")]
+    env_file_override: bool,
     /// Print the version
     #[arg(long)]
     version: bool,
+    /// Request parseable output format for `--version`.
+    #[arg(long, alias = "output", requires = "version")]
+    format: Option<VersionFormat>,
+    /// Turn warnings into errors ("warnings" for all, or a category).
+    #[arg(long, value_name = "CATEGORY", global = true)]
+    deny: Vec<WarningFilter>,
+    /// Silence warnings of a category.
+    #[arg(long, value_name = "CATEGORY", global = true)]
+    allow: Vec<WarningCategory>,
+    /// Retry uv invocations that failed because of the network this many times.
+    #[arg(long, value_name = "N")]
+    retries: Option<u32>,
+    /// Network timeout in seconds for uv.
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+    /// Write machine-readable progress events (JSON lines) to this file descriptor.
+    #[arg(long, value_name = "FD", conflicts_with = "progress_file")]
+    progress_fd: Option<i32>,
+    /// Write machine-readable progress events (JSON lines) to this file or named pipe.
+    #[arg(long, value_name = "PATH")]
+    progress_file: Option<PathBuf>,
+    /// Allow commands that modify a project to run as root.
+    #[arg(long, global = true)]
+    allow_root: bool,
+    /// Allow commands that modify a project to run in system folders.
+    #[arg(long, global = true)]
+    allow_system_dir: bool,
+}
+
+#[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
+#[value(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+enum VersionFormat {
+    Json,
 }
 
 #[derive(Parser, Debug)]
 enum Command {
     Add(add::Args),
     Build(build::Args),
+    CheckEntryPoints(check_entry_points::Args),
+    CheckScripts(check_scripts::Args),
     Config(config::Args),
     Fetch(fetch::Args),
     #[command(alias = "format")]
     Fmt(fmt::Args),
+    Generate(generate::Args),
     Init(init::Args),
     Install(install::Args),
     Lock(lock::Args),
     #[command(alias = "check")]
     Lint(lint::Args),
     MakeReq(make_req::Args),
+    MigrateLock(migrate_lock::Args),
     Pin(pin::Args),
+    PreCommit(pre_commit::Args),
     Publish(publish::Args),
     Remove(remove::Args),
     Run(run::Args),
     Show(show::Args),
+    Stats(stats::Args),
     Sync(sync::Args),
     Test(test::Args),
     Toolchain(toolchain::Args),
@@ -79,12 +139,80 @@ enum Command {
     #[command(name = "self")]
     Rye(rye::Args),
     Uninstall(uninstall::Args),
+    Vendor(vendor::Args),
     Version(version::Args),
     List(list::Args),
     #[command(hide = true)]
     Shell(shell::Args),
 }
 
+impl Command {
+    /// Does the command spawn Python in or install into the project's virtualenv?
+    fn uses_project_venv(&self) -> bool {
+        matches!(
+            self,
+            Command::Add(_)
+                | Command::Build(_)
+                | Command::CheckEntryPoints(_)
+                | Command::Lock(_)
+                | Command::Remove(_)
+                | Command::Run(_)
+                | Command::Sync(_)
+                | Command::Test(_)
+                | Command::Vendor(_)
+        )
+    }
+
+    /// Does the command modify the project, its virtualenv or the rye home?
+    fn is_mutating(&self) -> bool {
+        match self {
+            Command::Add(_)
+            | Command::Build(_)
+            | Command::Init(_)
+            | Command::Install(_)
+            | Command::Lock(_)
+            | Command::MigrateLock(_)
+            | Command::Pin(_)
+            | Command::Remove(_)
+            | Command::Sync(_)
+            | Command::Uninstall(_)
+            | Command::Vendor(_) => true,
+            Command::Fetch(args) => args.is_mutating(),
+            Command::Toolchain(args) => args.is_mutating(),
+            Command::Tools(args) => args.is_mutating(),
+            Command::Version(args) => args.is_mutating(),
+            _ => false,
+        }
+    }
+
+    /// Can the command be influenced by the current project?
+    ///
+    /// Only for those the project is discovered ahead of time to apply its
+    /// warning policy.
+    fn uses_project(&self) -> bool {
+        !matches!(
+            self,
+            Command::Install(_)
+                | Command::Rye(_)
+                | Command::Stats(_)
+                | Command::Tools(_)
+                | Command::Uninstall(_)
+        )
+    }
+
+    /// Does the command only modify the rye home and not the current project?
+    fn only_modifies_rye_home(&self) -> bool {
+        matches!(
+            self,
+            Command::Fetch(_)
+                | Command::Install(_)
+                | Command::Toolchain(_)
+                | Command::Tools(_)
+                | Command::Uninstall(_)
+        )
+    }
+}
+
 pub mod shell {
     /// The shell command was removed.
     #[derive(clap::Parser, Debug)]
@@ -106,51 +234,151 @@ pub fn execute() -> Result<(), Error> {
         return Ok(());
     }
 
-    let args = Args::try_parse()?;
+    let matches = Args::command().try_get_matches()?;
+    let command_name = matches.subcommand_name().map(|x| x.to_string());
+    let args = Args::from_arg_matches(&matches)?;
 
     // handle --env-file.  As this happens here this cannot influence `RYE_HOME` or
-    // the behavior of the shims.
-    for env_file in &args.env_file {
-        dotenvy::from_path(env_file).path_context(env_file, "unable to load env file")?;
+    // the behavior of the shims.  Later files override earlier ones, the existing
+    // environment is only overridden with --env-file-override.
+    for (key, value) in load_env_files(&args.env_file)? {
+        if args.env_file_override || env::var_os(&key).is_none() {
+            env::set_var(key, value);
+        }
+    }
+
+    // network settings from the command line override the config.  They are
+    // passed through the environment so that they also reach nested rye calls.
+    if let Some(retries) = args.retries {
+        env::set_var("RYE_NETWORK_RETRIES", retries.to_string());
+    }
+    if let Some(timeout) = args.timeout {
+        env::set_var("RYE_NETWORK_TIMEOUT", timeout.to_string());
+    }
+
+    if let Some(fd) = args.progress_fd {
+        set_progress_fd(fd)?;
+    } else if let Some(ref path) = args.progress_file {
+        set_progress_file(path)?;
     }
 
     let cmd = if args.version {
-        return print_version();
+        return print_version(args.format);
     } else if let Some(cmd) = args.command {
         cmd
     } else {
         unreachable!()
     };
 
+    // tooling invokes `rye run python` a lot, so the discovery of the project
+    // and the sync check are skipped if nothing changed since the last run.
+    // This does not return if the cached interpreter is used.
+    if let Command::Run(ref cmd) = cmd {
+        if let Some(entry) = run::load_fast_path(cmd) {
+            if active_environment_vars(entry.venv()).is_empty() {
+                entry.exec(cmd)?;
+            }
+        }
+    }
+
+    // warning policies from the project are extended by the ones from the command line
+    let project = if cmd.uses_project() {
+        match PyProject::load_or_discover(find_pyproject_arg(&matches).as_deref()) {
+            Ok(project) => Ok(Some(project)),
+            Err(err) if err.is::<DiscoveryUnsuccessful>() => Ok(None),
+            Err(err) => Err(err),
+        }
+    } else {
+        Ok(None)
+    };
+    let (mut warning_policy, policy_err) = match project {
+        Ok(Some(ref project)) => match project.warning_policy() {
+            Ok(policy) => (policy, None),
+            Err(err) => (WarningPolicy::default(), Some(err)),
+        },
+        _ => (WarningPolicy::default(), None),
+    };
+    warning_policy.extend(WarningPolicy {
+        deny: args.deny,
+        allow: args.allow,
+    });
+    set_warning_policy(warning_policy);
+
+    // a broken pyproject.toml is reported by the commands that need it
+    let project = match project {
+        Ok(project) => project,
+        Err(err) => {
+            warn!(category = Config, "{:#}", err);
+            None
+        }
+    };
+    if let Some(err) = policy_err {
+        warn!(category = Config, "{:#}", err);
+    }
+
+    if let Some(ref project) = project {
+        if cmd.uses_project_venv() {
+            check_active_environment(project);
+        }
+    }
+
+    if cmd.is_mutating() {
+        let project_root = if cmd.only_modifies_rye_home() {
+            None
+        } else if let Command::Init(ref args) = cmd {
+            // the project created by init is not the one we are in
+            Some(args.project_dir()?)
+        } else {
+            match project {
+                Some(ref project) => Some(project.root_path().into_owned()),
+                None => Some(env::current_dir()?),
+            }
+        };
+        check_mutating_command(
+            project_root.as_deref(),
+            args.allow_root,
+            args.allow_system_dir,
+        )?;
+    }
+
     // Add this to warn about the deprecated use of pip-tools
     if !Config::current().use_uv() {
         warn!(
+            category = Deprecated,
             "The `use-uv` setting is deprecated, as `pip-tools` support was removed in rye 0.40.0"
         );
     }
 
-    match cmd {
+    let start = Instant::now();
+    let rv = match cmd {
         Command::Add(cmd) => add::execute(cmd),
         Command::Build(cmd) => build::execute(cmd),
+        Command::CheckEntryPoints(cmd) => check_entry_points::execute(cmd),
+        Command::CheckScripts(cmd) => check_scripts::execute(cmd),
         Command::Config(cmd) => config::execute(cmd),
         Command::Fetch(cmd) => fetch::execute(cmd),
         Command::Fmt(cmd) => fmt::execute(cmd),
+        Command::Generate(cmd) => generate::execute(cmd),
         Command::Init(cmd) => init::execute(cmd),
         Command::Install(cmd) => install::execute(cmd),
         Command::Lock(cmd) => lock::execute(cmd),
         Command::Lint(cmd) => lint::execute(cmd),
         Command::MakeReq(cmd) => make_req::execute(cmd),
+        Command::MigrateLock(cmd) => migrate_lock::execute(cmd),
         Command::Pin(cmd) => pin::execute(cmd),
+        Command::PreCommit(cmd) => pre_commit::execute(cmd),
         Command::Publish(cmd) => publish::execute(cmd),
         Command::Remove(cmd) => remove::execute(cmd),
         Command::Run(cmd) => run::execute(cmd),
         Command::Show(cmd) => show::execute(cmd),
+        Command::Stats(cmd) => stats::execute(cmd),
         Command::Sync(cmd) => sync::execute(cmd),
         Command::Test(cmd) => test::execute(cmd),
         Command::Toolchain(cmd) => toolchain::execute(cmd),
         Command::Tools(cmd) => tools::execute(cmd),
         Command::Rye(cmd) => rye::execute(cmd),
         Command::Uninstall(cmd) => uninstall::execute(cmd),
+        Command::Vendor(cmd) => vendor::execute(cmd),
         Command::Version(cmd) => version::execute(cmd),
         Command::List(cmd) => list::execute(cmd),
         Command::Shell(..) => {
@@ -164,9 +392,136 @@ pub fn execute() -> Result<(), Error> {
             );
         }
     }
+    .and_then(|()| check_denied_warnings());
+
+    if let Some(command_name) = command_name {
+        crate::stats::record_invocation(&command_name, start.elapsed(), rv.is_ok());
+    }
+
+    rv
+}
+
+/// Returns the `--pyproject` passed to the (innermost) subcommand.
+fn find_pyproject_arg(matches: &clap::ArgMatches) -> Option<PathBuf> {
+    let mut rv = None;
+    let mut matches = matches;
+    while let Some((_, sub_matches)) = matches.subcommand() {
+        if let Ok(Some(path)) = sub_matches.try_get_one::<PathBuf>("pyproject") {
+            rv = Some(path.clone());
+        }
+        matches = sub_matches;
+    }
+    rv
+}
+
+/// Machine readable version information printed by `rye --version --format json`.
+#[derive(Serialize, Debug)]
+struct VersionInfo {
+    version: &'static str,
+    commit: Option<&'static str>,
+    build_date: &'static str,
+    platform: PlatformInfo,
+    self_python: Option<String>,
+    self_python_target: String,
+    uv_version: Option<String>,
+    symlink_support: bool,
+    uv_enabled: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct PlatformInfo {
+    os: &'static str,
+    arch: &'static str,
+}
+
+/// Variables of activated environments that conflict with the virtualenv
+/// of the project together with a hint how to get rid of them.
+const ACTIVE_ENV_VARS: &[(&str, &str)] = &[
+    ("VIRTUAL_ENV", "run `deactivate`"),
+    ("CONDA_PREFIX", "run `conda deactivate`"),
+    ("PYTHONHOME", "unset it"),
+];
+
+/// Returns the variables of activated virtualenvs or conda environments that
+/// are not the given virtualenv, together with their value and a hint.
+///
+/// The `base` conda environment is ignored as conda activates it in every
+/// shell by default.
+fn active_environment_vars(venv: &Path) -> Vec<(&'static str, OsString, &'static str)> {
+    ACTIVE_ENV_VARS
+        .iter()
+        .filter_map(|(var, hint)| {
+            let value = env::var_os(var)?;
+            if *var == "VIRTUAL_ENV"
+                && (Path::new(&value) == venv || is_same_file(&value, venv).unwrap_or(false))
+            {
+                return None;
+            }
+            if *var == "CONDA_PREFIX" && env::var("CONDA_DEFAULT_ENV").as_deref() == Ok("base") {
+                return None;
+            }
+            Some((*var, value, *hint))
+        })
+        .collect()
+}
+
+/// Warns about activated virtualenvs or conda environments that are not the
+/// virtualenv of the project.
+///
+/// With `behavior.ignore-active-venv` the variables are removed instead so
+/// that they are not inherited by the processes rye spawns.
+fn check_active_environment(project: &PyProject) {
+    let venv = project.venv_path();
+    let ignore = Config::current().ignore_active_venv();
+    for (var, value, hint) in active_environment_vars(&venv) {
+        if ignore {
+            env::remove_var(var);
+        } else {
+            warn!(
+                category = Environment,
+                "{} is set to {} which is not the virtualenv of this project ({}). \
+                 To avoid unpredictable behavior {} or set behavior.ignore-active-venv = true",
+                var,
+                Path::new(&value).display(),
+                venv.display(),
+                hint
+            );
+        }
+    }
 }
 
-fn print_version() -> Result<(), Error> {
+fn print_version(format: Option<VersionFormat>) -> Result<(), Error> {
+    let self_venv_python = match get_self_venv_status() {
+        Ok(venv_dir) | Err((venv_dir, _)) => read_venv_marker(&venv_dir).map(|mark| mark.python),
+    };
+
+    if let Some(VersionFormat::Json) = format {
+        let (commit, build_date) = match TESTAMENT.commit {
+            CommitKind::NoRepository(_, date) | CommitKind::NoCommit(_, date) => (None, date),
+            CommitKind::NoTags(hash, date) | CommitKind::FromTag(_, hash, date, _) => {
+                (Some(hash), date)
+            }
+        };
+        let info = VersionInfo {
+            version: env!("CARGO_PKG_VERSION"),
+            commit,
+            build_date,
+            platform: PlatformInfo {
+                os: std::env::consts::OS,
+                arch: std::env::consts::ARCH,
+            },
+            self_python: self_venv_python.map(|x| x.to_string()),
+            self_python_target: SELF_PYTHON_TARGET_VERSION.to_string(),
+            uv_version: UvDownload::try_from(UvRequest::default())
+                .ok()
+                .map(|x| x.version()),
+            symlink_support: symlinks_supported(),
+            uv_enabled: true,
+        };
+        echo!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
     echo!("rye {}", env!("CARGO_PKG_VERSION"));
     echo!("commit: {}", TESTAMENT.commit);
     echo!(
@@ -175,10 +530,6 @@ fn print_version() -> Result<(), Error> {
         std::env::consts::ARCH
     );
 
-    let self_venv_python = match get_self_venv_status() {
-        Ok(venv_dir) | Err((venv_dir, _)) => read_venv_marker(&venv_dir).map(|mark| mark.python),
-    };
-
     if let Some(python) = self_venv_python {
         echo!("self-python: {}", python);
     } else {
@@ -191,3 +542,24 @@ fn print_version() -> Result<(), Error> {
     echo!("uv enabled: {}", true);
     Ok(())
 }
+
+#[test]
+fn test_is_mutating() {
+    let is_mutating = |args: &[&str]| {
+        let args =
+            Args::try_parse_from(std::iter::once("rye").chain(args.iter().copied())).unwrap();
+        args.command.unwrap().is_mutating()
+    };
+    assert!(!is_mutating(&["version"]));
+    assert!(!is_mutating(&["version", "sync-from-vcs", "--dry-run"]));
+    assert!(is_mutating(&["version", "1.0.0"]));
+    assert!(is_mutating(&["version", "--bump", "minor"]));
+    assert!(is_mutating(&["fetch", "3.12"]));
+    assert!(!is_mutating(&["fetch", "--verify-only", "3.12"]));
+    assert!(is_mutating(&["tools", "install", "pycowsay"]));
+    assert!(!is_mutating(&["tools", "list"]));
+    assert!(is_mutating(&["toolchain", "remove", "cpython@3.12.1"]));
+    assert!(!is_mutating(&["toolchain", "list"]));
+    assert!(!is_mutating(&["toolchain", "verify"]));
+    assert!(is_mutating(&["toolchain", "verify", "--repair"]));
+}
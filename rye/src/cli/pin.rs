@@ -1,21 +1,23 @@
 use std::env;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
-use anyhow::{anyhow, Error};
+use anyhow::{anyhow, bail, Error};
 use clap::Parser;
+use console::style;
 
 use crate::platform::get_pinnable_version;
 use crate::pyproject::DiscoveryUnsuccessful;
-use crate::pyproject::PyProject;
+use crate::pyproject::{PyProject, PythonVersionSource};
 use crate::sources::py::PythonVersionRequest;
-use crate::utils::IoPathContext;
+use crate::sync::{sync, SyncMode, SyncOptions};
+use crate::utils::{CommandOutput, IoPathContext, QuietExit};
 
 /// Pins a Python version to this project.
 ///
-/// This will update the `.python-version` to point to the provided version.
-/// Additionally it will update `requires-python` in the `pyproject.toml`
+/// This will update the `.python-version` (or `tool.rye.python` with `--toml`)
+/// to point to the provided version.  Additionally it will update `requires-python` in the `pyproject.toml`
 /// if it's lower than the current version.  This can be disabled by passing
 /// `--no-update-requires-python`.
 #[derive(Parser, Debug)]
@@ -28,9 +30,18 @@ pub struct Args {
     /// Prevent updating requires-python in the pyproject.toml.
     #[arg(long)]
     no_update_requires_python: bool,
+    /// Pin in `tool.rye.python` of the pyproject.toml instead of `.python-version`.
+    #[arg(long)]
+    toml: bool,
     /// Use this pyproject.toml file
     #[arg(long, value_name = "PYPROJECT_TOML")]
     pyproject: Option<PathBuf>,
+    /// Pin all projects listed in this file (one path per line).
+    #[arg(long, value_name = "MANIFEST", conflicts_with = "pyproject")]
+    manifest: Option<PathBuf>,
+    /// Do not re-lock the projects listed in the manifest after pinning.
+    #[arg(long, requires = "manifest")]
+    no_lock: bool,
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
@@ -41,6 +52,10 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     let to_write = get_pinnable_version(&req, cmd.relaxed)
         .ok_or_else(|| anyhow!("unsupported/unknown version for this platform"))?;
 
+    if let Some(ref manifest) = cmd.manifest {
+        return pin_manifest(manifest, &to_write, &cmd);
+    }
+
     let pyproject = match PyProject::load_or_discover(cmd.pyproject.as_deref()) {
         Ok(proj) => Some(proj),
         Err(err) => {
@@ -53,15 +68,31 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         }
     };
 
-    let version_file = match pyproject {
-        Some(ref proj) => proj.root_path().join(".python-version"),
-        None => env::current_dir()?.join(".python-version"),
-    };
-    fs::write(&version_file, format!("{}\n", to_write))
-        .path_context(&version_file, "failed to write .python-version file")?;
+    let version_file = pin_project(
+        &to_write,
+        pyproject,
+        !cmd.no_update_requires_python,
+        cmd.toml,
+    )?;
+    echo!("pinned {} in {}", to_write, version_file.display());
 
-    if !cmd.no_update_requires_python {
-        if let Some(mut pyproject_toml) = pyproject {
+    Ok(())
+}
+
+/// Writes the `.python-version` file of a project (or the current folder if
+/// there is none) and returns its path.
+///
+/// With `toml` the version is pinned in `tool.rye.python` instead.  As the
+/// Python version is shared by a workspace, it goes into the `pyproject.toml`
+/// of the workspace root.
+fn pin_project(
+    to_write: &str,
+    mut pyproject: Option<PyProject>,
+    update_requires_python: bool,
+    toml: bool,
+) -> Result<PathBuf, Error> {
+    if update_requires_python {
+        if let Some(ref mut pyproject_toml) = pyproject {
             let new_version = to_write.parse::<PythonVersionRequest>()?;
             if let Some(curr_version) = pyproject_toml.target_python_version() {
                 if new_version < curr_version {
@@ -72,7 +103,102 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         }
     }
 
-    echo!("pinned {} in {}", to_write, version_file.display());
+    if toml {
+        let Some(pyproject) = pyproject else {
+            bail!("cannot pin in tool.rye.python without a pyproject.toml");
+        };
+        let workspace_toml = pyproject
+            .workspace()
+            .map(|x| x.path().join("pyproject.toml"));
+        let mut target = match workspace_toml {
+            // reloaded as the requires-python update might have changed it
+            Some(path) => PyProject::load(&path)?,
+            None => pyproject,
+        };
+        target.set_python_pin(to_write)?;
+        target.save()?;
+        return Ok(target.toml_path().into_owned());
+    }
 
-    Ok(())
+    let version_file = match pyproject {
+        Some(ref proj) => proj.root_path().join(".python-version"),
+        None => env::current_dir()?.join(".python-version"),
+    };
+    fs::write(&version_file, format!("{}\n", to_write))
+        .path_context(&version_file, "failed to write .python-version file")?;
+
+    if let Some((_, PythonVersionSource::PyprojectPin)) =
+        pyproject.as_ref().and_then(|x| x.python_version_request())
+    {
+        warn!(
+            category = Config,
+            "{} is shadowed by tool.rye.python, use `rye pin --toml` to update the pin",
+            version_file.display()
+        );
+    }
+
+    Ok(version_file)
+}
+
+/// Pins every project listed in a manifest and re-locks it.
+///
+/// Failures do not stop the run, they are collected into a report of the
+/// projects that need manual attention.
+fn pin_manifest(manifest: &Path, to_write: &str, cmd: &Args) -> Result<(), Error> {
+    let contents =
+        fs::read_to_string(manifest).path_context(manifest, "failed to read manifest")?;
+    let base = manifest.parent().unwrap_or(Path::new("."));
+    let projects = contents
+        .lines()
+        .map(|x| x.trim())
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .collect::<Vec<_>>();
+
+    let mut needs_attention = Vec::new();
+    for project in &projects {
+        let toml = base.join(project).join("pyproject.toml");
+        let rv = PyProject::load(&toml)
+            .and_then(|pyproject| {
+                pin_project(
+                    to_write,
+                    Some(pyproject),
+                    !cmd.no_update_requires_python,
+                    cmd.toml,
+                )
+            })
+            .context("failed to pin")
+            .and_then(|version_file| {
+                echo!("pinned {} in {}", to_write, version_file.display());
+                if cmd.no_lock {
+                    return Ok(());
+                }
+                sync(SyncOptions {
+                    output: CommandOutput::Quiet,
+                    mode: SyncMode::LockOnly,
+                    pyproject: Some(toml.clone()),
+                    ..SyncOptions::default()
+                })
+                .context("failed to lock")
+            });
+        if let Err(err) = rv {
+            // the first line carries the cause without the resolver output
+            let msg = format!("{:#}", err);
+            needs_attention.push((project, msg.lines().next().unwrap_or_default().to_string()));
+        }
+    }
+
+    echo!(
+        "{} of {} project(s) pinned to {} without issues",
+        projects.len() - needs_attention.len(),
+        projects.len(),
+        to_write
+    );
+    if needs_attention.is_empty() {
+        return Ok(());
+    }
+    echo!("{}", style("Projects that need manual attention:").yellow());
+    for (project, msg) in &needs_attention {
+        echo!("  {}: {}", project, msg);
+    }
+    Err(QuietExit(1).into())
 }
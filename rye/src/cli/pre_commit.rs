@@ -0,0 +1,148 @@
+use std::env::consts::EXE_EXTENSION;
+use std::env::{self, join_paths, split_paths};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+
+use anyhow::{bail, Error};
+use clap::Parser;
+
+use crate::config::Config;
+use crate::consts::VENV_BIN;
+use crate::lock::KeyringProvider;
+use crate::pyproject::{normalize_package_name, DependencyKind, PyProject};
+use crate::sync::autosync;
+use crate::utils::{CommandOutput, QuietExit};
+
+/// Manages the pre-commit hooks of the project.
+///
+/// pre-commit is always run from the project's virtualenv, so it has to be
+/// declared as a dev dependency (`rye add --dev pre-commit`).
+#[derive(Parser, Debug)]
+pub struct Args {
+    #[command(subcommand)]
+    command: SubCommand,
+}
+
+/// Installs the git hook that runs pre-commit from the virtualenv.
+#[derive(Parser, Debug)]
+pub struct InstallCommand {
+    #[command(flatten)]
+    common: CommonArgs,
+    /// Extra arguments to `pre-commit install`
+    #[arg(last = true)]
+    extra_args: Vec<OsString>,
+}
+
+/// Runs the pre-commit hooks.
+///
+/// All arguments are passed to `pre-commit run`, for instance `--all-files`.
+#[derive(Parser, Debug)]
+pub struct RunCommand {
+    #[command(flatten)]
+    common: CommonArgs,
+    /// Arguments to `pre-commit run`
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+    args: Vec<OsString>,
+}
+
+#[derive(Parser, Debug)]
+struct CommonArgs {
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+    /// Enables verbose diagnostics.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Turns off all output.
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Attempt to use `keyring` for authentication for index URLs.
+    #[arg(long, value_enum, default_value_t)]
+    keyring_provider: KeyringProvider,
+}
+
+#[derive(Parser, Debug)]
+enum SubCommand {
+    Install(InstallCommand),
+    Run(RunCommand),
+}
+
+pub fn execute(cmd: Args) -> Result<(), Error> {
+    match cmd.command {
+        SubCommand::Install(args) => run_pre_commit(&args.common, "install", &args.extra_args),
+        SubCommand::Run(args) => run_pre_commit(&args.common, "run", &args.args),
+    }
+}
+
+fn run_pre_commit(common: &CommonArgs, command: &str, args: &[OsString]) -> Result<(), Error> {
+    let output = CommandOutput::from_quiet_and_verbose(common.quiet, common.verbose);
+    let project = PyProject::load_or_discover(common.pyproject.as_deref())?;
+    let venv_bin = project.venv_path().join(VENV_BIN);
+    let pre_commit = venv_bin.join("pre-commit").with_extension(EXE_EXTENSION);
+
+    if !pre_commit.is_file() {
+        if !has_pre_commit_dependency(&project)? {
+            bail!("pre-commit is not a dev dependency. Run `rye add --dev pre-commit`");
+        } else if Config::current().autosync() {
+            autosync(
+                &project,
+                output,
+                false,
+                false,
+                false,
+                common.keyring_provider,
+                Vec::new(),
+            )?;
+        } else {
+            bail!("pre-commit not installed but in dependencies. Run `rye sync`.");
+        }
+    }
+
+    let mut pre_commit_cmd = Command::new(&pre_commit);
+    pre_commit_cmd.arg(command);
+    if command == "run" && output == CommandOutput::Verbose {
+        pre_commit_cmd.arg("--verbose");
+    }
+    pre_commit_cmd.args(args);
+
+    // hooks with `language: system` should find the tools of the virtualenv
+    pre_commit_cmd
+        .env("VIRTUAL_ENV", &*project.venv_path())
+        .env_remove("PYTHONHOME")
+        .current_dir(project.workspace_path());
+    if let Some(path) = env::var_os("PATH") {
+        let mut paths = split_paths(&path).collect::<Vec<_>>();
+        paths.insert(0, venv_bin.clone());
+        pre_commit_cmd.env("PATH", join_paths(paths)?);
+    }
+
+    let status = pre_commit_cmd.status()?;
+    if !status.success() {
+        return Err(Error::new(QuietExit(status.code().unwrap_or(1))));
+    }
+    Ok(())
+}
+
+/// Is pre-commit a dev dependency of the project or any of the workspace members?
+fn has_pre_commit_dependency(project: &PyProject) -> Result<bool, Error> {
+    let is_pre_commit = |project: &PyProject| {
+        project.iter_dependencies(DependencyKind::Dev).any(|dep| {
+            dep.expand(|name| std::env::var(name).ok())
+                .map_or(false, |req| {
+                    normalize_package_name(&req.name) == "pre-commit"
+                })
+        })
+    };
+    if is_pre_commit(project) {
+        return Ok(true);
+    }
+    if let Some(workspace) = project.workspace() {
+        for member in workspace.iter_projects() {
+            if is_pre_commit(&member?) {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
@@ -1,5 +1,7 @@
+use std::env;
+use std::fs;
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 
 use age::{
@@ -7,19 +9,23 @@ use age::{
     Decryptor, Encryptor,
 };
 use anyhow::{bail, Context, Error};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use console::style;
 use toml_edit::{Item, Table};
 use url::Url;
 
 use crate::bootstrap::ensure_self_venv;
+use crate::gates::{run_gate, Gate};
 use crate::platform::{get_credentials, write_credentials};
 use crate::pyproject::PyProject;
-use crate::utils::{escape_string, get_venv_python_bin, tui_theme, CommandOutput};
+use crate::tui::ensure_interactive;
+use crate::utils::{escape_string, get_venv_python_bin, tls_ca_bundle, tui_theme, CommandOutput};
+use crate::vcs_version::compute_vcs_version;
 
 /// Publish packages to a package repository.
 #[derive(Parser, Debug)]
 pub struct Args {
-    /// The distribution files to upload to the repository (defaults to <workspace-root>/dist/*).
+    /// The distribution files to upload to the repository (defaults to the wheels and sdists in <workspace-root>/dist).
     dist: Option<Vec<PathBuf>>,
     /// The repository to publish to.
     #[arg(short, long, default_value = "pypi")]
@@ -27,6 +33,9 @@ pub struct Args {
     /// The repository url to publish to.
     #[arg(long)]
     repository_url: Option<Url>,
+    /// The kind of registry the repository is hosted on (detected from the url by default).
+    #[arg(long)]
+    registry: Option<Registry>,
     /// The username to authenticate to the repository with.
     #[arg(short, long)]
     username: Option<String>,
@@ -39,7 +48,7 @@ pub struct Args {
     /// GPG identity used to sign files.
     #[arg(short, long)]
     identity: Option<String>,
-    /// Path to alternate CA bundle.
+    /// Path to alternate CA bundle (defaults to `tls.ca-bundle` from the config).
     #[arg(long)]
     cert: Option<PathBuf>,
     /// Skip files that have already been published (only applies to repositories supporting this feature)
@@ -48,6 +57,9 @@ pub struct Args {
     /// Skip prompts.
     #[arg(short, long)]
     yes: bool,
+    /// Skip the checks configured in `tool.rye.gates.publish`
+    #[arg(long)]
+    no_verify: bool,
     /// Enables verbose diagnostics.
     #[arg(short, long)]
     verbose: bool,
@@ -56,8 +68,123 @@ pub struct Args {
     quiet: bool,
 }
 
+/// The kind of registry a repository is hosted on.
+///
+/// Registries disagree on which username goes with an access token, so
+/// this is used to pick the defaults for a credentials entry.
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+#[value(rename_all = "kebab-case")]
+pub enum Registry {
+    /// PyPI or TestPyPI (username `__token__`).
+    Pypi,
+    /// A GitLab package registry (username `gitlab-ci-token` in CI, `__token__` otherwise).
+    Gitlab,
+    /// An Azure Artifacts feed (username is the organization).
+    Azure,
+    /// A Cloudsmith repository (username is the owner).
+    Cloudsmith,
+    /// Any other registry.
+    Generic,
+}
+
+impl Registry {
+    /// Probes the upload url to figure out which registry it belongs to.
+    pub fn detect(url: &Url) -> Registry {
+        let host = url.host_str().unwrap_or_default();
+        if host == "upload.pypi.org" || host == "test.pypi.org" {
+            Registry::Pypi
+        } else if host == "pkgs.dev.azure.com" || host.ends_with(".pkgs.visualstudio.com") {
+            Registry::Azure
+        } else if host == "cloudsmith.io" || host.ends_with(".cloudsmith.io") {
+            Registry::Cloudsmith
+        } else if url.path().contains("/api/v4/projects/") && url.path().contains("/packages/pypi")
+        {
+            // this also matches self-hosted GitLab instances
+            Registry::Gitlab
+        } else {
+            Registry::Generic
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Registry::Pypi => "pypi",
+            Registry::Gitlab => "gitlab",
+            Registry::Azure => "azure",
+            Registry::Cloudsmith => "cloudsmith",
+            Registry::Generic => "generic",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Registry> {
+        Registry::from_str(name, true).ok()
+    }
+
+    /// The username that goes with an access token on this registry.
+    pub fn default_username(self, url: &Url) -> String {
+        let first_segment = || {
+            url.path_segments()
+                .and_then(|mut x| x.next())
+                .filter(|x| !x.is_empty())
+                .map(|x| x.to_string())
+        };
+        match self {
+            Registry::Gitlab if env::var_os("CI_JOB_TOKEN").is_some() => {
+                Some("gitlab-ci-token".to_string())
+            }
+            Registry::Azure => match url.host_str() {
+                Some(host) if host.ends_with(".pkgs.visualstudio.com") => {
+                    host.split('.').next().map(|x| x.to_string())
+                }
+                _ => first_segment(),
+            },
+            Registry::Cloudsmith => first_segment(),
+            _ => None,
+        }
+        .unwrap_or_else(|| "__token__".to_string())
+    }
+
+    /// A token provided by the environment, for instance in a CI job.
+    fn token_from_env(self, username: &str) -> Option<String> {
+        match self {
+            Registry::Gitlab if username == "gitlab-ci-token" => env::var("CI_JOB_TOKEN").ok(),
+            _ => None,
+        }
+    }
+
+    /// Where to generate a new access token for this registry.
+    fn token_page(self, url: &Url, username: &str) -> Option<String> {
+        match self {
+            Registry::Pypi => Some(format!(
+                "https://{}/manage/account/token/",
+                if url.host_str() == Some("test.pypi.org") {
+                    "test.pypi.org"
+                } else {
+                    "pypi.org"
+                }
+            )),
+            Registry::Gitlab => Some(format!(
+                "{}://{}/-/user_settings/personal_access_tokens",
+                url.scheme(),
+                url.host_str()?
+            )),
+            Registry::Azure => Some(format!(
+                "https://dev.azure.com/{}/_usersSettings/tokens",
+                username
+            )),
+            Registry::Cloudsmith => Some("https://cloudsmith.io/user/settings/api/".to_string()),
+            Registry::Generic => None,
+        }
+    }
+}
+
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+    if !cmd.no_verify {
+        if let Ok(project) = PyProject::discover() {
+            run_gate(&project, Gate::Publish, output)?;
+        }
+    }
     let venv = ensure_self_venv(output)?;
 
     // Get the files to publish.
@@ -68,7 +195,35 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
             if project.is_virtual() {
                 bail!("virtual packages cannot be published");
             }
-            vec![project.workspace_path().join("dist").join("*")]
+            let dist = project.workspace_path().join("dist");
+            let files = find_distributions(&dist);
+            if project.version_from_vcs() {
+                let version = compute_vcs_version(&project)?;
+                echo!(
+                    if output,
+                    "publishing {} {}",
+                    style(project.normalized_name()?).cyan(),
+                    style(&version).cyan()
+                );
+                let fragment = format!("-{}", version);
+                if !files.iter().any(|x| {
+                    x.file_name()
+                        .map_or(false, |x| x.to_string_lossy().contains(&fragment))
+                }) {
+                    warn!(
+                        "no distribution of version {} found in {}, run `rye build` first",
+                        version,
+                        dist.display()
+                    );
+                }
+            }
+            if files.is_empty() {
+                bail!(
+                    "no distributions found in {}, run `rye build` first",
+                    dist.display()
+                );
+            }
+            files
         }
     };
 
@@ -101,6 +256,24 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         bail!("invalid pypi url {} (use -h for help)", repository_url);
     }
 
+    let registry = match cmd.registry {
+        Some(registry) => {
+            credentials[repository]["registry"] = Item::Value(registry.name().into());
+            registry
+        }
+        None => credentials
+            .get(repository)
+            .and_then(|table| table.get("registry"))
+            .and_then(|registry| Registry::from_name(&escape_string(registry.to_string())))
+            .unwrap_or_else(|| Registry::detect(&repository_url)),
+    };
+    echo!(
+        if verbose output,
+        "Using {} registry defaults for {}",
+        registry.name(),
+        repository_url
+    );
+
     let username = match cmd.username {
         Some(username) => username,
         None => credentials
@@ -108,7 +281,7 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
             .and_then(|table| table.get("username"))
             .map(|username| username.to_string())
             .map(escape_string)
-            .unwrap_or("__token__".to_string()),
+            .unwrap_or_else(|| registry.default_username(&repository_url)),
     };
 
     let token = if let Some(token) = cmd.token {
@@ -127,8 +300,14 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         let secret = Secret::new(token);
 
         maybe_decrypt(&secret, cmd.yes)?
+    } else if let Some(token) = registry.token_from_env(&username) {
+        // job tokens are short lived, so they are never stored
+        Secret::new(token)
     } else {
-        echo!("No access token found, generate one at: https://pypi.org/manage/account/token/");
+        match registry.token_page(&repository_url, &username) {
+            Some(page) => echo!("No access token found, generate one at: {}", page),
+            None => echo!("No access token found"),
+        }
         let token = if !cmd.yes {
             prompt_for_token()?
         } else {
@@ -167,7 +346,11 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     if let Some(identity) = cmd.identity {
         publish_cmd.arg("--identity").arg(identity);
     }
-    if let Some(cert) = cmd.cert {
+    let cert = match cmd.cert {
+        Some(cert) => Some(cert),
+        None => tls_ca_bundle()?,
+    };
+    if let Some(cert) = cert {
         publish_cmd.arg("--cert").arg(cert);
     }
     if cmd.skip_existing {
@@ -188,6 +371,7 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
 }
 
 fn prompt_for_token() -> Result<String, Error> {
+    ensure_interactive("pass the token with --token")?;
     eprint!("Access token: ");
     let token = get_trimmed_user_input().context("failed to read provided token")?;
 
@@ -196,6 +380,7 @@ fn prompt_for_token() -> Result<String, Error> {
 
 fn maybe_encrypt(secret: &Secret<String>, yes: bool) -> Result<Secret<Vec<u8>>, Error> {
     let phrase = if !yes {
+        ensure_interactive("pass --yes to store the token without a passphrase")?;
         dialoguer::Password::with_theme(tui_theme())
             .with_prompt("Encrypt with passphrase (optional)")
             .allow_empty_password(true)
@@ -224,6 +409,7 @@ fn maybe_encrypt(secret: &Secret<String>, yes: bool) -> Result<Secret<Vec<u8>>,
 
 fn maybe_decrypt(secret: &Secret<String>, yes: bool) -> Result<Secret<String>, Error> {
     let phrase = if !yes {
+        ensure_interactive("pass --yes if the stored token has no passphrase")?;
         dialoguer::Password::with_theme(tui_theme())
             .with_prompt("Decrypt with passphrase (optional)")
             .allow_empty_password(true)
@@ -284,3 +470,113 @@ fn pad_hex(s: String) -> String {
         s
     }
 }
+
+/// Finds the wheels and source distributions in the given folder.
+///
+/// Anything else in there, such as provenance records written by
+/// `rye build --provenance` or signatures, is not uploaded.
+fn find_distributions(dist: &Path) -> Vec<PathBuf> {
+    let mut rv = fs::read_dir(dist)
+        .into_iter()
+        .flatten()
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .filter(|x| {
+            x.is_file()
+                && x.file_name()
+                    .and_then(|x| x.to_str())
+                    .map_or(false, |x| x.ends_with(".whl") || x.ends_with(".tar.gz"))
+        })
+        .collect::<Vec<_>>();
+    rv.sort();
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_registry() {
+        let detect = |url: &str| Registry::detect(&Url::parse(url).unwrap());
+        assert_eq!(detect("https://upload.pypi.org/legacy/"), Registry::Pypi);
+        assert_eq!(detect("https://test.pypi.org/legacy/"), Registry::Pypi);
+        assert_eq!(
+            detect("https://gitlab.example.com/api/v4/projects/42/packages/pypi"),
+            Registry::Gitlab
+        );
+        assert_eq!(
+            detect("https://pkgs.dev.azure.com/acme/_packaging/feed/pypi/upload/"),
+            Registry::Azure
+        );
+        assert_eq!(
+            detect("https://acme.pkgs.visualstudio.com/_packaging/feed/pypi/upload/"),
+            Registry::Azure
+        );
+        assert_eq!(
+            detect("https://python.cloudsmith.io/acme/packages/"),
+            Registry::Cloudsmith
+        );
+        assert_eq!(detect("https://pypi.example.com/"), Registry::Generic);
+    }
+
+    #[test]
+    fn test_default_username() {
+        let username =
+            |registry: Registry, url: &str| registry.default_username(&Url::parse(url).unwrap());
+        assert_eq!(
+            username(Registry::Pypi, "https://upload.pypi.org/legacy/"),
+            "__token__"
+        );
+        assert_eq!(
+            username(
+                Registry::Azure,
+                "https://pkgs.dev.azure.com/acme/_packaging/feed/pypi/upload/"
+            ),
+            "acme"
+        );
+        assert_eq!(
+            username(
+                Registry::Azure,
+                "https://acme.pkgs.visualstudio.com/_packaging/feed/pypi/upload/"
+            ),
+            "acme"
+        );
+        assert_eq!(
+            username(
+                Registry::Cloudsmith,
+                "https://python.cloudsmith.io/acme/packages/"
+            ),
+            "acme"
+        );
+        assert_eq!(
+            username(Registry::Generic, "https://pypi.example.com/"),
+            "__token__"
+        );
+    }
+
+    #[test]
+    fn test_find_distributions() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "example-0.1.0.tar.gz",
+            "example-0.1.0-py3-none-any.whl",
+            "example-0.1.0-py3-none-any.whl.asc",
+            "example-0.1.0.tar.gz.sig",
+            "example.provenance.json",
+            "example.intoto.jsonl",
+            ".gitignore",
+        ] {
+            fs::write(dir.path().join(name), "").unwrap();
+        }
+        fs::create_dir(dir.path().join("old.whl")).unwrap();
+        assert_eq!(
+            find_distributions(dir.path()),
+            vec![
+                dir.path().join("example-0.1.0-py3-none-any.whl"),
+                dir.path().join("example-0.1.0.tar.gz"),
+            ]
+        );
+        assert!(find_distributions(&dir.path().join("missing")).is_empty());
+    }
+}
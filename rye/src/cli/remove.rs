@@ -1,15 +1,26 @@
+use std::collections::{BTreeSet, HashSet};
 use std::str::FromStr;
 
 use anyhow::Error;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use console::style;
 use pep508_rs::Requirement;
+use serde::Serialize;
 
+use crate::cli::add::{EditSummary, RequirementChange};
 use crate::config::Config;
-use crate::lock::KeyringProvider;
-use crate::pyproject::{DependencyKind, PyProject};
-use crate::sync::autosync;
+use crate::lock::{parse_locked_versions, KeyringProvider, LockGraph};
+use crate::pyproject::{normalize_package_name, DependencyKind, PyProject};
+use crate::sync::{autosync, preview_lockfiles, LockPreview};
 use crate::utils::{format_requirement, CommandOutput};
 
+#[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
+#[value(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+enum Format {
+    Json,
+}
+
 /// Removes a package from this project.
 #[derive(Parser, Debug)]
 pub struct Args {
@@ -28,12 +39,18 @@ pub struct Args {
     /// Does not run `sync` even if auto-sync is enabled.
     #[arg(long, conflicts_with = "sync")]
     no_sync: bool,
+    /// Only report which packages would disappear from the lockfiles.
+    #[arg(long, conflicts_with_all = ["sync", "no_sync"])]
+    dry_run: bool,
     /// Enables verbose diagnostics.
     #[arg(short, long)]
     verbose: bool,
     /// Turns off all output.
     #[arg(short, long, conflicts_with = "verbose")]
     quiet: bool,
+    /// Print a machine readable summary of the changes instead of the regular output.
+    #[arg(long, alias = "output", conflicts_with_all = ["verbose", "dry_run"])]
+    format: Option<Format>,
 
     /// Include pre-releases when automatically syncing the workspace.
     #[arg(long)]
@@ -50,35 +67,52 @@ pub struct Args {
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
-    let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
-    let mut removed_packages = Vec::new();
+    let output = if cmd.format.is_some() {
+        CommandOutput::Quiet
+    } else {
+        CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose)
+    };
+    let kind = if cmd.dev {
+        DependencyKind::Dev
+    } else if let Some(ref section) = cmd.optional {
+        DependencyKind::Optional(section.into())
+    } else {
+        DependencyKind::Normal
+    };
+    let requirements = cmd
+        .requirements
+        .iter()
+        .map(|x| Requirement::from_str(x))
+        .collect::<Result<Vec<_>, _>>()?;
 
     let mut pyproject_toml = PyProject::discover()?;
-    for str_requirement in cmd.requirements {
-        let requirement = Requirement::from_str(&str_requirement)?;
-        if let Some(removed) = pyproject_toml.remove_dependency(
-            &requirement,
-            if cmd.dev {
-                DependencyKind::Dev
-            } else if let Some(ref section) = cmd.optional {
-                DependencyKind::Optional(section.into())
-            } else {
-                DependencyKind::Normal
+    let removed_packages = remove_requirements(&mut pyproject_toml, &requirements, &kind)?;
+
+    if cmd.dry_run {
+        let toml_path = pyproject_toml.toml_path().into_owned();
+        let previews = preview_lockfiles(
+            || {
+                let mut pyproject = PyProject::load_or_discover(Some(&toml_path))?;
+                remove_requirements(&mut pyproject, &requirements, &kind)?;
+                Ok(pyproject)
             },
-        )? {
-            removed_packages.push(removed);
-        }
+            output,
+            cmd.keyring_provider,
+        )?;
+        print_dry_run(&removed_packages, &previews);
+        return Ok(());
     }
 
     pyproject_toml.save()?;
 
     if output != CommandOutput::Quiet {
-        for requirement in removed_packages {
-            echo!("Removed {}", format_requirement(&requirement));
+        for requirement in &removed_packages {
+            echo!("Removed {}", format_requirement(requirement));
         }
     }
 
-    if (Config::current().autosync() && !cmd.no_sync) || cmd.sync {
+    let with_autosync = (Config::current().autosync() && !cmd.no_sync) || cmd.sync;
+    if with_autosync {
         autosync(
             &pyproject_toml,
             output,
@@ -86,8 +120,105 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
             cmd.with_sources,
             cmd.generate_hashes,
             cmd.keyring_provider,
+            Vec::new(),
         )?;
     }
 
+    if let Some(Format::Json) = cmd.format {
+        let summary = EditSummary {
+            changes: removed_packages
+                .iter()
+                .map(|x| RequirementChange::new(&kind, Some(x), None))
+                .collect(),
+            autosync: with_autosync,
+        };
+        echo!("{}", serde_json::to_string_pretty(&summary)?);
+    }
+
     Ok(())
 }
+
+fn remove_requirements(
+    pyproject: &mut PyProject,
+    requirements: &[Requirement],
+    kind: &DependencyKind,
+) -> Result<Vec<Requirement>, Error> {
+    let mut removed_packages = Vec::new();
+    for requirement in requirements {
+        if let Some(removed) = pyproject.remove_dependency(requirement, kind.clone())? {
+            removed_packages.push(removed);
+        }
+    }
+    Ok(removed_packages)
+}
+
+/// Reports how removing the requirements changes the lockfiles.
+///
+/// Packages that disappear from a lockfile are grouped into the removed
+/// requirements themselves and the transitive dependencies that nothing else
+/// needs anymore.  Packages that stay locked because something else still
+/// requires them are listed together with their remaining dependents.
+fn print_dry_run(removed_packages: &[Requirement], previews: &[LockPreview]) {
+    if removed_packages.is_empty() {
+        echo!("No matching dependencies to remove");
+        return;
+    }
+    for requirement in removed_packages {
+        echo!("Would remove {}", format_requirement(requirement));
+    }
+    let direct = removed_packages
+        .iter()
+        .map(|x| normalize_package_name(&x.name))
+        .collect::<HashSet<_>>();
+
+    for preview in previews {
+        let before = LockGraph::parse(&preview.before);
+        let after = LockGraph::parse(&preview.after);
+        let still_locked = parse_locked_versions(&preview.after);
+        let mut dropped = parse_locked_versions(&preview.before)
+            .into_keys()
+            .filter(|x| !still_locked.contains_key(x))
+            .collect::<Vec<_>>();
+        dropped.sort();
+        let (dropped_direct, orphaned): (Vec<_>, Vec<_>) =
+            dropped.iter().partition(|x| direct.contains(*x));
+        let kept = before
+            .reachable_from(&direct)
+            .into_iter()
+            .filter(|x| still_locked.contains_key(x) && !after.required_by(x).is_empty())
+            .collect::<BTreeSet<_>>();
+
+        echo!(
+            "{} ({}):",
+            style(preview.path.display()).cyan(),
+            preview.mode
+        );
+        if dropped.is_empty() && kept.is_empty() {
+            echo!("  no changes");
+            continue;
+        }
+        let pinned = |name: &str| before.requirement(name).unwrap_or(name).to_string();
+        if !dropped_direct.is_empty() {
+            echo!("  direct:");
+            for name in dropped_direct {
+                echo!("    - {}", pinned(name));
+            }
+        }
+        if !orphaned.is_empty() {
+            echo!("  orphaned transitive:");
+            for name in orphaned {
+                echo!("    - {}", pinned(name));
+            }
+        }
+        if !kept.is_empty() {
+            echo!("  still required:");
+            for name in kept {
+                echo!(
+                    "    - {} (by {})",
+                    pinned(&name),
+                    after.required_by(&name).join(", ")
+                );
+            }
+        }
+    }
+}
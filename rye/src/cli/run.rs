@@ -1,17 +1,39 @@
 use std::collections::HashMap;
 use std::env::{self, join_paths, split_paths};
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus};
+use std::time::UNIX_EPOCH;
 
-use anyhow::{bail, Context, Error};
+use anyhow::{anyhow, bail, Context, Error};
 use clap::Parser;
 use console::style;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
 
-use crate::pyproject::{PyProject, Script};
+use crate::config::Config;
+use crate::platform::{find_pyenv_pin, get_app_dir};
+use crate::pyproject::{locate_projects, PyProject, Script};
 use crate::sync::{sync, SyncOptions};
 use crate::tui::redirect_to_stderr;
-use crate::utils::{exec_spawn, get_venv_python_bin, success_status, IoPathContext};
+use crate::utils::{
+    exec_spawn, get_venv_python_bin, load_env_files, set_dotenv_variables, success_status,
+};
+
+static CALL_PLACEHOLDER_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{(\d+|args)\}").unwrap());
+
+/// Variables that are passed to isolated commands even if not requested as
+/// processes cannot be started without them.
+#[cfg(windows)]
+const ISOLATED_REQUIRED_VARS: &[&str] = &["SYSTEMROOT"];
+#[cfg(not(windows))]
+const ISOLATED_REQUIRED_VARS: &[&str] = &[];
+
+/// Commands that can skip project discovery and the sync check if cached.
+const FAST_PATH_COMMANDS: &[&str] = &["python", "python3"];
 
 /// Runs a command installed into this package.
 #[derive(Parser, Debug)]
@@ -26,6 +48,20 @@ pub struct Args {
     /// Use this pyproject.toml file
     #[arg(long, value_name = "PYPROJECT_TOML")]
     pyproject: Option<PathBuf>,
+    /// Run in the environment of this workspace member.
+    ///
+    /// Members that override the Python version have their own virtualenv.
+    #[arg(short, long)]
+    package: Option<String>,
+    /// Run with a clean environment instead of inheriting it.
+    ///
+    /// Only `PATH` (pointing to the virtualenv), `VIRTUAL_ENV`, the variables
+    /// of the script and the ones passed with `--pass` are set.
+    #[arg(long)]
+    isolated: bool,
+    /// Pass an environment variable to an isolated command.
+    #[arg(long = "pass", value_name = "VAR", requires = "isolated")]
+    pass_env: Vec<String>,
 }
 
 #[derive(Parser, Debug)]
@@ -35,13 +71,28 @@ enum Cmd {
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
+    let fast_path = fast_path_command(&cmd);
     let guard = redirect_to_stderr(true);
-    let pyproject = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    let mut pyproject = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    let mut pyproject_path = cmd.pyproject;
+    if let Some(ref package) = cmd.package {
+        pyproject = locate_projects(pyproject, false, &[package.clone()])?
+            .pop()
+            .ok_or_else(|| anyhow!("unknown project '{}'", package))?;
+        pyproject_path = Some(pyproject.toml_path().into_owned());
+    }
 
     // make sure we have the minimal virtualenv.
-    sync(SyncOptions::python_only().pyproject(cmd.pyproject))
+    sync(SyncOptions::python_only().pyproject(pyproject_path))
         .context("failed to sync ahead of run")?;
 
+    if let Some(name) = fast_path {
+        if let Some(Script::External(_)) = pyproject.get_script_cmd(name) {
+            // the cache is an optimization only, failing to write it is fine
+            RunCacheEntry::store(&pyproject, name).ok();
+        }
+    }
+
     if cmd.list || cmd.cmd.is_none() {
         drop(guard);
         return list_scripts(&pyproject);
@@ -51,48 +102,51 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         None => unreachable!(),
     };
 
-    invoke_script(&pyproject, args, true)?;
+    let isolated = cmd.isolated.then_some(&cmd.pass_env[..]);
+    invoke_script(&pyproject, args, true, isolated)?;
     unreachable!();
 }
 
+/// Invokes a script or command in the virtualenv.
+///
+/// If `isolated` is set, the environment is not inherited and only the given
+/// variables are passed on.
 fn invoke_script(
     pyproject: &PyProject,
     mut args: Vec<OsString>,
     exec: bool,
+    isolated: Option<&[String]>,
 ) -> Result<ExitStatus, Error> {
     let venv_bin = pyproject.venv_bin_path();
     let mut env_overrides = None;
 
     match pyproject.get_script_cmd(&args[0].to_string_lossy()) {
-        Some(Script::Call(entry, env_vars, env_file)) => {
+        Some(Script::Call(entry, env_vars, env_files)) => {
             let py = OsString::from(get_venv_python_bin(&pyproject.venv_path()));
-            env_overrides = Some(load_env_vars(pyproject, env_file, env_vars)?);
+            env_overrides = Some(load_env_vars(pyproject, env_files, env_vars)?);
             args = if let Some((module, func)) = entry.split_once(':') {
                 if module.is_empty() || func.is_empty() {
                     bail!("Python callable must be in the form <module_name>:<callable_name> or <module_name>")
                 }
-                let call = if !func.contains('(') {
-                    format!("{func}()")
-                } else {
-                    func.to_string()
-                };
-                [
-                    py,
-                    OsString::from("-c"),
-                    OsString::from(format!("import sys, {module} as _1; sys.exit(_1.{call})")),
-                ]
+                let code = build_call_code(module, func, args.len() - 1)?;
+                // the script name is passed as first argument so that it ends up
+                // as `sys.argv[0]` rather than `-c`.
+                [py, OsString::from("-c"), OsString::from(code)]
+                    .into_iter()
+                    .chain(args)
+                    .collect()
             } else {
                 [py, OsString::from("-m"), OsString::from(entry)]
-            }
-            .into_iter()
-            .chain(args.into_iter().skip(1))
-            .collect();
+                    .into_iter()
+                    .chain(args.into_iter().skip(1))
+                    .collect()
+            };
         }
-        Some(Script::Cmd(script_args, env_vars, env_file)) => {
+        Some(Script::Cmd(script_args, env_vars, env_files, _)) => {
             if script_args.is_empty() {
                 bail!("script has no arguments");
             }
-            env_overrides = Some(load_env_vars(pyproject, env_file, env_vars)?);
+            env_overrides = Some(load_env_vars(pyproject, env_files, env_vars)?);
             let script_target = venv_bin.join(&script_args[0]);
             if script_target.is_file() {
                 args = Some(script_target.as_os_str().to_owned())
@@ -108,6 +162,13 @@ fn invoke_script(
                     .collect();
             }
         }
+        Some(Script::Unavailable(platforms)) => {
+            bail!(
+                "script '{}' is not available on this platform (only on {})",
+                args[0].to_string_lossy(),
+                platforms.join(", ")
+            );
+        }
         Some(Script::External(_)) => {
             args[0] = venv_bin.join(&args[0]).into();
         }
@@ -116,8 +177,12 @@ fn invoke_script(
                 bail!("extra arguments to chained commands are not allowed");
             }
             for args in commands {
-                let status =
-                    invoke_script(pyproject, args.into_iter().map(Into::into).collect(), false)?;
+                let status = invoke_script(
+                    pyproject,
+                    args.into_iter().map(Into::into).collect(),
+                    false,
+                    isolated,
+                )?;
                 if !status.success() {
                     if !exec {
                         return Ok(status);
@@ -138,19 +203,32 @@ fn invoke_script(
 
     let mut cmd = Command::new(&args[0]);
     cmd.args(&args[1..]);
-    cmd.env("VIRTUAL_ENV", &*pyproject.venv_path());
-    if let Some(path) = env::var_os("PATH") {
-        let mut paths = split_paths(&path).collect::<Vec<_>>();
-        paths.insert(0, venv_bin.into());
-        let new_path = join_paths(paths)?;
-        cmd.env("PATH", new_path);
-    } else {
-        cmd.env("PATH", &*venv_bin);
-    }
+    let inherited_path = match isolated {
+        Some(pass_env) => {
+            cmd.env_clear();
+            for name in pass_env
+                .iter()
+                .map(|x| x.as_str())
+                .chain(ISOLATED_REQUIRED_VARS.iter().copied())
+            {
+                if let Some(value) = env::var_os(name) {
+                    cmd.env(name, value);
+                }
+            }
+            // the PATH is only extended if it was passed explicitly
+            pass_env
+                .iter()
+                .any(|x| x == "PATH")
+                .then(|| env::var_os("PATH"))
+                .flatten()
+        }
+        None => env::var_os("PATH"),
+    };
+    set_venv_env(&mut cmd, &pyproject.venv_path(), &venv_bin, inherited_path)?;
+    set_dotenv_variables(&mut cmd, &pyproject.dotenv_files(), isolated.is_none())?;
     if let Some(env_overrides) = env_overrides {
         cmd.envs(env_overrides.iter());
     }
-    cmd.env_remove("PYTHONHOME");
 
     if exec {
         match exec_spawn(&mut cmd)? {};
@@ -159,20 +237,218 @@ fn invoke_script(
     }
 }
 
+/// Makes a command run within the virtualenv.
+fn set_venv_env(
+    cmd: &mut Command,
+    venv: &Path,
+    venv_bin: &Path,
+    inherited_path: Option<OsString>,
+) -> Result<(), Error> {
+    cmd.env("VIRTUAL_ENV", venv);
+    if let Some(path) = inherited_path {
+        let mut paths = split_paths(&path).collect::<Vec<_>>();
+        paths.insert(0, venv_bin.into());
+        let new_path = join_paths(paths)?;
+        cmd.env("PATH", new_path);
+    } else {
+        cmd.env("PATH", venv_bin);
+    }
+    cmd.env_remove("PYTHONHOME");
+    Ok(())
+}
+
+/// Returns the cached interpreter for the invocation if it is eligible for
+/// the fast path and nothing changed since the last run.
+pub fn load_fast_path(cmd: &Args) -> Option<RunCacheEntry> {
+    fast_path_command(cmd).and_then(RunCacheEntry::load)
+}
+
+/// Returns the command if the invocation is eligible for the fast path.
+fn fast_path_command(cmd: &Args) -> Option<&'static str> {
+    if cmd.list || cmd.isolated || cmd.pyproject.is_some() || cmd.package.is_some() {
+        return None;
+    }
+    match cmd.cmd {
+        Some(Cmd::External(ref args)) => {
+            let name = args.first()?.to_str()?;
+            FAST_PATH_COMMANDS.iter().copied().find(|x| *x == name)
+        }
+        None => None,
+    }
+}
+
+/// Remembers where `rye run python` went for a working directory.
+///
+/// An entry is only used as long as none of the files that influence the
+/// project discovery or the virtualenv changed.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RunCacheEntry {
+    command: String,
+    venv: PathBuf,
+    venv_bin: PathBuf,
+    python: PathBuf,
+    /// The `.env` files to load.
+    #[serde(default)]
+    dotenv_files: Vec<PathBuf>,
+    /// Watched files with their modification time and size (`None` if missing).
+    watched: Vec<(PathBuf, Option<(u64, u64)>)>,
+}
+
+type RunCache = HashMap<PathBuf, RunCacheEntry>;
+
+impl RunCacheEntry {
+    fn cache_path() -> PathBuf {
+        get_app_dir().join("run-cache.json")
+    }
+
+    fn load_cache() -> RunCache {
+        fs::read(Self::cache_path())
+            .ok()
+            .and_then(|x| serde_json::from_slice(&x).ok())
+            .unwrap_or_default()
+    }
+
+    /// The virtualenv the command runs in.
+    pub fn venv(&self) -> &Path {
+        &self.venv
+    }
+
+    /// Runs the command with the cached interpreter.  Does not return on success.
+    pub fn exec(&self, cmd: &Args) -> Result<(), Error> {
+        if let Some(Cmd::External(ref args)) = cmd.cmd {
+            let mut cmd = Command::new(&self.python);
+            cmd.args(&args[1..]);
+            set_venv_env(&mut cmd, &self.venv, &self.venv_bin, env::var_os("PATH"))?;
+            set_dotenv_variables(&mut cmd, &self.dotenv_files, true)?;
+            match exec_spawn(&mut cmd)? {};
+        }
+        Ok(())
+    }
+
+    /// Loads the entry for the current directory if it is still valid.
+    fn load(command: &str) -> Option<RunCacheEntry> {
+        let cwd = env::current_dir().ok()?;
+        let entry = Self::load_cache().remove(&cwd)?;
+        let valid = entry.command == command
+            && entry.python.is_file()
+            && entry
+                .watched
+                .iter()
+                .all(|(path, stamp)| get_file_stamp(path) == *stamp);
+        valid.then_some(entry)
+    }
+
+    /// Stores the entry for the current directory.
+    fn store(pyproject: &PyProject, command: &str) -> Result<(), Error> {
+        let cwd = env::current_dir()?;
+        let venv = pyproject.venv_path().into_owned();
+        let venv_bin = pyproject.venv_bin_path().into_owned();
+        let mut watched = vec![
+            pyproject.toml_path().into_owned(),
+            pyproject.workspace_path().join("pyproject.toml"),
+            pyproject.root_path().join(".python-version"),
+            pyproject.workspace_path().join(".python-version"),
+            venv.join("rye-venv.json"),
+            Config::current().path().to_path_buf(),
+        ];
+        watched.extend(find_pyenv_pin(&cwd).map(|(path, _)| path));
+        // a project created between the current directory and the workspace
+        // takes over the discovery, so their (missing) pyproject.toml are watched.
+        let workspace_path = pyproject.workspace_path();
+        if cwd.starts_with(&workspace_path) {
+            watched.extend(
+                cwd.ancestors()
+                    .take_while(|x| *x != workspace_path)
+                    .map(|x| x.join("pyproject.toml")),
+            );
+        }
+        watched.sort();
+        watched.dedup();
+        let entry = RunCacheEntry {
+            command: command.to_string(),
+            python: venv_bin.join(command),
+            dotenv_files: pyproject.dotenv_files(),
+            venv,
+            venv_bin,
+            watched: watched
+                .into_iter()
+                .map(|path| {
+                    let stamp = get_file_stamp(&path);
+                    (path, stamp)
+                })
+                .collect(),
+        };
+
+        let mut cache = Self::load_cache();
+        cache.retain(|_, entry| entry.venv.is_dir());
+        cache.insert(cwd, entry);
+        let tmp = NamedTempFile::new_in(get_app_dir())?;
+        fs::write(tmp.path(), serde_json::to_vec(&cache)?)?;
+        tmp.persist(Self::cache_path())?;
+        Ok(())
+    }
+}
+
+fn get_file_stamp(path: &Path) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((u64::try_from(modified.as_nanos()).ok()?, metadata.len()))
+}
+
+/// Generates the code for invoking a `call` script.
+///
+/// If extra arguments are passed on the command line, placeholders in the call
+/// arguments are replaced with them: `{0}`, `{1}`, ... refer to a single argument
+/// and `{args}` passes all of them as positional arguments.  Without extra
+/// arguments the call is left alone, so set literals like `{1}` keep working.
+/// The return value of the function is passed to `sys.exit`.
+fn build_call_code(module: &str, func: &str, argc: usize) -> Result<String, Error> {
+    let call = if !func.contains('(') {
+        format!("{func}()")
+    } else if argc == 0 {
+        func.to_string()
+    } else {
+        let mut missing = None;
+        let call = CALL_PLACEHOLDER_RE.replace_all(func, |caps: &Captures| match &caps[1] {
+            "args" => "*_args".to_string(),
+            idx => {
+                let idx: usize = idx.parse().unwrap_or(usize::MAX);
+                if idx >= argc {
+                    missing = missing.max(Some(idx));
+                }
+                format!("_args[{idx}]")
+            }
+        });
+        if let Some(idx) = missing {
+            bail!(
+                "script expects at least {} argument{}, got {}",
+                idx.saturating_add(1),
+                if idx == 0 { "" } else { "s" },
+                argc
+            );
+        }
+        call.into_owned()
+    };
+    Ok(format!(
+        "import sys; sys.argv.pop(0); _args = sys.argv[1:]; import {module} as _1; \
+         sys.exit(_1.{call})"
+    ))
+}
+
+/// Layers the env files of a script on top of its `env` table.
+///
+/// Later files override earlier ones.  The result overrides the environment
+/// the script is invoked with.
 fn load_env_vars(
     pyproject: &PyProject,
-    env_file: Option<PathBuf>,
+    env_files: Vec<PathBuf>,
     mut env_vars: HashMap<String, String>,
 ) -> Result<HashMap<String, String>, Error> {
-    if let Some(ref env_file) = env_file {
-        let env_file = pyproject.root_path().join(env_file);
-        for item in
-            dotenvy::from_path_iter(&env_file).path_context(&env_file, "could not load env-file")?
-        {
-            let (k, v) = item.path_context(&env_file, "invalid value in env-file")?;
-            env_vars.insert(k, v);
-        }
-    }
+    let env_files = env_files
+        .iter()
+        .map(|x| pyproject.root_path().join(x))
+        .collect::<Vec<_>>();
+    env_vars.extend(load_env_files(&env_files)?);
     Ok(env_vars)
 }
 
@@ -191,6 +467,9 @@ fn list_scripts(pyproject: &PyProject) -> Result<(), Error> {
             echo!("{}", name);
         } else {
             echo!("{} ({})", name, style(script).dim());
+            if let Some(help) = pyproject.script_help(&name) {
+                echo!("  {}", help);
+            }
         }
     }
     Ok(())
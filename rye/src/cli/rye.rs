@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::env::consts::{ARCH, EXE_EXTENSION, OS};
 use std::env::{join_paths, split_paths};
 use std::path::{Path, PathBuf};
@@ -12,9 +13,13 @@ use clap::{CommandFactory, Parser, ValueEnum};
 use clap_complete::{Generator, Shell};
 use clap_complete_nushell::Nushell;
 use console::style;
+use indicatif::HumanBytes;
 use minijinja::render;
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
 use self_replace::self_delete_outside_path;
-use tempfile::tempdir;
+use serde::{Deserialize, Serialize};
+use tempfile::{tempdir, NamedTempFile};
 
 use crate::bootstrap::{
     download_url, download_url_ignore_404, ensure_self_venv_with_toolchain,
@@ -23,8 +28,15 @@ use crate::bootstrap::{
 use crate::cli::toolchain::register_toolchain;
 use crate::config::Config;
 use crate::platform::{get_app_dir, symlinks_supported};
-use crate::sources::py::{get_download_url, PythonVersionRequest};
-use crate::utils::{check_checksum, toml, tui_theme, CommandOutput, IoPathContext, QuietExit};
+use crate::schema::tool_rye_schema;
+use crate::sources::py::{
+    get_download_url, get_downloads_index_path, DownloadsIndex, PythonVersionRequest,
+};
+use crate::tui::{ensure_interactive, ensure_terminal, redirect_to_stderr};
+use crate::utils::{
+    add_source_line, check_checksum, remove_source_line, toml, tui_theme, verify_signature,
+    CommandOutput, IoPathContext, QuietExit,
+};
 
 #[cfg(windows)]
 const DEFAULT_HOME: &str = "%USERPROFILE%\\.rye";
@@ -32,6 +44,7 @@ const DEFAULT_HOME: &str = "%USERPROFILE%\\.rye";
 const DEFAULT_HOME: &str = "$HOME/.rye";
 
 const GITHUB_REPO: &str = "https://github.com/astral-sh/rye";
+const GITHUB_API: &str = "https://api.github.com/repos/astral-sh/rye";
 const UNIX_ENV_FILE: &str = r#"
 # rye shell setup
 {%- if custom_home %}
@@ -128,6 +141,14 @@ pub struct CompletionCommand {
     /// The shell to generate a completion script for (defaults to 'bash').
     #[arg(short, long)]
     shell: Option<ShellCompletion>,
+    /// Install the completion script for the current (or given) shell.
+    ///
+    /// Installed completion scripts are kept up to date by `rye self update`.
+    #[arg(long)]
+    install: bool,
+    /// Regenerate all previously installed completion scripts.
+    #[arg(long, hide = true, conflicts_with = "install")]
+    refresh: bool,
 }
 
 /// Performs an update of rye.
@@ -151,6 +172,31 @@ pub struct UpdateCommand {
     /// Force reinstallation
     #[arg(long)]
     force: bool,
+    /// Only print the version that would be installed without updating.
+    #[arg(long)]
+    dry_run: bool,
+    /// Print the release notes of the version that is installed.
+    #[arg(long, conflicts_with = "rev", conflicts_with = "branch")]
+    show_notes: bool,
+}
+
+/// A release of rye on GitHub.
+#[derive(Deserialize, Debug)]
+struct Release {
+    tag_name: String,
+    html_url: String,
+    #[serde(default)]
+    body: Option<String>,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ReleaseAsset {
+    name: String,
+    size: u64,
 }
 
 /// Triggers the initial installation of Rye.
@@ -169,19 +215,56 @@ pub struct InstallCommand {
     /// Use a specific toolchain version.
     #[arg(long)]
     toolchain_version: Option<PythonVersionRequest>,
+    /// Mark the installation as managed by a system package manager.
+    ///
+    /// This disables `rye self update` and leaves the registration of the
+    /// program to the package manager.
+    #[arg(long)]
+    externally_managed: bool,
+    /// Print the environment changes the installation needs on stdout.
+    ///
+    /// This is meant for provisioning tools that apply the changes themselves,
+    /// typically together with `--yes --no-modify-shell`.
+    #[arg(long, value_name = "FORMAT", num_args = 0..=1, default_missing_value = "shell")]
+    print_env: Option<EnvFormat>,
 
     #[command(flatten)]
     mp: ModifyPath,
 }
 
+/// The format for `rye self install --print-env`.
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+#[value(rename_all = "snake_case")]
+enum EnvFormat {
+    /// `export` statements for POSIX shells.
+    Shell,
+    /// A JSON object.
+    Json,
+}
+
+/// The environment changes an installation needs.
+#[derive(Serialize, Debug)]
+struct InstallEnv {
+    /// The Rye home folder.
+    rye_home: PathBuf,
+    /// Folders that need to be added to `PATH`.
+    path: Vec<PathBuf>,
+    /// Environment variables that need to be set.
+    env: BTreeMap<String, String>,
+    /// The env file that can be sourced instead (unix only).
+    env_file: Option<PathBuf>,
+    /// Did the installer modify the shell profile (or the registry on Windows)?
+    shell_modified: bool,
+}
+
 #[derive(Parser, Debug)]
 #[group(required = false, multiple = false)]
 pub struct ModifyPath {
     /// Always modify without asking the PATH environment variable.
     #[arg(long)]
     modify_path: bool,
-    /// Do not modify the PATH environment variable.
-    #[arg(long)]
+    /// Do not modify the PATH environment variable or any shell profile.
+    #[arg(long, visible_alias = "no-modify-shell")]
     no_modify_path: bool,
 }
 
@@ -230,6 +313,58 @@ pub struct UninstallCommand {
     yes: bool,
 }
 
+/// Checks that this build of Rye works end to end.
+///
+/// Runs the core commands (init, pin, sync, add, run and build) on a fresh
+/// project with a temporary `RYE_HOME`.  This lets people that package Rye
+/// validate their builds.  Network access is required.
+#[derive(Parser, Debug)]
+pub struct TestCommand {
+    /// The Python version to test with.
+    #[arg(long, default_value = "3.12")]
+    python: String,
+    /// The package to add to the test project.
+    #[arg(long, default_value = "idna")]
+    package: String,
+    /// Keep the temporary directory for inspection.
+    #[arg(long)]
+    keep: bool,
+    /// Enables verbose diagnostics.
+    #[arg(short, long)]
+    verbose: bool,
+}
+
+/// Refreshes the checksums of the Python downloads from a signed index.
+///
+/// The index is only accepted if the detached signature next to it (the
+/// index URL with `.sig` appended) matches the configured public key.
+#[derive(Parser, Debug)]
+pub struct RefreshDownloadsIndexCommand {
+    /// The URL of the index (defaults to `downloads-index.url` in the config).
+    #[arg(long)]
+    url: Option<String>,
+    /// The hex encoded ed25519 public key the index is signed with (defaults
+    /// to `downloads-index.public-key` in the config).
+    #[arg(long)]
+    public_key: Option<String>,
+    /// Enables verbose diagnostics.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Turns off all output.
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+/// Prints the JSON schema of the `[tool.rye]` table in `pyproject.toml`.
+///
+/// Editors can use the schema to validate and complete Rye's settings.
+#[derive(Parser, Debug)]
+pub struct SchemaCommand {
+    /// Write the schema to this file instead of stdout.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
 #[derive(Parser, Debug)]
 enum SubCommand {
     Completion(CompletionCommand),
@@ -237,6 +372,9 @@ enum SubCommand {
     #[command(hide = true)]
     Install(InstallCommand),
     Uninstall(UninstallCommand),
+    Test(TestCommand),
+    Schema(SchemaCommand),
+    RefreshDownloadsIndex(RefreshDownloadsIndexCommand),
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
@@ -245,10 +383,23 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         SubCommand::Update(args) => update(args),
         SubCommand::Install(args) => install(args),
         SubCommand::Uninstall(args) => uninstall(args),
+        SubCommand::Test(args) => self_test(args),
+        SubCommand::Schema(args) => schema(args),
+        SubCommand::RefreshDownloadsIndex(args) => refresh_downloads_index(args),
     }
 }
 
 fn completion(args: CompletionCommand) -> Result<(), Error> {
+    if args.refresh {
+        return refresh_completions();
+    } else if args.install {
+        let shell = match args.shell.or_else(detect_shell) {
+            Some(shell) => shell,
+            None => bail!("unable to detect shell, pass it explicitly with --shell"),
+        };
+        return install_completion(shell);
+    }
+
     clap_complete::generate(
         args.shell.unwrap_or(ShellCompletion::Bash),
         &mut super::Args::command(),
@@ -259,13 +410,195 @@ fn completion(args: CompletionCommand) -> Result<(), Error> {
     Ok(())
 }
 
+/// Detects the shell of the user from the environment.
+fn detect_shell() -> Option<ShellCompletion> {
+    let shell = match env::var_os("SHELL") {
+        Some(shell) => PathBuf::from(shell),
+        None if cfg!(windows) => return Some(ShellCompletion::PowerShell),
+        None => return None,
+    };
+    match shell.file_stem()?.to_str()? {
+        "bash" => Some(ShellCompletion::Bash),
+        "elvish" => Some(ShellCompletion::Elvish),
+        "fish" => Some(ShellCompletion::Fish),
+        "pwsh" | "powershell" => Some(ShellCompletion::PowerShell),
+        "zsh" => Some(ShellCompletion::Zsh),
+        "nu" => Some(ShellCompletion::Nushell),
+        _ => None,
+    }
+}
+
+/// Returns the path the completion script for a shell is installed to.
+///
+/// For bash and fish these are the folders the shells load completions from
+/// automatically.  The scripts of all other shells are placed in the rye home
+/// and loaded from the shell configuration.  The configuration file and the
+/// line that needs to be added to it are returned as second value.
+fn get_completion_path(
+    shell: &ShellCompletion,
+) -> Result<(PathBuf, Option<(PathBuf, String)>), Error> {
+    let home = home::home_dir().ok_or_else(|| anyhow!("could not find home directory"))?;
+    let xdg_dir = |var: &str, default: &str| {
+        env::var_os(var)
+            .map(PathBuf::from)
+            .filter(|x| x.is_absolute())
+            .unwrap_or_else(|| home.join(default))
+    };
+    let config_dir = || match env::var_os("APPDATA") {
+        Some(app_data) if cfg!(windows) => PathBuf::from(app_data),
+        _ => xdg_dir("XDG_CONFIG_HOME", ".config"),
+    };
+    let completions = get_app_dir().join("completions");
+    Ok(match shell {
+        ShellCompletion::Bash => (
+            xdg_dir("XDG_DATA_HOME", ".local/share")
+                .join("bash-completion")
+                .join("completions")
+                .join("rye"),
+            None,
+        ),
+        ShellCompletion::Fish => (
+            xdg_dir("XDG_CONFIG_HOME", ".config")
+                .join("fish")
+                .join("completions")
+                .join("rye.fish"),
+            None,
+        ),
+        ShellCompletion::Zsh => {
+            let path = completions.join("_rye");
+            // the script registers itself with compdef when sourced, which
+            // only exists once compinit ran.
+            let line = format!(
+                "(( $+functions[compdef] )) && source \"{}\"",
+                path.display()
+            );
+            (path, Some((xdg_dir("ZDOTDIR", "").join(".zshrc"), line)))
+        }
+        ShellCompletion::Elvish => {
+            let path = completions.join("rye.elv");
+            let line = format!("eval (slurp < \"{}\")", path.display());
+            (
+                path,
+                Some((config_dir().join("elvish").join("rc.elv"), line)),
+            )
+        }
+        ShellCompletion::PowerShell => {
+            let path = completions.join("rye.ps1");
+            let line = format!(". \"{}\"", path.display());
+            let profile = if cfg!(windows) {
+                home.join("Documents").join("PowerShell")
+            } else {
+                config_dir().join("powershell")
+            };
+            (
+                path,
+                Some((profile.join("Microsoft.PowerShell_profile.ps1"), line)),
+            )
+        }
+        ShellCompletion::Nushell => {
+            let path = completions.join("rye.nu");
+            let line = format!("source \"{}\"", path.display());
+            let nu_config = if cfg!(target_os = "macos") && env::var_os("XDG_CONFIG_HOME").is_none()
+            {
+                home.join("Library").join("Application Support")
+            } else {
+                config_dir()
+            };
+            (
+                path,
+                Some((nu_config.join("nushell").join("config.nu"), line)),
+            )
+        }
+    })
+}
+
+fn write_completion(shell: &ShellCompletion, path: &Path) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).path_context(parent, "could not create completion folder")?;
+    }
+    let mut buf = Vec::new();
+    clap_complete::generate(shell.clone(), &mut super::Args::command(), "rye", &mut buf);
+    fs::write(path, buf).path_context(path, "could not write completion script")
+}
+
+fn get_installed_completions() -> BTreeMap<String, PathBuf> {
+    fs::read(get_app_dir().join("completions.json"))
+        .ok()
+        .and_then(|x| serde_json::from_slice(&x).ok())
+        .unwrap_or_default()
+}
+
+fn install_completion(shell: ShellCompletion) -> Result<(), Error> {
+    let (path, source) = get_completion_path(&shell)?;
+    write_completion(&shell, &path)?;
+
+    let name = shell.to_possible_value().unwrap().get_name().to_string();
+    let mut installed = get_installed_completions();
+    installed.insert(name.clone(), path.clone());
+    let manifest = get_app_dir().join("completions.json");
+    fs::write(&manifest, serde_json::to_string_pretty(&installed)?)
+        .path_context(&manifest, "could not record installed completion")?;
+
+    echo!(
+        "Installed {} completion to {}",
+        style(name).cyan(),
+        style(path.display()).cyan()
+    );
+    if let Some((config, line)) = source {
+        if add_source_line(&config, &line)? {
+            echo!(
+                "Added completion loading to {}",
+                style(config.display()).cyan()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn refresh_completions() -> Result<(), Error> {
+    for (name, path) in get_installed_completions() {
+        let shell = match ShellCompletion::from_str(&name, false) {
+            Ok(shell) => shell,
+            Err(_) => continue,
+        };
+        write_completion(&shell, &path)?;
+    }
+    Ok(())
+}
+
 fn update(args: UpdateCommand) -> Result<(), Error> {
+    if !args.dry_run && Config::current().externally_managed_self() {
+        bail!(
+            "this Rye installation is managed by a system package manager \
+             (behavior.externally-managed-self).\n\
+             Update it through the package manager it was installed with instead."
+        );
+    }
+
     // make sure to read the exe before self_replace as otherwise we might read
     // a bad executable name on Linux where the move is picked up.
     let current_exe = env::current_exe()?;
 
     // git based installation with cargo
     if args.rev.is_some() || args.tag.is_some() || args.branch.is_some() {
+        if args.show_notes {
+            if let Some(ref tag) = args.tag {
+                print_release_notes(&fetch_release(tag)?);
+            }
+        }
+        if args.dry_run {
+            let target = match (&args.rev, &args.tag, &args.branch) {
+                (Some(rev), _, _) => format!("rev {}", rev),
+                (_, Some(tag), _) => format!("tag {}", tag),
+                (_, _, branch) => format!("branch {}", branch.as_deref().unwrap_or_default()),
+            };
+            echo!(
+                "Would compile and install rye from {} of {} with cargo",
+                style(target).cyan(),
+                GITHUB_REPO
+            );
+            return Ok(());
+        }
         let mut cmd = Command::new("cargo");
         let tmp = tempdir()?;
         cmd.arg("install")
@@ -307,9 +640,21 @@ fn update(args: UpdateCommand) -> Result<(), Error> {
         )
     } else {
         let version = args.version.as_deref().unwrap_or("latest");
-        echo!("Updating to {version}");
         let binary = format!("rye-{ARCH}-{OS}");
         let ext = if cfg!(unix) { ".gz" } else { ".exe" };
+        if args.dry_run || args.show_notes {
+            let release = fetch_release(version)?;
+            if args.dry_run {
+                print_release_summary(&release, &format!("{binary}{ext}"));
+            }
+            if args.show_notes {
+                print_release_notes(&release);
+            }
+            if args.dry_run {
+                return Ok(());
+            }
+        }
+        echo!("Updating to {version}");
         let url = if version == "latest" {
             format!("{GITHUB_REPO}/releases/latest/download/{binary}{ext}")
         } else {
@@ -353,6 +698,20 @@ fn update(args: UpdateCommand) -> Result<(), Error> {
     validate_updated_exe(&current_exe)
         .context("unable to perform validation of updated installation")?;
 
+    // the new executable might know about new commands, so it has to
+    // regenerate the installed completion scripts itself.
+    if !get_installed_completions().is_empty() {
+        echo!("Updating shell completions");
+        let status = Command::new(&current_exe)
+            .arg("self")
+            .arg("completion")
+            .arg("--refresh")
+            .status()?;
+        if !status.success() {
+            warn!("failed to update shell completions");
+        }
+    }
+
     echo!("Updated!");
     echo!();
     Command::new(current_exe).arg("--version").status()?;
@@ -360,6 +719,106 @@ fn update(args: UpdateCommand) -> Result<(), Error> {
     Ok(())
 }
 
+/// Fetches the release with the given tag (or the latest release) from GitHub.
+fn fetch_release(tag: &str) -> Result<Release, Error> {
+    let url = if tag == "latest" {
+        format!("{GITHUB_API}/releases/latest")
+    } else {
+        format!("{GITHUB_API}/releases/tags/{tag}")
+    };
+    let Some(body) = download_url_ignore_404(&url, CommandOutput::Quiet)
+        .context("could not fetch release information from GitHub")?
+    else {
+        bail!("release {} not found", tag);
+    };
+    serde_json::from_slice(&body).context("invalid release information from GitHub")
+}
+
+fn print_release_summary(release: &Release, asset: &str) {
+    let current = env!("CARGO_PKG_VERSION");
+    echo!("Current version: {}", style(current).cyan());
+    if release.tag_name == current {
+        echo!(
+            "Target version: {} (already installed)",
+            style(&release.tag_name).cyan()
+        );
+    } else {
+        echo!("Target version: {}", style(&release.tag_name).cyan());
+    }
+    if let Some(date) = release.published_at.as_deref() {
+        echo!("Published: {}", date.split('T').next().unwrap_or(date));
+    }
+    echo!("Release page: {}", release.html_url);
+    match release.assets.iter().find(|x| x.name == asset) {
+        Some(asset) => echo!("Binary: {} ({})", asset.name, HumanBytes(asset.size)),
+        None => warn!(
+            "release {} has no binary for this platform ({})",
+            release.tag_name, asset
+        ),
+    }
+    echo!("Nothing was updated (dry run)");
+}
+
+fn print_release_notes(release: &Release) {
+    echo!();
+    echo!(
+        "{}",
+        style(format!("Release notes for {}", release.tag_name)).bold()
+    );
+    echo!();
+    match release.body.as_deref().filter(|x| !x.trim().is_empty()) {
+        Some(body) => {
+            for line in render_release_notes(body) {
+                echo!("{}", line);
+            }
+        }
+        None => echo!("{}", style("(no release notes)").dim()),
+    }
+    echo!();
+}
+
+/// Renders the markdown of release notes for the terminal.
+///
+/// Headings are highlighted, list markers replaced by bullets, emphasis is
+/// dropped and links are shown with their target.
+fn render_release_notes(body: &str) -> Vec<String> {
+    static HEADING_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^#{1,6}\s+(.*)$").unwrap());
+    static BULLET_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(\s*)[-*+]\s+(.*)$").unwrap());
+    static LINK_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\[([^\]]+)\]\(([^)\s]+)\)").unwrap());
+    static EMPHASIS_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"\*\*([^*]+)\*\*").unwrap());
+
+    let mut rv = Vec::<String>::new();
+    for line in body.lines() {
+        let line = line.trim_end();
+        if line.trim_start().starts_with("<!--") {
+            continue;
+        }
+        // collapse runs of empty lines
+        if line.is_empty() && rv.last().map_or(true, |x| x.is_empty()) {
+            continue;
+        }
+        let line = EMPHASIS_RE.replace_all(line, "$1");
+        let line = LINK_RE.replace_all(&line, |caps: &Captures| {
+            if caps[1] == caps[2] {
+                caps[2].to_string()
+            } else {
+                format!("{} ({})", &caps[1], &caps[2])
+            }
+        });
+        if let Some(caps) = HEADING_RE.captures(&line) {
+            rv.push(style(&caps[1]).bold().to_string());
+        } else if let Some(caps) = BULLET_RE.captures(&line) {
+            rv.push(format!("{}• {}", &caps[1], &caps[2]));
+        } else {
+            rv.push(line.into_owned());
+        }
+    }
+    while rv.last().map_or(false, |x| x.is_empty()) {
+        rv.pop();
+    }
+    rv
+}
+
 fn validate_updated_exe(rye: &Path) -> Result<(), Error> {
     let folder = tempfile::tempdir()?;
 
@@ -416,7 +875,9 @@ fn update_exe_and_shims(new_exe: &Path) -> Result<(), Error> {
 }
 
 fn install(args: InstallCommand) -> Result<(), Error> {
-    perform_install(
+    // with --print-env stdout only carries the environment changes
+    let guard = redirect_to_stderr(args.print_env.is_some());
+    let install_env = perform_install(
         if args.yes {
             InstallMode::NoPrompts
         } else {
@@ -425,7 +886,33 @@ fn install(args: InstallCommand) -> Result<(), Error> {
         args.toolchain.as_deref(),
         args.toolchain_version,
         YesNoArg::from(args.mp).with_yes(args.yes),
-    )
+        args.externally_managed,
+    )?;
+    drop(guard);
+
+    match args.print_env {
+        Some(EnvFormat::Json) => {
+            println!("{}", serde_json::to_string_pretty(&install_env)?);
+        }
+        Some(EnvFormat::Shell) => {
+            for (key, value) in &install_env.env {
+                println!("export {}={}", key, shell_quote(value));
+            }
+            for path in &install_env.path {
+                println!(
+                    "export PATH={}:\"$PATH\"",
+                    shell_quote(&path.display().to_string())
+                );
+            }
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+/// Quotes a value for POSIX shells.
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
 }
 
 fn remove_dir_all_if_exists(path: &Path) -> Result<(), Error> {
@@ -436,6 +923,9 @@ fn remove_dir_all_if_exists(path: &Path) -> Result<(), Error> {
 }
 
 fn uninstall(args: UninstallCommand) -> Result<(), Error> {
+    if !args.yes {
+        ensure_interactive("pass --yes to uninstall without confirmation")?;
+    }
     if !args.yes
         && !dialoguer::Confirm::with_theme(tui_theme())
             .with_prompt("Do you want to uninstall rye?")
@@ -464,6 +954,19 @@ fn uninstall(args: UninstallCommand) -> Result<(), Error> {
         remove_dir_all_if_exists(&app_dir.join("uv"))?;
         remove_dir_all_if_exists(&app_dir.join("tools"))?;
 
+        // completion scripts might live outside of the app dir and the shell
+        // configuration must no longer load the ones that are removed.
+        for (name, path) in get_installed_completions() {
+            fs::remove_file(path).ok();
+            if let Ok(shell) = ShellCompletion::from_str(&name, false) {
+                if let Some((config, line)) = get_completion_path(&shell)?.1 {
+                    remove_source_line(&config, &line)?;
+                }
+            }
+        }
+        fs::remove_file(app_dir.join("completions.json")).ok();
+        remove_dir_all_if_exists(&app_dir.join("completions"))?;
+
         // special deleting logic if we are placed in the app dir and the shim deletion
         // did not succeed.  This is likely the case on windows where we then use the
         // `self_delete` crate.
@@ -505,6 +1008,135 @@ fn uninstall(args: UninstallCommand) -> Result<(), Error> {
     Ok(())
 }
 
+fn schema(args: SchemaCommand) -> Result<(), Error> {
+    let schema = serde_json::to_string_pretty(&tool_rye_schema())?;
+    match args.output {
+        Some(path) => {
+            fs::write(&path, format!("{}\n", schema))
+                .path_context(&path, "failed to write schema")?;
+        }
+        None => echo!("{}", schema),
+    }
+    Ok(())
+}
+
+fn refresh_downloads_index(args: RefreshDownloadsIndexCommand) -> Result<(), Error> {
+    let output = CommandOutput::from_quiet_and_verbose(args.quiet, args.verbose);
+    let config = Config::current();
+    let url = args
+        .url
+        .or_else(|| config.downloads_index_url())
+        .ok_or_else(|| {
+            anyhow!("no downloads index configured (set downloads-index.url or pass --url)")
+        })?;
+    let public_key = args
+        .public_key
+        .or_else(|| config.downloads_index_public_key())
+        .ok_or_else(|| {
+            anyhow!(
+                "no public key configured (set downloads-index.public-key or pass --public-key)"
+            )
+        })?;
+
+    echo!(if verbose output, "index url: {}", url);
+    let index = download_url(&url, output)?;
+    let signature = download_url(&format!("{}.sig", url), output)?;
+    verify_signature(&index, &String::from_utf8_lossy(&signature), &public_key)
+        .with_context(|| format!("signature check of {} failed", url))?;
+    let downloads = DownloadsIndex::parse(&index)
+        .with_context(|| format!("invalid downloads index {}", url))?
+        .downloads;
+
+    let path = get_downloads_index_path();
+    let tmp = NamedTempFile::new_in(get_app_dir())?;
+    fs::write(tmp.path(), &index)?;
+    tmp.persist(&path)
+        .path_context(&path, "failed to store downloads index")?;
+
+    echo!(
+        if output,
+        "{} checksums of {} downloads",
+        style("Refreshed").green(),
+        downloads.len()
+    );
+    Ok(())
+}
+
+fn self_test(args: TestCommand) -> Result<(), Error> {
+    let rye = env::current_exe()?;
+    let dir = tempdir()?;
+    let home = dir.path().join("home");
+    let project = dir.path().join("project");
+    fs::create_dir_all(&project).path_context(&project, "failed to create test project")?;
+    echo!(
+        "Testing {} in {}",
+        rye.display(),
+        style(dir.path().display()).cyan()
+    );
+
+    let check_import = format!("import rye_self_test, {}", args.package.replace('-', "_"));
+    let steps: &[(&str, &[&str])] = &[
+        ("init", &["init", "--name", "rye-self-test"]),
+        ("pin", &["pin", args.python.as_str()]),
+        ("sync", &["sync"]),
+        ("add", &["add", args.package.as_str()]),
+        ("run", &["run", "python", "-c", check_import.as_str()]),
+        ("build", &["build", "--wheel", "--clean"]),
+    ];
+
+    let mut failed = None;
+    for (name, step_args) in steps {
+        let output = Command::new(&rye)
+            .args(*step_args)
+            .env("RYE_HOME", &home)
+            .env_remove("VIRTUAL_ENV")
+            .env_remove("RYE_TOOLCHAIN")
+            .current_dir(&project)
+            .output()
+            .with_context(|| format!("failed to run rye {}", name))?;
+        if args.verbose || !output.status.success() {
+            echo!("$ rye {}", step_args.join(" "));
+            for stream in [&output.stdout, &output.stderr] {
+                let text = String::from_utf8_lossy(stream);
+                if !text.trim().is_empty() {
+                    echo!("{}", text.trim_end());
+                }
+            }
+        }
+        if output.status.success() {
+            echo!("{:<6} {}", style(name).cyan(), style("ok").green());
+        } else {
+            echo!("{:<6} {}", style(name).cyan(), style("failed").red());
+            failed = Some(name);
+            break;
+        }
+    }
+
+    if failed.is_none() {
+        let dist = project.join("dist");
+        let has_wheel = fs::read_dir(&dist)
+            .path_context(&dist, "build produced no output")?
+            .filter_map(|x| x.ok())
+            .any(|x| x.path().extension().map_or(false, |ext| ext == "whl"));
+        if !has_wheel {
+            bail!("build did not produce a wheel in {}", dist.display());
+        }
+    }
+
+    if args.keep {
+        let path = dir.into_path();
+        echo!("Kept test directory {}", style(path.display()).cyan());
+    }
+
+    match failed {
+        Some(name) => bail!("self test failed in step '{}'", name),
+        None => {
+            echo!("Self test passed");
+            Ok(())
+        }
+    }
+}
+
 #[cfg(unix)]
 fn has_fish() -> bool {
     use which::which;
@@ -522,7 +1154,11 @@ fn perform_install(
     toolchain_path: Option<&Path>,
     toolchain_version: Option<PythonVersionRequest>,
     modify_path: YesNoArg,
-) -> Result<(), Error> {
+    externally_managed: bool,
+) -> Result<InstallEnv, Error> {
+    if !matches!(mode, InstallMode::NoPrompts) {
+        ensure_terminal("pass --yes to install with the default settings")?;
+    }
     let mut config = Config::current();
     let mut registered_toolchain: Option<PythonVersionRequest> = None;
     let config_doc = Arc::make_mut(&mut config).doc_mut();
@@ -651,6 +1287,11 @@ fn perform_install(
         }
     }
 
+    if externally_managed {
+        toml::ensure_table(config_doc, "behavior")["externally-managed-self"] =
+            toml_edit::value(true);
+    }
+
     // place executable in rye home folder
     fs::create_dir_all(&shims).ok();
     if target.is_file() {
@@ -665,11 +1306,14 @@ fn perform_install(
         .map(|x| (true, Cow::Owned(x)))
         .unwrap_or((false, Cow::Borrowed(DEFAULT_HOME)));
 
-    if cfg!(unix) {
+    let env_file = if cfg!(unix) {
         let env_path = app_dir.join("env");
         fs::write(&env_path, render!(UNIX_ENV_FILE, custom_home, rye_home))
             .path_context(&env_path, "failed to write env file")?;
-    }
+        Some(env_path)
+    } else {
+        None
+    };
 
     // Register a toolchain if provided.
     if let Some(toolchain_path) = toolchain_path {
@@ -705,32 +1349,47 @@ fn perform_install(
         prompt_for_default_toolchain(registered_toolchain.unwrap(), config_doc)?;
     }
 
-    match modify_path {
-        YesNoArg::Yes => {
-            add_rye_to_path(&mode, shims.as_path(), false)?;
-        }
+    let shell_modified = match modify_path {
+        YesNoArg::Yes => add_rye_to_path(&mode, shims.as_path(), false, externally_managed)?,
         YesNoArg::No => {
             echo!(
                 "Skipping PATH modification. You will need to add {} to your PATH manually.",
                 style(shims.display()).cyan()
             );
+            false
         }
-        YesNoArg::Ask => {
-            add_rye_to_path(&mode, shims.as_path(), true)?;
-        }
-    }
+        YesNoArg::Ask => add_rye_to_path(&mode, shims.as_path(), true, externally_managed)?,
+    };
 
     echo!();
     echo!("{}", style("All done!").green());
 
     config.save()?;
 
-    Ok(())
+    let mut env = BTreeMap::new();
+    if custom_home {
+        env.insert("RYE_HOME".to_string(), app_dir.display().to_string());
+    }
+    Ok(InstallEnv {
+        rye_home: app_dir.to_path_buf(),
+        path: vec![shims],
+        env,
+        env_file,
+        shell_modified,
+    })
 }
 
 /// Add rye to the users path.
+///
+/// Returns `true` if a shell profile (or the registry) was modified.
 #[cfg_attr(windows, allow(unused_variables))]
-fn add_rye_to_path(mode: &InstallMode, shims: &Path, ask: bool) -> Result<(), Error> {
+fn add_rye_to_path(
+    mode: &InstallMode,
+    shims: &Path,
+    ask: bool,
+    externally_managed: bool,
+) -> Result<bool, Error> {
+    let mut modified = false;
     let rye_home = env::var("RYE_HOME")
         .map(Cow::Owned)
         .unwrap_or(Cow::Borrowed(DEFAULT_HOME));
@@ -741,6 +1400,8 @@ fn add_rye_to_path(mode: &InstallMode, shims: &Path, ask: bool) -> Result<(), Er
     // See [`crate::utils::unix::add_to_path`].
     #[cfg(unix)]
     {
+        // package managers only register installations on windows
+        let _ = externally_managed;
         if !env::split_paths(&env::var_os("PATH").unwrap())
             .any(|x| same_file::is_same_file(x, shims).unwrap_or(false))
         {
@@ -762,6 +1423,7 @@ fn add_rye_to_path(mode: &InstallMode, shims: &Path, ask: bool) -> Result<(), Er
                     .interact()?
             {
                 crate::utils::unix::add_to_path(rye_home)?;
+                modified = true;
                 echo!("Added to {}.", style("PATH").cyan());
                 echo!(
                     "{}: for this to take effect you will need to restart your shell or run this manually:",
@@ -796,12 +1458,16 @@ fn add_rye_to_path(mode: &InstallMode, shims: &Path, ask: bool) -> Result<(), Er
         }
     }
     // On Windows, we add the rye directory to the user's PATH unconditionally.
+    // Externally managed installations are registered by the package manager.
     #[cfg(windows)]
     {
-        crate::utils::windows::add_to_programs(rye_home)?;
+        if !externally_managed {
+            crate::utils::windows::add_to_programs(rye_home)?;
+        }
         crate::utils::windows::add_to_path(rye_home)?;
+        modified = true;
     }
-    Ok(())
+    Ok(modified)
 }
 
 fn prompt_for_default_toolchain(
@@ -849,7 +1515,25 @@ pub fn auto_self_install() -> Result<bool, Error> {
             crate::request_continue_prompt();
         }
 
-        perform_install(InstallMode::AutoInstall, None, None, YesNoArg::Yes)?;
+        perform_install(InstallMode::AutoInstall, None, None, YesNoArg::Yes, false)?;
         Ok(true)
     }
 }
+
+#[test]
+fn test_render_release_notes() {
+    console::set_colors_enabled(false);
+    assert_eq!(
+        render_release_notes(
+            "<!-- generated -->\r\n## Fixes\r\n\r\n\r\n* **Sync** no longer hangs in \
+             [#1234](https://github.com/astral-sh/rye/pull/1234)\r\n  - see \
+             [https://rye.astral.sh](https://rye.astral.sh)\r\n\r\n"
+        ),
+        vec![
+            "Fixes",
+            "",
+            "• Sync no longer hangs in #1234 (https://github.com/astral-sh/rye/pull/1234)",
+            "  • see https://rye.astral.sh",
+        ]
+    );
+}
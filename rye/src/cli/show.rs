@@ -1,15 +1,43 @@
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fmt;
+use std::fs;
 use std::path::Path;
 use std::path::PathBuf;
 
-use anyhow::Error;
-use clap::Parser;
+use anyhow::{Context, Error};
+use clap::{Parser, Subcommand, ValueEnum};
 use console::style;
+use pep440_rs::{Version, VersionSpecifiers};
+use serde::Serialize;
+use toml_edit::{ImDocument, Item, TableLike, Value};
 
-use crate::pyproject::{get_current_venv_python_version, PyProject};
+use crate::cli::config::value_to_json;
+use crate::config::{Config, CONFIG_SETTINGS};
+use crate::lock::{parse_locked_versions, KeyringProvider, LockGraph, LockMode};
+use crate::platform::{
+    get_canonical_py_path, get_toolchain_python_bin, is_in_shared_toolchain_dir,
+    list_known_toolchains,
+};
+use crate::pyproject::{
+    get_current_venv_python_version, read_venv_marker, DependencyKind, ExpandedSources, PyProject,
+    PythonVersionSource,
+};
+use crate::schema::{SettingScope, SETTINGS};
+use crate::sources::py::{get_download_url, matches_version, PythonVersion, PythonVersionRequest};
+use crate::sync::preview_lockfiles_for_python;
+use crate::utils::{
+    get_venv_python_bin, mask_url_credentials, CommandOutput, IoPathContext, QuietExit,
+};
+use crate::yanked::{
+    filename_version, find_project_page, wheel_supports_python, ProjectFile, ProjectPage,
+};
 
 /// Prints the current state of the project.
 #[derive(Parser, Debug)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Print the installed dependencies from the venv
     #[arg(long)]
     installed_deps: bool,
@@ -18,9 +46,123 @@ pub struct Args {
     pyproject: Option<PathBuf>,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    #[command(alias = "dependencies")]
+    Deps(DepsArgs),
+    Python(PythonArgs),
+    DiffPython(DiffPythonArgs),
+    Config(ConfigArgs),
+}
+
+/// Prints the declared dependencies of the project.
+#[derive(Parser, Debug)]
+struct DepsArgs {
+    /// Show the comments attached to each dependency (eg: owners)
+    #[arg(long)]
+    owners: bool,
+    /// Explain why a package is in the lockfile.
+    ///
+    /// Prints the chains of packages through which the package is required.
+    /// Exits with 1 if the package is not locked.
+    #[arg(long, value_name = "PACKAGE", conflicts_with = "owners")]
+    why: Option<String>,
+    /// Look up the package in the dev lockfile.
+    #[arg(long, requires = "why")]
+    dev: bool,
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+}
+
+/// Prints which Python interpreter the project uses and why.
+///
+/// This is useful for debugging which interpreter shims and editors pick up.
+#[derive(Parser, Debug)]
+struct PythonArgs {
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+    /// Request parseable output format
+    #[arg(long)]
+    format: Option<Format>,
+}
+
+/// Previews the effect of pinning another Python version.
+///
+/// Checks if the interpreter is available, resolves the lockfiles for the
+/// new version and reports locked packages that lack wheels for it or
+/// exclude it with their `requires-python`.  No files are modified.
+#[derive(Parser, Debug)]
+struct DiffPythonArgs {
+    /// The Python version to compare against (eg: 3.13)
+    version: String,
+    /// Attempt to use `keyring` for authentication for index URLs.
+    #[arg(long, value_enum, default_value_t)]
+    keyring_provider: KeyringProvider,
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+}
+
+/// Prints the configured settings and where they come from.
+///
+/// Settings are read from the environment, the global `config.toml`, the
+/// `pyproject.toml` of the project and the one of its workspace.
+#[derive(Parser, Debug)]
+struct ConfigArgs {
+    /// Also print the settings that are not configured with their default.
+    #[arg(long)]
+    resolved: bool,
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+    /// Request parseable output format
+    #[arg(long)]
+    format: Option<Format>,
+}
+
+#[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
+#[value(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+enum Format {
+    Json,
+}
+
 pub fn execute(cmd: Args) -> Result<(), Error> {
+    match cmd.command {
+        Some(Command::Deps(args)) => {
+            let pyproject = args.pyproject.or(cmd.pyproject);
+            return match args.why {
+                Some(package) => show_why(pyproject.as_deref(), &package, args.dev),
+                None => show_deps(pyproject.as_deref(), args.owners),
+            };
+        }
+        Some(Command::Python(args)) => {
+            return show_python(args.pyproject.or(cmd.pyproject).as_deref(), args.format);
+        }
+        Some(Command::DiffPython(args)) => {
+            return show_diff_python(
+                args.pyproject.or(cmd.pyproject).as_deref(),
+                &args.version,
+                args.keyring_provider,
+            );
+        }
+        Some(Command::Config(args)) => {
+            return show_config(
+                args.pyproject.or(cmd.pyproject).as_deref(),
+                args.resolved,
+                args.format,
+            );
+        }
+        None => {}
+    }
+
     if cmd.installed_deps {
-        warn!("--installed-deps is deprecated, use `rye list`");
+        warn!(
+            category = Deprecated,
+            "--installed-deps is deprecated, use `rye list`"
+        );
         return crate::cli::list::execute(crate::cli::list::Args {
             pyproject: cmd.pyproject,
         });
@@ -78,7 +220,7 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
                     "  {} ({}: {})",
                     style(&source.name).cyan(),
                     style(&source.ty).yellow(),
-                    style(&source.url).dim(),
+                    style(source.display_url()).dim(),
                 );
             }
         }
@@ -87,3 +229,698 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
 
     Ok(())
 }
+
+fn show_deps(pyproject: Option<&Path>, owners: bool) -> Result<(), Error> {
+    let project = PyProject::load_or_discover(pyproject)?;
+    let mut extras = project.extras().into_iter().collect::<Vec<_>>();
+    extras.sort();
+    let sections = [
+        ("dependencies".to_string(), DependencyKind::Normal),
+        ("dev-dependencies".to_string(), DependencyKind::Dev),
+        (
+            "excluded-dependencies".to_string(),
+            DependencyKind::Excluded,
+        ),
+    ]
+    .into_iter()
+    .chain(extras.into_iter().map(|extra| {
+        (
+            format!("optional-dependencies.{}", extra),
+            DependencyKind::Optional(extra.into()),
+        )
+    }));
+
+    for (title, kind) in sections {
+        let deps = project
+            .iter_dependencies_with_comments(kind)
+            .collect::<Vec<_>>();
+        if deps.is_empty() {
+            continue;
+        }
+        echo!("{}:", style(title).yellow());
+        for (dep, comments) in deps {
+            if owners && !comments.is_empty() {
+                echo!("  {}  {}", dep, style(comments.join("; ")).dim());
+            } else {
+                echo!("  {}", dep);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn show_why(pyproject: Option<&Path>, package: &str, dev: bool) -> Result<(), Error> {
+    let project = PyProject::load_or_discover(pyproject)?;
+    let lockfile = project.lockfile_path(if dev {
+        LockMode::Dev
+    } else {
+        LockMode::Production
+    });
+    let contents = fs::read_to_string(&lockfile)
+        .path_context(&lockfile, "could not read lockfile (run `rye lock` first)")?;
+    let graph = LockGraph::parse(&contents);
+    if !graph.contains(package) {
+        elog!(
+            "{} is not in {}",
+            style(package).yellow(),
+            style(lockfile.display()).cyan()
+        );
+        return Err(QuietExit(1).into());
+    }
+
+    echo!(
+        "{}",
+        style(graph.requirement(package).unwrap_or(package)).yellow()
+    );
+    for chain in graph.why(package) {
+        echo!("  {}", chain.join(" -> "));
+    }
+    Ok(())
+}
+
+/// Output structure for show python --format=json
+#[derive(Serialize, Debug)]
+struct PythonInfo {
+    requested: Option<String>,
+    requested_from: Option<String>,
+    requested_from_path: Option<PathBuf>,
+    toolchain: Option<PythonVersion>,
+    toolchain_kind: &'static str,
+    toolchain_path: Option<PathBuf>,
+    venv: PathBuf,
+    venv_python: Option<PathBuf>,
+    venv_marker: Option<VenvMarkerInfo>,
+    rye_managed: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct VenvMarkerInfo {
+    python: PythonVersion,
+    venv_path: Option<PathBuf>,
+}
+
+fn show_python(pyproject: Option<&Path>, format: Option<Format>) -> Result<(), Error> {
+    let project = PyProject::load_or_discover(pyproject)?;
+    let request = project.python_version_request();
+    let toolchain = project.venv_python_version().ok();
+
+    let (toolchain_kind, toolchain_path) = match toolchain {
+        Some(ref ver) => {
+            let canonical = get_canonical_py_path(ver)?;
+            let kind = if is_in_shared_toolchain_dir(&canonical) {
+                "shared"
+            } else if canonical.is_dir() {
+                "rye-managed"
+            } else if canonical.is_file() {
+                "registered"
+            } else {
+                "not installed"
+            };
+            let path = get_toolchain_python_bin(ver).ok().filter(|x| x.is_file());
+            (kind, path)
+        }
+        None => ("unknown", None),
+    };
+
+    let venv = project.venv_path().into_owned();
+    let venv_python = Some(get_venv_python_bin(&venv)).filter(|x| x.is_file());
+    let marker = read_venv_marker(&venv);
+
+    if let Some(Format::Json) = format {
+        let info = PythonInfo {
+            requested: request.as_ref().map(|(ver, _)| ver.to_string()),
+            requested_from: request.as_ref().map(|(_, source)| {
+                match source {
+                    PythonVersionSource::PyprojectPin => "tool-rye-python",
+                    PythonVersionSource::PinFile(_) => "python-version-file",
+                    PythonVersionSource::RequiresPython => "requires-python",
+                    PythonVersionSource::ConfigDefault => "config-default",
+                }
+                .to_string()
+            }),
+            requested_from_path: match request {
+                Some((_, PythonVersionSource::PinFile(ref path))) => Some(path.clone()),
+                Some((
+                    _,
+                    PythonVersionSource::PyprojectPin | PythonVersionSource::RequiresPython,
+                )) => Some(project.toml_path().into_owned()),
+                Some((_, PythonVersionSource::ConfigDefault)) => {
+                    Some(Config::current().path().to_path_buf())
+                }
+                None => None,
+            },
+            toolchain,
+            toolchain_kind,
+            toolchain_path,
+            venv,
+            venv_python,
+            venv_marker: marker.map(|x| VenvMarkerInfo {
+                python: x.python,
+                venv_path: x.venv_path,
+            }),
+            rye_managed: project.rye_managed(),
+        };
+        echo!("{}", serde_json::to_string_pretty(&info)?);
+        return Ok(());
+    }
+
+    match request {
+        Some((ver, source)) => echo!(
+            "requested: {} (from {})",
+            style(ver).cyan(),
+            style(source).dim()
+        ),
+        None => echo!("requested: {}", style("nothing").red()),
+    }
+    match toolchain {
+        Some(ref ver) => echo!(
+            "toolchain: {} ({})",
+            style(ver).cyan(),
+            style(toolchain_kind).yellow()
+        ),
+        None => echo!("toolchain: {}", style("unknown").red()),
+    }
+    if let Some(path) = toolchain_path {
+        echo!("toolchain interpreter: {}", style(path.display()).cyan());
+    }
+    echo!("venv: {}", style(venv.display()).cyan());
+    match venv_python {
+        Some(path) => echo!("venv interpreter: {}", style(path.display()).cyan()),
+        None => echo!("venv interpreter: {}", style("not created").red()),
+    }
+    if let Some(marker) = marker {
+        echo!("venv marker:");
+        echo!("  python: {}", style(&marker.python).cyan());
+        if let Some(path) = marker.venv_path {
+            echo!("  venv path: {}", style(path.display()).cyan());
+        }
+        if toolchain.as_ref().map_or(false, |x| x != &marker.python) {
+            echo!(
+                "  {}",
+                style("the venv was created with a different toolchain, run `rye sync`").red()
+            );
+        }
+    }
+    echo!("rye managed: {}", style(project.rye_managed()).cyan());
+
+    Ok(())
+}
+
+fn show_diff_python(
+    pyproject: Option<&Path>,
+    version: &str,
+    keyring_provider: KeyringProvider,
+) -> Result<(), Error> {
+    let project = PyProject::load_or_discover(pyproject)?;
+    let req: PythonVersionRequest = version
+        .parse()
+        .with_context(|| format!("'{}' is not a valid version", version))?;
+    let mut problems = false;
+
+    echo!(
+        "python: {} -> {}",
+        style(project.venv_python_version()?).cyan(),
+        style(&req).cyan()
+    );
+
+    let installed = list_known_toolchains()?
+        .into_iter()
+        .map(|(ver, _)| ver)
+        .filter(|ver| matches_version(&req, ver))
+        .max();
+    let target = match installed {
+        Some(ver) => {
+            echo!(
+                "interpreter: {} ({})",
+                style(&ver).cyan(),
+                style("installed").green()
+            );
+            ver
+        }
+        None => match get_download_url(&req) {
+            Some((ver, _, _)) => {
+                echo!(
+                    "interpreter: {} ({})",
+                    style(&ver).cyan(),
+                    style("downloadable").green()
+                );
+                ver
+            }
+            None => {
+                echo!(
+                    "interpreter: {}",
+                    style("not available for this platform").red()
+                );
+                problems = true;
+                version.parse()?
+            }
+        },
+    };
+    let target_version = Version::from(target.clone());
+    let target_simple = format!("{}.{}", target.major, target.minor);
+
+    if let Some(requires_python) = project.requires_python() {
+        if requires_python.contains(&target_version) {
+            echo!("requires-python: {}", style(&requires_python).cyan());
+        } else {
+            echo!(
+                "requires-python: {} {}",
+                style(&requires_python).cyan(),
+                style(format!("(excludes {})", target_simple)).red()
+            );
+            problems = true;
+        }
+    }
+
+    // resolve from the current lockfiles, so that only what has to change moves
+    let toml_path = project.toml_path().into_owned();
+    let lockfiles = match preview_lockfiles_for_python(
+        || PyProject::load_or_discover(Some(&toml_path)),
+        &target,
+        CommandOutput::Normal,
+        keyring_provider,
+    ) {
+        Ok(previews) => previews
+            .into_iter()
+            .map(|x| (x.mode, x.path, x.before, Ok(x.after)))
+            .collect::<Vec<_>>(),
+        Err(err) => {
+            problems = true;
+            let mut rv = Vec::new();
+            for mode in [LockMode::Production, LockMode::Dev] {
+                let path = project.lockfile_path(mode);
+                if path.is_file() {
+                    let before =
+                        fs::read_to_string(&path).path_context(&path, "could not read lockfile")?;
+                    rv.push((mode, path, before, Err(format!("{:#}", err))));
+                }
+            }
+            rv
+        }
+    };
+    if lockfiles.is_empty() {
+        echo!("no lockfiles to compare, run `rye lock` first");
+    }
+
+    let sources =
+        ExpandedSources::from_sources_relative_to(&project.sources()?, &project.workspace_path())?;
+    let mut handle = curl::easy::Easy::new();
+    let mut pages = HashMap::new();
+
+    for (mode, path, before, after) in lockfiles {
+        echo!("{} ({}):", style(path.display()).cyan(), mode);
+        let before = parse_locked_versions(&before);
+        let after = after.map(|x| parse_locked_versions(&x));
+
+        match after {
+            Ok(ref after) => {
+                let names = before.keys().chain(after.keys()).collect::<BTreeSet<_>>();
+                let pinned = |ver: &Option<Version>| {
+                    ver.as_ref()
+                        .map_or_else(|| "(unpinned)".to_string(), |x| x.to_string())
+                };
+                let mut changes = Vec::new();
+                for name in names {
+                    match (before.get(name), after.get(name)) {
+                        (Some(old), Some(new)) if old != new => changes.push(format!(
+                            "{} {} {} -> {}",
+                            style("  ~").yellow(),
+                            name,
+                            pinned(old),
+                            pinned(new)
+                        )),
+                        (Some(old), None) => {
+                            changes.push(format!("{} {} {}", style("  -").red(), name, pinned(old)))
+                        }
+                        (None, Some(new)) => changes.push(format!(
+                            "{} {} {}",
+                            style("  +").green(),
+                            name,
+                            pinned(new)
+                        )),
+                        _ => {}
+                    }
+                }
+                if changes.is_empty() {
+                    echo!("  resolution: no changes");
+                } else {
+                    echo!("  resolution:");
+                    for change in changes {
+                        echo!("  {}", change);
+                    }
+                }
+            }
+            Err(ref err) => echo!("  resolution: {}", style(format!("failed: {}", err)).red()),
+        }
+
+        // the resolved versions are checked for wheels, the current ones for
+        // their requires-python which explains why they have to change.
+        let mut excluding = Vec::new();
+        for (name, ver) in sorted_versions(&before) {
+            let excluded_by = locked_files(&mut pages, &mut handle, &sources, name, ver)
+                .into_iter()
+                .find_map(|x| {
+                    x.requires_python
+                        .as_deref()?
+                        .parse::<VersionSpecifiers>()
+                        .ok()
+                })
+                .filter(|x| !x.contains(&target_version));
+            if let Some(specifiers) = excluded_by {
+                excluding.push(format!("{}=={} ({})", name, ver, specifiers));
+            }
+        }
+        let mut without_wheels = Vec::new();
+        if target.name == "cpython" {
+            for (name, ver) in sorted_versions(after.as_ref().unwrap_or(&before)) {
+                let wheels = locked_files(&mut pages, &mut handle, &sources, name, ver)
+                    .into_iter()
+                    .filter(|x| x.filename.ends_with(".whl"))
+                    .collect::<Vec<_>>();
+                // packages without any wheels are already built from source today
+                if !wheels.is_empty()
+                    && !wheels
+                        .iter()
+                        .any(|x| wheel_supports_python(&x.filename, target.major, target.minor))
+                {
+                    without_wheels.push(format!("{}=={}", name, ver));
+                }
+            }
+        }
+
+        if !without_wheels.is_empty() {
+            echo!("  no wheels for {} (built from source):", target_simple);
+            for package in without_wheels {
+                echo!("    - {}", style(package).yellow());
+            }
+        }
+        if !excluding.is_empty() {
+            echo!("  requires-python excludes {}:", target_simple);
+            for package in excluding {
+                echo!("    - {}", style(package).yellow());
+            }
+        }
+    }
+
+    if problems {
+        return Err(QuietExit(1).into());
+    }
+    Ok(())
+}
+
+/// Returns the pinned versions of a lockfile sorted by name.
+fn sorted_versions(versions: &HashMap<String, Option<Version>>) -> Vec<(&str, &Version)> {
+    let mut rv = versions
+        .iter()
+        .filter_map(|(name, ver)| Some((name.as_str(), ver.as_ref()?)))
+        .collect::<Vec<_>>();
+    rv.sort();
+    rv
+}
+
+/// Looks up the files of a locked version on the package indexes.
+///
+/// Pages are cached by package name, packages that cannot be looked up have
+/// no files.
+fn locked_files<'p>(
+    pages: &'p mut HashMap<String, Option<ProjectPage>>,
+    handle: &mut curl::easy::Easy,
+    sources: &ExpandedSources,
+    name: &str,
+    version: &Version,
+) -> Vec<&'p ProjectFile> {
+    let page: &'p Option<ProjectPage> = pages
+        .entry(name.to_string())
+        .or_insert_with(|| find_project_page(handle, sources, name).ok().flatten());
+    page.iter()
+        .flat_map(|x| &x.files)
+        .filter(|x| filename_version(&x.filename).as_ref() == Some(version))
+        .collect()
+}
+
+/// The layer a setting comes from.
+///
+/// Settings of `config.toml` come from the defaults, the global config or the
+/// environment, in order of increasing precedence.  Settings below `tool.rye`
+/// come from the defaults or the project or workspace root, depending on the
+/// scope of the setting.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum Layer {
+    Default,
+    Global,
+    Workspace,
+    Project,
+    Env,
+}
+
+impl fmt::Display for Layer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Layer::Default => write!(f, "default"),
+            Layer::Global => write!(f, "global"),
+            Layer::Workspace => write!(f, "workspace"),
+            Layer::Project => write!(f, "project"),
+            Layer::Env => write!(f, "env"),
+        }
+    }
+}
+
+/// Output structure for show config
+#[derive(Serialize, Debug)]
+struct ResolvedSetting {
+    key: String,
+    value: serde_json::Value,
+    layer: Layer,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    env: Option<&'static str>,
+}
+
+/// Converts the TOML default of a setting to JSON.
+fn default_to_json(default: Option<&str>) -> serde_json::Value {
+    default
+        .and_then(|x| x.parse::<Value>().ok())
+        .map_or(serde_json::Value::Null, |x| value_to_json(Some(&x)))
+}
+
+/// A parsed TOML file that remembers where its settings are defined.
+struct SettingsFile {
+    layer: Layer,
+    path: PathBuf,
+    doc: ImDocument<String>,
+}
+
+impl SettingsFile {
+    fn load(layer: Layer, path: &Path) -> Result<Option<SettingsFile>, Error> {
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let contents = fs::read_to_string(path).path_context(path, "failed to read settings")?;
+        let doc = ImDocument::parse(contents).path_context(path, "failed to parse settings")?;
+        Ok(Some(SettingsFile {
+            layer,
+            path: path.to_path_buf(),
+            doc,
+        }))
+    }
+
+    /// Looks up a dotted key and returns it as resolved setting.
+    fn resolve(&self, key: &str, name: &str) -> Option<ResolvedSetting> {
+        let (parent, last) = key.rsplit_once('.').unwrap_or(("", key));
+        let mut table: &dyn TableLike = self.doc.as_table();
+        for piece in parent.split('.').filter(|x| !x.is_empty()) {
+            table = table.get(piece)?.as_table_like()?;
+        }
+        let (key, item) = table.get_key_value(last)?;
+        Some(ResolvedSetting {
+            key: name.to_string(),
+            value: item_to_json(item),
+            layer: self.layer,
+            path: Some(self.path.clone()),
+            line: key
+                .span()
+                .map(|span| self.doc.raw()[..span.start].matches('\n').count() + 1),
+            env: None,
+        })
+    }
+}
+
+fn item_to_json(item: &Item) -> serde_json::Value {
+    match item {
+        Item::Value(value) => value_to_json(Some(value)),
+        Item::Table(table) => serde_json::Value::Object(
+            table
+                .iter()
+                .map(|(k, v)| (k.to_string(), item_to_json(v)))
+                .collect(),
+        ),
+        Item::ArrayOfTables(tables) => serde_json::Value::Array(
+            tables
+                .iter()
+                .map(|x| item_to_json(&Item::Table(x.clone())))
+                .collect(),
+        ),
+        Item::None => serde_json::Value::Null,
+    }
+}
+
+/// Secrets can be embedded in URLs, never print them.
+fn mask_secrets(value: serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => serde_json::Value::String(mask_url_credentials(&s).into()),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.into_iter().map(mask_secrets).collect())
+        }
+        serde_json::Value::Object(items) => serde_json::Value::Object(
+            items
+                .into_iter()
+                .map(|(k, v)| {
+                    let v = if k == "password" {
+                        "***".into()
+                    } else {
+                        mask_secrets(v)
+                    };
+                    (k, v)
+                })
+                .collect(),
+        ),
+        other => other,
+    }
+}
+
+fn show_config(
+    pyproject: Option<&Path>,
+    resolved: bool,
+    format: Option<Format>,
+) -> Result<(), Error> {
+    let project = match pyproject {
+        Some(path) => Some(PyProject::load(path)?),
+        None => PyProject::discover().ok(),
+    };
+    let config = Config::current();
+    let global = SettingsFile::load(Layer::Global, config.path())?;
+    let mut rv = Vec::new();
+
+    for setting in CONFIG_SETTINGS {
+        let from_env = setting
+            .env
+            .iter()
+            .find_map(|name| Some((*name, env::var(name).ok()?)));
+        let resolved_setting = if let Some((name, value)) = from_env {
+            Some(ResolvedSetting {
+                key: setting.key.to_string(),
+                value: serde_json::Value::String(value),
+                layer: Layer::Env,
+                path: None,
+                line: None,
+                env: Some(name),
+            })
+        } else {
+            global
+                .as_ref()
+                .and_then(|x| x.resolve(setting.key, setting.key))
+        };
+        match resolved_setting {
+            Some(resolved_setting) => rv.push(resolved_setting),
+            None if resolved => rv.push(ResolvedSetting {
+                key: setting.key.to_string(),
+                value: default_to_json(setting.default),
+                layer: Layer::Default,
+                path: None,
+                line: None,
+                env: None,
+            }),
+            None => {}
+        }
+    }
+
+    if let Some(ref project) = project {
+        let project_file = SettingsFile::load(Layer::Project, &project.toml_path())?;
+        let workspace_file = match project.workspace() {
+            Some(workspace) if !project.is_workspace_root() => {
+                SettingsFile::load(Layer::Workspace, &workspace.path().join("pyproject.toml"))?
+            }
+            _ => None,
+        };
+        for setting in SETTINGS {
+            let name = format!("tool.rye.{}", setting.key);
+            // same precedence as the accessors on `PyProject`: workspace wide
+            // settings of members are only ever read from the workspace root.
+            let files = match setting.scope {
+                SettingScope::Project => [project_file.as_ref(), None],
+                SettingScope::Workspace => {
+                    [workspace_file.as_ref().or(project_file.as_ref()), None]
+                }
+                SettingScope::Inherited => [project_file.as_ref(), workspace_file.as_ref()],
+            };
+            match files.iter().flatten().find_map(|x| x.resolve(&name, &name)) {
+                Some(resolved_setting) => rv.push(resolved_setting),
+                // `tool.rye.load-dotenv` falls back to `behavior.load-dotenv`
+                None if resolved && setting.key == "load-dotenv" => {
+                    let fallback =
+                        rv.iter()
+                            .find(|x| x.key == "behavior.load-dotenv")
+                            .map(|fallback| ResolvedSetting {
+                                key: name,
+                                value: fallback.value.clone(),
+                                layer: fallback.layer,
+                                path: fallback.path.clone(),
+                                line: fallback.line,
+                                env: fallback.env,
+                            });
+                    rv.extend(fallback);
+                }
+                None if resolved => rv.push(ResolvedSetting {
+                    key: name,
+                    value: default_to_json(setting.default),
+                    layer: Layer::Default,
+                    path: None,
+                    line: None,
+                    env: None,
+                }),
+                None => {}
+            }
+        }
+    }
+
+    for setting in &mut rv {
+        setting.value = if setting.key.ends_with(".password") && !setting.value.is_null() {
+            "***".into()
+        } else {
+            mask_secrets(setting.value.take())
+        };
+    }
+
+    if let Some(Format::Json) = format {
+        echo!("{}", serde_json::to_string_pretty(&rv)?);
+        return Ok(());
+    }
+
+    for setting in rv {
+        let value = match setting.value {
+            serde_json::Value::Null => "(unset)".to_string(),
+            serde_json::Value::String(s) => s,
+            other => other.to_string(),
+        };
+        let origin = match (setting.path, setting.line, setting.env) {
+            (_, _, Some(env)) => format!("{}: {}", setting.layer, env),
+            (Some(path), Some(line), _) => {
+                format!("{}: {}:{}", setting.layer, path.display(), line)
+            }
+            (Some(path), None, _) => format!("{}: {}", setting.layer, path.display()),
+            (None, _, _) => setting.layer.to_string(),
+        };
+        echo!(
+            "{} = {}  {}",
+            style(setting.key).cyan(),
+            value,
+            style(format!("({})", origin)).dim()
+        );
+    }
+    Ok(())
+}
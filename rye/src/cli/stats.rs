@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use anyhow::Error;
+use clap::Parser;
+use clap::ValueEnum;
+use console::style;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::stats::{clear_invocations, load_invocations, summarize};
+
+/// Shows locally recorded usage statistics.
+///
+/// Statistics are only recorded if `behavior.local-stats` is enabled in the
+/// config.  They are stored in the Rye home folder and never leave the machine.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// Remove all recorded statistics.
+    #[arg(long)]
+    clear: bool,
+    /// Request parseable output format
+    #[arg(long)]
+    format: Option<Format>,
+}
+
+#[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
+#[value(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+enum Format {
+    Json,
+}
+
+pub fn execute(cmd: Args) -> Result<(), Error> {
+    if cmd.clear {
+        clear_invocations()?;
+        echo!("Cleared local statistics");
+        return Ok(());
+    }
+
+    let invocations = load_invocations()?;
+    let summary = summarize(&invocations);
+
+    if let Some(Format::Json) = cmd.format {
+        serde_json::to_writer_pretty(std::io::stdout().lock(), &summary)?;
+        echo!();
+        return Ok(());
+    }
+
+    if !Config::current().local_stats() {
+        warn!(
+            category = Config,
+            "local statistics are disabled. Enable with `rye config --set-bool behavior.local-stats=true`"
+        );
+    }
+
+    if summary.is_empty() {
+        echo!("No invocations recorded");
+        return Ok(());
+    }
+
+    let mut rows = summary.into_iter().collect::<Vec<_>>();
+    rows.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total_ms));
+
+    echo!(
+        "{:<12} {:>6} {:>9} {:>10} {:>10} {:>10}",
+        style("command").bold(),
+        style("runs").bold(),
+        style("failed").bold(),
+        style("total").bold(),
+        style("avg").bold(),
+        style("max").bold(),
+    );
+    for (command, stats) in rows {
+        echo!(
+            "{:<12} {:>6} {:>8.1}% {:>10} {:>10} {:>10}",
+            style(command).cyan(),
+            stats.invocations,
+            stats.failure_rate(),
+            format_duration(stats.total_ms),
+            format_duration(stats.average_ms()),
+            format_duration(stats.max_ms),
+        );
+    }
+
+    Ok(())
+}
+
+fn format_duration(ms: u64) -> String {
+    let duration = Duration::from_millis(ms);
+    if duration.as_secs() >= 60 {
+        format!(
+            "{}m{:02}s",
+            duration.as_secs() / 60,
+            duration.as_secs() % 60
+        )
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
@@ -1,11 +1,24 @@
 use std::path::PathBuf;
 
 use anyhow::Error;
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use console::style;
+use serde::Serialize;
 
-use crate::lock::{KeyringProvider, LockOptions};
-use crate::sync::{sync, SyncMode, SyncOptions};
-use crate::utils::CommandOutput;
+use crate::lock::{KeyringProvider, LockMode, LockOptions};
+use crate::pyproject::PyProject;
+use crate::sync::{
+    sync, sync_plan, LockfileStatus, PackageAction, SyncMode, SyncOptions, SyncPlan,
+};
+use crate::tui::ensure_interactive;
+use crate::utils::{tui_theme, CommandOutput, QuietExit};
+
+#[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
+#[value(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+enum Format {
+    Json,
+}
 
 /// Updates the virtualenv based on the pyproject.toml
 #[derive(Parser, Debug)]
@@ -13,12 +26,24 @@ pub struct Args {
     /// Force the environment to be re-created
     #[arg(short, long)]
     force: bool,
+    /// Delete and re-create the virtualenv, reusing the pinned Python and the lockfiles.
+    #[arg(long)]
+    reinstall: bool,
+    /// Reinstall a specific package even if it is already installed.
+    #[arg(long, value_name = "PACKAGE")]
+    reinstall_package: Vec<String>,
+    /// Skip prompts.
+    #[arg(short, long)]
+    yes: bool,
     /// Do not include dev dependencies.
     #[arg(long)]
     no_dev: bool,
     /// Do not update the lockfile.
     #[arg(long)]
     no_lock: bool,
+    /// Install from the `vendor` folder without network access (implies --no-lock).
+    #[arg(long, conflicts_with_all = ["update", "update_all", "refresh_yanked", "deny_yanked"])]
+    offline: bool,
     /// Enables verbose diagnostics.
     #[arg(short, long)]
     verbose: bool,
@@ -55,23 +80,48 @@ pub struct Args {
     /// Do not reuse (reset) prior lock options.
     #[arg(long)]
     reset: bool,
+    /// Move off yanked versions pinned in the lockfile.
+    #[arg(long, conflicts_with = "no_lock")]
+    refresh_yanked: bool,
+    /// Fail if the lockfile contains yanked versions.
+    #[arg(long, conflicts_with = "no_lock")]
+    deny_yanked: bool,
     /// Use universal lock files
     #[arg(long)]
     universal: bool,
+    /// Compile Python files to bytecode after installation.
+    #[arg(long)]
+    compile_bytecode: bool,
+    /// Only regenerate the dev lockfile, leaving the production lockfile untouched.
+    #[arg(long, conflicts_with_all = ["only_prod", "no_lock"])]
+    only_dev: bool,
+    /// Only regenerate the production lockfile, leaving the dev lockfile untouched.
+    #[arg(long, conflicts_with = "no_lock")]
+    only_prod: bool,
+    /// Print what would be done without modifying the lockfiles or the virtualenv.
+    #[arg(long)]
+    dry_run: bool,
+    /// Request parseable output format for the dry run.
+    #[arg(long, requires = "dry_run")]
+    format: Option<Format>,
+    /// Extra argument passed to uv as is (unsupported, use at your own risk).
+    #[arg(long = "uv-arg", value_name = "ARG", allow_hyphen_values = true)]
+    uv_args: Vec<String>,
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
-    sync(SyncOptions {
+    let (dry_run, format) = (cmd.dry_run, cmd.format);
+    let options = SyncOptions {
         output,
         dev: !cmd.no_dev,
-        mode: if cmd.force {
+        mode: if cmd.force || cmd.reinstall {
             SyncMode::Full
         } else {
             SyncMode::Regular
         },
-        force: cmd.force,
-        no_lock: cmd.no_lock,
+        force: cmd.force || cmd.reinstall,
+        no_lock: cmd.no_lock || cmd.offline,
         lock_options: LockOptions {
             update: cmd.update,
             update_all: cmd.update_all,
@@ -82,9 +132,115 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
             reset: cmd.reset,
             generate_hashes: cmd.generate_hashes,
             universal: cmd.universal,
+            refresh_yanked: cmd.refresh_yanked,
+            deny_yanked: cmd.deny_yanked,
+            uv_args: cmd.uv_args,
+            ..Default::default()
         },
         keyring_provider: cmd.keyring_provider,
         pyproject: cmd.pyproject,
-    })?;
+        compile_bytecode: cmd.compile_bytecode,
+        only: if cmd.only_dev {
+            Some(LockMode::Dev)
+        } else if cmd.only_prod {
+            Some(LockMode::Production)
+        } else {
+            None
+        },
+        reinstall_packages: cmd.reinstall_package,
+        offline: cmd.offline,
+    };
+
+    if dry_run {
+        let plan = sync_plan(&options)?;
+        match format {
+            None => print_plan(&plan),
+            Some(Format::Json) => echo!("{}", serde_json::to_string_pretty(&plan)?),
+        }
+        return Ok(());
+    }
+
+    if cmd.reinstall && !cmd.yes {
+        let venv = PyProject::load_or_discover(options.pyproject.as_deref())?.venv_path();
+        if venv.is_dir() {
+            ensure_interactive("pass --yes to re-create the virtualenv without confirmation")?;
+            if !dialoguer::Confirm::with_theme(tui_theme())
+                .with_prompt(format!(
+                    "Do you want to delete and re-create the virtualenv at {}?",
+                    style(venv.display()).cyan()
+                ))
+                .interact()?
+            {
+                elog!("Reinstall cancelled!");
+                return Err(QuietExit(1).into());
+            }
+        }
+    }
+
+    sync(options)?;
     Ok(())
 }
+
+fn print_plan(plan: &SyncPlan) {
+    echo!(
+        "Virtualenv {} would be {}",
+        style(plan.venv.display()).cyan(),
+        plan.venv_action
+    );
+
+    echo!("Lockfiles:");
+    for lockfile in &plan.lockfiles {
+        echo!(
+            "  {}: {} ({})",
+            lockfile.mode,
+            lockfile.path.display(),
+            lockfile.status
+        );
+    }
+
+    echo!("Packages (from {}):", plan.install_from.display());
+    let install_status = plan
+        .lockfiles
+        .iter()
+        .find(|x| x.path == plan.install_from)
+        .map(|x| x.status);
+    match install_status {
+        Some(LockfileStatus::Missing) => {
+            echo!("  unknown until the lockfile is generated");
+        }
+        Some(status) if status.regenerates() => {
+            echo!(
+                "  {}",
+                style("based on the current lockfile, versions may change when it is regenerated")
+                    .dim()
+            );
+        }
+        _ => {
+            if plan.packages.is_empty() {
+                echo!("  no changes");
+            }
+        }
+    }
+    for change in &plan.packages {
+        let from = change.from.as_deref().unwrap_or_default();
+        let to = change.to.as_deref().unwrap_or_default();
+        match change.action {
+            PackageAction::Install => echo!("  {} {} {}", style("+").green(), change.name, to),
+            PackageAction::Update => echo!(
+                "  {} {} {} -> {}",
+                style("~").yellow(),
+                change.name,
+                from,
+                to
+            ),
+            PackageAction::Remove => echo!("  {} {} {}", style("-").red(), change.name, from),
+        }
+    }
+
+    if !plan.editables.is_empty() {
+        echo!("Editables (rebuilt):");
+        for editable in &plan.editables {
+            echo!("  {}", editable);
+        }
+    }
+}
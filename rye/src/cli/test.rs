@@ -1,19 +1,23 @@
 use std::env::consts::EXE_EXTENSION;
 use std::ffi::OsString;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::{env, fs};
 
-use anyhow::{bail, Error};
+use anyhow::{bail, Context, Error};
 use clap::Parser;
 use console::style;
 use same_file::is_same_file;
+use serde::Serialize;
 
 use crate::config::Config;
 use crate::consts::VENV_BIN;
 use crate::lock::KeyringProvider;
 use crate::pyproject::{locate_projects, normalize_package_name, DependencyKind, PyProject};
 use crate::sync::autosync;
-use crate::utils::{CommandOutput, QuietExit};
+use crate::tui::is_interactive;
+use crate::utils::junit::{count_tests, merge_reports, TestCounts};
+use crate::utils::{set_dotenv_variables, tui_theme, CommandOutput, IoPathContext, QuietExit};
 
 /// Run the tests on the project.
 ///
@@ -32,6 +36,17 @@ pub struct Args {
     /// Disable test output capture to stdout.
     #[arg(long = "no-capture", short = 's')]
     no_capture: bool,
+    /// Write a JUnit XML report to this path.
+    ///
+    /// When testing multiple packages their reports are merged into one file.
+    #[arg(long, value_name = "PATH")]
+    junit_xml: Option<PathBuf>,
+    /// Write a JSON summary of the test counts per package to this path.
+    #[arg(long, value_name = "PATH")]
+    json_summary: Option<PathBuf>,
+    /// Add pytest as dev dependency and sync if it's missing.
+    #[arg(long)]
+    install_missing: bool,
     /// Enables verbose diagnostics.
     #[arg(short, long)]
     verbose: bool,
@@ -56,6 +71,21 @@ pub struct Args {
     keyring_provider: KeyringProvider,
 }
 
+/// Test results of a single package for `--json-summary`.
+#[derive(Serialize, Debug)]
+struct PackageSummary {
+    name: String,
+    exit_code: i32,
+    #[serde(flatten)]
+    counts: TestCounts,
+}
+
+#[derive(Serialize, Debug)]
+struct Summary {
+    packages: Vec<PackageSummary>,
+    totals: TestCounts,
+}
+
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
     let project = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
@@ -81,6 +111,7 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         .join("pytest")
         .with_extension(EXE_EXTENSION);
 
+    let root_path = project.root_path().to_path_buf();
     let projects = locate_projects(project, cmd.all, &cmd.package[..])?;
 
     if !pytest.is_file() {
@@ -94,15 +125,41 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
                     cmd.with_sources,
                     cmd.generate_hashes,
                     cmd.keyring_provider,
+                    Vec::new(),
                 )?;
             } else {
                 bail!("pytest not installed but in dependencies. Run `rye sync`.")
             }
+        } else if cmd.install_missing
+            || (output != CommandOutput::Quiet
+                && is_interactive()
+                && dialoguer::Confirm::with_theme(tui_theme())
+                    .with_prompt(format!(
+                        "pytest is not installed. Add it with {} and sync?",
+                        style("rye add --dev pytest").cyan()
+                    ))
+                    .default(true)
+                    .interact()?)
+        {
+            install_pytest(&root_path, output)?;
         } else {
-            bail!("pytest not installed. Run `rye add --dev pytest`");
+            bail!(
+                "pytest is not installed and not a dev dependency of the project. \
+                 Run `rye add --dev pytest` or pass --install-missing."
+            );
+        }
+        if !pytest.is_file() {
+            bail!("pytest is still not installed after syncing");
         }
     }
 
+    // pytest runs in the project folder, so make the report paths absolute first.
+    let cwd = env::current_dir()?;
+    let junit_xml = cmd.junit_xml.map(|x| cwd.join(x));
+    let json_summary = cmd.json_summary.map(|x| cwd.join(x));
+    let report_dir = tempfile::tempdir().context("failed to create temporary directory")?;
+    let mut reports = Vec::new();
+
     for (idx, project) in projects.iter().enumerate() {
         if output != CommandOutput::Quiet {
             if idx > 0 {
@@ -128,11 +185,21 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
                 pytest_cmd.arg("-q");
             }
         }
+        let name = project.name().unwrap_or("unknown").to_string();
+        let report_path = report_dir.path().join(format!("{}.xml", idx));
+        if junit_xml.is_some() || json_summary.is_some() {
+            pytest_cmd
+                .arg("--junit-xml")
+                .arg(&report_path)
+                .arg("-o")
+                .arg(format!("junit_suite_name={}", name));
+        }
         pytest_cmd.args(&cmd.extra_args);
         pytest_cmd
             .arg("--rootdir")
             .arg(project.root_path().as_os_str())
             .current_dir(project.root_path());
+        set_dotenv_variables(&mut pytest_cmd, &project.dotenv_files(), true)?;
 
         // always ignore projects that are nested but not selected.
         for path in &project_roots {
@@ -145,6 +212,43 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         if !status.success() {
             failed_with = Some(status.code().unwrap_or(1));
         }
+
+        // a report might be missing if pytest failed before running tests
+        if junit_xml.is_some() || json_summary.is_some() {
+            let report = fs::read_to_string(&report_path).unwrap_or_default();
+            reports.push((name, report, status.code().unwrap_or(1)));
+        }
+    }
+
+    if let Some(path) = junit_xml {
+        let contents = match &reports[..] {
+            [(_, report, _)] => report.clone(),
+            _ => merge_reports(
+                &reports
+                    .iter()
+                    .map(|(name, report, _)| (name.clone(), report.clone()))
+                    .collect::<Vec<_>>(),
+            ),
+        };
+        fs::write(&path, contents).path_context(&path, "failed to write JUnit XML report")?;
+    }
+
+    if let Some(path) = json_summary {
+        let mut totals = TestCounts::default();
+        let packages = reports
+            .into_iter()
+            .map(|(name, report, exit_code)| {
+                let counts = count_tests(&report);
+                totals.add(&counts);
+                PackageSummary {
+                    name,
+                    exit_code,
+                    counts,
+                }
+            })
+            .collect();
+        let summary = serde_json::to_string_pretty(&Summary { packages, totals })?;
+        fs::write(&path, summary).path_context(&path, "failed to write JSON summary")?;
     }
 
     if let Some(code) = failed_with {
@@ -154,6 +258,29 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
     }
 }
 
+/// Adds pytest as dev dependency by running `rye add --dev pytest --sync`.
+fn install_pytest(root_path: &Path, output: CommandOutput) -> Result<(), Error> {
+    let rye = env::current_exe().context("unable to determine rye executable")?;
+    let mut add_cmd = Command::new(rye);
+    add_cmd
+        .args(["add", "--dev", "pytest", "--sync"])
+        .current_dir(root_path);
+    match output {
+        CommandOutput::Quiet => {
+            add_cmd.arg("--quiet");
+        }
+        CommandOutput::Verbose => {
+            add_cmd.arg("--verbose");
+        }
+        CommandOutput::Normal => {}
+    }
+    let status = add_cmd.status().context("failed to run rye add")?;
+    if !status.success() {
+        bail!("failed to add pytest as dev dependency");
+    }
+    Ok(())
+}
+
 /// Does any of those projects have a pytest dependency?
 fn has_pytest_dependency(projects: &[PyProject]) -> Result<bool, Error> {
     for project in projects {
@@ -12,11 +12,19 @@ use console::style;
 use serde::Deserialize;
 use serde::Serialize;
 
-use crate::installer::list_installed_tools;
-use crate::platform::{get_app_dir, get_canonical_py_path, list_known_toolchains};
-use crate::pyproject::read_venv_marker;
-use crate::sources::py::{iter_downloadable, PythonVersion};
-use crate::utils::{symlink_file, IoPathContext};
+use crate::bootstrap::{fetch, find_changed_toolchain_files, FetchOptions};
+use crate::installer::{list_installed_tools, rebuild_tool};
+use crate::platform::{
+    get_app_dir, get_canonical_py_path, get_toolchain_folders, get_toolchain_python_bin,
+    is_in_shared_toolchain_dir, list_known_toolchains,
+};
+use crate::pyproject::{read_venv_marker, DiscoveryUnsuccessful, PyProject};
+use crate::sources::py::{
+    get_download_url, iter_downloadable, PythonVersion, PythonVersionRequest,
+};
+use crate::sync::VenvMarker;
+use crate::tui::is_interactive;
+use crate::utils::{symlink_file, tui_theme, CommandOutput, IoPathContext};
 
 const INSPECT_SCRIPT: &str = r#"
 import json
@@ -29,6 +37,20 @@ print(json.dumps({
 }))
 "#;
 
+const PROBE_SCRIPT: &str = r#"
+import json
+import platform
+import encodings, zlib, ssl, sqlite3, ctypes
+print(json.dumps({
+    "python_version": platform.python_version(),
+}))
+"#;
+
+#[derive(Debug, Deserialize)]
+struct ProbeInfo {
+    python_version: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct InspectInfo {
     python_implementation: String,
@@ -65,6 +87,9 @@ pub struct RemoveCommand {
     /// Force removal even if the toolchain is in use.
     #[arg(short, long)]
     force: bool,
+    /// Rebuild the tools that used the toolchain against a compatible one without asking.
+    #[arg(long, requires = "force")]
+    rebuild_tools: bool,
 }
 
 /// List all registered toolchains
@@ -73,11 +98,36 @@ pub struct ListCommand {
     /// Also include non installed, but downloadable toolchains
     #[arg(long)]
     include_downloadable: bool,
+    /// Only list toolchains for which a newer patch release can be downloaded.
+    #[arg(long, conflicts_with = "include_downloadable")]
+    check_updates: bool,
+    /// Fetch the newest patch release of outdated toolchains.
+    #[arg(long, requires = "check_updates")]
+    upgrade: bool,
+    /// Also update the `.python-version` pin of the current project if it
+    /// pins an upgraded toolchain.
+    #[arg(long, requires = "upgrade")]
+    update_pins: bool,
     /// Request parseable output format
     #[arg(long)]
     format: Option<Format>,
 }
 
+/// Verifies the integrity of installed toolchains.
+///
+/// The files of fetched toolchains are compared against the checksums recorded
+/// when they were fetched, every toolchain is run with a probe script that
+/// imports a few core modules and on Linux is checked for missing shared
+/// libraries.
+#[derive(Parser, Debug)]
+pub struct VerifyCommand {
+    /// Name and version of the toolchain.  If not provided all toolchains are verified.
+    version: Option<String>,
+    /// Re-fetch downloaded toolchains that fail verification.
+    #[arg(long)]
+    repair: bool,
+}
+
 #[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
 #[value(rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
@@ -91,6 +141,19 @@ enum SubCommand {
     List(ListCommand),
     Register(RegisterCommand),
     Remove(RemoveCommand),
+    Verify(VerifyCommand),
+}
+
+impl Args {
+    /// Does the command write into the toolchain folder?
+    pub fn is_mutating(&self) -> bool {
+        match self.command {
+            SubCommand::Fetch(ref args) => args.is_mutating(),
+            SubCommand::List(ref cmd) => cmd.upgrade,
+            SubCommand::Register(_) | SubCommand::Remove(_) => true,
+            SubCommand::Verify(ref cmd) => cmd.repair,
+        }
+    }
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
@@ -99,6 +162,7 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
         SubCommand::Fetch(args) => crate::cli::fetch::execute(args),
         SubCommand::List(args) => list(args),
         SubCommand::Remove(args) => remove(args),
+        SubCommand::Verify(args) => verify(args),
     }
 }
 
@@ -151,11 +215,25 @@ fn check_in_use(ver: &PythonVersion) -> Result<(), Error> {
 pub fn remove(cmd: RemoveCommand) -> Result<(), Error> {
     let ver: PythonVersion = cmd.version.parse()?;
     let path = get_canonical_py_path(&ver)?;
+    if is_in_shared_toolchain_dir(&path) {
+        bail!(
+            "toolchain {} is in the shared toolchain store ({}) and cannot be removed",
+            ver,
+            path.display()
+        );
+    }
 
     if !cmd.force && path.exists() {
         check_in_use(&ver)?;
     }
 
+    // tools built against the toolchain are orphaned by the removal
+    let orphaned = if path.exists() {
+        tools_using_toolchain(&ver)?
+    } else {
+        Vec::new()
+    };
+
     if path.is_file() {
         fs::remove_file(&path).path_context(&path, "failed to remove toolchain link")?;
         echo!("Removed toolchain link {}", &ver);
@@ -165,6 +243,219 @@ pub fn remove(cmd: RemoveCommand) -> Result<(), Error> {
     } else {
         echo!("Toolchain is not installed");
     }
+
+    if !orphaned.is_empty() {
+        rebuild_orphaned_tools(&ver, orphaned, cmd.rebuild_tools)?;
+    }
+    Ok(())
+}
+
+/// Returns the installed tools whose virtualenv uses the given toolchain.
+fn tools_using_toolchain(ver: &PythonVersion) -> Result<Vec<(String, VenvMarker)>, Error> {
+    let mut rv = list_installed_tools()?
+        .into_iter()
+        .filter_map(|(tool, info)| {
+            info.venv_marker
+                .filter(|marker| &marker.python == ver)
+                .map(|marker| (tool, marker))
+        })
+        .collect::<Vec<_>>();
+    rv.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(rv)
+}
+
+/// Offers to rebuild the tools that were using a removed toolchain.
+fn rebuild_orphaned_tools(
+    ver: &PythonVersion,
+    orphaned: Vec<(String, VenvMarker)>,
+    rebuild: bool,
+) -> Result<(), Error> {
+    let interactive = is_interactive();
+    for (tool, marker) in orphaned {
+        if !rebuild {
+            let confirmed = interactive
+                && dialoguer::Confirm::with_theme(tui_theme())
+                    .with_prompt(format!(
+                        "Tool {} was using {}. Rebuild it against a compatible toolchain?",
+                        style(&tool).cyan(),
+                        ver
+                    ))
+                    .default(true)
+                    .interact()?;
+            if !confirmed {
+                warn!(
+                    "tool {} was using toolchain {} and no longer works, reinstall it with `rye install --force {}`",
+                    tool,
+                    ver,
+                    marker.tool.as_ref().map_or(tool.as_str(), |x| x.requirement.as_str())
+                );
+                continue;
+            }
+        }
+        match rebuild_tool(&tool, &marker, CommandOutput::Quiet) {
+            Ok(_) => echo!("Rebuilt tool {}", style(&tool).cyan()),
+            Err(err) => warn!("failed to rebuild tool {}: {:#}", tool, err),
+        }
+    }
+    Ok(())
+}
+
+/// Returns all toolchains in the toolchain folder, including broken ones.
+fn list_toolchain_versions() -> Result<Vec<PythonVersion>, Error> {
+    let mut rv = Vec::new();
+    for folder in get_toolchain_folders() {
+        let Ok(iter) = folder.read_dir() else {
+            continue;
+        };
+        for entry in iter {
+            let entry = entry?;
+            if let Ok(ver) = entry.file_name().to_string_lossy().parse::<PythonVersion>() {
+                rv.push(ver);
+            }
+        }
+    }
+    rv.sort();
+    rv.dedup();
+    Ok(rv)
+}
+
+/// Checks a single toolchain and returns the list of detected problems.
+fn verify_toolchain(ver: &PythonVersion) -> Result<Vec<String>, Error> {
+    let toolchain_dir = get_canonical_py_path(ver)?;
+    match get_toolchain_python_bin(ver) {
+        Ok(py_bin) => Ok(check_toolchain(ver, &toolchain_dir, &py_bin)),
+        Err(err) => Ok(vec![format!("{:#}", err)]),
+    }
+}
+
+/// Checks the toolchain in `toolchain_dir` with the interpreter `py_bin`.
+///
+/// Failures to inspect the toolchain are reported as problems so that a
+/// single broken toolchain does not stop the others from being verified.
+fn check_toolchain(ver: &PythonVersion, toolchain_dir: &Path, py_bin: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    // registered toolchains are files, only fetched ones have recorded checksums
+    if toolchain_dir.is_dir() {
+        match find_changed_toolchain_files(toolchain_dir) {
+            Ok(Some(changed)) => {
+                for (kind, rel_path) in changed {
+                    problems.push(format!("{} file {}", kind, rel_path));
+                }
+            }
+            Ok(None) => {}
+            Err(err) => problems.push(format!("could not verify checksums: {:#}", err)),
+        }
+    }
+
+    if !py_bin.is_file() {
+        problems.push(format!("interpreter {} does not exist", py_bin.display()));
+        return problems;
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        match crate::bootstrap::find_missing_shared_libraries(py_bin) {
+            Ok(missing) if !missing.is_empty() => {
+                problems.push(format!("missing shared libraries: {}", missing.join(", ")));
+            }
+            Ok(_) => {}
+            Err(err) => problems.push(format!("could not check shared libraries: {:#}", err)),
+        }
+    }
+
+    let output = match Command::new(py_bin).arg("-c").arg(PROBE_SCRIPT).output() {
+        Ok(output) => output,
+        Err(err) => {
+            problems.push(format!("unable to run interpreter: {}", err));
+            return problems;
+        }
+    };
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        problems.push(format!(
+            "probe script failed: {}",
+            stderr.lines().last().unwrap_or("unknown error")
+        ));
+        return problems;
+    }
+
+    match serde_json::from_slice::<ProbeInfo>(&output.stdout) {
+        Ok(info) => {
+            let expected = format!("{}.{}.{}", ver.major, ver.minor, ver.patch);
+            if info.python_version != expected {
+                problems.push(format!(
+                    "interpreter reports version {} instead of {}",
+                    info.python_version, expected
+                ));
+            }
+        }
+        Err(err) => problems.push(format!("could not parse interpreter output: {}", err)),
+    }
+
+    problems
+}
+
+fn verify(cmd: VerifyCommand) -> Result<(), Error> {
+    let versions = match cmd.version {
+        Some(ref version) => vec![version.parse::<PythonVersion>()?],
+        None => list_toolchain_versions()?,
+    };
+
+    let mut failed = 0;
+    for ver in versions {
+        let problems = verify_toolchain(&ver)?;
+        if problems.is_empty() {
+            echo!("{} {}", style(&ver).green(), style("ok").dim());
+            continue;
+        }
+
+        echo!(
+            "{} {}",
+            style(&ver).red(),
+            style("failed verification").red()
+        );
+        for problem in &problems {
+            echo!("  - {}", problem);
+        }
+
+        if !cmd.repair {
+            failed += 1;
+            continue;
+        }
+
+        // registered toolchains are links to interpreters rye does not manage
+        if get_canonical_py_path(&ver)?.is_file() {
+            warn!("{} is a registered toolchain and cannot be repaired", ver);
+            failed += 1;
+        } else if get_download_url(&ver.clone().into()).is_none() {
+            warn!("{} is not downloadable and cannot be repaired", ver);
+            failed += 1;
+        } else {
+            fetch(
+                &ver.clone().into(),
+                FetchOptions {
+                    force: true,
+                    ..FetchOptions::with_output(CommandOutput::Normal)
+                },
+            )
+            .with_context(|| format!("failed to re-fetch toolchain {}", ver))?;
+            if verify_toolchain(&ver)?.is_empty() {
+                echo!("Repaired {}", style(&ver).green());
+            } else {
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        bail!(
+            "{} toolchain{} failed verification",
+            failed,
+            if failed == 1 { "" } else { "s" }
+        );
+    }
+
     Ok(())
 }
 
@@ -189,6 +480,10 @@ fn secondary_architectures() -> &'static [&'static str] {
 }
 
 fn list(cmd: ListCommand) -> Result<(), Error> {
+    if cmd.check_updates {
+        return check_updates(cmd);
+    }
+
     let mut toolchains = list_known_toolchains()?
         .into_iter()
         .map(|(version, path)| (version, Some(path)))
@@ -235,6 +530,156 @@ fn list(cmd: ListCommand) -> Result<(), Error> {
     Ok(())
 }
 
+/// Output structure for toolchain list --check-updates --format=json
+#[derive(Serialize)]
+struct ToolchainUpdate {
+    name: PythonVersion,
+    latest: PythonVersion,
+}
+
+/// Returns the newest downloadable patch release for every installed
+/// toolchain that is outdated.
+///
+/// Registered toolchains are not managed by Rye and are never reported.
+fn find_patch_updates() -> Result<Vec<(PythonVersion, PythonVersion)>, Error> {
+    let mut rv = Vec::new();
+    for (version, _) in list_known_toolchains()? {
+        if get_canonical_py_path(&version)?.is_file() {
+            continue;
+        }
+        let candidates = iter_downloadable(&version.os, &version.arch);
+        if let Some(latest) = newest_patch_release(&version, candidates) {
+            rv.push((version, latest));
+        }
+    }
+    rv.sort();
+    Ok(rv)
+}
+
+/// Picks the newest patch release of the same minor version (and variant)
+/// that is newer than the given version.
+fn newest_patch_release(
+    version: &PythonVersion,
+    candidates: impl Iterator<Item = PythonVersion>,
+) -> Option<PythonVersion> {
+    candidates
+        .filter(|x| {
+            x.name == version.name
+                && x.major == version.major
+                && x.minor == version.minor
+                && x.suffix == version.suffix
+                && x.patch > version.patch
+        })
+        .max()
+}
+
+fn check_updates(cmd: ListCommand) -> Result<(), Error> {
+    let updates = find_patch_updates()?;
+
+    if let Some(Format::Json) = cmd.format {
+        let json_updates = updates
+            .iter()
+            .map(|(name, latest)| ToolchainUpdate {
+                name: name.clone(),
+                latest: latest.clone(),
+            })
+            .collect::<Vec<_>>();
+        serde_json::to_writer_pretty(std::io::stdout().lock(), &json_updates)?;
+        echo!();
+    } else if updates.is_empty() {
+        echo!("All toolchains are up to date");
+    } else {
+        for (version, latest) in &updates {
+            echo!(
+                "{} {} {}",
+                style(version).yellow(),
+                style("->").dim(),
+                style(latest).green()
+            );
+        }
+    }
+
+    if !cmd.upgrade {
+        return Ok(());
+    }
+
+    let output = if cmd.format.is_some() {
+        CommandOutput::Quiet
+    } else {
+        CommandOutput::Normal
+    };
+    for (_, latest) in &updates {
+        fetch(&latest.clone().into(), FetchOptions::with_output(output))
+            .with_context(|| format!("failed to fetch toolchain {}", latest))?;
+    }
+
+    if cmd.update_pins {
+        update_pin(&updates, output)?;
+    }
+
+    Ok(())
+}
+
+/// Updates the `.python-version` file of the current project if it pins
+/// one of the upgraded toolchains with its full version.
+///
+/// Relaxed pins (such as `3.12`) already pick up new patch releases and are
+/// left alone.
+fn update_pin(
+    updates: &[(PythonVersion, PythonVersion)],
+    output: CommandOutput,
+) -> Result<(), Error> {
+    let version_file = match PyProject::discover() {
+        Ok(project) => project.root_path().join(".python-version"),
+        Err(err) if err.is::<DiscoveryUnsuccessful>() => {
+            std::env::current_dir()?.join(".python-version")
+        }
+        Err(err) => return Err(err),
+    };
+    let Ok(contents) = fs::read_to_string(&version_file) else {
+        return Ok(());
+    };
+
+    let mut seen_version = false;
+    let mut updated = false;
+    let mut new_contents = String::new();
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        let mut line = line.to_string();
+        if !seen_version && !trimmed.is_empty() && !trimmed.starts_with('#') {
+            if let Ok(req) = trimmed.parse::<PythonVersionRequest>() {
+                let upgrade = updates.iter().find(|(old, _)| {
+                    req.name.as_deref().unwrap_or("cpython") == old.name
+                        && req.arch.as_ref().map_or(true, |x| x == &old.arch)
+                        && req.os.as_ref().map_or(true, |x| x == &old.os)
+                        && req.major == old.major
+                        && req.minor == Some(old.minor)
+                        && req.patch == Some(old.patch)
+                        && req.suffix == old.suffix
+                });
+                if let Some((old, new)) = upgrade {
+                    line = line.replace(
+                        &format!("{}.{}.{}", old.major, old.minor, old.patch),
+                        &format!("{}.{}.{}", new.major, new.minor, new.patch),
+                    );
+                    updated = true;
+                }
+            }
+            // only the first version in the file is relevant
+            seen_version = true;
+        }
+        new_contents.push_str(&line);
+        new_contents.push('\n');
+    }
+
+    if updated {
+        fs::write(&version_file, new_contents)
+            .path_context(&version_file, "failed to write .python-version file")?;
+        echo!(if output, "Updated pin in {}", version_file.display());
+    }
+    Ok(())
+}
+
 pub fn register_toolchain<F>(
     path: &Path,
     name: Option<&str>,
@@ -305,3 +750,61 @@ where
 
     Ok(target_version)
 }
+
+#[test]
+fn test_check_broken_toolchain() {
+    let dir = tempfile::tempdir().unwrap();
+    let py_bin = crate::platform::get_python_bin_within(dir.path());
+    fs::create_dir_all(py_bin.parent().unwrap()).unwrap();
+    // a truncated download leaves an interpreter that cannot be executed
+    fs::write(&py_bin, "").unwrap();
+    fs::write(
+        dir.path().join(crate::bootstrap::INSTALL_RECORD),
+        r#"{"url": "https://example.com/python.tar.gz", "sha256": "", "files": {"lib/os.py": "00"}}"#,
+    )
+    .unwrap();
+
+    let ver: PythonVersion = "cpython@3.12.1".parse().unwrap();
+    let problems = check_toolchain(&ver, dir.path(), &py_bin);
+    assert_eq!(problems.first().unwrap(), "missing file lib/os.py");
+    assert!(problems
+        .last()
+        .unwrap()
+        .starts_with("unable to run interpreter"));
+}
+
+#[test]
+fn test_newest_patch_release() {
+    let version = |s: &str| s.parse::<PythonVersion>().unwrap();
+    let candidates = || {
+        [
+            "cpython@3.11.9",
+            "cpython@3.12.1",
+            "cpython@3.12.4",
+            "cpython@3.12.8",
+            "cpython@3.13.0",
+            "pypy@3.12.9",
+        ]
+        .into_iter()
+        .map(version)
+    };
+
+    assert_eq!(
+        newest_patch_release(&version("cpython@3.12.1"), candidates()),
+        Some(version("cpython@3.12.8"))
+    );
+    // the newest release is never an update of itself
+    assert_eq!(
+        newest_patch_release(&version("cpython@3.12.8"), candidates()),
+        None
+    );
+    // a newer minor version is not a patch update
+    assert_eq!(
+        newest_patch_release(&version("cpython@3.11.9"), candidates()),
+        None
+    );
+    assert_eq!(
+        newest_patch_release(&version("pypy@3.12.1"), candidates()),
+        Some(version("pypy@3.12.9"))
+    );
+}
@@ -1,8 +1,11 @@
-use anyhow::Error;
+use anyhow::{bail, Error};
 use clap::Parser;
 use console::style;
 
-use crate::installer::list_installed_tools;
+use crate::bootstrap::ensure_self_venv;
+use crate::cli::install::{run_parallel, ToolAction};
+use crate::installer::{list_installed_tools, list_shims, lock_tool, upgrade_tool, ShimOwner};
+use crate::utils::CommandOutput;
 
 /// Helper utility to manage global tools.
 #[derive(Parser, Debug)]
@@ -22,19 +25,85 @@ pub struct ListCommand {
     include_version: bool,
 }
 
+/// Show which tool provides a shim.
+///
+/// Without an executable all shims are listed with the tool that provides them.
+#[derive(Parser, Debug)]
+pub struct WhichCommand {
+    /// The name of the executable.
+    executable: Option<String>,
+}
+
+/// Write the lockfiles of tools from their installed packages (without resolving again).
+#[derive(Parser, Debug)]
+pub struct LockCommand {
+    /// The tools to lock.
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    names: Vec<String>,
+    /// Lock all installed tools.
+    #[arg(short, long)]
+    all: bool,
+}
+
+/// Upgrade tools to the latest versions that satisfy their requirements.
+///
+/// The tools are installed again with the requirements, Python version and
+/// included dependencies they were installed with.  Multiple tools are
+/// upgraded in parallel.
+#[derive(Parser, Debug)]
+pub struct UpgradeCommand {
+    /// The tools to upgrade.
+    #[arg(required_unless_present = "all", conflicts_with = "all")]
+    names: Vec<String>,
+    /// Upgrade all installed tools.
+    #[arg(short, long)]
+    all: bool,
+    /// How many tools to upgrade in parallel (defaults to the number of CPUs).
+    #[arg(short, long)]
+    jobs: Option<usize>,
+    /// Extra argument passed to uv as is (unsupported, use at your own risk).
+    #[arg(long = "uv-arg", value_name = "ARG", allow_hyphen_values = true)]
+    uv_args: Vec<String>,
+    /// Enables verbose diagnostics.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Turns off all output.
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
 #[derive(Parser, Debug)]
 #[allow(clippy::large_enum_variant)]
 enum SubCommand {
     Install(crate::cli::install::Args),
+    Upgrade(UpgradeCommand),
     Uninstall(crate::cli::uninstall::Args),
     List(ListCommand),
+    Which(WhichCommand),
+    Lock(LockCommand),
+}
+
+impl Args {
+    /// Does the command write into the tool folder?
+    pub fn is_mutating(&self) -> bool {
+        matches!(
+            self.command,
+            SubCommand::Install(_)
+                | SubCommand::Upgrade(_)
+                | SubCommand::Uninstall(_)
+                | SubCommand::Lock(_)
+        )
+    }
 }
 
 pub fn execute(cmd: Args) -> Result<(), Error> {
     match cmd.command {
         SubCommand::Install(args) => crate::cli::install::execute(args),
+        SubCommand::Upgrade(args) => upgrade(args),
         SubCommand::Uninstall(args) => crate::cli::uninstall::execute(args),
         SubCommand::List(args) => list_tools(args),
+        SubCommand::Which(args) => which(args),
+        SubCommand::Lock(args) => lock(args),
     }
 }
 
@@ -66,3 +135,66 @@ fn list_tools(cmd: ListCommand) -> Result<(), Error> {
 
     Ok(())
 }
+
+fn which(cmd: WhichCommand) -> Result<(), Error> {
+    let shims = list_shims()?;
+
+    if let Some(executable) = cmd.executable {
+        match shims.get(&executable) {
+            Some(owner) => echo!("{}", format_owner(owner)),
+            None => bail!("no shim named {} is installed", executable),
+        }
+        return Ok(());
+    }
+
+    let width = shims.keys().map(|x| x.len()).max().unwrap_or(0);
+    for (shim, owner) in &shims {
+        echo!("{:<width$}  {}", style(shim).cyan(), format_owner(owner));
+    }
+    Ok(())
+}
+
+fn lock(cmd: LockCommand) -> Result<(), Error> {
+    let mut names = if cmd.all {
+        list_installed_tools()?.into_keys().collect()
+    } else {
+        cmd.names
+    };
+    names.sort();
+
+    for name in names {
+        let lockfile = lock_tool(&name)?;
+        echo!("Locked {} ({})", style(&name).cyan(), lockfile.display());
+    }
+    Ok(())
+}
+
+fn upgrade(cmd: UpgradeCommand) -> Result<(), Error> {
+    let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+    let mut names = if cmd.all {
+        list_installed_tools()?.into_keys().collect()
+    } else {
+        cmd.names
+    };
+    names.sort();
+
+    // the internal virtualenv is shared by all tools, make sure it exists
+    // before upgrading in parallel.
+    ensure_self_venv(output)?;
+    if let [name] = &names[..] {
+        upgrade_tool(name, output, &cmd.uv_args)?;
+        return Ok(());
+    }
+    let tools = names.into_iter().map(|x| (x.clone(), x)).collect();
+    run_parallel(tools, cmd.jobs, ToolAction::Upgrade, output, |name| {
+        upgrade_tool(&name, CommandOutput::Quiet, &cmd.uv_args)
+    })
+}
+
+fn format_owner(owner: &ShimOwner) -> String {
+    match owner {
+        ShimOwner::Tool(tool) => tool.clone(),
+        ShimOwner::Rye => style("rye").dim().to_string(),
+        ShimOwner::Unknown => style("unknown").yellow().to_string(),
+    }
+}
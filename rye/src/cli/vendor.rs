@@ -0,0 +1,82 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Error};
+use clap::Parser;
+use console::style;
+
+use crate::lock::{update_build_requirements_lockfile, KeyringProvider, LockMode, LockOptions};
+use crate::pyproject::{ExpandedSources, PyProject};
+use crate::utils::CommandOutput;
+use crate::vendor::{vendor_lockfiles, VENDOR_DIR};
+
+/// Downloads all locked distributions into the `vendor` folder.
+///
+/// This includes the build requirements of the local projects and source
+/// distributions.  The vendored distributions can be installed without network
+/// access with `rye sync --offline`.
+#[derive(Parser, Debug)]
+pub struct Args {
+    /// Only vendor the distributions of the production lockfile.
+    #[arg(long)]
+    no_dev: bool,
+    /// Use this pyproject.toml file
+    #[arg(long, value_name = "PYPROJECT_TOML")]
+    pyproject: Option<PathBuf>,
+    /// Enables verbose diagnostics.
+    #[arg(short, long)]
+    verbose: bool,
+    /// Turns off all output.
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+}
+
+pub fn execute(cmd: Args) -> Result<(), Error> {
+    let output = CommandOutput::from_quiet_and_verbose(cmd.quiet, cmd.verbose);
+    let pyproject = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    let workspace_path = pyproject.workspace_path();
+
+    let lockfile = pyproject.lockfile_path(LockMode::Production);
+    let dev_lockfile = pyproject.lockfile_path(LockMode::Dev);
+    let mut lockfiles = Vec::new();
+    if lockfile.is_file() {
+        lockfiles.push(lockfile.as_path());
+    }
+    if !cmd.no_dev && dev_lockfile.is_file() {
+        lockfiles.push(dev_lockfile.as_path());
+    }
+    if lockfiles.is_empty() {
+        bail!("no lockfile found, run `rye lock` first");
+    }
+
+    let sources =
+        ExpandedSources::from_sources_relative_to(&pyproject.sources()?, &workspace_path)?;
+
+    // the build backends are needed to install the local projects offline
+    let scratch = tempfile::tempdir()?;
+    let build_lockfile = scratch.path().join("build-requirements.lock");
+    update_build_requirements_lockfile(
+        &pyproject.venv_python_version()?,
+        &pyproject,
+        &lockfiles,
+        &build_lockfile,
+        output.quieter(),
+        &sources,
+        &LockOptions::default(),
+        KeyringProvider::Disabled,
+    )
+    .context("failed to lock build requirements")?;
+    lockfiles.push(&build_lockfile);
+
+    let dir = workspace_path.join(VENDOR_DIR);
+    let summary = vendor_lockfiles(&lockfiles, &sources, &dir, output)?;
+
+    echo!(
+        if output,
+        "Vendored {} distribution(s) into {} ({} downloaded, {} removed)",
+        summary.files,
+        style(dir.display()).cyan(),
+        summary.downloaded,
+        summary.removed
+    );
+    Ok(())
+}
@@ -1,18 +1,46 @@
+use std::fs;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::time::SystemTime;
 
+use crate::provenance::format_timestamp;
 use crate::pyproject::PyProject;
+use crate::utils::changelog::add_release_section;
+use crate::utils::IoPathContext;
+use crate::vcs_version::{compute_vcs_version, sync_version_from_vcs};
 use anyhow::{anyhow, bail, Error};
-use clap::{Parser, ValueEnum};
+use clap::{Parser, Subcommand, ValueEnum};
 use pep440_rs::Version;
 
 /// Get or set project version
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct Args {
+    #[command(subcommand)]
+    command: Option<SubCommand>,
     /// The version to set
     version: Option<String>,
     /// The version bump to apply
     #[arg(short, long)]
     bump: Option<Bump>,
+    /// Add a section for the new version to this changelog.
+    ///
+    /// The entries of the "Unreleased" section are moved into it.
+    #[arg(long, value_name = "CHANGELOG")]
+    changelog: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum SubCommand {
+    SyncFromVcs(SyncFromVcsCommand),
+}
+
+/// Compute the version from the version control system and write it into the version file.
+#[derive(Parser, Debug)]
+pub struct SyncFromVcsCommand {
+    /// Only print the computed version.
+    #[arg(long)]
+    dry_run: bool,
 }
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -22,9 +50,29 @@ pub enum Bump {
     Patch,
 }
 
+impl Args {
+    /// Does the command write the version instead of only printing it?
+    pub fn is_mutating(&self) -> bool {
+        match self.command {
+            Some(SubCommand::SyncFromVcs(ref cmd)) => !cmd.dry_run,
+            None => self.version.is_some() || self.bump.is_some(),
+        }
+    }
+}
+
 pub fn execute(cmd: Args) -> Result<(), Error> {
     let mut pyproject_toml = PyProject::discover()?;
-    match cmd.version {
+    if let Some(SubCommand::SyncFromVcs(args)) = cmd.command {
+        return sync_from_vcs(&pyproject_toml, args);
+    }
+    if pyproject_toml.version_from_vcs() {
+        if cmd.version.is_some() || cmd.bump.is_some() || cmd.changelog.is_some() {
+            bail!("the version is computed from the version control system, tag a commit instead");
+        }
+        echo!("{}", compute_vcs_version(&pyproject_toml)?);
+        return Ok(());
+    }
+    let (version, message) = match cmd.version {
         Some(version) => {
             let version =
                 Version::from_str(&version).map_err(|msg| anyhow!("invalid version: {}", msg))?;
@@ -34,25 +82,62 @@ pub fn execute(cmd: Args) -> Result<(), Error> {
                 .contains(&"version".to_string())
             {
                 bail!("unsupported set dynamic version");
-            } else {
-                pyproject_toml.set_version(&version);
-                pyproject_toml.save()?;
-
-                echo!("version set to {}", version);
             }
+            (version, "version set to")
         }
         None => {
             let mut version = pyproject_toml.version()?;
             match cmd.bump {
-                Some(bump) => bump_version(&mut version, bump, &mut pyproject_toml)?,
-                None => echo!("{}", version),
+                Some(bump) => {
+                    bump_version(&mut version, bump);
+                    (version, "version bumped to")
+                }
+                None => {
+                    if cmd.changelog.is_some() {
+                        bail!("--changelog requires a version to set or --bump");
+                    }
+                    echo!("{}", version);
+                    return Ok(());
+                }
             }
         }
+    };
+
+    // the changelog is prepared first so that an existing section leaves
+    // both files untouched.
+    let changelog = match cmd.changelog {
+        Some(path) => {
+            let contents =
+                fs::read_to_string(&path).path_context(&path, "failed to read changelog")?;
+            let timestamp = format_timestamp(SystemTime::now());
+            let date = timestamp.split('T').next().unwrap_or_default();
+            let contents = add_release_section(&contents, &version.to_string(), date)?;
+            Some((path, contents))
+        }
+        None => None,
+    };
+
+    pyproject_toml.set_version(&version);
+    pyproject_toml.save()?;
+    if let Some((path, contents)) = changelog {
+        fs::write(&path, contents).path_context(&path, "failed to write changelog")?;
+    }
+
+    echo!("{} {}", message, version);
+    Ok(())
+}
+
+fn sync_from_vcs(pyproject_toml: &PyProject, cmd: SyncFromVcsCommand) -> Result<(), Error> {
+    if cmd.dry_run {
+        echo!("{}", compute_vcs_version(pyproject_toml)?);
+        return Ok(());
     }
+    let (version, path) = sync_version_from_vcs(pyproject_toml)?;
+    echo!("version synced to {} ({})", version, path.display());
     Ok(())
 }
 
-fn bump_version(version: &mut Version, bump: Bump, pyproject: &mut PyProject) -> Result<(), Error> {
+fn bump_version(version: &mut Version, bump: Bump) {
     if version.is_post() {
         version.post = None;
     }
@@ -69,11 +154,4 @@ fn bump_version(version: &mut Version, bump: Bump, pyproject: &mut PyProject) ->
             version.release[i] = 0;
         }
     }
-
-    pyproject.set_version(version);
-    pyproject.save().unwrap();
-
-    echo!("version bumped to {}", version);
-
-    Ok(())
 }
@@ -1,8 +1,8 @@
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Once};
 
-use anyhow::{Context, Error};
+use anyhow::{bail, Context, Error};
 use once_cell::sync::Lazy;
 use pep440_rs::Operator;
 use regex::Regex;
@@ -11,12 +11,89 @@ use toml_edit::DocumentMut;
 use crate::platform::{get_app_dir, get_latest_cpython_version};
 use crate::pyproject::{BuildSystem, SourceRef, SourceRefType};
 use crate::sources::py::PythonVersionRequest;
-use crate::utils::{toml, IoPathContext};
+use crate::utils::{expand_env_vars, toml, IoPathContext};
 
 static CONFIG: Mutex<Option<Arc<Config>>> = Mutex::new(None);
 static AUTHOR_REGEX: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^\s*(.*?)\s*<\s*(.*?)\s*>\s*$").unwrap());
 
+/// Which certificates are trusted for TLS connections.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TlsBackend {
+    /// The certificates each tool ships with.
+    Default,
+    /// The certificate store of the operating system.
+    Native,
+}
+
+/// A setting of the global `config.toml`.
+pub struct ConfigSetting {
+    /// The dotted key in `config.toml`.
+    pub key: &'static str,
+    /// Environment variables that take precedence over the config, in order.
+    pub env: &'static [&'static str],
+    /// The value used when the setting is not configured, as TOML.
+    pub default: Option<&'static str>,
+}
+
+const fn setting(key: &'static str, default: Option<&'static str>) -> ConfigSetting {
+    ConfigSetting {
+        key,
+        env: &[],
+        default,
+    }
+}
+
+/// The known settings of `config.toml` (used by `rye show config`).
+pub const CONFIG_SETTINGS: &[ConfigSetting] = &[
+    setting("default.requires-python", Some("\">= 3.8\"")),
+    setting("default.toolchain", None),
+    setting("default.build-system", None),
+    setting("default.license", None),
+    setting("default.author", None),
+    setting("default.dependency-operator", Some("\">=\"")),
+    setting("behavior.global-python", Some("false")),
+    setting("behavior.force-rye-managed", Some("false")),
+    setting("behavior.venv-mark-sync-ignore", Some("true")),
+    setting("behavior.autosync", Some("true")),
+    setting("behavior.parallel-lock", Some("false")),
+    setting("behavior.use-uv", Some("true")),
+    setting("behavior.fetch-with-build-info", Some("false")),
+    setting("behavior.local-stats", Some("false")),
+    setting("behavior.externally-managed-self", Some("false")),
+    setting("behavior.clone-worktree-venvs", Some("false")),
+    setting("behavior.ignore-active-venv", Some("false")),
+    setting("behavior.load-dotenv", Some("false")),
+    setting("behavior.tls-backend", Some("\"default\"")),
+    setting("behavior.check-yanked", Some("false")),
+    setting("tls.ca-bundle", None),
+    ConfigSetting {
+        key: "proxy.http",
+        env: &["http_proxy"],
+        default: None,
+    },
+    ConfigSetting {
+        key: "proxy.https",
+        env: &["HTTPS_PROXY", "https_proxy"],
+        default: None,
+    },
+    setting("proxy.username", None),
+    setting("proxy.password", None),
+    ConfigSetting {
+        key: "network.retries",
+        env: &["RYE_NETWORK_RETRIES"],
+        default: Some("0"),
+    },
+    ConfigSetting {
+        key: "network.timeout",
+        env: &["RYE_NETWORK_TIMEOUT"],
+        default: None,
+    },
+    setting("downloads-index.url", None),
+    setting("downloads-index.public-key", None),
+    setting("sources", None),
+];
+
 pub fn load() -> Result<(), Error> {
     let cfg_path = get_app_dir().join("config.toml");
     let cfg = if cfg_path.is_file() {
@@ -225,6 +302,144 @@ impl Config {
             })
     }
 
+    /// Returns the username and password for the proxy.
+    ///
+    /// `${VAR}` references are expanded from the environment so that the
+    /// credentials do not have to be stored in the config.
+    pub fn proxy_credentials(&self) -> Result<Option<(String, String)>, Error> {
+        let proxy = match self.doc.get("proxy") {
+            Some(proxy) => proxy,
+            None => return Ok(None),
+        };
+        let expand = |key: &str| -> Result<Option<String>, Error> {
+            let Some(value) = proxy.get(key).and_then(|x| x.as_str()) else {
+                return Ok(None);
+            };
+            let mut missing = None;
+            let rv = expand_env_vars(value, |name| {
+                std::env::var(name).ok().or_else(|| {
+                    missing.get_or_insert_with(|| name.to_string());
+                    None
+                })
+            });
+            if let Some(name) = missing {
+                bail!(
+                    "proxy.{} references environment variable {} which is not set",
+                    key,
+                    name
+                );
+            }
+            Ok(Some(rv.into_owned()))
+        };
+        Ok(match (expand("username")?, expand("password")?) {
+            (Some(username), password) => Some((username, password.unwrap_or_default())),
+            (None, _) => None,
+        })
+    }
+
+    /// Returns how often a failed uv invocation is retried.
+    ///
+    /// The `RYE_NETWORK_RETRIES` environment variable takes precedence.
+    pub fn network_retries(&self) -> u32 {
+        std::env::var("RYE_NETWORK_RETRIES")
+            .ok()
+            .and_then(|x| x.parse().ok())
+            .or_else(|| {
+                self.doc
+                    .get("network")
+                    .and_then(|x| x.get("retries"))
+                    .and_then(|x| x.as_integer())
+                    .and_then(|x| u32::try_from(x).ok())
+            })
+            .unwrap_or(0)
+    }
+
+    /// Returns the network timeout in seconds that uv should use.
+    ///
+    /// The `RYE_NETWORK_TIMEOUT` environment variable takes precedence.
+    pub fn network_timeout(&self) -> Option<u64> {
+        std::env::var("RYE_NETWORK_TIMEOUT")
+            .ok()
+            .and_then(|x| x.parse().ok())
+            .or_else(|| {
+                self.doc
+                    .get("network")
+                    .and_then(|x| x.get("timeout"))
+                    .and_then(|x| x.as_integer())
+                    .and_then(|x| u64::try_from(x).ok())
+            })
+    }
+
+    /// Check lockfiles for yanked versions after every lock?
+    pub fn check_yanked(&self) -> bool {
+        self.doc
+            .get("behavior")
+            .and_then(|x| x.get("check-yanked"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Load the `.env` files of projects that do not configure `load-dotenv`?
+    pub fn load_dotenv(&self) -> bool {
+        self.doc
+            .get("behavior")
+            .and_then(|x| x.get("load-dotenv"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Returns which certificates are trusted for TLS connections.
+    ///
+    /// Unknown values fall back to the default backend with a warning.
+    pub fn tls_backend(&self) -> TlsBackend {
+        let Some(value) = self.doc.get("behavior").and_then(|x| x.get("tls-backend")) else {
+            return TlsBackend::Default;
+        };
+        match value.as_str() {
+            Some("default") => TlsBackend::Default,
+            Some("native") => TlsBackend::Native,
+            _ => {
+                // the backend is looked up for every connection, warn only once
+                static WARNED: Once = Once::new();
+                WARNED.call_once(|| {
+                    warn!(
+                        category = Config,
+                        "ignoring invalid behavior.tls-backend {}, expected \"default\" or \"native\"",
+                        value.to_string().trim()
+                    )
+                });
+                TlsBackend::Default
+            }
+        }
+    }
+
+    /// Returns the CA bundle that is trusted instead of the default certificates.
+    pub fn ca_bundle(&self) -> Option<PathBuf> {
+        self.doc
+            .get("tls")
+            .and_then(|x| x.get("ca-bundle"))
+            .and_then(|x| x.as_str())
+            .map(PathBuf::from)
+    }
+
+    /// Returns the URL of the signed downloads index.
+    pub fn downloads_index_url(&self) -> Option<String> {
+        self.doc
+            .get("downloads-index")
+            .and_then(|x| x.get("url"))
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string())
+    }
+
+    /// Returns the hex encoded ed25519 public key the downloads index is signed with.
+    pub fn downloads_index_public_key(&self) -> Option<String> {
+        self.doc
+            .get("downloads-index")
+            .and_then(|x| x.get("public-key"))
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string())
+    }
+
     /// Returns the list of default sources.
     pub fn sources(&self) -> Result<Vec<SourceRef>, Error> {
         let mut rv = Vec::new();
@@ -261,6 +476,15 @@ impl Config {
             .unwrap_or(true)
     }
 
+    /// Generate the production and dev lockfiles concurrently?
+    pub fn parallel_lock(&self) -> bool {
+        self.doc
+            .get("behavior")
+            .and_then(|x| x.get("parallel-lock"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    }
+
     /// Indicates if uv should be used.
     ///
     /// This setting is deprecated, as pip-tools support was removed in Rye 0.40.
@@ -282,6 +506,45 @@ impl Config {
             .and_then(|x| x.as_bool())
             .unwrap_or(false)
     }
+
+    /// Record command invocations locally for `rye stats`.
+    pub fn local_stats(&self) -> bool {
+        self.doc
+            .get("behavior")
+            .and_then(|x| x.get("local-stats"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Is this Rye installation managed by a system package manager?
+    ///
+    /// If set, `rye self update` refuses to replace the executable.
+    pub fn externally_managed_self(&self) -> bool {
+        self.doc
+            .get("behavior")
+            .and_then(|x| x.get("externally-managed-self"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Clone the virtualenv of the main worktree when syncing a linked git worktree?
+    pub fn clone_worktree_venvs(&self) -> bool {
+        self.doc
+            .get("behavior")
+            .and_then(|x| x.get("clone-worktree-venvs"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Ignore activated virtualenvs and conda environments that are not the
+    /// virtualenv of the project?
+    pub fn ignore_active_venv(&self) -> bool {
+        self.doc
+            .get("behavior")
+            .and_then(|x| x.get("ignore-active-venv"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    }
 }
 
 #[cfg(test)]
@@ -382,6 +645,70 @@ author = "John Doe <john@example.com>""#,
         assert!(!cfg.venv_mark_sync_ignore());
     }
 
+    #[test]
+    fn test_behavior_flags() {
+        let flags: &[(&str, fn(&Config) -> bool)] = &[
+            ("global-python", Config::global_python),
+            ("force-rye-managed", Config::force_rye_managed),
+            ("venv-mark-sync-ignore", Config::venv_mark_sync_ignore),
+            ("autosync", Config::autosync),
+            ("parallel-lock", Config::parallel_lock),
+            ("use-uv", Config::use_uv),
+            ("fetch-with-build-info", Config::fetch_with_build_info),
+            ("local-stats", Config::local_stats),
+            ("externally-managed-self", Config::externally_managed_self),
+            ("clone-worktree-venvs", Config::clone_worktree_venvs),
+            ("ignore-active-venv", Config::ignore_active_venv),
+            ("load-dotenv", Config::load_dotenv),
+            ("check-yanked", Config::check_yanked),
+        ];
+        let (cfg_path, _temp_dir) = setup_config("");
+        let empty = Config::from_path(&cfg_path).expect("Failed to load config");
+        for (key, flag) in flags {
+            // the default matches the one that is documented in the settings
+            let default = CONFIG_SETTINGS
+                .iter()
+                .find(|x| x.key == format!("behavior.{}", key))
+                .and_then(|x| x.default)
+                .unwrap_or_else(|| panic!("behavior.{} has no default", key));
+            assert_eq!(flag(&empty).to_string(), default, "behavior.{}", key);
+
+            let (cfg_path, _temp_dir) =
+                setup_config(&format!("[behavior]\n{} = {}", key, !flag(&empty)));
+            let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+            assert_eq!(flag(&cfg), !flag(&empty), "behavior.{}", key);
+        }
+    }
+
+    #[test]
+    fn test_network_settings() {
+        let (cfg_path, _temp_dir) = setup_config("");
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(cfg.network_retries(), 0);
+        assert_eq!(cfg.network_timeout(), None);
+        let (cfg_path, _temp_dir) = setup_config("[network]\nretries = 3\ntimeout = 60");
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(cfg.network_retries(), 3);
+        assert_eq!(cfg.network_timeout(), Some(60));
+    }
+
+    #[test]
+    fn test_tls_settings() {
+        let (cfg_path, _temp_dir) = setup_config("");
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(cfg.tls_backend(), TlsBackend::Default);
+        assert_eq!(cfg.ca_bundle(), None);
+        let (cfg_path, _temp_dir) = setup_config(
+            "[behavior]\ntls-backend = 'native'\n[tls]\nca-bundle = '/etc/corp/ca.pem'",
+        );
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(cfg.tls_backend(), TlsBackend::Native);
+        assert_eq!(cfg.ca_bundle(), Some(PathBuf::from("/etc/corp/ca.pem")));
+        let (cfg_path, _temp_dir) = setup_config("[behavior]\ntls-backend = 'system'");
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(cfg.tls_backend(), TlsBackend::Default);
+    }
+
     #[test]
     fn test_http_proxy_url() {
         let (cfg_path, _temp_dir) = setup_config("[proxy]\nhttp = 'http://proxy.example.com'");
@@ -402,6 +729,48 @@ author = "John Doe <john@example.com>""#,
         );
     }
 
+    #[test]
+    fn test_proxy_credentials() {
+        let (cfg_path, _temp_dir) = setup_config("[proxy]\nhttps = 'https://proxy.example.com'");
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(cfg.proxy_credentials().unwrap(), None);
+
+        std::env::set_var("RYE_TEST_PROXY_PASSWORD", "s3cr3t");
+        let (cfg_path, _temp_dir) =
+            setup_config("[proxy]\nusername = 'user'\npassword = '${RYE_TEST_PROXY_PASSWORD}'");
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(
+            cfg.proxy_credentials().unwrap(),
+            Some(("user".to_string(), "s3cr3t".to_string()))
+        );
+
+        let (cfg_path, _temp_dir) =
+            setup_config("[proxy]\nusername = 'user'\npassword = '${RYE_TEST_PROXY_MISSING}'");
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(
+            cfg.proxy_credentials().unwrap_err().to_string(),
+            "proxy.password references environment variable RYE_TEST_PROXY_MISSING which is not set"
+        );
+    }
+
+    #[test]
+    fn test_downloads_index() {
+        let (cfg_path, _temp_dir) = setup_config("");
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(cfg.downloads_index_url(), None);
+        assert_eq!(cfg.downloads_index_public_key(), None);
+
+        let (cfg_path, _temp_dir) = setup_config(
+            "[downloads-index]\nurl = 'https://example.com/index.json'\npublic-key = 'abcd'",
+        );
+        let cfg = Config::from_path(&cfg_path).expect("Failed to load config");
+        assert_eq!(
+            cfg.downloads_index_url(),
+            Some("https://example.com/index.json".to_string())
+        );
+        assert_eq!(cfg.downloads_index_public_key(), Some("abcd".to_string()));
+    }
+
     #[test]
     fn test_sources_default_inclusion() {
         let (cfg_path, _temp_dir) = setup_config("");
@@ -411,4 +780,25 @@ author = "John Doe <john@example.com>""#,
             .iter()
             .any(|src| src.name == "default" && src.url == "https://pypi.org/simple/"));
     }
+
+    #[test]
+    fn test_config_settings_cover_read_keys() {
+        let mut keys = crate::schema::find_read_keys(
+            "src/config.rs",
+            r#"self\s*\.doc\s*\.get\("([a-z0-9-]+)"\)"#,
+        );
+        // read through a helper
+        keys.extend(["proxy.username".into(), "proxy.password".into()]);
+        assert!(keys.contains("behavior.autosync"));
+        assert!(keys.contains("default.requires-python"));
+        for key in keys {
+            assert!(
+                CONFIG_SETTINGS
+                    .iter()
+                    .any(|x| x.key == key || x.key.starts_with(&format!("{}.", key))),
+                "{} is read but missing in CONFIG_SETTINGS",
+                key
+            );
+        }
+    }
 }
@@ -0,0 +1,106 @@
+use std::path::Path;
+use std::process::Command;
+
+use anyhow::{bail, Context, Error};
+use serde::Deserialize;
+
+use crate::pyproject::PyProject;
+use crate::utils::{get_venv_python_bin, IoPathContext};
+
+/// Imports every entry point and reports the ones that cannot be resolved.
+///
+/// Importing user code might print to stdout, so the results are written to
+/// the original stdout only after all imports happened.
+const CHECK_SCRIPT: &str = r#"
+import importlib
+import json
+import sys
+
+out = sys.stdout
+sys.stdout = sys.stderr
+rv = []
+for name, target in json.loads(sys.argv[1]):
+    module, _, attrs = target.split("[", 1)[0].strip().partition(":")
+    try:
+        obj = importlib.import_module(module.strip())
+        for attr in filter(None, attrs.strip().split(".")):
+            obj = getattr(obj, attr)
+        if attrs.strip() and not callable(obj):
+            raise TypeError("%s is not callable" % attrs.strip())
+    except BaseException as e:
+        rv.append({"name": name, "target": target, "error": "%s: %s" % (type(e).__name__, e)})
+out.write(json.dumps(rv))
+"#;
+
+/// An entry point that failed to resolve in the virtualenv.
+#[derive(Debug, Deserialize)]
+pub struct BrokenEntryPoint {
+    pub name: String,
+    pub target: String,
+    pub error: String,
+}
+
+/// Validates the entry points of the given projects against a virtualenv.
+pub fn find_broken_entry_points(
+    venv: &Path,
+    projects: &[&PyProject],
+) -> Result<Vec<BrokenEntryPoint>, Error> {
+    let entry_points = projects
+        .iter()
+        .filter(|x| !x.is_virtual())
+        .flat_map(|x| x.entry_points())
+        .collect::<Vec<_>>();
+    if entry_points.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let py = get_venv_python_bin(venv);
+    let output = Command::new(&py)
+        .arg("-c")
+        .arg(CHECK_SCRIPT)
+        .arg(serde_json::to_string(&entry_points)?)
+        .output()
+        .path_context(&py, "unable to run interpreter")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!(
+            "entry point check failed: {}",
+            stderr.lines().last().unwrap_or("unknown error")
+        );
+    }
+    serde_json::from_slice(&output.stdout).context("could not parse entry point check output")
+}
+
+/// Warns about broken entry points after a sync.
+///
+/// Broken console scripts otherwise only surface when the package is
+/// published or installed elsewhere.  This imports user code, so sync only
+/// does it when `tool.rye.sync.check-entry-points` is enabled.
+pub fn warn_about_broken_entry_points(pyproject: &PyProject) {
+    let members;
+    let projects = match pyproject.workspace() {
+        Some(workspace) => {
+            members = workspace
+                .iter_projects()
+                .filter_map(|x| x.ok())
+                .collect::<Vec<_>>();
+            members.iter().collect::<Vec<_>>()
+        }
+        None => vec![pyproject],
+    };
+
+    match find_broken_entry_points(&pyproject.venv_path(), &projects) {
+        Ok(broken) => {
+            for entry_point in broken {
+                warn!(
+                    category = Config,
+                    "entry point '{}' ({}) is broken: {}",
+                    entry_point.name,
+                    entry_point.target,
+                    entry_point.error
+                );
+            }
+        }
+        Err(err) => warn!("could not validate entry points: {}", err),
+    }
+}
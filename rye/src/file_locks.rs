@@ -0,0 +1,247 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::thread;
+use std::time::Duration;
+
+use anyhow::Error;
+use console::style;
+
+use crate::tui::is_interactive;
+use crate::utils::{
+    is_sharing_violation, rename_with_retry, tui_theme, CommandOutput, IoPathContext,
+};
+
+/// How often an operation on a virtualenv in use is retried without prompting.
+const LOCKED_RETRIES: u32 = 5;
+
+/// The suffix of virtualenvs that were moved aside because they were in use.
+const OLD_VENV_SUFFIX: &str = ".old";
+
+/// A process that keeps files of a virtualenv open.
+#[derive(Debug)]
+pub struct VenvProcess {
+    pub pid: u32,
+    pub exe: PathBuf,
+}
+
+/// Lists the processes that run an executable of a virtualenv.
+///
+/// On windows these keep the files of the virtualenv locked (for instance a
+/// `python.exe` in a terminal or started by an editor).
+#[cfg(windows)]
+pub fn find_venv_processes(venv: &Path) -> Vec<VenvProcess> {
+    crate::utils::windows::find_processes_in(venv)
+        .into_iter()
+        .map(|(pid, exe)| VenvProcess { pid, exe })
+        .collect()
+}
+
+/// Other platforms do not lock files that are in use.
+#[cfg(unix)]
+pub fn find_venv_processes(_venv: &Path) -> Vec<VenvProcess> {
+    Vec::new()
+}
+
+/// Is this error caused by files that are in use by another process?
+pub fn is_locked_error(err: &Error) -> bool {
+    err.chain()
+        .filter_map(|x| x.downcast_ref::<io::Error>())
+        .any(is_sharing_violation)
+}
+
+/// Runs an operation on a virtualenv and retries it while files of the
+/// virtualenv are in use by other processes.
+///
+/// The processes are named where they can be found.  When the user can be
+/// prompted, they are asked to close them and retry, otherwise the operation
+/// is retried with backoff a few times as the files are often released
+/// quickly (by virus scanners or processes about to exit).
+pub fn retry_while_locked<T>(
+    venv: &Path,
+    what: &str,
+    output: CommandOutput,
+    mut f: impl FnMut() -> Result<T, Error>,
+) -> Result<T, Error> {
+    let mut attempt = 0;
+    loop {
+        let err = match f() {
+            Ok(rv) => return Ok(rv),
+            Err(err) if is_locked_error(&err) => err,
+            Err(err) => return Err(err),
+        };
+
+        let processes = find_venv_processes(venv);
+        let users = if processes.is_empty() {
+            "another process (such as an editor, a shell with the virtualenv activated \
+             or a virus scanner)"
+                .to_string()
+        } else {
+            processes
+                .iter()
+                .map(|x| format!("{} (pid {})", x.exe.display(), x.pid))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        if output != CommandOutput::Quiet && is_interactive() {
+            elog!("The virtualenv is in use by {}", style(&users).yellow());
+            if dialoguer::Confirm::with_theme(tui_theme())
+                .with_prompt("Retry once the virtualenv is no longer in use?")
+                .default(true)
+                .interact()?
+            {
+                continue;
+            }
+        } else if attempt < LOCKED_RETRIES {
+            echo!(
+                if verbose output,
+                "virtualenv is in use by {}, retrying",
+                users
+            );
+            thread::sleep(Duration::from_millis(250 << attempt));
+            attempt += 1;
+            continue;
+        }
+
+        return Err(err.context(format!(
+            "{} failed because files of the virtualenv at {} are in use by {}. \
+             Close them and try again",
+            what,
+            venv.display(),
+            users
+        )));
+    }
+}
+
+/// Removes a virtualenv that is about to be recreated.
+///
+/// The virtualenv is renamed out of the way first so that the new virtualenv
+/// can be created in its place even if deleting it fails halfway.  If files
+/// of the renamed virtualenv are still in use, it is removed by
+/// [`remove_old_venvs`] once it was released.
+pub fn remove_venv(venv: &Path) -> Result<(), Error> {
+    let mut name = venv.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}{}", process::id(), OLD_VENV_SUFFIX));
+    let aside = venv.with_file_name(name);
+    rename_with_retry(venv, &aside)
+        .path_context(venv, "failed to move existing virtualenv aside")?;
+    match fs::remove_dir_all(&aside) {
+        Ok(()) => Ok(()),
+        Err(err) if is_sharing_violation(&err) => {
+            warn!(
+                category = Environment,
+                "the old virtualenv is still in use and was moved to {}, it is removed \
+                 by the next sync",
+                aside.display()
+            );
+            Ok(())
+        }
+        Err(err) => Err(err).path_context(&aside, "failed to delete old virtualenv"),
+    }
+}
+
+/// Is this the file name of a virtualenv that [`remove_venv`] moved aside?
+fn is_old_venv(venv_name: &str, file_name: &str) -> bool {
+    file_name
+        .strip_prefix(venv_name)
+        .and_then(|x| x.strip_prefix('.'))
+        .and_then(|x| x.strip_suffix(OLD_VENV_SUFFIX))
+        .map_or(false, |pid| {
+            !pid.is_empty() && pid.chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+/// Removes the virtualenvs that [`remove_venv`] moved aside, if they are no
+/// longer in use.
+pub fn remove_old_venvs(venv: &Path) {
+    let (Some(parent), Some(name)) = (venv.parent(), venv.file_name()) else {
+        return;
+    };
+    let name = name.to_string_lossy();
+    for entry in fs::read_dir(parent).into_iter().flatten().flatten() {
+        if is_old_venv(&name, &entry.file_name().to_string_lossy()) {
+            fs::remove_dir_all(entry.path()).ok();
+        }
+    }
+}
+
+#[test]
+fn test_is_old_venv() {
+    assert!(is_old_venv(".venv", ".venv.1234.old"));
+    assert!(!is_old_venv(".venv", ".venv"));
+    assert!(!is_old_venv(".venv", ".venv..old"));
+    assert!(!is_old_venv(".venv", ".venv.abc.old"));
+    assert!(!is_old_venv(".venv", ".venv.1234.old.bak"));
+    assert!(!is_old_venv(".venv", ".venv2.1234.old"));
+    assert!(!is_old_venv(".venv", "venv.1234.old"));
+}
+
+#[test]
+fn test_remove_old_venvs() {
+    let dir = tempfile::tempdir().unwrap();
+    let venv = dir.path().join(".venv");
+    for name in [
+        ".venv",
+        ".venv.1234.old",
+        ".venv.abc.old",
+        ".venv2.1234.old",
+    ] {
+        fs::create_dir(dir.path().join(name)).unwrap();
+    }
+    remove_old_venvs(&venv);
+    let mut left = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|x| x.unwrap().file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    left.sort();
+    assert_eq!(left, [".venv", ".venv.abc.old", ".venv2.1234.old"]);
+}
+
+#[test]
+fn test_remove_venv() {
+    let dir = tempfile::tempdir().unwrap();
+    let venv = dir.path().join(".venv");
+    fs::create_dir_all(venv.join("bin")).unwrap();
+    remove_venv(&venv).unwrap();
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_retry_while_locked_other_error() {
+    let mut calls = 0;
+    let rv: Result<(), Error> =
+        retry_while_locked(Path::new(".venv"), "testing", CommandOutput::Quiet, || {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound).into())
+        });
+    assert!(rv.is_err());
+    assert_eq!(calls, 1);
+}
+
+#[cfg(windows)]
+#[test]
+fn test_retry_while_locked_retries() {
+    // without a prompt the operation is retried until it succeeds
+    let mut calls = 0;
+    let rv = retry_while_locked(Path::new(".venv"), "testing", CommandOutput::Quiet, || {
+        calls += 1;
+        if calls < 3 {
+            Err(io::Error::from_raw_os_error(32).into())
+        } else {
+            Ok(calls)
+        }
+    });
+    assert_eq!(rv.unwrap(), 3);
+
+    // and gives up after a few retries
+    let mut calls = 0;
+    let rv: Result<(), Error> =
+        retry_while_locked(Path::new(".venv"), "testing", CommandOutput::Quiet, || {
+            calls += 1;
+            Err(io::Error::from_raw_os_error(32).into())
+        });
+    assert!(is_locked_error(&rv.unwrap_err()));
+    assert_eq!(calls, LOCKED_RETRIES + 1);
+}
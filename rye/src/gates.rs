@@ -0,0 +1,103 @@
+use std::env;
+use std::fmt;
+use std::process::Command;
+
+use anyhow::{bail, Context, Error};
+use clap::ValueEnum;
+use console::style;
+
+use crate::pyproject::PyProject;
+use crate::utils::CommandOutput;
+
+/// Commands that can be guarded by quality gates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gate {
+    Build,
+    Publish,
+}
+
+impl Gate {
+    /// The key of the gate below `tool.rye.gates`.
+    pub fn key(self) -> &'static str {
+        match self {
+            Gate::Build => "build",
+            Gate::Publish => "publish",
+        }
+    }
+}
+
+/// A check that can be run as part of a quality gate.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[value(rename_all = "kebab-case")]
+pub enum GateCheck {
+    /// `rye fmt --check`
+    Fmt,
+    /// `rye lint`
+    Lint,
+    /// `rye test`
+    Test,
+}
+
+impl GateCheck {
+    fn args(self) -> &'static [&'static str] {
+        match self {
+            GateCheck::Fmt => &["fmt", "--check"],
+            GateCheck::Lint => &["lint"],
+            GateCheck::Test => &["test"],
+        }
+    }
+}
+
+impl fmt::Display for GateCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rye {}", self.args().join(" "))
+    }
+}
+
+/// Runs the checks configured for a gate and fails if any of them fails.
+///
+/// The checks run for the whole workspace in the order they are configured.
+/// Every check is invoked as a separate `rye` process so that it behaves
+/// exactly as if it was run by hand.
+pub fn run_gate(project: &PyProject, gate: Gate, output: CommandOutput) -> Result<(), Error> {
+    let checks = project.gate_checks(gate)?;
+    if checks.is_empty() {
+        return Ok(());
+    }
+    let rye = env::current_exe().context("unable to determine rye executable")?;
+    for check in checks {
+        echo!(
+            if output,
+            "Running {} gate: {}",
+            gate.key(),
+            style(check).cyan()
+        );
+        let mut cmd = Command::new(&rye);
+        cmd.args(check.args())
+            .arg("--pyproject")
+            .arg(project.toml_path().as_os_str());
+        if project.workspace().is_some() {
+            cmd.arg("--all");
+        }
+        match output {
+            CommandOutput::Quiet => {
+                cmd.arg("--quiet");
+            }
+            CommandOutput::Verbose => {
+                cmd.arg("--verbose");
+            }
+            CommandOutput::Normal => {}
+        }
+        let status = cmd
+            .status()
+            .with_context(|| format!("failed to run {}", check))?;
+        if !status.success() {
+            bail!(
+                "{} gate failed: `{}` did not pass (pass --no-verify to skip the gate)",
+                gate.key(),
+                check
+            );
+        }
+    }
+    Ok(())
+}
@@ -1,14 +1,21 @@
 use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
-use std::{env, fs};
+use std::process::{self, Command, Stdio};
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use std::{env, fs, thread};
 
-use anyhow::{bail, Context, Error};
+use anyhow::{anyhow, bail, Context, Error};
 use console::style;
 use once_cell::sync::Lazy;
 use pep508_rs::{Requirement, VersionOrUrl};
 use regex::Regex;
 use same_file::is_same_file;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
 use url::Url;
 
 use crate::bootstrap::{ensure_self_venv, fetch, FetchOptions};
@@ -17,13 +24,19 @@ use crate::consts::VENV_BIN;
 use crate::lock::KeyringProvider;
 use crate::platform::get_app_dir;
 use crate::pyproject::{normalize_package_name, read_venv_marker, ExpandedSources};
-use crate::sources::py::PythonVersionRequest;
+use crate::sources::py::{PythonVersion, PythonVersionRequest};
 use crate::sync::{create_virtualenv, VenvMarker};
 use crate::utils::{
-    get_short_executable_name, get_venv_python_bin, is_executable, symlink_file, CommandOutput,
-    IoPathContext,
+    get_short_executable_name, get_venv_python_bin, is_executable, rename_with_retry, symlink_file,
+    tui_theme, CommandOutput, IoPathContext,
 };
-use crate::uv::{UvBuilder, UvInstallOptions};
+use crate::uv::{UvBuilder, UvInstallOptions, UvSyncOptions, Venv};
+
+/// The name of the lockfile in the folder of a tool.
+const TOOL_LOCKFILE: &str = "requirements.lock";
+
+/// How long the `--version` probe of a freshly installed shim may run.
+const SHIM_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
 
 const FIND_SCRIPT_SCRIPT: &str = r#"
 import os
@@ -71,6 +84,19 @@ print(json.dumps(result))
 static SUCCESSFULLY_DOWNLOADED_RE: Lazy<Regex> =
     Lazy::new(|| Regex::new("(?m)^Successfully downloaded (.*)").unwrap());
 
+/// How a tool was installed.  This is recorded in the venv marker of the
+/// tool so that it can be rebuilt when its toolchain goes away.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ToolSpec {
+    pub requirement: String,
+    /// The interpreter that was requested for the tool.
+    pub python: String,
+    #[serde(default)]
+    pub include_deps: Vec<String>,
+    #[serde(default)]
+    pub extra_requirements: Vec<String>,
+}
+
 #[derive(Eq, PartialEq)]
 pub struct ToolInfo {
     pub version: String,
@@ -95,6 +121,41 @@ impl ToolInfo {
     }
 }
 
+/// The shims that rye itself places into the shim folder.
+const CORE_SHIMS: &[&str] = &["rye", "python", "python3", "pythonw"];
+
+/// Who provides a shim in the shim folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShimOwner {
+    /// The shim links to a script of an installed tool.
+    Tool(String),
+    /// The shim is one of rye's own shims.
+    Rye,
+    /// The shim is not known to rye.
+    Unknown,
+}
+
+impl fmt::Display for ShimOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShimOwner::Tool(tool) => write!(f, "tool {}", tool),
+            ShimOwner::Rye => write!(f, "rye"),
+            ShimOwner::Unknown => write!(f, "an unknown program"),
+        }
+    }
+}
+
+/// What to do when a script of a tool would replace an existing shim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShimConflict {
+    /// Fail the installation.
+    Fail,
+    /// Install the script as `<script>@<tool>` instead.
+    Suffix,
+    /// Ask the user what to do.
+    Prompt,
+}
+
 const TOOL_VERSION_SCRIPT: &str = r#"
 import sys
 from importlib.metadata import version
@@ -103,15 +164,20 @@ tool_name = sys.argv[1]
 print(version(tool_name))
 "#;
 
+#[allow(clippy::too_many_arguments)]
 pub fn install(
     requirement: Requirement,
     py_ver: &PythonVersionRequest,
     force: bool,
+    locked: bool,
     include_deps: &[String],
     extra_requirements: &[Requirement],
     output: CommandOutput,
     keyring_provider: KeyringProvider,
-) -> Result<(), Error> {
+    compile_bytecode: bool,
+    on_conflict: ShimConflict,
+    uv_args: &[String],
+) -> Result<Vec<String>, Error> {
     let config = Config::current();
     let sources = ExpandedSources::from_sources(&config.sources()?)?;
     let app_dir = get_app_dir();
@@ -123,16 +189,37 @@ pub fn install(
         .map(|x| normalize_package_name(x))
         .collect::<Vec<_>>();
 
-    let target_venv_path = tool_dir.join(normalize_package_name(&requirement.name));
-    if target_venv_path.is_dir() && !force {
+    let tool_name = normalize_package_name(&requirement.name);
+    let target_venv_path = tool_dir.join(&tool_name);
+    if target_venv_path.join(VENV_BIN).is_dir() && !force {
         bail!("package already installed");
     }
     let py = get_venv_python_bin(&target_venv_path);
     let target_venv_bin_path = target_venv_path.join(VENV_BIN);
 
+    // the lockfile lives in the folder of the tool which is recreated below,
+    // so it has to be read before.
+    let lockfile = target_venv_path.join(TOOL_LOCKFILE);
+    let locked_requirements = if locked {
+        let contents = fs::read_to_string(&lockfile).map_err(|_| {
+            anyhow!(
+                "no lockfile for {} found at {}. Install it without --locked to create one",
+                tool_name,
+                lockfile.display()
+            )
+        })?;
+        check_locked_requirement(&contents, &requirement, extra_requirements)?;
+        Some(contents)
+    } else {
+        None
+    };
+
     uninstall_helper(&target_venv_path, &shim_dir)?;
+    // a lockfile can be placed in the folder before the tool is installed
+    fs::remove_file(&lockfile).ok();
 
     // make sure we have a compatible python version
+    let py_ver_request = py_ver;
     let py_ver = fetch(py_ver, FetchOptions::with_output(output))?;
 
     create_virtualenv(
@@ -143,25 +230,71 @@ pub fn install(
         requirement.name.as_str(),
     )?;
 
-    let result = UvBuilder::new()
+    let venv = UvBuilder::new()
         .with_output(output.quieter())
         .with_sources(sources)
+        .with_uv_args(uv_args)
         .ensure_exists()?
         .venv(&target_venv_path, &py, &py_ver, None)?
-        .with_output(output)
-        .install(
-            &requirement,
-            UvInstallOptions {
-                importlib_workaround: py_ver.major == 3 && py_ver.minor == 7,
-                extras: extra_requirements.to_vec(),
-                refresh: force,
-                keyring_provider,
-            },
-        );
-    if result.is_err() {
+        .with_output(output);
+    let extras = extra_requirements
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>();
+    let result = match locked_requirements {
+        // the lockfile only moves into the tool folder once the sync worked,
+        // the folder is removed again if it fails.
+        Some(ref contents) => NamedTempFile::new()
+            .and_then(|mut tmp| tmp.write_all(contents.as_bytes()).map(|()| tmp))
+            .context("failed to write temporary lockfile")
+            .and_then(|tmp| {
+                venv.sync(
+                    tmp.path(),
+                    UvSyncOptions {
+                        keyring_provider,
+                        compile_bytecode,
+                        ..UvSyncOptions::default()
+                    },
+                )
+            })
+            .and_then(|()| {
+                fs::write(&lockfile, contents)
+                    .path_context(&lockfile, "failed to write tool lockfile")
+            }),
+        None => venv
+            .install(
+                &requirement,
+                UvInstallOptions {
+                    importlib_workaround: py_ver.major == 3 && py_ver.minor == 7,
+                    extras: extra_requirements.to_vec(),
+                    refresh: force,
+                    keyring_provider,
+                    compile_bytecode,
+                },
+            )
+            .and_then(|()| {
+                let requirement = requirement.to_string();
+                write_tool_lockfile(&venv, &lockfile, &requirement, &extras, &py_ver)
+            }),
+    };
+    if let Err(err) = result {
         uninstall_helper(&target_venv_path, &shim_dir)?;
-        return result;
+        // do not lose a lockfile that was installed from
+        if let Some(contents) = locked_requirements {
+            fs::create_dir_all(&target_venv_path).ok();
+            fs::write(&lockfile, contents).ok();
+        }
+        return Err(err);
     }
+    write_tool_spec(
+        &target_venv_path,
+        ToolSpec {
+            requirement: requirement.to_string(),
+            python: py_ver_request.to_string(),
+            include_deps: include_deps.clone(),
+            extra_requirements: extras,
+        },
+    )?;
 
     let out = Command::new(py)
         .arg("-c")
@@ -178,10 +311,19 @@ pub fn install(
             )
         })?;
 
+    let mut shims = ShimInstaller {
+        tool: &tool_name,
+        target_venv_bin_path: &target_venv_bin_path,
+        shim_dir: &shim_dir,
+        owners: list_shims()?,
+        on_conflict,
+        replaced: Vec::new(),
+    };
     let mut installed = Vec::new();
     let mut scripts_found = Vec::new();
+    let mut to_install = Vec::new();
     if let Some(files) = all_files.get("") {
-        installed.extend(install_scripts(files, &target_venv_bin_path, &shim_dir)?);
+        to_install.extend(files);
     }
 
     for (package, files) in all_files.iter() {
@@ -189,7 +331,7 @@ pub fn install(
             continue;
         }
         if include_deps.contains(&normalize_package_name(package)) {
-            installed.extend(install_scripts(files, &target_venv_bin_path, &shim_dir)?);
+            to_install.extend(files);
         } else {
             let scripts = find_scripts(files, &target_venv_bin_path);
             if !scripts.is_empty() {
@@ -198,6 +340,19 @@ pub fn install(
         }
     }
 
+    for file in to_install {
+        match shims.install(file) {
+            Ok(Some(script)) => installed.push(script),
+            Ok(None) => {}
+            Err(err) => {
+                shims.restore_replaced();
+                uninstall_helper(&target_venv_path, &shim_dir)?;
+                return Err(err);
+            }
+        }
+    }
+    shims.discard_replaced();
+
     if !scripts_found.is_empty()
         && output != CommandOutput::Quiet
         && (installed.is_empty() || output == CommandOutput::Verbose)
@@ -222,7 +377,7 @@ pub fn install(
             warn!("installed package did not expose any scripts")
         } else {
             echo!("Installed scripts:");
-            for script in installed {
+            for script in &installed {
                 echo!("  - {}", style(script).cyan());
             }
             if output != CommandOutput::Verbose && !scripts_found.is_empty() {
@@ -236,9 +391,227 @@ pub fn install(
         }
     }
 
+    Ok(installed)
+}
+
+/// Records how a tool was installed in the venv marker of the tool.
+fn write_tool_spec(venv: &Path, spec: ToolSpec) -> Result<(), Error> {
+    let mut marker =
+        read_venv_marker(venv).ok_or_else(|| anyhow!("tool virtualenv has no venv marker"))?;
+    marker.tool = Some(spec);
+    let path = venv.join("rye-venv.json");
+    fs::write(&path, serde_json::to_string_pretty(&marker)?)
+        .path_context(&path, "failed writing venv marker file")?;
+    Ok(())
+}
+
+/// Records the packages installed in the virtualenv of a tool in its lockfile.
+fn write_tool_lockfile(
+    venv: &impl Venv,
+    lockfile: &Path,
+    requirement: &str,
+    extra_requirements: &[String],
+    python: &PythonVersion,
+) -> Result<(), Error> {
+    let requirements = venv.freeze_requirements()?;
+    let mut contents = format!("# generated by rye\n# tool: {}\n", requirement);
+    for extra in extra_requirements {
+        contents.push_str(&format!("# extra-requirement: {}\n", extra));
+    }
+    contents.push_str(&format!("# python: {}\n\n{}", python, requirements));
+    fs::write(lockfile, contents).path_context(lockfile, "failed to write tool lockfile")?;
     Ok(())
 }
 
+/// Makes sure a tool lockfile was created for the requested requirement.
+///
+/// A bare package name installs whatever version was locked, anything more
+/// specific (a version, extras or extra requirements) has to match.
+fn check_locked_requirement(
+    contents: &str,
+    requirement: &Requirement,
+    extra_requirements: &[Requirement],
+) -> Result<(), Error> {
+    let header = |prefix: &str| {
+        contents
+            .lines()
+            .take_while(|x| x.starts_with('#'))
+            .filter_map(|x| x.strip_prefix(prefix))
+            .map(|x| x.trim().to_string())
+            .collect::<Vec<_>>()
+    };
+    let requested = requirement.to_string();
+    if requirement.version_or_url.is_some()
+        || requirement.extras.as_ref().map_or(false, |x| !x.is_empty())
+    {
+        if let Some(locked) = header("# tool: ").first() {
+            if *locked != requested {
+                bail!(
+                    "the lockfile of {} was created for {} but {} was requested. \
+                     Install it without --locked to update the lockfile",
+                    requirement.name,
+                    locked,
+                    requested
+                );
+            }
+        }
+    }
+    if !extra_requirements.is_empty() {
+        let mut locked = header("# extra-requirement: ");
+        let mut requested = extra_requirements
+            .iter()
+            .map(|x| x.to_string())
+            .collect::<Vec<_>>();
+        locked.sort();
+        requested.sort();
+        if locked != requested {
+            bail!(
+                "the lockfile of {} was created with different extra requirements. \
+                 Install it without --locked to update the lockfile",
+                requirement.name
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Regenerates the lockfile of an installed tool from its virtualenv.
+///
+/// Returns the path of the lockfile.
+pub fn lock_tool(tool: &str) -> Result<PathBuf, Error> {
+    let target_venv_path = get_app_dir()
+        .join("tools")
+        .join(normalize_package_name(tool));
+    let marker = match read_venv_marker(&target_venv_path) {
+        Some(marker) if target_venv_path.join(VENV_BIN).is_dir() => marker,
+        _ => bail!("{} is not installed", tool),
+    };
+    let (requirement, extra_requirements) = match marker.tool {
+        Some(ref spec) => (spec.requirement.clone(), spec.extra_requirements.clone()),
+        None => (tool.to_string(), Vec::new()),
+    };
+    let venv = UvBuilder::new()
+        .with_output(CommandOutput::Quiet)
+        .ensure_exists()?
+        .read_only_venv(&target_venv_path)?;
+    let lockfile = target_venv_path.join(TOOL_LOCKFILE);
+    write_tool_lockfile(
+        &venv,
+        &lockfile,
+        &requirement,
+        &extra_requirements,
+        &marker.python,
+    )?;
+    Ok(lockfile)
+}
+
+/// Re-installs a tool whose toolchain was removed against a compatible one.
+///
+/// The tool is rebuilt from its recorded [`ToolSpec`].  Tools installed before
+/// the spec was recorded are rebuilt from their name alone.  If the tool has a
+/// lockfile the locked versions are installed again.
+pub fn rebuild_tool(
+    tool: &str,
+    marker: &VenvMarker,
+    output: CommandOutput,
+) -> Result<Vec<String>, Error> {
+    let (requirement, py_ver, include_deps, extra_requirements) = recorded_spec(tool, marker)?;
+
+    // the toolchain the tool was built against is gone, any patch release of
+    // the same version will do.
+    let py_ver = PythonVersionRequest {
+        patch: None,
+        suffix: None,
+        ..py_ver
+    };
+
+    let locked = get_app_dir()
+        .join("tools")
+        .join(tool)
+        .join(TOOL_LOCKFILE)
+        .is_file();
+    install(
+        requirement,
+        &py_ver,
+        true,
+        locked,
+        &include_deps,
+        &extra_requirements,
+        output,
+        KeyringProvider::default(),
+        false,
+        ShimConflict::Fail,
+        &[],
+    )
+}
+
+/// Re-installs a tool from its recorded [`ToolSpec`] with the latest versions
+/// that satisfy it.
+///
+/// The lockfile of the tool is updated.  Scripts that conflict with shims of
+/// other tools are installed as `<script>@<tool>`.
+pub fn upgrade_tool(
+    tool: &str,
+    output: CommandOutput,
+    uv_args: &[String],
+) -> Result<Vec<String>, Error> {
+    let target_venv_path = get_app_dir()
+        .join("tools")
+        .join(normalize_package_name(tool));
+    let marker = match read_venv_marker(&target_venv_path) {
+        Some(marker) if target_venv_path.join(VENV_BIN).is_dir() => marker,
+        _ => bail!("{} is not installed", tool),
+    };
+    let (requirement, py_ver, include_deps, extra_requirements) = recorded_spec(tool, &marker)?;
+    install(
+        requirement,
+        &py_ver,
+        true,
+        false,
+        &include_deps,
+        &extra_requirements,
+        output,
+        KeyringProvider::default(),
+        false,
+        ShimConflict::Suffix,
+        uv_args,
+    )
+}
+
+/// The requirement, interpreter, included dependencies and extra requirements
+/// a tool was installed with.
+type RecordedSpec = (
+    Requirement,
+    PythonVersionRequest,
+    Vec<String>,
+    Vec<Requirement>,
+);
+
+/// Returns how a tool was installed.
+///
+/// Tools installed before the spec was recorded only have their name.
+fn recorded_spec(tool: &str, marker: &VenvMarker) -> Result<RecordedSpec, Error> {
+    Ok(match marker.tool {
+        Some(ref spec) => (
+            Requirement::from_str(&spec.requirement)
+                .map_err(|msg| anyhow!("invalid recorded requirement: {}", msg))?,
+            spec.python.parse::<PythonVersionRequest>()?,
+            spec.include_deps.clone(),
+            spec.extra_requirements
+                .iter()
+                .map(|x| Requirement::from_str(x))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|msg| anyhow!("invalid recorded requirement: {}", msg))?,
+        ),
+        None => (
+            Requirement::from_str(tool).map_err(|msg| anyhow!("invalid tool name: {}", msg))?,
+            PythonVersionRequest::from(marker.python.clone()),
+            Vec::new(),
+            Vec::new(),
+        ),
+    })
+}
+
 fn find_scripts(files: &[PathBuf], target_venv_bin_path: &Path) -> Vec<String> {
     let mut rv = Vec::new();
     for file in files {
@@ -249,47 +622,242 @@ fn find_scripts(files: &[PathBuf], target_venv_bin_path: &Path) -> Vec<String> {
     rv
 }
 
-fn install_scripts(
-    files: &[PathBuf],
-    target_venv_bin_path: &Path,
-    shim_dir: &Path,
-) -> Result<Vec<String>, Error> {
-    let mut rv = Vec::new();
-    for file in files {
-        if let Ok(rest) = file.strip_prefix(target_venv_bin_path) {
-            // In some cases we are given paths here which point to sub-folders of the
-            // script/bin folder.  For instance in some cases it has been shown that
-            // __pycache__/something.pyc shows up there.  These are obviously not good
-            // targets to link as they would never show up via PATH discovery.  Skip
-            // over these.
-            //
-            // Also do not try to link things which are not considered executables on
-            // this operating system.
-            if !rest.parent().map_or(true, |x| x == Path::new("")) || !is_executable(file) {
-                continue;
-            }
+/// Links the scripts of a tool into the shim folder.
+struct ShimInstaller<'a> {
+    tool: &'a str,
+    target_venv_bin_path: &'a Path,
+    shim_dir: &'a Path,
+    owners: BTreeMap<String, ShimOwner>,
+    on_conflict: ShimConflict,
+    /// Shims that were replaced, as pairs of the backup and the original path.
+    replaced: Vec<(PathBuf, PathBuf)>,
+}
+
+/// How a script that conflicts with an existing shim is installed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConflictResolution {
+    /// Install the script as `<script>@<tool>`.
+    Suffix,
+    /// Replace the existing shim.
+    Replace,
+    /// Do not install the script.
+    Skip,
+}
+
+impl ShimInstaller<'_> {
+    /// Installs a single file as shim if it is a script.
+    ///
+    /// Returns the name of the installed shim.
+    fn install(&mut self, file: &Path) -> Result<Option<String>, Error> {
+        let Ok(rest) = file.strip_prefix(self.target_venv_bin_path) else {
+            return Ok(None);
+        };
+        // In some cases we are given paths here which point to sub-folders of the
+        // script/bin folder.  For instance in some cases it has been shown that
+        // __pycache__/something.pyc shows up there.  These are obviously not good
+        // targets to link as they would never show up via PATH discovery.  Skip
+        // over these.
+        //
+        // Also do not try to link things which are not considered executables on
+        // this operating system.
+        if !rest.parent().map_or(true, |x| x == Path::new("")) || !is_executable(file) {
+            return Ok(None);
+        }
 
-            let shim_target = shim_dir.join(rest);
-
-            // on windows we want to fall back to hardlinks.  That might be problematic in
-            // some cases, but it should work for most cases where setuptools or other
-            // systems created exe files.  Caveat: uninstallation currently does not work
-            // when hardlinks are used.
-            #[cfg(windows)]
-            {
-                if symlink_file(file, &shim_target).is_err() {
-                    fs::hard_link(file, &shim_target)
-                        .path_context(file, "unable to symlink tool")?;
+        let mut shim_target = self.shim_dir.join(rest);
+        if fs::symlink_metadata(&shim_target).is_ok() {
+            let script = get_short_executable_name(file);
+            let owner = self
+                .owners
+                .get(&script)
+                .cloned()
+                .unwrap_or(ShimOwner::Unknown);
+            let suffixed = self
+                .shim_dir
+                .join(suffixed_shim_name(rest.as_os_str(), self.tool));
+            let suffixed_name = get_short_executable_name(&suffixed);
+            match self.resolve_conflict(&script, &owner, &suffixed_name)? {
+                ConflictResolution::Suffix => {
+                    // a leftover shim with our suffix can only belong to us
+                    fs::remove_file(&suffixed).ok();
+                    shim_target = suffixed;
                 }
+                ConflictResolution::Replace => {
+                    // keep the existing shim so that it can be restored if the
+                    // installation fails later on.
+                    let mut backup_name = OsString::from(".");
+                    backup_name.push(rest.as_os_str());
+                    backup_name.push(format!(".{}.bak", process::id()));
+                    let backup = self.shim_dir.join(backup_name);
+                    rename_with_retry(&shim_target, &backup)
+                        .path_context(&shim_target, "unable to replace existing shim")?;
+                    self.replaced.push((backup, shim_target.clone()));
+                }
+                ConflictResolution::Skip => return Ok(None),
             }
-            #[cfg(unix)]
-            {
-                symlink_file(file, shim_target).path_context(file, "unable to symlink tool")?;
+        }
+
+        link_shim(file, &shim_target)?;
+        // virus scanners on windows are known to interfere with freshly written
+        // executables, make sure the shim actually works before reporting success.
+        if cfg!(windows) {
+            if let Err(err) = probe_shim(&shim_target) {
+                fs::remove_file(&shim_target).ok();
+                return Err(err);
             }
-            rv.push(get_short_executable_name(file));
+        }
+        let name = get_short_executable_name(&shim_target);
+        self.owners
+            .insert(name.clone(), ShimOwner::Tool(self.tool.to_string()));
+        Ok(Some(name))
+    }
+
+    /// Decides what to do with a script that conflicts with an existing shim.
+    fn resolve_conflict(
+        &self,
+        script: &str,
+        owner: &ShimOwner,
+        suffixed_name: &str,
+    ) -> Result<ConflictResolution, Error> {
+        match self.on_conflict {
+            ShimConflict::Suffix => Ok(ConflictResolution::Suffix),
+            ShimConflict::Fail => bail!(
+                "script {} of {} conflicts with an existing shim provided by {}. \
+                 Use --suffix to install it as {} instead",
+                script,
+                self.tool,
+                owner,
+                suffixed_name
+            ),
+            ShimConflict::Prompt => {
+                let choice = dialoguer::Select::with_theme(tui_theme())
+                    .with_prompt(format!(
+                        "The script {} is already provided by {}. What should happen?",
+                        style(script).cyan(),
+                        owner
+                    ))
+                    .item(format!("Install it as {}", suffixed_name))
+                    .item(format!("Replace the shim of {}", owner))
+                    .item("Skip the script")
+                    .default(0)
+                    .interact()?;
+                Ok(match choice {
+                    0 => ConflictResolution::Suffix,
+                    1 => ConflictResolution::Replace,
+                    _ => ConflictResolution::Skip,
+                })
+            }
+        }
+    }
+
+    /// Puts the shims that were replaced back in place after a failed install.
+    fn restore_replaced(&mut self) {
+        for (backup, original) in self.replaced.drain(..) {
+            if let Err(err) = rename_with_retry(&backup, &original) {
+                warn!(
+                    category = Environment,
+                    "failed to restore shim {}: {}",
+                    original.display(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// Removes the backups of the replaced shims once the install succeeded.
+    fn discard_replaced(&mut self) {
+        for (backup, _) in self.replaced.drain(..) {
+            fs::remove_file(&backup).ok();
         }
     }
-    Ok(rv)
+}
+
+/// Links a script into the shim folder.
+///
+/// The link is created under a temporary name and then renamed into place so
+/// that a shim is never observed half-written, not even by a concurrent install.
+fn link_shim(file: &Path, shim_target: &Path) -> Result<(), Error> {
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(shim_target.file_name().unwrap_or_default());
+    tmp_name.push(format!(".{}.tmp", process::id()));
+    let tmp = shim_target.with_file_name(tmp_name);
+    fs::remove_file(&tmp).ok();
+
+    // on windows we want to fall back to hardlinks.  That might be problematic in
+    // some cases, but it should work for most cases where setuptools or other
+    // systems created exe files.  Caveat: uninstallation currently does not work
+    // when hardlinks are used.
+    #[cfg(windows)]
+    {
+        if symlink_file(file, &tmp).is_err() {
+            fs::hard_link(file, &tmp).path_context(file, "unable to symlink tool")?;
+        }
+    }
+    #[cfg(unix)]
+    {
+        symlink_file(file, &tmp).path_context(file, "unable to symlink tool")?;
+    }
+
+    if let Err(err) = rename_with_retry(&tmp, shim_target) {
+        fs::remove_file(&tmp).ok();
+        return Err(err).path_context(shim_target, "unable to install shim");
+    }
+    Ok(())
+}
+
+/// Makes sure that a shim can be launched by running it with `--version`.
+///
+/// Only launching matters, scripts do not have to support the flag.  Scripts
+/// that do not exit in time are killed.
+fn probe_shim(shim: &Path) -> Result<(), Error> {
+    let mut child = Command::new(shim)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .path_context(shim, "installed shim cannot be launched")?;
+    let started = Instant::now();
+    while child.try_wait()?.is_none() {
+        if started.elapsed() > SHIM_PROBE_TIMEOUT {
+            child.kill().ok();
+            child.wait().ok();
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    Ok(())
+}
+
+/// Returns the file name of a shim with the tool name as suffix (`<script>@<tool>`).
+fn suffixed_shim_name(name: &OsStr, tool: &str) -> OsString {
+    let path = Path::new(name);
+    // keep the extension on windows, as it decides how the script is run
+    if cfg!(windows) {
+        if let (Some(stem), Some(ext)) = (path.file_stem(), path.extension()) {
+            let mut rv = stem.to_os_string();
+            rv.push(format!("@{}.", tool));
+            rv.push(ext);
+            return rv;
+        }
+    }
+    let mut rv = name.to_os_string();
+    rv.push(format!("@{}", tool));
+    rv
+}
+
+/// Finds the shim of a script in a tool's virtualenv.
+///
+/// The shim is either named like the script or carries the tool name as suffix.
+fn find_shim(shim_dir: &Path, script_path: &Path, tool: &str) -> Option<PathBuf> {
+    let base_name = script_path.file_name()?;
+    [
+        base_name.to_os_string(),
+        suffixed_shim_name(base_name, tool),
+    ]
+    .into_iter()
+    .map(|name| shim_dir.join(name))
+    .find(|shim_path| matches!(is_same_file(shim_path, script_path), Ok(true)))
 }
 
 pub fn uninstall(package: &str, output: CommandOutput) -> Result<(), Error> {
@@ -328,19 +896,10 @@ pub fn list_installed_tools() -> Result<HashMap<String, ToolInfo>, Error> {
         let target_venv_bin_path = folder.path().join(VENV_BIN);
         let venv_marker = read_venv_marker(&folder.path());
 
-        let mut scripts = Vec::new();
-        for script in fs::read_dir(target_venv_bin_path.clone())
-            .path_context(&target_venv_bin_path, "unable to enumerate scripts")?
-        {
-            let script = script?;
-            let script_path = script.path();
-            if let Some(base_name) = script_path.file_name() {
-                let shim_path = shim_dir.join(base_name);
-                if let Ok(true) = is_same_file(&shim_path, &script_path) {
-                    scripts.push(get_short_executable_name(&script_path));
-                }
-            }
-        }
+        let scripts = tool_shims(&shim_dir, &folder.path(), &tool_name)?
+            .iter()
+            .map(|x| get_short_executable_name(x))
+            .collect();
 
         let output = Command::new(target_venv_bin_path.join("python"))
             .arg("-c")
@@ -363,23 +922,78 @@ pub fn list_installed_tools() -> Result<HashMap<String, ToolInfo>, Error> {
     Ok(rv)
 }
 
-fn uninstall_helper(target_venv_path: &Path, shim_dir: &Path) -> Result<(), Error> {
+/// Lists all shims in the shim folder with the tool or program that provides them.
+pub fn list_shims() -> Result<BTreeMap<String, ShimOwner>, Error> {
+    let app_dir = get_app_dir();
+    let shim_dir = app_dir.join("shims");
+    let tool_dir = app_dir.join("tools");
+    let mut rv = BTreeMap::new();
+    if !shim_dir.is_dir() {
+        return Ok(rv);
+    }
+
+    if tool_dir.is_dir() {
+        for folder in
+            fs::read_dir(&tool_dir).path_context(&tool_dir, "unable to enumerate tools")?
+        {
+            let folder = folder?;
+            if !folder.file_type()?.is_dir() {
+                continue;
+            }
+            let tool_name = folder.file_name().to_string_lossy().to_string();
+            for shim in tool_shims(&shim_dir, &folder.path(), &tool_name)? {
+                rv.insert(
+                    get_short_executable_name(&shim),
+                    ShimOwner::Tool(tool_name.clone()),
+                );
+            }
+        }
+    }
+
+    for shim in fs::read_dir(&shim_dir).path_context(&shim_dir, "unable to enumerate shims")? {
+        let name = get_short_executable_name(&shim?.path());
+        if !rv.contains_key(&name) {
+            let owner = if CORE_SHIMS.contains(&name.as_str()) {
+                ShimOwner::Rye
+            } else {
+                ShimOwner::Unknown
+            };
+            rv.insert(name, owner);
+        }
+    }
+
+    Ok(rv)
+}
+
+/// Returns the paths of the shims that link into a tool's virtualenv.
+fn tool_shims(shim_dir: &Path, target_venv_path: &Path, tool: &str) -> Result<Vec<PathBuf>, Error> {
     let target_venv_bin_path = target_venv_path.join(VENV_BIN);
+    let mut rv = Vec::new();
     if !target_venv_bin_path.is_dir() {
-        return Ok(());
+        return Ok(rv);
     }
-
     for script in fs::read_dir(&target_venv_bin_path)
         .path_context(&target_venv_bin_path, "unable to enumerate scripts")?
     {
-        let script = script?;
-        if let Some(base_name) = script.path().file_name() {
-            let shim_path = shim_dir.join(base_name);
-            if let Ok(true) = is_same_file(&shim_path, script.path()) {
-                fs::remove_file(&shim_path).ok();
-            }
+        if let Some(shim_path) = find_shim(shim_dir, &script?.path(), tool) {
+            rv.push(shim_path);
         }
     }
+    Ok(rv)
+}
+
+fn uninstall_helper(target_venv_path: &Path, shim_dir: &Path) -> Result<(), Error> {
+    if !target_venv_path.join(VENV_BIN).is_dir() {
+        return Ok(());
+    }
+
+    let tool = target_venv_path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    for shim_path in tool_shims(shim_dir, target_venv_path, &tool)? {
+        fs::remove_file(&shim_path).ok();
+    }
 
     fs::remove_dir_all(target_venv_path).ok();
 
@@ -422,3 +1036,43 @@ pub fn resolve_local_requirement(
         Ok(None)
     }
 }
+
+#[cfg(unix)]
+#[test]
+fn test_suffixed_shim_name() {
+    assert_eq!(
+        suffixed_shim_name(OsStr::new("black"), "black"),
+        OsString::from("black@black")
+    );
+    assert_eq!(
+        suffixed_shim_name(OsStr::new("foo.py"), "bar"),
+        OsString::from("foo.py@bar")
+    );
+}
+
+#[test]
+fn test_link_shim() {
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("script");
+    fs::write(&script, "").unwrap();
+    let shim = dir.path().join("shim");
+    fs::write(&shim, "stale").unwrap();
+    link_shim(&script, &shim).unwrap();
+    assert!(is_same_file(&script, &shim).unwrap());
+    // the temporary link does not stay behind
+    assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn test_check_locked_requirement() {
+    let req = |s: &str| Requirement::from_str(s).unwrap();
+    let contents = "# generated by rye\n# tool: black==24.1.0\n\
+                    # extra-requirement: click<9\n# python: cpython@3.12.8\n\nblack==24.1.0\n";
+
+    assert!(check_locked_requirement(contents, &req("black"), &[]).is_ok());
+    assert!(check_locked_requirement(contents, &req("black==24.1.0"), &[]).is_ok());
+    assert!(check_locked_requirement(contents, &req("black==23.1.0"), &[]).is_err());
+    assert!(check_locked_requirement(contents, &req("black[d]"), &[]).is_err());
+    assert!(check_locked_requirement(contents, &req("black"), &[req("click<9")]).is_ok());
+    assert!(check_locked_requirement(contents, &req("black"), &[req("colorama")]).is_err());
+}
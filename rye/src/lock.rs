@@ -1,27 +1,35 @@
 use std::borrow::Cow;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::io::{BufWriter, Write};
 use std::path::Path;
 use std::process::Command;
 use std::sync::Arc;
+use std::time::SystemTime;
 use std::{env, fmt, fs};
 
-use anyhow::{anyhow, Context, Error};
+use anyhow::{anyhow, bail, Context, Error};
 use clap::ValueEnum;
 use minijinja::render;
 use once_cell::sync::Lazy;
-use pep508_rs::Requirement;
+use pep440_rs::{Operator, Version};
+use pep508_rs::{MarkerEnvironment, MarkerTree, Requirement, StringVersion, VersionOrUrl};
 use regex::Regex;
 use serde::Serialize;
 use tempfile::NamedTempFile;
 use url::Url;
 
+use crate::config::Config;
+use crate::presets::DevPreset;
+use crate::provenance::format_timestamp;
 use crate::pyproject::{
-    normalize_package_name, DependencyKind, ExpandedSources, PyProject, Workspace,
+    normalize_package_name, DependencyKind, DependencyRef, ExpandedSources, PyProject, Workspace,
 };
+use crate::sdist::sdist_build_requires;
 use crate::sources::py::PythonVersion;
+use crate::sources::uv::{UvDownload, UvRequest};
 use crate::utils::{CommandOutput, IoPathContext};
 use crate::uv::{UvBuilder, UvPackageUpgrade};
+use crate::yanked::find_yanked;
 
 static FILE_EDITABLE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^-e (file://.*?)\s*$").unwrap());
 static DEP_COMMENT_RE: Lazy<Regex> =
@@ -36,13 +44,40 @@ static REQUIREMENTS_HEADER: &str = r#"# generated by rye
 #   with-sources: {{ lock_options.with_sources|tojson }}
 #   generate-hashes: {{ lock_options.generate_hashes|tojson }}
 #   universal: {{ lock_options.universal|tojson }}
+{%- if lock_options.binary_policy.no_binary %}
+#   no-binary: {{ lock_options.binary_policy.no_binary|tojson }}
+{%- endif %}
+{%- if lock_options.binary_policy.only_binary %}
+#   only-binary: {{ lock_options.binary_policy.only_binary|tojson }}
+{%- endif %}
+{%- for preset in lock_options.dev_presets %}
+#   dev-preset: {{ preset.source }} (sha256:{{ preset.sha256 }})
+{%- endfor %}
+{%- if metadata %}
+#
+# locked with:
+#   rye-version: {{ metadata.rye_version }}
+{%- if metadata.uv_version %}
+#   uv-version: {{ metadata.uv_version }}
+{%- endif %}
+#   locked-at: {{ metadata.locked_at }}
+{%- if metadata.exclude_newer %}
+#   exclude-newer: {{ metadata.exclude_newer }}
+{%- endif %}
+{%- endif %}
+
+"#;
+static CONSTRAINTS_HEADER: &str = r#"# generated by rye
+# constraints for {{ member }} derived from {{ lockfile }}
+# use `rye lock` or `rye sync` to update this file
 
 "#;
 static PARAM_RE: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^#   (pre|features|all-features|with-sources|universal):\s*(.*)").unwrap()
 });
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum LockMode {
     Production,
     Dev,
@@ -84,6 +119,153 @@ impl KeyringProvider {
     }
 }
 
+/// Which packages may be installed from wheels and which from source.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BinaryPolicy {
+    /// Packages that must be built from source (`:all:` for every package).
+    pub no_binary: Vec<String>,
+    /// Packages that must never be built from source (`:all:` for every package).
+    pub only_binary: Vec<String>,
+}
+
+impl BinaryPolicy {
+    pub fn add_as_pip_args(&self, cmd: &mut Command) {
+        for pkg in &self.no_binary {
+            cmd.arg("--no-binary").arg(pkg);
+        }
+        for pkg in &self.only_binary {
+            cmd.arg("--only-binary").arg(pkg);
+        }
+    }
+
+    /// Is the given package always built from source?
+    pub fn requires_source_build(&self, name: &str) -> bool {
+        let name = normalize_package_name(name);
+        let listed = |pkgs: &[String]| pkgs.iter().any(|x| normalize_package_name(x) == name);
+        (listed(&self.no_binary) || self.no_binary.iter().any(|x| x == ":all:"))
+            && !listed(&self.only_binary)
+    }
+}
+
+/// A temporary pin from `tool.rye.pins` that is applied as constraint when locking.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pin {
+    /// The name of the pinned package.
+    pub name: String,
+    /// The version specifiers the package is held to.
+    pub version: String,
+    /// The date (`YYYY-MM-DD`) from which on the pin is considered expired.
+    pub expires: Option<String>,
+    /// Why the package is held back.
+    pub reason: Option<String>,
+}
+
+impl Pin {
+    /// Has the pin expired as of `today` (`YYYY-MM-DD`)?
+    pub fn is_expired(&self, today: &str) -> bool {
+        self.expires.as_deref().map_or(false, |x| x <= today)
+    }
+}
+
+impl fmt::Display for Pin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.name, self.version)
+    }
+}
+
+/// Warns about expired pins or fails if `deny` is set.
+pub fn check_expired_pins(pins: &[Pin], deny: bool) -> Result<(), Error> {
+    let now = format_timestamp(SystemTime::now());
+    let today = &now[..10];
+    let expired = pins
+        .iter()
+        .filter(|x| x.is_expired(today))
+        .collect::<Vec<_>>();
+    if expired.is_empty() {
+        return Ok(());
+    }
+    let describe = |pin: &Pin| {
+        format!(
+            "{} (expired {}{})",
+            pin,
+            pin.expires.as_deref().unwrap_or_default(),
+            match pin.reason {
+                Some(ref reason) => format!(", reason: {}", reason),
+                None => String::new(),
+            }
+        )
+    };
+    if deny {
+        bail!(
+            "expired pins in tool.rye.pins:\n  - {}",
+            expired
+                .iter()
+                .copied()
+                .map(describe)
+                .collect::<Vec<_>>()
+                .join("\n  - ")
+        );
+    }
+    for pin in expired {
+        warn!(
+            category = Config,
+            "expired pin in tool.rye.pins: {}",
+            describe(pin)
+        );
+    }
+    Ok(())
+}
+
+/// How the build backend installs the project in editable mode.
+///
+/// This is passed to the build backend as `editable_mode` config setting when
+/// installing, which only setuptools understands.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[value(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum EditableMode {
+    /// Only the files of the project are exposed, new modules require a re-sync.
+    Strict,
+    /// The source folders are put on the path, new modules are picked up immediately.
+    Lenient,
+}
+
+impl EditableMode {
+    pub fn add_as_pip_args(self, cmd: &mut Command) {
+        cmd.arg("--config-setting").arg(format!(
+            "editable_mode={}",
+            match self {
+                EditableMode::Strict => "strict",
+                EditableMode::Lenient => "lenient",
+            }
+        ));
+    }
+}
+
+/// Controls what goes into the header of a lockfile.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[value(rename_all = "snake_case")]
+#[serde(rename_all = "snake_case")]
+pub enum LockHeader {
+    /// The flags the lockfile was generated with and the tool versions,
+    /// the time of locking and the exclude-newer cutoff.
+    Full,
+    /// Only the flags the lockfile was generated with.
+    #[default]
+    Minimal,
+    /// No header at all.  Flags cannot be restored from the lockfile.
+    None,
+}
+
+/// Metadata about the locking that goes into a full header.
+#[derive(Debug, Serialize)]
+struct LockMetadata {
+    rye_version: &'static str,
+    uv_version: Option<String>,
+    locked_at: String,
+    exclude_newer: Option<String>,
+}
+
 /// Controls how locking should work.
 #[derive(Debug, Clone, Default, Serialize)]
 pub struct LockOptions {
@@ -105,12 +287,51 @@ pub struct LockOptions {
     pub generate_hashes: bool,
     /// Use universal lock files.
     pub universal: bool,
+    /// What to write into the header of the lock file.
+    pub header: LockHeader,
+    /// Which packages may be installed from wheels.
+    pub binary_policy: BinaryPolicy,
+    /// Move off yanked versions pinned in the lock file.
+    pub refresh_yanked: bool,
+    /// Fail if the lock file contains yanked versions.
+    pub deny_yanked: bool,
+    /// Write a constraints file per workspace member next to the production lock file.
+    pub member_constraints: bool,
+    /// Temporary pins applied as constraints.
+    #[serde(skip)]
+    pub pins: Vec<Pin>,
+    /// Fail if any of the pins has expired.
+    pub deny_expired_pins: bool,
+    /// Shared presets whose dev-dependencies are locked into the dev lockfile.
+    pub dev_presets: Vec<DevPreset>,
+    /// Also lock the build requirements of all source builds.
+    #[serde(skip)]
+    pub emit_build_requirements: bool,
+    /// Raw arguments passed on to uv (unsupported).
+    #[serde(skip)]
+    pub uv_args: Vec<String>,
 }
 
 impl LockOptions {
     /// Writes the lock options as header.
     pub fn write_header<W: Write>(&self, mut w: W) -> Result<(), Error> {
-        writeln!(w, "{}", render!(REQUIREMENTS_HEADER, lock_options => self))?;
+        let metadata = match self.header {
+            LockHeader::None => return Ok(()),
+            LockHeader::Minimal => None,
+            LockHeader::Full => Some(LockMetadata {
+                rye_version: env!("CARGO_PKG_VERSION"),
+                uv_version: UvDownload::try_from(UvRequest::default())
+                    .ok()
+                    .map(|x| x.version()),
+                locked_at: format_timestamp(SystemTime::now()),
+                exclude_newer: env::var("__RYE_UV_EXCLUDE_NEWER").ok(),
+            }),
+        };
+        writeln!(
+            w,
+            "{}",
+            render!(REQUIREMENTS_HEADER, lock_options => self, metadata => metadata)
+        )?;
         Ok(())
     }
 
@@ -168,17 +389,53 @@ pub fn update_workspace_lockfile(
     sources: &ExpandedSources,
     lock_options: &LockOptions,
     keyring_provider: KeyringProvider,
+) -> Result<(), Error> {
+    update_workspace_lockfile_with(
+        py_ver,
+        workspace,
+        None,
+        lock_mode,
+        lockfile,
+        output,
+        sources,
+        lock_options,
+        keyring_provider,
+    )
+}
+
+/// Like [`update_workspace_lockfile`] but locks `replacement` instead of the
+/// workspace member with the same `pyproject.toml` as it is on disk.
+#[allow(clippy::too_many_arguments)]
+pub fn update_workspace_lockfile_with(
+    py_ver: &PythonVersion,
+    workspace: &Arc<Workspace>,
+    mut replacement: Option<PyProject>,
+    lock_mode: LockMode,
+    lockfile: &Path,
+    output: CommandOutput,
+    sources: &ExpandedSources,
+    lock_options: &LockOptions,
+    keyring_provider: KeyringProvider,
 ) -> Result<(), Error> {
     echo!(if output, "Generating {} lockfile: {}", lock_mode, lockfile.display());
 
-    let lock_options = restore_lock_options(lockfile, lock_options)?;
+    let mut lock_options = restore_lock_options(lockfile, lock_options)?;
+    if lock_mode == LockMode::Production && !lock_options.dev_presets.is_empty() {
+        lock_options.to_mut().dev_presets.clear();
+    }
     let features_by_project = collect_workspace_features(&lock_options);
     let mut req_file = NamedTempFile::new()?;
 
     let mut local_projects = HashMap::new();
     let mut projects = Vec::new();
-    for pyproject_result in workspace.iter_projects() {
-        let pyproject = pyproject_result?;
+    for pyproject_result in workspace.iter_venv_projects() {
+        let mut pyproject = pyproject_result?;
+        if replacement
+            .as_ref()
+            .map_or(false, |x| x.toml_path() == pyproject.toml_path())
+        {
+            pyproject = replacement.take().unwrap();
+        }
         let rel_url = make_relative_url(&pyproject.root_path(), &workspace.path())?;
         let applicable_extras = format_project_extras(features_by_project.as_ref(), &pyproject)?;
 
@@ -192,6 +449,14 @@ pub fn update_workspace_lockfile(
     }
 
     for pyproject in &projects {
+        if pyproject.is_virtual() {
+            dump_virtual_features(
+                pyproject,
+                &enabled_features(features_by_project.as_ref(), pyproject)?,
+                &local_projects,
+                req_file.as_file_mut(),
+            )?;
+        }
         dump_dependencies(
             pyproject,
             &local_projects,
@@ -207,6 +472,7 @@ pub fn update_workspace_lockfile(
             )?;
         }
     }
+    dump_preset_dependencies(&lock_options.dev_presets, req_file.as_file_mut())?;
 
     req_file.flush()?;
 
@@ -224,9 +490,252 @@ pub fn update_workspace_lockfile(
         keyring_provider,
     )?;
 
+    if lock_mode == LockMode::Production && lock_options.member_constraints {
+        write_member_constraints(&workspace.path(), &projects, lockfile, output)?;
+    }
+
     Ok(())
 }
 
+/// Writes a pip-tools style constraints file per workspace member.
+///
+/// Each file lives in `constraints/<member>.txt` and pins the packages of the
+/// lockfile that the member depends on, directly or transitively.  All files
+/// are written before any of them replaces the old one, and files of members
+/// that no longer exist are removed.
+fn write_member_constraints(
+    workspace_path: &Path,
+    projects: &[PyProject],
+    lockfile: &Path,
+    output: CommandOutput,
+) -> Result<(), Error> {
+    let contents =
+        fs::read_to_string(lockfile).path_context(lockfile, "could not read lockfile")?;
+    let dir = workspace_path.join("constraints");
+    fs::create_dir_all(&dir).path_context(&dir, "unable to create constraints folder")?;
+
+    let local_projects = projects
+        .iter()
+        .map(|x| x.normalized_name())
+        .collect::<Result<HashSet<_>, _>>()?;
+    let lockfile_name = lockfile.file_name().unwrap_or_default().to_string_lossy();
+
+    let mut files = Vec::new();
+    for project in projects {
+        let member = project.normalized_name()?;
+        let mut roots = HashSet::from([member.clone()]);
+        for dep in project.iter_dependencies(DependencyKind::Normal) {
+            if let Ok(req) = dep.expand(|_| Some("VARIABLE".into())) {
+                roots.insert(normalize_package_name(&req.name));
+            }
+        }
+
+        let mut file = NamedTempFile::new_in(&dir)?;
+        writeln!(
+            file,
+            "{}",
+            render!(CONSTRAINTS_HEADER, member, lockfile => lockfile_name)
+        )?;
+        for line in member_constraints(&contents, &roots, &local_projects) {
+            writeln!(file, "{}", line)?;
+        }
+        file.flush()?;
+        files.push((dir.join(format!("{}.txt", member)), file));
+    }
+
+    for (path, file) in files {
+        file.persist(&path)
+            .map_err(|err| err.error)
+            .path_context(&path, "unable to write constraints file")?;
+        echo!(if verbose output, "Wrote constraints file: {}", path.display());
+    }
+
+    // remove the constraints of former members, but never files we did not write
+    for entry in fs::read_dir(&dir).path_context(&dir, "unable to enumerate constraints")? {
+        let path = entry?.path();
+        let is_member = path.file_stem().map_or(false, |x| {
+            local_projects.contains(x.to_string_lossy().as_ref())
+        });
+        if !is_member
+            && path.extension().map_or(false, |x| x == "txt")
+            && fs::read_to_string(&path).map_or(false, |x| x.starts_with("# generated by rye"))
+        {
+            fs::remove_file(&path).path_context(&path, "unable to remove constraints file")?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The dependency graph of a lockfile.
+///
+/// The graph is reconstructed from the `# via` comments of the lockfile, so
+/// no resolver is needed to inspect it.
+pub struct LockGraph {
+    /// The pinned requirements by normalized name in lockfile order.
+    requirements: Vec<(String, String)>,
+    /// The packages a package requires by normalized name.
+    dependencies: HashMap<String, Vec<String>>,
+    /// The packages a package is required by by normalized name.
+    required_by: HashMap<String, Vec<String>>,
+}
+
+impl LockGraph {
+    /// Parses the dependency graph of a lockfile.
+    pub fn parse(contents: &str) -> LockGraph {
+        let mut requirements = Vec::new();
+        let mut dependencies = HashMap::<String, Vec<String>>::new();
+        let mut required_by = HashMap::<String, Vec<String>>::new();
+        let mut current = None;
+        for line in contents.lines() {
+            if let Some(m) = DEP_COMMENT_RE.captures(line) {
+                if let (Some(name), Some(dep)) = (&current, m.get(1).or_else(|| m.get(2))) {
+                    let dep = normalize_package_name(dep.as_str());
+                    dependencies
+                        .entry(dep.clone())
+                        .or_default()
+                        .push(name.clone());
+                    required_by.entry(name.clone()).or_default().push(dep);
+                }
+                continue;
+            }
+            if line.starts_with(' ') {
+                continue;
+            }
+            current = None;
+            if line.is_empty() || line.starts_with(['#', '-']) {
+                continue;
+            }
+            let stripped = line.trim_end_matches('\\').trim();
+            if let Ok(req) = stripped.parse::<Requirement>() {
+                let name = normalize_package_name(&req.name);
+                requirements.push((name.clone(), stripped.to_string()));
+                current = Some(name);
+            }
+        }
+        LockGraph {
+            requirements,
+            dependencies,
+            required_by,
+        }
+    }
+
+    /// Checks if a package is in the lockfile.
+    ///
+    /// Local projects are not pinned but show up as the reason other packages
+    /// are locked.
+    pub fn contains(&self, name: &str) -> bool {
+        let name = normalize_package_name(name);
+        self.requirements
+            .iter()
+            .any(|(it_name, _)| *it_name == name)
+            || self.dependencies.contains_key(&name)
+    }
+
+    /// Returns the pinned requirement of a package.
+    pub fn requirement(&self, name: &str) -> Option<&str> {
+        let name = normalize_package_name(name);
+        self.requirements
+            .iter()
+            .find(|(it_name, _)| *it_name == name)
+            .map(|(_, line)| line.as_str())
+    }
+
+    /// Returns the packages that directly require the given package.
+    pub fn required_by(&self, name: &str) -> &[String] {
+        self.required_by
+            .get(&normalize_package_name(name))
+            .map_or(&[], |x| x.as_slice())
+    }
+
+    /// Returns the packages the given package directly depends on.
+    pub fn dependencies(&self, name: &str) -> &[String] {
+        self.dependencies
+            .get(&normalize_package_name(name))
+            .map_or(&[], |x| x.as_slice())
+    }
+
+    /// Returns the packages that nothing else in the lockfile requires.
+    ///
+    /// These are typically the local projects.
+    pub fn roots(&self) -> Vec<String> {
+        let mut rv = self
+            .requirements
+            .iter()
+            .map(|x| &x.0)
+            .chain(self.dependencies.keys())
+            .filter(|x| !self.required_by.contains_key(*x))
+            .cloned()
+            .collect::<Vec<_>>();
+        rv.sort();
+        rv.dedup();
+        rv
+    }
+
+    /// Returns the packages the given packages depend on, directly or
+    /// transitively, including the packages themselves.
+    pub fn reachable_from(&self, roots: &HashSet<String>) -> BTreeSet<String> {
+        let mut reachable = BTreeSet::new();
+        let mut stack = roots.iter().cloned().collect::<Vec<_>>();
+        while let Some(name) = stack.pop() {
+            if reachable.insert(name.clone()) {
+                stack.extend(self.dependencies.get(&name).into_iter().flatten().cloned());
+            }
+        }
+        reachable
+    }
+
+    /// Returns the chains of packages through which a package is required.
+    ///
+    /// Each chain starts with a package that nothing else in the lockfile
+    /// requires (typically a local project) and ends with the package itself.
+    pub fn why(&self, name: &str) -> Vec<Vec<String>> {
+        fn walk(graph: &LockGraph, chain: &mut Vec<String>, chains: &mut BTreeSet<Vec<String>>) {
+            let name = chain.last().unwrap();
+            let dependents = graph
+                .required_by
+                .get(name)
+                .into_iter()
+                .flatten()
+                .filter(|x| !chain.contains(x))
+                .cloned()
+                .collect::<Vec<_>>();
+            if dependents.is_empty() {
+                chains.insert(chain.iter().rev().cloned().collect());
+            }
+            for dependent in dependents {
+                chain.push(dependent);
+                walk(graph, chain, chains);
+                chain.pop();
+            }
+        }
+
+        let mut chains = BTreeSet::new();
+        walk(self, &mut vec![normalize_package_name(name)], &mut chains);
+        chains.into_iter().collect()
+    }
+}
+
+/// Returns the pinned requirements of a lockfile that are reachable from the
+/// given roots.
+///
+/// Local projects are followed but not emitted as they cannot be constrained,
+/// and hashes are dropped.
+fn member_constraints(
+    contents: &str,
+    roots: &HashSet<String>,
+    local_projects: &HashSet<String>,
+) -> Vec<String> {
+    let graph = LockGraph::parse(contents);
+    let reachable = graph.reachable_from(roots);
+    graph
+        .requirements
+        .into_iter()
+        .filter(|(name, _)| reachable.contains(name) && !local_projects.contains(name))
+        .map(|(_, line)| line)
+        .collect()
+}
+
 /// Tries to restore the lock options from the given lockfile.
 fn restore_lock_options<'o>(
     lockfile: &Path,
@@ -240,11 +749,12 @@ fn restore_lock_options<'o>(
     }
 }
 
-fn format_project_extras<'a>(
+/// Returns the features (extras) of a project that should be enabled.
+fn enabled_features<'a>(
     features_by_project: Option<&'a HashMap<String, HashSet<&str>>>,
-    project: &PyProject,
-) -> Result<Cow<'a, str>, Error> {
-    let features: Vec<_> = match features_by_project {
+    project: &'a PyProject,
+) -> Result<Vec<&'a str>, Error> {
+    Ok(match features_by_project {
         Some(features_by_project) => features_by_project
             .get(&project.normalized_name()?)
             .map_or(None.into_iter(), |x| Some(x.iter().copied()).into_iter())
@@ -260,7 +770,14 @@ fn format_project_extras<'a>(
             })
             .collect(),
         None => project.extras().iter().copied().collect(),
-    };
+    })
+}
+
+fn format_project_extras<'a>(
+    features_by_project: Option<&'a HashMap<String, HashSet<&str>>>,
+    project: &PyProject,
+) -> Result<Cow<'a, str>, Error> {
+    let features = enabled_features(features_by_project, project)?;
     Ok(if features.is_empty() {
         Cow::Borrowed("")
     } else {
@@ -319,23 +836,76 @@ fn dump_dependencies(
     dep_kind: DependencyKind,
 ) -> Result<(), Error> {
     for dep in pyproject.iter_dependencies(dep_kind) {
-        if let Ok(expanded_dep) = dep.expand(|_| {
-            // we actually do not care what it expands to much, for as long
-            // as the end result parses
-            Some("VARIABLE".into())
-        }) {
-            if let Some(path) = local_projects.get(&normalize_package_name(&expanded_dep.name)) {
-                // if there are extras and we have a local dependency, we just write it
-                // out again for pip-compile to pick up the extras.
-                // XXX: this drops the marker, but pip-compile already has other
-                // problems with markers too: https://github.com/jazzband/pip-tools/issues/826
-                if let Some(ref extras) = expanded_dep.extras {
-                    writeln!(out, "-e {}[{}]", path, extras.join(","))?;
+        dump_dependency(&dep, local_projects, out)?;
+    }
+    Ok(())
+}
+
+/// Writes the dev-dependencies of the presets (only set for dev lockfiles).
+fn dump_preset_dependencies(presets: &[DevPreset], out: &mut fs::File) -> Result<(), Error> {
+    for dep in presets.iter().flat_map(|x| x.dev_dependencies.iter()) {
+        writeln!(out, "{}", dep)?;
+    }
+    Ok(())
+}
+
+fn dump_dependency(
+    dep: &DependencyRef,
+    local_projects: &HashMap<String, String>,
+    out: &mut fs::File,
+) -> Result<(), Error> {
+    if let Ok(expanded_dep) = dep.expand(|_| {
+        // we actually do not care what it expands to much, for as long
+        // as the end result parses
+        Some("VARIABLE".into())
+    }) {
+        if let Some(path) = local_projects.get(&normalize_package_name(&expanded_dep.name)) {
+            // if there are extras and we have a local dependency, we just write it
+            // out again for pip-compile to pick up the extras.
+            // XXX: this drops the marker, but pip-compile already has other
+            // problems with markers too: https://github.com/jazzband/pip-tools/issues/826
+            if let Some(ref extras) = expanded_dep.extras {
+                writeln!(out, "-e {}[{}]", path, extras.join(","))?;
+            }
+            return Ok(());
+        }
+    }
+    writeln!(out, "{}", dep)?;
+    Ok(())
+}
+
+/// Writes the requirements of the enabled features of a virtual project.
+///
+/// Virtual projects are not installed, so their extras cannot be requested
+/// the usual way.  Instead the requirements of the features are written out
+/// directly, expanding features that refer to other features of the project.
+fn dump_virtual_features(
+    pyproject: &PyProject,
+    features: &[&str],
+    local_projects: &HashMap<String, String>,
+    out: &mut fs::File,
+) -> Result<(), Error> {
+    let name = pyproject.normalized_name()?;
+    let extras = pyproject.extras();
+    let mut pending = features.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+    let mut seen = HashSet::new();
+    while let Some(feature) = pending.pop() {
+        if !seen.insert(feature.clone()) {
+            continue;
+        }
+        if !extras.contains(feature.as_str()) {
+            warn!("project {} does not have a feature named {}", name, feature);
+            continue;
+        }
+        for dep in pyproject.iter_dependencies(DependencyKind::Optional(feature.as_str().into())) {
+            if let Ok(req) = dep.expand(|_| Some("VARIABLE".into())) {
+                if normalize_package_name(&req.name) == name {
+                    pending.extend(req.extras.into_iter().flatten());
+                    continue;
                 }
-                continue;
             }
+            dump_dependency(&dep, local_projects, out)?;
         }
-        writeln!(out, "{}", dep)?;
     }
     Ok(())
 }
@@ -354,12 +924,23 @@ pub fn update_single_project_lockfile(
 ) -> Result<(), Error> {
     echo!(if output, "Generating {} lockfile: {}", lock_mode, lockfile.display());
 
-    let lock_options = restore_lock_options(lockfile, lock_options)?;
+    let mut lock_options = restore_lock_options(lockfile, lock_options)?;
+    if lock_mode == LockMode::Production && !lock_options.dev_presets.is_empty() {
+        lock_options.to_mut().dev_presets.clear();
+    }
     let mut req_file = NamedTempFile::new()?;
 
-    // virtual packages are themselves not installed
-    if !pyproject.is_virtual() {
-        let features_by_project = collect_workspace_features(&lock_options);
+    // virtual packages are themselves not installed, only the requirements
+    // of their features are.
+    let features_by_project = collect_workspace_features(&lock_options);
+    if pyproject.is_virtual() {
+        dump_virtual_features(
+            pyproject,
+            &enabled_features(features_by_project.as_ref(), pyproject)?,
+            &HashMap::new(),
+            req_file.as_file_mut(),
+        )?;
+    } else {
         let applicable_extras = format_project_extras(features_by_project.as_ref(), pyproject)?;
         writeln!(
             req_file,
@@ -377,6 +958,7 @@ pub fn update_single_project_lockfile(
             writeln!(req_file, "{}", dep)?;
         }
     }
+    dump_preset_dependencies(&lock_options.dev_presets, req_file.as_file_mut())?;
 
     req_file.flush()?;
 
@@ -397,6 +979,96 @@ pub fn update_single_project_lockfile(
     Ok(())
 }
 
+/// Locks the build requirements of the local projects and of all source
+/// distributions pinned in the given lockfiles.
+#[allow(clippy::too_many_arguments)]
+pub fn update_build_requirements_lockfile(
+    py_ver: &PythonVersion,
+    pyproject: &PyProject,
+    lockfiles: &[&Path],
+    lockfile: &Path,
+    output: CommandOutput,
+    sources: &ExpandedSources,
+    lock_options: &LockOptions,
+    keyring_provider: KeyringProvider,
+) -> Result<(), Error> {
+    echo!(if output, "Generating build requirements lockfile: {}", lockfile.display());
+
+    let mut lock_options = restore_lock_options(lockfile, lock_options)?;
+    if !lock_options.dev_presets.is_empty() {
+        lock_options.to_mut().dev_presets.clear();
+    }
+
+    let mut requires = BTreeSet::new();
+    match pyproject.workspace() {
+        Some(workspace) => {
+            for project in workspace.iter_projects() {
+                let project = project?;
+                if !project.is_virtual() {
+                    requires.extend(project.build_requires());
+                }
+            }
+        }
+        None if !pyproject.is_virtual() => requires.extend(pyproject.build_requires()),
+        None => {}
+    }
+    requires.extend(sdist_build_requires(
+        lockfiles,
+        sources,
+        &lock_options.binary_policy,
+        output,
+    )?);
+
+    let mut req_file = NamedTempFile::new()?;
+    for req in &requires {
+        writeln!(req_file, "{}", req)?;
+    }
+    req_file.flush()?;
+
+    generate_lockfile(
+        output,
+        py_ver,
+        &pyproject.workspace_path(),
+        req_file.path(),
+        lockfile,
+        sources,
+        &lock_options,
+        &HashSet::new(),
+        false,
+        keyring_provider,
+    )
+}
+
+/// Locks a standalone requirements file that is not part of a project.
+///
+/// Relative references in the requirements file are resolved against the
+/// folder it is placed in.
+pub fn update_requirements_lockfile(
+    py_ver: &PythonVersion,
+    requirements_in: &Path,
+    lockfile: &Path,
+    output: CommandOutput,
+    sources: &ExpandedSources,
+    lock_options: &LockOptions,
+    keyring_provider: KeyringProvider,
+) -> Result<(), Error> {
+    echo!(if output, "Generating lockfile: {}", lockfile.display());
+
+    let lock_options = restore_lock_options(lockfile, lock_options)?;
+    generate_lockfile(
+        output,
+        py_ver,
+        requirements_in.parent().unwrap_or(Path::new(".")),
+        requirements_in,
+        lockfile,
+        sources,
+        &lock_options,
+        &HashSet::new(),
+        false,
+        keyring_provider,
+    )
+}
+
 #[allow(clippy::too_many_arguments)]
 fn generate_lockfile(
     output: CommandOutput,
@@ -417,7 +1089,19 @@ fn generate_lockfile(
             .path_context(&requirements_file, "unable to restore requirements file")?;
     };
 
-    let upgrade = {
+    let pins_file = if lock_options.pins.is_empty() {
+        None
+    } else {
+        let path = scratch.path().join("pins.txt");
+        let mut contents = String::new();
+        for pin in &lock_options.pins {
+            contents.push_str(&format!("{}\n", pin));
+        }
+        fs::write(&path, contents).path_context(&path, "unable to write pins")?;
+        Some(path)
+    };
+
+    let mut upgrade = {
         if lock_options.update_all {
             UvPackageUpgrade::All
         } else if !lock_options.update.is_empty() {
@@ -427,10 +1111,34 @@ fn generate_lockfile(
         }
     };
 
+    // yanked versions are kept by the resolver unless they are upgraded
+    if lock_options.refresh_yanked && requirements_file.is_file() {
+        let contents = fs::read_to_string(&requirements_file)
+            .path_context(&requirements_file, "unable to read lockfile")?;
+        let yanked = find_yanked(&contents, sources, output);
+        if !yanked.is_empty() {
+            echo!(
+                if output,
+                "Moving off yanked versions: {}",
+                yanked.iter().map(|x| x.name.as_str()).collect::<Vec<_>>().join(", ")
+            );
+            let names = yanked.into_iter().map(|x| x.name);
+            upgrade = match upgrade {
+                UvPackageUpgrade::All => UvPackageUpgrade::All,
+                UvPackageUpgrade::Packages(mut pkgs) => {
+                    pkgs.extend(names);
+                    UvPackageUpgrade::Packages(pkgs)
+                }
+                UvPackageUpgrade::Nothing => UvPackageUpgrade::Packages(names.collect()),
+            };
+        }
+    }
+
     UvBuilder::new()
         .with_output(output.quieter())
         .with_sources(sources.clone())
         .with_workdir(workspace_path)
+        .with_uv_args(&lock_options.uv_args)
         .ensure_exists()?
         .lockfile(
             py_ver,
@@ -442,12 +1150,51 @@ fn generate_lockfile(
             keyring_provider,
             lock_options.generate_hashes,
             lock_options.universal,
+            &lock_options.binary_policy,
+            pins_file.as_deref(),
         )?;
 
+    // looking up every locked package on the index is slow, so this only
+    // happens on request.
+    let yanked = if lock_options.refresh_yanked
+        || lock_options.deny_yanked
+        || Config::current().check_yanked()
+    {
+        let contents = fs::read_to_string(&requirements_file)
+            .path_context(&requirements_file, "unable to parse resolver output")?;
+        find_yanked(&contents, sources, output)
+    } else {
+        Vec::new()
+    };
+    for version in &yanked {
+        warn!(
+            category = Dependencies,
+            "{} in {} is yanked{}. Use `rye lock --refresh-yanked` to move off it",
+            version,
+            lockfile.file_name().unwrap_or_default().to_string_lossy(),
+            match version.reason {
+                Some(ref reason) => format!(" (reason: {})", reason),
+                None => String::new(),
+            }
+        );
+    }
+    if lock_options.deny_yanked && !yanked.is_empty() {
+        bail!(
+            "{} contains yanked versions: {}",
+            lockfile.display(),
+            yanked
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
     finalize_lockfile(
         &requirements_file,
         lockfile,
         workspace_path,
+        py_ver,
         exclusions,
         sources,
         lock_options,
@@ -456,10 +1203,86 @@ fn generate_lockfile(
     Ok(())
 }
 
+/// The environment a non universal lockfile is resolved for: the current
+/// platform with the Python version of the project.
+fn lock_target_environment(py_ver: &PythonVersion) -> Result<MarkerEnvironment, Error> {
+    let (sys_platform, os_name, platform_system) = match &*py_ver.os {
+        "windows" => ("win32", "nt", "Windows"),
+        "macos" => ("darwin", "posix", "Darwin"),
+        "linux" => ("linux", "posix", "Linux"),
+        other => (other, "posix", other),
+    };
+    let platform_machine = match (&*py_ver.os, &*py_ver.arch) {
+        ("macos", "aarch64") => "arm64",
+        ("windows", "x86_64") => "AMD64",
+        ("windows", "aarch64") => "ARM64",
+        (_, arch) => arch,
+    };
+    let version = |s: String| {
+        s.parse::<StringVersion>()
+            .map_err(|err| anyhow!("invalid Python version '{}': {}", s, err))
+    };
+    let full_version = version(py_ver.format_simple())?;
+    Ok(MarkerEnvironment {
+        implementation_name: py_ver.name.to_string(),
+        implementation_version: full_version.clone(),
+        os_name: os_name.into(),
+        platform_machine: platform_machine.into(),
+        platform_python_implementation: match &*py_ver.name {
+            "pypy" => "PyPy".into(),
+            _ => "CPython".into(),
+        },
+        platform_release: String::new(),
+        platform_system: platform_system.into(),
+        platform_version: String::new(),
+        python_full_version: full_version,
+        python_version: version(format!("{}.{}", py_ver.major, py_ver.minor))?,
+        sys_platform: sys_platform.into(),
+    })
+}
+
+/// Checks if a locked requirement is dropped by an exclusion.
+///
+/// An exclusion with markers only applies where the markers hold: in the
+/// target environment of the lockfile, or for universal lockfiles (no target)
+/// if the locked requirement is limited to environments the exclusion covers.
+fn is_excluded(
+    exclusion: &Requirement,
+    req: &Requirement,
+    target: Option<&MarkerEnvironment>,
+) -> bool {
+    if normalize_package_name(&exclusion.name) != normalize_package_name(&req.name)
+        || (exclusion.version_or_url.is_some() && exclusion.version_or_url != req.version_or_url)
+    {
+        return false;
+    }
+    match (&exclusion.marker, target, &req.marker) {
+        (None, _, _) => true,
+        (Some(marker), Some(env), _) => marker.evaluate(env, &[]),
+        (Some(marker), None, Some(req_marker)) => marker_implies(req_marker, marker),
+        (Some(_), None, None) => false,
+    }
+}
+
+/// Conservatively checks if marker `a` can only be true where `b` is true.
+fn marker_implies(a: &MarkerTree, b: &MarkerTree) -> bool {
+    if a == b {
+        return true;
+    }
+    match (a, b) {
+        (MarkerTree::Or(children), _) => children.iter().all(|x| marker_implies(x, b)),
+        (_, MarkerTree::And(children)) => children.iter().all(|x| marker_implies(a, x)),
+        (MarkerTree::And(children), _) => children.iter().any(|x| marker_implies(x, b)),
+        (_, MarkerTree::Or(children)) => children.iter().any(|x| marker_implies(a, x)),
+        _ => false,
+    }
+}
+
 fn finalize_lockfile(
     generated: &Path,
     out: &Path,
     workspace_root: &Path,
+    py_ver: &PythonVersion,
     exclusions: &HashSet<Requirement>,
     sources: &ExpandedSources,
     lock_options: &LockOptions,
@@ -474,11 +1297,19 @@ fn finalize_lockfile(
         writeln!(rv)?;
     }
 
+    let contents =
+        fs::read_to_string(generated).path_context(generated, "unable to parse resolver output")?;
+
+    // universal lockfiles are not locked for a single environment, there the
+    // markers of the locked requirements are compared instead.
+    let target = if lock_options.universal {
+        None
+    } else {
+        Some(lock_target_environment(py_ver)?)
+    };
+
     let mut exclude = false;
-    for line in fs::read_to_string(generated)
-        .path_context(generated, "unable to parse resolver output")?
-        .lines()
-    {
+    for line in contents.lines() {
         let trimmed = line.trim();
 
         // if we're in an exclusion, continue to skip lines
@@ -509,11 +1340,10 @@ fn finalize_lockfile(
                 continue;
             }
         } else if let Ok(ref req) = stripped.parse::<Requirement>() {
-            // TODO: this does not evaluate markers
-            if exclusions.iter().any(|x| {
-                normalize_package_name(&x.name) == normalize_package_name(&req.name)
-                    && (x.version_or_url.is_none() || x.version_or_url == req.version_or_url)
-            }) {
+            if exclusions
+                .iter()
+                .any(|x| is_excluded(x, req, target.as_ref()))
+            {
                 // skip exclusions.
                 writeln!(rv, "# {stripped} (excluded)")?;
 
@@ -542,6 +1372,108 @@ fn finalize_lockfile(
     Ok(())
 }
 
+/// Checks that an existing lockfile still satisfies the declared dependencies.
+///
+/// This is used when only one of the lockfiles is regenerated so that the
+/// other one is not silently left out of date.  Only direct dependencies are
+/// validated.  Dependencies with markers are skipped as they might legitimately
+/// not be part of the lockfile.
+pub fn check_lockfile_is_current(
+    lockfile: &Path,
+    projects: &[&PyProject],
+    lock_mode: LockMode,
+) -> Result<(), Error> {
+    let contents =
+        fs::read_to_string(lockfile).path_context(lockfile, "could not read lockfile")?;
+    let problems = find_lock_problems(&parse_locked_versions(&contents), projects, lock_mode);
+    if !problems.is_empty() {
+        bail!(
+            "{} lockfile {} is out of date:\n  - {}",
+            lock_mode,
+            lockfile.display(),
+            problems.join("\n  - ")
+        );
+    }
+    Ok(())
+}
+
+/// Returns the direct dependencies of the projects that the locked versions
+/// do not satisfy.
+pub fn find_lock_problems(
+    locked: &HashMap<String, Option<Version>>,
+    projects: &[&PyProject],
+    lock_mode: LockMode,
+) -> Vec<String> {
+    let local_projects = projects
+        .iter()
+        .filter_map(|x| x.normalized_name().ok())
+        .collect::<HashSet<_>>();
+
+    let mut problems = Vec::new();
+    for project in projects {
+        let deps = project.iter_dependencies(DependencyKind::Normal).chain(
+            (lock_mode == LockMode::Dev)
+                .then(|| project.iter_dependencies(DependencyKind::Dev))
+                .into_iter()
+                .flatten(),
+        );
+        for dep in deps {
+            let Ok(req) = dep.expand(|name: &str| {
+                if name == "PROJECT_ROOT" {
+                    Some(project.workspace_path().to_string_lossy().to_string())
+                } else {
+                    std::env::var(name).ok()
+                }
+            }) else {
+                continue;
+            };
+            let name = normalize_package_name(&req.name);
+            if req.marker.is_some() || local_projects.contains(&name) {
+                continue;
+            }
+            match (locked.get(&name), &req.version_or_url) {
+                (None, _) => problems.push(format!("{} is not locked", req.name)),
+                (Some(Some(version)), Some(VersionOrUrl::VersionSpecifier(specs)))
+                    if !specs.contains(version) =>
+                {
+                    problems.push(format!(
+                        "{} is locked at {} which does not satisfy {}",
+                        req.name, version, specs
+                    ));
+                }
+                _ => {}
+            }
+        }
+    }
+    problems
+}
+
+/// Returns the pinned versions of all packages in a lockfile by normalized name.
+///
+/// Packages that are not pinned to an exact version (eg: URLs) map to `None`.
+pub fn parse_locked_versions(contents: &str) -> HashMap<String, Option<Version>> {
+    contents
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with(['#', '-', ' ']))
+        .filter_map(|line| {
+            line.trim_end_matches('\\')
+                .trim()
+                .parse::<Requirement>()
+                .ok()
+        })
+        .map(|req| {
+            let version = match req.version_or_url {
+                Some(VersionOrUrl::VersionSpecifier(ref specs)) => specs
+                    .iter()
+                    .find(|x| *x.operator() == Operator::Equal)
+                    .map(|x| x.version().clone()),
+                _ => None,
+            };
+            (normalize_package_name(&req.name), version)
+        })
+        .collect()
+}
+
 pub fn make_project_root_fragment(root: &Path) -> String {
     // XXX: ${PROJECT_ROOT} is supposed to be used in the context of file:///
     // so let's make sure it is url escaped.  This is pretty hacky but
@@ -552,7 +1484,7 @@ pub fn make_project_root_fragment(root: &Path) -> String {
         .replace(' ', "%20")
 }
 
-fn make_relative_url(path: &Path, base: &Path) -> Result<String, Error> {
+pub fn make_relative_url(path: &Path, base: &Path) -> Result<String, Error> {
     // TODO: consider using ${PROJECT_ROOT} here which is what pdm does or make-req prints
     let rv = pathdiff::diff_paths(path, base).ok_or_else(|| {
         anyhow!(
@@ -590,3 +1522,205 @@ fn test_make_relativec_url() {
         "file:."
     );
 }
+
+#[test]
+fn test_parse_locked_versions() {
+    let versions = parse_locked_versions(
+        r#"# generated by rye
+-e file:.
+Flask==2.3.0
+    # via my-project
+colorama==0.4.6 ; sys_platform == 'win32'
+idna==3.7 \
+    --hash=sha256:abc
+"#,
+    );
+    assert_eq!(versions.len(), 3);
+    assert_eq!(versions["flask"], Some("2.3.0".parse().unwrap()));
+    assert_eq!(versions["colorama"], Some("0.4.6".parse().unwrap()));
+    assert_eq!(versions["idna"], Some("3.7".parse().unwrap()));
+}
+
+#[test]
+fn test_lock_header() {
+    let mut opts = LockOptions {
+        features: vec!["foo".into()],
+        ..Default::default()
+    };
+    let render = |opts: &LockOptions| {
+        let mut buf = Vec::new();
+        opts.write_header(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    };
+
+    let minimal = render(&opts);
+    assert!(minimal.contains("#   features: [\"foo\"]\n"));
+    assert!(!minimal.contains("rye-version"));
+
+    opts.header = LockHeader::Full;
+    let full = render(&opts);
+    assert!(full.starts_with(minimal.trim_end()));
+    assert!(full.contains(&format!("#   rye-version: {}\n", env!("CARGO_PKG_VERSION"))));
+    assert!(full.contains("#   locked-at: "));
+
+    // the flags can still be restored from a full header
+    let restored = LockOptions::restore(&full, &LockOptions::default()).unwrap();
+    assert_eq!(restored.features, vec!["foo".to_string()]);
+
+    opts.header = LockHeader::None;
+    assert_eq!(render(&opts), "");
+
+    // binary policies are only recorded when set
+    opts.header = LockHeader::Minimal;
+    assert!(!render(&opts).contains("binary"));
+    opts.binary_policy = BinaryPolicy {
+        no_binary: vec!["cryptography".into()],
+        only_binary: vec![":all:".into()],
+    };
+    let with_policy = render(&opts);
+    assert!(with_policy.contains("#   no-binary: [\"cryptography\"]\n"));
+    assert!(with_policy.contains("#   only-binary: [\":all:\"]\n"));
+}
+
+#[test]
+fn test_member_constraints() {
+    let contents = r#"# generated by rye
+-e file:packages/a
+-e file:packages/b
+anyio==4.4.0 \
+    --hash=sha256:abc
+    # via httpx
+certifi==2024.7.4
+    # via httpx
+    # via requests
+httpx==0.27.0
+    # via a
+idna==3.7
+    # via anyio
+    # via requests
+requests==2.32.3
+    # via b
+sniffio==1.3.1 ; sys_platform != 'emscripten'
+    # via anyio
+"#;
+    let local_projects = HashSet::from(["a".to_string(), "b".to_string()]);
+    assert_eq!(
+        member_constraints(contents, &HashSet::from(["a".into()]), &local_projects),
+        vec![
+            "anyio==4.4.0",
+            "certifi==2024.7.4",
+            "httpx==0.27.0",
+            "idna==3.7",
+            "sniffio==1.3.1 ; sys_platform != 'emscripten'",
+        ]
+    );
+    assert_eq!(
+        member_constraints(contents, &HashSet::from(["b".into()]), &local_projects),
+        vec!["certifi==2024.7.4", "idna==3.7", "requests==2.32.3"]
+    );
+}
+
+#[test]
+fn test_lock_graph_why() {
+    let graph = LockGraph::parse(
+        r#"# generated by rye
+-e file:packages/a
+-e file:packages/b
+anyio==4.4.0
+    # via httpx
+httpx==0.27.0
+    # via a
+idna==3.7
+    # via
+    #   anyio
+    #   requests
+requests==2.32.3
+    # via b
+"#,
+    );
+    assert!(graph.contains("IDNA"));
+    assert!(graph.contains("a"));
+    assert!(!graph.contains("flask"));
+    assert_eq!(graph.requirement("idna"), Some("idna==3.7"));
+    assert_eq!(graph.requirement("a"), None);
+    assert_eq!(
+        graph.why("idna"),
+        vec![
+            vec!["a", "httpx", "anyio", "idna"],
+            vec!["b", "requests", "idna"],
+        ]
+    );
+    assert_eq!(graph.why("a"), vec![vec!["a"]]);
+}
+
+#[test]
+fn test_pin_expiry() {
+    let mut pin = Pin {
+        name: "numpy".into(),
+        version: "<2".into(),
+        expires: None,
+        reason: None,
+    };
+    assert_eq!(pin.to_string(), "numpy<2");
+    assert!(!pin.is_expired("2024-06-01"));
+    pin.expires = Some("2025-01-01".into());
+    assert!(!pin.is_expired("2024-12-31"));
+    assert!(pin.is_expired("2025-01-01"));
+    assert!(check_expired_pins(std::slice::from_ref(&pin), true).is_err());
+}
+
+#[test]
+fn test_exclusion_markers() {
+    let py_ver = |os: &'static str| PythonVersion {
+        name: "cpython".into(),
+        arch: "x86_64".into(),
+        os: os.into(),
+        major: 3,
+        minor: 12,
+        patch: 8,
+        suffix: None,
+    };
+    let linux = lock_target_environment(&py_ver("linux")).unwrap();
+    let windows = lock_target_environment(&py_ver("windows")).unwrap();
+    let req = |s: &str| s.parse::<Requirement>().unwrap();
+
+    // exclusions without markers apply everywhere
+    let exclusion = req("pywin32");
+    assert!(is_excluded(&exclusion, &req("pywin32==306"), Some(&linux)));
+    assert!(is_excluded(&exclusion, &req("pywin32==306"), None));
+    assert!(!is_excluded(&exclusion, &req("six==1.16.0"), Some(&linux)));
+
+    // platform specific exclusions only apply on the matching target
+    let exclusion = req("pywin32; sys_platform == 'win32'");
+    assert!(is_excluded(
+        &exclusion,
+        &req("pywin32==306"),
+        Some(&windows)
+    ));
+    assert!(!is_excluded(&exclusion, &req("pywin32==306"), Some(&linux)));
+    let exclusion = req("uvloop; python_version < '3.12'");
+    assert!(!is_excluded(
+        &exclusion,
+        &req("uvloop==0.19.0"),
+        Some(&linux)
+    ));
+
+    // universal lockfiles keep entries the exclusion does not fully cover
+    let exclusion = req("colorama; sys_platform == 'win32'");
+    assert!(is_excluded(
+        &exclusion,
+        &req("colorama==0.4.6 ; sys_platform == \"win32\""),
+        None
+    ));
+    assert!(is_excluded(
+        &exclusion,
+        &req("colorama==0.4.6 ; sys_platform == 'win32' and python_version >= '3.8'"),
+        None
+    ));
+    assert!(!is_excluded(&exclusion, &req("colorama==0.4.6"), None));
+    assert!(!is_excluded(
+        &exclusion,
+        &req("colorama==0.4.6 ; sys_platform == 'win32' or os_name == 'nt'"),
+        None
+    ));
+}
@@ -0,0 +1,493 @@
+use std::fmt::Write;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::{anyhow, bail, Context, Error};
+use clap::ValueEnum;
+use pep440_rs::Operator;
+use pep508_rs::{Requirement, VersionOrUrl};
+use toml_edit::{DocumentMut, Item, TableLike};
+
+use crate::pyproject::normalize_package_name;
+
+/// The lockfile formats that can be imported.
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+#[value(rename_all = "kebab-case")]
+pub enum LockFormat {
+    /// A fully pinned requirements file as generated by `pip-compile`.
+    PipTools,
+    /// A `poetry.lock` file.
+    Poetry,
+    /// A `pdm.lock` file.
+    Pdm,
+}
+
+impl LockFormat {
+    /// Guesses the format from the name of the file.
+    pub fn from_path(path: &Path) -> LockFormat {
+        match path.file_name().and_then(|x| x.to_str()) {
+            Some("poetry.lock") => LockFormat::Poetry,
+            Some("pdm.lock") => LockFormat::Pdm,
+            _ => LockFormat::PipTools,
+        }
+    }
+}
+
+/// A single pinned package of an imported lockfile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LockedPackage {
+    /// The normalized name of the package.
+    pub name: String,
+    /// The requirement as it goes into the lockfile (`name==1.0`, `name @ url`).
+    pub requirement: String,
+    /// Hashes in `algorithm:digest` form.
+    pub hashes: Vec<String>,
+}
+
+/// The result of importing a foreign lockfile.
+#[derive(Debug, Default)]
+pub struct ImportedLock {
+    /// The pinned packages, sorted by name.
+    pub packages: Vec<LockedPackage>,
+    /// Things that could not be imported and need manual attention.
+    pub follow_ups: Vec<String>,
+}
+
+impl ImportedLock {
+    /// Are there any hashes in the lock?
+    pub fn has_hashes(&self) -> bool {
+        self.packages.iter().any(|x| !x.hashes.is_empty())
+    }
+
+    /// Renders the pinned packages in the format of a rye lockfile.
+    pub fn render_packages(&self) -> String {
+        let mut rv = String::new();
+        for package in &self.packages {
+            rv.push_str(&package.requirement);
+            for hash in &package.hashes {
+                write!(rv, " \\\n    --hash={}", hash).unwrap();
+            }
+            rv.push('\n');
+        }
+        rv
+    }
+
+    fn add(&mut self, package: LockedPackage) {
+        // pdm lists packages with extras as separate entries
+        match self.packages.iter_mut().find(|x| x.name == package.name) {
+            Some(existing) if existing.requirement == package.requirement => {
+                for hash in package.hashes {
+                    if !existing.hashes.contains(&hash) {
+                        existing.hashes.push(hash);
+                    }
+                }
+            }
+            Some(existing) => self.follow_ups.push(format!(
+                "{} is locked more than once, kept {}",
+                package.name, existing.requirement
+            )),
+            None => self.packages.push(package),
+        }
+    }
+
+    fn finish(mut self) -> ImportedLock {
+        self.packages.sort_by(|a, b| a.name.cmp(&b.name));
+        for package in &mut self.packages {
+            package.hashes.sort();
+        }
+        let missing = self.packages.iter().filter(|x| x.hashes.is_empty()).count();
+        if missing > 0 && missing < self.packages.len() {
+            self.follow_ups.push(format!(
+                "{} package(s) have no hashes, installing with hashes will fail for them",
+                missing
+            ));
+        }
+        self
+    }
+}
+
+/// Imports the pins of a pip-tools, Poetry or PDM lockfile.
+///
+/// Packages that are only needed for development are skipped unless
+/// `include_dev` is set.  Requirements files carry no such information so all
+/// their packages are imported.
+pub fn import_lockfile(
+    contents: &str,
+    format: LockFormat,
+    include_dev: bool,
+) -> Result<ImportedLock, Error> {
+    let rv = match format {
+        LockFormat::PipTools => import_requirements(contents)?,
+        LockFormat::Poetry => import_poetry_lock(contents, include_dev)?,
+        LockFormat::Pdm => import_pdm_lock(contents, include_dev)?,
+    };
+    Ok(rv.finish())
+}
+
+fn import_requirements(contents: &str) -> Result<ImportedLock, Error> {
+    let mut rv = ImportedLock::default();
+
+    // join continuation lines first so that hashes end up with their requirement
+    let mut logical_lines = Vec::new();
+    let mut current = String::new();
+    for line in contents.lines() {
+        let line = match line.find(" #") {
+            Some(idx) => &line[..idx],
+            None if line.trim_start().starts_with('#') => "",
+            None => line,
+        };
+        match line.trim_end().strip_suffix('\\') {
+            Some(rest) => {
+                current.push_str(rest);
+                current.push(' ');
+            }
+            None => {
+                current.push_str(line);
+                logical_lines.push(std::mem::take(&mut current));
+            }
+        }
+    }
+    logical_lines.push(current);
+
+    for line in logical_lines {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with("-e ") || line.starts_with("--editable") {
+            rv.follow_ups
+                .push(format!("editable requirement '{}' was skipped", line));
+            continue;
+        }
+        if line.starts_with('-') {
+            // index urls and other options are configured through sources
+            rv.follow_ups.push(format!("option '{}' was skipped", line));
+            continue;
+        }
+        let mut parts = line.split_whitespace().peekable();
+        let mut req_str = String::new();
+        while let Some(part) = parts.next_if(|x| !x.starts_with("--hash")) {
+            if !req_str.is_empty() {
+                req_str.push(' ');
+            }
+            req_str.push_str(part);
+        }
+        let hashes = parts
+            .filter_map(|x| x.strip_prefix("--hash="))
+            .map(|x| x.to_string())
+            .collect();
+        let req = Requirement::from_str(&req_str)
+            .with_context(|| format!("invalid requirement '{}'", req_str))?;
+        if !is_pinned(&req) {
+            bail!("'{}' is not pinned to an exact version", req_str);
+        }
+        rv.add(LockedPackage {
+            name: normalize_package_name(&req.name),
+            requirement: req.to_string(),
+            hashes,
+        });
+    }
+
+    Ok(rv)
+}
+
+fn import_poetry_lock(contents: &str, include_dev: bool) -> Result<ImportedLock, Error> {
+    let doc = contents
+        .parse::<DocumentMut>()
+        .context("failed to parse poetry.lock")?;
+    let mut rv = ImportedLock::default();
+    let legacy_files = doc
+        .get("metadata")
+        .and_then(|x| x.get("files"))
+        .and_then(|x| x.as_table_like());
+    let mut missing_groups = false;
+
+    for package in iter_packages(&doc)? {
+        let (name, version) = name_and_version(package)?;
+        let groups = get_strings(package, "groups");
+        let is_dev = match package.get("category").and_then(|x| x.as_str()) {
+            Some(category) => category == "dev",
+            None if !groups.is_empty() => !groups.iter().any(|x| x == "main"),
+            None => {
+                missing_groups = true;
+                false
+            }
+        };
+        if is_dev && !include_dev {
+            continue;
+        }
+
+        let source = package.get("source").and_then(|x| x.as_table_like());
+        let pin = match source.and_then(|x| x.get("type")).and_then(|x| x.as_str()) {
+            None | Some("legacy") => format!("=={}", version),
+            Some("git") => {
+                let source = source.unwrap();
+                let url = source.get("url").and_then(|x| x.as_str()).unwrap_or("");
+                let rev = source
+                    .get("resolved_reference")
+                    .or_else(|| source.get("reference"))
+                    .and_then(|x| x.as_str());
+                match rev {
+                    Some(rev) => format!(" @ git+{}@{}", url, rev),
+                    None => format!(" @ git+{}", url),
+                }
+            }
+            Some("url") => format!(
+                " @ {}",
+                source
+                    .unwrap()
+                    .get("url")
+                    .and_then(|x| x.as_str())
+                    .unwrap_or("")
+            ),
+            Some(other) => {
+                rv.follow_ups
+                    .push(format!("{} ({} source) was skipped", name, other));
+                continue;
+            }
+        };
+
+        let files = package.get("files").or_else(|| legacy_files?.get(name));
+        rv.add(LockedPackage {
+            name: normalize_package_name(name),
+            requirement: with_marker(
+                format!("{}{}", name, pin),
+                package.get("markers").and_then(|x| x.as_str()),
+            ),
+            hashes: collect_file_hashes(files),
+        });
+    }
+
+    if missing_groups && !include_dev {
+        rv.follow_ups.push(
+            "the lockfile does not record dependency groups, development packages were imported too"
+                .into(),
+        );
+    }
+    Ok(rv)
+}
+
+fn import_pdm_lock(contents: &str, include_dev: bool) -> Result<ImportedLock, Error> {
+    let doc = contents
+        .parse::<DocumentMut>()
+        .context("failed to parse pdm.lock")?;
+    let mut rv = ImportedLock::default();
+
+    for package in iter_packages(&doc)? {
+        let (name, version) = name_and_version(package)?;
+        let groups = get_strings(package, "groups");
+        if !include_dev && !groups.is_empty() && !groups.iter().any(|x| x == "default") {
+            continue;
+        }
+
+        let pin = if let Some(git) = package.get("git").and_then(|x| x.as_str()) {
+            match package.get("revision").and_then(|x| x.as_str()) {
+                Some(rev) => format!(" @ git+{}@{}", git, rev),
+                None => format!(" @ git+{}", git),
+            }
+        } else if let Some(url) = package.get("url").and_then(|x| x.as_str()) {
+            format!(" @ {}", url)
+        } else if package.get("path").is_some() {
+            rv.follow_ups
+                .push(format!("{} (local path) was skipped", name));
+            continue;
+        } else {
+            format!("=={}", version)
+        };
+
+        rv.add(LockedPackage {
+            name: normalize_package_name(name),
+            requirement: with_marker(
+                format!("{}{}", name, pin),
+                package.get("marker").and_then(|x| x.as_str()),
+            ),
+            hashes: collect_file_hashes(package.get("files")),
+        });
+    }
+
+    Ok(rv)
+}
+
+fn iter_packages(doc: &DocumentMut) -> Result<Vec<&dyn TableLike>, Error> {
+    match doc.get("package") {
+        None => Ok(Vec::new()),
+        Some(Item::ArrayOfTables(packages)) => {
+            Ok(packages.iter().map(|x| x as &dyn TableLike).collect())
+        }
+        Some(_) => bail!("lockfile is malformed, [[package]] is not a list of tables"),
+    }
+}
+
+fn name_and_version(package: &dyn TableLike) -> Result<(&str, &str), Error> {
+    let name = package
+        .get("name")
+        .and_then(|x| x.as_str())
+        .ok_or_else(|| anyhow!("lockfile contains a package without a name"))?;
+    let version = package
+        .get("version")
+        .and_then(|x| x.as_str())
+        .ok_or_else(|| anyhow!("{} has no version in the lockfile", name))?;
+    Ok((name, version))
+}
+
+fn get_strings(package: &dyn TableLike, key: &str) -> Vec<String> {
+    package
+        .get(key)
+        .and_then(|x| x.as_array())
+        .map(|x| {
+            x.iter()
+                .filter_map(|x| x.as_str())
+                .map(|x| x.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn collect_file_hashes(files: Option<&Item>) -> Vec<String> {
+    files
+        .and_then(|x| x.as_array())
+        .map(|files| {
+            files
+                .iter()
+                .filter_map(|x| x.as_inline_table()?.get("hash")?.as_str())
+                .map(|x| x.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn with_marker(requirement: String, marker: Option<&str>) -> String {
+    match marker {
+        Some(marker) if !marker.trim().is_empty() => format!("{} ; {}", requirement, marker),
+        _ => requirement,
+    }
+}
+
+fn is_pinned(req: &Requirement) -> bool {
+    match req.version_or_url {
+        Some(VersionOrUrl::VersionSpecifier(ref specs)) => {
+            specs.len() == 1 && specs.iter().all(|x| *x.operator() == Operator::Equal)
+        }
+        Some(VersionOrUrl::Url(_)) => true,
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_requirements() {
+        let lock = import_lockfile(
+            r#"#
+# This file is autogenerated by pip-compile with Python 3.12
+#
+--index-url https://pypi.org/simple
+
+click==8.1.7 \
+    --hash=sha256:ae74fb96c20a0277a1d615f1e4d73c8414f5a98db8b799a7931d1582f3390c28 \
+    --hash=sha256:ca9853ad459e787e2192211578cc907e7594e294c7ccc834310722b41b9ca6de
+    # via flask
+Flask==3.0.0 \
+    --hash=sha256:21128f47e4e3b9d597a3e8521a329bf56909b690fcc3fa3e477725aa81367638
+    # via -r requirements.in
+"#,
+            LockFormat::PipTools,
+            false,
+        )
+        .unwrap();
+        assert_eq!(lock.packages.len(), 2);
+        assert_eq!(lock.packages[0].requirement, "click==8.1.7");
+        assert_eq!(lock.packages[0].hashes.len(), 2);
+        assert_eq!(lock.packages[1].name, "flask");
+        assert_eq!(lock.follow_ups.len(), 1);
+
+        assert!(import_lockfile("flask>=3.0", LockFormat::PipTools, false).is_err());
+    }
+
+    #[test]
+    fn test_import_poetry_lock() {
+        let contents = r#"
+[[package]]
+name = "colorama"
+version = "0.4.6"
+optional = false
+python-versions = "*"
+groups = ["main"]
+markers = "sys_platform == \"win32\""
+files = [
+    {file = "colorama-0.4.6-py2.py3-none-any.whl", hash = "sha256:4f1d9991f5acc0ca119f9d443620b77f9d6b33703e51011c16baf57afb285fc6"},
+]
+
+[[package]]
+name = "pytest"
+version = "8.0.0"
+optional = false
+python-versions = ">=3.8"
+groups = ["dev"]
+files = []
+
+[[package]]
+name = "tomli"
+version = "2.0.1"
+optional = false
+python-versions = ">=3.7"
+groups = ["main"]
+files = []
+
+[package.source]
+type = "directory"
+url = "../tomli"
+"#;
+        let lock = import_lockfile(contents, LockFormat::Poetry, false).unwrap();
+        assert_eq!(
+            lock.render_packages(),
+            "colorama==0.4.6 ; sys_platform == \"win32\" \\\n    --hash=sha256:4f1d9991f5acc0ca119f9d443620b77f9d6b33703e51011c16baf57afb285fc6\n"
+        );
+        assert_eq!(
+            lock.follow_ups,
+            vec!["tomli (directory source) was skipped"]
+        );
+
+        let lock = import_lockfile(contents, LockFormat::Poetry, true).unwrap();
+        assert_eq!(lock.packages.len(), 2);
+    }
+
+    #[test]
+    fn test_import_pdm_lock() {
+        let lock = import_lockfile(
+            r#"
+[metadata]
+groups = ["default", "dev"]
+
+[[package]]
+name = "requests"
+version = "2.31.0"
+groups = ["default"]
+files = [
+    {file = "requests-2.31.0.tar.gz", hash = "sha256:942c5a758f98d790eaed1a29cb6eefc7ffb0d1cf7af05c3d2791656dbd6ad1e1"},
+]
+
+[[package]]
+name = "requests"
+version = "2.31.0"
+extras = ["socks"]
+groups = ["default"]
+files = [
+    {file = "requests-2.31.0-py3-none-any.whl", hash = "sha256:58cd2187c01e70e6e26505bca751777aa9f2ee0b7f4300988b709f44e013003f"},
+]
+
+[[package]]
+name = "ruff"
+version = "0.2.0"
+groups = ["dev"]
+"#,
+            LockFormat::Pdm,
+            false,
+        )
+        .unwrap();
+        assert_eq!(lock.packages.len(), 1);
+        assert_eq!(lock.packages[0].hashes.len(), 2);
+    }
+}
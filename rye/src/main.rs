@@ -6,18 +6,37 @@ use crate::utils::QuietExit;
 #[macro_use]
 mod tui;
 
+mod artifact;
 mod bootstrap;
+mod build_cache;
+mod build_hints;
 mod cli;
 mod config;
 mod consts;
+mod entry_points;
+mod file_locks;
+mod gates;
 mod installer;
 mod lock;
+mod lock_import;
 mod platform;
+mod poetry;
+mod presets;
+mod progress;
+mod provenance;
 mod pyproject;
+mod safety;
+mod schema;
+mod sdist;
 mod sources;
+mod stats;
 mod sync;
 mod utils;
 mod uv;
+mod vcs_version;
+mod vendor;
+mod wizard;
+mod yanked;
 
 static SHOW_CONTINUE_PROMPT: AtomicBool = AtomicBool::new(false);
 static DISABLE_CTRLC_HANDLER: AtomicBool = AtomicBool::new(false);
@@ -34,6 +53,7 @@ pub fn disable_ctrlc_handler() {
 
 pub fn main() {
     crate::utils::panic::set_panic_hook();
+    crate::tui::init_colors();
 
     ctrlc::set_handler(move || {
         if !DISABLE_CTRLC_HANDLER.load(Ordering::Relaxed) {
@@ -11,8 +11,13 @@ use crate::sources::py::{PythonVersion, PythonVersionRequest};
 use crate::utils::IoPathContext;
 
 static APP_DIR: Mutex<Option<&'static PathBuf>> = Mutex::new(None);
+static SHARED_TOOLCHAIN_DIR: Mutex<Option<&'static PathBuf>> = Mutex::new(None);
 
 pub fn init() -> Result<(), Error> {
+    if let Some(toolchain_dir) = env::var_os("RYE_TOOLCHAIN_DIR").filter(|x| !x.is_empty()) {
+        *SHARED_TOOLCHAIN_DIR.lock().unwrap() =
+            Some(Box::leak(Box::new(PathBuf::from(toolchain_dir))));
+    }
     let home = if let Some(rye_home) = env::var_os("RYE_HOME") {
         PathBuf::from(rye_home)
     } else {
@@ -29,6 +34,19 @@ pub fn get_app_dir() -> &'static Path {
     APP_DIR.lock().unwrap().expect("platform not initialized")
 }
 
+/// Returns the shared toolchain store configured with `RYE_TOOLCHAIN_DIR`.
+///
+/// The store is treated as read-only: toolchains and uv binaries are looked up
+/// in it, but everything Rye writes goes to the application directory.
+pub fn get_shared_toolchain_dir() -> Option<&'static Path> {
+    SHARED_TOOLCHAIN_DIR.lock().unwrap().map(|x| x.as_path())
+}
+
+/// Is the given path located in the shared toolchain store?
+pub fn is_in_shared_toolchain_dir(path: &Path) -> bool {
+    get_shared_toolchain_dir().map_or(false, |dir| path.starts_with(dir))
+}
+
 /// Runs a check if symlinks are supported.
 pub fn symlinks_supported() -> bool {
     #[cfg(unix)]
@@ -53,13 +71,42 @@ pub fn symlinks_supported() -> bool {
 }
 
 /// Returns the cache directory for a particular python version that can be downloaded.
+///
+/// If the version is not installed in the application directory but in the
+/// shared toolchain store, the path in the store is returned.
 pub fn get_canonical_py_path(version: &PythonVersion) -> Result<PathBuf, Error> {
+    let rv = get_local_py_path(version)?;
+    if !rv.exists() {
+        if let Some(shared) = get_shared_toolchain_dir() {
+            let shared = shared.join("py").join(version.to_string());
+            if shared.exists() {
+                return Ok(shared);
+            }
+        }
+    }
+    Ok(rv)
+}
+
+/// Returns the directory in the application directory for a python version.
+///
+/// Unlike [`get_canonical_py_path`] this never points into the shared
+/// toolchain store, so this is where toolchains are written to.
+pub fn get_local_py_path(version: &PythonVersion) -> Result<PathBuf, Error> {
     let mut rv = get_app_dir().to_path_buf();
     rv.push("py");
     rv.push(version.to_string());
     Ok(rv)
 }
 
+/// Returns the folders that contain toolchains, the application directory first.
+pub fn get_toolchain_folders() -> Vec<PathBuf> {
+    let mut rv = vec![get_app_dir().join("py")];
+    if let Some(shared) = get_shared_toolchain_dir() {
+        rv.push(shared.join("py"));
+    }
+    rv
+}
+
 /// Returns the path of the python binary for the given version.
 pub fn get_toolchain_python_bin(version: &PythonVersion) -> Result<PathBuf, Error> {
     let p = get_canonical_py_path(version)?;
@@ -154,10 +201,15 @@ pub fn get_pinnable_version(req: &PythonVersionRequest, relaxed: bool) -> Option
 }
 
 /// Returns a list of all registered toolchains.
+///
+/// Toolchains in the application directory shadow the ones with the same
+/// version in the shared toolchain store.
 pub fn list_known_toolchains() -> Result<Vec<(PythonVersion, PathBuf)>, Error> {
-    let folder = get_app_dir().join("py");
-    let mut rv = Vec::new();
-    if let Ok(iter) = folder.read_dir() {
+    let mut rv = Vec::<(PythonVersion, PathBuf)>::new();
+    for folder in get_toolchain_folders() {
+        let Ok(iter) = folder.read_dir() else {
+            continue;
+        };
         for entry in iter {
             let entry = entry?;
             if let Ok(ver) = entry
@@ -166,6 +218,9 @@ pub fn list_known_toolchains() -> Result<Vec<(PythonVersion, PathBuf)>, Error> {
                 .to_string_lossy()
                 .parse::<PythonVersion>()
             {
+                if rv.iter().any(|x| x.0 == ver) {
+                    continue;
+                }
                 let target = get_toolchain_python_bin(&ver)?;
                 if !target.exists() {
                     continue;
@@ -212,12 +267,18 @@ pub fn get_default_author_with_fallback(dir: &PathBuf) -> Option<(String, String
 
 /// Reads the current `.python-version` file.
 pub fn get_python_version_request_from_pyenv_pin(root: &Path) -> Option<PythonVersionRequest> {
+    find_pyenv_pin(root).map(|(_, ver)| ver)
+}
+
+/// Finds the closest `.python-version` file and returns its path and the
+/// version request in it.
+pub fn find_pyenv_pin(root: &Path) -> Option<(PathBuf, PythonVersionRequest)> {
     let mut here = root.to_owned();
 
     loop {
         here.push(".python-version");
         if let Ok(contents) = fs::read_to_string(&here) {
-            return read_python_version(&contents);
+            return read_python_version(&contents).map(|ver| (here, ver));
         }
 
         // pop filename
@@ -232,6 +293,13 @@ pub fn get_python_version_request_from_pyenv_pin(root: &Path) -> Option<PythonVe
     None
 }
 
+/// Reads the version request from a `.python-version` file at the given path.
+pub fn read_pin_file(path: &Path) -> Result<PythonVersionRequest, Error> {
+    let contents = fs::read_to_string(path).path_context(path, "failed to read pin file")?;
+    read_python_version(&contents)
+        .ok_or_else(|| anyhow!("no valid Python version in {}", path.display()))
+}
+
 /// Return the [`PythonVersionRequest`] from a `.python-version` file.
 fn read_python_version(contents: &str) -> Option<PythonVersionRequest> {
     // Skip empty lines and comments.
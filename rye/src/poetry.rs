@@ -0,0 +1,909 @@
+use std::path::{Component, Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Error};
+use once_cell::sync::Lazy;
+use pep508_rs::Requirement;
+use regex::Regex;
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, TableLike, Value};
+use walkdir::WalkDir;
+
+use crate::pyproject::normalize_package_name;
+use crate::utils::toml::reformat_array_multiline;
+
+static CONSTRAINT_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\^|~=|~|===|==|!=|>=|<=|>|<|=)?\s*(\S+)$").unwrap());
+
+/// Folders that are never searched for workspace members.
+const SKIPPED_DIRS: &[&str] = &["node_modules", "__pycache__", "build", "dist", "target"];
+
+/// The result of converting a Poetry `pyproject.toml`.
+#[derive(Debug)]
+pub struct Conversion {
+    /// The name of the converted package.
+    pub name: String,
+    /// The converted `pyproject.toml`.
+    pub pyproject: String,
+    /// Things that could not be converted and need manual attention.
+    pub follow_ups: Vec<String>,
+}
+
+/// Is this `pyproject.toml` managed by Poetry?
+pub fn is_poetry_project(doc: &DocumentMut) -> bool {
+    doc.get("tool").and_then(|x| x.get("poetry")).is_some()
+}
+
+/// Finds all Poetry projects below a workspace root (excluding the root itself).
+///
+/// The returned paths are relative to the root.
+pub fn find_poetry_members(root: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut rv = Vec::new();
+    let walker = WalkDir::new(root).min_depth(1).into_iter();
+    for entry in walker.filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        !(entry.file_type().is_dir()
+            && (name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_ref())))
+    }) {
+        let entry = entry?;
+        if entry.file_name() != "pyproject.toml" || entry.depth() < 2 {
+            continue;
+        }
+        let contents = std::fs::read_to_string(entry.path())?;
+        if let Ok(doc) = contents.parse::<DocumentMut>() {
+            if is_poetry_project(&doc) {
+                let dir = entry.path().parent().expect("pyproject.toml has a parent");
+                rv.push(dir.strip_prefix(root)?.to_path_buf());
+            }
+        }
+    }
+    rv.sort();
+    Ok(rv)
+}
+
+/// Converts a Poetry `pyproject.toml` into one that is managed by Rye.
+///
+/// `dir` is the folder of the project, `rel_path` its location relative to the
+/// workspace root and `members` are the locations of all workspace members.
+/// Path dependencies on workspace members become regular dependencies.
+pub fn convert_pyproject(
+    source: &str,
+    dir: &Path,
+    rel_path: &Path,
+    members: &[PathBuf],
+) -> Result<Conversion, Error> {
+    let mut doc = source
+        .parse::<DocumentMut>()
+        .context("failed to parse pyproject.toml")?;
+    let poetry = match doc.get_mut("tool").and_then(|x| x.as_table_like_mut()) {
+        Some(tool) => tool
+            .remove("poetry")
+            .ok_or_else(|| anyhow!("not a Poetry project, [tool.poetry] is missing"))?,
+        None => return Err(anyhow!("not a Poetry project, [tool.poetry] is missing")),
+    };
+    let poetry = poetry
+        .as_table_like()
+        .ok_or_else(|| anyhow!("[tool.poetry] is malformed"))?;
+
+    let mut converter = Converter {
+        rel_path,
+        members,
+        follow_ups: Vec::new(),
+    };
+    let mut new_doc = DocumentMut::new();
+
+    // [project]
+    let mut project = Table::new();
+    let name = poetry
+        .get("name")
+        .and_then(|x| x.as_str())
+        .or_else(|| doc.get("project")?.get("name")?.as_str())
+        .ok_or_else(|| anyhow!("project has no name"))?
+        .to_string();
+    project.insert("name", name.as_str().into());
+    for key in ["version", "description"] {
+        if let Some(value) = poetry.get(key).and_then(|x| x.as_str()) {
+            project.insert(key, value.into());
+        }
+    }
+    if let Some(authors) = poetry.get("authors").and_then(|x| x.as_array()) {
+        project.insert("authors", Item::Value(convert_authors(authors).into()));
+    }
+
+    let mut optional = Vec::new();
+    let mut dependencies = Array::new();
+    if let Some(deps) = poetry.get("dependencies").and_then(|x| x.as_table_like()) {
+        for (dep_name, spec) in deps.iter() {
+            if dep_name == "python" {
+                continue;
+            }
+            if let Some((req, is_optional)) = converter.convert_dependency(dep_name, spec) {
+                if is_optional {
+                    optional.push((normalize_package_name(dep_name), req));
+                } else {
+                    dependencies.push(req);
+                }
+            }
+        }
+    }
+    reformat_array_multiline(&mut dependencies);
+    project.insert("dependencies", Item::Value(dependencies.into()));
+
+    match poetry.get("readme") {
+        Some(Item::Value(Value::String(readme))) => {
+            project.insert("readme", readme.value().as_str().into());
+        }
+        Some(Item::Value(Value::Array(readmes))) => {
+            if let Some(readme) = readmes.get(0).and_then(|x| x.as_str()) {
+                project.insert("readme", readme.into());
+            }
+            if readmes.len() > 1 {
+                converter.note("only the first of multiple readme files was kept");
+            }
+        }
+        _ => {}
+    }
+
+    let python = poetry
+        .get("dependencies")
+        .and_then(|x| x.get("python"))
+        .and_then(|x| x.as_str());
+    match python.map(convert_constraint) {
+        Some(Ok(requires_python)) if !requires_python.is_empty() => {
+            project.insert("requires-python", requires_python.as_str().into());
+        }
+        Some(Err(err)) => converter.note(format!("python: {}", err)),
+        _ => converter.note("no Python version constraint declared, set requires-python"),
+    }
+
+    if let Some(license) = poetry.get("license").and_then(|x| x.as_str()) {
+        let mut table = InlineTable::new();
+        table.insert("text", license.into());
+        project.insert("license", Item::Value(table.into()));
+    }
+    for key in ["keywords", "classifiers"] {
+        if let Some(value) = poetry.get(key).and_then(|x| x.as_array()) {
+            project.insert(key, Item::Value(value.clone().into()));
+        }
+    }
+
+    // [project.optional-dependencies]
+    if let Some(extras) = poetry.get("extras").and_then(|x| x.as_table_like()) {
+        let mut table = Table::new();
+        for (extra, packages) in extras.iter() {
+            let mut reqs = Array::new();
+            for package in packages.as_array().into_iter().flatten() {
+                let package = normalize_package_name(package.as_str().unwrap_or_default());
+                match optional.iter().find(|(name, _)| *name == package) {
+                    Some((_, req)) => reqs.push(req.as_str()),
+                    None => converter.note(format!(
+                        "extra {} refers to unknown optional dependency {}",
+                        extra, package
+                    )),
+                }
+            }
+            reformat_array_multiline(&mut reqs);
+            table.insert(extra, Item::Value(reqs.into()));
+        }
+        for (package, _) in &optional {
+            let used = extras.iter().any(|(_, packages)| {
+                packages
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .any(|x| normalize_package_name(x.as_str().unwrap_or_default()) == *package)
+            });
+            if !used {
+                converter.note(format!(
+                    "optional dependency {} is not part of any extra and was left out",
+                    package
+                ));
+            }
+        }
+        project.insert("optional-dependencies", Item::Table(table));
+    } else {
+        for (package, _) in &optional {
+            converter.note(format!(
+                "optional dependency {} is not part of any extra and was left out",
+                package
+            ));
+        }
+    }
+
+    // [project.scripts]
+    if let Some(scripts) = poetry.get("scripts").and_then(|x| x.as_table_like()) {
+        let mut table = Table::new();
+        for (script, target) in scripts.iter() {
+            match target
+                .as_str()
+                .or_else(|| target.get("callable").and_then(|x| x.as_str()))
+            {
+                Some(target) => {
+                    table.insert(script, target.into());
+                }
+                None => converter.note(format!(
+                    "script {} is not a callable and was left out",
+                    script
+                )),
+            }
+        }
+        project.insert("scripts", Item::Table(table));
+    }
+
+    // [project.entry-points]
+    if let Some(plugins) = poetry.get("plugins").and_then(|x| x.as_table_like()) {
+        let mut table = Table::new();
+        for (group, entries) in plugins.iter() {
+            let mut group_table = Table::new();
+            for (entry, target) in entries.as_table_like().into_iter().flat_map(|x| x.iter()) {
+                if let Some(target) = target.as_str() {
+                    group_table.insert(entry, target.into());
+                }
+            }
+            table.insert(group, Item::Table(group_table));
+        }
+        project.insert("entry-points", Item::Table(table));
+    }
+
+    // [project.urls]
+    let mut urls = Table::new();
+    for (key, title) in [
+        ("homepage", "Homepage"),
+        ("repository", "Repository"),
+        ("documentation", "Documentation"),
+    ] {
+        if let Some(url) = poetry.get(key).and_then(|x| x.as_str()) {
+            urls.insert(title, url.into());
+        }
+    }
+    for (key, url) in poetry
+        .get("urls")
+        .and_then(|x| x.as_table_like())
+        .into_iter()
+        .flat_map(|x| x.iter())
+    {
+        if let Some(url) = url.as_str() {
+            urls.insert(key, url.into());
+        }
+    }
+    if !urls.is_empty() {
+        project.insert("urls", Item::Table(urls));
+    }
+
+    // Poetry 2 projects can already declare PEP 621 metadata which wins.
+    if let Some(existing) = doc.remove("project") {
+        for (key, value) in existing.as_table_like().into_iter().flat_map(|x| x.iter()) {
+            project.insert(key, value.clone());
+        }
+    }
+    new_doc.insert("project", Item::Table(project));
+
+    // [build-system]
+    let uses_poetry_backend = doc
+        .get("build-system")
+        .and_then(|x| x.get("build-backend"))
+        .and_then(|x| x.as_str())
+        .map_or(true, |x| x.starts_with("poetry"));
+    let mut wheel_packages = None;
+    if uses_poetry_backend {
+        doc.remove("build-system");
+        let mut build_system = Table::new();
+        let mut requires = Array::new();
+        requires.push("hatchling");
+        build_system.insert("requires", Item::Value(requires.into()));
+        build_system.insert("build-backend", "hatchling.build".into());
+        new_doc.insert("build-system", Item::Table(build_system));
+        wheel_packages = converter.wheel_packages(poetry, dir, &name);
+    }
+
+    // [tool.rye]
+    let mut rye = Table::new();
+    rye.insert("managed", true.into());
+    let mut dev_dependencies = Array::new();
+    let legacy_dev = poetry
+        .get("dev-dependencies")
+        .and_then(|x| x.as_table_like())
+        .map(|x| ("dev", x));
+    let groups = poetry
+        .get("group")
+        .and_then(|x| x.as_table_like())
+        .into_iter()
+        .flat_map(|x| x.iter())
+        .filter_map(|(group, x)| Some((group, x.get("dependencies")?.as_table_like()?)));
+    for (group, deps) in legacy_dev.into_iter().chain(groups) {
+        if group != "dev" && group != "test" {
+            converter.note(format!(
+                "dependency group {} was merged into dev-dependencies",
+                group
+            ));
+        }
+        for (dep_name, spec) in deps.iter() {
+            if let Some((req, _)) = converter.convert_dependency(dep_name, spec) {
+                if !dev_dependencies.iter().any(|x| x.as_str() == Some(&req)) {
+                    dev_dependencies.push(req);
+                }
+            }
+        }
+    }
+    reformat_array_multiline(&mut dev_dependencies);
+    rye.insert("dev-dependencies", Item::Value(dev_dependencies.into()));
+
+    let mut sources = toml_edit::ArrayOfTables::new();
+    for source in poetry
+        .get("source")
+        .and_then(|x| x.as_array_of_tables())
+        .into_iter()
+        .flatten()
+    {
+        let (Some(source_name), Some(url)) = (
+            source.get("name").and_then(|x| x.as_str()),
+            source.get("url").and_then(|x| x.as_str()),
+        ) else {
+            continue;
+        };
+        let mut table = Table::new();
+        table.insert("name", source_name.into());
+        table.insert("url", url.into());
+        sources.push(table);
+        if source.get("priority").and_then(|x| x.as_str()) == Some("explicit") {
+            converter.note(format!(
+                "source {} is explicit in Poetry but will be searched for all packages",
+                source_name
+            ));
+        }
+    }
+    if !sources.is_empty() {
+        rye.insert("sources", Item::ArrayOfTables(sources));
+    }
+    if let Some(existing) = doc
+        .get_mut("tool")
+        .and_then(|x| x.as_table_like_mut())
+        .and_then(|x| x.remove("rye"))
+    {
+        for (key, value) in existing.as_table_like().into_iter().flat_map(|x| x.iter()) {
+            if rye.get(key).is_none() {
+                rye.insert(key, value.clone());
+            }
+        }
+    }
+
+    let mut tool = Table::new();
+    tool.set_implicit(true);
+    tool.insert("rye", Item::Table(rye));
+    if uses_poetry_backend {
+        let mut hatch = Table::new();
+        hatch.set_implicit(true);
+        let mut metadata = Table::new();
+        metadata.insert("allow-direct-references", true.into());
+        hatch.insert("metadata", Item::Table(metadata));
+        if let Some(packages) = wheel_packages {
+            let mut build = Table::new();
+            build.set_implicit(true);
+            let mut targets = Table::new();
+            targets.set_implicit(true);
+            let mut wheel = Table::new();
+            wheel.insert("packages", Item::Value(packages.into()));
+            targets.insert("wheel", Item::Table(wheel));
+            build.insert("targets", Item::Table(targets));
+            hatch.insert("build", Item::Table(build));
+        }
+        tool.insert("hatch", Item::Table(hatch));
+    }
+    new_doc.insert("tool", Item::Table(tool));
+
+    // Poetry plugins and their configuration
+    for (plugin, _) in poetry
+        .get("requires-plugins")
+        .and_then(|x| x.as_table_like())
+        .into_iter()
+        .flat_map(|x| x.iter())
+    {
+        converter.note(format!("requires the Poetry plugin {}", plugin));
+    }
+    for (key, _) in doc
+        .get("tool")
+        .and_then(|x| x.as_table_like())
+        .into_iter()
+        .flat_map(|x| x.iter())
+    {
+        if key.starts_with("poetry") {
+            converter.note(format!(
+                "[tool.{}] configures a Poetry plugin and has no effect anymore",
+                key
+            ));
+        }
+    }
+    if poetry.get("build").is_some() {
+        converter.note("the custom build script (tool.poetry.build) needs to be ported to hatch");
+    }
+
+    let mut pos = 0;
+    renumber_tables(new_doc.as_table_mut(), &mut pos);
+    let mut pyproject = new_doc.to_string();
+    let rest = doc.to_string();
+    if !rest.trim().is_empty() {
+        pyproject.push('\n');
+        pyproject.push_str(rest.trim_start());
+    }
+    pyproject
+        .parse::<DocumentMut>()
+        .context("converted pyproject.toml is invalid, the remaining tables conflict")?;
+
+    Ok(Conversion {
+        name,
+        pyproject,
+        follow_ups: converter.follow_ups,
+    })
+}
+
+struct Converter<'a> {
+    rel_path: &'a Path,
+    members: &'a [PathBuf],
+    follow_ups: Vec<String>,
+}
+
+impl Converter<'_> {
+    fn note<S: Into<String>>(&mut self, msg: S) {
+        self.follow_ups.push(msg.into());
+    }
+
+    /// Converts a single Poetry dependency into a PEP 508 requirement.
+    ///
+    /// Returns the requirement and if the dependency is optional.
+    fn convert_dependency(&mut self, name: &str, spec: &Item) -> Option<(String, bool)> {
+        let rv = if let Some(version) = spec.as_str() {
+            convert_constraint(version).map(|x| (format!("{}{}", name, x), false))
+        } else if let Some(table) = spec.as_table_like() {
+            self.convert_dependency_table(name, table)
+        } else if spec.is_array() {
+            Err("multiple constraints per dependency cannot be converted".into())
+        } else {
+            Err("unsupported dependency specification".into())
+        };
+
+        match rv.and_then(|(req, optional)| match Requirement::from_str(&req) {
+            Ok(_) => Ok((req, optional)),
+            Err(err) => Err(format!("converted requirement {} is invalid: {}", req, err)),
+        }) {
+            Ok(rv) => Some(rv),
+            Err(err) => {
+                self.note(format!("dependency {}: {}, left out", name, err));
+                None
+            }
+        }
+    }
+
+    fn convert_dependency_table(
+        &mut self,
+        name: &str,
+        table: &dyn TableLike,
+    ) -> Result<(String, bool), String> {
+        let get_str = |key: &str| table.get(key).and_then(|x| x.as_str());
+        let mut rv = name.to_string();
+        if let Some(extras) = table.get("extras").and_then(|x| x.as_array()) {
+            let extras = extras
+                .iter()
+                .filter_map(|x| x.as_str())
+                .collect::<Vec<_>>()
+                .join(",");
+            rv.push_str(&format!("[{}]", extras));
+        }
+
+        let mut is_url = true;
+        if let Some(git) = get_str("git") {
+            let mut url = if git.starts_with("git+") {
+                git.to_string()
+            } else {
+                format!("git+{}", git)
+            };
+            if let Some(rev) = get_str("rev").or(get_str("tag")).or(get_str("branch")) {
+                url.push('@');
+                url.push_str(rev);
+            }
+            if let Some(subdirectory) = get_str("subdirectory") {
+                url.push_str("#subdirectory=");
+                url.push_str(subdirectory);
+            }
+            rv.push_str(" @ ");
+            rv.push_str(&url);
+        } else if let Some(path) = get_str("path") {
+            let target = normalize_path(&self.rel_path.join(path));
+            if self.members.contains(&target) {
+                is_url = false;
+            } else {
+                rv.push_str(&format!(
+                    " @ file:///${{PROJECT_ROOT}}/{}",
+                    target.to_string_lossy().replace('\\', "/")
+                ));
+            }
+        } else if let Some(url) = get_str("url") {
+            rv.push_str(" @ ");
+            rv.push_str(url);
+        } else {
+            is_url = false;
+            if let Some(version) = get_str("version") {
+                rv.push_str(&convert_constraint(version)?);
+            }
+        }
+
+        let mut markers = Vec::new();
+        if let Some(python) = get_str("python") {
+            markers.push(python_marker(python)?);
+        }
+        if let Some(platform) = get_str("platform") {
+            markers.push(format!("sys_platform == \"{}\"", platform));
+        }
+        if let Some(marker) = get_str("markers") {
+            markers.push(marker.to_string());
+        }
+        if !markers.is_empty() {
+            let joined = if markers.len() == 1 {
+                markers.remove(0)
+            } else {
+                markers
+                    .iter()
+                    .map(|x| {
+                        if x.contains(" or ") {
+                            format!("({})", x)
+                        } else {
+                            x.to_string()
+                        }
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" and ")
+            };
+            rv.push_str(if is_url { " ; " } else { "; " });
+            rv.push_str(&joined);
+        }
+
+        if let Some(source) = get_str("source") {
+            self.note(format!(
+                "dependency {} is pinned to source {}, which is not supported",
+                name, source
+            ));
+        }
+        if table.get("allow-prereleases").is_some() {
+            self.note(format!(
+                "dependency {} allows pre-releases, use `rye lock --pre`",
+                name
+            ));
+        }
+
+        let optional = table
+            .get("optional")
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false);
+        Ok((rv, optional))
+    }
+
+    /// Figures out which packages hatch should put into the wheel.
+    fn wheel_packages(&mut self, poetry: &dyn TableLike, dir: &Path, name: &str) -> Option<Array> {
+        let mut rv = Array::new();
+        if let Some(packages) = poetry.get("packages").and_then(|x| x.as_array()) {
+            for package in packages.iter().filter_map(|x| x.as_inline_table()) {
+                let Some(include) = package.get("include").and_then(|x| x.as_str()) else {
+                    continue;
+                };
+                if include.contains('*') || package.contains_key("format") {
+                    self.note(format!(
+                        "package include {} uses globs or formats, configure \
+                         [tool.hatch.build.targets.wheel] manually",
+                        include
+                    ));
+                    continue;
+                }
+                match package.get("from").and_then(|x| x.as_str()) {
+                    Some(from) => rv.push(format!("{}/{}", from.trim_end_matches('/'), include)),
+                    None => rv.push(include),
+                }
+            }
+        } else {
+            let name_safe = name.replace('-', "_").to_ascii_lowercase();
+            if dir.join("src").join(&name_safe).is_dir() {
+                rv.push(format!("src/{}", name_safe));
+            } else if dir.join(&name_safe).is_dir() {
+                rv.push(name_safe);
+            } else {
+                self.note(
+                    "could not determine the package to build, \
+                     configure [tool.hatch.build.targets.wheel] manually",
+                );
+            }
+        }
+        for key in ["include", "exclude"] {
+            if poetry.get(key).is_some() {
+                self.note(format!(
+                    "tool.poetry.{} needs to be ported to the hatch build configuration",
+                    key
+                ));
+            }
+        }
+        if rv.is_empty() {
+            None
+        } else {
+            Some(rv)
+        }
+    }
+}
+
+/// Converts Poetry authors (`Name <email>`) into PEP 621 authors.
+fn convert_authors(authors: &Array) -> Array {
+    let mut rv = Array::new();
+    for author in authors.iter().filter_map(|x| x.as_str()) {
+        let mut table = InlineTable::new();
+        match author.split_once('<') {
+            Some((name, email)) => {
+                table.insert("name", name.trim().into());
+                table.insert("email", email.trim_end_matches('>').trim().into());
+            }
+            None => {
+                table.insert("name", author.trim().into());
+            }
+        }
+        rv.push(table);
+    }
+    reformat_array_multiline(&mut rv);
+    rv
+}
+
+/// Converts a Poetry version constraint into a PEP 440 version specifier.
+///
+/// The caret (`^`) and tilde (`~`) operators are expanded into ranges.
+pub fn convert_constraint(constraint: &str) -> Result<String, String> {
+    if constraint.contains('|') {
+        return Err(format!(
+            "constraint `{}` with alternatives cannot be expressed",
+            constraint
+        ));
+    }
+
+    let mut rv = Vec::new();
+    for piece in split_constraint(constraint) {
+        let caps = CONSTRAINT_RE
+            .captures(&piece)
+            .ok_or_else(|| format!("invalid constraint `{}`", piece))?;
+        let op = caps.get(1).map_or("", |x| x.as_str());
+        let version = &caps[2];
+        if version == "*" {
+            continue;
+        }
+        match op {
+            "^" | "~" => {
+                let release = parse_release(version)
+                    .ok_or_else(|| format!("invalid version in constraint `{}`", piece))?;
+                let idx = if op == "^" {
+                    release
+                        .iter()
+                        .position(|&x| x != 0)
+                        .unwrap_or(release.len() - 1)
+                } else {
+                    usize::from(release.len() > 1)
+                };
+                let mut upper = release[..=idx].to_vec();
+                upper[idx] += 1;
+                rv.push(format!(">={}", version));
+                rv.push(format!(
+                    "<{}",
+                    upper
+                        .iter()
+                        .map(|x| x.to_string())
+                        .collect::<Vec<_>>()
+                        .join(".")
+                ));
+            }
+            "" | "=" => rv.push(format!("=={}", version)),
+            op => rv.push(format!("{}{}", op, version)),
+        }
+    }
+    Ok(rv.join(","))
+}
+
+/// Splits a constraint into its clauses (separated by commas or whitespace).
+fn split_constraint(constraint: &str) -> Vec<String> {
+    let mut rv = Vec::new();
+    for part in constraint.split(',') {
+        let mut pending_op = String::new();
+        for token in part.split_whitespace() {
+            if token.chars().all(|c| "<>=!~^".contains(c)) {
+                pending_op.push_str(token);
+            } else {
+                rv.push(format!("{}{}", pending_op, token));
+                pending_op.clear();
+            }
+        }
+    }
+    rv
+}
+
+/// Parses the numeric release segment of a version (eg: `1.2.3` of `1.2.3rc1`).
+fn parse_release(version: &str) -> Option<Vec<u64>> {
+    let end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    let release = version[..end].trim_end_matches('.');
+    if release.is_empty() {
+        return None;
+    }
+    release.split('.').map(|x| x.parse().ok()).collect()
+}
+
+/// Converts a Poetry `python` restriction of a dependency into a marker.
+fn python_marker(python: &str) -> Result<String, String> {
+    let specifier = convert_constraint(python)?;
+    let mut rv = Vec::new();
+    for clause in specifier.split(',').filter(|x| !x.is_empty()) {
+        let version_start = clause
+            .find(|c: char| c.is_ascii_digit())
+            .ok_or_else(|| format!("invalid python constraint `{}`", python))?;
+        let (op, version) = clause.split_at(version_start);
+        let var = if version.split('.').count() > 2 {
+            "python_full_version"
+        } else {
+            "python_version"
+        };
+        rv.push(format!("{} {} \"{}\"", var, op, version));
+    }
+    Ok(rv.join(" and "))
+}
+
+/// Lexically normalizes a relative path (resolves `.` and `..`).
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut rv = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if rv.file_name().is_some() {
+                    rv.pop();
+                } else {
+                    rv.push("..");
+                }
+            }
+            other => rv.push(other),
+        }
+    }
+    rv
+}
+
+/// Assigns positions to all tables so they are written in insertion order.
+fn renumber_tables(table: &mut Table, pos: &mut usize) {
+    table.set_position(*pos);
+    *pos += 1;
+    for (_, item) in table.iter_mut() {
+        match item {
+            Item::Table(table) => renumber_tables(table, pos),
+            Item::ArrayOfTables(tables) => {
+                for table in tables.iter_mut() {
+                    renumber_tables(table, pos);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_constraint() {
+        assert_eq!(convert_constraint("^1.2.3").unwrap(), ">=1.2.3,<2");
+        assert_eq!(convert_constraint("^0.2.3").unwrap(), ">=0.2.3,<0.3");
+        assert_eq!(convert_constraint("^0.0.3").unwrap(), ">=0.0.3,<0.0.4");
+        assert_eq!(convert_constraint("^0").unwrap(), ">=0,<1");
+        assert_eq!(convert_constraint("~1.2.3").unwrap(), ">=1.2.3,<1.3");
+        assert_eq!(convert_constraint("~1").unwrap(), ">=1,<2");
+        assert_eq!(convert_constraint("*").unwrap(), "");
+        assert_eq!(convert_constraint("1.2.3").unwrap(), "==1.2.3");
+        assert_eq!(convert_constraint(">= 1.2, < 2.0").unwrap(), ">=1.2,<2.0");
+        assert_eq!(convert_constraint(">=1.2 <2.0").unwrap(), ">=1.2,<2.0");
+        assert!(convert_constraint("^1.0 || ^2.0").is_err());
+    }
+
+    #[test]
+    fn test_python_marker() {
+        assert_eq!(
+            python_marker("^3.8").unwrap(),
+            "python_version >= \"3.8\" and python_version < \"4\""
+        );
+        assert_eq!(
+            python_marker(">=3.8.1").unwrap(),
+            "python_full_version >= \"3.8.1\""
+        );
+    }
+
+    #[test]
+    fn test_convert_pyproject() {
+        let source = r#"[tool.poetry]
+name = "my-app"
+version = "0.1.0"
+description = "An app"
+authors = ["Jane Doe <jane@example.com>"]
+packages = [{ include = "my_app", from = "src" }]
+
+[tool.poetry.dependencies]
+python = "^3.9"
+requests = "^2.31"
+my-lib = { path = "../my-lib", develop = true }
+click = { version = "^8.1", extras = ["colors"], python = ">=3.10" }
+weird = [{ version = "^1.0", python = "<3.10" }, { version = "^2.0", python = ">=3.10" }]
+rich = { version = "^13.0", optional = true }
+
+[tool.poetry.extras]
+fancy = ["rich"]
+
+[tool.poetry.group.dev.dependencies]
+pytest = "^8.0"
+
+[tool.poetry.scripts]
+my-app = "my_app.cli:main"
+
+[tool.black]
+line-length = 100
+
+[build-system]
+requires = ["poetry-core"]
+build-backend = "poetry.core.masonry.api"
+"#;
+        let members = [
+            PathBuf::from("packages/my-app"),
+            PathBuf::from("packages/my-lib"),
+        ];
+        let conversion = convert_pyproject(
+            source,
+            Path::new("/nonexistent"),
+            Path::new("packages/my-app"),
+            &members,
+        )
+        .unwrap();
+        assert_eq!(conversion.name, "my-app");
+        assert_eq!(
+            conversion.pyproject,
+            r#"[project]
+name = "my-app"
+version = "0.1.0"
+description = "An app"
+authors = [
+    { name = "Jane Doe", email = "jane@example.com" },
+]
+dependencies = [
+    "requests>=2.31,<3",
+    "my-lib",
+    'click[colors]>=8.1,<9; python_version >= "3.10"',
+]
+requires-python = ">=3.9,<4"
+
+[project.optional-dependencies]
+fancy = [
+    "rich>=13.0,<14",
+]
+
+[project.scripts]
+my-app = "my_app.cli:main"
+
+[build-system]
+requires = ["hatchling"]
+build-backend = "hatchling.build"
+
+[tool.rye]
+managed = true
+dev-dependencies = [
+    "pytest>=8.0,<9",
+]
+
+[tool.hatch.metadata]
+allow-direct-references = true
+
+[tool.hatch.build.targets.wheel]
+packages = ["src/my_app"]
+
+[tool.black]
+line-length = 100
+"#
+        );
+        assert_eq!(
+            conversion.follow_ups,
+            vec!["dependency weird: multiple constraints per dependency cannot be converted, left out"]
+        );
+    }
+}
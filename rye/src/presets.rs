@@ -0,0 +1,170 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Error};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use toml_edit::{DocumentMut, Table};
+
+use crate::bootstrap::download_url;
+use crate::platform::get_app_dir;
+use crate::pyproject::PyProject;
+use crate::tui::WarningCategory;
+use crate::utils::{CommandOutput, IoPathContext};
+
+/// A shared preset of dev-dependencies and scripts.
+///
+/// Only the source and the checksum of the preset are recorded in the header
+/// of the dev lockfile.
+#[derive(Serialize, Debug, Clone)]
+pub struct DevPreset {
+    pub source: String,
+    pub sha256: String,
+    #[serde(skip)]
+    pub dev_dependencies: Vec<String>,
+    #[serde(skip)]
+    pub scripts: Table,
+}
+
+/// Loads the presets configured in `tool.rye.dev-presets`.
+///
+/// Remote presets are cached in the rye home folder.  With `refresh` they are
+/// downloaded again, otherwise the cached copy is used.  If a download fails
+/// the last cached copy is used with a warning.
+pub fn load_dev_presets(
+    pyproject: &PyProject,
+    refresh: bool,
+    output: CommandOutput,
+) -> Result<Vec<DevPreset>, Error> {
+    pyproject
+        .dev_presets()?
+        .iter()
+        .map(|source| {
+            load_dev_preset(source, &pyproject.workspace_path(), refresh, output)
+                .with_context(|| format!("failed to load dev preset {}", source))
+        })
+        .collect()
+}
+
+fn load_dev_preset(
+    source: &str,
+    base: &Path,
+    refresh: bool,
+    output: CommandOutput,
+) -> Result<DevPreset, Error> {
+    let contents = if source.starts_with("https://") {
+        let cache_path = preset_cache_path(source);
+        if refresh || !cache_path.is_file() {
+            match download_url(source, output) {
+                Ok(contents) => {
+                    if let Some(parent) = cache_path.parent() {
+                        fs::create_dir_all(parent)
+                            .path_context(parent, "failed to create preset cache")?;
+                    }
+                    fs::write(&cache_path, &contents)
+                        .path_context(&cache_path, "failed to cache dev preset")?;
+                }
+                Err(err) if cache_path.is_file() => {
+                    warn!(
+                        category = WarningCategory::Environment,
+                        "failed to download dev preset {}, using cached copy: {}", source, err
+                    );
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        fs::read(&cache_path).path_context(&cache_path, "failed to read cached dev preset")?
+    } else {
+        let path = base.join(source);
+        fs::read(&path).path_context(&path, "failed to read dev preset")?
+    };
+    parse_dev_preset(source, &contents)
+}
+
+/// Where a remote preset is cached.
+fn preset_cache_path(url: &str) -> PathBuf {
+    get_app_dir().join("presets").join(format!(
+        "{}.toml",
+        hex::encode(Sha256::digest(url.as_bytes()))
+    ))
+}
+
+/// Reads the preset scripts without touching the network.
+///
+/// Remote presets that were never downloaded (by locking) are skipped.
+pub fn cached_preset_scripts(sources: &[String], base: &Path) -> Table {
+    let mut rv = Table::new();
+    for source in sources {
+        let path = if source.starts_with("https://") {
+            preset_cache_path(source)
+        } else {
+            base.join(source)
+        };
+        let Ok(contents) = fs::read(&path) else {
+            continue;
+        };
+        if let Ok(preset) = parse_dev_preset(source, &contents) {
+            for (key, value) in preset.scripts.iter() {
+                if !rv.contains_key(key) {
+                    rv.insert(key, value.clone());
+                }
+            }
+        }
+    }
+    rv
+}
+
+fn parse_dev_preset(source: &str, contents: &[u8]) -> Result<DevPreset, Error> {
+    let doc = std::str::from_utf8(contents)
+        .context("dev preset is not valid utf-8")?
+        .parse::<DocumentMut>()
+        .context("dev preset is not valid TOML")?;
+    let mut dev_dependencies = Vec::new();
+    for dep in doc
+        .get("dev-dependencies")
+        .map(|x| {
+            x.as_array()
+                .ok_or_else(|| anyhow!("dev-dependencies must be an array of strings"))
+        })
+        .transpose()?
+        .into_iter()
+        .flatten()
+    {
+        dev_dependencies.push(
+            dep.as_str()
+                .ok_or_else(|| anyhow!("dev-dependencies must be an array of strings"))?
+                .to_string(),
+        );
+    }
+    let scripts = match doc.get("scripts") {
+        Some(scripts) => scripts
+            .as_table()
+            .ok_or_else(|| anyhow!("scripts must be a table"))?
+            .clone(),
+        None => Table::new(),
+    };
+    Ok(DevPreset {
+        source: source.to_string(),
+        sha256: hex::encode(Sha256::digest(contents)),
+        dev_dependencies,
+        scripts,
+    })
+}
+
+#[test]
+fn test_parse_dev_preset() {
+    let preset = parse_dev_preset(
+        "presets/standard.toml",
+        br#"
+dev-dependencies = ["pytest>=8", "mypy"]
+
+[scripts]
+check = "mypy src"
+"#,
+    )
+    .unwrap();
+    assert_eq!(preset.dev_dependencies, vec!["pytest>=8", "mypy"]);
+    assert_eq!(preset.scripts["check"].as_str(), Some("mypy src"));
+    assert_eq!(preset.sha256.len(), 64);
+    assert!(parse_dev_preset("x", b"dev-dependencies = 42").is_err());
+}
@@ -0,0 +1,124 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{bail, Error};
+use serde::Serialize;
+
+use crate::utils::IoPathContext;
+
+/// The version of the event format.  It is only bumped on incompatible
+/// changes, new events and fields can be added at any time.
+pub const PROGRESS_FORMAT_VERSION: u32 = 1;
+
+static PROGRESS_SINK: Mutex<Option<File>> = Mutex::new(None);
+
+/// A progress event for frontends.
+///
+/// Events are written as one JSON object per line.  See the progress events
+/// documentation for the stable format.
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum ProgressEvent<'a> {
+    /// A download started.
+    DownloadStart { url: &'a str },
+    /// Progress of a download.  `total` is not known for all downloads.
+    DownloadProgress {
+        url: &'a str,
+        downloaded: u64,
+        total: Option<u64>,
+    },
+    /// A download finished successfully.
+    DownloadFinish { url: &'a str, downloaded: u64 },
+    /// A uv invocation started (`venv`, `pip compile`, `pip sync`, …).
+    UvStart { command: &'a str },
+    /// A uv invocation finished.
+    UvFinish { command: &'a str, success: bool },
+    /// A phase of `sync` or `lock` started.
+    Phase { name: &'a str },
+    /// Dependencies were installed into a virtualenv from a lockfile.
+    Installed { venv: &'a Path, packages: usize },
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    version: u32,
+    #[serde(flatten)]
+    event: &'a ProgressEvent<'a>,
+}
+
+/// Sends progress events to an already open file descriptor.
+#[cfg(unix)]
+pub fn set_progress_fd(fd: i32) -> Result<(), Error> {
+    use std::os::unix::io::FromRawFd;
+    if fd <= 2 {
+        bail!("--progress-fd cannot be stdin, stdout or stderr");
+    }
+    // SAFETY: the descriptor is handed to us by the parent process for
+    // exclusive use and is never closed by anything else.
+    let file = unsafe { File::from_raw_fd(fd) };
+    *PROGRESS_SINK.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_progress_fd(_fd: i32) -> Result<(), Error> {
+    bail!("--progress-fd is not supported on this platform, use --progress-file")
+}
+
+/// Sends progress events to a file or named pipe.
+pub fn set_progress_file(path: &Path) -> Result<(), Error> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .path_context(path, "unable to open progress file")?;
+    *PROGRESS_SINK.lock().unwrap() = Some(file);
+    Ok(())
+}
+
+/// Is anyone listening for progress events?
+pub fn progress_enabled() -> bool {
+    PROGRESS_SINK.lock().unwrap().is_some()
+}
+
+/// Emits a progress event if a progress sink is configured.
+///
+/// Failing to write an event never fails the operation, the sink is dropped
+/// instead (for instance when the frontend went away).
+pub fn emit(event: ProgressEvent<'_>) {
+    let mut sink = PROGRESS_SINK.lock().unwrap();
+    let Some(ref mut file) = *sink else {
+        return;
+    };
+    let envelope = Envelope {
+        version: PROGRESS_FORMAT_VERSION,
+        event: &event,
+    };
+    let mut line = match serde_json::to_vec(&envelope) {
+        Ok(line) => line,
+        Err(_) => return,
+    };
+    line.push(b'\n');
+    if file.write_all(&line).and_then(|_| file.flush()).is_err() {
+        *sink = None;
+    }
+}
+
+#[test]
+fn test_event_format() {
+    let event = ProgressEvent::DownloadProgress {
+        url: "https://example.com/python.tar.zst",
+        downloaded: 512,
+        total: Some(1024),
+    };
+    let envelope = Envelope {
+        version: PROGRESS_FORMAT_VERSION,
+        event: &event,
+    };
+    assert_eq!(
+        serde_json::to_string(&envelope).unwrap(),
+        r#"{"version":1,"event":"download-progress","url":"https://example.com/python.tar.zst","downloaded":512,"total":1024}"#
+    );
+}
@@ -6,33 +6,46 @@ use std::env;
 use std::ffi::OsStr;
 use std::ffi::OsString;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::bootstrap::ensure_self_venv;
 use crate::config::Config;
 use crate::consts::VENV_BIN;
-use crate::platform::{get_python_version_request_from_pyenv_pin, list_known_toolchains};
+use crate::gates::{Gate, GateCheck};
+use crate::lock::{BinaryPolicy, EditableMode, LockHeader, LockMode, Pin};
+use crate::platform::{
+    find_pyenv_pin, get_python_version_request_from_pyenv_pin, list_known_toolchains,
+};
+use crate::presets::cached_preset_scripts;
 use crate::sources::py::{get_download_url, matches_version, PythonVersion, PythonVersionRequest};
 use crate::sync::VenvMarker;
+use crate::tui::{WarningCategory, WarningFilter, WarningPolicy};
 use crate::utils::{
     escape_string, expand_env_vars, format_requirement, get_short_executable_name, is_executable,
-    toml,
+    mask_url_credentials, toml,
 };
 use crate::utils::{CommandOutput, IoPathContext};
 use anyhow::{anyhow, bail, Context, Error};
 use globset::GlobBuilder;
-use once_cell::sync::Lazy;
+use once_cell::sync::{Lazy, OnceCell};
 use pep440_rs::{Operator, Version, VersionSpecifiers};
 use pep508_rs::Requirement;
 use python_pkginfo::Metadata;
 use regex::Regex;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use tempfile::{NamedTempFile, TempPath};
 use toml_edit::{Array, DocumentMut, Formatted, Item, Table, TableLike, Value};
 use url::Url;
 static NORMALIZATION_SPLIT_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"[-_.]+").unwrap());
+static PIN_DATE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}$").unwrap());
+
+/// The `.env` files in the project root that are loaded with `load-dotenv`.
+const DOTENV_FILES: &[&str] = &[".env", ".env.local"];
 
 const PROJECT_METADATA_SCRIPT: &str = r#"
 import json
@@ -132,6 +145,8 @@ pub struct SourceRef {
     pub verify_ssl: bool,
     pub username: Option<String>,
     pub password: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
     pub ty: SourceRefType,
 }
 
@@ -143,6 +158,8 @@ impl SourceRef {
             verify_ssl: true,
             username: None,
             password: None,
+            client_cert: None,
+            client_key: None,
             ty,
         }
     }
@@ -171,6 +188,16 @@ impl SourceRef {
             .get("password")
             .and_then(|x| x.as_str())
             .map(|x| x.to_string());
+        let client_cert = source
+            .get("client-cert")
+            .or_else(|| source.get("client_cert"))
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string());
+        let client_key = source
+            .get("client-key")
+            .or_else(|| source.get("client_key"))
+            .and_then(|x| x.as_str())
+            .map(|x| x.to_string());
         let ty = source
             .get("type")
             .and_then(|x| x.as_str())
@@ -182,41 +209,213 @@ impl SourceRef {
             verify_ssl,
             username,
             password,
+            client_cert,
+            client_key,
             ty,
         })
     }
 
     /// Returns the URL with authentication expanded.
     ///
-    /// This also fills in environment variables if there are any.
+    /// This also fills in environment variables if there are any.  Referencing
+    /// an environment variable that is not set is an error.
     pub fn expand_url(&self) -> Result<Url, Error> {
-        let mut url =
-            Url::parse(&expand_env_vars(&self.url, |name: &str| std::env::var(name).ok()) as &str)
-                .context("invalid source url")?;
+        let expanded = self.expand_env_vars(&self.url)?;
+        let mut url = Url::parse(&expanded).with_context(|| {
+            format!(
+                "invalid url for source '{}': {}",
+                self.name,
+                mask_url_credentials(&expanded)
+            )
+        })?;
         if let Some(ref username) = self.username {
-            url.set_username(username).ok();
+            url.set_username(&self.expand_env_vars(username)?).ok();
         }
         if let Some(ref password) = self.password {
-            url.set_password(Some(password)).ok();
+            url.set_password(Some(&self.expand_env_vars(password)?))
+                .ok();
         }
         Ok(url)
     }
+
+    /// Returns the client certificate for mutual TLS with this source.
+    ///
+    /// Environment variables in the paths are expanded and the files must exist.
+    pub fn expand_client_cert(&self) -> Result<Option<ClientCert>, Error> {
+        let cert = match self.client_cert {
+            Some(ref cert) => PathBuf::from(&*self.expand_env_vars(cert)?),
+            None if self.client_key.is_some() => {
+                bail!("source '{}' has a client-key but no client-cert", self.name)
+            }
+            None => return Ok(None),
+        };
+        let key = match self.client_key {
+            Some(ref key) => Some(PathBuf::from(&*self.expand_env_vars(key)?)),
+            None => None,
+        };
+        for path in std::iter::once(&cert).chain(key.as_ref()) {
+            if !path.is_file() {
+                bail!(
+                    "client certificate file '{}' of source '{}' does not exist",
+                    path.display(),
+                    self.name
+                );
+            }
+        }
+        let url = self.expand_url()?;
+        let host = url
+            .host_str()
+            .ok_or_else(|| anyhow!("source '{}' has no host for its client-cert", self.name))?;
+        Ok(Some(ClientCert {
+            host: host.to_string(),
+            cert,
+            key,
+        }))
+    }
+
+    /// Returns the path of a find-links source given as relative `file:` url.
+    ///
+    /// Backslashes are accepted as separators and normalized to forward slashes.
+    pub fn relative_find_links_path(&self) -> Result<Option<String>, Error> {
+        if !matches!(self.ty, SourceRefType::FindLinks) {
+            return Ok(None);
+        }
+        let expanded = self.expand_env_vars(&self.url)?;
+        let Some(path) = expanded.strip_prefix("file:") else {
+            return Ok(None);
+        };
+        let path = path.replace('\\', "/");
+        let has_drive =
+            path.as_bytes().get(1) == Some(&b':') && path.as_bytes()[0].is_ascii_alphabetic();
+        if path.starts_with('/') || has_drive {
+            return Ok(None);
+        }
+        Ok(Some(path))
+    }
+
+    /// Returns the URL with the credentials masked for display.
+    pub fn display_url(&self) -> Cow<'_, str> {
+        mask_url_credentials(&self.url)
+    }
+
+    fn expand_env_vars<'a>(&self, value: &'a str) -> Result<Cow<'a, str>, Error> {
+        let mut missing = None;
+        let rv = expand_env_vars(value, |name: &str| match std::env::var(name) {
+            Ok(value) => Some(value),
+            // the project root is only known when invoking the resolver
+            Err(_) if name == "PROJECT_ROOT" => None,
+            Err(_) => {
+                missing.get_or_insert_with(|| name.to_string());
+                None
+            }
+        });
+        if let Some(name) = missing {
+            bail!(
+                "source '{}' references environment variable {} which is not set",
+                self.name,
+                name
+            );
+        }
+        Ok(rv)
+    }
+}
+
+/// A client certificate for mutual TLS with the host of a source.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClientCert {
+    pub host: String,
+    pub cert: PathBuf,
+    pub key: Option<PathBuf>,
+}
+
+impl ClientCert {
+    /// Finds the client certificate to present to the host of a URL.
+    pub fn find<'a>(client_certs: &'a [ClientCert], url: &str) -> Option<&'a ClientCert> {
+        let url = Url::parse(url).ok()?;
+        let host = url.host_str()?;
+        client_certs.iter().find(|x| x.host == host)
+    }
+
+    /// Configures a curl handle to present this certificate.
+    pub fn configure_curl(&self, handle: &mut curl::easy::Easy) -> Result<(), Error> {
+        handle.ssl_cert(&self.cert)?;
+        if let Some(ref key) = self.key {
+            handle.ssl_key(key)?;
+        }
+        Ok(())
+    }
+}
+
+/// The identity file that is passed to uv in `SSL_CLIENT_CERT`.
+///
+/// uv expects the certificate and the private key in a single file, so a
+/// separate key is combined with the certificate into a temporary file.
+#[derive(Debug)]
+enum UvClientCert {
+    File(PathBuf),
+    Combined(TempPath),
+}
+
+impl UvClientCert {
+    fn new(client_cert: &ClientCert) -> Result<UvClientCert, Error> {
+        let key = match client_cert.key {
+            Some(ref key) => key,
+            None => return Ok(UvClientCert::File(client_cert.cert.clone())),
+        };
+        let mut file = NamedTempFile::new()?;
+        for path in [&client_cert.cert, key] {
+            let mut contents =
+                fs::read(path).path_context(path, "failed to read client certificate")?;
+            if !contents.ends_with(b"\n") {
+                contents.push(b'\n');
+            }
+            file.write_all(&contents)?;
+        }
+        file.flush()?;
+        Ok(UvClientCert::Combined(file.into_temp_path()))
+    }
+
+    fn path(&self) -> &Path {
+        match self {
+            UvClientCert::File(path) => path,
+            UvClientCert::Combined(path) => path,
+        }
+    }
 }
 
 type EnvVars = HashMap<String, String>;
-type EnvFile = Option<PathBuf>;
+type EnvFiles = Vec<PathBuf>;
 
 /// A reference to a script
 #[derive(Clone, Debug)]
 pub enum Script {
     /// Call python module entry
-    Call(String, EnvVars, EnvFile),
-    /// A command alias
-    Cmd(Vec<String>, EnvVars, EnvFile),
+    Call(String, EnvVars, EnvFiles),
+    /// A command alias with the platform variant it was selected from
+    Cmd(Vec<String>, EnvVars, EnvFiles, Option<&'static str>),
     /// A multi-script execution
     Chain(Vec<Vec<String>>),
     /// External script reference
     External(PathBuf),
+    /// A command alias without a variant for the current platform
+    Unavailable(Vec<String>),
+}
+
+/// The keys of platform specific command variants.
+pub const SCRIPT_PLATFORMS: &[&str] = &["windows", "macos", "linux", "unix"];
+
+/// The keys of platform specific command variants that apply to the current
+/// platform, the most specific one first.
+fn current_platform_keys() -> &'static [&'static str] {
+    if cfg!(windows) {
+        &["windows"]
+    } else if cfg!(target_os = "macos") {
+        &["macos", "unix"]
+    } else if cfg!(target_os = "linux") {
+        &["linux", "unix"]
+    } else {
+        &["unix"]
+    }
 }
 
 fn toml_array_as_string_array(arr: &Array) -> Vec<String> {
@@ -259,34 +458,52 @@ impl Script {
             env_vars
         }
 
-        fn get_env_file(detailed: &dyn TableLike) -> EnvFile {
-            detailed
-                .get("env-file")
-                .and_then(|x| x.as_str())
-                .map(PathBuf::from)
+        fn get_env_files(detailed: &dyn TableLike) -> EnvFiles {
+            match detailed.get("env-file").and_then(|x| x.as_value()) {
+                Some(Value::String(s)) => vec![PathBuf::from(s.value())],
+                Some(Value::Array(arr)) => toml_array_as_string_array(arr)
+                    .into_iter()
+                    .map(PathBuf::from)
+                    .collect(),
+                _ => Vec::new(),
+            }
         }
 
         if let Some(detailed) = item.as_table_like() {
             if let Some(call) = detailed.get("call") {
                 let entry = call.as_str()?.to_string();
                 let env_vars = get_env_vars(detailed);
-                let env_file = get_env_file(detailed);
-                Some(Script::Call(entry, env_vars, env_file))
+                let env_files = get_env_files(detailed);
+                Some(Script::Call(entry, env_vars, env_files))
             } else if let Some(cmds) = detailed.get("chain").and_then(|x| x.as_array()) {
                 Some(Script::Chain(
                     cmds.iter().flat_map(toml_value_as_command_args).collect(),
                 ))
             } else if let Some(cmd) = detailed.get("cmd") {
-                let cmd = toml_value_as_command_args(cmd.as_value()?)?;
+                let (cmd, platform) = match cmd.as_table_like() {
+                    Some(variants) => {
+                        let Some(platform) = current_platform_keys()
+                            .iter()
+                            .find(|key| variants.contains_key(key))
+                        else {
+                            return Some(Script::Unavailable(
+                                variants.iter().map(|x| x.0.to_string()).collect(),
+                            ));
+                        };
+                        let cmd = variants.get(platform)?.as_value()?;
+                        (toml_value_as_command_args(cmd)?, Some(*platform))
+                    }
+                    None => (toml_value_as_command_args(cmd.as_value()?)?, None),
+                };
                 let env_vars = get_env_vars(detailed);
-                let env_file = get_env_file(detailed);
-                Some(Script::Cmd(cmd, env_vars, env_file))
+                let env_files = get_env_files(detailed);
+                Some(Script::Cmd(cmd, env_vars, env_files, platform))
             } else {
                 None
             }
         } else {
             toml_value_as_command_args(item.as_value()?)
-                .map(|cmd| Script::Cmd(cmd, EnvVars::default(), None))
+                .map(|cmd| Script::Cmd(cmd, EnvVars::default(), EnvFiles::default(), None))
         }
     }
 }
@@ -299,7 +516,7 @@ fn shlex_quote_unsafe(s: &str) -> Cow<'_, str> {
 impl fmt::Display for Script {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Script::Call(entry, env, env_file) => {
+            Script::Call(entry, env, env_files) => {
                 write!(f, "{}", shlex_quote_unsafe(entry))?;
                 if !env.is_empty() {
                     write!(f, " (env: ")?;
@@ -316,12 +533,19 @@ impl fmt::Display for Script {
                     }
                     write!(f, ")")?;
                 }
-                if let Some(ref env_file) = env_file {
-                    write!(f, " (env-file: {})", env_file.display())?;
+                if !env_files.is_empty() {
+                    write!(f, " (env-file: ")?;
+                    for (idx, env_file) in env_files.iter().enumerate() {
+                        if idx > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", env_file.display())?;
+                    }
+                    write!(f, ")")?;
                 }
                 Ok(())
             }
-            Script::Cmd(args, env, env_file) => {
+            Script::Cmd(args, env, env_files, platform) => {
                 let mut need_space = false;
                 for (key, value) in env.iter() {
                     if need_space {
@@ -342,8 +566,18 @@ impl fmt::Display for Script {
                     write!(f, "{}", shlex_quote_unsafe(arg))?;
                     need_space = true;
                 }
-                if let Some(ref env_file) = env_file {
-                    write!(f, " (env-file: {})", env_file.display())?;
+                if !env_files.is_empty() {
+                    write!(f, " (env-file: ")?;
+                    for (idx, env_file) in env_files.iter().enumerate() {
+                        if idx > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}", env_file.display())?;
+                    }
+                    write!(f, ")")?;
+                }
+                if let Some(platform) = platform {
+                    write!(f, " (platform: {})", platform)?;
                 }
                 Ok(())
             }
@@ -365,10 +599,24 @@ impl fmt::Display for Script {
                 Ok(())
             }
             Script::External(ref script) => write!(f, "external: {}", script.display()),
+            Script::Unavailable(platforms) => {
+                write!(f, "not available, only on: {}", platforms.join(", "))
+            }
         }
     }
 }
 
+/// Name of the workspace member cache within the virtualenv.
+const WORKSPACE_MEMBER_CACHE: &str = "rye-workspace-members.json";
+
+/// Cached result of the workspace member discovery.
+#[derive(Serialize, Deserialize)]
+struct WorkspaceMemberCache {
+    members: Option<Vec<String>>,
+    dirs: Vec<(String, u128)>,
+    projects: Vec<String>,
+}
+
 #[derive(Debug)]
 pub struct Workspace {
     root: PathBuf,
@@ -452,7 +700,7 @@ impl Workspace {
                                     }
                                 }
                                 Err(err) => {
-                                    echo!("warning: workspace.members: {}", err);
+                                    warn!(category = Workspace, "workspace.members: {}", err);
                                 }
                             }
                         }
@@ -469,29 +717,150 @@ impl Workspace {
     pub fn iter_projects<'a>(
         self: &'a Arc<Self>,
     ) -> impl Iterator<Item = Result<PyProject, Error>> + 'a {
-        walkdir::WalkDir::new(&self.root)
+        self.member_dirs()
+            .into_iter()
+            .filter_map(move |dir| match dir {
+                Ok(dir) => {
+                    match PyProject::load_with_workspace(&dir.join("pyproject.toml"), self.clone())
+                    {
+                        Ok(Some(project)) => Some(Ok(project)),
+                        Ok(None) => None,
+                        Err(err) => Some(Err(err)),
+                    }
+                }
+                Err(err) => Some(Err(err)),
+            })
+    }
+
+    /// Iterates through the projects that share the workspace virtualenv.
+    ///
+    /// Members that override the Python version are skipped.
+    pub fn iter_venv_projects<'a>(
+        self: &'a Arc<Self>,
+    ) -> impl Iterator<Item = Result<PyProject, Error>> + 'a {
+        self.iter_projects()
+            .filter(|x| x.as_ref().map_or(true, |x| !x.has_python_override()))
+    }
+
+    /// Returns the folders of all projects that are members of the workspace.
+    ///
+    /// If all members are declared as literal paths no directory walking is
+    /// necessary.  Otherwise the result of the walk is cached in the virtualenv
+    /// together with the modification times of all visited directories.
+    fn member_dirs(&self) -> Vec<Result<PathBuf, Error>> {
+        if let Some(dirs) = self.literal_member_dirs() {
+            return dirs.into_iter().map(Ok).collect();
+        }
+
+        let cache_path = self.venv_path().join(WORKSPACE_MEMBER_CACHE);
+        if let Some(dirs) = self.load_member_cache(&cache_path) {
+            return dirs.into_iter().map(Ok).collect();
+        }
+
+        let mut rv = Vec::new();
+        let mut visited = Vec::new();
+        let mut failed = false;
+        for entry in walkdir::WalkDir::new(&self.root)
             .into_iter()
             .filter_entry(|entry| {
                 !(entry.file_type().is_dir() && skip_recurse_into(entry.file_name()))
             })
-            .filter_map(move |entry| match entry {
+        {
+            match entry {
                 Ok(entry) => {
-                    if entry.file_type().is_file()
+                    if entry.file_type().is_dir() {
+                        match entry.metadata().ok().and_then(|x| x.modified().ok()) {
+                            Some(mtime) => visited.push((entry.path().to_path_buf(), mtime)),
+                            None => failed = true,
+                        }
+                    } else if entry.file_type().is_file()
                         && entry.file_name() == OsStr::new("pyproject.toml")
                         && self.is_member(entry.path().parent().unwrap())
                     {
-                        let project =
-                            match PyProject::load_with_workspace(entry.path(), self.clone()) {
-                                Ok(Some(project)) => project,
-                                Ok(None) => return None,
-                                Err(err) => return Some(Err(err)),
-                            };
-                        return Some(Ok(project));
+                        rv.push(Ok(entry.path().parent().unwrap().to_path_buf()));
                     }
-                    None
                 }
-                Err(err) => Some(Err(err.into())),
-            })
+                Err(err) => {
+                    failed = true;
+                    rv.push(Err(err.into()));
+                }
+            }
+        }
+
+        if !failed && cache_path.parent().map_or(false, |x| x.is_dir()) {
+            self.write_member_cache(&cache_path, &visited, &rv);
+        }
+
+        rv
+    }
+
+    /// Returns the member folders if all members are literal paths.
+    fn literal_member_dirs(&self) -> Option<Vec<PathBuf>> {
+        let members = self.members.as_ref()?;
+        let mut rv = vec![self.root.clone()];
+        for member in members {
+            if member.contains(['*', '?', '[', '{'])
+                || Path::new(member)
+                    .components()
+                    .any(|x| skip_recurse_into(x.as_os_str()))
+            {
+                return None;
+            }
+            let path = self.root.join(member);
+            if path.join("pyproject.toml").is_file() && !rv.contains(&path) {
+                rv.push(path);
+            }
+        }
+        Some(rv)
+    }
+
+    fn load_member_cache(&self, cache_path: &Path) -> Option<Vec<PathBuf>> {
+        let cache: WorkspaceMemberCache =
+            serde_json::from_slice(&fs::read(cache_path).ok()?).ok()?;
+        if cache.members != self.members {
+            return None;
+        }
+        for (dir, mtime) in &cache.dirs {
+            let modified = fs::metadata(self.root.join(dir)).ok()?.modified().ok()?;
+            if modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() != *mtime {
+                return None;
+            }
+        }
+        Some(cache.projects.iter().map(|x| self.root.join(x)).collect())
+    }
+
+    fn write_member_cache(
+        &self,
+        cache_path: &Path,
+        visited: &[(PathBuf, SystemTime)],
+        dirs: &[Result<PathBuf, Error>],
+    ) {
+        let relative = |path: &Path| {
+            path.strip_prefix(&self.root)
+                .ok()
+                .map(|x| x.to_string_lossy().into_owned())
+        };
+        let cache = WorkspaceMemberCache {
+            members: self.members.clone(),
+            dirs: visited
+                .iter()
+                .filter_map(|(path, mtime)| {
+                    Some((
+                        relative(path)?,
+                        mtime.duration_since(UNIX_EPOCH).ok()?.as_nanos(),
+                    ))
+                })
+                .collect(),
+            projects: dirs
+                .iter()
+                .filter_map(|x| x.as_ref().ok())
+                .filter_map(|x| relative(x))
+                .collect(),
+        };
+        if let Ok(contents) = serde_json::to_vec(&cache) {
+            // the cache is only an optimization, failing to write it is not an error.
+            fs::write(cache_path, contents).ok();
+        }
     }
 
     /// Looks up a single project.
@@ -526,6 +895,11 @@ impl Workspace {
         resolve_intended_venv_python_version(&self.doc, &self.root)
     }
 
+    /// Returns the requested Python version and where it was configured.
+    pub fn python_version_request(&self) -> Option<(PythonVersionRequest, PythonVersionSource)> {
+        resolve_python_version_request(&self.doc, &self.root)
+    }
+
     /// Returns a list of index URLs that should be considered.
     pub fn sources(&self) -> Result<Vec<SourceRef>, Error> {
         get_sources(&self.doc)
@@ -550,12 +924,80 @@ impl Workspace {
     pub fn lock_with_sources(&self) -> bool {
         lock_with_sources(&self.doc)
     }
+
+    /// Should sync compile Python files to bytecode?
+    pub fn compile_bytecode(&self) -> bool {
+        compile_bytecode(&self.doc)
+    }
+
+    /// Should sync check that the entry points are importable?
+    pub fn check_entry_points(&self) -> bool {
+        check_entry_points(&self.doc)
+    }
+
+    /// Should builds reuse cached artifacts?
+    pub fn build_cache(&self) -> bool {
+        build_cache(&self.doc)
+    }
+
+    /// Should locking write a constraints file per workspace member?
+    pub fn member_constraints(&self) -> bool {
+        member_constraints(&self.doc)
+    }
+
+    /// Should dependency arrays be kept sorted?
+    pub fn sort_dependencies(&self) -> bool {
+        sort_dependencies(&self.doc)
+    }
+
+    /// Must all scripts in `tool.rye.scripts` have a help text?
+    pub fn require_script_help(&self) -> bool {
+        require_script_help(&self.doc)
+    }
+
+    /// What to write into the header of lockfiles.
+    pub fn lock_header(&self) -> Result<LockHeader, Error> {
+        lock_header(&self.doc)
+    }
+
+    /// Which packages may be installed from wheels and which from source.
+    pub fn binary_policy(&self) -> Result<BinaryPolicy, Error> {
+        binary_policy(&self.doc)
+    }
+
+    /// The editable mode requested from the build backend.
+    pub fn editable_mode(&self) -> Result<Option<EditableMode>, Error> {
+        editable_mode(&self.doc)
+    }
+
+    /// Temporary pins that are applied as constraints when locking.
+    pub fn pins(&self) -> Result<Vec<Pin>, Error> {
+        pins(&self.doc)
+    }
+
+    /// The shared presets whose dev-dependencies and scripts are merged in.
+    pub fn dev_presets(&self) -> Result<Vec<String>, Error> {
+        dev_presets(&self.doc)
+    }
+
+    /// Returns the warning policy configured for the workspace.
+    pub fn warning_policy(&self) -> Result<WarningPolicy, Error> {
+        warning_policy(&self.doc)
+    }
+
+    /// The checks that have to pass before a gated command runs.
+    pub fn gate_checks(&self, gate: Gate) -> Result<Vec<GateCheck>, Error> {
+        gate_checks(&self.doc, gate)
+    }
 }
 
 /// Check if recurse should be skipped into directory with this name
 fn skip_recurse_into(name: &OsStr) -> bool {
-    // We want to ignore hidden directories: .venv, .git, and others.
-    name.to_str().map(|s| s.starts_with('.')).unwrap_or(false)
+    // We want to ignore hidden directories: .venv, .git, and others.  Bytecode caches
+    // are skipped as they change all the time which would defeat the member cache.
+    name.to_str()
+        .map(|s| s.starts_with('.') || s == "__pycache__")
+        .unwrap_or(false)
 }
 
 /// Could not auto-discover any pyproject
@@ -577,6 +1019,8 @@ pub struct PyProject {
     basename: OsString,
     workspace: Option<Arc<Workspace>>,
     doc: DocumentMut,
+    preset_scripts: OnceCell<Table>,
+    python_override: Option<(PythonVersionRequest, PythonVersionSource)>,
 }
 
 /// Returns an implicit table.
@@ -635,12 +1079,7 @@ impl PyProject {
             None => bail!("project {} has no file name", root.display()),
         };
 
-        Ok(PyProject {
-            root: root.to_owned(),
-            basename,
-            workspace,
-            doc,
-        })
+        Ok(PyProject::new(root, basename, workspace, doc))
     }
 
     /// Loads a pyproject toml with a given workspace.
@@ -670,12 +1109,25 @@ impl PyProject {
             None => bail!("project {} has no file name", root.display()),
         };
 
-        Ok(Some(PyProject {
+        Ok(Some(PyProject::new(root, basename, Some(workspace), doc)))
+    }
+
+    fn new(
+        root: &Path,
+        basename: OsString,
+        workspace: Option<Arc<Workspace>>,
+        doc: DocumentMut,
+    ) -> PyProject {
+        let mut rv = PyProject {
             root: root.to_owned(),
             basename,
-            workspace: Some(workspace),
+            workspace,
             doc,
-        }))
+            preset_scripts: OnceCell::new(),
+            python_override: None,
+        };
+        rv.python_override = rv.find_python_override();
+        rv
     }
 
     /// Returns a reference to the workspace.
@@ -711,14 +1163,63 @@ impl PyProject {
         Cow::Owned(self.root.join(&self.basename))
     }
 
+    /// Returns the Python version a workspace member pins for itself.
+    ///
+    /// A member other than the workspace root can override the Python version
+    /// of the workspace with `tool.rye.python` or a `.python-version` file in
+    /// its own folder.  Such a member gets its own virtualenv and lockfiles.
+    pub fn python_override(&self) -> Option<&(PythonVersionRequest, PythonVersionSource)> {
+        self.python_override.as_ref()
+    }
+
+    /// Looks up the Python version override once when the project is loaded.
+    fn find_python_override(&self) -> Option<(PythonVersionRequest, PythonVersionSource)> {
+        if self.is_workspace_root() {
+            return None;
+        }
+        python_pin(&self.doc)
+            .map(|ver| (ver, PythonVersionSource::PyprojectPin))
+            .or_else(|| {
+                find_pyenv_pin(&self.root)
+                    .filter(|(path, _)| path.parent() == Some(self.root.as_path()))
+                    .map(|(path, ver)| (ver, PythonVersionSource::PinFile(path)))
+            })
+    }
+
+    /// Does this workspace member override the Python version of the workspace?
+    pub fn has_python_override(&self) -> bool {
+        self.python_override().is_some()
+    }
+
+    /// Returns the workspace that this project shares its virtualenv with.
+    pub fn venv_workspace(&self) -> Option<&Arc<Workspace>> {
+        self.workspace().filter(|_| !self.has_python_override())
+    }
+
     /// Returns the location of the virtualenv.
     pub fn venv_path(&self) -> Cow<'_, Path> {
-        match self.workspace() {
+        match self.venv_workspace() {
             Some(ws) => ws.venv_path(),
             None => self.root.join(".venv").into(),
         }
     }
 
+    /// Returns the path of the lockfile for the given mode.
+    ///
+    /// Lockfiles are stored in the workspace root.  Members that override
+    /// the Python version have their own lockfiles named after the member.
+    pub fn lockfile_path(&self, lock_mode: LockMode) -> PathBuf {
+        let base = match lock_mode {
+            LockMode::Production => "requirements",
+            LockMode::Dev => "requirements-dev",
+        };
+        let filename = match self.name().filter(|_| self.has_python_override()) {
+            Some(name) => format!("{}.{}.lock", base, normalize_package_name(name)),
+            None => format!("{}.lock", base),
+        };
+        self.workspace_path().join(filename)
+    }
+
     /// Returns the virtualenv bin path of the virtualenv.
     pub fn venv_bin_path(&self) -> Cow<'_, Path> {
         Cow::Owned(self.venv_path().join(VENV_BIN))
@@ -726,24 +1227,42 @@ impl PyProject {
 
     /// Returns the project's target python version
     pub fn target_python_version(&self) -> Option<PythonVersionRequest> {
-        if let Some(workspace) = self.workspace() {
+        if let Some(workspace) = self.venv_workspace() {
             workspace.target_python_version()
         } else {
             resolve_target_python_version(&self.doc, &self.root, &self.venv_path())
         }
     }
 
+    /// Returns the `requires-python` constraint of the project.
+    pub fn requires_python(&self) -> Option<VersionSpecifiers> {
+        self.doc
+            .get("project")
+            .and_then(|x| x.get("requires-python"))
+            .and_then(|x| x.as_str())
+            .and_then(|s| s.parse().ok())
+    }
+
     /// Returns the project's intended venv python version.
     ///
     /// This is the python version that should be used for virtualenvs.
     pub fn venv_python_version(&self) -> Result<PythonVersion, Error> {
-        if let Some(workspace) = self.workspace() {
+        if let Some(workspace) = self.venv_workspace() {
             workspace.venv_python_version()
         } else {
             resolve_intended_venv_python_version(&self.doc, &self.root)
         }
     }
 
+    /// Returns the requested Python version and where it was configured.
+    pub fn python_version_request(&self) -> Option<(PythonVersionRequest, PythonVersionSource)> {
+        if let Some(workspace) = self.venv_workspace() {
+            workspace.python_version_request()
+        } else {
+            resolve_python_version_request(&self.doc, &self.root)
+        }
+    }
+
     /// Set the target Python version.
     pub fn set_target_python_version(&mut self, version: &PythonVersionRequest) {
         let mut marker = format!(">= {}", version.major);
@@ -759,6 +1278,14 @@ impl PyProject {
         project["requires-python"] = Item::Value(Value::String(Formatted::new(marker)));
     }
 
+    /// Pins the Python version in `tool.rye.python`.
+    pub fn set_python_pin(&mut self, version: &str) -> Result<(), Error> {
+        self.obtain_tool_config_table()?["python"] =
+            Item::Value(Value::String(Formatted::new(version.to_string())));
+        self.python_override = self.find_python_override();
+        Ok(())
+    }
+
     /// Set the project version.
     pub fn set_version(&mut self, version: &Version) {
         let project = self
@@ -833,6 +1360,26 @@ impl PyProject {
         }
     }
 
+    /// Returns the raw `build-system.build-backend` value.
+    pub fn build_backend_name(&self) -> Option<&str> {
+        self.doc
+            .get("build-system")
+            .and_then(|x| x.get("build-backend"))
+            .and_then(|x| x.as_str())
+    }
+
+    /// Returns the requirements declared in `build-system.requires`.
+    pub fn build_requires(&self) -> Vec<String> {
+        self.doc
+            .get("build-system")
+            .and_then(|x| x.get("requires"))
+            .and_then(|x| x.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.as_str().map(|x| x.to_string()))
+            .collect()
+    }
+
     /// Returns the build backend.
     pub fn build_backend(&self) -> Option<BuildSystem> {
         let backend = self
@@ -849,6 +1396,7 @@ impl PyProject {
         };
         if self.is_virtual() && build_system.is_some() {
             warn!(
+                category = Config,
                 "project '{}' is virtual but defines build-system",
                 self.name().unwrap_or("")
             );
@@ -863,28 +1411,68 @@ impl PyProject {
         if is_executable(&external) && !is_unsafe_script(&external) {
             Some(Script::External(external))
         } else {
-            Script::from_toml_item(
-                self.doc
-                    .get("tool")
-                    .and_then(|x| x.get("rye"))
-                    .and_then(|x| x.get("scripts"))
-                    .and_then(|x| x.get(key))?,
-            )
+            self.get_declared_script(key)
         }
     }
 
-    /// Returns a list of known scripts.
-    pub fn list_scripts(&self) -> HashSet<String> {
-        let mut rv = match self
-            .doc
+    /// Looks up a script declared in `tool.rye.scripts` or in a dev preset.
+    pub fn get_declared_script(&self, key: &str) -> Option<Script> {
+        Script::from_toml_item(self.declared_script_item(key)?)
+    }
+
+    /// Returns the help text of a script declared in `tool.rye.scripts` or in a dev preset.
+    pub fn script_help(&self, key: &str) -> Option<&str> {
+        self.declared_script_item(key)?
+            .as_table_like()?
+            .get("help")?
+            .as_str()
+    }
+
+    /// Returns the names of the scripts declared in `tool.rye.scripts` and in dev presets.
+    pub fn declared_scripts(&self) -> Vec<String> {
+        let mut rv = match self.scripts_table() {
+            Some(tbl) => tbl.iter().map(|x| x.0.to_string()).collect(),
+            None => Vec::new(),
+        };
+        for (key, _) in self.preset_scripts().iter() {
+            if !rv.iter().any(|x| x == key) {
+                rv.push(key.to_string());
+            }
+        }
+        rv
+    }
+
+    /// Scripts of the project take precedence over the ones of dev presets.
+    fn declared_script_item(&self, key: &str) -> Option<&Item> {
+        self.scripts_table()
+            .and_then(|x| x.get(key))
+            .or_else(|| self.preset_scripts().get(key))
+    }
+
+    /// The scripts of the dev presets.  Remote presets are only known once
+    /// they were fetched by locking.
+    fn preset_scripts(&self) -> &Table {
+        self.preset_scripts.get_or_init(|| {
+            let sources = self.dev_presets().unwrap_or_default();
+            if sources.is_empty() {
+                Table::new()
+            } else {
+                cached_preset_scripts(&sources, &self.workspace_path())
+            }
+        })
+    }
+
+    fn scripts_table(&self) -> Option<&dyn TableLike> {
+        self.doc
             .get("tool")
             .and_then(|x| x.get("rye"))
             .and_then(|x| x.get("scripts"))
             .and_then(|x| x.as_table_like())
-        {
-            Some(tbl) => tbl.iter().map(|x| x.0.to_string()).collect(),
-            None => HashSet::new(),
-        };
+    }
+
+    /// Returns a list of known scripts.
+    pub fn list_scripts(&self) -> HashSet<String> {
+        let mut rv = self.declared_scripts().into_iter().collect::<HashSet<_>>();
         for entry in fs::read_dir(self.venv_bin_path())
             .ok()
             .into_iter()
@@ -898,12 +1486,76 @@ impl PyProject {
         rv
     }
 
-    /// Returns a set of all extras.
-    pub fn extras(&self) -> HashSet<&str> {
+    /// The `.env` files that `rye run` and `rye test` load, later files take
+    /// precedence.
+    ///
+    /// Only returns files if `tool.rye.load-dotenv` (or `behavior.load-dotenv`
+    /// in the config) is enabled.  The files do not need to exist.
+    pub fn dotenv_files(&self) -> Vec<PathBuf> {
+        let enabled = self
+            .doc
+            .get("tool")
+            .and_then(|x| x.get("rye"))
+            .and_then(|x| x.get("load-dotenv"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or_else(|| Config::current().load_dotenv());
+        if !enabled {
+            return Vec::new();
+        }
+        DOTENV_FILES
+            .iter()
+            .map(|x| self.root_path().join(x))
+            .collect()
+    }
+
+    /// Is the version of the project computed from the version control system?
+    pub fn version_from_vcs(&self) -> bool {
         self.doc
-            .get("project")
-            .and_then(|x| x.get("optional-dependencies"))
-            .and_then(|x| x.as_table_like())
+            .get("tool")
+            .and_then(|x| x.get("rye"))
+            .and_then(|x| x.get("version"))
+            .and_then(|x| x.get("from-vcs"))
+            .and_then(|x| x.as_bool())
+            .unwrap_or(false)
+    }
+
+    /// Returns the file the version is written to by `rye version sync-from-vcs`
+    /// and if it was configured in `tool.rye.version.file`.
+    ///
+    /// Defaults to the file the build backend reads the version from.
+    pub fn version_file(&self) -> Option<(PathBuf, bool)> {
+        let tool = self.doc.get("tool")?;
+        let (index, path) = [
+            &["rye", "version", "file"][..],
+            &["hatch", "version", "path"],
+            &["pdm", "version", "path"],
+            &["setuptools", "dynamic", "version", "file"],
+        ]
+        .iter()
+        .enumerate()
+        .find_map(|(index, keys)| {
+            keys.iter()
+                .try_fold(tool, |item, key| item.get(*key))
+                .and_then(|x| x.as_str())
+                .map(|path| (index, path))
+        })?;
+        Some((self.root_path().join(path), index == 0))
+    }
+
+    /// Must all scripts in `tool.rye.scripts` have a help text?
+    pub fn require_script_help(&self) -> bool {
+        match self.workspace {
+            Some(ref workspace) => workspace.require_script_help(),
+            None => require_script_help(&self.doc),
+        }
+    }
+
+    /// Returns a set of all extras.
+    pub fn extras(&self) -> HashSet<&str> {
+        self.doc
+            .get("project")
+            .and_then(|x| x.get("optional-dependencies"))
+            .and_then(|x| x.as_table_like())
             .map_or(None.into_iter(), |x| {
                 Some(x.iter().map(|x| x.0)).into_iter()
             })
@@ -917,6 +1569,7 @@ impl PyProject {
         req: &Requirement,
         kind: &DependencyKind,
     ) -> Result<(), Error> {
+        let sort = self.sort_dependencies();
         let dependencies = match kind {
             DependencyKind::Normal => &mut self.doc["project"]["dependencies"],
             DependencyKind::Dev => self
@@ -939,12 +1592,13 @@ impl PyProject {
         if dependencies.is_none() {
             *dependencies = Item::Value(Value::Array(Array::new()));
         }
-        set_dependency(
-            dependencies
-                .as_array_mut()
-                .ok_or_else(|| anyhow!("dependencies in pyproject.toml are malformed"))?,
-            req,
-        );
+        let dependencies = dependencies
+            .as_array_mut()
+            .ok_or_else(|| anyhow!("dependencies in pyproject.toml are malformed"))?;
+        set_dependency(dependencies, req);
+        if sort {
+            normalize_dependency_array(dependencies);
+        }
         Ok(())
     }
 
@@ -954,6 +1608,7 @@ impl PyProject {
         req: &Requirement,
         kind: DependencyKind,
     ) -> Result<Option<Requirement>, Error> {
+        let sort = self.sort_dependencies();
         let dependencies = match kind {
             DependencyKind::Normal => &mut self.doc["project"]["dependencies"],
             DependencyKind::Dev => self
@@ -969,15 +1624,63 @@ impl PyProject {
             }
         };
         if !dependencies.is_none() {
-            Ok(remove_dependency(
+            let dependencies = dependencies
+                .as_array_mut()
+                .ok_or_else(|| anyhow!("dependencies in pyproject.toml are malformed"))?;
+            let rv = remove_dependency(dependencies, req);
+            if sort {
+                normalize_dependency_array(dependencies);
+            }
+            Ok(rv)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Normalizes and sorts all dependency arrays.
+    ///
+    /// Returns `true` if the document changed.
+    pub fn normalize_dependencies(&mut self) -> Result<bool, Error> {
+        let before = self.doc.to_string();
+        let mut arrays = Vec::new();
+        for (key, item) in self.doc.iter_mut() {
+            let Some(table) = item.as_table_like_mut() else {
+                continue;
+            };
+            match key.get() {
+                "project" => {
+                    for (key, item) in table.iter_mut() {
+                        match key.get() {
+                            "dependencies" => arrays.push(item),
+                            "optional-dependencies" => {
+                                if let Some(optional) = item.as_table_like_mut() {
+                                    arrays.extend(optional.iter_mut().map(|x| x.1));
+                                }
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                "tool" => {
+                    if let Some(rye) = table.get_mut("rye").and_then(|x| x.as_table_like_mut()) {
+                        for (key, item) in rye.iter_mut() {
+                            if matches!(key.get(), "dev-dependencies" | "excluded-dependencies") {
+                                arrays.push(item);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        for dependencies in arrays {
+            normalize_dependency_array(
                 dependencies
                     .as_array_mut()
                     .ok_or_else(|| anyhow!("dependencies in pyproject.toml are malformed"))?,
-                req,
-            ))
-        } else {
-            Ok(None)
+            );
         }
+        Ok(self.doc.to_string() != before)
     }
 
     /// Iterates over all dependencies.
@@ -985,6 +1688,25 @@ impl PyProject {
         &self,
         kind: DependencyKind,
     ) -> impl Iterator<Item = DependencyRef> + '_ {
+        self.dependency_array(kind)
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.as_str())
+            .map(DependencyRef::new)
+    }
+
+    /// Iterates over all dependencies together with the comments attached to them.
+    pub fn iter_dependencies_with_comments(
+        &self,
+        kind: DependencyKind,
+    ) -> impl Iterator<Item = (DependencyRef, Vec<String>)> + '_ {
+        self.dependency_array(kind)
+            .into_iter()
+            .flat_map(|deps| deps.iter().zip(toml::array_item_comments(deps)))
+            .filter_map(|(dep, comments)| Some((DependencyRef::new(dep.as_str()?), comments)))
+    }
+
+    fn dependency_array(&self, kind: DependencyKind) -> Option<&Array> {
         let sec = match kind {
             DependencyKind::Normal => self.doc.get("project").and_then(|x| x.get("dependencies")),
             DependencyKind::Dev => self
@@ -1004,10 +1726,6 @@ impl PyProject {
                 .and_then(|x| x.get(section as &str)),
         };
         sec.and_then(|x| x.as_array())
-            .into_iter()
-            .flatten()
-            .filter_map(|x| x.as_str())
-            .map(DependencyRef::new)
     }
 
     /// Returns a list of sources that should be considered.
@@ -1026,6 +1744,21 @@ impl PyProject {
         }
     }
 
+    /// Returns the declared console and GUI entry points as name and target.
+    pub fn entry_points(&self) -> Vec<(String, String)> {
+        ["scripts", "gui-scripts"]
+            .iter()
+            .filter_map(|key| {
+                self.doc
+                    .get("project")
+                    .and_then(|x| x.get(key))
+                    .and_then(|x| x.as_table_like())
+            })
+            .flat_map(|table| table.iter())
+            .filter_map(|(name, target)| Some((name.to_string(), target.as_str()?.to_string())))
+            .collect()
+    }
+
     /// Is this a virtual package (does not build)
     pub fn is_virtual(&self) -> bool {
         self.doc
@@ -1060,6 +1793,107 @@ impl PyProject {
         }
     }
 
+    /// Should sync compile Python files to bytecode?
+    pub fn compile_bytecode(&self) -> bool {
+        match self.workspace {
+            Some(ref workspace) => workspace.compile_bytecode(),
+            None => compile_bytecode(&self.doc),
+        }
+    }
+
+    /// Should sync check that the entry points are importable?
+    pub fn check_entry_points(&self) -> bool {
+        match self.workspace {
+            Some(ref workspace) => workspace.check_entry_points(),
+            None => check_entry_points(&self.doc),
+        }
+    }
+
+    /// Should builds reuse cached artifacts?
+    pub fn build_cache(&self) -> bool {
+        match self.workspace {
+            Some(ref workspace) => workspace.build_cache(),
+            None => build_cache(&self.doc),
+        }
+    }
+
+    /// Should locking write a constraints file per workspace member?
+    pub fn member_constraints(&self) -> bool {
+        match self.workspace {
+            Some(ref workspace) => workspace.member_constraints(),
+            None => member_constraints(&self.doc),
+        }
+    }
+
+    /// Should dependency arrays be kept sorted?
+    pub fn sort_dependencies(&self) -> bool {
+        match self.workspace {
+            Some(ref workspace) => workspace.sort_dependencies(),
+            None => sort_dependencies(&self.doc),
+        }
+    }
+
+    /// What to write into the header of lockfiles.
+    pub fn lock_header(&self) -> Result<LockHeader, Error> {
+        match self.workspace {
+            Some(ref workspace) => workspace.lock_header(),
+            None => lock_header(&self.doc),
+        }
+    }
+
+    /// Which packages may be installed from wheels and which from source.
+    pub fn binary_policy(&self) -> Result<BinaryPolicy, Error> {
+        match self.workspace {
+            Some(ref workspace) => workspace.binary_policy(),
+            None => binary_policy(&self.doc),
+        }
+    }
+
+    /// The editable mode requested from the build backend.
+    pub fn editable_mode(&self) -> Result<Option<EditableMode>, Error> {
+        match self.workspace {
+            Some(ref workspace) => workspace.editable_mode(),
+            None => editable_mode(&self.doc),
+        }
+    }
+
+    /// Temporary pins that are applied as constraints when locking.
+    pub fn pins(&self) -> Result<Vec<Pin>, Error> {
+        match self.workspace {
+            Some(ref workspace) => workspace.pins(),
+            None => pins(&self.doc),
+        }
+    }
+
+    /// The shared presets whose dev-dependencies and scripts are merged in.
+    pub fn dev_presets(&self) -> Result<Vec<String>, Error> {
+        match self.workspace {
+            Some(ref workspace) => workspace.dev_presets(),
+            None => dev_presets(&self.doc),
+        }
+    }
+
+    /// Returns the warning policy configured for the project.
+    pub fn warning_policy(&self) -> Result<WarningPolicy, Error> {
+        match self.workspace {
+            Some(ref workspace) => workspace.warning_policy(),
+            None => warning_policy(&self.doc),
+        }
+    }
+
+    /// The checks that have to pass before a gated command runs.
+    pub fn gate_checks(&self, gate: Gate) -> Result<Vec<GateCheck>, Error> {
+        match self.workspace {
+            Some(ref workspace) => workspace.gate_checks(gate),
+            None => gate_checks(&self.doc, gate),
+        }
+    }
+
+    /// Returns the underlying document for direct modification.
+    pub fn doc_mut(&mut self) -> &mut DocumentMut {
+        &mut self.doc
+    }
+
     /// Save back changes
     pub fn save(&self) -> Result<(), Error> {
         let path = self.toml_path();
@@ -1115,6 +1949,37 @@ fn set_dependency(deps: &mut Array, req: &Requirement) {
     toml::reformat_array_multiline(deps);
 }
 
+/// Normalizes the entries of a dependency array and sorts them by name.
+///
+/// Names are normalized and extras sorted.  Entries that are not valid
+/// requirements are kept as they are and sorted by their lowercased text.
+fn normalize_dependency_array(deps: &mut Array) {
+    for idx in 0..deps.len() {
+        let Some(dep) = deps.get(idx).and_then(|x| x.as_str()) else {
+            continue;
+        };
+        let Ok(mut req) = Requirement::from_str(dep) else {
+            continue;
+        };
+        req.name = normalize_package_name(&req.name);
+        if let Some(ref mut extras) = req.extras {
+            extras.sort();
+            extras.dedup();
+        }
+        let normalized = format_requirement(&req).to_string();
+        if normalized != dep {
+            deps.replace(idx, normalized);
+        }
+    }
+    toml::sort_array_by_key(deps, |dep| {
+        let dep = dep.as_str().unwrap_or_default();
+        match Requirement::from_str(dep) {
+            Ok(req) => req.name,
+            Err(_) => dep.to_ascii_lowercase(),
+        }
+    });
+}
+
 fn remove_dependency(deps: &mut Array, req: &Requirement) -> Option<Requirement> {
     let mut to_remove = None;
     for (idx, dep) in deps.iter().enumerate() {
@@ -1129,12 +1994,9 @@ fn remove_dependency(deps: &mut Array, req: &Requirement) -> Option<Requirement>
     }
 
     if let Some(idx) = to_remove {
-        let rv = deps
-            .remove(idx)
+        toml::remove_array_item(deps, idx)
             .as_str()
-            .and_then(|x| Requirement::from_str(x).ok());
-        toml::reformat_array_multiline(deps);
-        rv
+            .and_then(|x| Requirement::from_str(x).ok())
     } else {
         None
     }
@@ -1153,6 +2015,7 @@ pub fn write_venv_marker(venv_path: &Path, py_ver: &PythonVersion) -> Result<(),
         serde_json::to_string_pretty(&VenvMarker {
             python: py_ver.clone(),
             venv_path: Some(venv_path.into()),
+            tool: None,
         })?,
     )
     .path_context(&marker, "failed writing venv marker file")?;
@@ -1205,23 +2068,70 @@ fn resolve_target_python_version(
 ) -> Option<PythonVersionRequest> {
     resolve_lower_bound_python_version(doc)
         .or_else(|| get_current_venv_python_version(venv_path).map(Into::into))
+        .or_else(|| python_pin(doc))
         .or_else(|| get_python_version_request_from_pyenv_pin(root).map(Into::into))
         .or_else(|| Config::current().default_toolchain().ok())
 }
 
+/// Where the requested Python version of a project comes from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PythonVersionSource {
+    /// `tool.rye.python` in the `pyproject.toml`.
+    PyprojectPin,
+    /// A `.python-version` file.
+    PinFile(PathBuf),
+    /// The lower bound of `requires-python` in the `pyproject.toml`.
+    RequiresPython,
+    /// The default toolchain from the config.
+    ConfigDefault,
+}
+
+impl fmt::Display for PythonVersionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PythonVersionSource::PyprojectPin => write!(f, "tool.rye.python"),
+            PythonVersionSource::PinFile(path) => write!(f, "{}", path.display()),
+            PythonVersionSource::RequiresPython => write!(f, "requires-python"),
+            PythonVersionSource::ConfigDefault => write!(f, "default.toolchain in config"),
+        }
+    }
+}
+
+/// Resolves the requested Python version of a project.
+///
+/// The precedence is `tool.rye.python`, the closest `.python-version` file,
+/// the lower bound of `requires-python` and finally the default toolchain.
+fn resolve_python_version_request(
+    doc: &DocumentMut,
+    root: &Path,
+) -> Option<(PythonVersionRequest, PythonVersionSource)> {
+    python_pin(doc)
+        .map(|ver| (ver, PythonVersionSource::PyprojectPin))
+        .or_else(|| {
+            find_pyenv_pin(root).map(|(path, ver)| (ver, PythonVersionSource::PinFile(path)))
+        })
+        .or_else(|| {
+            resolve_lower_bound_python_version(doc)
+                .map(|ver| (ver, PythonVersionSource::RequiresPython))
+        })
+        .or_else(|| {
+            Config::current()
+                .default_toolchain()
+                .ok()
+                .map(|ver| (ver, PythonVersionSource::ConfigDefault))
+        })
+}
+
 fn resolve_intended_venv_python_version(
     doc: &DocumentMut,
     root: &Path,
 ) -> Result<PythonVersion, Error> {
-    let requested_version = get_python_version_request_from_pyenv_pin(root)
-        .or_else(|| resolve_lower_bound_python_version(doc))
-        .or_else(|| Config::current().default_toolchain().ok())
-        .ok_or_else(|| {
-            anyhow!(
-                "could not determine a target Python version.  Define requires-python in \
-                 pyproject.toml or use a .python-version file"
-            )
-        })?;
+    let (requested_version, _) = resolve_python_version_request(doc, root).ok_or_else(|| {
+        anyhow!(
+            "could not determine a target Python version.  Define requires-python or \
+                 tool.rye.python in pyproject.toml or use a .python-version file"
+        )
+    })?;
 
     if let Ok(ver) = PythonVersion::try_from(requested_version.clone()) {
         return Ok(ver);
@@ -1364,6 +2274,284 @@ fn lock_with_sources(doc: &DocumentMut) -> bool {
         .unwrap_or(false)
 }
 
+fn compile_bytecode(doc: &DocumentMut) -> bool {
+    doc.get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("sync"))
+        .and_then(|x| x.get("compile-bytecode"))
+        .and_then(|x| x.as_bool())
+        .unwrap_or(false)
+}
+
+fn check_entry_points(doc: &DocumentMut) -> bool {
+    doc.get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("sync"))
+        .and_then(|x| x.get("check-entry-points"))
+        .and_then(|x| x.as_bool())
+        .unwrap_or(false)
+}
+
+fn build_cache(doc: &DocumentMut) -> bool {
+    doc.get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("build"))
+        .and_then(|x| x.get("cache"))
+        .and_then(|x| x.as_bool())
+        .unwrap_or(false)
+}
+
+fn require_script_help(doc: &DocumentMut) -> bool {
+    doc.get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("require-script-help"))
+        .and_then(|x| x.as_bool())
+        .unwrap_or(false)
+}
+
+fn member_constraints(doc: &DocumentMut) -> bool {
+    doc.get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("lock"))
+        .and_then(|x| x.get("member-constraints"))
+        .and_then(|x| x.as_bool())
+        .unwrap_or(false)
+}
+
+fn sort_dependencies(doc: &DocumentMut) -> bool {
+    doc.get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("sort-dependencies"))
+        .and_then(|x| x.as_bool())
+        .unwrap_or(false)
+}
+
+fn lock_header(doc: &DocumentMut) -> Result<LockHeader, Error> {
+    let value = match doc
+        .get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("lock-header"))
+    {
+        Some(value) => value,
+        None => return Ok(LockHeader::default()),
+    };
+    match value
+        .as_str()
+        .and_then(|x| <LockHeader as ValueEnum>::from_str(x, false).ok())
+    {
+        Some(header) => Ok(header),
+        None => bail!(
+            "invalid value for tool.rye.lock-header (expected \"full\", \"minimal\" or \"none\")"
+        ),
+    }
+}
+
+fn editable_mode(doc: &DocumentMut) -> Result<Option<EditableMode>, Error> {
+    let value = match doc
+        .get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("editable-mode"))
+    {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    match value
+        .as_str()
+        .and_then(|x| <EditableMode as ValueEnum>::from_str(x, false).ok())
+    {
+        Some(mode) => Ok(Some(mode)),
+        None => {
+            bail!("invalid value for tool.rye.editable-mode (expected \"strict\" or \"lenient\")")
+        }
+    }
+}
+
+/// Reads the Python version pinned in `tool.rye.python`.
+///
+/// An invalid pin is ignored with a warning.
+fn python_pin(doc: &DocumentMut) -> Option<PythonVersionRequest> {
+    let value = doc
+        .get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("python"))?;
+    match value.as_str().map(|x| x.parse::<PythonVersionRequest>()) {
+        Some(Ok(ver)) => Some(ver),
+        _ => {
+            warn!(
+                category = Config,
+                "ignoring invalid Python version in tool.rye.python: {}",
+                value.to_string().trim()
+            );
+            None
+        }
+    }
+}
+
+fn dev_presets(doc: &DocumentMut) -> Result<Vec<String>, Error> {
+    let mut rv = Vec::new();
+    for value in doc
+        .get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("dev-presets"))
+        .and_then(|x| x.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let value = value
+            .as_str()
+            .ok_or_else(|| anyhow!("tool.rye.dev-presets must be an array of strings"))?;
+        rv.push(value.to_string());
+    }
+    Ok(rv)
+}
+
+fn pins(doc: &DocumentMut) -> Result<Vec<Pin>, Error> {
+    let table = match doc
+        .get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("pins"))
+    {
+        Some(table) => table
+            .as_table_like()
+            .ok_or_else(|| anyhow!("tool.rye.pins must be a table"))?,
+        None => return Ok(Vec::new()),
+    };
+    let mut rv = Vec::new();
+    for (name, value) in table.iter() {
+        let (version, expires, reason) = if let Some(version) = value.as_str() {
+            (version, None, None)
+        } else if let Some(pin) = value.as_table_like() {
+            let version = pin
+                .get("version")
+                .and_then(|x| x.as_str())
+                .ok_or_else(|| anyhow!("tool.rye.pins.{} has no version", name))?;
+            // dates can be written as TOML dates or as strings
+            let expires = match pin.get("expires") {
+                None => None,
+                Some(value) => Some(
+                    value
+                        .as_str()
+                        .map(|x| x.to_string())
+                        .or_else(|| value.as_datetime().map(|x| x.to_string()))
+                        .filter(|x| PIN_DATE_RE.is_match(x))
+                        .ok_or_else(|| {
+                            anyhow!("tool.rye.pins.{}.expires must be a date (YYYY-MM-DD)", name)
+                        })?,
+                ),
+            };
+            let reason = pin
+                .get("reason")
+                .and_then(|x| x.as_str())
+                .map(|x| x.to_string());
+            (version, expires, reason)
+        } else {
+            bail!("tool.rye.pins.{} must be a version or a table", name);
+        };
+        VersionSpecifiers::from_str(version)
+            .map_err(|err| anyhow!("invalid version in tool.rye.pins.{}: {}", name, err))?;
+        rv.push(Pin {
+            name: name.to_string(),
+            version: version.to_string(),
+            expires,
+            reason,
+        });
+    }
+    Ok(rv)
+}
+
+fn binary_policy(doc: &DocumentMut) -> Result<BinaryPolicy, Error> {
+    let packages = |key: &str| -> Result<Vec<String>, Error> {
+        let mut rv = Vec::new();
+        for value in doc
+            .get("tool")
+            .and_then(|x| x.get("rye"))
+            .and_then(|x| x.get(key))
+            .and_then(|x| x.as_array())
+            .into_iter()
+            .flatten()
+        {
+            let value = value
+                .as_str()
+                .ok_or_else(|| anyhow!("tool.rye.{} must be an array of strings", key))?;
+            rv.push(value.to_string());
+        }
+        Ok(rv)
+    };
+    Ok(BinaryPolicy {
+        no_binary: packages("no-binary")?,
+        only_binary: packages("only-binary")?,
+    })
+}
+
+fn gate_checks(doc: &DocumentMut, gate: Gate) -> Result<Vec<GateCheck>, Error> {
+    let mut rv = Vec::new();
+    for value in doc
+        .get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("gates"))
+        .and_then(|x| x.get(gate.key()))
+        .and_then(|x| x.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let check = value
+            .as_str()
+            .and_then(|x| <GateCheck as ValueEnum>::from_str(x, false).ok())
+            .ok_or_else(|| {
+                anyhow!(
+                    "invalid value in tool.rye.gates.{} (expected \"fmt\", \"lint\" or \"test\")",
+                    gate.key()
+                )
+            })?;
+        if !rv.contains(&check) {
+            rv.push(check);
+        }
+    }
+    Ok(rv)
+}
+
+fn warning_policy(doc: &DocumentMut) -> Result<WarningPolicy, Error> {
+    let mut rv = WarningPolicy::default();
+    let table = match doc
+        .get("tool")
+        .and_then(|x| x.get("rye"))
+        .and_then(|x| x.get("lint-warnings"))
+    {
+        Some(table) => table,
+        None => return Ok(rv),
+    };
+    for value in table
+        .get("deny")
+        .and_then(|x| x.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let value = value
+            .as_str()
+            .ok_or_else(|| anyhow!("tool.rye.lint-warnings.deny must contain strings"))?;
+        rv.deny.push(
+            value
+                .parse::<WarningFilter>()
+                .map_err(|err| anyhow!("invalid value in tool.rye.lint-warnings.deny: {}", err))?,
+        );
+    }
+    for value in table
+        .get("allow")
+        .and_then(|x| x.as_array())
+        .into_iter()
+        .flatten()
+    {
+        let value = value
+            .as_str()
+            .ok_or_else(|| anyhow!("tool.rye.lint-warnings.allow must contain strings"))?;
+        rv.allow.push(
+            <WarningCategory as ValueEnum>::from_str(value, false)
+                .map_err(|err| anyhow!("invalid value in tool.rye.lint-warnings.allow: {}", err))?,
+        );
+    }
+    Ok(rv)
+}
+
 fn get_project_metadata(path: &Path) -> Result<Metadata, Error> {
     let self_venv = ensure_self_venv(CommandOutput::Normal)?;
     let mut metadata = Command::new(self_venv.join(VENV_BIN).join("python"));
@@ -1382,6 +2570,11 @@ pub struct ExpandedSources {
     pub index_urls: Vec<(Url, bool)>,
     pub find_links: Vec<Url>,
     pub trusted_hosts: HashSet<String>,
+    pub client_certs: Vec<ClientCert>,
+    /// Find-links urls that were given relative to the root, with their relative path.
+    pub relative_find_links: HashMap<Url, String>,
+    #[serde(skip)]
+    uv_client_cert: Option<Arc<UvClientCert>>,
 }
 
 impl ExpandedSources {
@@ -1390,32 +2583,94 @@ impl ExpandedSources {
             index_urls: Vec::new(),
             find_links: Vec::new(),
             trusted_hosts: HashSet::new(),
+            client_certs: Vec::new(),
+            relative_find_links: HashMap::new(),
+            uv_client_cert: None,
         }
     }
 
     /// Takes some sources and expands them.
+    ///
+    /// Relative find-links are not supported as there is nothing to resolve
+    /// them against.
     pub fn from_sources(sources: &[SourceRef]) -> Result<ExpandedSources, Error> {
+        ExpandedSources::expand(sources, None)
+    }
+
+    /// Takes some sources and expands them, resolving relative find-links
+    /// against the given root (usually the workspace root).
+    pub fn from_sources_relative_to(
+        sources: &[SourceRef],
+        root: &Path,
+    ) -> Result<ExpandedSources, Error> {
+        ExpandedSources::expand(sources, Some(root))
+    }
+
+    fn expand(sources: &[SourceRef], root: Option<&Path>) -> Result<ExpandedSources, Error> {
         let mut index_urls = Vec::new();
         let mut find_links = Vec::new();
         let mut trusted_hosts = HashSet::new();
+        let mut client_certs = Vec::<ClientCert>::new();
+        let mut relative_find_links = HashMap::new();
 
         for source in sources {
-            let url = source.expand_url()?;
+            let url = match source.relative_find_links_path()? {
+                Some(path) => {
+                    let Some(root) = root else {
+                        bail!(
+                            "find-links source '{}' uses a relative path which is only supported \
+                             for projects",
+                            source.name
+                        );
+                    };
+                    let url = Url::from_directory_path(
+                        path.split('/')
+                            .filter(|x| !x.is_empty() && *x != ".")
+                            .fold(root.to_path_buf(), |acc, x| acc.join(x)),
+                    )
+                    .map_err(|_| anyhow!("invalid path for source '{}': {}", source.name, path))?;
+                    relative_find_links.insert(url.clone(), path);
+                    url
+                }
+                None => source.expand_url()?,
+            };
             if !source.verify_ssl {
                 if let Some(host) = url.host_str() {
                     trusted_hosts.insert(host.to_string());
                 }
             }
+            if let Some(client_cert) = source.expand_client_cert()? {
+                if !client_certs.contains(&client_cert) {
+                    client_certs.push(client_cert);
+                }
+            }
             match source.ty {
                 SourceRefType::Index => index_urls.push((url, source.name == "default")),
                 SourceRefType::FindLinks => find_links.push(url),
             }
         }
 
+        // uv does not support client certificates per index
+        let uv_client_cert = match client_certs.as_slice() {
+            [] => None,
+            [first, rest @ ..] => {
+                if rest
+                    .iter()
+                    .any(|x| x.cert != first.cert || x.key != first.key)
+                {
+                    bail!("all sources must use the same client certificate");
+                }
+                Some(Arc::new(UvClientCert::new(first)?))
+            }
+        };
+
         Ok(ExpandedSources {
             index_urls,
             find_links,
             trusted_hosts,
+            client_certs,
+            relative_find_links,
+            uv_client_cert,
         })
     }
 
@@ -1445,6 +2700,9 @@ impl ExpandedSources {
             cmd.arg("--trusted-host");
             cmd.arg(host);
         }
+        if let Some(ref client_cert) = self.uv_client_cert {
+            cmd.env("SSL_CLIENT_CERT", client_cert.path());
+        }
     }
 
     /// Write the sources to a lockfile.
@@ -1464,13 +2722,38 @@ impl ExpandedSources {
             writeln!(out, "--extra-index-url {url}")?;
         }
         for link in &self.find_links {
-            writeln!(out, "--find-links {link}")?;
+            // relative paths keep the lockfile usable on other machines
+            match self.relative_find_links.get(link) {
+                Some(path) => writeln!(out, "--find-links {path}")?,
+                None => writeln!(out, "--find-links {link}")?,
+            }
         }
         for host in &self.trusted_hosts {
             writeln!(out, "--trusted-host {host}")?;
         }
         Ok(())
     }
+
+    /// Prints the sources in verbose mode.
+    ///
+    /// Credentials in the URLs are masked.
+    pub fn echo_verbose(&self, output: CommandOutput) {
+        for (url, default) in &self.index_urls {
+            echo!(
+                if verbose output,
+                "Using {} {}",
+                if *default { "index" } else { "extra index" },
+                mask_url_credentials(url.as_str())
+            );
+        }
+        for link in &self.find_links {
+            echo!(
+                if verbose output,
+                "Using find-links {}",
+                mask_url_credentials(link.as_str())
+            );
+        }
+    }
 }
 
 #[derive(ValueEnum, Copy, Clone, Serialize, Debug, PartialEq)]
@@ -0,0 +1,187 @@
+use std::path::{Component, Path};
+
+use anyhow::{bail, Error};
+
+use crate::platform::get_app_dir;
+
+/// Folders of the operating system that never hold projects.
+#[cfg(unix)]
+const SYSTEM_DIRS: &[&str] = &[
+    "/bin",
+    "/boot",
+    "/dev",
+    "/etc",
+    "/lib",
+    "/lib64",
+    "/proc",
+    "/sbin",
+    "/sys",
+    "/usr/bin",
+    "/usr/lib",
+    "/usr/lib64",
+    "/usr/local/bin",
+    "/usr/local/lib",
+    "/usr/sbin",
+    "/usr/share",
+    "/System",
+    "/Library",
+];
+
+/// Environment variables pointing to folders of the operating system.
+#[cfg(windows)]
+const SYSTEM_DIR_VARS: &[&str] = &["SystemRoot", "ProgramFiles", "ProgramFiles(x86)"];
+
+/// Refuses to run a command that modifies the project where it would do harm.
+///
+/// Running as root is refused if that leaves root owned files behind for a
+/// regular user, which is the case when rye is invoked through `sudo` or the
+/// project or the rye home belong to another user.  Inside of containers only
+/// `sudo` is refused.  Projects in system folders or in the `site-packages` of
+/// a Python installation are refused as well.  A rye home that the current
+/// user cannot write to is only warned about.
+///
+/// `project_root` is `None` for commands that only modify the rye home.
+pub fn check_mutating_command(
+    project_root: Option<&Path>,
+    allow_root: bool,
+    allow_system_dir: bool,
+) -> Result<(), Error> {
+    if !allow_root {
+        if let Some(reason) = root_reason(project_root) {
+            bail!(
+                "refusing to run as root: {}.  Files created now would be owned by root. \
+                 Run rye as your regular user (without sudo) or pass --allow-root if this is intended",
+                reason
+            );
+        }
+    }
+
+    if let (false, Some(project_root)) = (allow_system_dir, project_root) {
+        if let Some(reason) = system_dir_reason(project_root) {
+            bail!(
+                "refusing to modify a project in {}: {}.  Create the project in a folder \
+                 of your own (for instance in your home folder) or pass --allow-system-dir \
+                 if this is intended",
+                project_root.display(),
+                reason
+            );
+        }
+    }
+
+    let app_dir = get_app_dir();
+    if app_dir.is_dir() && !is_writable(app_dir) {
+        warn!(
+            category = Environment,
+            "the rye home ({}) is not writable by the current user.  If it was created with \
+             sudo, fix the ownership with `sudo chown -R $(id -un) {}` or point RYE_HOME to a \
+             writable folder",
+            app_dir.display(),
+            app_dir.display()
+        );
+    }
+
+    Ok(())
+}
+
+/// Checks if the current user may write to a folder.
+#[cfg(unix)]
+fn is_writable(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    match std::ffi::CString::new(path.as_os_str().as_bytes()) {
+        Ok(path) => unsafe { libc::access(path.as_ptr(), libc::W_OK) == 0 },
+        Err(_) => true,
+    }
+}
+
+#[cfg(windows)]
+fn is_writable(path: &Path) -> bool {
+    path.metadata()
+        .map_or(true, |metadata| !metadata.permissions().readonly())
+}
+
+/// Is rye running in a container?
+///
+/// Containers usually run as root, often on folders bind mounted from the
+/// host, without a regular user that root owned files could get in the way of.
+#[cfg(unix)]
+fn is_container() -> bool {
+    Path::new("/.dockerenv").exists()
+        || Path::new("/run/.containerenv").exists()
+        || std::env::var_os("container").is_some()
+}
+
+/// Returns why running as root would leave root owned files behind.
+#[cfg(unix)]
+fn root_reason(project_root: Option<&Path>) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+
+    if unsafe { libc::geteuid() } != 0 {
+        return None;
+    }
+    if let Ok(user) = std::env::var("SUDO_USER") {
+        return Some(format!("rye was invoked through sudo by {}", user));
+    }
+    if is_container() {
+        return None;
+    }
+    let project = project_root.map(|path| ("the project", path));
+    for (what, path) in project.into_iter().chain([("the rye home", get_app_dir())]) {
+        if let Ok(metadata) = path.metadata() {
+            if metadata.uid() != 0 {
+                return Some(format!(
+                    "{} ({}) belongs to another user",
+                    what,
+                    path.display()
+                ));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(windows)]
+fn root_reason(_project_root: Option<&Path>) -> Option<String> {
+    None
+}
+
+/// Returns why a folder is not a suitable place for a project.
+fn system_dir_reason(path: &Path) -> Option<&'static str> {
+    if path.parent().is_none() {
+        return Some("it is the root of the file system");
+    }
+    if path.components().any(|x| {
+        matches!(x, Component::Normal(name) if name == "site-packages" || name == "dist-packages")
+    }) {
+        return Some("it is inside the packages of a Python installation");
+    }
+    if is_system_dir(path) {
+        return Some("it is a folder of the operating system");
+    }
+    None
+}
+
+#[cfg(unix)]
+fn is_system_dir(path: &Path) -> bool {
+    SYSTEM_DIRS.iter().any(|x| path.starts_with(x))
+}
+
+#[cfg(windows)]
+fn is_system_dir(path: &Path) -> bool {
+    SYSTEM_DIR_VARS
+        .iter()
+        .filter_map(std::env::var_os)
+        .any(|x| path.starts_with(x))
+}
+
+#[cfg(unix)]
+#[test]
+fn test_system_dir_reason() {
+    assert!(system_dir_reason(Path::new("/")).is_some());
+    assert!(system_dir_reason(Path::new("/usr/lib/python3/dist-packages/foo")).is_some());
+    assert!(system_dir_reason(Path::new("/home/me/.venv/lib/python3.12/site-packages")).is_some());
+    assert!(system_dir_reason(Path::new("/etc/myapp")).is_some());
+    assert!(system_dir_reason(Path::new("/home/me/projects/foo")).is_none());
+    assert!(system_dir_reason(Path::new("/usr/src/app")).is_none());
+    assert!(system_dir_reason(Path::new("/Users/me/Library/foo")).is_none());
+}
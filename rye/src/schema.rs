@@ -0,0 +1,558 @@
+use clap::ValueEnum;
+use serde_json::{json, Map, Value};
+
+use crate::gates::GateCheck;
+use crate::lock::{EditableMode, LockHeader};
+use crate::pyproject::{SourceRefType, SCRIPT_PLATFORMS};
+use crate::tui::WarningCategory;
+
+/// The kind of value a setting below `tool.rye` holds.
+#[derive(Debug, Clone, Copy)]
+pub enum SettingKind {
+    Boolean,
+    String,
+    /// One of a fixed set of strings.
+    Choice(fn() -> Vec<String>),
+    /// An array of strings.
+    Strings,
+    /// An array of strings from a fixed set.
+    Choices(fn() -> Vec<String>),
+    Scripts,
+    Sources,
+    Workspace,
+    LintWarnings,
+    Pins,
+}
+
+impl SettingKind {
+    /// The name of the TOML type as reported by `toml_edit`.
+    pub fn toml_type(&self) -> &'static str {
+        match self {
+            SettingKind::Boolean => "boolean",
+            SettingKind::String | SettingKind::Choice(_) => "string",
+            SettingKind::Strings | SettingKind::Choices(_) | SettingKind::Sources => "array",
+            SettingKind::Scripts
+            | SettingKind::Workspace
+            | SettingKind::LintWarnings
+            | SettingKind::Pins => "table",
+        }
+    }
+
+    fn json_schema(&self) -> Value {
+        match self {
+            SettingKind::Boolean => json!({"type": "boolean"}),
+            SettingKind::String => json!({"type": "string"}),
+            SettingKind::Choice(values) => json!({"type": "string", "enum": values()}),
+            SettingKind::Strings => string_array(),
+            SettingKind::Choices(values) => {
+                json!({"type": "array", "items": {"type": "string", "enum": values()}})
+            }
+            SettingKind::Scripts => json!({
+                "type": "object",
+                "additionalProperties": script_schema(),
+            }),
+            SettingKind::Sources => json!({
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "url": {"type": "string"},
+                        "type": {"type": "string", "enum": source_types()},
+                        "username": {"type": "string"},
+                        "password": {"type": "string"},
+                        "verify-ssl": {"type": "boolean"},
+                        "client-cert": {"type": "string"},
+                        "client-key": {"type": "string"},
+                    },
+                    "required": ["name", "url"],
+                    "additionalProperties": false,
+                },
+            }),
+            SettingKind::Workspace => json!({
+                "type": "object",
+                "properties": {"members": string_array()},
+                "additionalProperties": false,
+            }),
+            SettingKind::Pins => json!({
+                "type": "object",
+                "additionalProperties": {
+                    "oneOf": [
+                        {"type": "string"},
+                        {
+                            "type": "object",
+                            "properties": {
+                                "version": {"type": "string"},
+                                "expires": {"type": "string", "format": "date"},
+                                "reason": {"type": "string"},
+                            },
+                            "required": ["version"],
+                            "additionalProperties": false,
+                        },
+                    ],
+                },
+            }),
+            SettingKind::LintWarnings => {
+                let mut deny = warning_categories();
+                deny.push("warnings".into());
+                json!({
+                    "type": "object",
+                    "properties": {
+                        "allow": {
+                            "type": "array",
+                            "items": {"type": "string", "enum": warning_categories()},
+                        },
+                        "deny": {
+                            "type": "array",
+                            "items": {"type": "string", "enum": deny},
+                        },
+                    },
+                    "additionalProperties": false,
+                })
+            }
+        }
+    }
+}
+
+/// Where a setting below `tool.rye` is read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingScope {
+    /// Read from the project itself.
+    Project,
+    /// Read from the workspace root only, the members of a workspace cannot
+    /// override it.
+    Workspace,
+    /// Read from the project, workspace members fall back to the workspace root.
+    Inherited,
+}
+
+/// A setting below `tool.rye`.
+#[derive(Debug)]
+pub struct Setting {
+    /// The key below `tool.rye` in dotted notation.
+    pub key: &'static str,
+    pub kind: SettingKind,
+    pub scope: SettingScope,
+    /// The value used when the setting is not configured, as TOML.
+    pub default: Option<&'static str>,
+    pub description: &'static str,
+}
+
+/// All settings that rye understands below `tool.rye`.
+pub const SETTINGS: &[Setting] = &[
+    Setting {
+        key: "managed",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "The project is managed by rye.",
+    },
+    Setting {
+        key: "virtual",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Project,
+        default: Some("false"),
+        description: "The project is not installed itself, only its dependencies.",
+    },
+    Setting {
+        key: "python",
+        kind: SettingKind::String,
+        scope: SettingScope::Inherited,
+        default: None,
+        description: "The Python version to use, takes precedence over `.python-version`.",
+    },
+    Setting {
+        key: "dev-dependencies",
+        kind: SettingKind::Strings,
+        scope: SettingScope::Project,
+        default: Some("[]"),
+        description: "Dependencies that are only installed for development.",
+    },
+    Setting {
+        key: "dev-presets",
+        kind: SettingKind::Strings,
+        scope: SettingScope::Workspace,
+        default: Some("[]"),
+        description:
+            "Shared presets (URLs or paths) whose dev-dependencies and scripts are merged in.",
+    },
+    Setting {
+        key: "excluded-dependencies",
+        kind: SettingKind::Strings,
+        scope: SettingScope::Project,
+        default: Some("[]"),
+        description: "Dependencies that are never installed.",
+    },
+    Setting {
+        key: "sort-dependencies",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "Keep the dependency arrays sorted when adding or removing dependencies.",
+    },
+    Setting {
+        key: "universal",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "Generate lockfiles that work on all platforms.",
+    },
+    Setting {
+        key: "generate-hashes",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "Record hashes in the lockfiles.",
+    },
+    Setting {
+        key: "lock-with-sources",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "Record the sources in the lockfiles.",
+    },
+    Setting {
+        key: "lock-header",
+        kind: SettingKind::Choice(lock_headers),
+        scope: SettingScope::Workspace,
+        default: Some("\"minimal\""),
+        description: "What to write into the header of the lockfiles.",
+    },
+    Setting {
+        key: "lock.member-constraints",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "Write a constraints file per workspace member when locking.",
+    },
+    Setting {
+        key: "pins",
+        kind: SettingKind::Pins,
+        scope: SettingScope::Workspace,
+        default: Some("{}"),
+        description: "Packages that are temporarily held back, optionally until a date.",
+    },
+    Setting {
+        key: "no-binary",
+        kind: SettingKind::Strings,
+        scope: SettingScope::Workspace,
+        default: Some("[]"),
+        description: "Packages that are always built from source (`:all:` for all packages).",
+    },
+    Setting {
+        key: "only-binary",
+        kind: SettingKind::Strings,
+        scope: SettingScope::Workspace,
+        default: Some("[]"),
+        description: "Packages that are never built from source (`:all:` for all packages).",
+    },
+    Setting {
+        key: "editable-mode",
+        kind: SettingKind::Choice(editable_modes),
+        scope: SettingScope::Workspace,
+        default: None,
+        description: "How setuptools installs the projects in editable mode.",
+    },
+    Setting {
+        key: "sync.compile-bytecode",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "Compile Python files to bytecode after installation.",
+    },
+    Setting {
+        key: "sync.check-entry-points",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "Warn about entry points that cannot be imported after installation.",
+    },
+    Setting {
+        key: "build.cache",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "Reuse built artifacts of unchanged projects from the build cache.",
+    },
+    Setting {
+        key: "version.from-vcs",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Project,
+        default: Some("false"),
+        description: "Compute the version from the tags of the version control system.",
+    },
+    Setting {
+        key: "version.file",
+        kind: SettingKind::String,
+        scope: SettingScope::Project,
+        default: None,
+        description: "The file `rye version sync-from-vcs` writes the version to.",
+    },
+    Setting {
+        key: "scripts",
+        kind: SettingKind::Scripts,
+        scope: SettingScope::Project,
+        default: Some("{}"),
+        description: "Scripts that can be invoked with `rye run`.",
+    },
+    Setting {
+        key: "require-script-help",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Workspace,
+        default: Some("false"),
+        description: "All scripts must have a help text (checked by `rye check-scripts`).",
+    },
+    Setting {
+        key: "load-dotenv",
+        kind: SettingKind::Boolean,
+        scope: SettingScope::Project,
+        default: None,
+        description:
+            "Load `.env` and `.env.local` from the project root in `rye run` and `rye test`.",
+    },
+    Setting {
+        key: "sources",
+        kind: SettingKind::Sources,
+        scope: SettingScope::Workspace,
+        default: Some("[]"),
+        description: "Package indexes and find-links sources.",
+    },
+    Setting {
+        key: "workspace",
+        kind: SettingKind::Workspace,
+        scope: SettingScope::Project,
+        default: None,
+        description: "Turns the project into a workspace.",
+    },
+    Setting {
+        key: "gates.build",
+        kind: SettingKind::Choices(gate_checks),
+        scope: SettingScope::Workspace,
+        default: Some("[]"),
+        description: "Checks that must pass before `rye build` (`fmt`, `lint` or `test`).",
+    },
+    Setting {
+        key: "gates.publish",
+        kind: SettingKind::Choices(gate_checks),
+        scope: SettingScope::Workspace,
+        default: Some("[]"),
+        description: "Checks that must pass before `rye publish` (`fmt`, `lint` or `test`).",
+    },
+    Setting {
+        key: "lint-warnings",
+        kind: SettingKind::LintWarnings,
+        scope: SettingScope::Workspace,
+        default: Some("{}"),
+        description: "Warning categories that are silenced or turned into errors.",
+    },
+];
+
+/// Looks up a setting by its full key (`tool.rye.…`).
+pub fn find_setting(key: &str) -> Option<&'static Setting> {
+    let key = key.strip_prefix("tool.rye.")?;
+    SETTINGS.iter().find(|x| x.key == key)
+}
+
+/// Returns the JSON schema of the `tool.rye` table.
+pub fn tool_rye_schema() -> Value {
+    let mut properties = Map::new();
+    for setting in SETTINGS {
+        let mut schema = setting.kind.json_schema();
+        schema["description"] = setting.description.into();
+        insert_setting(&mut properties, setting.key, schema);
+    }
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "tool.rye",
+        "description": "Rye settings in pyproject.toml",
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false,
+    })
+}
+
+/// Inserts the schema of a setting, creating the tables of dotted keys.
+fn insert_setting(properties: &mut Map<String, Value>, key: &str, schema: Value) {
+    match key.split_once('.') {
+        None => {
+            properties.insert(key.to_string(), schema);
+        }
+        Some((table, rest)) => {
+            let table = properties.entry(table).or_insert_with(
+                || json!({"type": "object", "properties": {}, "additionalProperties": false}),
+            );
+            if let Some(properties) = table["properties"].as_object_mut() {
+                insert_setting(properties, rest, schema);
+            }
+        }
+    }
+}
+
+fn script_schema() -> Value {
+    let command = json!({"oneOf": [{"type": "string"}, string_array()]});
+    let platforms = SCRIPT_PLATFORMS
+        .iter()
+        .map(|x| (x.to_string(), command.clone()))
+        .collect::<Map<_, _>>();
+    json!({
+        "oneOf": [
+            command,
+            {
+                "type": "object",
+                "properties": {
+                    "cmd": {"oneOf": [
+                        command,
+                        {"type": "object", "properties": platforms, "additionalProperties": false},
+                    ]},
+                    "call": {"type": "string"},
+                    "chain": {"type": "array", "items": command},
+                    "env": {"type": "object", "additionalProperties": {"type": "string"}},
+                    "env-file": {"oneOf": [{"type": "string"}, string_array()]},
+                    "help": {"type": "string"},
+                },
+                "additionalProperties": false,
+            },
+        ]
+    })
+}
+
+fn string_array() -> Value {
+    json!({"type": "array", "items": {"type": "string"}})
+}
+
+fn lock_headers() -> Vec<String> {
+    value_names::<LockHeader>()
+}
+
+fn gate_checks() -> Vec<String> {
+    value_names::<GateCheck>()
+}
+
+fn editable_modes() -> Vec<String> {
+    value_names::<EditableMode>()
+}
+
+fn source_types() -> Vec<String> {
+    [SourceRefType::Index, SourceRefType::FindLinks]
+        .iter()
+        .map(|x| x.to_string())
+        .collect()
+}
+
+fn warning_categories() -> Vec<String> {
+    value_names::<WarningCategory>()
+}
+
+fn value_names<T: ValueEnum>() -> Vec<String> {
+    T::value_variants()
+        .iter()
+        .filter_map(|x| x.to_possible_value())
+        .map(|x| x.get_name().to_string())
+        .collect()
+}
+
+/// Scans the sources for chains of `get` calls and returns the keys they read
+/// in dotted notation.
+///
+/// A chain starts where `anchor` matches, its first capture group (if any) is
+/// the first key.  Every following `.and_then(|x| x.get("…"))` adds a key.
+#[cfg(test)]
+pub(crate) fn find_read_keys(path: &str, anchor: &str) -> std::collections::BTreeSet<String> {
+    let anchor = regex::Regex::new(anchor).unwrap();
+    let link =
+        regex::Regex::new(r#"^\)?\s*\.and_then\(\|x\|\s*\{?\s*x\.get\("([a-z0-9-]+)"\)"#).unwrap();
+    let mut rv = std::collections::BTreeSet::new();
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(path);
+    for entry in walkdir::WalkDir::new(root) {
+        let entry = entry.unwrap();
+        if entry.path().extension().map_or(true, |x| x != "rs") {
+            continue;
+        }
+        let source = std::fs::read_to_string(entry.path()).unwrap();
+        for m in anchor.captures_iter(&source) {
+            let mut keys = m.get(1).map(|x| x.as_str()).into_iter().collect::<Vec<_>>();
+            let mut rest = &source[m.get(0).unwrap().end()..];
+            while let Some(link) = link.captures(rest) {
+                keys.push(link.get(1).unwrap().as_str());
+                rest = &rest[link.get(0).unwrap().end()..];
+            }
+            if !keys.is_empty() {
+                rv.insert(keys.join("."));
+            }
+        }
+    }
+    rv
+}
+
+#[test]
+fn test_settings_cover_read_keys() {
+    let mut keys = find_read_keys("src", r#"get\("rye"\)"#);
+    keys.extend(find_read_keys("src", r#"rye\.get\("([a-z0-9-]+)"\)"#));
+    // keys that are not read through a literal chain
+    keys.extend([
+        "no-binary".into(),
+        "only-binary".into(),
+        "version.file".into(),
+    ]);
+    keys.extend(
+        [crate::gates::Gate::Build, crate::gates::Gate::Publish]
+            .iter()
+            .map(|gate| format!("gates.{}", gate.key())),
+    );
+    assert!(keys.contains("lock.member-constraints"));
+    for key in keys {
+        // tables like `tool.rye.lock` are fine as long as they hold settings
+        assert!(
+            SETTINGS
+                .iter()
+                .any(|x| x.key == key || x.key.starts_with(&format!("{}.", key))),
+            "tool.rye.{} is read but missing in SETTINGS",
+            key
+        );
+    }
+}
+
+#[test]
+fn test_tool_rye_schema() {
+    let schema = tool_rye_schema();
+    let properties = &schema["properties"];
+    assert_eq!(properties["managed"]["type"], "boolean");
+    assert_eq!(
+        properties["lock-header"]["enum"],
+        json!(["full", "minimal", "none"])
+    );
+    assert_eq!(
+        properties["lock"]["properties"]["member-constraints"]["type"],
+        "boolean"
+    );
+    assert_eq!(
+        properties["sync"]["properties"]["compile-bytecode"]["type"],
+        "boolean"
+    );
+    assert_eq!(
+        properties["editable-mode"]["enum"],
+        json!(["strict", "lenient"])
+    );
+    assert_eq!(
+        properties["gates"]["properties"]["publish"]["items"]["enum"],
+        json!(["fmt", "lint", "test"])
+    );
+    assert_eq!(
+        properties["sources"]["items"]["properties"]["type"]["enum"],
+        json!(["index", "find-links"])
+    );
+}
+
+#[test]
+fn test_find_setting() {
+    assert_eq!(
+        find_setting("tool.rye.generate-hashes").map(|x| x.kind.toml_type()),
+        Some("boolean")
+    );
+    assert_eq!(
+        find_setting("tool.rye.gates.build").map(|x| x.kind.toml_type()),
+        Some("array")
+    );
+    assert!(find_setting("tool.rye.unknown").is_none());
+    assert!(find_setting("generate-hashes").is_none());
+}
@@ -0,0 +1,134 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{bail, Context, Error};
+use sha2::{Digest, Sha256};
+use toml_edit::DocumentMut;
+use url::Url;
+
+use crate::bootstrap::download_url;
+use crate::lock::{parse_locked_versions, BinaryPolicy};
+use crate::pyproject::ExpandedSources;
+use crate::utils::{unpack_archive, CommandOutput, IoPathContext};
+use crate::yanked::{filename_version, find_project_page};
+
+/// The build requirements PEP 517 assumes for projects without `build-system.requires`.
+const LEGACY_BUILD_REQUIRES: &[&str] = &["setuptools>=40.8.0", "wheel"];
+
+/// Collects the `build-system.requires` of all source distributions pinned
+/// in the lockfiles.
+///
+/// A pinned package is built from source if its version has no wheel on the
+/// index or if the binary policy asks for it.  The source distributions of
+/// those packages are downloaded and their `pyproject.toml` is inspected.
+/// Requirements that a build backend only reports dynamically while building
+/// are not included.
+pub fn sdist_build_requires(
+    lockfiles: &[&Path],
+    sources: &ExpandedSources,
+    binary_policy: &BinaryPolicy,
+    output: CommandOutput,
+) -> Result<BTreeSet<String>, Error> {
+    let mut locked = BTreeSet::new();
+    for lockfile in lockfiles {
+        let contents =
+            fs::read_to_string(lockfile).path_context(lockfile, "failed to read lockfile")?;
+        for (name, version) in parse_locked_versions(&contents) {
+            // local and url dependencies are not served by an index
+            if let Some(version) = version {
+                locked.insert((name, version));
+            }
+        }
+    }
+
+    let mut rv = BTreeSet::new();
+    let mut handle = curl::easy::Easy::new();
+    for (name, version) in &locked {
+        let Some(page) = find_project_page(&mut handle, sources, name)? else {
+            bail!(
+                "{}=={} was not found on any index that serves the JSON simple API",
+                name,
+                version
+            );
+        };
+        let files = page
+            .files
+            .iter()
+            .filter(|x| filename_version(&x.filename).as_ref() == Some(version))
+            .collect::<Vec<_>>();
+        if files.iter().any(|x| x.filename.ends_with(".whl"))
+            && !binary_policy.requires_source_build(name)
+        {
+            continue;
+        }
+        let Some(sdist) = files
+            .iter()
+            .find(|x| x.filename.ends_with(".tar.gz") || x.filename.ends_with(".zip"))
+        else {
+            bail!("no source distribution of {}=={} found", name, version);
+        };
+
+        let url = match page.url {
+            Some(ref base) => base.join(&sdist.url)?,
+            None => Url::parse(&sdist.url)?,
+        };
+        echo!(if verbose output, "Inspecting {}", sdist.filename);
+        let contents = download_url(url.as_str(), output)
+            .with_context(|| format!("failed to download {}", sdist.filename))?;
+        if let Some(sha256) = sdist.hashes.get("sha256") {
+            if hex::encode(Sha256::digest(&contents)) != *sha256 {
+                bail!("hash mismatch for {}", sdist.filename);
+            }
+        }
+        let scratch = tempfile::tempdir()?;
+        unpack_archive(&contents, scratch.path(), 1)
+            .with_context(|| format!("failed to unpack {}", sdist.filename))?;
+        let pyproject_toml = scratch.path().join("pyproject.toml");
+        let requires = match fs::read_to_string(&pyproject_toml) {
+            Ok(contents) => parse_build_requires(&contents)
+                .with_context(|| format!("invalid pyproject.toml in {}", sdist.filename))?,
+            Err(_) => None,
+        };
+        match requires {
+            Some(requires) => rv.extend(requires),
+            None => rv.extend(LEGACY_BUILD_REQUIRES.iter().map(|x| x.to_string())),
+        }
+    }
+
+    Ok(rv)
+}
+
+/// Reads `build-system.requires` from the contents of a `pyproject.toml`.
+fn parse_build_requires(contents: &str) -> Result<Option<Vec<String>>, Error> {
+    let doc = contents.parse::<DocumentMut>()?;
+    Ok(doc
+        .get("build-system")
+        .and_then(|x| x.get("requires"))
+        .and_then(|x| x.as_array())
+        .map(|x| {
+            x.iter()
+                .filter_map(|x| x.as_str().map(|x| x.to_string()))
+                .collect()
+        }))
+}
+
+#[test]
+fn test_parse_build_requires() {
+    assert_eq!(
+        parse_build_requires(
+            r#"
+[build-system]
+requires = ["hatchling", "hatch-vcs>=0.3"]
+build-backend = "hatchling.build"
+"#
+        )
+        .unwrap(),
+        Some(vec!["hatchling".to_string(), "hatch-vcs>=0.3".to_string()])
+    );
+    assert_eq!(
+        parse_build_requires("[tool.black]\nline-length = 100\n").unwrap(),
+        None
+    );
+    assert!(parse_build_requires("[build-system").is_err());
+}
@@ -1,12 +1,18 @@
 use std::borrow::Cow;
+use std::collections::HashMap;
 use std::env::consts::{ARCH, OS};
-use std::fmt;
+use std::fs;
+use std::path::PathBuf;
 use std::str::FromStr;
+use std::{fmt, io};
 
-use anyhow::{anyhow, Error};
+use anyhow::{anyhow, bail, Error};
 use pep440_rs::Version;
 use serde::{de, Deserialize, Serialize};
 
+use crate::platform::get_app_dir;
+use crate::utils::IoPathContext;
+
 mod downloads {
     use super::PythonVersion;
     include!("generated/python_downloads.inc");
@@ -345,6 +351,102 @@ pub fn iter_downloadable<'s>(
         })
 }
 
+/// A checksum database for the Python downloads.
+///
+/// Rye ships with the checksums of all downloads it knows about.  An updated
+/// database can be fetched with `rye self refresh-downloads-index` which then
+/// takes precedence over the embedded checksums.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DownloadsIndex {
+    pub downloads: Vec<IndexedDownload>,
+}
+
+/// A single entry in the [`DownloadsIndex`].
+#[derive(Serialize, Deserialize, Debug)]
+pub struct IndexedDownload {
+    pub url: String,
+    pub sha256: String,
+}
+
+impl DownloadsIndex {
+    /// Parses and validates a downloads index.
+    pub fn parse(bytes: &[u8]) -> Result<DownloadsIndex, Error> {
+        let index: DownloadsIndex = serde_json::from_slice(bytes)?;
+        for download in &index.downloads {
+            if !download.url.starts_with("https://") {
+                bail!("refusing insecure download url {}", download.url);
+            }
+            if download.sha256.len() != 64
+                || !download.sha256.bytes().all(|x| x.is_ascii_hexdigit())
+            {
+                bail!(
+                    "invalid sha256 checksum '{}' for {}",
+                    download.sha256,
+                    download.url
+                );
+            }
+        }
+        Ok(index)
+    }
+}
+
+/// Returns the path where the refreshed downloads index is stored.
+pub fn get_downloads_index_path() -> PathBuf {
+    get_app_dir().join("downloads-index.json")
+}
+
+fn load_downloads_index() -> Result<Option<HashMap<String, String>>, Error> {
+    let path = get_downloads_index_path();
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err).path_context(&path, "failed to read downloads index"),
+    };
+    let index = DownloadsIndex::parse(&bytes).path_context(&path, "invalid downloads index")?;
+    Ok(Some(
+        index
+            .downloads
+            .into_iter()
+            .map(|x| (x.url, x.sha256))
+            .collect(),
+    ))
+}
+
+/// Returns the published sha256 checksum of a download.
+///
+/// A refreshed downloads index takes precedence over the checksums that
+/// are embedded in Rye.
+pub fn get_published_checksum(url: &str) -> Result<Option<Cow<'static, str>>, Error> {
+    if let Some(mut index) = load_downloads_index()? {
+        if let Some(sha256) = index.remove(url) {
+            return Ok(Some(Cow::Owned(sha256)));
+        }
+    }
+    Ok(downloads::PYTHON_VERSIONS
+        .iter()
+        .find(|(_, it_url, _)| *it_url == url)
+        .and_then(|(_, _, sha256)| sha256.map(Cow::Borrowed)))
+}
+
+#[test]
+fn test_parse_downloads_index() {
+    let index = DownloadsIndex::parse(
+        br#"{"downloads": [{"url": "https://example.com/python.tar.zst", "sha256": "d17a3fcc161345efa2ec0b4ab9c9ed6c139d29128f2e34bb636338a484aa7b72"}]}"#,
+    )
+    .unwrap();
+    assert_eq!(index.downloads.len(), 1);
+    assert_eq!(index.downloads[0].url, "https://example.com/python.tar.zst");
+
+    assert!(DownloadsIndex::parse(
+        br#"{"downloads": [{"url": "https://example.com/python.tar.zst", "sha256": "d17a3fcc"}]}"#
+    )
+    .is_err());
+    assert!(DownloadsIndex::parse(
+        br#"{"downloads": [{"url": "http://example.com/python.tar.zst", "sha256": "d17a3fcc161345efa2ec0b4ab9c9ed6c139d29128f2e34bb636338a484aa7b72"}]}"#
+    )
+    .is_err());
+}
+
 #[test]
 fn test_get_download_url() {
     let url = get_download_url(&"cpython-aarch64-macos@3.8.14".parse().unwrap());
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::platform::get_app_dir;
+use crate::utils::IoPathContext;
+
+/// A single recorded command invocation.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Invocation {
+    pub command: String,
+    pub timestamp: u64,
+    pub duration_ms: u64,
+    pub success: bool,
+}
+
+/// Aggregated statistics for a single command.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct CommandStats {
+    pub invocations: u64,
+    pub failures: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+}
+
+impl CommandStats {
+    /// Returns the average duration of an invocation.
+    pub fn average_ms(&self) -> u64 {
+        self.total_ms.checked_div(self.invocations).unwrap_or(0)
+    }
+
+    /// Returns the failure rate in percent.
+    pub fn failure_rate(&self) -> f64 {
+        if self.invocations == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.invocations as f64 * 100.0
+        }
+    }
+}
+
+/// Returns the path to the local statistics file.
+pub fn get_stats_path() -> PathBuf {
+    get_app_dir().join("stats.jsonl")
+}
+
+/// Records a command invocation if local statistics are enabled.
+///
+/// Failing to record is never an error, the statistics are purely
+/// informational and must not break the actual command.
+pub fn record_invocation(command: &str, duration: Duration, success: bool) {
+    if !Config::current().local_stats() {
+        return;
+    }
+    let invocation = Invocation {
+        command: command.to_string(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_secs()),
+        duration_ms: duration.as_millis() as u64,
+        success,
+    };
+    let Ok(line) = serde_json::to_string(&invocation) else {
+        return;
+    };
+    if let Ok(mut f) = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(get_stats_path())
+    {
+        writeln!(f, "{}", line).ok();
+    }
+}
+
+/// Loads all recorded invocations.
+///
+/// Lines that cannot be parsed (for instance from a partial write) are skipped.
+pub fn load_invocations() -> Result<Vec<Invocation>, Error> {
+    let path = get_stats_path();
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let contents = fs::read_to_string(&path).path_context(&path, "could not read statistics")?;
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+/// Removes all recorded statistics.
+pub fn clear_invocations() -> Result<(), Error> {
+    let path = get_stats_path();
+    if path.is_file() {
+        fs::remove_file(&path).path_context(&path, "could not remove statistics")?;
+    }
+    Ok(())
+}
+
+/// Aggregates invocations by command.
+pub fn summarize(invocations: &[Invocation]) -> BTreeMap<String, CommandStats> {
+    let mut rv = BTreeMap::<String, CommandStats>::new();
+    for invocation in invocations {
+        let stats = rv.entry(invocation.command.clone()).or_default();
+        stats.invocations += 1;
+        if !invocation.success {
+            stats.failures += 1;
+        }
+        stats.total_ms += invocation.duration_ms;
+        stats.max_ms = stats.max_ms.max(invocation.duration_ms);
+    }
+    rv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invocation(command: &str, duration_ms: u64, success: bool) -> Invocation {
+        Invocation {
+            command: command.into(),
+            timestamp: 0,
+            duration_ms,
+            success,
+        }
+    }
+
+    #[test]
+    fn test_summarize() {
+        let summary = summarize(&[
+            invocation("sync", 1000, true),
+            invocation("sync", 3000, false),
+            invocation("add", 200, true),
+        ]);
+        let sync = &summary["sync"];
+        assert_eq!(sync.invocations, 2);
+        assert_eq!(sync.failures, 1);
+        assert_eq!(sync.average_ms(), 2000);
+        assert_eq!(sync.max_ms, 3000);
+        assert_eq!(sync.failure_rate(), 50.0);
+        assert_eq!(summary["add"].failures, 0);
+    }
+}
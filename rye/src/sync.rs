@@ -1,21 +1,36 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::{panic, thread};
 
 use anyhow::{bail, Context, Error};
 use console::style;
+use pep440_rs::Version;
 use same_file::is_same_file;
 use serde::{Deserialize, Serialize};
 
 use crate::bootstrap::{ensure_self_venv, fetch, FetchOptions};
+use crate::config::Config;
+use crate::entry_points::warn_about_broken_entry_points;
+use crate::file_locks::{remove_old_venvs, remove_venv, retry_while_locked};
+use crate::installer::ToolSpec;
 use crate::lock::{
-    update_single_project_lockfile, update_workspace_lockfile, KeyringProvider, LockMode,
-    LockOptions,
+    check_expired_pins, check_lockfile_is_current, parse_locked_versions,
+    update_build_requirements_lockfile, update_single_project_lockfile, update_workspace_lockfile,
+    update_workspace_lockfile_with, EditableMode, KeyringProvider, LockMode, LockOptions,
 };
 use crate::platform::get_toolchain_python_bin;
-use crate::pyproject::{read_venv_marker, ExpandedSources, PyProject};
+use crate::presets::load_dev_presets;
+use crate::progress::{emit, ProgressEvent};
+use crate::pyproject::{
+    normalize_package_name, read_venv_marker, write_venv_marker, ExpandedSources, PyProject,
+};
 use crate::sources::py::PythonVersion;
-use crate::utils::{get_venv_python_bin, CommandOutput, IoPathContext};
-use crate::uv::{UvBuilder, UvSyncOptions};
+use crate::utils::worktree::{clone_venv, find_linked_worktree};
+use crate::utils::{get_venv_python_bin, update_venv_sync_marker, CommandOutput, IoPathContext};
+use crate::uv::{UvBuilder, UvSyncOptions, Venv};
+use crate::vendor::VENDOR_DIR;
 
 /// Controls the sync mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
@@ -32,7 +47,7 @@ pub enum SyncMode {
 }
 
 /// Updates the virtualenv based on the pyproject.toml
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct SyncOptions {
     /// How verbose should the sync be?
     pub output: CommandOutput,
@@ -50,6 +65,14 @@ pub struct SyncOptions {
     pub pyproject: Option<PathBuf>,
     /// Keyring provider to use for credential lookup.
     pub keyring_provider: KeyringProvider,
+    /// Compile Python files to bytecode after installation.
+    pub compile_bytecode: bool,
+    /// Only regenerate the lockfile for this mode, the other one is left untouched.
+    pub only: Option<LockMode>,
+    /// Packages that are reinstalled even if they are already installed.
+    pub reinstall_packages: Vec<String>,
+    /// Install from the vendor folder without network access.
+    pub offline: bool,
 }
 
 impl SyncOptions {
@@ -72,6 +95,9 @@ impl SyncOptions {
 pub struct VenvMarker {
     pub python: PythonVersion,
     pub venv_path: Option<PathBuf>,
+    /// How the tool in this virtualenv was installed (tool virtualenvs only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<ToolSpec>,
 }
 
 impl VenvMarker {
@@ -83,8 +109,8 @@ impl VenvMarker {
 /// Synchronizes a project's virtualenv.
 pub fn sync(mut cmd: SyncOptions) -> Result<(), Error> {
     let pyproject = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
-    let lockfile = pyproject.workspace_path().join("requirements.lock");
-    let dev_lockfile = pyproject.workspace_path().join("requirements-dev.lock");
+    let lockfile = pyproject.lockfile_path(LockMode::Production);
+    let dev_lockfile = pyproject.lockfile_path(LockMode::Dev);
     let venv = pyproject.venv_path();
     let py_ver = pyproject.venv_python_version()?;
     let output = cmd.output;
@@ -97,19 +123,11 @@ pub fn sync(mut cmd: SyncOptions) -> Result<(), Error> {
         bail!("cannot sync or generate lockfile: package needs 'pyproject.toml'");
     }
 
-    // Turn on generate_hashes if the project demands it.
-    if pyproject.generate_hashes() {
-        cmd.lock_options.generate_hashes = true;
-    }
-
-    // Turn on universal locking if the project demands it.
-    if pyproject.universal() {
-        cmd.lock_options.universal = true;
-    }
+    apply_project_lock_options(&pyproject, &mut cmd.lock_options)?;
 
-    // Turn on locking with sources if the project demands it.
-    if pyproject.lock_with_sources() {
-        cmd.lock_options.with_sources = true;
+    // Turn on bytecode compilation if the project demands it.
+    if pyproject.compile_bytecode() {
+        cmd.compile_bytecode = true;
     }
 
     // ensure we are bootstrapped
@@ -152,12 +170,18 @@ pub fn sync(mut cmd: SyncOptions) -> Result<(), Error> {
     }
 
     // make sure we have a compatible python version
+    emit(ProgressEvent::Phase {
+        name: "fetch-python",
+    });
     let py_ver = fetch(&py_ver.into(), FetchOptions::with_output(output))
         .context("failed fetching toolchain ahead of sync")?;
 
     // kill the virtualenv if it's there and we need to get rid of it.
+    remove_old_venvs(&venv);
     if recreate && venv.is_dir() {
-        fs::remove_dir_all(&venv).path_context(&venv, "failed to delete existing virtualenv")?;
+        retry_while_locked(&venv, "recreating the virtualenv", output, || {
+            remove_venv(&venv)
+        })?;
     }
 
     if venv.is_dir() {
@@ -172,9 +196,14 @@ pub fn sync(mut cmd: SyncOptions) -> Result<(), Error> {
             style(venv.display()).cyan()
         );
         echo!(if output, "Python version: {}", style(&py_ver).cyan());
-        let prompt = pyproject.name().unwrap_or("venv");
-        create_virtualenv(output, &self_venv, &py_ver, &venv, prompt)
-            .context("failed creating virtualenv ahead of sync")?;
+        emit(ProgressEvent::Phase {
+            name: "create-venv",
+        });
+        if !clone_worktree_venv(output, &pyproject, &py_ver, &venv)? {
+            let prompt = pyproject.name().unwrap_or("venv");
+            create_virtualenv(output, &self_venv, &py_ver, &venv, prompt)
+                .context("failed creating virtualenv ahead of sync")?;
+        }
     }
 
     // prepare necessary utilities for pip-sync.  This is a super crude
@@ -182,7 +211,24 @@ pub fn sync(mut cmd: SyncOptions) -> Result<(), Error> {
     // into a folder all by itself and place a second file in there which we
     // can pass to pip-sync to install the local package.
     if recreate || cmd.mode != SyncMode::PythonOnly {
-        let sources = ExpandedSources::from_sources(&pyproject.sources()?)?;
+        let sources = ExpandedSources::from_sources_relative_to(
+            &pyproject.sources()?,
+            &pyproject.workspace_path(),
+        )?;
+        sources.echo_verbose(output);
+
+        // the lockfile we do not regenerate must still match the pyproject.toml,
+        // check this before writing anything so a failure leaves both untouched
+        match cmd.only {
+            Some(LockMode::Dev) => {
+                check_untouched_lockfile(&pyproject, &lockfile, LockMode::Production)?
+            }
+            Some(LockMode::Production) => {
+                check_untouched_lockfile(&pyproject, &dev_lockfile, LockMode::Dev)?
+            }
+            None => {}
+        }
+
         if cmd.no_lock {
             let lockfile = if cmd.dev { &dev_lockfile } else { &lockfile };
             if !lockfile.is_file() {
@@ -191,59 +237,95 @@ pub fn sync(mut cmd: SyncOptions) -> Result<(), Error> {
                     lockfile.display()
                 );
             }
-        } else if let Some(workspace) = pyproject.workspace() {
-            // make sure we have an up-to-date lockfile
-            update_workspace_lockfile(
-                &py_ver,
-                workspace,
-                LockMode::Production,
-                &lockfile,
-                cmd.output,
-                &sources,
-                &cmd.lock_options,
-                cmd.keyring_provider,
-            )
-            .context("could not write production lockfile for workspace")?;
-            update_workspace_lockfile(
-                &py_ver,
-                workspace,
-                LockMode::Dev,
-                &dev_lockfile,
-                cmd.output,
-                &sources,
-                &cmd.lock_options,
-                cmd.keyring_provider,
-            )
-            .context("could not write dev lockfile for workspace")?;
+            let vendor_dir = pyproject.workspace_path().join(VENDOR_DIR);
+            if cmd.offline && !vendor_dir.is_dir() {
+                bail!(
+                    "Cannot sync offline: '{}' does not exist, run `rye vendor` first",
+                    vendor_dir.display()
+                );
+            }
         } else {
-            // make sure we have an up-to-date lockfile
-            update_single_project_lockfile(
-                &py_ver,
-                &pyproject,
-                LockMode::Production,
-                &lockfile,
-                cmd.output,
-                &sources,
-                &cmd.lock_options,
-                cmd.keyring_provider,
-            )
-            .context("could not write production lockfile for project")?;
-            update_single_project_lockfile(
-                &py_ver,
-                &pyproject,
-                LockMode::Dev,
-                &dev_lockfile,
-                cmd.output,
-                &sources,
-                &cmd.lock_options,
-                cmd.keyring_provider,
-            )
-            .context("could not write dev lockfile for project")?;
+            // make sure we have up-to-date lockfiles
+            emit(ProgressEvent::Phase { name: "lock" });
+            check_expired_pins(&cmd.lock_options.pins, cmd.lock_options.deny_expired_pins)?;
+            cmd.lock_options.dev_presets = load_dev_presets(&pyproject, true, output)?;
+            let lock = |lock_mode: LockMode| -> Result<(), Error> {
+                let lockfile = match lock_mode {
+                    LockMode::Production => &lockfile,
+                    LockMode::Dev => &dev_lockfile,
+                };
+                if let Some(workspace) = pyproject.venv_workspace() {
+                    update_workspace_lockfile(
+                        &py_ver,
+                        workspace,
+                        lock_mode,
+                        lockfile,
+                        cmd.output,
+                        &sources,
+                        &cmd.lock_options,
+                        cmd.keyring_provider,
+                    )
+                    .with_context(|| {
+                        format!("could not write {} lockfile for workspace", lock_mode)
+                    })
+                } else {
+                    update_single_project_lockfile(
+                        &py_ver,
+                        &pyproject,
+                        lock_mode,
+                        lockfile,
+                        cmd.output,
+                        &sources,
+                        &cmd.lock_options,
+                        cmd.keyring_provider,
+                    )
+                    .with_context(|| format!("could not write {} lockfile for project", lock_mode))
+                }
+            };
+            match cmd.only {
+                Some(lock_mode) => lock(lock_mode)?,
+                // both resolutions are independent of each other
+                None if Config::current().parallel_lock() => {
+                    let (production, dev) = thread::scope(|scope| {
+                        let dev = scope.spawn(|| lock(LockMode::Dev));
+                        let production = lock(LockMode::Production);
+                        (
+                            production,
+                            dev.join().unwrap_or_else(|err| panic::resume_unwind(err)),
+                        )
+                    });
+                    production?;
+                    dev?;
+                }
+                None => {
+                    lock(LockMode::Production)?;
+                    lock(LockMode::Dev)?;
+                }
+            }
+
+            if cmd.lock_options.emit_build_requirements && !pyproject.has_python_override() {
+                let lockfiles = [lockfile.as_path(), dev_lockfile.as_path()]
+                    .into_iter()
+                    .filter(|x| x.is_file())
+                    .collect::<Vec<_>>();
+                update_build_requirements_lockfile(
+                    &py_ver,
+                    &pyproject,
+                    &lockfiles,
+                    &pyproject.workspace_path().join("build-requirements.lock"),
+                    cmd.output,
+                    &sources,
+                    &cmd.lock_options,
+                    cmd.keyring_provider,
+                )
+                .context("could not write build requirements lockfile")?;
+            }
         }
 
         // run pip install with the lockfile.
         if cmd.mode != SyncMode::LockOnly {
             echo!(if output, "Installing dependencies");
+            emit(ProgressEvent::Phase { name: "install" });
 
             let target_lockfile = if cmd.dev && dev_lockfile.is_file() {
                 dev_lockfile
@@ -252,20 +334,64 @@ pub fn sync(mut cmd: SyncOptions) -> Result<(), Error> {
             };
 
             let py_path = get_venv_python_bin(&venv);
-            let uv_options = UvSyncOptions {
-                keyring_provider: cmd.keyring_provider,
-            };
-            UvBuilder::new()
+            let editable_mode = scoped_editable_mode(&pyproject)?;
+            let uv_venv = UvBuilder::new()
                 .with_output(output.quieter())
                 .with_workdir(&pyproject.workspace_path())
                 .with_sources(sources)
+                .with_uv_args(&cmd.lock_options.uv_args)
                 .ensure_exists()?
                 .venv(&venv, &py_path, &py_ver, None)?
-                .with_output(output)
-                .sync(&target_lockfile, uv_options)?;
+                .with_output(output);
+            retry_while_locked(&venv, "installing dependencies", output, || {
+                let uv_options = UvSyncOptions {
+                    keyring_provider: cmd.keyring_provider,
+                    compile_bytecode: cmd.compile_bytecode,
+                    binary_policy: cmd.lock_options.binary_policy.clone(),
+                    editable_mode,
+                    reinstall_packages: cmd.reinstall_packages.clone(),
+                    vendor_dir: if cmd.offline {
+                        Some(pyproject.workspace_path().join(VENDOR_DIR))
+                    } else {
+                        None
+                    },
+                };
+                uv_venv.sync(&target_lockfile, uv_options)
+            })?;
+            emit(ProgressEvent::Installed {
+                venv: &venv,
+                packages: fs::read_to_string(&target_lockfile)
+                    .map_or(0, |x| parse_locked_versions(&x).len()),
+            });
+
+            if pyproject.check_entry_points() && output != CommandOutput::Quiet {
+                warn_about_broken_entry_points(&pyproject);
+            }
         };
     }
 
+    // members that override the Python version have their own virtualenv
+    if cmd.mode != SyncMode::PythonOnly && !pyproject.has_python_override() {
+        if let Some(workspace) = pyproject.workspace() {
+            for member in workspace.iter_projects() {
+                let member = member?;
+                if !member.has_python_override() {
+                    continue;
+                }
+                echo!(
+                    if output,
+                    "Syncing workspace member {} with its own virtualenv",
+                    style(member.name().unwrap_or("")).cyan()
+                );
+                sync(SyncOptions {
+                    pyproject: Some(member.toml_path().into_owned()),
+                    ..cmd.clone()
+                })
+                .with_context(|| format!("could not sync {}", member.root_path().display()))?;
+            }
+        }
+    }
+
     if cmd.mode != SyncMode::PythonOnly {
         echo!(if output, "Done!");
     }
@@ -273,6 +399,434 @@ pub fn sync(mut cmd: SyncOptions) -> Result<(), Error> {
     Ok(())
 }
 
+/// Ensures a lockfile that was not regenerated still satisfies the project.
+fn check_untouched_lockfile(
+    pyproject: &PyProject,
+    lockfile: &Path,
+    lock_mode: LockMode,
+) -> Result<(), Error> {
+    if !lockfile.is_file() {
+        bail!(
+            "{} lockfile '{}' does not exist, it must be generated first",
+            lock_mode,
+            lockfile.display()
+        );
+    }
+    let members;
+    let projects = match pyproject.venv_workspace() {
+        Some(workspace) => {
+            members = workspace
+                .iter_venv_projects()
+                .collect::<Result<Vec<_>, _>>()?;
+            members.iter().collect::<Vec<_>>()
+        }
+        None => vec![pyproject],
+    };
+    check_lockfile_is_current(lockfile, &projects, lock_mode)
+        .context("refusing to regenerate only one lockfile")
+}
+
+/// What a sync would do to the virtualenv.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum VenvAction {
+    Create,
+    Recreate,
+    Reuse,
+}
+
+impl fmt::Display for VenvAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VenvAction::Create => f.write_str("created"),
+            VenvAction::Recreate => f.write_str("recreated"),
+            VenvAction::Reuse => f.write_str("reused"),
+        }
+    }
+}
+
+/// The state of a lockfile ahead of a sync.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LockfileStatus {
+    /// The lockfile does not exist yet.
+    Missing,
+    /// The lockfile no longer satisfies the declared dependencies.
+    Outdated,
+    /// Packages in the lockfile were requested to be updated.
+    UpdateRequested,
+    /// The lockfile satisfies the declared dependencies.
+    UpToDate,
+    /// The lockfile is not regenerated (`--no-lock`, `--only-dev`, `--only-prod`).
+    Untouched,
+}
+
+impl LockfileStatus {
+    /// Is the lockfile regenerated with changes?
+    pub fn regenerates(self) -> bool {
+        matches!(
+            self,
+            LockfileStatus::Missing | LockfileStatus::Outdated | LockfileStatus::UpdateRequested
+        )
+    }
+}
+
+impl fmt::Display for LockfileStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockfileStatus::Missing => f.write_str("missing, would be generated"),
+            LockfileStatus::Outdated => f.write_str("outdated, would be regenerated"),
+            LockfileStatus::UpdateRequested => f.write_str("would be regenerated with updates"),
+            LockfileStatus::UpToDate => f.write_str("up to date"),
+            LockfileStatus::Untouched => f.write_str("untouched"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct LockfilePlan {
+    pub path: PathBuf,
+    pub mode: LockMode,
+    pub status: LockfileStatus,
+}
+
+/// How a package in the virtualenv would change.
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PackageAction {
+    Install,
+    Update,
+    Remove,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct PackageChange {
+    pub name: String,
+    pub action: PackageAction,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+/// The plan of a sync, computed without modifying anything.
+#[derive(Serialize, Debug, Clone)]
+pub struct SyncPlan {
+    pub venv: PathBuf,
+    pub venv_action: VenvAction,
+    pub lockfiles: Vec<LockfilePlan>,
+    /// The lockfile that is installed into the virtualenv.
+    pub install_from: PathBuf,
+    pub packages: Vec<PackageChange>,
+    /// The editable packages (local projects) that are rebuilt.
+    pub editables: Vec<String>,
+}
+
+/// Computes what a sync would do without modifying the lockfiles or the
+/// virtualenv.
+///
+/// Package changes are computed by comparing the lockfile that would be
+/// installed with the output of `uv pip list`.  If that lockfile would be
+/// regenerated, its current contents are used as the new versions are only
+/// known after resolving.
+pub fn sync_plan(cmd: &SyncOptions) -> Result<SyncPlan, Error> {
+    let pyproject = PyProject::load_or_discover(cmd.pyproject.as_deref())?;
+    let lockfile = pyproject.lockfile_path(LockMode::Production);
+    let dev_lockfile = pyproject.lockfile_path(LockMode::Dev);
+    let venv = pyproject.venv_path().into_owned();
+    let py_ver = pyproject.venv_python_version()?;
+
+    let venv_action = if !venv.is_dir() {
+        VenvAction::Create
+    } else if let Some(marker) = read_venv_marker(&venv) {
+        let relocated = marker.venv_path.as_ref().map_or(false, |venv_path| {
+            !is_same_file(&venv, venv_path).unwrap_or(false)
+        });
+        if cmd.mode == SyncMode::Full || marker.python != py_ver || relocated {
+            VenvAction::Recreate
+        } else {
+            VenvAction::Reuse
+        }
+    } else if cmd.force {
+        VenvAction::Recreate
+    } else {
+        bail!("virtualenv is not managed by rye. Run `rye sync -f` to force.");
+    };
+
+    let members;
+    let projects = match pyproject.venv_workspace() {
+        Some(workspace) => {
+            members = workspace
+                .iter_venv_projects()
+                .collect::<Result<Vec<_>, _>>()?;
+            members.iter().collect::<Vec<_>>()
+        }
+        None => vec![&pyproject],
+    };
+    let update_requested = cmd.lock_options.update_all
+        || !cmd.lock_options.update.is_empty()
+        || cmd.lock_options.refresh_yanked;
+    let lockfiles = [
+        (LockMode::Production, &lockfile),
+        (LockMode::Dev, &dev_lockfile),
+    ]
+    .into_iter()
+    .map(|(mode, path)| {
+        let status = if cmd.no_lock || cmd.only.map_or(false, |only| only != mode) {
+            LockfileStatus::Untouched
+        } else if !path.is_file() {
+            LockfileStatus::Missing
+        } else if update_requested {
+            LockfileStatus::UpdateRequested
+        } else if check_lockfile_is_current(path, &projects, mode).is_err() {
+            LockfileStatus::Outdated
+        } else {
+            LockfileStatus::UpToDate
+        };
+        LockfilePlan {
+            path: path.clone(),
+            mode,
+            status,
+        }
+    })
+    .collect();
+
+    let install_from = if cmd.dev && dev_lockfile.is_file() {
+        dev_lockfile
+    } else {
+        lockfile
+    };
+    let contents = if install_from.is_file() {
+        fs::read_to_string(&install_from).path_context(&install_from, "could not read lockfile")?
+    } else {
+        String::new()
+    };
+    let locked = parse_locked_versions(&contents)
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+    let editables = contents
+        .lines()
+        .filter_map(|line| line.strip_prefix("-e "))
+        .map(|x| x.trim().to_string())
+        .collect();
+
+    let installed = if venv_action == VenvAction::Reuse {
+        UvBuilder::new()
+            .with_output(cmd.output.quieter())
+            .ensure_exists()?
+            .read_only_venv(&venv)?
+            .list_packages()?
+            .into_iter()
+            .map(|pkg| (normalize_package_name(&pkg.name), pkg))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut packages = Vec::new();
+    for (name, version) in &locked {
+        match installed.get(name) {
+            None => packages.push(PackageChange {
+                name: name.clone(),
+                action: PackageAction::Install,
+                from: None,
+                to: version.as_ref().map(|x| x.to_string()),
+            }),
+            Some(pkg) if pkg.editable_project_location.is_none() => {
+                if let Some(version) = version {
+                    if pkg.version.parse::<Version>().ok().as_ref() != Some(version) {
+                        packages.push(PackageChange {
+                            name: pkg.name.clone(),
+                            action: PackageAction::Update,
+                            from: Some(pkg.version.clone()),
+                            to: Some(version.to_string()),
+                        });
+                    }
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    let mut removed = installed
+        .iter()
+        .filter(|(name, pkg)| {
+            pkg.editable_project_location.is_none() && !locked.contains_key(*name)
+        })
+        .map(|(_, pkg)| PackageChange {
+            name: pkg.name.clone(),
+            action: PackageAction::Remove,
+            from: Some(pkg.version.clone()),
+            to: None,
+        })
+        .collect::<Vec<_>>();
+    removed.sort_by(|a, b| a.name.cmp(&b.name));
+    packages.extend(removed);
+
+    Ok(SyncPlan {
+        venv,
+        venv_action,
+        lockfiles,
+        install_from,
+        packages,
+        editables,
+    })
+}
+
+/// Returns the editable mode to pass to the build backend when installing.
+///
+/// uv hands config settings to every build, not only to the editable builds of
+/// the local projects.  Only setuptools understands `editable_mode` (and only
+/// for editable builds), so it is only passed on if one of the local projects
+/// installed into the virtualenv is built with setuptools.
+fn scoped_editable_mode(pyproject: &PyProject) -> Result<Option<EditableMode>, Error> {
+    let Some(mode) = pyproject.editable_mode()? else {
+        return Ok(None);
+    };
+    let uses_setuptools = |project: &PyProject| {
+        // projects without a build backend are built with legacy setuptools
+        !project.is_virtual()
+            && project
+                .build_backend_name()
+                .map_or(true, |x| x.starts_with("setuptools."))
+    };
+    let any_setuptools = match pyproject.venv_workspace() {
+        Some(workspace) => workspace
+            .iter_venv_projects()
+            .filter_map(|x| x.ok())
+            .any(|x| uses_setuptools(&x)),
+        None => uses_setuptools(pyproject),
+    };
+    if !any_setuptools {
+        warn!(
+            category = Config,
+            "tool.rye.editable-mode is ignored as none of the local projects is built with setuptools"
+        );
+        return Ok(None);
+    }
+    Ok(Some(mode))
+}
+
+/// Applies the lock settings of the project to the lock options.
+fn apply_project_lock_options(
+    pyproject: &PyProject,
+    lock_options: &mut LockOptions,
+) -> Result<(), Error> {
+    // Turn on generate_hashes if the project demands it.
+    if pyproject.generate_hashes() {
+        lock_options.generate_hashes = true;
+    }
+    lock_options.header = pyproject.lock_header()?;
+    lock_options.binary_policy = pyproject.binary_policy()?;
+    lock_options.member_constraints = pyproject.member_constraints();
+    lock_options.pins = pyproject.pins()?;
+
+    // Turn on universal locking if the project demands it.
+    if pyproject.universal() {
+        lock_options.universal = true;
+    }
+
+    // Turn on locking with sources if the project demands it.
+    if pyproject.lock_with_sources() {
+        lock_options.with_sources = true;
+    }
+    Ok(())
+}
+
+/// The current and the previewed contents of a lockfile.
+#[derive(Debug)]
+pub struct LockPreview {
+    pub mode: LockMode,
+    pub path: PathBuf,
+    pub before: String,
+    pub after: String,
+}
+
+/// Resolves the lockfiles of a project that was modified in memory without
+/// writing anything.
+///
+/// `load` is invoked once per lockfile and returns the modified project.  The
+/// resolution starts from the current lockfile so that unrelated pins stay as
+/// they are.  Lockfiles that do not exist yet are skipped.
+pub fn preview_lockfiles(
+    load: impl Fn() -> Result<PyProject, Error>,
+    output: CommandOutput,
+    keyring_provider: KeyringProvider,
+) -> Result<Vec<LockPreview>, Error> {
+    ensure_self_venv(output).context("could not lock because bootstrap failed")?;
+    let py_ver = fetch(
+        &load()?.venv_python_version()?.into(),
+        FetchOptions::with_output(output),
+    )
+    .context("failed fetching toolchain ahead of locking")?;
+    preview_lockfiles_for_python(load, &py_ver, output, keyring_provider)
+}
+
+/// Like [`preview_lockfiles`] but resolves for the given Python version
+/// instead of the one the project uses.  The interpreter does not have to be
+/// installed for this.
+pub fn preview_lockfiles_for_python(
+    load: impl Fn() -> Result<PyProject, Error>,
+    py_ver: &PythonVersion,
+    output: CommandOutput,
+    keyring_provider: KeyringProvider,
+) -> Result<Vec<LockPreview>, Error> {
+    let pyproject = load()?;
+    let mut lock_options = LockOptions::default();
+    apply_project_lock_options(&pyproject, &mut lock_options)?;
+    lock_options.dev_presets = load_dev_presets(&pyproject, false, output)?;
+    ensure_self_venv(output).context("could not lock because bootstrap failed")?;
+    let sources = ExpandedSources::from_sources_relative_to(
+        &pyproject.sources()?,
+        &pyproject.workspace_path(),
+    )?;
+
+    let mut rv = Vec::new();
+    for mode in [LockMode::Production, LockMode::Dev] {
+        let path = pyproject.lockfile_path(mode);
+        if !path.is_file() {
+            continue;
+        }
+        let before = fs::read_to_string(&path).path_context(&path, "could not read lockfile")?;
+        let scratch = tempfile::tempdir()?;
+        let preview = scratch.path().join(path.file_name().unwrap_or_default());
+        fs::write(&preview, &before).path_context(&preview, "could not copy lockfile")?;
+
+        let project = load()?;
+        match project.venv_workspace().cloned() {
+            Some(workspace) => update_workspace_lockfile_with(
+                py_ver,
+                &workspace,
+                Some(project),
+                mode,
+                &preview,
+                CommandOutput::Quiet,
+                &sources,
+                &lock_options,
+                keyring_provider,
+            ),
+            None => update_single_project_lockfile(
+                py_ver,
+                &project,
+                mode,
+                &preview,
+                CommandOutput::Quiet,
+                &sources,
+                &lock_options,
+                keyring_provider,
+            ),
+        }
+        .with_context(|| format!("could not resolve {} lockfile", mode))?;
+
+        let after =
+            fs::read_to_string(&preview).path_context(&preview, "could not read lockfile")?;
+        rv.push(LockPreview {
+            mode,
+            path,
+            before,
+            after,
+        });
+    }
+    Ok(rv)
+}
+
 /// Performs an autosync.
 pub fn autosync(
     pyproject: &PyProject,
@@ -281,6 +835,7 @@ pub fn autosync(
     with_sources: bool,
     generate_hashes: bool,
     keyring_provider: KeyringProvider,
+    uv_args: Vec<String>,
 ) -> Result<(), Error> {
     sync(SyncOptions {
         output,
@@ -292,13 +847,75 @@ pub fn autosync(
             pre,
             with_sources,
             generate_hashes,
+            uv_args,
             ..Default::default()
         },
         pyproject: Some(pyproject.toml_path().to_path_buf()),
         keyring_provider,
+        compile_bytecode: false,
+        only: None,
+        reinstall_packages: Vec::new(),
+        offline: false,
     })
 }
 
+/// Clones the virtualenv of the main worktree if the project is in a linked
+/// git worktree and `behavior.clone-worktree-venvs` is enabled.
+///
+/// The regular sync afterwards only needs to install what differs between the
+/// worktrees.  Returns `false` if nothing was cloned.
+fn clone_worktree_venv(
+    output: CommandOutput,
+    pyproject: &PyProject,
+    py_ver: &PythonVersion,
+    venv: &Path,
+) -> Result<bool, Error> {
+    // the script launchers on windows embed the virtualenv path and cannot be relocated
+    if cfg!(windows) || !Config::current().clone_worktree_venvs() {
+        return Ok(false);
+    }
+    let workspace_path = pyproject.workspace_path();
+    let worktree = match find_linked_worktree(&workspace_path) {
+        Some(worktree) => worktree,
+        None => return Ok(false),
+    };
+    let source = match workspace_path
+        .canonicalize()
+        .ok()
+        .and_then(|x| Some(x.strip_prefix(&worktree.root).ok()?.to_path_buf()))
+        .zip(venv.strip_prefix(&*workspace_path).ok())
+    {
+        Some((workspace_rel, venv_rel)) => worktree.main_root.join(workspace_rel).join(venv_rel),
+        None => return Ok(false),
+    };
+    if !read_venv_marker(&source).map_or(false, |marker| marker.is_compatible(py_ver)) {
+        return Ok(false);
+    }
+
+    // try to kill the empty venv if there is one so it can be cloned into.
+    fs::remove_dir(venv).ok();
+    let method = match clone_venv(&source, venv) {
+        Ok(method) => method,
+        Err(err) => {
+            warn!(
+                category = Environment,
+                "unable to clone virtualenv of main worktree: {}", err
+            );
+            fs::remove_dir_all(venv).ok();
+            return Ok(false);
+        }
+    };
+    write_venv_marker(venv, py_ver)?;
+    update_venv_sync_marker(output, venv);
+    echo!(
+        if output,
+        "Cloned virtualenv of main worktree from {} ({})",
+        style(source.display()).cyan(),
+        method
+    );
+    Ok(true)
+}
+
 pub fn create_virtualenv(
     output: CommandOutput,
     _self_venv: &Path,
@@ -1,7 +1,22 @@
-use std::fmt;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::io::IsTerminal;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::{env, fmt, io};
+
+use anyhow::{bail, Error};
+use clap::ValueEnum;
+use console::Term;
+use indicatif::ProgressBar;
+
+use crate::utils::CommandOutput;
 
 static ECHO_TO_STDERR: AtomicBool = AtomicBool::new(false);
+static WARNING_POLICY: Mutex<WarningPolicy> = Mutex::new(WarningPolicy {
+    deny: Vec::new(),
+    allow: Vec::new(),
+});
+static DENIED_WARNINGS: AtomicUsize = AtomicUsize::new(0);
 
 #[doc(hidden)]
 pub fn _print(args: fmt::Arguments) {
@@ -29,6 +44,157 @@ impl Drop for RedirectGuard {
     }
 }
 
+/// Configures colors from the environment.
+///
+/// By default colors are only used for streams that are terminals.
+/// `CLICOLOR_FORCE` enables them for pipes too and `NO_COLOR` disables them
+/// altogether, taking precedence over `CLICOLOR_FORCE`.
+pub fn init_colors() {
+    if env::var_os("NO_COLOR").map_or(false, |x| !x.is_empty()) {
+        console::set_colors_enabled(false);
+        console::set_colors_enabled_stderr(false);
+    } else if env::var_os("CLICOLOR_FORCE").map_or(false, |x| !x.is_empty() && x != "0") {
+        console::set_colors_enabled(true);
+        console::set_colors_enabled_stderr(true);
+    }
+}
+
+/// Can the user be prompted?
+///
+/// This requires stdin to be a terminal to read the answer from and stderr to
+/// be a terminal to render the prompt on.
+pub fn is_interactive() -> bool {
+    io::stdin().is_terminal() && Term::stderr().is_term()
+}
+
+/// Fails if the user cannot be prompted.
+///
+/// The hint should tell the user how to avoid the prompt.
+pub fn ensure_interactive(hint: &str) -> Result<(), Error> {
+    if !is_interactive() {
+        bail!(
+            "cannot prompt for input because stdin is not a terminal; {}",
+            hint
+        );
+    }
+    Ok(())
+}
+
+/// Fails if there is no terminal to prompt the user on.
+///
+/// Unlike [`ensure_interactive`] stdin does not have to be a terminal as the
+/// prompts read the answers from the terminal directly.  This matters for the
+/// installer which is piped into a shell (`curl -sSf https://rye.astral.sh/get | bash`).
+pub fn ensure_terminal(hint: &str) -> Result<(), Error> {
+    if !Term::stderr().is_term() {
+        bail!(
+            "cannot prompt for input because stderr is not a terminal; {}",
+            hint
+        );
+    }
+    Ok(())
+}
+
+/// Creates a progress bar that is only drawn if stderr is a terminal.
+pub fn progress_bar(len: u64, output: CommandOutput) -> ProgressBar {
+    if output == CommandOutput::Quiet || !Term::stderr().is_term() {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(len)
+    }
+}
+
+/// The category of a warning.
+#[derive(ValueEnum, Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WarningCategory {
+    /// Use of deprecated settings, flags or commands.
+    Deprecated,
+    /// Questionable configuration in `pyproject.toml` or `config.toml`.
+    Config,
+    /// Problems with the workspace definition.
+    Workspace,
+    /// Issues with the environment Rye runs in.
+    Environment,
+    /// Problems with locked dependencies, such as yanked versions.
+    Dependencies,
+    /// Warnings without a more specific category.
+    Other,
+}
+
+impl fmt::Display for WarningCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => write!(f, "{}", value.get_name()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Selects warnings for `--deny`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WarningFilter {
+    /// All warnings (`warnings`).
+    All,
+    /// Only warnings of a specific category.
+    Category(WarningCategory),
+}
+
+impl FromStr for WarningFilter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "warnings" {
+            Ok(WarningFilter::All)
+        } else {
+            <WarningCategory as ValueEnum>::from_str(s, false).map(WarningFilter::Category)
+        }
+    }
+}
+
+/// Controls which warnings are silenced and which are turned into errors.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WarningPolicy {
+    pub deny: Vec<WarningFilter>,
+    pub allow: Vec<WarningCategory>,
+}
+
+impl WarningPolicy {
+    /// Adds the rules of another policy to this one.
+    pub fn extend(&mut self, other: WarningPolicy) {
+        self.deny.extend(other.deny);
+        self.allow.extend(other.allow);
+    }
+
+    /// Returns `None` if the warning should be silenced, otherwise whether it's denied.
+    fn is_denied(&self, category: WarningCategory) -> Option<bool> {
+        if self.deny.contains(&WarningFilter::Category(category)) {
+            Some(true)
+        } else if self.allow.contains(&category) {
+            None
+        } else {
+            Some(self.deny.contains(&WarningFilter::All))
+        }
+    }
+}
+
+/// Replaces the warning policy for this process.
+pub fn set_warning_policy(policy: WarningPolicy) {
+    *WARNING_POLICY.lock().unwrap() = policy;
+}
+
+/// Fails if warnings were emitted which are denied by the warning policy.
+pub fn check_denied_warnings() -> Result<(), Error> {
+    let denied = DENIED_WARNINGS.load(Ordering::Relaxed);
+    if denied > 0 {
+        bail!(
+            "aborting due to {} denied warning{}",
+            denied,
+            if denied == 1 { "" } else { "s" }
+        );
+    }
+    Ok(())
+}
+
 /// Echo a line to the output stream (usually stdout).
 macro_rules! echo {
     () => {
@@ -63,14 +229,22 @@ macro_rules! elog {
 }
 
 /// Emits a warning
+///
+/// Warnings can optionally be tagged with a category (`warn!(category = Deprecated, ...)`)
+/// so that they can be silenced or denied.
 macro_rules! warn {
+    (category = $category:ident, $($arg:tt)+) => {
+        $crate::tui::_warn(
+            $crate::tui::WarningCategory::$category,
+            format_args!($($arg)*)
+        )
+    };
     ($($arg:tt)+) => {
-        elog!(
-            "{} {}",
-            console::style("warning:").yellow().bold(),
+        $crate::tui::_warn(
+            $crate::tui::WarningCategory::Other,
             format_args!($($arg)*)
         )
-    }
+    };
 }
 
 /// Logs errors
@@ -83,3 +257,62 @@ macro_rules! error {
         )
     }
 }
+
+#[doc(hidden)]
+pub fn _warn(category: WarningCategory, args: fmt::Arguments) {
+    let denied = match WARNING_POLICY.lock().unwrap().is_denied(category) {
+        Some(denied) => denied,
+        None => return,
+    };
+    let label = match category {
+        WarningCategory::Other => String::new(),
+        category => format!("[{}]", category),
+    };
+    if denied {
+        DENIED_WARNINGS.fetch_add(1, Ordering::Relaxed);
+        elog!(
+            "{} {}",
+            console::style(format!("error{}:", label)).red().bold(),
+            args
+        );
+    } else {
+        elog!(
+            "{} {}",
+            console::style(format!("warning{}:", label)).yellow().bold(),
+            args
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_warning_policy {
+    use super::*;
+
+    #[test]
+    fn test_warning_filter_from_str() {
+        assert_eq!("warnings".parse(), Ok(WarningFilter::All));
+        assert_eq!(
+            "deprecated".parse(),
+            Ok(WarningFilter::Category(WarningCategory::Deprecated))
+        );
+        assert!("nonsense".parse::<WarningFilter>().is_err());
+    }
+
+    #[test]
+    fn test_warning_policy() {
+        let policy = WarningPolicy {
+            deny: vec![
+                WarningFilter::All,
+                WarningFilter::Category(WarningCategory::Config),
+            ],
+            allow: vec![WarningCategory::Deprecated, WarningCategory::Config],
+        };
+        assert_eq!(policy.is_denied(WarningCategory::Deprecated), None);
+        assert_eq!(policy.is_denied(WarningCategory::Config), Some(true));
+        assert_eq!(policy.is_denied(WarningCategory::Other), Some(true));
+        assert_eq!(
+            WarningPolicy::default().is_denied(WarningCategory::Other),
+            Some(false)
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{bail, Error};
+
+/// Is this line a second level heading?
+fn section_title(line: &str) -> Option<&str> {
+    line.strip_prefix("## ").map(|x| x.trim())
+}
+
+/// Does a section title refer to the given version?
+///
+/// Matches `1.0.0`, `[1.0.0]`, `1.0.0 - 2024-01-01` and similar.
+fn is_version_title(title: &str, version: &str) -> bool {
+    let title = title.strip_prefix('[').unwrap_or(title);
+    title.strip_prefix(version).map_or(false, |rest| {
+        rest.is_empty() || rest.starts_with([']', ' ', '('])
+    })
+}
+
+/// Adds a section for a release to the contents of a changelog.
+///
+/// The entries of the `Unreleased` section are moved into the new section
+/// and the (then empty) `Unreleased` section is kept above it.  Without an
+/// `Unreleased` section an empty section is inserted before the first
+/// release.  Fails if there is already a section for the version.
+pub fn add_release_section(contents: &str, version: &str, date: &str) -> Result<String, Error> {
+    let lines = contents.lines().collect::<Vec<_>>();
+    let sections = lines
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, line)| section_title(line).map(|title| (idx, title)))
+        .collect::<Vec<_>>();
+
+    if sections
+        .iter()
+        .any(|(_, title)| is_version_title(title, version))
+    {
+        bail!("changelog already has a section for version {}", version);
+    }
+
+    let unreleased = sections.iter().position(|(_, title)| {
+        title
+            .trim_matches(|c| c == '[' || c == ']')
+            .eq_ignore_ascii_case("unreleased")
+    });
+
+    let mut rv = Vec::new();
+    match unreleased {
+        Some(pos) => {
+            let (start, title) = sections[pos];
+            let end = sections.get(pos + 1).map_or(lines.len(), |x| x.0);
+            let heading = if title.starts_with('[') {
+                format!("## [{}] - {}", version, date)
+            } else {
+                format!("## {} - {}", version, date)
+            };
+            let entries = &lines[start + 1..end];
+            let first = entries.iter().position(|x| !x.trim().is_empty());
+            rv.extend(lines[..=start].iter().map(|x| x.to_string()));
+            rv.push(String::new());
+            rv.push(heading);
+            if let Some(first) = first {
+                rv.push(String::new());
+                rv.extend(entries[first..].iter().map(|x| x.to_string()));
+            }
+            if end < lines.len() {
+                if rv.last().map_or(false, |x| !x.trim().is_empty()) {
+                    rv.push(String::new());
+                }
+                rv.extend(lines[end..].iter().map(|x| x.to_string()));
+            }
+        }
+        None => {
+            let at = sections.first().map_or(lines.len(), |x| x.0);
+            rv.extend(lines[..at].iter().map(|x| x.to_string()));
+            if rv.last().map_or(false, |x| !x.trim().is_empty()) {
+                rv.push(String::new());
+            }
+            rv.push(format!("## {} - {}", version, date));
+            if at < lines.len() {
+                rv.push(String::new());
+                rv.extend(lines[at..].iter().map(|x| x.to_string()));
+            }
+        }
+    }
+
+    let mut rv = rv.join("\n");
+    rv.push('\n');
+    Ok(rv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moves_unreleased_entries() {
+        let contents = "# Changelog\n\n## [Unreleased]\n\n* Added foo\n* Fixed bar\n\n## [1.0.0] - 2024-01-01\n\n* Initial release\n";
+        assert_eq!(
+            add_release_section(contents, "1.1.0", "2024-02-01").unwrap(),
+            "# Changelog\n\n## [Unreleased]\n\n## [1.1.0] - 2024-02-01\n\n* Added foo\n* Fixed bar\n\n## [1.0.0] - 2024-01-01\n\n* Initial release\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_unreleased() {
+        let contents = "# Changelog\n\n## Unreleased\n\n## 1.0.0\n\n* Initial release\n";
+        assert_eq!(
+            add_release_section(contents, "1.1.0", "2024-02-01").unwrap(),
+            "# Changelog\n\n## Unreleased\n\n## 1.1.0 - 2024-02-01\n\n## 1.0.0\n\n* Initial release\n"
+        );
+    }
+
+    #[test]
+    fn test_without_unreleased() {
+        let contents = "# Changelog\n\n## 1.0.0\n\n* Initial release\n";
+        assert_eq!(
+            add_release_section(contents, "1.1.0", "2024-02-01").unwrap(),
+            "# Changelog\n\n## 1.1.0 - 2024-02-01\n\n## 1.0.0\n\n* Initial release\n"
+        );
+        assert_eq!(
+            add_release_section("# Changelog\n", "1.0.0", "2024-02-01").unwrap(),
+            "# Changelog\n\n## 1.0.0 - 2024-02-01\n"
+        );
+    }
+
+    #[test]
+    fn test_existing_section() {
+        for contents in [
+            "## [1.0.0] - 2024-01-01\n",
+            "## 1.0.0\n",
+            "## Unreleased\n\n## 1.0.0 (2024-01-01)\n",
+        ] {
+            assert_eq!(
+                add_release_section(contents, "1.0.0", "2024-02-01")
+                    .unwrap_err()
+                    .to_string(),
+                "changelog already has a section for version 1.0.0"
+            );
+        }
+        // other versions with the same prefix do not count
+        assert!(add_release_section("## 1.0.0.post1\n", "1.0.0", "2024-02-01").is_ok());
+    }
+}
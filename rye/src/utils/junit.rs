@@ -0,0 +1,129 @@
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use serde::Serialize;
+
+static TESTSUITE_RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"<testsuite\s[^>]*>").unwrap());
+static COUNT_ATTR_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\s(tests|failures|errors|skipped|time)="([^"]*)""#).unwrap());
+static CLASSNAME_RE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"(<testcase\s[^>]*?\bclassname=")"#).unwrap());
+
+/// Test counts as reported by a JUnit XML report.
+#[derive(Serialize, Debug, Default, Clone, PartialEq)]
+pub struct TestCounts {
+    pub tests: u64,
+    pub failures: u64,
+    pub errors: u64,
+    pub skipped: u64,
+    pub time: f64,
+}
+
+impl TestCounts {
+    /// Adds the counts of another report to this one.
+    pub fn add(&mut self, other: &TestCounts) {
+        self.tests += other.tests;
+        self.failures += other.failures;
+        self.errors += other.errors;
+        self.skipped += other.skipped;
+        self.time += other.time;
+    }
+}
+
+/// Sums up the counts of all test suites in a JUnit XML report.
+pub fn count_tests(report: &str) -> TestCounts {
+    let mut rv = TestCounts::default();
+    for tag in TESTSUITE_RE.find_iter(report) {
+        for attr in COUNT_ATTR_RE.captures_iter(tag.as_str()) {
+            let value = &attr[2];
+            match &attr[1] {
+                "tests" => rv.tests += value.parse().unwrap_or(0),
+                "failures" => rv.failures += value.parse().unwrap_or(0),
+                "errors" => rv.errors += value.parse().unwrap_or(0),
+                "skipped" => rv.skipped += value.parse().unwrap_or(0),
+                "time" => rv.time += value.parse().unwrap_or(0.0),
+                _ => {}
+            }
+        }
+    }
+    rv
+}
+
+/// Returns the test suites of a report with the test case class names prefixed.
+fn prefixed_suites(report: &str, prefix: &str) -> String {
+    let body = match (report.find("<testsuites"), report.rfind("</testsuites>")) {
+        (Some(start), Some(end)) if start < end => report[start..end]
+            .find('>')
+            .map_or("", |offset| &report[start + offset + 1..end]),
+        _ => report
+            .trim_start()
+            .strip_prefix("<?xml")
+            .and_then(|x| x.split_once("?>"))
+            .map_or(report, |x| x.1),
+    };
+    CLASSNAME_RE
+        .replace_all(body.trim(), |caps: &Captures| {
+            format!("{}{}.", &caps[1], prefix)
+        })
+        .into_owned()
+}
+
+/// Merges the JUnit XML reports of multiple packages into a single report.
+///
+/// The class names of all test cases are prefixed with the package name so
+/// that tests with the same name in different packages can be told apart.
+pub fn merge_reports(reports: &[(String, String)]) -> String {
+    let mut totals = TestCounts::default();
+    let mut body = String::new();
+    for (name, report) in reports {
+        totals.add(&count_tests(report));
+        let suites = prefixed_suites(report, name);
+        if !suites.is_empty() {
+            body.push_str(&suites);
+            body.push('\n');
+        }
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n\
+         <testsuites name=\"rye\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n\
+         {}</testsuites>\n",
+        totals.tests, totals.failures, totals.errors, totals.skipped, totals.time, body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT_A: &str = r#"<?xml version="1.0" encoding="utf-8"?><testsuites><testsuite name="pkg-a" errors="0" failures="1" skipped="0" tests="2" time="0.5"><testcase classname="tests.test_a" name="test_ok" time="0.1" /><testcase classname="tests.test_a" name="test_fail" time="0.4"><failure message="assert False" /></testcase></testsuite></testsuites>"#;
+    const REPORT_B: &str = r#"<?xml version="1.0" encoding="utf-8"?><testsuites><testsuite name="pkg-b" errors="0" failures="0" skipped="1" tests="1" time="0.25"><testcase classname="tests.test_b" name="test_skip" time="0.0"><skipped /></testcase></testsuite></testsuites>"#;
+
+    #[test]
+    fn test_count_tests() {
+        assert_eq!(
+            count_tests(REPORT_A),
+            TestCounts {
+                tests: 2,
+                failures: 1,
+                errors: 0,
+                skipped: 0,
+                time: 0.5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_merge_reports() {
+        let merged = merge_reports(&[
+            ("pkg-a".to_string(), REPORT_A.to_string()),
+            ("pkg-b".to_string(), REPORT_B.to_string()),
+        ]);
+        assert!(merged.starts_with("<?xml"));
+        assert!(merged.contains(
+            r#"<testsuites name="rye" tests="3" failures="1" errors="0" skipped="1" time="0.750">"#
+        ));
+        assert!(merged.contains(r#"<testcase classname="pkg-a.tests.test_a" name="test_ok""#));
+        assert!(merged.contains(r#"<testcase classname="pkg-b.tests.test_b" name="test_skip""#));
+        assert_eq!(merged.matches("<testsuites").count(), 1);
+        assert_eq!(count_tests(&merged).tests, 3);
+    }
+}
@@ -1,10 +1,11 @@
 use std::env;
 use std::ffi::OsString;
-use std::path::PathBuf;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
 
 use anyhow::Error;
 use clap::Parser;
+use console::style;
 
 use crate::bootstrap::ensure_self_venv;
 use crate::consts::VENV_BIN;
@@ -35,46 +36,126 @@ pub struct RuffArgs {
     extra_args: Vec<OsString>,
 }
 
+impl RuffArgs {
+    /// Returns the output mode requested on the command line.
+    pub fn output(&self) -> CommandOutput {
+        CommandOutput::from_quiet_and_verbose(self.quiet, self.verbose)
+    }
+
+    /// Returns the `pyproject.toml` file to use, if one was given.
+    fn pyproject(&self) -> Option<&Path> {
+        self.pyproject
+            .as_deref()
+            .filter(|x| !x.as_os_str().is_empty())
+    }
+
+    /// Was `--pyproject` passed without a path?
+    ///
+    /// `rye fmt` accepts this as shorthand for `--normalize-pyproject`.
+    pub fn normalize_requested(&self) -> bool {
+        self.pyproject
+            .as_deref()
+            .map_or(false, |x| x.as_os_str().is_empty())
+    }
+
+    /// Returns the projects the operation applies to.
+    pub fn projects(&self) -> Result<Vec<PyProject>, Error> {
+        let project = PyProject::load_or_discover(self.pyproject())?;
+        locate_projects(project, self.all, &self.package[..])
+    }
+}
+
 pub fn execute_ruff(args: RuffArgs, extra_args: &[&str]) -> Result<(), Error> {
-    let project = PyProject::load_or_discover(args.pyproject.as_deref())?;
+    let project = PyProject::load_or_discover(args.pyproject())?;
     let output = CommandOutput::from_quiet_and_verbose(args.quiet, args.verbose);
     let venv = ensure_self_venv(output)?;
     let ruff = venv.join(VENV_BIN).join("ruff");
+    let cache_dir = project.workspace_path().join(".ruff_cache");
 
-    let mut ruff_cmd = Command::new(ruff);
-    if env::var_os("RUFF_CACHE_DIR").is_none() {
-        ruff_cmd.env(
-            "RUFF_CACHE_DIR",
-            project.workspace_path().join(".ruff_cache"),
-        );
-    }
-    ruff_cmd.args(extra_args);
+    let make_cmd = || {
+        let mut ruff_cmd = Command::new(&ruff);
+        if env::var_os("RUFF_CACHE_DIR").is_none() {
+            ruff_cmd.env("RUFF_CACHE_DIR", &cache_dir);
+        }
+        ruff_cmd.args(extra_args);
+
+        match output {
+            CommandOutput::Normal => {}
+            CommandOutput::Verbose => {
+                ruff_cmd.arg("--verbose");
+            }
+            CommandOutput::Quiet => {
+                ruff_cmd.arg("--quiet");
+            }
+        }
 
-    match output {
-        CommandOutput::Normal => {}
-        CommandOutput::Verbose => {
-            ruff_cmd.arg("--verbose");
+        ruff_cmd.args(&args.extra_args);
+        ruff_cmd
+    };
+
+    if !args.paths.is_empty() {
+        let mut ruff_cmd = make_cmd();
+        ruff_cmd.arg("--");
+        for file in &args.paths {
+            ruff_cmd.arg(file.as_os_str());
         }
-        CommandOutput::Quiet => {
-            ruff_cmd.arg("--quiet");
+        return exit_with_status(ruff_cmd.status()?);
+    }
+
+    let project_roots = match project.workspace() {
+        Some(workspace) => workspace
+            .iter_projects()
+            .map(|x| x.map(|x| x.root_path().to_path_buf()))
+            .collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
+    let projects = locate_projects(project, args.all, &args.package[..])?;
+    if projects.len() < 2 {
+        let mut ruff_cmd = make_cmd();
+        ruff_cmd.arg("--");
+        for project in &projects {
+            ruff_cmd.arg(project.root_path().as_os_str());
         }
+        return exit_with_status(ruff_cmd.status()?);
     }
 
-    ruff_cmd.args(args.extra_args);
+    // with multiple members, ruff runs in each of them so that the member's
+    // own configuration applies, and the diagnostics are prefixed with it.
+    let mut failed_with = None;
+    for project in &projects {
+        let root = project.root_path();
+        let prefix = style(format!("[{}]", project.name().unwrap_or("unknown"))).cyan();
+        let mut ruff_cmd = make_cmd();
+        if console::colors_enabled() {
+            ruff_cmd.env("CLICOLOR_FORCE", "1");
+        }
+        // nested members are checked on their own
+        for path in &project_roots {
+            if path.starts_with(&root) && path.as_path() != root {
+                ruff_cmd.arg("--extend-exclude").arg(path.as_os_str());
+            }
+        }
+        ruff_cmd.arg("--").arg(".").current_dir(&root);
 
-    ruff_cmd.arg("--");
-    if args.paths.is_empty() {
-        let projects = locate_projects(project, args.all, &args.package[..])?;
-        for project in projects {
-            ruff_cmd.arg(project.root_path().as_os_str());
+        let result = ruff_cmd.output()?;
+        for line in String::from_utf8_lossy(&result.stdout).lines() {
+            echo!("{}", format!("{} {}", prefix, line).trim_end());
         }
-    } else {
-        for file in args.paths {
-            ruff_cmd.arg(file.as_os_str());
+        for line in String::from_utf8_lossy(&result.stderr).lines() {
+            elog!("{}", format!("{} {}", prefix, line).trim_end());
         }
+        if !result.status.success() {
+            let code = result.status.code().unwrap_or(1);
+            failed_with = Some(failed_with.map_or(code, |x: i32| x.max(code)));
+        }
+    }
+    match failed_with {
+        Some(code) => Err(QuietExit(code).into()),
+        None => Ok(()),
     }
+}
 
-    let status = ruff_cmd.status()?;
+fn exit_with_status(status: ExitStatus) -> Result<(), Error> {
     if !status.success() {
         let code = status.code().unwrap_or(1);
         Err(QuietExit(code).into())
@@ -1,5 +1,5 @@
 use anyhow::{anyhow, bail, Error};
-use toml_edit::{Array, DocumentMut, Item, RawString, Table, TableLike};
+use toml_edit::{Array, DocumentMut, Item, RawString, Table, TableLike, Value};
 
 /// Given a toml document, ensures that a given named table exists toplevel.
 ///
@@ -13,49 +13,173 @@ pub fn ensure_table<'a>(doc: &'a mut DocumentMut, name: &str) -> &'a mut Item {
     &mut doc.as_item_mut()[name]
 }
 
-/// Reformats a TOML array to multi line while trying to
-/// preserve all comments and move them around.  This also makes
-/// the array to have a trailing comma.
-pub fn reformat_array_multiline(deps: &mut Array) {
-    fn find_comments(s: Option<&RawString>) -> impl Iterator<Item = &str> {
-        s.and_then(|x| x.as_str())
-            .unwrap_or("")
-            .lines()
-            .filter_map(|line| {
-                let line = line.trim();
-                line.starts_with('#').then_some(line)
-            })
+/// The comments attached to an item of an array.
+#[derive(Debug, Default, Clone)]
+struct ItemComments {
+    /// Comments on the lines above the item.
+    leading: Vec<String>,
+    /// A comment on the same line right after the item.
+    trailing: Option<String>,
+}
+
+impl ItemComments {
+    fn add_trailing(&mut self, comment: &str) {
+        if self.trailing.is_none() {
+            self.trailing = Some(comment.to_string());
+        } else {
+            self.leading.push(comment.to_string());
+        }
+    }
+}
+
+fn find_comments(s: &str) -> impl Iterator<Item = &str> {
+    s.lines().filter_map(|line| {
+        let line = line.trim();
+        line.starts_with('#').then_some(line)
+    })
+}
+
+/// Splits the comment off that is on the same line as the preceding item.
+fn split_same_line_comment(s: &str) -> (Option<&str>, &str) {
+    match s.split_once('\n') {
+        Some((first, rest)) => (find_comments(first).next(), rest),
+        None => (None, s),
+    }
+}
+
+/// Figures out which comments belong to which item of an array.
+///
+/// The comment after an item on the same line ends up in the prefix of the
+/// next item (or the trailing part of the array) when TOML is parsed, so it is
+/// moved back to the item it was written next to.  Comments that cannot be
+/// attached to an item are returned separately.
+fn collect_comments(deps: &Array) -> (Vec<ItemComments>, Vec<String>) {
+    fn raw(s: Option<&RawString>) -> &str {
+        s.and_then(|x| x.as_str()).unwrap_or("")
     }
 
-    for item in deps.iter_mut() {
+    let mut rv = vec![ItemComments::default(); deps.len()];
+    for (idx, item) in deps.iter().enumerate() {
+        let decor = item.decor();
+        let mut prefix = raw(decor.prefix());
+        if idx > 0 {
+            let (same_line, rest) = split_same_line_comment(prefix);
+            if let Some(comment) = same_line {
+                rv[idx - 1].add_trailing(comment);
+            }
+            prefix = rest;
+        }
+        rv[idx]
+            .leading
+            .extend(find_comments(prefix).map(|x| x.to_string()));
+        for comment in find_comments(raw(decor.suffix())) {
+            rv[idx].add_trailing(comment);
+        }
+    }
+
+    let mut trailing = raw(Some(deps.trailing()));
+    if let Some(last) = rv.last_mut() {
+        let (same_line, rest) = split_same_line_comment(trailing);
+        if let Some(comment) = same_line {
+            last.add_trailing(comment);
+        }
+        trailing = rest;
+    }
+    let trailing = find_comments(trailing).map(|x| x.to_string()).collect();
+
+    (rv, trailing)
+}
+
+/// Lays out an array one item per line with the given comments.
+fn apply_comments(deps: &mut Array, comments: &[ItemComments], trailing: &[String]) {
+    let mut same_line = None;
+    for (item, comments) in deps.iter_mut().zip(comments) {
         let decor = item.decor_mut();
         let mut prefix = String::new();
-        for comment in find_comments(decor.prefix()).chain(find_comments(decor.suffix())) {
+        if let Some(comment) = same_line.take() {
+            prefix.push(' ');
+            prefix.push_str(comment);
+        }
+        for comment in &comments.leading {
             prefix.push_str("\n    ");
             prefix.push_str(comment);
         }
         prefix.push_str("\n    ");
         decor.set_prefix(prefix);
         decor.set_suffix("");
+        same_line = comments.trailing.as_deref();
     }
 
-    deps.set_trailing(&{
-        let mut comments = find_comments(Some(deps.trailing())).peekable();
-        let mut rv = String::new();
-        if comments.peek().is_some() {
-            for comment in comments {
-                rv.push_str("\n    ");
-                rv.push_str(comment);
-            }
-        }
-        if !rv.is_empty() || !deps.is_empty() {
-            rv.push('\n');
-        }
-        rv
-    });
+    let mut rv = String::new();
+    if let Some(comment) = same_line {
+        rv.push(' ');
+        rv.push_str(comment);
+    }
+    for comment in trailing {
+        rv.push_str("\n    ");
+        rv.push_str(comment);
+    }
+    if !rv.is_empty() || !deps.is_empty() {
+        rv.push('\n');
+    }
+    deps.set_trailing(rv);
     deps.set_trailing_comma(true);
 }
 
+/// Reformats a TOML array to multi line while trying to
+/// preserve all comments and move them around.  This also makes
+/// the array to have a trailing comma.
+///
+/// Comments stay attached to the item they were written above or
+/// next to.
+pub fn reformat_array_multiline(deps: &mut Array) {
+    let (comments, trailing) = collect_comments(deps);
+    apply_comments(deps, &comments, &trailing);
+}
+
+/// Removes an item from an array together with the comments attached to it
+/// and reformats the array to multi line.
+pub fn remove_array_item(deps: &mut Array, idx: usize) -> Value {
+    let (mut comments, trailing) = collect_comments(deps);
+    comments.remove(idx);
+    let rv = deps.remove(idx);
+    apply_comments(deps, &comments, &trailing);
+    rv
+}
+
+/// Sorts the items of an array by the given key and reformats it to
+/// multi line.
+///
+/// The sort is stable and comments move together with their items.
+pub fn sort_array_by_key<K: Ord>(deps: &mut Array, key: impl Fn(&Value) -> K) {
+    let (comments, trailing) = collect_comments(deps);
+    let mut items = deps.iter().cloned().zip(comments).collect::<Vec<_>>();
+    items.sort_by_cached_key(|(item, _)| key(item));
+    deps.clear();
+    let mut comments = Vec::with_capacity(items.len());
+    for (item, item_comments) in items {
+        deps.push_formatted(item);
+        comments.push(item_comments);
+    }
+    apply_comments(deps, &comments, &trailing);
+}
+
+/// Returns the text of the comments attached to each item of an array.
+pub fn array_item_comments(deps: &Array) -> Vec<Vec<String>> {
+    collect_comments(deps)
+        .0
+        .into_iter()
+        .map(|comments| {
+            comments
+                .leading
+                .into_iter()
+                .chain(comments.trailing)
+                .map(|x| x.trim_start_matches('#').trim().to_string())
+                .collect()
+        })
+        .collect()
+}
+
 /// Iterate over tables in an array.
 ///
 /// This helps one iterate over
@@ -79,3 +203,83 @@ pub fn iter_tables<'x>(
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPS: &str = r#"[
+    # owned by: web-team
+    "flask>=3.0.0",
+    "requests>=2.31.0",  # owned by: platform-team
+    "colorama>=0.4.6"  # owned by: cli-team
+]"#;
+
+    fn parse_array(s: &str) -> Array {
+        let doc: DocumentMut = format!("deps = {}\n", s).parse().unwrap();
+        doc["deps"].as_array().unwrap().clone()
+    }
+
+    #[test]
+    fn test_reformat_array_multiline_keeps_comments() {
+        let mut deps = parse_array(DEPS);
+        reformat_array_multiline(&mut deps);
+        assert_eq!(
+            deps.to_string().trim_start(),
+            r#"[
+    # owned by: web-team
+    "flask>=3.0.0",
+    "requests>=2.31.0", # owned by: platform-team
+    "colorama>=0.4.6", # owned by: cli-team
+]"#
+        );
+    }
+
+    #[test]
+    fn test_remove_array_item() {
+        let mut deps = parse_array(DEPS);
+        remove_array_item(&mut deps, 1);
+        assert_eq!(
+            deps.to_string().trim_start(),
+            r#"[
+    # owned by: web-team
+    "flask>=3.0.0",
+    "colorama>=0.4.6", # owned by: cli-team
+]"#
+        );
+        remove_array_item(&mut deps, 0);
+        assert_eq!(
+            deps.to_string().trim_start(),
+            r#"[
+    "colorama>=0.4.6", # owned by: cli-team
+]"#
+        );
+    }
+
+    #[test]
+    fn test_sort_array_by_key() {
+        let mut deps = parse_array(DEPS);
+        sort_array_by_key(&mut deps, |x| x.as_str().map(|x| x.to_string()));
+        assert_eq!(
+            deps.to_string().trim_start(),
+            r#"[
+    "colorama>=0.4.6", # owned by: cli-team
+    # owned by: web-team
+    "flask>=3.0.0",
+    "requests>=2.31.0", # owned by: platform-team
+]"#
+        );
+    }
+
+    #[test]
+    fn test_array_item_comments() {
+        assert_eq!(
+            array_item_comments(&parse_array(DEPS)),
+            vec![
+                vec!["owned by: web-team".to_string()],
+                vec!["owned by: platform-team".to_string()],
+                vec!["owned by: cli-team".to_string()],
+            ]
+        );
+    }
+}
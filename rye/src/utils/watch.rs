@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A simple polling file watcher.
+///
+/// This does not rely on operating system notifications but compares
+/// modification times of all matching files on every poll.  This is good
+/// enough for source trees and has no platform specific behavior.
+pub struct FileWatcher {
+    roots: Vec<PathBuf>,
+    extensions: Vec<&'static str>,
+    filenames: Vec<&'static str>,
+    state: HashMap<PathBuf, SystemTime>,
+}
+
+impl FileWatcher {
+    /// Creates a watcher for files with the given extensions or names below the roots.
+    pub fn new(
+        roots: Vec<PathBuf>,
+        extensions: &[&'static str],
+        filenames: &[&'static str],
+    ) -> FileWatcher {
+        let mut rv = FileWatcher {
+            roots,
+            extensions: extensions.to_vec(),
+            filenames: filenames.to_vec(),
+            state: HashMap::new(),
+        };
+        rv.state = rv.scan();
+        rv
+    }
+
+    /// Returns the files that were added, modified or removed since the last poll.
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let new_state = self.scan();
+        let mut changed = new_state
+            .iter()
+            .filter(|(path, mtime)| self.state.get(*path) != Some(*mtime))
+            .map(|(path, _)| path.clone())
+            .chain(
+                self.state
+                    .keys()
+                    .filter(|path| !new_state.contains_key(*path))
+                    .cloned(),
+            )
+            .collect::<Vec<_>>();
+        changed.sort();
+        self.state = new_state;
+        changed
+    }
+
+    fn is_watched(&self, path: &Path) -> bool {
+        path.file_name()
+            .and_then(|x| x.to_str())
+            .map_or(false, |x| self.filenames.contains(&x))
+            || path
+                .extension()
+                .and_then(|x| x.to_str())
+                .map_or(false, |x| self.extensions.contains(&x))
+    }
+
+    fn scan(&self) -> HashMap<PathBuf, SystemTime> {
+        let mut rv = HashMap::new();
+        for root in &self.roots {
+            for entry in walkdir::WalkDir::new(root)
+                .into_iter()
+                .filter_entry(|entry| !(entry.file_type().is_dir() && skip_dir(entry.file_name())))
+                .filter_map(|x| x.ok())
+            {
+                if entry.file_type().is_file() && self.is_watched(entry.path()) {
+                    if let Some(mtime) = entry.metadata().ok().and_then(|x| x.modified().ok()) {
+                        rv.insert(entry.into_path(), mtime);
+                    }
+                }
+            }
+        }
+        rv
+    }
+}
+
+/// Skips hidden folders as well as build output and caches.
+fn skip_dir(name: &OsStr) -> bool {
+    name.to_str().map_or(false, |x| {
+        x.starts_with('.') || x == "target" || x == "__pycache__"
+    })
+}
+
+#[test]
+fn test_file_watcher_poll() {
+    use std::fs;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    fs::create_dir_all(root.join("src")).unwrap();
+    fs::create_dir_all(root.join("target")).unwrap();
+    fs::write(root.join("src/lib.rs"), "").unwrap();
+    fs::write(root.join("Cargo.toml"), "").unwrap();
+
+    let mut watcher = FileWatcher::new(vec![root.to_path_buf()], &["rs"], &["Cargo.toml"]);
+    assert!(watcher.poll().is_empty());
+
+    // modifications are detected by the modification time
+    let lib = fs::File::options()
+        .write(true)
+        .open(root.join("src/lib.rs"))
+        .unwrap();
+    lib.set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    assert_eq!(watcher.poll(), vec![root.join("src/lib.rs")]);
+    assert!(watcher.poll().is_empty());
+
+    // unwatched files and skipped folders are ignored
+    fs::write(root.join("src/README.md"), "").unwrap();
+    fs::write(root.join("target/build.rs"), "").unwrap();
+    fs::create_dir_all(root.join(".git")).unwrap();
+    fs::write(root.join(".git/hook.rs"), "").unwrap();
+    assert!(watcher.poll().is_empty());
+
+    // added and removed files are reported together
+    fs::write(root.join("src/new.rs"), "").unwrap();
+    fs::remove_file(root.join("Cargo.toml")).unwrap();
+    assert_eq!(
+        watcher.poll(),
+        vec![root.join("Cargo.toml"), root.join("src/new.rs")]
+    );
+}
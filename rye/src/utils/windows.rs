@@ -100,6 +100,7 @@ fn get_windows_path_var() -> Result<Option<Vec<u16>>, Error> {
                 Ok(Some(s))
             } else {
                 warn!(
+                    category = Environment,
                     "the registry key HKEY_CURRENT_USER\\Environment\\PATH is not a string. \
                        Not modifying the PATH variable"
                 );
@@ -153,6 +154,60 @@ fn remove_entry_from_path(old_path: Vec<u16>, path_str: Vec<u16>) -> Option<Vec<
     Some(new_path)
 }
 
+/// Lists the processes that run an executable from inside a folder.
+///
+/// Returns the process ids with the path of the executable.  Processes that
+/// cannot be queried (such as the ones of other users) are skipped.
+pub(crate) fn find_processes_in(dir: &Path) -> Vec<(u32, PathBuf)> {
+    use std::mem;
+    use winapi::shared::minwindef::{DWORD, FALSE};
+    use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+    use winapi::um::processthreadsapi::OpenProcess;
+    use winapi::um::tlhelp32::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+    use winapi::um::winbase::QueryFullProcessImageNameW;
+    use winapi::um::winnt::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    let normalize = |path: &Path| {
+        path.to_string_lossy()
+            .trim_start_matches(r"\\?\")
+            .replace('/', "\\")
+            .to_lowercase()
+    };
+    let prefix = format!("{}\\", normalize(dir).trim_end_matches('\\'));
+
+    let mut rv = Vec::new();
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0);
+        if snapshot == INVALID_HANDLE_VALUE {
+            return rv;
+        }
+        let mut entry: PROCESSENTRY32W = mem::zeroed();
+        entry.dwSize = mem::size_of::<PROCESSENTRY32W>() as DWORD;
+        let mut buf = vec![0u16; 32768];
+        let mut more = Process32FirstW(snapshot, &mut entry) != FALSE;
+        while more {
+            let pid = entry.th32ProcessID;
+            let process = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, FALSE, pid);
+            if !process.is_null() {
+                let mut len = buf.len() as DWORD;
+                if QueryFullProcessImageNameW(process, 0, buf.as_mut_ptr(), &mut len) != FALSE {
+                    let exe = PathBuf::from(OsString::from_wide(&buf[..len as usize]));
+                    if normalize(&exe).starts_with(&prefix) {
+                        rv.push((pid, exe));
+                    }
+                }
+                CloseHandle(process);
+            }
+            more = Process32NextW(snapshot, &mut entry) != FALSE;
+        }
+        CloseHandle(snapshot);
+    }
+    rv
+}
+
 /// Registers rye as installed program.
 pub(crate) fn add_to_programs(rye_home: &Path) -> Result<(), Error> {
     let key = RegKey::predef(HKEY_CURRENT_USER)
@@ -160,7 +215,10 @@ pub(crate) fn add_to_programs(rye_home: &Path) -> Result<(), Error> {
         .context("Failed creating uninstall key")?
         .0;
 
-    // Don't overwrite registry if Rye is already installed
+    let current_version: &str = env!("CARGO_PKG_VERSION");
+
+    // Don't overwrite registry if Rye is already installed, but keep the
+    // displayed version current.
     let prev = key
         .get_raw_value("UninstallString")
         .map(|val| from_winreg_value(&val));
@@ -168,6 +226,8 @@ pub(crate) fn add_to_programs(rye_home: &Path) -> Result<(), Error> {
         let mut path = PathBuf::from(OsString::from_wide(&s));
         path.pop();
         if path.exists() {
+            key.set_value("DisplayVersion", &current_version)
+                .context("Failed to set display version")?;
             return Ok(());
         }
     }
@@ -175,16 +235,29 @@ pub(crate) fn add_to_programs(rye_home: &Path) -> Result<(), Error> {
     let mut uninstall_cmd = OsString::from("\"");
     uninstall_cmd.push(rye_home);
     uninstall_cmd.push("\\shims\\rye.exe\" self uninstall");
+    let mut quiet_uninstall_cmd = uninstall_cmd.clone();
+    quiet_uninstall_cmd.push(" --yes");
 
     let reg_value = RegValue {
         bytes: to_winreg_bytes(uninstall_cmd.encode_wide().collect()),
         vtype: RegType::REG_SZ,
     };
-
-    let current_version: &str = env!("CARGO_PKG_VERSION");
+    let quiet_reg_value = RegValue {
+        bytes: to_winreg_bytes(quiet_uninstall_cmd.encode_wide().collect()),
+        vtype: RegType::REG_SZ,
+    };
 
     key.set_raw_value("UninstallString", &reg_value)
         .context("Failed to set uninstall string")?;
+    key.set_raw_value("QuietUninstallString", &quiet_reg_value)
+        .context("Failed to set quiet uninstall string")?;
+    key.set_value("InstallLocation", &rye_home.as_os_str())
+        .context("Failed to set install location")?;
+    // rye can only be updated or removed, not modified or repaired
+    key.set_value("NoModify", &1u32)
+        .context("Failed to set NoModify")?;
+    key.set_value("NoRepair", &1u32)
+        .context("Failed to set NoRepair")?;
     key.set_value(
         "DisplayName",
         &"Rye: An Experimental Package Management Solution for Python",
@@ -0,0 +1,159 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use anyhow::{bail, Error};
+use walkdir::WalkDir;
+
+use crate::consts::VENV_BIN;
+use crate::utils::{symlink_file, IoPathContext};
+
+/// A linked git worktree (created with `git worktree add`).
+#[derive(Debug, Clone)]
+pub struct LinkedWorktree {
+    /// The root of the linked worktree.
+    pub root: PathBuf,
+    /// The root of the main worktree the linked worktree belongs to.
+    pub main_root: PathBuf,
+}
+
+/// If the directory is inside a linked git worktree, returns it.
+///
+/// Returns `None` for the main worktree, bare repositories or if the
+/// directory is not inside a git repository at all.
+pub fn find_linked_worktree(dir: &Path) -> Option<LinkedWorktree> {
+    let output = Command::new("git")
+        .arg("rev-parse")
+        .arg("--path-format=absolute")
+        .arg("--show-toplevel")
+        .arg("--git-dir")
+        .arg("--git-common-dir")
+        .current_dir(dir)
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    let stdout = String::from_utf8(output.stdout).ok()?;
+    let mut lines = stdout.lines();
+    let root = PathBuf::from(lines.next()?);
+    let git_dir = lines.next()?;
+    let common_dir = Path::new(lines.next()?);
+    if Path::new(git_dir) == common_dir || common_dir.file_name()? != ".git" {
+        return None;
+    }
+
+    Some(LinkedWorktree {
+        root,
+        main_root: common_dir.parent()?.to_path_buf(),
+    })
+}
+
+/// How a virtualenv was cloned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloneMethod {
+    /// All files are copy-on-write clones.
+    Reflink,
+    /// Installed packages are hard linked, everything else is copied.
+    Hardlink,
+}
+
+impl fmt::Display for CloneMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CloneMethod::Reflink => f.write_str("reflink"),
+            CloneMethod::Hardlink => f.write_str("hardlink"),
+        }
+    }
+}
+
+/// Clones a virtualenv to a new location.
+///
+/// Copy-on-write clones are used where the file system supports them,
+/// otherwise the files of the installed packages are hard linked.  The
+/// scripts and the configuration of the virtualenv are always copied and
+/// then rewritten to point to the new location.  The target must not exist.
+pub fn clone_venv(source: &Path, target: &Path) -> Result<CloneMethod, Error> {
+    if target.exists() {
+        bail!(
+            "cannot clone virtualenv, {} already exists",
+            target.display()
+        );
+    }
+
+    let method = if reflink_tree(source, target) {
+        CloneMethod::Reflink
+    } else {
+        fs::remove_dir_all(target).ok();
+        hardlink_tree(source, target)?;
+        CloneMethod::Hardlink
+    };
+
+    let (from, to) = (source.display().to_string(), target.display().to_string());
+    let bin_dir = target.join(VENV_BIN);
+    let files = fs::read_dir(&bin_dir)
+        .path_context(&bin_dir, "failed to read cloned virtualenv")?
+        .filter_map(|x| x.ok())
+        .map(|x| x.path())
+        .chain(Some(target.join("pyvenv.cfg")));
+    for path in files {
+        if path.is_symlink() || !path.is_file() {
+            continue;
+        }
+        // binary files (and everything else that is not utf-8) are left alone
+        if let Ok(contents) = fs::read_to_string(&path) {
+            if contents.contains(&from) {
+                fs::write(&path, contents.replace(&from, &to))
+                    .path_context(&path, "failed to relocate cloned virtualenv")?;
+            }
+        }
+    }
+
+    Ok(method)
+}
+
+/// Tries to clone a directory tree with copy-on-write clones.
+fn reflink_tree(source: &Path, target: &Path) -> bool {
+    let mut cmd = Command::new("cp");
+    if cfg!(target_os = "macos") {
+        cmd.arg("-c").arg("-R");
+    } else if cfg!(target_os = "linux") {
+        cmd.arg("-R").arg("--reflink=always");
+    } else {
+        return false;
+    }
+    cmd.arg(source)
+        .arg(target)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map_or(false, |status| status.success())
+}
+
+/// Recreates a directory tree with hard links.
+///
+/// Files in the root of the tree and in the bin folder are copied instead as
+/// they are modified after cloning.
+fn hardlink_tree(source: &Path, target: &Path) -> Result<(), Error> {
+    for entry in WalkDir::new(source) {
+        let entry = entry?;
+        let rel_path = entry.path().strip_prefix(source)?;
+        let dest = target.join(rel_path);
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            fs::create_dir_all(&dest).path_context(&dest, "failed to create directory")?;
+        } else if file_type.is_symlink() {
+            symlink_file(fs::read_link(entry.path())?, &dest)
+                .path_context(&dest, "failed to create symlink")?;
+        } else if rel_path.parent().map_or(true, |x| {
+            x.as_os_str().is_empty() || x == Path::new(VENV_BIN)
+        }) || fs::hard_link(entry.path(), &dest).is_err()
+        {
+            fs::copy(entry.path(), &dest).path_context(&dest, "failed to copy file")?;
+        }
+    }
+    Ok(())
+}
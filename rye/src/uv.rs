@@ -1,19 +1,27 @@
 use crate::bootstrap::{download_url, SELF_REQUIREMENTS};
-use crate::lock::{make_project_root_fragment, KeyringProvider};
-use crate::platform::get_app_dir;
+use crate::build_hints::print_build_hints;
+use crate::config::Config;
+use crate::file_locks::find_venv_processes;
+use crate::lock::{make_project_root_fragment, BinaryPolicy, EditableMode, KeyringProvider};
+use crate::platform::{get_app_dir, get_shared_toolchain_dir};
+use crate::progress::{emit, ProgressEvent};
 use crate::pyproject::{read_venv_marker, write_venv_marker, ExpandedSources};
 use crate::sources::py::PythonVersion;
 use crate::sources::uv::{UvDownload, UvRequest};
 use crate::utils::{
-    check_checksum, set_proxy_variables, unpack_archive, update_venv_sync_marker, CommandOutput,
-    IoPathContext,
+    check_checksum, is_sharing_violation, set_proxy_variables, set_uv_tls_variables,
+    unpack_archive, update_venv_sync_marker, CommandOutput, IoPathContext,
 };
 use anyhow::{anyhow, Context, Error};
 use pep508_rs::Requirement;
+use serde::Deserialize;
 use std::fs::{self, remove_dir_all};
-use std::io::Write;
+use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
-use std::process::{Command, Stdio};
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::Once;
+use std::thread;
+use std::time::Duration;
 use tempfile::NamedTempFile;
 
 #[derive(Default)]
@@ -22,6 +30,7 @@ pub struct UvInstallOptions {
     pub extras: Vec<Requirement>,
     pub refresh: bool,
     pub keyring_provider: KeyringProvider,
+    pub compile_bytecode: bool,
 }
 
 pub enum UvPackageUpgrade {
@@ -42,6 +51,7 @@ struct UvCompileOptions {
     pub keyring_provider: KeyringProvider,
     pub generate_hashes: bool,
     pub universal: bool,
+    pub binary_policy: BinaryPolicy,
 }
 
 impl UvCompileOptions {
@@ -70,6 +80,8 @@ impl UvCompileOptions {
             cmd.arg("--universal");
         }
 
+        self.binary_policy.add_as_pip_args(cmd);
+
         match self.upgrade {
             UvPackageUpgrade::All => {
                 cmd.arg("--upgrade");
@@ -97,17 +109,43 @@ impl Default for UvCompileOptions {
             generate_hashes: false,
             keyring_provider: KeyringProvider::Disabled,
             universal: false,
+            binary_policy: BinaryPolicy::default(),
         }
     }
 }
 
 pub struct UvSyncOptions {
     pub keyring_provider: KeyringProvider,
+    pub compile_bytecode: bool,
+    pub binary_policy: BinaryPolicy,
+    pub editable_mode: Option<EditableMode>,
+    pub reinstall_packages: Vec<String>,
+    /// Install from this folder only, without network access.
+    pub vendor_dir: Option<PathBuf>,
 }
 
 impl UvSyncOptions {
     pub fn add_as_pip_args(self, cmd: &mut Command) {
         self.keyring_provider.add_as_pip_args(cmd);
+        self.binary_policy.add_as_pip_args(cmd);
+        if let Some(mode) = self.editable_mode {
+            mode.add_as_pip_args(cmd);
+        }
+
+        if self.compile_bytecode {
+            cmd.arg("--compile-bytecode");
+        }
+
+        for package in self.reinstall_packages {
+            cmd.arg("--reinstall-package").arg(package);
+        }
+
+        if let Some(vendor_dir) = self.vendor_dir {
+            cmd.arg("--offline")
+                .arg("--no-index")
+                .arg("--find-links")
+                .arg(vendor_dir);
+        }
     }
 }
 
@@ -115,13 +153,48 @@ impl Default for UvSyncOptions {
     fn default() -> Self {
         Self {
             keyring_provider: KeyringProvider::Disabled,
+            compile_bytecode: false,
+            binary_policy: BinaryPolicy::default(),
+            editable_mode: None,
+            reinstall_packages: Vec::new(),
+            vendor_dir: None,
+        }
+    }
+}
+/// The uv subcommands that arguments passed with `--uv-arg` can target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum UvSubcommand {
+    Compile,
+    Install,
+    Sync,
+}
+
+impl UvSubcommand {
+    /// Splits the subcommand prefix (like `compile:`) off an argument.
+    fn split_prefix(arg: &str) -> (Option<UvSubcommand>, &str) {
+        for (prefix, subcommand) in [
+            ("compile:", UvSubcommand::Compile),
+            ("install:", UvSubcommand::Install),
+            ("sync:", UvSubcommand::Sync),
+        ] {
+            if let Some(rest) = arg.strip_prefix(prefix) {
+                return (Some(subcommand), rest);
+            }
         }
+        (None, arg)
+    }
+
+    /// Does this subcommand resolve packages?
+    fn resolves(self) -> bool {
+        matches!(self, UvSubcommand::Compile | UvSubcommand::Install)
     }
 }
+
 pub struct UvBuilder {
     workdir: Option<PathBuf>,
     sources: Option<ExpandedSources>,
     output: CommandOutput,
+    uv_args: Vec<String>,
 }
 
 impl UvBuilder {
@@ -130,6 +203,7 @@ impl UvBuilder {
             workdir: None,
             sources: None,
             output: CommandOutput::Normal,
+            uv_args: Vec::new(),
         }
     }
 
@@ -151,10 +225,21 @@ impl UvBuilder {
         Self { output, ..self }
     }
 
+    /// Raw arguments appended to the uv invocations that install or resolve
+    /// packages.  These are not validated by rye.
+    pub fn with_uv_args(self, uv_args: &[String]) -> Self {
+        Self {
+            uv_args: uv_args.to_vec(),
+            ..self
+        }
+    }
+
     pub fn ensure_exists(self) -> Result<Uv, Error> {
         let workdir = self.workdir.unwrap_or(std::env::current_dir()?);
         let sources = self.sources.unwrap_or_else(ExpandedSources::empty);
-        Uv::ensure(workdir, sources, self.output)
+        let mut uv = Uv::ensure(workdir, sources, self.output)?;
+        uv.uv_args = self.uv_args;
+        Ok(uv)
     }
 }
 
@@ -166,6 +251,7 @@ pub struct Uv {
     uv_bin: PathBuf,
     workdir: PathBuf,
     sources: ExpandedSources,
+    uv_args: Vec<String>,
 }
 
 impl Default for Uv {
@@ -175,6 +261,7 @@ impl Default for Uv {
             uv_bin: PathBuf::new(),
             workdir: std::env::current_dir().unwrap_or_default(),
             sources: ExpandedSources::empty(),
+            uv_args: Vec::new(),
         }
     }
 }
@@ -209,9 +296,28 @@ impl Uv {
                 uv_bin,
                 workdir,
                 sources,
+                uv_args: Vec::new(),
             });
         }
 
+        // the shared toolchain store is never written to, but a uv binary
+        // placed there is picked up.
+        if let Some(shared) = get_shared_toolchain_dir() {
+            let shared_bin = shared
+                .join("uv")
+                .join(download.version())
+                .join(uv_bin.file_name().unwrap_or_default());
+            if shared_bin.is_file() {
+                return Ok(Uv {
+                    output,
+                    uv_bin: shared_bin,
+                    workdir,
+                    sources,
+                    uv_args: Vec::new(),
+                });
+            }
+        }
+
         Self::download(&download, &uv_dir, output)?;
         Self::cleanup_old_versions(&base_dir, &uv_dir)?;
         if uv_dir.exists() && uv_bin.is_file() {
@@ -220,6 +326,7 @@ impl Uv {
                 uv_bin,
                 workdir,
                 sources,
+                uv_args: Vec::new(),
             });
         }
 
@@ -236,7 +343,10 @@ impl Uv {
 
         for entry in versions {
             if let Err(e) = remove_dir_all(entry.path()) {
-                warn!("Failed to remove old uv version: {}", e);
+                warn!(
+                    category = Environment,
+                    "Failed to remove old uv version: {}", e
+                );
             }
         }
         Ok(())
@@ -274,7 +384,7 @@ impl Uv {
     }
 
     /// Returns a new command with the uv binary as the command to run.
-    /// The command will have the correct proxy settings and verbosity level based on CommandOutput.
+    /// The command will have the correct proxy and TLS settings and verbosity level based on CommandOutput.
     pub fn cmd(&self) -> Command {
         let mut cmd = Command::new(&self.uv_bin);
         cmd.current_dir(&self.workdir);
@@ -292,9 +402,44 @@ impl Uv {
         }
 
         set_proxy_variables(&mut cmd);
+        set_uv_tls_variables(&mut cmd);
+        if let Some(timeout) = Config::current().network_timeout() {
+            cmd.env("UV_HTTP_TIMEOUT", timeout.to_string());
+        }
         cmd
     }
 
+    /// Appends the unsupported raw arguments passed with `--uv-arg` that
+    /// apply to the given uv subcommand.
+    ///
+    /// Arguments can be prefixed with the subcommand they are meant for
+    /// (`compile:`, `install:` or `sync:`).  Arguments without a prefix are
+    /// passed to the invocations that resolve packages (`pip compile` and
+    /// `pip install`).
+    fn add_uv_args(&self, cmd: &mut Command, subcommand: UvSubcommand) {
+        let args = self
+            .uv_args
+            .iter()
+            .filter_map(|arg| match UvSubcommand::split_prefix(arg) {
+                (Some(prefix), arg) => (prefix == subcommand).then_some(arg),
+                (None, arg) => subcommand.resolves().then_some(arg),
+            })
+            .collect::<Vec<_>>();
+        if args.is_empty() {
+            return;
+        }
+        // uv is invoked multiple times per command, warn only once
+        static WARNED: Once = Once::new();
+        WARNED.call_once(|| {
+            warn!(
+                category = Other,
+                "passing unsupported arguments to uv: {}",
+                self.uv_args.join(" ")
+            )
+        });
+        cmd.args(args);
+    }
+
     /// Ensures a venv exists, creating it at the given path if necessary.
     ///
     /// Returns a [`ReadWriteVenv`] that can be used to run commands in the venv.
@@ -347,7 +492,7 @@ impl Uv {
             cmd.arg("--prompt").arg(prompt);
         }
         cmd.arg(venv_dir);
-        let status = cmd.status().with_context(|| {
+        let status = run_uv("venv", &mut cmd).with_context(|| {
             format!(
                 "unable to create self venv using {}. It might be that \
                       the used Python build is incompatible with this machine. \
@@ -378,6 +523,8 @@ impl Uv {
         keyring_provider: KeyringProvider,
         generate_hashes: bool,
         universal: bool,
+        binary_policy: &BinaryPolicy,
+        constraints: Option<&Path>,
     ) -> Result<(), Error> {
         let options = UvCompileOptions {
             allow_prerelease,
@@ -388,6 +535,7 @@ impl Uv {
             generate_hashes,
             keyring_provider,
             universal,
+            binary_policy: binary_policy.clone(),
         };
 
         let mut cmd = self.cmd();
@@ -396,14 +544,19 @@ impl Uv {
         self.sources.add_as_pip_args(&mut cmd);
         options.add_as_pip_args(&mut cmd);
 
+        if let Some(constraints) = constraints {
+            cmd.arg("--constraint").arg(constraints);
+        }
+
         cmd.arg("--python-version")
             .arg(py_version.format_simple())
             .arg("--output-file")
             .arg(target);
+        self.add_uv_args(&mut cmd, UvSubcommand::Compile);
 
         cmd.arg(source);
 
-        let status = cmd.status().with_context(|| {
+        let status = run_uv("pip compile", &mut cmd).with_context(|| {
             format!(
                 "Unable to run uv pip compile and generate {}",
                 target.to_str().unwrap_or("<unknown>")
@@ -436,13 +589,22 @@ pub struct ReadWriteVenv {
     py_version: PythonVersion,
 }
 
+/// A package installed in a venv as reported by `uv pip list`.
+#[derive(Deserialize, Debug, Clone)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub editable_project_location: Option<String>,
+}
+
 pub trait Venv {
     fn cmd(&self) -> Command;
 
     fn venv_path(&self) -> &Path;
 
     /// Returns a new command with the uv binary as the command to run.
-    /// The command will have the correct proxy settings and verbosity level based on CommandOutput.
+    /// The command will have the correct proxy and TLS settings and verbosity level based on CommandOutput.
     /// The command will also have the VIRTUAL_ENV environment variable set to the venv path.
     fn venv_cmd(&self) -> Command {
         let mut cmd = self.cmd();
@@ -469,6 +631,55 @@ pub trait Venv {
 
         Ok(())
     }
+
+    /// Returns the packages installed in the venv in requirements format.
+    fn freeze_requirements(&self) -> Result<String, Error> {
+        let rv = self
+            .venv_cmd()
+            .arg("pip")
+            .arg("freeze")
+            .output()
+            .with_context(|| format!("unable to freeze venv at {}", self.venv_path().display()))?;
+
+        if !rv.status.success() {
+            return Err(anyhow!(
+                "Failed to freeze venv at {}. uv exited with status: {}\n{}",
+                self.venv_path().display(),
+                rv.status,
+                String::from_utf8_lossy(&rv.stderr)
+            ));
+        }
+
+        Ok(String::from_utf8_lossy(&rv.stdout).into_owned())
+    }
+
+    /// Lists the packages installed in the venv.
+    fn list_packages(&self) -> Result<Vec<InstalledPackage>, Error> {
+        let rv = self
+            .venv_cmd()
+            .arg("pip")
+            .arg("list")
+            .arg("--format")
+            .arg("json")
+            .stderr(Stdio::inherit())
+            .output()
+            .with_context(|| {
+                format!(
+                    "unable to list packages in venv at {}",
+                    self.venv_path().display()
+                )
+            })?;
+
+        if !rv.status.success() {
+            return Err(anyhow!(
+                "Failed to list packages in venv at {}. uv exited with status: {}",
+                self.venv_path().display(),
+                rv.status
+            ));
+        }
+
+        serde_json::from_slice(&rv.stdout).context("unable to parse package list from uv.")
+    }
 }
 
 impl Venv for ReadOnlyVenv {
@@ -532,19 +743,18 @@ impl ReadWriteVenv {
         let mut req_file = NamedTempFile::new()?;
         writeln!(req_file, "{}", requirements)?;
 
-        self.venv_cmd()
-            .arg("pip")
+        let mut cmd = self.venv_cmd();
+        cmd.arg("pip")
             .arg("install")
             .arg("--upgrade")
             .arg("-r")
-            .arg(req_file.path())
-            .status()
-            .with_context(|| {
-                format!(
-                    "unable to update requirements in venv at {}",
-                    self.venv_path.display()
-                )
-            })?;
+            .arg(req_file.path());
+        run_uv("pip install", &mut cmd).with_context(|| {
+            format!(
+                "unable to update requirements in venv at {}",
+                self.venv_path.display()
+            )
+        })?;
 
         Ok(())
     }
@@ -566,9 +776,14 @@ impl ReadWriteVenv {
             cmd.arg("--refresh");
         }
 
+        if options.compile_bytecode {
+            cmd.arg("--compile-bytecode");
+        }
+
         options.keyring_provider.add_as_pip_args(&mut cmd);
 
         self.uv.sources.add_as_pip_args(&mut cmd);
+        self.uv.add_uv_args(&mut cmd, UvSubcommand::Install);
 
         cmd.arg("--").arg(requirement.to_string());
 
@@ -582,23 +797,58 @@ impl ReadWriteVenv {
             cmd.arg("importlib-metadata==6.6.0");
         }
 
-        let status = cmd.status().with_context(|| {
-            format!(
-                "unable to install {} in venv at {}",
+        let status =
+            run_build_command("pip install", &mut cmd, &self.venv_path).with_context(|| {
+                format!(
+                    "unable to install {} in venv at {}",
+                    requirement,
+                    self.venv_path.display()
+                )
+            })?;
+
+        if !status.success() {
+            return Err(anyhow!(
+                "Installation of {} failed in venv at {}. uv exited with status: {}",
                 requirement,
+                self.venv_path.display(),
+                status
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Rebuilds and reinstalls a local project as editable package.
+    ///
+    /// Dependencies are left untouched, they are expected to be installed
+    /// by a previous sync already.
+    pub fn reinstall_editable(&self, name: &str, path: &Path) -> Result<(), Error> {
+        let mut cmd = self.venv_cmd();
+        cmd.arg("pip")
+            .arg("install")
+            .arg("--no-deps")
+            .arg("--reinstall-package")
+            .arg(name);
+
+        self.uv.sources.add_as_pip_args(&mut cmd);
+
+        cmd.arg("-e").arg(path);
+        let status = run_uv("pip install", &mut cmd).with_context(|| {
+            format!(
+                "unable to reinstall {} in venv at {}",
+                name,
                 self.venv_path.display()
             )
         })?;
 
         if !status.success() {
             return Err(anyhow!(
-                "Installation of {} failed in venv at {}. uv exited with status: {}",
-                requirement,
+                "Rebuilding {} failed in venv at {}. uv exited with status: {}",
+                name,
                 self.venv_path.display(),
                 status
             ));
         }
-
         Ok(())
     }
 
@@ -610,10 +860,10 @@ impl ReadWriteVenv {
         options.add_as_pip_args(&mut cmd);
 
         self.uv.sources.add_as_pip_args(&mut cmd);
+        self.uv.add_uv_args(&mut cmd, UvSubcommand::Sync);
 
-        let status = cmd
-            .arg(lockfile)
-            .status()
+        cmd.arg(lockfile);
+        let status = run_build_command("pip sync", &mut cmd, &self.venv_path)
             .with_context(|| format!("unable to run sync {}", self.venv_path.display()))?;
 
         if !status.success() {
@@ -649,6 +899,7 @@ impl ReadWriteVenv {
         allow_prerelease: bool,
         exclude_newer: Option<String>,
         keyring_provider: KeyringProvider,
+        binary_policy: &BinaryPolicy,
     ) -> Result<Requirement, Error> {
         let mut cmd = self.venv_cmd();
         let options = UvCompileOptions {
@@ -660,6 +911,7 @@ impl ReadWriteVenv {
             generate_hashes: false,
             keyring_provider,
             universal: false,
+            binary_policy: binary_policy.clone(),
         };
 
         cmd.arg("pip").arg("compile");
@@ -668,22 +920,28 @@ impl ReadWriteVenv {
         options.add_as_pip_args(&mut cmd);
 
         cmd.arg("--python-version").arg(py_version.format_simple());
+        self.uv.add_uv_args(&mut cmd, UvSubcommand::Compile);
 
         // We are using stdin so we can create the requirements in memory and don't
         // have to create a temporary file.
         cmd.arg("-");
 
-        let mut child = cmd
-            .stdin(Stdio::piped())
+        cmd.stdin(Stdio::piped())
             .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()?;
-
-        // Write requirement to stdin
-        let child_stdin = child.stdin.as_mut().unwrap();
-        writeln!(child_stdin, "{}", requirement)?;
-
-        let rv = child.wait_with_output()?;
+            .stderr(Stdio::piped());
+        let rv = run_with_retries(
+            "pip compile",
+            || {
+                let mut child = cmd.spawn()?;
+
+                // Write requirement to stdin
+                let child_stdin = child.stdin.as_mut().unwrap();
+                writeln!(child_stdin, "{}", requirement)?;
+
+                child.wait_with_output()
+            },
+            |rv| (rv.status.success(), rv.stderr.as_slice()),
+        )?;
         if !rv.status.success() {
             let log = String::from_utf8_lossy(&rv.stderr);
             return Err(anyhow!(
@@ -698,3 +956,216 @@ impl ReadWriteVenv {
             .context("unable to parse requirement from uv.")
     }
 }
+
+/// Runs a uv invocation that might build packages from source.
+///
+/// The error output of uv is passed through and, if the invocation fails,
+/// checked for system dependencies that are missing to build a package.
+/// Failures caused by files of the virtualenv that are in use are reported as
+/// error so that callers can detect them, they are not retried here.
+fn run_build_command(command: &str, cmd: &mut Command, venv: &Path) -> std::io::Result<ExitStatus> {
+    let (status, log) = run_with_retries(
+        command,
+        || {
+            let (status, log) = run_tee_stderr(cmd)?;
+            match find_locked_error(status, &log, venv) {
+                Some(err) => Err(err),
+                None => Ok((status, log)),
+            }
+        },
+        |(status, log)| (status.success(), log.as_slice()),
+    )?;
+    if !status.success() {
+        print_build_hints(&String::from_utf8_lossy(&log));
+    }
+    Ok(status)
+}
+
+/// Detects uv failing because files of the virtualenv are in use.
+///
+/// Files that are in use by another process cannot be replaced on windows.
+/// Access denied (os error 5) has other causes as well, so it only counts if
+/// a process is running from the virtualenv.
+fn find_locked_error(status: ExitStatus, log: &[u8], venv: &Path) -> Option<std::io::Error> {
+    if !cfg!(windows) || status.success() {
+        return None;
+    }
+    let log = String::from_utf8_lossy(log);
+    [32, 33, 5]
+        .into_iter()
+        .filter(|code| log.contains(&format!("(os error {})", code)))
+        .find(|&code| code != 5 || !find_venv_processes(venv).is_empty())
+        .map(std::io::Error::from_raw_os_error)
+}
+
+/// Fragments of uv error output that point to network problems.
+const NETWORK_ERRORS: &[&str] = &[
+    "error sending request",
+    "tcp connect error",
+    "dns error",
+    "connection reset",
+    "connection refused",
+    "connection closed",
+    "operation timed out",
+    "network is unreachable",
+    "http status server error",
+];
+
+/// Does the error output of uv point to a network problem?
+fn is_network_error(log: &[u8]) -> bool {
+    let log = String::from_utf8_lossy(log).to_ascii_lowercase();
+    NETWORK_ERRORS.iter().any(|x| log.contains(x))
+}
+
+/// Runs a uv invocation and passes its error output through.
+///
+/// The error output is also returned so that failures can be inspected.  On a
+/// terminal uv writes to it directly so that its progress display is kept, the
+/// output is then only captured by running uv again if the invocation fails.
+fn run_tee_stderr(cmd: &mut Command) -> std::io::Result<(ExitStatus, Vec<u8>)> {
+    if !console::Term::stderr().is_term() {
+        return run_piped_stderr(cmd, true);
+    }
+    let status = cmd.stderr(Stdio::inherit()).status()?;
+    if status.success() {
+        return Ok((status, Vec::new()));
+    }
+    // the error output was already shown, only capture it for inspection
+    cmd.env("NO_COLOR", "1");
+    let rv = run_piped_stderr(cmd, false);
+    cmd.env_remove("NO_COLOR");
+    rv
+}
+
+/// Runs a uv invocation with its error output piped and returns it.
+fn run_piped_stderr(cmd: &mut Command, echo: bool) -> std::io::Result<(ExitStatus, Vec<u8>)> {
+    // the output is piped, so colors have to be requested explicitly
+    if echo && console::colors_enabled_stderr() {
+        cmd.env("FORCE_COLOR", "1");
+    }
+    cmd.stderr(Stdio::piped());
+    let mut child = cmd.spawn()?;
+    let mut reader = BufReader::new(child.stderr.take().unwrap());
+    let mut log = Vec::new();
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        if echo {
+            std::io::stderr().write_all(&line)?;
+        }
+        log.append(&mut line);
+    }
+    Ok((child.wait()?, log))
+}
+
+/// Runs a uv invocation with retries (see [`run_with_retries`]).
+fn run_uv(command: &str, cmd: &mut Command) -> std::io::Result<ExitStatus> {
+    run_with_retries(
+        command,
+        || run_tee_stderr(cmd),
+        |(status, log)| (status.success(), log.as_slice()),
+    )
+    .map(|(status, _)| status)
+}
+
+/// Runs a uv invocation, re-invoking it with exponential backoff as long
+/// as it fails because of the network and retries are left (see
+/// `network.retries`).
+///
+/// `outcome` returns if an invocation succeeded together with its error
+/// output.  Failures to run uv are retried as well, except for files that
+/// are in use.  Other failures (like resolution errors) are returned right
+/// away as running uv again cannot fix them.
+fn run_with_retries<T>(
+    command: &str,
+    f: impl FnMut() -> std::io::Result<T>,
+    outcome: impl Fn(&T) -> (bool, &[u8]),
+) -> std::io::Result<T> {
+    retry_loop(command, Config::current().network_retries(), f, outcome)
+}
+
+fn retry_loop<T>(
+    command: &str,
+    retries: u32,
+    mut f: impl FnMut() -> std::io::Result<T>,
+    outcome: impl Fn(&T) -> (bool, &[u8]),
+) -> std::io::Result<T> {
+    let mut attempt = 0;
+    loop {
+        emit(ProgressEvent::UvStart { command });
+        let rv = f();
+        let (success, retry) = match rv {
+            Ok(ref rv) => {
+                let (success, log) = outcome(rv);
+                (success, !success && is_network_error(log))
+            }
+            Err(ref err) => (false, !is_sharing_violation(err)),
+        };
+        emit(ProgressEvent::UvFinish { command, success });
+        if !retry || attempt >= retries {
+            return rv;
+        }
+        let delay = Duration::from_secs(1 << attempt.min(6));
+        attempt += 1;
+        warn!(
+            category = Environment,
+            "uv failed, retrying in {}s (attempt {} of {})",
+            delay.as_secs(),
+            attempt,
+            retries
+        );
+        thread::sleep(delay);
+    }
+}
+
+#[test]
+fn test_uv_arg_prefixes() {
+    assert_eq!(
+        UvSubcommand::split_prefix("--resolution=lowest"),
+        (None, "--resolution=lowest")
+    );
+    assert_eq!(
+        UvSubcommand::split_prefix("sync:--link-mode=copy"),
+        (Some(UvSubcommand::Sync), "--link-mode=copy")
+    );
+    assert_eq!(
+        UvSubcommand::split_prefix("compile:--no-strip-markers"),
+        (Some(UvSubcommand::Compile), "--no-strip-markers")
+    );
+    assert!(UvSubcommand::Install.resolves());
+    assert!(!UvSubcommand::Sync.resolves());
+}
+
+#[test]
+fn test_retry_only_network_errors() {
+    let count_attempts = |log: &str| {
+        let mut attempts = 0;
+        retry_loop(
+            "pip compile",
+            1,
+            || {
+                attempts += 1;
+                Ok(log.as_bytes())
+            },
+            |log| (false, *log),
+        )
+        .unwrap();
+        attempts
+    };
+
+    assert_eq!(
+        count_attempts(
+            "  × No solution found when resolving dependencies:\n  \
+             ╰─▶ Because there is no version of foo==99 and you require foo==99, \
+             we can conclude that your requirements are unsatisfiable.\n"
+        ),
+        1
+    );
+    assert_eq!(
+        count_attempts(
+            "error: Failed to fetch: `https://pypi.org/simple/foo/`\n  \
+             Caused by: error sending request for url (https://pypi.org/simple/foo/)\n  \
+             Caused by: operation timed out\n"
+        ),
+        2
+    );
+}
@@ -0,0 +1,308 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::time::SystemTime;
+
+use anyhow::{anyhow, bail, Context, Error};
+use pep440_rs::Version;
+
+use crate::provenance::format_timestamp;
+use crate::pyproject::PyProject;
+use crate::utils::IoPathContext;
+
+/// The first line of the version files written by rye.
+const GENERATED_HEADER: &str = "# generated by rye";
+
+/// The position of the current commit relative to the most recent version tag.
+#[derive(Debug, PartialEq, Eq)]
+struct Describe {
+    /// The most recent version tag, if there is one.
+    tag: Option<String>,
+    /// The number of commits since the tag (or since the first commit).
+    distance: u64,
+    /// The abbreviated hash of the current commit.
+    node: String,
+    /// Are there uncommitted changes to tracked files (other than the version file)?
+    dirty: bool,
+}
+
+/// Computes the version of a project from the version control system.
+///
+/// The scheme follows setuptools-scm: a clean checkout of a version tag
+/// (`v1.2.3` or `1.2.3`) has the version of the tag.  Otherwise the next
+/// version is guessed and marked as development release of it, with the
+/// commit and the date of uncommitted changes as local version
+/// (`1.2.4.dev3+gabc1234.d20240101`).  Changes to the version file itself do
+/// not count as uncommitted changes.
+pub fn compute_vcs_version(project: &PyProject) -> Result<Version, Error> {
+    let root = project.root_path();
+    let version_file = project.version_file().map(|(path, _)| path);
+    let timestamp = format_timestamp(SystemTime::now());
+    let date = timestamp
+        .split('T')
+        .next()
+        .unwrap_or_default()
+        .replace('-', "");
+    format_version(&describe(&root, version_file.as_deref())?, &date)
+}
+
+/// Writes the version computed from the version control system into the
+/// version file of a project.
+///
+/// Files that are not configured in `tool.rye.version.file` are only
+/// overwritten if rye wrote them before, as the build backends commonly read
+/// the version from a module with other code in it.
+///
+/// Returns the version and the path of the version file.
+pub fn sync_version_from_vcs(project: &PyProject) -> Result<(Version, PathBuf), Error> {
+    if !project
+        .dynamic()
+        .unwrap_or_default()
+        .contains(&"version".to_string())
+    {
+        bail!("the version can only be synced if `version` is listed in project.dynamic");
+    }
+    let (path, configured) = project.version_file().ok_or_else(|| {
+        anyhow!("no version file configured, set tool.rye.version.file in pyproject.toml")
+    })?;
+    if !configured && !is_generated_version_file(&path)? {
+        bail!(
+            "refusing to overwrite {} as it was not generated by rye, set tool.rye.version.file \
+             to a file that only holds the version",
+            path.display()
+        );
+    }
+    let version = compute_vcs_version(project)?;
+    fs::write(&path, render_version_file(&path, &version))
+        .path_context(&path, "failed to write version file")?;
+    Ok((version, path))
+}
+
+/// Checks if a version file can be overwritten without losing anything: it
+/// does not exist yet, was written by rye or only holds a version.
+fn is_generated_version_file(path: &Path) -> Result<bool, Error> {
+    if !path.exists() {
+        return Ok(true);
+    }
+    let contents = fs::read_to_string(path).path_context(path, "failed to read version file")?;
+    Ok(contents.starts_with(GENERATED_HEADER)
+        || (path.extension().map_or(true, |x| x != "py")
+            && Version::from_str(contents.trim()).is_ok()))
+}
+
+/// Renders a version file.  Python files define `__version__` which all
+/// common build backends can read, other files only hold the version.
+fn render_version_file(path: &Path, version: &Version) -> String {
+    if path.extension().map_or(false, |x| x == "py") {
+        format!(
+            "{} (`rye version sync-from-vcs`), do not edit\n\
+             __version__ = \"{}\"\n\
+             version = __version__\n",
+            GENERATED_HEADER, version
+        )
+    } else {
+        format!("{}\n", version)
+    }
+}
+
+fn describe(root: &Path, version_file: Option<&Path>) -> Result<Describe, Error> {
+    let output = git(
+        root,
+        &[
+            "describe", "--tags", "--long", "--always", "--match", "v[0-9]*", "--match", "[0-9]*",
+        ],
+    )?;
+    let mut rv = parse_describe(&output);
+
+    // `--dirty` would also count the version file which changes with every sync
+    let mut pathspecs = vec![":/".to_string()];
+    if let Some(rel) = version_file.and_then(|x| x.strip_prefix(root).ok()) {
+        pathspecs.push(format!(":(exclude){}", rel.display()));
+    }
+    let mut args = vec!["status", "--porcelain", "--untracked-files=no", "--"];
+    args.extend(pathspecs.iter().map(|x| x.as_str()));
+    rv.dirty = !git(root, &args)?.is_empty();
+
+    if rv.tag.is_none() {
+        let count = git(root, &["rev-list", "--count", "HEAD"])?;
+        rv.distance = count
+            .parse()
+            .with_context(|| format!("unexpected commit count '{}'", count))?;
+    }
+    Ok(rv)
+}
+
+fn git(root: &Path, args: &[&str]) -> Result<String, Error> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .stdin(Stdio::null())
+        .output()
+        .context("unable to run git, is it installed?")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Parses the output of `git describe --tags --long --always` (optionally with
+/// `--dirty`).
+///
+/// Without a tag only the commit hash is printed, the distance is then
+/// unknown and left at zero.
+fn parse_describe(output: &str) -> Describe {
+    let (rest, dirty) = match output.strip_suffix("-dirty") {
+        Some(rest) => (rest, true),
+        None => (output, false),
+    };
+    let mut parts = rest.rsplitn(3, '-');
+    if let (Some(node), Some(distance), Some(tag)) = (parts.next(), parts.next(), parts.next()) {
+        if let (Some(node), Ok(distance)) = (node.strip_prefix('g'), distance.parse()) {
+            return Describe {
+                tag: Some(tag.to_string()),
+                distance,
+                node: node.to_string(),
+                dirty,
+            };
+        }
+    }
+    Describe {
+        tag: None,
+        distance: 0,
+        node: rest.to_string(),
+        dirty,
+    }
+}
+
+fn format_version(describe: &Describe, date: &str) -> Result<Version, Error> {
+    let tag = match describe.tag {
+        Some(ref tag) => Some(
+            Version::from_str(tag.strip_prefix('v').unwrap_or(tag))
+                .map_err(|msg| anyhow!("tag {} is not a valid version: {}", tag, msg))?,
+        ),
+        None => None,
+    };
+    let public = match tag {
+        Some(tag) if describe.distance == 0 && !describe.dirty => return Ok(tag),
+        Some(tag) => next_dev_version(tag, describe.distance),
+        None => format!("0.1.dev{}", describe.distance),
+    };
+    let local = match (describe.distance, describe.dirty) {
+        (0, _) => format!("d{}", date),
+        (_, false) => format!("g{}", describe.node),
+        (_, true) => format!("g{}.d{}", describe.node, date),
+    };
+    Version::from_str(&format!("{}+{}", public, local))
+        .map_err(|msg| anyhow!("invalid version: {}", msg))
+}
+
+/// Guesses the development release that follows a tag.  The last number of the
+/// tag is incremented (`1.2.3` becomes `1.2.4.devN`, `2.0rc1` becomes
+/// `2.0rc2.devN`), tagged development releases are counted up instead.
+fn next_dev_version(mut tag: Version, distance: u64) -> String {
+    if let Some(dev) = tag.dev {
+        tag.dev = Some(dev + distance);
+        return public_version(&tag);
+    }
+    let version = public_version(&tag);
+    let head = version.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = version[head.len()..].parse::<u64>().map_or(0, |x| x + 1);
+    format!("{}{}.dev{}", head, number, distance)
+}
+
+/// Formats a version without its local part.
+fn public_version(version: &Version) -> String {
+    let version = version.to_string();
+    match version.split_once('+') {
+        Some((public, _)) => public.to_string(),
+        None => version,
+    }
+}
+
+#[test]
+fn test_parse_describe() {
+    assert_eq!(
+        parse_describe("v1.2.3-4-gabc1234-dirty"),
+        Describe {
+            tag: Some("v1.2.3".into()),
+            distance: 4,
+            node: "abc1234".into(),
+            dirty: true,
+        }
+    );
+    assert_eq!(
+        parse_describe("1.0-rc-0-gabc1234"),
+        Describe {
+            tag: Some("1.0-rc".into()),
+            distance: 0,
+            node: "abc1234".into(),
+            dirty: false,
+        }
+    );
+    assert_eq!(
+        parse_describe("abc1234-dirty"),
+        Describe {
+            tag: None,
+            distance: 0,
+            node: "abc1234".into(),
+            dirty: true,
+        }
+    );
+}
+
+#[test]
+fn test_format_version() {
+    let describe = |tag: Option<&str>, distance, dirty| Describe {
+        tag: tag.map(|x| x.to_string()),
+        distance,
+        node: "abc1234".into(),
+        dirty,
+    };
+    let format = |describe| format_version(&describe, "20240101").unwrap().to_string();
+
+    assert_eq!(format(describe(Some("v1.2.3"), 0, false)), "1.2.3");
+    assert_eq!(
+        format(describe(Some("v1.2.3"), 0, true)),
+        "1.2.4.dev0+d20240101"
+    );
+    assert_eq!(
+        format(describe(Some("v1.2.3"), 3, false)),
+        "1.2.4.dev3+gabc1234"
+    );
+    assert_eq!(
+        format(describe(Some("2.0rc1"), 3, true)),
+        "2.0rc2.dev3+gabc1234.d20240101"
+    );
+    assert_eq!(
+        format(describe(Some("1.0.dev2"), 1, false)),
+        "1.0.dev3+gabc1234"
+    );
+    assert_eq!(format(describe(None, 7, false)), "0.1.dev7+gabc1234");
+    assert!(format_version(&describe(Some("release-1"), 1, false), "20240101").is_err());
+}
+
+#[test]
+fn test_is_generated_version_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("_version.py");
+    assert!(is_generated_version_file(&path).unwrap());
+    fs::write(
+        &path,
+        render_version_file(&path, &Version::from_str("1.0").unwrap()),
+    )
+    .unwrap();
+    assert!(is_generated_version_file(&path).unwrap());
+    fs::write(&path, "__version__ = \"1.0\"\n\ndef main():\n    pass\n").unwrap();
+    assert!(!is_generated_version_file(&path).unwrap());
+
+    let path = dir.path().join("VERSION");
+    fs::write(&path, "1.0\n").unwrap();
+    assert!(is_generated_version_file(&path).unwrap());
+    fs::write(&path, "version: 1.0\n").unwrap();
+    assert!(!is_generated_version_file(&path).unwrap());
+}
@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Error};
+use pep508_rs::Requirement;
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::bootstrap::download_url;
+use crate::lock::parse_locked_versions;
+use crate::pyproject::{normalize_package_name, ExpandedSources};
+use crate::utils::{CommandOutput, IoPathContext};
+use crate::yanked::{filename_version, find_project_page};
+
+/// The folder (relative to the workspace) that holds the vendored distributions.
+pub const VENDOR_DIR: &str = "vendor";
+
+/// Records the files in the vendor folder that were downloaded by rye.
+const VENDOR_MANIFEST: &str = ".rye-vendor.json";
+
+/// What `vendor_lockfiles` did.
+#[derive(Debug, Default)]
+pub struct VendorSummary {
+    pub files: usize,
+    pub downloaded: usize,
+    pub removed: usize,
+}
+
+/// Downloads all distributions pinned in the lockfiles into `dir`.
+///
+/// If a lockfile records hashes, only the distributions with those hashes are
+/// downloaded, otherwise all distributions of the pinned version.  Every
+/// download is verified against the sha256 hash published by the index.
+/// Files that were vendored by an earlier run but no longer belong to any of
+/// the lockfiles are removed, files placed in `dir` by hand are left alone.
+pub fn vendor_lockfiles(
+    lockfiles: &[&Path],
+    sources: &ExpandedSources,
+    dir: &Path,
+    output: CommandOutput,
+) -> Result<VendorSummary, Error> {
+    let mut locked = Vec::new();
+    let mut locked_hashes = HashMap::<String, HashSet<String>>::new();
+    for lockfile in lockfiles {
+        let contents =
+            fs::read_to_string(lockfile).path_context(lockfile, "failed to read lockfile")?;
+        for (name, version) in parse_locked_versions(&contents) {
+            // local and url dependencies are not served by an index
+            if let Some(version) = version {
+                locked.push((name, version));
+            }
+        }
+        for (name, hashes) in parse_locked_sha256(&contents) {
+            locked_hashes.entry(name).or_default().extend(hashes);
+        }
+    }
+    locked.sort();
+    locked.dedup();
+
+    fs::create_dir_all(dir).path_context(dir, "failed to create vendor directory")?;
+    let mut handle = curl::easy::Easy::new();
+    let mut summary = VendorSummary::default();
+    let mut wanted = HashSet::new();
+    for (name, version) in &locked {
+        let page = find_project_page(&mut handle, sources, name)?.ok_or_else(|| {
+            anyhow!(
+                "{}=={} was not found on any index that serves the JSON simple API",
+                name,
+                version
+            )
+        })?;
+
+        let hashes = locked_hashes.get(name);
+        let mut found = false;
+        for file in &page.files {
+            if filename_version(&file.filename).as_ref() != Some(version) {
+                continue;
+            }
+            let Some(sha256) = file.hashes.get("sha256") else {
+                continue;
+            };
+            if hashes.map_or(false, |x| !x.is_empty() && !x.contains(sha256)) {
+                continue;
+            }
+            found = true;
+            summary.files += 1;
+            wanted.insert(file.filename.clone());
+
+            let target = dir.join(&file.filename);
+            if target.is_file()
+                && fs::read(&target).map_or(false, |x| hex::encode(Sha256::digest(x)) == *sha256)
+            {
+                continue;
+            }
+            let url = match page.url {
+                Some(ref base) => base.join(&file.url)?,
+                None => Url::parse(&file.url)?,
+            };
+            echo!(if verbose output, "Downloading {}", file.filename);
+            let contents = download_url(url.as_str(), output)
+                .with_context(|| format!("failed to download {}", file.filename))?;
+            if hex::encode(Sha256::digest(&contents)) != *sha256 {
+                bail!("hash mismatch for {}", file.filename);
+            }
+            fs::write(&target, contents).path_context(&target, "failed to write distribution")?;
+            summary.downloaded += 1;
+        }
+        if !found {
+            bail!(
+                "no distributions of {}=={} found on the index",
+                name,
+                version
+            );
+        }
+    }
+
+    let manifest = dir.join(VENDOR_MANIFEST);
+    let previous: Vec<String> = fs::read(&manifest)
+        .ok()
+        .and_then(|x| serde_json::from_slice(&x).ok())
+        .unwrap_or_default();
+    for name in previous {
+        let path = dir.join(&name);
+        if !wanted.contains(&name) && path.is_file() {
+            fs::remove_file(&path).path_context(&path, "failed to remove file")?;
+            summary.removed += 1;
+        }
+    }
+    let mut wanted = wanted.into_iter().collect::<Vec<_>>();
+    wanted.sort();
+    fs::write(&manifest, serde_json::to_string_pretty(&wanted)?)
+        .path_context(&manifest, "failed to write vendor manifest")?;
+
+    Ok(summary)
+}
+
+/// Collects the sha256 hashes recorded for every package of a lockfile.
+fn parse_locked_sha256(contents: &str) -> HashMap<String, HashSet<String>> {
+    let mut rv = HashMap::<String, HashSet<String>>::new();
+    let mut current = None;
+    for line in contents.lines() {
+        let line = line.trim_end().trim_end_matches('\\').trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut tokens = line.split_whitespace().peekable();
+        if let Some(first) = tokens.peek() {
+            if !first.starts_with('-') {
+                current = line
+                    .split(" --hash=")
+                    .next()
+                    .and_then(|x| x.trim().parse::<Requirement>().ok())
+                    .map(|x| normalize_package_name(&x.name));
+            } else if !first.starts_with("--hash=") {
+                current = None;
+            }
+        }
+        for token in tokens {
+            if let (Some(name), Some(hash)) = (&current, token.strip_prefix("--hash=sha256:")) {
+                rv.entry(name.clone()).or_default().insert(hash.to_string());
+            }
+        }
+    }
+    rv
+}
+
+#[test]
+fn test_parse_locked_sha256() {
+    let hashes = parse_locked_sha256(
+        r#"# generated by rye
+-e file:.
+certifi==2024.2.2 \
+    --hash=sha256:aaa \
+    --hash=sha256:bbb
+Idna==3.4 --hash=sha256:ccc
+six==1.16.0
+"#,
+    );
+    assert_eq!(
+        hashes["certifi"],
+        HashSet::from(["aaa".to_string(), "bbb".to_string()])
+    );
+    assert_eq!(hashes["idna"], HashSet::from(["ccc".to_string()]));
+    assert!(!hashes.contains_key("six"));
+}
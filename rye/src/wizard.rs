@@ -0,0 +1,354 @@
+use std::collections::BTreeMap;
+use std::env::consts::{ARCH, OS};
+
+use anyhow::{bail, Error};
+use license::License;
+use pep508_rs::Requirement;
+
+use crate::config::Config;
+use crate::platform::list_known_toolchains;
+use crate::pyproject::{normalize_package_name, BuildSystem, ExpandedSources};
+use crate::sources::py::{iter_downloadable, PythonVersion};
+use crate::utils::tui_theme;
+use crate::yanked::find_project_names;
+
+/// Licenses that are offered when searching, any other SPDX identifier can
+/// be typed in directly.
+const COMMON_LICENSES: &[&str] = &[
+    "0BSD",
+    "AGPL-3.0-only",
+    "AGPL-3.0-or-later",
+    "Apache-2.0",
+    "Artistic-2.0",
+    "BSD-2-Clause",
+    "BSD-3-Clause",
+    "BSL-1.0",
+    "CC0-1.0",
+    "EPL-2.0",
+    "EUPL-1.2",
+    "GPL-2.0-only",
+    "GPL-2.0-or-later",
+    "GPL-3.0-only",
+    "GPL-3.0-or-later",
+    "ISC",
+    "LGPL-2.1-only",
+    "LGPL-2.1-or-later",
+    "LGPL-3.0-only",
+    "LGPL-3.0-or-later",
+    "MIT",
+    "MPL-2.0",
+    "PSF-2.0",
+    "Unlicense",
+    "Zlib",
+];
+
+/// The build systems in the order they are offered.
+const BUILD_SYSTEMS: &[(BuildSystem, &str)] = &[
+    (BuildSystem::Hatchling, "hatchling"),
+    (BuildSystem::Setuptools, "setuptools"),
+    (BuildSystem::Flit, "flit"),
+    (BuildSystem::Pdm, "pdm"),
+    (BuildSystem::Maturin, "maturin (Rust extension module)"),
+];
+
+/// How many search results are offered at most.
+const MAX_MATCHES: usize = 10;
+
+/// The answers given in the `rye init --interactive` wizard.
+#[derive(Debug)]
+pub struct InitAnswers {
+    pub name: String,
+    pub is_virtual: bool,
+    pub script: bool,
+    pub build_system: BuildSystem,
+    pub license: Option<String>,
+    pub python: PythonVersion,
+    pub dependencies: Vec<String>,
+}
+
+/// Walks through the choices of `rye init`.
+///
+/// The given values are the defaults of the flag based flow.
+pub fn run_init_wizard(
+    name: &str,
+    build_system: BuildSystem,
+    license: Option<String>,
+) -> Result<InitAnswers, Error> {
+    let name = dialoguer::Input::<String>::with_theme(tui_theme())
+        .with_prompt("Project name")
+        .default(name.to_string())
+        .validate_with(|x: &String| {
+            if slug::slugify(x).is_empty() {
+                Err("the name must contain letters or digits")
+            } else {
+                Ok(())
+            }
+        })
+        .interact_text()?;
+
+    let kind = dialoguer::Select::with_theme(tui_theme())
+        .with_prompt("What kind of project is this?")
+        .item("Library package")
+        .item("Application package with a script")
+        .item("Virtual project (dependencies only, not installed itself)")
+        .default(0)
+        .interact()?;
+    let is_virtual = kind == 2;
+    let script = kind == 1;
+
+    let build_system = if is_virtual {
+        build_system
+    } else {
+        // maturin has no template for scripts
+        let choices = BUILD_SYSTEMS
+            .iter()
+            .filter(|(x, _)| !(script && *x == BuildSystem::Maturin))
+            .collect::<Vec<_>>();
+        let idx = dialoguer::Select::with_theme(tui_theme())
+            .with_prompt("Build backend")
+            .items(&choices.iter().map(|x| x.1).collect::<Vec<_>>())
+            .default(
+                choices
+                    .iter()
+                    .position(|x| x.0 == build_system)
+                    .unwrap_or(0),
+            )
+            .interact()?;
+        choices[idx].0
+    };
+
+    let license = prompt_license(license)?;
+    let python = prompt_python()?;
+    let dependencies = prompt_dependencies()?;
+
+    Ok(InitAnswers {
+        name,
+        is_virtual,
+        script,
+        build_system,
+        license,
+        python,
+        dependencies,
+    })
+}
+
+fn prompt_license(default: Option<String>) -> Result<Option<String>, Error> {
+    loop {
+        let query = dialoguer::Input::<String>::with_theme(tui_theme())
+            .with_prompt("License (SPDX identifier or search, empty for none)")
+            .default(default.clone().unwrap_or_default())
+            .allow_empty(true)
+            .interact_text()?;
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(None);
+        }
+        if query.parse::<&dyn License>().is_ok() {
+            return Ok(Some(query.to_string()));
+        }
+        let matches = fuzzy_matches(query, COMMON_LICENSES.iter().copied(), MAX_MATCHES);
+        if matches.is_empty() {
+            elog!("No license matches '{}'", query);
+            continue;
+        }
+        if let Some(license) = select_match("Matching licenses", &matches)? {
+            return Ok(Some(license.to_string()));
+        }
+    }
+}
+
+fn prompt_python() -> Result<PythonVersion, Error> {
+    // offer the latest patch release of every minor version, newest first
+    let mut latest = BTreeMap::new();
+    let installed = list_known_toolchains()?
+        .into_iter()
+        .map(|x| x.0)
+        .filter(|x| x.name == "cpython")
+        .collect::<Vec<_>>();
+    for version in installed
+        .iter()
+        .cloned()
+        .chain(iter_downloadable(OS, ARCH).filter(|x| x.name == "cpython" && x.suffix.is_none()))
+    {
+        let entry = latest
+            .entry((version.major, version.minor))
+            .or_insert_with(|| version.clone());
+        if version > *entry {
+            *entry = version;
+        }
+    }
+    let versions = latest.into_values().rev().collect::<Vec<_>>();
+    if versions.is_empty() {
+        bail!("no Python toolchains are available on this platform");
+    }
+
+    let default = Config::current().default_toolchain().ok();
+    let idx = dialoguer::Select::with_theme(tui_theme())
+        .with_prompt("Python version")
+        .items(
+            &versions
+                .iter()
+                .map(|x| {
+                    let note = if installed.contains(x) {
+                        "installed"
+                    } else {
+                        "download"
+                    };
+                    format!("{}.{}.{} ({})", x.major, x.minor, x.patch, note)
+                })
+                .collect::<Vec<_>>(),
+        )
+        .default(
+            default
+                .and_then(|req| {
+                    versions
+                        .iter()
+                        .position(|x| Some(x.minor) == req.minor && x.major == req.major)
+                })
+                .unwrap_or(0),
+        )
+        .interact()?;
+    Ok(versions[idx].clone())
+}
+
+fn prompt_dependencies() -> Result<Vec<String>, Error> {
+    let mut rv = Vec::new();
+    let mut names = None;
+    loop {
+        let query = dialoguer::Input::<String>::with_theme(tui_theme())
+            .with_prompt("Add a dependency (name, requirement or search, empty to finish)")
+            .allow_empty(true)
+            .interact_text()?;
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(rv);
+        }
+        let Ok(req) = query.parse::<Requirement>() else {
+            elog!("'{}' is not a valid requirement", query);
+            continue;
+        };
+
+        // requirements with a version or url are taken as they are
+        if req.version_or_url.is_some() {
+            rv.push(req.to_string());
+            continue;
+        }
+
+        let names = names.get_or_insert_with(load_project_names);
+        let Some(names) = names else {
+            rv.push(req.to_string());
+            continue;
+        };
+        let matches = fuzzy_matches(query, names.iter().map(|x| x.as_str()), MAX_MATCHES);
+        match matches.first() {
+            None => elog!("No package on the index matches '{}'", query),
+            Some(&first) if normalize_package_name(first) == normalize_package_name(query) => {
+                rv.push(req.to_string());
+            }
+            Some(_) => {
+                if let Some(name) = select_match("Matching packages", &matches)? {
+                    rv.push(name.to_string());
+                }
+            }
+        }
+    }
+}
+
+/// Fetches all project names from the configured indexes for searching.
+fn load_project_names() -> Option<Vec<String>> {
+    elog!("Fetching the list of packages from the index ...");
+    let sources = match Config::current()
+        .sources()
+        .and_then(|x| ExpandedSources::from_sources(&x))
+    {
+        Ok(sources) => sources,
+        Err(err) => {
+            warn!(category = Config, "cannot search packages: {}", err);
+            return None;
+        }
+    };
+    match find_project_names(&mut curl::easy::Easy::new(), &sources) {
+        Ok(Some(names)) => Some(names),
+        Ok(None) => {
+            warn!(
+                category = Environment,
+                "cannot search packages: no index lists its packages"
+            );
+            None
+        }
+        Err(err) => {
+            warn!(category = Environment, "cannot search packages: {:#}", err);
+            None
+        }
+    }
+}
+
+/// Offers the search results, `None` means the user wants to search again.
+fn select_match<'a>(prompt: &str, matches: &[&'a str]) -> Result<Option<&'a str>, Error> {
+    let idx = dialoguer::Select::with_theme(tui_theme())
+        .with_prompt(prompt)
+        .items(matches)
+        .item("(search again)")
+        .default(0)
+        .interact()?;
+    Ok(matches.get(idx).copied())
+}
+
+/// Ranks the candidates that match a search query.
+///
+/// Exact matches come first, then prefix matches, substring matches and
+/// finally candidates that contain the characters of the query in order.
+/// Shorter candidates are preferred within each group.
+fn fuzzy_matches<'a>(
+    query: &str,
+    candidates: impl Iterator<Item = &'a str>,
+    limit: usize,
+) -> Vec<&'a str> {
+    let query = normalize_package_name(query);
+    let mut rv = candidates
+        .filter_map(|candidate| {
+            let normalized = normalize_package_name(candidate);
+            let rank = if normalized == query {
+                0
+            } else if normalized.starts_with(&query) {
+                1
+            } else if normalized.contains(&query) {
+                2
+            } else if is_subsequence(&query, &normalized) {
+                3
+            } else {
+                return None;
+            };
+            Some((rank, candidate.len(), candidate))
+        })
+        .collect::<Vec<_>>();
+    rv.sort();
+    rv.into_iter().take(limit).map(|x| x.2).collect()
+}
+
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|x| x == c))
+}
+
+#[test]
+fn test_fuzzy_matches() {
+    let candidates = ["flask-cors", "Flask", "pyflakes", "fastapi", "django"];
+    assert_eq!(
+        fuzzy_matches("flask", candidates.iter().copied(), 10),
+        vec!["Flask", "flask-cors"]
+    );
+    assert_eq!(
+        fuzzy_matches("fla", candidates.iter().copied(), 10),
+        vec!["Flask", "flask-cors", "pyflakes"]
+    );
+    assert_eq!(
+        fuzzy_matches("fapi", candidates.iter().copied(), 10),
+        vec!["fastapi"]
+    );
+    assert_eq!(
+        fuzzy_matches("bsd3", COMMON_LICENSES.iter().copied(), 1),
+        vec!["BSD-3-Clause"]
+    );
+    assert!(fuzzy_matches("rails", candidates.iter().copied(), 10).is_empty());
+}
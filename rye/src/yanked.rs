@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+use anyhow::{bail, Context, Error};
+use pep440_rs::Version;
+use serde::Deserialize;
+use url::Url;
+
+use crate::config::Config;
+use crate::lock::parse_locked_versions;
+use crate::pyproject::{ClientCert, ExpandedSources};
+use crate::utils::{set_curl_proxy, set_curl_tls, CommandOutput};
+
+/// The content type of the JSON based simple repository API (PEP 691).
+const SIMPLE_JSON_CONTENT_TYPE: &str = "application/vnd.pypi.simple.v1+json";
+
+/// The timeout for index lookups if `network.timeout` is not configured.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A locked version that was yanked from the package index.
+#[derive(Debug, Clone, PartialEq)]
+pub struct YankedVersion {
+    pub name: String,
+    pub version: Version,
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for YankedVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}=={}", self.name, self.version)
+    }
+}
+
+/// A project page of the JSON simple repository API.
+#[derive(Deserialize, Debug)]
+pub struct ProjectPage {
+    /// The URL the page was fetched from, file URLs can be relative to it.
+    #[serde(skip)]
+    pub url: Option<Url>,
+    pub files: Vec<ProjectFile>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ProjectFile {
+    pub filename: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub hashes: HashMap<String, String>,
+    #[serde(default, rename = "requires-python")]
+    pub requires_python: Option<String>,
+    #[serde(default)]
+    yanked: Option<Yanked>,
+}
+
+/// PEP 592: a file is yanked if this is `true` or a string with the reason.
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+enum Yanked {
+    Flag(bool),
+    Reason(String),
+}
+
+/// Finds the yanked versions in the contents of a lockfile.
+///
+/// The versions are looked up on the package indexes that serve the JSON
+/// simple repository API.  Packages that cannot be looked up (for instance
+/// because the index is unreachable or only serves HTML) are skipped.
+pub fn find_yanked(
+    contents: &str,
+    sources: &ExpandedSources,
+    output: CommandOutput,
+) -> Vec<YankedVersion> {
+    let mut locked = parse_locked_versions(contents)
+        .into_iter()
+        .filter_map(|(name, version)| Some((name, version?)))
+        .collect::<Vec<_>>();
+    locked.sort();
+
+    let mut handle = curl::easy::Easy::new();
+    let mut rv = Vec::new();
+    for (name, version) in locked {
+        for (index_url, _) in &sources.index_urls {
+            match fetch_project_page(&mut handle, index_url, &sources.client_certs, &name) {
+                Ok(Some(page)) => {
+                    if let Some(reason) = yanked_reason(&page, &version) {
+                        rv.push(YankedVersion {
+                            name: name.clone(),
+                            version: version.clone(),
+                            reason,
+                        });
+                    }
+                    break;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    if output == CommandOutput::Verbose {
+                        echo!("Unable to check {} for yanked versions: {}", name, err);
+                    }
+                }
+            }
+        }
+    }
+    rv
+}
+
+/// Checks if all files of a version on a project page are yanked.
+///
+/// Returns `Some` with the optional yank reason if the version is yanked.
+fn yanked_reason(page: &ProjectPage, version: &Version) -> Option<Option<String>> {
+    let mut reason = None;
+    let mut found = false;
+    for file in &page.files {
+        if filename_version(&file.filename).as_ref() != Some(version) {
+            continue;
+        }
+        found = true;
+        match file.yanked {
+            None | Some(Yanked::Flag(false)) => return None,
+            Some(Yanked::Flag(true)) => {}
+            Some(Yanked::Reason(ref value)) => {
+                if reason.is_none() && !value.is_empty() {
+                    reason = Some(value.clone());
+                }
+            }
+        }
+    }
+    found.then_some(reason)
+}
+
+/// Extracts the version from the filename of a wheel or source distribution.
+pub fn filename_version(filename: &str) -> Option<Version> {
+    let version = if let Some(stem) = filename.strip_suffix(".whl") {
+        stem.split('-').nth(1)?
+    } else {
+        let stem = [".tar.gz", ".zip", ".tar.bz2", ".tgz"]
+            .iter()
+            .find_map(|ext| filename.strip_suffix(ext))?;
+        stem.rsplit_once('-')?.1
+    };
+    Version::from_str(version).ok()
+}
+
+/// Checks if a wheel can be installed on a CPython version.
+///
+/// Only the Python and ABI tags are considered, not the platform tag.
+pub fn wheel_supports_python(filename: &str, major: u8, minor: u8) -> bool {
+    let Some(stem) = filename.strip_suffix(".whl") else {
+        return false;
+    };
+    let mut parts = stem.rsplitn(4, '-');
+    let (Some(_platform), Some(abi), Some(python)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return false;
+    };
+    let stable_abi = abi.split('.').any(|x| x == "abi3");
+    python.split('.').any(|tag| {
+        let (implementation, version) =
+            tag.split_at(tag.find(|c: char| c.is_ascii_digit()).unwrap_or(tag.len()));
+        if version.get(..1).and_then(|x| x.parse::<u8>().ok()) != Some(major) {
+            return false;
+        }
+        let tag_minor = match version.get(1..).filter(|x| !x.is_empty()) {
+            Some(tag_minor) => match tag_minor.parse::<u8>() {
+                Ok(tag_minor) => Some(tag_minor),
+                Err(_) => return false,
+            },
+            None => None,
+        };
+        match (implementation, tag_minor) {
+            ("py", None) => true,
+            ("py", Some(tag_minor)) => tag_minor <= minor,
+            ("cp", Some(tag_minor)) if stable_abi => tag_minor <= minor,
+            // free-threaded builds (`cp313t`) are not compatible with regular ones
+            ("cp", Some(tag_minor)) => {
+                tag_minor == minor
+                    && abi
+                        .split('.')
+                        .any(|x| x == "none" || (x.starts_with(tag) && !x.ends_with('t')))
+            }
+            _ => false,
+        }
+    })
+}
+
+/// Fetches the project page of a package from the first index that knows it.
+pub fn find_project_page(
+    handle: &mut curl::easy::Easy,
+    sources: &ExpandedSources,
+    name: &str,
+) -> Result<Option<ProjectPage>, Error> {
+    for (index_url, _) in &sources.index_urls {
+        if let Some(page) = fetch_project_page(handle, index_url, &sources.client_certs, name)
+            .with_context(|| format!("failed to look up {} on {}", name, index_url))?
+        {
+            return Ok(Some(page));
+        }
+    }
+    Ok(None)
+}
+
+/// Fetches the project page of a package from an index.
+///
+/// Returns `None` if the index does not know the package or does not serve
+/// the JSON simple repository API.
+pub fn fetch_project_page(
+    handle: &mut curl::easy::Easy,
+    index_url: &Url,
+    client_certs: &[ClientCert],
+    name: &str,
+) -> Result<Option<ProjectPage>, Error> {
+    if index_url.scheme() != "https" {
+        return Ok(None);
+    }
+    let mut base = index_url.clone();
+    if !base.path().ends_with('/') {
+        base.set_path(&format!("{}/", base.path()));
+    }
+    let url = base.join(&format!("{}/", name))?;
+    let Some(body) = fetch_simple_json(handle, &url, client_certs)? else {
+        return Ok(None);
+    };
+    let mut page: ProjectPage = serde_json::from_slice(&body)?;
+    page.url = Some(url);
+    Ok(Some(page))
+}
+
+/// The index page of the JSON simple repository API.
+#[derive(Deserialize, Debug)]
+struct IndexPage {
+    projects: Vec<IndexProject>,
+}
+
+#[derive(Deserialize, Debug)]
+struct IndexProject {
+    name: String,
+}
+
+/// Fetches the names of all projects from the first index that lists them.
+///
+/// Indexes that do not serve the JSON simple repository API are skipped.
+pub fn find_project_names(
+    handle: &mut curl::easy::Easy,
+    sources: &ExpandedSources,
+) -> Result<Option<Vec<String>>, Error> {
+    for (index_url, _) in &sources.index_urls {
+        if index_url.scheme() != "https" {
+            continue;
+        }
+        let mut url = index_url.clone();
+        if !url.path().ends_with('/') {
+            url.set_path(&format!("{}/", url.path()));
+        }
+        if let Some(body) = fetch_simple_json(handle, &url, &sources.client_certs)
+            .with_context(|| format!("failed to list projects on {}", index_url))?
+        {
+            let page: IndexPage = serde_json::from_slice(&body)?;
+            return Ok(Some(page.projects.into_iter().map(|x| x.name).collect()));
+        }
+    }
+    Ok(None)
+}
+
+/// Fetches a page of the JSON simple repository API.
+///
+/// Returns `None` if the page does not exist or is only served as HTML.
+fn fetch_simple_json(
+    handle: &mut curl::easy::Easy,
+    url: &Url,
+    client_certs: &[ClientCert],
+) -> Result<Option<Vec<u8>>, Error> {
+    let config = Config::current();
+    let mut headers = curl::easy::List::new();
+    headers.append(&format!("Accept: {}", SIMPLE_JSON_CONTENT_TYPE))?;
+    // the handle is reused across indexes, so drop the certificate of the last one
+    handle.reset();
+    handle.url(url.as_str())?;
+    handle.http_headers(headers)?;
+    handle.follow_location(true)?;
+    set_curl_proxy(handle, CommandOutput::Normal)?;
+    set_curl_tls(handle)?;
+    if let Some(client_cert) = ClientCert::find(client_certs, url.as_str()) {
+        client_cert.configure_curl(handle)?;
+    }
+    handle.timeout(
+        config
+            .network_timeout()
+            .map_or(DEFAULT_TIMEOUT, Duration::from_secs),
+    )?;
+
+    let mut body = Vec::new();
+    {
+        let mut transfer = handle.transfer();
+        transfer.write_function(|data| {
+            body.extend_from_slice(data);
+            Ok(data.len())
+        })?;
+        transfer.perform()?;
+    }
+
+    match handle.response_code()? {
+        200 => {}
+        404 => return Ok(None),
+        code => bail!("index responded with status {}", code),
+    }
+    // indexes that only serve HTML cannot be checked
+    if !handle
+        .content_type()?
+        .map_or(false, |x| x.starts_with(SIMPLE_JSON_CONTENT_TYPE))
+    {
+        return Ok(None);
+    }
+    Ok(Some(body))
+}
+
+#[test]
+fn test_filename_version() {
+    let v = |s: &str| Version::from_str(s).unwrap();
+    assert_eq!(
+        filename_version("requests-2.32.0-py3-none-any.whl"),
+        Some(v("2.32.0"))
+    );
+    assert_eq!(
+        filename_version("zope.interface-6.0.tar.gz"),
+        Some(v("6.0"))
+    );
+    assert_eq!(filename_version("my-package-1.0rc1.zip"), Some(v("1.0rc1")));
+    assert_eq!(filename_version("package-1.0.exe"), None);
+}
+
+#[test]
+fn test_wheel_supports_python() {
+    assert!(wheel_supports_python("foo-1.0-py3-none-any.whl", 3, 13));
+    assert!(wheel_supports_python("foo-1.0-py2.py3-none-any.whl", 3, 13));
+    assert!(!wheel_supports_python("foo-1.0-py2-none-any.whl", 3, 13));
+    assert!(wheel_supports_python("foo-1.0-1-py38-none-any.whl", 3, 13));
+    assert!(!wheel_supports_python("foo-1.0-1-py38-none-any.whl", 3, 7));
+    assert!(wheel_supports_python(
+        "foo-1.0-cp311-cp311-manylinux_2_17_x86_64.whl",
+        3,
+        11
+    ));
+    assert!(!wheel_supports_python(
+        "foo-1.0-cp311-cp311-manylinux_2_17_x86_64.whl",
+        3,
+        13
+    ));
+    assert!(wheel_supports_python(
+        "foo-1.0-cp38-abi3-win_amd64.whl",
+        3,
+        13
+    ));
+    assert!(!wheel_supports_python(
+        "foo-1.0-cp313-cp313t-macosx_11_0_arm64.whl",
+        3,
+        13
+    ));
+    assert!(!wheel_supports_python(
+        "foo-1.0-pp310-pypy310_pp73-any.whl",
+        3,
+        10
+    ));
+    assert!(!wheel_supports_python("foo-1.0.tar.gz", 3, 13));
+}
+
+#[test]
+fn test_yanked_reason() {
+    let page: ProjectPage = serde_json::from_str(
+        r#"{
+            "files": [
+                {"filename": "foo-1.0-py3-none-any.whl"},
+                {"filename": "foo-1.1-py3-none-any.whl", "yanked": "broken"},
+                {"filename": "foo-1.1.tar.gz", "yanked": true},
+                {"filename": "foo-1.2-py3-none-any.whl", "yanked": true},
+                {"filename": "foo-1.3-py3-none-any.whl", "yanked": false}
+            ]
+        }"#,
+    )
+    .unwrap();
+    let v = |s: &str| Version::from_str(s).unwrap();
+    assert_eq!(yanked_reason(&page, &v("1.0")), None);
+    assert_eq!(yanked_reason(&page, &v("1.1")), Some(Some("broken".into())));
+    assert_eq!(yanked_reason(&page, &v("1.2")), Some(None));
+    assert_eq!(yanked_reason(&page, &v("1.3")), None);
+    assert_eq!(yanked_reason(&page, &v("2.0")), None);
+}
@@ -147,6 +147,11 @@ impl Space {
         rv.env("RYE_HOME", self.rye_home().as_os_str());
         rv.env("UV_CACHE_DIR", self.tempdir.path().join("uv-cache"));
         rv.env("__RYE_UV_EXCLUDE_NEWER", EXCLUDE_NEWER);
+        // activated environments of the developer must not leak into tests
+        rv.env_remove("VIRTUAL_ENV");
+        rv.env_remove("CONDA_PREFIX");
+        rv.env_remove("PYTHONHOME");
+        rv.env_remove("RYE_TOOLCHAIN_DIR");
         rv.current_dir(self.project_path());
         rv
     }
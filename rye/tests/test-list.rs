@@ -98,3 +98,37 @@ fn test_list_never_overwrite() {
         venv_marker
     );
 }
+
+#[test]
+fn test_list_tree_package() {
+    let space = Space::new();
+    space.init("my-project");
+
+    space
+        .rye_cmd()
+        .arg("add")
+        .arg("jinja2")
+        .status()
+        .expect("ok");
+
+    rye_cmd_snapshot!(
+        space.rye_cmd().arg("list").arg("--tree").arg("--package").arg("Jinja2"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    jinja2==3.1.2
+    └── markupsafe==2.1.3
+
+    ----- stderr -----
+    "###);
+
+    rye_cmd_snapshot!(
+        space.rye_cmd().arg("list").arg("--tree").arg("--package").arg("flask"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: package 'flask' is not in [TEMP_PATH]/project/requirements-dev.lock
+    "###);
+}
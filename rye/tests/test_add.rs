@@ -267,3 +267,94 @@ fn test_add_dev() {
     "###
     );
 }
+
+#[test]
+fn test_remove_dry_run() {
+    let space = Space::new();
+    space.init("my-project");
+    let status = space
+        .rye_cmd()
+        .arg("add")
+        .arg("flask==3.0.0")
+        .arg("click==8.1.7")
+        .arg("--no-sync")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let status = space.rye_cmd().arg("lock").status().unwrap();
+    assert!(status.success());
+    let lock = space.read_string("requirements.lock");
+
+    let output = space
+        .rye_cmd()
+        .arg("remove")
+        .arg("flask")
+        .arg("--dry-run")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Would remove flask==3.0.0"));
+    assert!(stdout.contains("direct:\n    - flask==3.0.0\n"));
+    assert!(stdout.contains("    - werkzeug==3.0.1\n"));
+    assert!(stdout.contains("    - click==8.1.7 (by my-project)\n"));
+
+    // nothing was touched
+    assert!(space
+        .read_string("pyproject.toml")
+        .contains("\"flask==3.0.0\""));
+    assert_eq!(space.read_string("requirements.lock"), lock);
+}
+
+#[test]
+fn test_add_json_output() {
+    let space = Space::new();
+    space.init("my-project");
+    let output = space
+        .rye_cmd()
+        .arg("add")
+        .arg("colorama>=0.4")
+        .arg("--excluded")
+        .arg("--no-sync")
+        .arg("--format")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let summary: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(summary["autosync"], false);
+    assert_eq!(summary["changes"][0]["name"], "colorama");
+    assert_eq!(summary["changes"][0]["kind"], "excluded");
+    assert_eq!(summary["changes"][0]["old"], serde_json::Value::Null);
+    assert_eq!(summary["changes"][0]["new"], ">=0.4");
+}
+
+#[test]
+fn test_add_passes_binary_policy_to_uv() {
+    let space = Space::new();
+    space.init("my-project");
+
+    // uv rejects invalid package names in the binary flags which makes it
+    // visible that the policy reached the resolver.
+    for key in ["no-binary", "only-binary"] {
+        space.edit_toml("pyproject.toml", |doc| {
+            doc["tool"]["rye"]["no-binary"] = value(toml_edit::Array::new());
+            doc["tool"]["rye"]["only-binary"] = value(toml_edit::Array::new());
+            let mut packages = toml_edit::Array::new();
+            packages.push("not a package");
+            doc["tool"]["rye"][key] = value(packages);
+        });
+        let output = space
+            .rye_cmd()
+            .arg("add")
+            .arg("anyio")
+            .arg("--no-sync")
+            .output()
+            .unwrap();
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(!output.status.success());
+        assert!(stderr.contains("Failed to run uv compile"), "{}", stderr);
+        assert!(stderr.contains(&format!("--{}", key)), "{}", stderr);
+    }
+    assert!(!space.read_string("pyproject.toml").contains("anyio"));
+}
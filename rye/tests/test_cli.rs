@@ -1,6 +1,7 @@
 use std::fs;
 
-use toml_edit::value;
+use insta::Settings;
+use toml_edit::{value, Array};
 
 use crate::common::{rye_cmd_snapshot, Space};
 
@@ -50,3 +51,481 @@ fn test_dotenv() {
     ----- stderr -----
     "###);
 }
+
+#[test]
+fn test_dotenv_layering() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["scripts"]["hello"]["cmd"] = value(
+            "python -c \"import os; print(os.environ['A'], os.environ['B'], os.environ['C'])\"",
+        );
+        let mut env_files = toml_edit::Array::new();
+        env_files.push(".script1.env");
+        env_files.push(".script2.env");
+        doc["tool"]["rye"]["scripts"]["hello"]["env-file"] = value(env_files);
+    });
+    fs::write(space.project_path().join(".env1"), "A=1\nB=1").unwrap();
+    fs::write(space.project_path().join(".env2"), "B=2").unwrap();
+    fs::write(space.project_path().join(".script1.env"), "C=1").unwrap();
+    fs::write(space.project_path().join(".script2.env"), "C=2").unwrap();
+    space
+        .rye_cmd()
+        .arg("sync")
+        .arg("-q")
+        .status()
+        .expect("sync successful");
+
+    // later files override earlier ones, the environment wins
+    rye_cmd_snapshot!(space.rye_cmd()
+        .env("A", "outer")
+        .arg("--env-file=.env1")
+        .arg("--env-file=.env2")
+        .arg("run")
+        .arg("hello"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    outer 2 2
+
+    ----- stderr -----
+    "###);
+
+    // unless the env files are told to override it
+    rye_cmd_snapshot!(space.rye_cmd()
+        .env("A", "outer")
+        .arg("--env-file=.env1")
+        .arg("--env-file=.env2")
+        .arg("--env-file-override")
+        .arg("run")
+        .arg("hello"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    1 2 2
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_load_dotenv() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["scripts"]["hello"]["cmd"] = value(
+            "python -c \"import os; print(os.environ.get('A'), os.environ.get('B'), os.environ.get('C'))\"",
+        );
+        doc["tool"]["rye"]["scripts"]["hello"]["env"]["C"] = value("script");
+    });
+    fs::write(space.project_path().join(".env"), "A=1\nB=1\nC=1").unwrap();
+    fs::write(space.project_path().join(".env.local"), "B=2").unwrap();
+    space
+        .rye_cmd()
+        .arg("sync")
+        .arg("-q")
+        .status()
+        .expect("sync successful");
+
+    // not loaded unless enabled
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("hello"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    None None script
+
+    ----- stderr -----
+    "###);
+
+    // .env.local overrides .env, the environment and the script win
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["load-dotenv"] = value(true);
+    });
+    rye_cmd_snapshot!(space.rye_cmd()
+        .env("A", "outer")
+        .arg("run")
+        .arg("hello"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    outer 2 script
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_foreign_active_venv() {
+    let space = Space::new();
+    space.init("my-project");
+
+    let warning = "warning[environment]: VIRTUAL_ENV is set to";
+    let output = space
+        .rye_cmd()
+        .env("VIRTUAL_ENV", space.project_path().join("other-venv"))
+        .arg("run")
+        .arg("--list")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&format!(
+        "{} {} which is not the virtualenv of this project",
+        warning,
+        space.project_path().join("other-venv").display()
+    )));
+
+    // the virtualenv of the project itself is fine
+    let output = space
+        .rye_cmd()
+        .env("VIRTUAL_ENV", space.project_path().join(".venv"))
+        .arg("run")
+        .arg("--list")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains(warning));
+
+    // commands that do not use the virtualenv do not care
+    rye_cmd_snapshot!(space.rye_cmd()
+        .env("VIRTUAL_ENV", space.project_path().join("other-venv"))
+        .arg("version"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    0.1.0
+
+    ----- stderr -----
+    "###);
+
+    // neither does the conda base environment
+    let output = space
+        .rye_cmd()
+        .env("CONDA_PREFIX", space.project_path().join("conda"))
+        .env("CONDA_DEFAULT_ENV", "base")
+        .arg("run")
+        .arg("--list")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("CONDA_PREFIX"));
+}
+
+#[test]
+fn test_warning_policy_args() {
+    let space = Space::new();
+    space.init("my-project");
+
+    // --deny and --allow are accepted after the subcommand
+    rye_cmd_snapshot!(space.rye_cmd()
+        .arg("version")
+        .arg("--deny")
+        .arg("warnings")
+        .arg("--allow")
+        .arg("config"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    0.1.0
+
+    ----- stderr -----
+    "###);
+
+    // the policy comes from the pyproject.toml given to the subcommand
+    space.write(
+        "other/pyproject.toml",
+        r#"[project]
+name = "other"
+version = "1.0.0"
+
+[tool.rye.lint-warnings]
+deny = ["nonsense"]
+"#,
+    );
+    // an invalid policy is only warned about
+    let output = space
+        .rye_cmd()
+        .arg("show")
+        .arg("--pyproject")
+        .arg("other/pyproject.toml")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "warning[config]: invalid value in tool.rye.lint-warnings.deny: invalid variant: nonsense"
+    ));
+
+    // and never stops commands that do not need the project
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deny = toml_edit::Array::new();
+        deny.push("nonsense");
+        doc["tool"]["rye"]["lint-warnings"]["deny"] = value(deny);
+    });
+    let output = space.rye_cmd().arg("--version").output().unwrap();
+    assert!(output.status.success());
+    assert!(output.stderr.is_empty());
+    let output = space
+        .rye_cmd()
+        .arg("toolchain")
+        .arg("list")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+}
+
+#[test]
+fn test_pre_commit_requires_dev_dependency() {
+    let space = Space::new();
+    space.init("my-project");
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("pre-commit").arg("run").arg("--all-files"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: pre-commit is not a dev dependency. Run `rye add --dev pre-commit`
+    "###);
+}
+
+#[test]
+fn test_build_gate_invalid_check() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut checks = toml_edit::Array::new();
+        checks.push("lint");
+        checks.push("typecheck");
+        doc["tool"]["rye"]["gates"]["build"] = value(checks);
+    });
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("build"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: invalid value in tool.rye.gates.build (expected "fmt", "lint" or "test")
+    "###);
+}
+
+#[test]
+fn test_build_cache() {
+    let space = Space::new();
+    space.init("my-project");
+    let build = || {
+        let output = space
+            .rye_cmd()
+            .arg("build")
+            .arg("--wheel")
+            .arg("--cache-dir")
+            .arg(space.project_path().join("cache"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let stdout = build();
+    assert!(
+        stdout.contains("build cache: 0 hit(s), 1 miss(es)"),
+        "{}",
+        stdout
+    );
+    let stdout = build();
+    assert!(
+        stdout.contains("using cached build of my-project"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("build cache: 1 hit(s), 0 miss(es)"),
+        "{}",
+        stdout
+    );
+
+    // editing a source file invalidates the cached build
+    space.write("src/my_project/__init__.py", "VALUE = 1\n");
+    let stdout = build();
+    assert!(!stdout.contains("using cached build"), "{}", stdout);
+    assert!(
+        stdout.contains("build cache: 0 hit(s), 1 miss(es)"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn test_build_watch_requires_maturin() {
+    let space = Space::new();
+    space.init("my-project");
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("build").arg("--watch"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: --watch requires at least one project using the maturin build backend
+    "###);
+}
+
+#[test]
+fn test_show_deps_why() {
+    let space = Space::new();
+    space.init("my-project");
+    fs::write(
+        space.project_path().join("requirements.lock"),
+        r#"# generated by rye
+-e file:.
+anyio==4.0.0
+    # via my-project
+idna==3.4
+    # via anyio
+sniffio==1.3.0
+    # via anyio
+"#,
+    )
+    .unwrap();
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("show").arg("deps").arg("--why").arg("idna"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    idna==3.4
+      my-project -> anyio -> idna
+
+    ----- stderr -----
+    "###);
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("show").arg("dependencies").arg("--why").arg("flask"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    flask is not in [TEMP_PATH]/project/requirements.lock
+    "###);
+}
+
+#[test]
+fn test_show_diff_python() {
+    let space = Space::new();
+    space.init("my-project");
+    space.write(".python-version", "cpython@3.12.8\n");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deps = Array::new();
+        deps.push("anyio==4.0.0");
+        doc["project"]["dependencies"] = value(deps);
+        doc["project"]["requires-python"] = value(">= 3.8");
+    });
+    let status = space.rye_cmd().arg("lock").status().unwrap();
+    assert!(status.success());
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("show").arg("diff-python").arg("3.11"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    python: cpython@3.12.8 -> 3.11
+    interpreter: cpython@3.11.11 (installed)
+    requires-python: >=3.8
+    [TEMP_PATH]/project/requirements.lock (production):
+      resolution: no changes
+    [TEMP_PATH]/project/requirements-dev.lock (dev):
+      resolution: no changes
+
+    ----- stderr -----
+    "###);
+
+    // anyio needs a backport below 3.11
+    let mut settings = Settings::clone_current();
+    settings.add_filter(r"cpython@3\.10\.\d+", "cpython@3.10.[PATCH]");
+    let _guard = settings.bind_to_scope();
+    rye_cmd_snapshot!(space.rye_cmd().arg("show").arg("diff-python").arg("3.10"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    python: cpython@3.12.8 -> 3.10
+    interpreter: cpython@3.10.[PATCH] (downloadable)
+    requires-python: >=3.8
+    [TEMP_PATH]/project/requirements.lock (production):
+      resolution:
+        + exceptiongroup 1.1.3
+    [TEMP_PATH]/project/requirements-dev.lock (dev):
+      resolution:
+        + exceptiongroup 1.1.3
+
+    ----- stderr -----
+    "###);
+
+    // nothing was written
+    assert!(!space
+        .read_string("requirements.lock")
+        .contains("exceptiongroup"));
+}
+
+#[test]
+fn test_pin_toml() {
+    let space = Space::new();
+    space.init("my-project");
+
+    let output = space
+        .rye_cmd()
+        .arg("pin")
+        .arg("3.12.8")
+        .arg("--toml")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        space.read_toml("pyproject.toml")["tool"]["rye"]["python"].as_str(),
+        Some("3.12.8")
+    );
+
+    // the pin in pyproject.toml wins over the dotfile
+    space.write(".python-version", "3.11\n");
+    let output = space.rye_cmd().arg("show").arg("python").output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("requested: 3.12.8 (from tool.rye.python)"));
+}
+
+#[test]
+fn test_pin_manifest() {
+    let space = Space::new();
+    space.write(
+        "repos/a/pyproject.toml",
+        r#"[project]
+name = "a"
+version = "0.1.0"
+requires-python = ">= 3.13"
+"#,
+    );
+    space.write(
+        "repos/b/pyproject.toml",
+        "[project]\nname = \"b\"\nversion = \"0.1.0\"\n",
+    );
+    space.write(
+        "repos.txt",
+        "# python upgrade\nrepos/a\n\nrepos/b\nrepos/missing\n",
+    );
+
+    let output = space
+        .rye_cmd()
+        .arg("pin")
+        .arg("3.12.8")
+        .arg("--manifest")
+        .arg("repos.txt")
+        .arg("--no-lock")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("2 of 3 project(s) pinned to 3.12.8 without issues"));
+    assert!(stdout.contains("  repos/missing: failed to pin"));
+
+    assert_eq!(space.read_string("repos/a/.python-version"), "3.12.8\n");
+    assert_eq!(space.read_string("repos/b/.python-version"), "3.12.8\n");
+    assert_eq!(
+        space.read_toml("repos/a/pyproject.toml")["project"]["requires-python"].as_str(),
+        Some(">= 3.12")
+    );
+}
@@ -20,15 +20,17 @@ fn test_config_empty() {
       Usage: rye config [OPTIONS]
 
       Options:
-            --show-path            Print the path to the config
-            --format <FORMAT>      Request parseable output format rather than lines [possible values:
-                                   json]
-            --get <GET>            Reads a config key
-            --set <SET>            Sets a config key to a string
-            --set-int <SET_INT>    Sets a config key to an integer
-            --set-bool <SET_BOOL>  Sets a config key to a bool
-            --unset <UNSET>        Remove a config key
-        -h, --help                 Print help (see more with '--help')
+            --show-path                   Print the path to the config
+            --project                     Read or modify the settings of the current project instead
+            --pyproject <PYPROJECT_TOML>  Use this pyproject.toml file
+            --format <FORMAT>             Request parseable output format rather than lines [possible
+                                          values: json]
+            --get <GET>                   Reads a config key
+            --set <SET>                   Sets a config key to a string
+            --set-int <SET_INT>           Sets a config key to an integer
+            --set-bool <SET_BOOL>         Sets a config key to a bool
+            --unset <UNSET>               Remove a config key
+        -h, --help                        Print help (see more with '--help')
     "###);
 }
 
@@ -134,3 +136,138 @@ fn test_config_save_missing_folder() {
     toolchain = "cpython@3.12"
     "###);
 }
+
+#[test]
+fn test_config_project() {
+    let space = Space::new();
+    space.init("my-project");
+    rye_cmd_snapshot!(space.rye_cmd()
+        .arg("config")
+        .arg("--project")
+        .arg("--set-bool")
+        .arg("tool.rye.generate-hashes=true"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    "###);
+    space.load_toml("pyproject.toml", |doc| {
+        assert_eq!(doc["tool"]["rye"]["generate-hashes"].as_bool(), Some(true));
+    });
+
+    rye_cmd_snapshot!(space.rye_cmd()
+        .arg("config")
+        .arg("--project")
+        .arg("--get")
+        .arg("tool.rye.generate-hashes"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    true
+
+    ----- stderr -----
+    "###);
+
+    rye_cmd_snapshot!(space.rye_cmd()
+        .arg("config")
+        .arg("--project")
+        .arg("--set")
+        .arg("tool.rye.universal=yes"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: invalid value for tool.rye.universal: expected a boolean, got a string
+    "###);
+
+    rye_cmd_snapshot!(space.rye_cmd()
+        .arg("config")
+        .arg("--project")
+        .arg("--set")
+        .arg("project.name=other"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: only settings below tool.rye can be modified in pyproject.toml (got project.name)
+    "###);
+}
+
+#[test]
+fn test_show_config_origin() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["generate-hashes"] = toml_edit::value(true);
+    });
+
+    let output = space
+        .rye_cmd()
+        .arg("show")
+        .arg("config")
+        .arg("--resolved")
+        .env("RYE_NETWORK_RETRIES", "3")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("network.retries = 3  (env: RYE_NETWORK_RETRIES)"));
+    assert!(stdout.contains("default.license = (unset)  (default)"));
+    let line = stdout
+        .lines()
+        .find(|x| x.starts_with("tool.rye.generate-hashes = true  (project: "))
+        .unwrap();
+    assert!(line.contains("pyproject.toml:"));
+
+    let output = space.rye_cmd().arg("show").arg("config").output().unwrap();
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("(default)"));
+}
+
+#[test]
+fn test_show_config_workspace_precedence() {
+    let space = Space::new();
+    space.init("my-workspace");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut members = toml_edit::Array::new();
+        members.push("member");
+        doc["tool"]["rye"]["workspace"]["members"] = toml_edit::value(members);
+        doc["tool"]["rye"]["generate-hashes"] = toml_edit::value(true);
+    });
+    let status = space
+        .rye_cmd()
+        .arg("init")
+        .arg("-q")
+        .arg(space.project_path().join("member"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    space.edit_toml("member/pyproject.toml", |doc| {
+        doc["tool"]["rye"]["generate-hashes"] = toml_edit::value(false);
+        doc["tool"]["rye"]["virtual"] = toml_edit::value(true);
+    });
+
+    let output = space
+        .rye_cmd()
+        .arg("show")
+        .arg("config")
+        .arg("--resolved")
+        .arg("--pyproject")
+        .arg(space.project_path().join("member/pyproject.toml"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // the workspace root wins for workspace wide settings
+    assert!(stdout
+        .lines()
+        .any(|x| x.starts_with("tool.rye.generate-hashes = true  (workspace: ")));
+    assert!(stdout
+        .lines()
+        .any(|x| x.starts_with("tool.rye.virtual = true  (project: ")));
+    assert!(stdout.contains("tool.rye.universal = false  (default)"));
+    assert!(stdout.contains("tool.rye.lock-header = minimal  (default)"));
+    assert!(stdout.contains("tool.rye.load-dotenv = false  (default)"));
+}
@@ -0,0 +1,60 @@
+use std::fs;
+
+use crate::common::{rye_cmd_snapshot, Space};
+
+mod common;
+
+#[test]
+fn test_generate_docker() {
+    let space = Space::new();
+    space.init("my-project");
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("generate").arg("docker"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: no lockfile found, run `rye lock` first
+    "###);
+
+    fs::write(
+        space.project_path().join("requirements.lock"),
+        "-e file:.\nidna==3.7\n",
+    )
+    .unwrap();
+    rye_cmd_snapshot!(space.rye_cmd().arg("generate").arg("docker").arg("--devcontainer"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Generated [TEMP_PATH]/project/Dockerfile
+    Generated [TEMP_PATH]/project/.devcontainer/devcontainer.json
+    Generated [TEMP_PATH]/project/.dockerignore
+    Build the image with docker build .
+
+    ----- stderr -----
+    "###);
+
+    let dockerfile = fs::read_to_string(space.project_path().join("Dockerfile")).unwrap();
+    assert!(dockerfile.contains("FROM python:3.12.8-slim AS builder\n"));
+    assert!(dockerfile.contains("COPY requirements.lock ./\n"));
+    assert!(dockerfile.contains("uv pip install --no-deps .\n"));
+    assert!(dockerfile.contains("CMD [\"python\",\"-m\",\"my_project\"]\n"));
+    let devcontainer = fs::read_to_string(
+        space
+            .project_path()
+            .join(".devcontainer")
+            .join("devcontainer.json"),
+    )
+    .unwrap();
+    assert!(devcontainer.contains("\"name\": \"my-project\""));
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("generate").arg("docker"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: [TEMP_PATH]/project/Dockerfile already exists, use --force to overwrite it
+    "###);
+}
@@ -1,3 +1,7 @@
+use std::fs;
+
+use insta::assert_snapshot;
+
 use crate::common::{get_bin, rye_cmd_snapshot, Space};
 
 mod common;
@@ -166,3 +170,330 @@ fn test_init_lib_and_script_incompatible() {
         error: an argument cannot be used with one or more of the other specified arguments
     "###);
 }
+
+#[test]
+fn test_init_migrate_poetry_workspace() {
+    let space = Space::new();
+    space.write(
+        "packages/my-lib/pyproject.toml",
+        r#"[tool.poetry]
+name = "my-lib"
+version = "0.1.0"
+description = ""
+authors = ["Jane Doe <jane@example.com>"]
+
+[tool.poetry.dependencies]
+python = "^3.9"
+attrs = "^23.1"
+
+[build-system]
+requires = ["poetry-core"]
+build-backend = "poetry.core.masonry.api"
+"#,
+    );
+    space.write("packages/my-lib/src/my_lib/__init__.py", "");
+    space.write(
+        "packages/my-app/pyproject.toml",
+        r#"[tool.poetry]
+name = "my-app"
+version = "0.1.0"
+description = ""
+authors = ["Jane Doe <jane@example.com>"]
+
+[tool.poetry.dependencies]
+python = "^3.9"
+my-lib = { path = "../my-lib", develop = true }
+
+[tool.poetry.group.dev.dependencies]
+pytest = "^8.0"
+
+[tool.poetry.requires-plugins]
+poetry-dynamic-versioning = ">=1.0"
+
+[build-system]
+requires = ["poetry-core"]
+build-backend = "poetry.core.masonry.api"
+"#,
+    );
+    space.write("packages/my-app/src/my_app/__init__.py", "");
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("init").arg("--migrate").arg("--workspace"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Converted my-app (packages/my-app/pyproject.toml)
+    Converted my-lib (packages/my-lib/pyproject.toml)
+    Configured workspace with 2 member(s)
+    success: Migrated 2 Poetry project(s) in [TEMP_PATH]/project/.
+    Manual follow-ups:
+      my-app: requires the Poetry plugin poetry-dynamic-versioning
+      Run `rye sync` to get started
+
+    ----- stderr -----
+    "###);
+
+    let root = space.read_toml("pyproject.toml");
+    assert_eq!(root["tool"]["rye"]["virtual"].as_bool(), Some(true));
+    assert_snapshot!(root["tool"]["rye"]["workspace"]["members"], @r###"
+     [
+        "packages/my-app",
+        "packages/my-lib",
+    ]
+    "###);
+    let app = space.read_toml("packages/my-app/pyproject.toml");
+    assert_snapshot!(app["project"]["dependencies"], @r###"
+     [
+        "my-lib",
+    ]
+    "###);
+    assert_snapshot!(app["tool"]["rye"]["dev-dependencies"], @r###"
+     [
+        "pytest>=8.0,<9",
+    ]
+    "###);
+}
+
+// Test that a member that fails to convert leaves all projects untouched
+#[test]
+fn test_init_migrate_poetry_workspace_is_staged() {
+    let space = Space::new();
+    let app = r#"[tool.poetry]
+name = "my-app"
+version = "0.1.0"
+
+[tool.poetry.dependencies]
+python = "^3.9"
+"#;
+    space.write("packages/my-app/pyproject.toml", app);
+    space.write(
+        "packages/my-lib/pyproject.toml",
+        "[tool.poetry]\nversion = \"0.1.0\"\n",
+    );
+
+    let output = space
+        .rye_cmd()
+        .arg("init")
+        .arg("--migrate")
+        .arg("--workspace")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("failed to convert"), "{}", stderr);
+    assert!(stderr.contains("project has no name"), "{}", stderr);
+    assert_eq!(space.read_string("packages/my-app/pyproject.toml"), app);
+    assert!(!space.project_path().join("pyproject.toml").exists());
+}
+
+// Test that init --from bootstraps a project from an sdist
+#[test]
+fn test_init_from_sdist() {
+    let space = Space::new();
+    space.write(
+        "sdist/foo-1.2.3/PKG-INFO",
+        r#"Metadata-Version: 2.1
+Name: foo
+Version: 1.2.3
+Summary: A legacy package
+Author: Jane Doe
+Author-email: jane@example.com
+Requires-Python: >=3.8
+Requires-Dist: requests>=2.0
+Requires-Dist: pytest>=7; extra == "test"
+"#,
+    );
+    space.write(
+        "sdist/foo-1.2.3/foo.egg-info/entry_points.txt",
+        "[console_scripts]\nfoo = foo.cli:main\n",
+    );
+    space.write("sdist/foo-1.2.3/foo/__init__.py", "");
+    space.write("sdist/foo-1.2.3/foo/cli.py", "def main():\n    pass\n");
+    space.write("sdist/foo-1.2.3/setup.py", "");
+    space.write("sdist/foo-1.2.3/tests/test_foo.py", "");
+    space.write("sdist/foo-1.2.3/Makefile", "");
+    let status = std::process::Command::new("tar")
+        .arg("czf")
+        .arg("../foo-1.2.3.tar.gz")
+        .arg("foo-1.2.3")
+        .current_dir(space.project_path().join("sdist"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    std::fs::remove_dir_all(space.project_path().join("sdist")).unwrap();
+
+    let status = space
+        .rye_cmd()
+        .arg("init")
+        .arg("--from")
+        .arg("foo-1.2.3.tar.gz")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let doc = space.read_toml("pyproject.toml");
+    assert_eq!(doc["project"]["name"].as_str(), Some("foo"));
+    assert_eq!(doc["project"]["version"].as_str(), Some("1.2.3"));
+    assert_eq!(doc["project"]["requires-python"].as_str(), Some(">=3.8"));
+    assert_eq!(
+        doc["project"]["dependencies"][0].as_str(),
+        Some("requests>=2.0")
+    );
+    assert_eq!(
+        doc["project"]["optional-dependencies"]["test"][0].as_str(),
+        Some("pytest>=7")
+    );
+    assert_eq!(
+        doc["project"]["scripts"]["foo"].as_str(),
+        Some("foo.cli:main")
+    );
+    assert_eq!(
+        doc["tool"]["hatch"]["build"]["targets"]["wheel"]["packages"][0].as_str(),
+        Some("src/foo")
+    );
+    assert!(space.project_path().join("src/foo/cli.py").is_file());
+    assert!(space.project_path().join("tests/test_foo.py").is_file());
+    assert!(!space.project_path().join("Makefile").exists());
+}
+
+#[test]
+fn test_init_interactive_without_tty() {
+    let space = Space::new();
+    let output = space
+        .rye_cmd()
+        .arg("init")
+        .arg("-i")
+        .arg(space.project_path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pass the options as flags"));
+    assert!(!space.project_path().join("pyproject.toml").exists());
+}
+
+#[test]
+fn test_init_in_site_packages() {
+    let space = Space::new();
+    let target = space.project_path().join("lib/site-packages/foo");
+    std::fs::create_dir_all(&target).unwrap();
+    let output = space
+        .rye_cmd()
+        .arg("init")
+        .current_dir(&target)
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-system-dir"));
+
+    let status = space
+        .rye_cmd()
+        .arg("init")
+        .arg("--allow-system-dir")
+        .current_dir(&target)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(target.join("pyproject.toml").is_file());
+}
+
+#[test]
+fn test_init_checks_target_not_current_dir() {
+    let space = Space::new();
+
+    // the target is refused even if the current folder is fine
+    let output = space
+        .rye_cmd()
+        .arg("init")
+        .arg("lib/site-packages/foo")
+        .current_dir(space.project_path())
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--allow-system-dir"));
+    assert!(!space
+        .project_path()
+        .join("lib/site-packages/foo/pyproject.toml")
+        .exists());
+
+    // and the other way round: creating a project from the root of the file system
+    let target = space.project_path().join("my-project");
+    let status = space
+        .rye_cmd()
+        .arg("init")
+        .arg(&target)
+        .current_dir("/")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(target.join("pyproject.toml").is_file());
+}
+
+#[test]
+fn test_init_new_project_defaults() {
+    let space = Space::new();
+    // a separate rye home so that other tests do not pick up the defaults
+    let home = space.project_path().join("home");
+    fs::create_dir_all(&home).unwrap();
+    fs::write(
+        home.join("new-project-defaults.toml"),
+        r#"[tool.rye]
+dev-dependencies = ["pytest>=8.0"]
+
+[tool.rye.scripts]
+test = "pytest"
+
+[[tool.rye.sources]]
+name = "mirror"
+url = "https://mirror.example.com/simple/"
+"#,
+    )
+    .unwrap();
+
+    let status = space
+        .rye_cmd()
+        .env("RYE_HOME", &home)
+        .arg("init")
+        .arg("-q")
+        .arg("with-defaults")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let doc = space.read_toml("with-defaults/pyproject.toml");
+    let rye = &doc["tool"]["rye"];
+    assert_eq!(rye["dev-dependencies"][0].as_str(), Some("pytest>=8.0"));
+    assert_eq!(rye["scripts"]["test"].as_str(), Some("pytest"));
+    assert_eq!(rye["sources"][0]["name"].as_str(), Some("mirror"));
+
+    let status = space
+        .rye_cmd()
+        .env("RYE_HOME", &home)
+        .arg("init")
+        .arg("-q")
+        .arg("--no-defaults")
+        .arg("without-defaults")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let doc = space.read_toml("without-defaults/pyproject.toml");
+    assert!(doc["tool"]["rye"].get("scripts").is_none());
+    assert!(doc["tool"]["rye"].get("sources").is_none());
+
+    // invalid defaults are reported with the file they come from
+    fs::write(
+        home.join("new-project-defaults.toml"),
+        "[tool.rye]\nscripts = \"pytest\"\n",
+    )
+    .unwrap();
+    let output = space
+        .rye_cmd()
+        .env("RYE_HOME", &home)
+        .arg("init")
+        .arg("-q")
+        .arg("broken-defaults")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&format!(
+        "tool.rye.scripts in {} must be a table",
+        home.join("new-project-defaults.toml").display()
+    )));
+}
@@ -1,4 +1,5 @@
 use insta::assert_snapshot;
+use toml_edit::{value, Array};
 
 use crate::common::{rye_cmd_snapshot, Space};
 
@@ -88,3 +89,115 @@ def hello():
         return "Hello World"
     "###);
 }
+
+#[test]
+fn test_lint_and_format_workspace() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut workspace_members = Array::new();
+        workspace_members.push(".");
+        workspace_members.push("child");
+        doc["tool"]["rye"]["workspace"]["members"] = value(workspace_members);
+    });
+    let status = space
+        .rye_cmd()
+        .arg("init")
+        .arg("-q")
+        .arg(space.project_path().join("child"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    // the child has its own ruff configuration
+    space.edit_toml("child/pyproject.toml", |doc| {
+        let mut ignore = Array::new();
+        ignore.push("F401");
+        doc["tool"]["ruff"]["lint"]["ignore"] = value(ignore);
+    });
+    space.write("src/my_project/test.py", "import os\n");
+    space.write("child/src/child/test.py", "import os\n");
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("lint").arg("--all"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    [child] All checks passed!
+    [my-project] src/my_project/test.py:1:8: F401 [*] `os` imported but unused
+    [my-project]   |
+    [my-project] 1 | import os
+    [my-project]   |        ^^ F401
+    [my-project]   |
+    [my-project]   = help: Remove unused import: `os`
+    [my-project]
+    [my-project] Found 1 error.
+    [my-project] [*] 1 fixable with the `--fix` option.
+
+    ----- stderr -----
+    "###);
+
+    space.write("src/my_project/test.py", "x  =  1\n");
+    rye_cmd_snapshot!(space.rye_cmd().arg("fmt").arg("--all").arg("--check"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    [child] 2 files already formatted
+    [my-project] Would reformat: src/my_project/test.py
+    [my-project] 1 file would be reformatted, 1 file already formatted
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_format_normalize_pyproject() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deps = Array::new();
+        deps.push("Requests[socks,security]>=2.31.0");
+        deps.push("flask>=3.0.0");
+        deps.push("Colorama>=0.4.6");
+        doc["project"]["dependencies"] = value(deps);
+    });
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("fmt").arg("--normalize-pyproject").arg("--check"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Would normalize [TEMP_PATH]/project/pyproject.toml
+
+    ----- stderr -----
+    "###);
+    // `--pyproject` without a path is a shorthand
+    rye_cmd_snapshot!(space.rye_cmd().arg("fmt").arg("--pyproject"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Normalized [TEMP_PATH]/project/pyproject.toml
+
+    ----- stderr -----
+    "###);
+    space.load_toml("pyproject.toml", |doc| {
+        let deps = doc["project"]["dependencies"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|x| x.as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            deps,
+            vec![
+                "colorama>=0.4.6",
+                "flask>=3.0.0",
+                "requests[security,socks]>=2.31.0"
+            ]
+        );
+    });
+    rye_cmd_snapshot!(space.rye_cmd().arg("fmt").arg("--normalize-pyproject").arg("--check"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    ----- stderr -----
+    "###);
+}
@@ -1,3 +1,6 @@
+use std::fs;
+use std::path::Path;
+
 use toml_edit::value;
 
 use crate::common::{rye_cmd_snapshot, Space};
@@ -37,3 +40,287 @@ fn test_basic_script() {
     ----- stderr -----
     "###);
 }
+
+#[test]
+fn test_call_script_arguments() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["scripts"]["greet"]["call"] = value("builtins:print('Hello', {0})");
+        doc["tool"]["rye"]["scripts"]["argv"]["call"] = value("builtins:print({args})");
+        doc["tool"]["rye"]["scripts"]["pair"]["call"] = value("builtins:print({0}, {1})");
+        doc["tool"]["rye"]["scripts"]["check"]["call"] = value("builtins:len({0})");
+        doc["tool"]["rye"]["scripts"]["set"]["call"] = value("builtins:print({1})");
+    });
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("greet").arg("World"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Hello World
+
+    ----- stderr -----
+    Initializing new virtualenv in [TEMP_PATH]/project/.venv
+    Python version: cpython@3.12.8
+    "###);
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("argv").arg("a").arg("b"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    a b
+
+    ----- stderr -----
+    "###);
+
+    // the return value is the exit code
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("check").arg("abc"), @r###"
+    success: false
+    exit_code: 3
+    ----- stdout -----
+
+    ----- stderr -----
+    "###);
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("pair").arg("a"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: script expects at least 2 arguments, got 1
+    "###);
+
+    // without extra arguments nothing is replaced
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("set"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    {1}
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_isolated_run() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["scripts"]["show-env"]["cmd"] = value(
+            "python -c \"import os; print(os.environ.get('PASSED'), os.environ.get('STRIPPED'), os.environ.get('FROM_SCRIPT'), 'VIRTUAL_ENV' in os.environ)\"",
+        );
+        doc["tool"]["rye"]["scripts"]["show-env"]["env"]["FROM_SCRIPT"] = value("1");
+    });
+
+    rye_cmd_snapshot!(space.rye_cmd()
+        .env("PASSED", "yes")
+        .env("STRIPPED", "yes")
+        .arg("run")
+        .arg("--isolated")
+        .arg("--pass")
+        .arg("PASSED")
+        .arg("show-env"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    yes None 1 True
+
+    ----- stderr -----
+    Initializing new virtualenv in [TEMP_PATH]/project/.venv
+    Python version: cpython@3.12.8
+    "###);
+
+    rye_cmd_snapshot!(space.rye_cmd()
+        .env("PASSED", "yes")
+        .env("STRIPPED", "yes")
+        .arg("run")
+        .arg("show-env"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    yes yes 1 True
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_platform_scripts() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let scripts = &mut doc["tool"]["rye"]["scripts"];
+        scripts["hello"]["cmd"]["windows"] = value("python -c \"print('Hello Windows')\"");
+        scripts["hello"]["cmd"]["unix"] = value("python -c \"print('Hello Unix')\"");
+        scripts["other"]["cmd"]["plan9"] = value("echo");
+    });
+
+    let output = space.rye_cmd().arg("run").arg("hello").output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout).trim(),
+        if cfg!(windows) {
+            "Hello Windows"
+        } else {
+            "Hello Unix"
+        }
+    );
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("other"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: script 'other' is not available on this platform (only on plan9)
+    "###);
+}
+
+#[test]
+fn test_script_help() {
+    let mut settings = insta::Settings::clone_current();
+    settings.add_filter(r"(?m)(^py[a-z\d._]+$\r?\n)+", "[PYTHON SCRIPTS]\n");
+    let _guard = settings.bind_to_scope();
+
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["scripts"]["hello"]["cmd"] = value("python -c 'print(1)'");
+        doc["tool"]["rye"]["scripts"]["hello"]["help"] = value("Prints a greeting");
+        doc["tool"]["rye"]["scripts"]["undocumented"] = value("python -V");
+    });
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("--list"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    hello (python -c 'print(1)')
+      Prints a greeting
+    [PYTHON SCRIPTS]
+    undocumented (python -V)
+
+    ----- stderr -----
+    Initializing new virtualenv in [TEMP_PATH]/project/.venv
+    Python version: cpython@3.12.8
+    "###);
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("check-scripts"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    All 2 script(s) are valid
+
+    ----- stderr -----
+    "###);
+
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["require-script-help"] = value(true);
+    });
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("check-scripts"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    error undocumented (my-project): missing help text
+
+    ----- stderr -----
+    error: 1 of 2 script(s) have problems
+    "###);
+}
+
+#[test]
+fn test_run_python_cache() {
+    let space = Space::new();
+    space.init("my-project");
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("python").arg("-c").arg("print('hello')"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    hello
+
+    ----- stderr -----
+    Initializing new virtualenv in [TEMP_PATH]/project/.venv
+    Python version: cpython@3.12.8
+    "###);
+    assert!(space.rye_home().join("run-cache.json").is_file());
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("python").arg("-c").arg("print('hello')"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    hello
+
+    ----- stderr -----
+    "###);
+
+    // changing the pyproject.toml invalidates the cache
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["scripts"]["python"] = value("python -c 'print(\"from script\")'");
+    });
+    rye_cmd_snapshot!(space.rye_cmd().arg("run").arg("python"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    from script
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_run_python_fast_path() {
+    let space = Space::new();
+    space.init("my-project");
+    fs::create_dir(space.project_path().join("sub")).unwrap();
+    let run_python = |cwd: &Path| {
+        let output = space
+            .rye_cmd()
+            .arg("run")
+            .arg("python")
+            .arg("-c")
+            .arg("import os, sys; print(os.environ.get('RYE_FAST_PATH'), sys.prefix)")
+            .current_dir(cwd)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8_lossy(&output.stdout).trim().to_string()
+    };
+    let sub_dir = space.project_path().join("sub");
+    run_python(&sub_dir);
+
+    // only the fast path loads the `.env` files remembered in the cache, so
+    // sneak one in to see which path was taken.
+    space.write("fast-path.env", "RYE_FAST_PATH=1\n");
+    let cache_path = space.rye_home().join("run-cache.json");
+    let mut cache: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+    let canonical_sub_dir = sub_dir.canonicalize().unwrap();
+    let mut found = false;
+    for (cwd, entry) in cache.as_object_mut().unwrap() {
+        if Path::new(cwd) == sub_dir || Path::new(cwd) == canonical_sub_dir {
+            entry["dotenv_files"] = serde_json::json!([space.project_path().join("fast-path.env")]);
+            found = true;
+        }
+    }
+    assert!(found);
+    fs::write(&cache_path, serde_json::to_vec(&cache).unwrap()).unwrap();
+
+    let stdout = run_python(&sub_dir);
+    assert!(stdout.starts_with("1 "), "fast path not taken: {}", stdout);
+    assert!(Path::new(stdout.trim_start_matches("1 ")).ends_with("project/.venv"));
+
+    // a project created in between takes over, even though the folder is the same
+    let status = space
+        .rye_cmd()
+        .arg("init")
+        .arg("-q")
+        .arg(&sub_dir)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let stdout = run_python(&sub_dir);
+    assert!(stdout.starts_with("None "), "fast path taken: {}", stdout);
+    assert!(Path::new(stdout.trim_start_matches("None ")).ends_with("sub/.venv"));
+}
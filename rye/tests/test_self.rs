@@ -1,3 +1,7 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
 use insta::Settings;
 
 use crate::common::{rye_cmd_snapshot, Space};
@@ -70,3 +74,213 @@ fn test_version() {
     ----- stderr -----
     "###);
 }
+
+#[test]
+fn test_version_json() {
+    let space = Space::new();
+    let _guard = space.lock_rye_home();
+
+    let mut settings = Settings::clone_current();
+    settings.add_filter(r#"("version": )"\d+\.\d+\.\d+""#, r#"$1"[VERSION]""#);
+    settings.add_filter(r#"("commit": )(null|"[0-9a-f]+")"#, r#"$1"[COMMIT]""#);
+    settings.add_filter(r#"("build_date": )".*""#, r#"$1"[DATE]""#);
+    settings.add_filter(r#"("os": )".*""#, r#"$1"[OS]""#);
+    settings.add_filter(r#"("arch": )".*""#, r#"$1"[ARCH]""#);
+    settings.add_filter(r#"("uv_version": )"\d+\.\d+\.\d+""#, r#"$1"[UV_VERSION]""#);
+    let _guard = settings.bind_to_scope();
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("--version").arg("--format").arg("json"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    {
+      "version": "[VERSION]",
+      "commit": "[COMMIT]",
+      "build_date": "[DATE]",
+      "platform": {
+        "os": "[OS]",
+        "arch": "[ARCH]"
+      },
+      "self_python": "cpython@3.12.8",
+      "self_python_target": "cpython@3.12",
+      "uv_version": "[UV_VERSION]",
+      "symlink_support": true,
+      "uv_enabled": true
+    }
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_self_install_print_env() {
+    let space = Space::new();
+    let _guard = space.lock_rye_home();
+
+    let output = space
+        .rye_cmd()
+        .arg("self")
+        .arg("install")
+        .arg("--yes")
+        .arg("--no-modify-shell")
+        .arg("--print-env")
+        .arg("json")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let env: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let shims = space.rye_home().join("shims");
+    assert_eq!(env["path"][0].as_str(), shims.to_str());
+    assert_eq!(env["shell_modified"], false);
+    assert!(env["env"]["RYE_HOME"].is_string());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("All done!"));
+}
+
+#[test]
+fn test_self_uninstall_without_tty() {
+    let space = Space::new();
+    let output = space
+        .rye_cmd()
+        .arg("self")
+        .arg("uninstall")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pass --yes"));
+    assert!(space.rye_home().join("self").is_dir());
+}
+
+// The installer script runs `rye self install` with stdin attached to the pipe
+// from curl, the prompts have to read from the terminal instead.
+#[test]
+#[cfg(target_os = "linux")]
+fn test_self_install_with_piped_stdin() {
+    let space = Space::new();
+    let _guard = space.lock_rye_home();
+
+    // `script` runs the installer with stderr on a pseudo terminal
+    let rye_cmd = space.rye_cmd();
+    let mut cmd = Command::new("script");
+    cmd.arg("-qec")
+        .arg(format!(
+            "'{}' self install --no-modify-shell < /dev/null",
+            rye_cmd.get_program().to_string_lossy()
+        ))
+        .arg("/dev/null")
+        .current_dir(space.project_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    for (key, value) in rye_cmd.get_envs() {
+        match value {
+            Some(value) => cmd.env(key, value),
+            None => cmd.env_remove(key),
+        };
+    }
+    let Ok(mut child) = cmd.spawn() else {
+        // script is not installed
+        return;
+    };
+
+    // decline the installation at the first prompt
+    child.stdin.take().unwrap().write_all(b"n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("stdin is not a terminal"), "{}", stdout);
+    assert!(stdout.contains("Installation cancelled!"), "{}", stdout);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_completion_install() {
+    let space = Space::new();
+    let home = space.project_path().join("home");
+    let rye_home = home.join(".rye");
+    let install = |shell: &str| {
+        let output = space
+            .rye_cmd()
+            .env("HOME", &home)
+            .env("RYE_HOME", &rye_home)
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("ZDOTDIR")
+            .arg("self")
+            .arg("completion")
+            .arg("--install")
+            .arg("--shell")
+            .arg(shell)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{:?}", output);
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // bash picks up the script without configuration
+    install("bash");
+    assert!(home
+        .join(".local/share/bash-completion/completions/rye")
+        .is_file());
+
+    // zsh loads the script from the rye home through .zshrc
+    let stdout = install("zsh");
+    assert!(stdout.contains("Added completion loading to"), "{}", stdout);
+    let script = rye_home.join("completions/_rye");
+    assert!(script.is_file());
+    let line = format!(
+        "(( $+functions[compdef] )) && source \"{}\"",
+        script.display()
+    );
+    let zshrc = fs::read_to_string(home.join(".zshrc")).unwrap();
+    assert_eq!(zshrc.lines().filter(|x| *x == line).count(), 1);
+
+    // installing again does not add the line a second time
+    let stdout = install("zsh");
+    assert!(
+        !stdout.contains("Added completion loading to"),
+        "{}",
+        stdout
+    );
+    assert_eq!(fs::read_to_string(home.join(".zshrc")).unwrap(), zshrc);
+
+    install("elvish");
+    let config = fs::read_to_string(home.join(".config/elvish/rc.elv")).unwrap();
+    assert!(config.contains(&format!(
+        "eval (slurp < \"{}\")",
+        rye_home.join("completions/rye.elv").display()
+    )));
+
+    let completions = fs::read_to_string(rye_home.join("completions.json")).unwrap();
+    for shell in ["bash", "zsh", "elvish"] {
+        assert!(
+            completions.contains(&format!("\"{}\"", shell)),
+            "{}",
+            completions
+        );
+    }
+
+    // uninstalling removes the scripts and the lines that load them
+    fs::write(home.join(".zshrc"), format!("export FOO=1\n{}", zshrc)).unwrap();
+    let status = space
+        .rye_cmd()
+        .env("HOME", &home)
+        .env("RYE_HOME", &rye_home)
+        .env_remove("XDG_DATA_HOME")
+        .env_remove("XDG_CONFIG_HOME")
+        .env_remove("ZDOTDIR")
+        .arg("self")
+        .arg("uninstall")
+        .arg("--yes")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(!script.exists());
+    assert!(!home
+        .join(".local/share/bash-completion/completions/rye")
+        .exists());
+    assert_eq!(
+        fs::read_to_string(home.join(".zshrc")).unwrap(),
+        "export FOO=1\n"
+    );
+    let config = fs::read_to_string(home.join(".config/elvish/rc.elv")).unwrap();
+    assert!(!config.contains("rye.elv"), "{}", config);
+}
@@ -1,6 +1,8 @@
 use std::fs;
+use std::io::Write;
 
 use insta::{assert_snapshot, Settings};
+use toml_edit::{value, Array, ArrayOfTables, Table};
 
 use crate::common::{rye_cmd_snapshot, Space};
 
@@ -393,6 +395,47 @@ fn test_lockfile() {
     "###);
 }
 
+#[test]
+fn test_virtual_project_features() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["virtual"] = value(true);
+        let mut docs = Array::new();
+        docs.push("sniffio==1.3.0");
+        doc["project"]["optional-dependencies"]["docs"] = value(docs);
+        let mut test = Array::new();
+        test.push("idna==3.4");
+        test.push("my-project[docs]");
+        doc["project"]["optional-dependencies"]["test"] = value(test);
+    });
+
+    let status = space
+        .rye_cmd()
+        .arg("lock")
+        .arg("--features")
+        .arg("test")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    assert_snapshot!(space.read_string(space.project_path().join("requirements.lock")), @r###"
+    # generated by rye
+    # use `rye lock` or `rye sync` to update this lockfile
+    #
+    # last locked with the following flags:
+    #   pre: false
+    #   features: ["test"]
+    #   all-features: false
+    #   with-sources: false
+    #   generate-hashes: false
+    #   universal: false
+
+    idna==3.4
+    sniffio==1.3.0
+    "###);
+}
+
 #[test]
 fn test_generate_hashes() {
     let space = Space::new();
@@ -472,3 +515,711 @@ fn test_generate_hashes() {
         # via anyio
     "###);
 }
+
+#[test]
+fn test_check_entry_points() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["project"]["scripts"]["hello"] = value("my_project:hello");
+        doc["project"]["scripts"]["broken"] = value("my_project:missing");
+    });
+    space
+        .rye_cmd()
+        .arg("sync")
+        .arg("-q")
+        .status()
+        .expect("sync successful");
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("check-entry-points"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    broken broken (my_project:missing): AttributeError: module 'my_project' has no attribute 'missing'
+
+    ----- stderr -----
+    error: 1 of 2 entry point(s) are broken
+    "###);
+}
+
+#[test]
+fn test_sync_check_entry_points_opt_in() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["project"]["scripts"]["broken"] = value("my_project:missing");
+    });
+
+    let output = space.rye_cmd().arg("sync").output().unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("is broken"));
+
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["sync"]["check-entry-points"] = value(true);
+    });
+    let output = space.rye_cmd().arg("sync").output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("entry point 'broken' (my_project:missing) is broken"));
+
+    let output = space.rye_cmd().arg("sync").arg("-q").output().unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("is broken"));
+}
+
+#[test]
+fn test_sync_editable_mode() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["tool"]["rye"]["editable-mode"] = value("strict");
+    });
+
+    // hatchling does not know about the setting
+    let output = space.rye_cmd().arg("sync").output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "tool.rye.editable-mode is ignored as none of the local projects is built with setuptools"
+    ));
+
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut requires = Array::new();
+        requires.push("setuptools>=64");
+        doc["build-system"]["requires"] = value(requires);
+        doc["build-system"]["build-backend"] = value("setuptools.build_meta");
+    });
+    let output = space
+        .rye_cmd()
+        .arg("sync")
+        .arg("--reinstall-package")
+        .arg("my-project")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("is ignored"));
+    // in strict mode setuptools exposes the project through a link tree
+    assert!(fs::read_dir(space.project_path().join("build"))
+        .unwrap()
+        .filter_map(|x| x.ok())
+        .any(|x| x.file_name().to_string_lossy().starts_with("__editable__")));
+}
+
+#[test]
+fn test_sync_dry_run() {
+    let space = Space::new();
+    space.init("my-project");
+    rye_cmd_snapshot!(space.rye_cmd().arg("sync").arg("--dry-run"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Virtualenv [TEMP_PATH]/project/.venv would be created
+    Lockfiles:
+      production: [TEMP_PATH]/project/requirements.lock (missing, would be generated)
+      dev: [TEMP_PATH]/project/requirements-dev.lock (missing, would be generated)
+    Packages (from [TEMP_PATH]/project/requirements.lock):
+      unknown until the lockfile is generated
+
+    ----- stderr -----
+    "###);
+    assert!(!space.venv_path().exists());
+    assert!(!space.project_path().join("requirements.lock").exists());
+
+    space
+        .rye_cmd()
+        .arg("sync")
+        .arg("-q")
+        .status()
+        .expect("sync successful");
+    rye_cmd_snapshot!(space.rye_cmd().arg("sync").arg("--dry-run"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Virtualenv [TEMP_PATH]/project/.venv would be reused
+    Lockfiles:
+      production: [TEMP_PATH]/project/requirements.lock (up to date)
+      dev: [TEMP_PATH]/project/requirements-dev.lock (up to date)
+    Packages (from [TEMP_PATH]/project/requirements-dev.lock):
+      no changes
+    Editables (rebuilt):
+      file:.
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_missing_client_cert() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut source = Table::new();
+        source["name"] = value("internal");
+        source["url"] = value("https://pypi.example.com/simple/");
+        source["client-cert"] = value("certs/client.pem");
+        source["client-key"] = value("certs/client.key");
+        let mut sources = ArrayOfTables::new();
+        sources.push(source);
+        doc["tool"]["rye"]["sources"] = value(sources.into_array());
+    });
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("lock"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+    Initializing new virtualenv in [TEMP_PATH]/project/.venv
+    Python version: cpython@3.12.8
+
+    ----- stderr -----
+    error: client certificate file 'certs/client.pem' of source 'internal' does not exist
+    "###);
+}
+
+#[test]
+fn test_relative_find_links() {
+    let space = Space::new();
+    space.init("my-project");
+    space.write("wheels/.keep", "");
+    space.write("vendor/wheels/.keep", "");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut sources = ArrayOfTables::new();
+        let mut source = Table::new();
+        source["name"] = value("wheelhouse");
+        source["type"] = value("find-links");
+        source["url"] = value("file:wheels");
+        sources.push(source);
+        // windows path separators are normalized
+        let mut source = Table::new();
+        source["name"] = value("vendored");
+        source["type"] = value("find-links");
+        source["url"] = value("file:.\\vendor\\wheels");
+        sources.push(source);
+        doc["tool"]["rye"]["sources"] = value(sources.into_array());
+        doc["tool"]["rye"]["lock-with-sources"] = value(true);
+    });
+
+    let status = space.rye_cmd().arg("sync").status().unwrap();
+    assert!(status.success());
+
+    for lockfile in ["requirements.lock", "requirements-dev.lock"] {
+        let contents = space.read_string(lockfile);
+        assert!(contents.contains("--find-links wheels\n"), "{}", contents);
+        assert!(
+            contents.contains("--find-links ./vendor/wheels\n"),
+            "{}",
+            contents
+        );
+        assert!(!contents.contains("--find-links file:"), "{}", contents);
+    }
+}
+
+/// Writes a minimal pure Python wheel of `localpkg` into the given folder.
+fn write_local_wheel(dir: &std::path::Path) {
+    let files = [
+        ("localpkg/__init__.py", "VALUE = 42\n"),
+        (
+            "localpkg-1.0.0.dist-info/METADATA",
+            "Metadata-Version: 2.1\nName: localpkg\nVersion: 1.0.0\n",
+        ),
+        (
+            "localpkg-1.0.0.dist-info/WHEEL",
+            "Wheel-Version: 1.0\nGenerator: test\nRoot-Is-Purelib: true\nTag: py3-none-any\n",
+        ),
+        (
+            "localpkg-1.0.0.dist-info/RECORD",
+            "localpkg/__init__.py,,\nlocalpkg-1.0.0.dist-info/METADATA,,\n\
+             localpkg-1.0.0.dist-info/WHEEL,,\nlocalpkg-1.0.0.dist-info/RECORD,,\n",
+        ),
+    ];
+    fs::create_dir_all(dir).unwrap();
+    let file = fs::File::create(dir.join("localpkg-1.0.0-py3-none-any.whl")).unwrap();
+    let mut zip = zip::ZipWriter::new(file);
+    for (name, contents) in files {
+        zip.start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+}
+
+#[test]
+fn test_install_from_relative_find_links() {
+    let space = Space::new();
+    space.init("my-project");
+    write_local_wheel(&space.project_path().join("wheels"));
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deps = Array::new();
+        deps.push("localpkg==1.0.0");
+        doc["project"]["dependencies"] = value(deps);
+        let mut sources = ArrayOfTables::new();
+        let mut source = Table::new();
+        source["name"] = value("wheelhouse");
+        source["type"] = value("find-links");
+        source["url"] = value("file:wheels");
+        sources.push(source);
+        doc["tool"]["rye"]["sources"] = value(sources.into_array());
+        doc["tool"]["rye"]["lock-with-sources"] = value(true);
+    });
+
+    let output = space.rye_cmd().arg("sync").output().unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let lockfile = space.read_string("requirements.lock");
+    assert!(lockfile.contains("--find-links wheels\n"), "{}", lockfile);
+    assert!(lockfile.contains("\nlocalpkg==1.0.0\n"), "{}", lockfile);
+
+    rye_cmd_snapshot!(space.rye_cmd()
+        .arg("run")
+        .arg("python")
+        .arg("-c")
+        .arg("import localpkg; print(localpkg.VALUE)"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    42
+
+    ----- stderr -----
+    "###);
+}
+
+#[test]
+fn test_lock_requirements_in() {
+    let space = Space::new();
+    space.write("requirements.in", "anyio==4.0.0\n");
+
+    let status = space
+        .rye_cmd()
+        .arg("lock")
+        .arg("--requirements-in")
+        .arg("requirements.in")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert!(!space.project_path().join("pyproject.toml").is_file());
+
+    assert_snapshot!(space.read_string("requirements.txt"), @r###"
+    # generated by rye
+    # use `rye lock` or `rye sync` to update this lockfile
+    #
+    # last locked with the following flags:
+    #   pre: false
+    #   features: []
+    #   all-features: false
+    #   with-sources: false
+    #   generate-hashes: false
+    #   universal: false
+
+    anyio==4.0.0
+    idna==3.4
+        # via anyio
+    sniffio==1.3.0
+        # via anyio
+    "###);
+
+    // the input is never overwritten
+    rye_cmd_snapshot!(space.rye_cmd().arg("lock").arg("--requirements-in").arg("requirements.txt"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: refusing to overwrite '[TEMP_PATH]/project/requirements.txt' with its own lockfile, pass --output
+    "###);
+}
+
+#[test]
+fn test_lock_uv_arg() {
+    let space = Space::new();
+    space.write("requirements.in", "idna>=3.0\n");
+
+    let output = space
+        .rye_cmd()
+        .arg("lock")
+        .arg("--requirements-in")
+        .arg("requirements.in")
+        .arg("--uv-arg=--resolution=lowest-direct")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("passing unsupported arguments to uv: --resolution=lowest-direct"));
+    assert!(space
+        .read_string("requirements.txt")
+        .contains("\nidna==3.0\n"));
+}
+
+#[test]
+fn test_sync_uv_arg_scopes() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deps = Array::new();
+        deps.push("idna>=3.0");
+        doc["project"]["dependencies"] = value(deps);
+    });
+
+    // `pip sync` does not know about resolutions, unprefixed arguments only go
+    // to the resolver while prefixed ones go to the named subcommand.
+    let output = space
+        .rye_cmd()
+        .arg("sync")
+        .arg("--uv-arg=--resolution=lowest-direct")
+        .arg("--uv-arg=sync:--link-mode=copy")
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(space
+        .read_string("requirements.lock")
+        .contains("\nidna==3.0\n"));
+}
+
+#[test]
+fn test_migrate_lock() {
+    let space = Space::new();
+    space.write(
+        "pyproject.toml",
+        r#"[project]
+name = "my-project"
+version = "0.1.0"
+dependencies = ["click>=8"]
+"#,
+    );
+    space.write(
+        "poetry.lock",
+        r#"[[package]]
+name = "click"
+version = "8.1.7"
+optional = false
+python-versions = ">=3.7"
+groups = ["main"]
+files = [
+    {file = "click-8.1.7.tar.gz", hash = "sha256:ca9853ad459e787e2192211578cc907e7594e294c7ccc834310722b41b9ca6de"},
+]
+
+[[package]]
+name = "pytest"
+version = "8.0.0"
+optional = false
+python-versions = ">=3.8"
+groups = ["dev"]
+files = [
+    {file = "pytest-8.0.0.tar.gz", hash = "sha256:249b1b0864530ba251b7438274c4d251c58d868edaaec8762893ad4a0d71c36c"},
+]
+"#,
+    );
+
+    rye_cmd_snapshot!(space.rye_cmd().arg("migrate-lock").arg("poetry.lock"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Converted 1 package(s) from poetry.lock into [TEMP_PATH]/project/requirements.lock
+
+    ----- stderr -----
+    "###);
+    let lockfile = space.read_string("requirements.lock");
+    assert!(lockfile.contains("#   generate-hashes: true\n"));
+    assert!(lockfile.contains("\n-e file:.\nclick==8.1.7 \\\n    --hash=sha256:ca9853ad459e787e2192211578cc907e7594e294c7ccc834310722b41b9ca6de\n"));
+    assert!(!lockfile.contains("pytest"));
+
+    // pins that do not satisfy the project are rejected
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deps = Array::new();
+        deps.push("click>=9");
+        doc["project"]["dependencies"] = value(deps);
+    });
+    rye_cmd_snapshot!(space.rye_cmd().arg("migrate-lock").arg("poetry.lock").arg("--dev").arg("--force"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: poetry.lock does not satisfy the dependencies of the project:
+      - click is locked at 8.1.7 which does not satisfy >=9
+    "###);
+    assert!(!space.project_path().join("requirements-dev.lock").exists());
+}
+
+#[test]
+fn test_lock_pins() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deps = Array::new();
+        deps.push("idna");
+        doc["project"]["dependencies"] = value(deps);
+        let mut pin = toml_edit::InlineTable::new();
+        pin.insert("version", "<3.5".into());
+        pin.insert("expires", "2000-01-01".into());
+        pin.insert("reason", "testing".into());
+        doc["tool"]["rye"]["pins"]["idna"] = value(pin);
+    });
+
+    let output = space
+        .rye_cmd()
+        .arg("lock")
+        .arg("--deny-expired-pins")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "expired pins in tool.rye.pins:\n  - idna<3.5 (expired 2000-01-01, reason: testing)"
+    ));
+
+    let output = space.rye_cmd().arg("lock").output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("expired pin in tool.rye.pins: idna<3.5 (expired 2000-01-01, reason: testing)"));
+    assert!(space
+        .read_string("requirements.lock")
+        .contains("\nidna==3.4\n"));
+}
+
+#[test]
+fn test_lock_dev_presets() {
+    let space = Space::new();
+    space.init("my-project");
+    space.write(
+        "presets/standard.toml",
+        r#"dev-dependencies = ["idna==3.4"]
+
+[scripts]
+hello = { cmd = "python -c 'print(1)'", help = "Say hello" }
+"#,
+    );
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut presets = Array::new();
+        presets.push("presets/standard.toml");
+        doc["tool"]["rye"]["dev-presets"] = value(presets);
+    });
+
+    let output = space.rye_cmd().arg("lock").output().unwrap();
+    assert!(output.status.success());
+    let dev_lock = space.read_string("requirements-dev.lock");
+    assert!(dev_lock.contains("#   dev-preset: presets/standard.toml (sha256:"));
+    assert!(dev_lock.contains("\nidna==3.4\n"));
+    let lock = space.read_string("requirements.lock");
+    assert!(!lock.contains("dev-preset"));
+    assert!(!lock.contains("idna"));
+
+    let output = space.rye_cmd().arg("run").arg("--list").output().unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout).contains("hello"));
+}
+
+#[test]
+fn test_sync_offline_requires_vendor() {
+    let space = Space::new();
+    space.init("my-project");
+    let status = space.rye_cmd().arg("lock").status().unwrap();
+    assert!(status.success());
+
+    let output = space
+        .rye_cmd()
+        .arg("sync")
+        .arg("--offline")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("run `rye vendor` first"));
+}
+
+#[test]
+fn test_vendor_then_sync_offline() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deps = Array::new();
+        deps.push("idna==3.4");
+        doc["project"]["dependencies"] = value(deps);
+    });
+    let status = space.rye_cmd().arg("lock").status().unwrap();
+    assert!(status.success());
+
+    // files placed into the vendor folder by hand are kept
+    space.write("vendor/custom_pkg-1.0-py3-none-any.whl", "");
+    let output = space.rye_cmd().arg("vendor").output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let vendored = fs::read_dir(space.project_path().join("vendor"))
+        .unwrap()
+        .map(|x| x.unwrap().file_name().to_string_lossy().into_owned())
+        .collect::<Vec<_>>();
+    assert!(vendored.iter().any(|x| x.starts_with("idna-3.4")));
+    // the build backend of the project is vendored too
+    assert!(vendored.iter().any(|x| x.starts_with("hatchling-")));
+    assert!(vendored.contains(&"custom_pkg-1.0-py3-none-any.whl".to_string()));
+
+    let output = space
+        .rye_cmd()
+        .arg("sync")
+        .arg("--offline")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let status = space
+        .rye_cmd()
+        .arg("run")
+        .arg("python")
+        .arg("-c")
+        .arg("import idna, my_project")
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    // distributions vendored by rye are removed once they are no longer locked
+    space.edit_toml("pyproject.toml", |doc| {
+        doc["project"]["dependencies"] = value(Array::new());
+    });
+    let status = space.rye_cmd().arg("lock").status().unwrap();
+    assert!(status.success());
+    let output = space.rye_cmd().arg("vendor").output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    assert!(!space
+        .project_path()
+        .join("vendor/idna-3.4-py3-none-any.whl")
+        .exists());
+    assert!(space
+        .project_path()
+        .join("vendor/custom_pkg-1.0-py3-none-any.whl")
+        .is_file());
+}
+
+#[test]
+fn test_lock_emit_build_requirements() {
+    let space = Space::new();
+    space.init("my-project");
+    let status = space
+        .rye_cmd()
+        .arg("lock")
+        .arg("--emit-build-requirements")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let build_lock = space.read_string("build-requirements.lock");
+    assert!(build_lock.contains("\nhatchling=="));
+    assert!(!space.read_string("requirements.lock").contains("hatchling"));
+}
+
+#[test]
+fn test_sync_member_python_override() {
+    let space = Space::new();
+    space.init("foo");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut workspace_members = Array::new();
+        workspace_members.push(".");
+        workspace_members.push("legacy");
+        doc["tool"]["rye"]["workspace"]["members"] = value(workspace_members);
+    });
+    let status = space
+        .rye_cmd()
+        .arg("init")
+        .arg("-q")
+        .arg(space.project_path().join("legacy"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+    space.edit_toml("legacy/pyproject.toml", |doc| {
+        doc["tool"]["rye"]["python"] = value("3.11");
+        doc["project"]["requires-python"] = value(">= 3.8");
+    });
+
+    let status = space.rye_cmd().arg("sync").status().unwrap();
+    assert!(status.success());
+    assert!(space.project_path().join(".venv").is_dir());
+    assert!(space.project_path().join("legacy/.venv").is_dir());
+    assert!(!space.read_string("requirements.lock").contains("legacy"));
+    assert!(space
+        .read_string("requirements.legacy.lock")
+        .contains("-e file:legacy"));
+
+    let output = space
+        .rye_cmd()
+        .arg("run")
+        .arg("--package")
+        .arg("legacy")
+        .arg("python")
+        .arg("--version")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Python 3.11"));
+}
+
+#[test]
+fn test_lock_only_dev_with_stale_production_lockfile() {
+    let space = Space::new();
+    space.init("my-project");
+    let status = space.rye_cmd().arg("lock").status().unwrap();
+    assert!(status.success());
+    let dev_lock = space.read_string("requirements-dev.lock");
+
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut deps = Array::new();
+        deps.push("idna==3.4");
+        doc["project"]["dependencies"] = value(deps);
+    });
+
+    let output = space
+        .rye_cmd()
+        .arg("lock")
+        .arg("--only-dev")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("refusing to regenerate only one lockfile"));
+    assert!(stderr.contains("idna is not locked"));
+    assert_eq!(space.read_string("requirements-dev.lock"), dev_lock);
+}
+
+#[test]
+fn test_workspace_member_cache() {
+    let space = Space::new();
+    space.init("my-project");
+    space.edit_toml("pyproject.toml", |doc| {
+        let mut workspace_members = Array::new();
+        workspace_members.push(".");
+        workspace_members.push("pkgs/*");
+        doc["tool"]["rye"]["workspace"]["members"] = value(workspace_members);
+    });
+    let init_member = |name: &str| {
+        let status = space
+            .rye_cmd()
+            .arg("init")
+            .arg("-q")
+            .arg(space.project_path().join("pkgs").join(name))
+            .status()
+            .unwrap();
+        assert!(status.success());
+    };
+    init_member("a");
+
+    for cmd in ["sync", "lock"] {
+        let status = space.rye_cmd().arg(cmd).status().unwrap();
+        assert!(status.success());
+    }
+    let cache = space.venv_path().join("rye-workspace-members.json");
+    let cache_modified = || fs::metadata(&cache).unwrap().modified().unwrap();
+    let written = cache_modified();
+    assert!(space
+        .read_string("requirements.lock")
+        .contains("-e file:pkgs/a"));
+
+    // nothing changed, so the members are taken from the cache
+    let status = space.rye_cmd().arg("lock").status().unwrap();
+    assert!(status.success());
+    assert_eq!(cache_modified(), written);
+
+    // a new member folder invalidates the cache
+    init_member("b");
+    let status = space.rye_cmd().arg("lock").status().unwrap();
+    assert!(status.success());
+    assert_ne!(cache_modified(), written);
+    let lock = space.read_string("requirements.lock");
+    assert!(lock.contains("-e file:pkgs/a"));
+    assert!(lock.contains("-e file:pkgs/b"));
+}
@@ -149,3 +149,16 @@ fn test_basic_tool_behavior() {
     ----- stderr -----
     "###);
 }
+
+#[test]
+fn test_missing_pytest() {
+    let space = Space::new();
+    space.init("foo");
+
+    let output = space.rye_cmd().arg("test").output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains(
+        "pytest is not installed and not a dev dependency of the project. \
+         Run `rye add --dev pytest` or pass --install-missing."
+    ));
+}
@@ -1,4 +1,4 @@
-use crate::common::{rye_cmd_snapshot, Space};
+use crate::common::{get_bin, rye_cmd_snapshot, Space};
 
 mod common;
 
@@ -36,3 +36,85 @@ fn test_fetch() {
     ----- stderr -----
     "###);
 }
+
+#[test]
+fn test_shared_toolchain_dir_is_read_only() {
+    let space = Space::new();
+    let shared = space.project_path().join("shared");
+    space.write("shared/py/cpython@3.12.99", "/usr/bin/python3\n");
+
+    rye_cmd_snapshot!(space
+        .rye_cmd()
+        .env("RYE_TOOLCHAIN_DIR", &shared)
+        .arg("toolchain")
+        .arg("remove")
+        .arg("cpython@3.12.99"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: toolchain cpython@3.12.99 is in the shared toolchain store ([TEMP_PATH]/project/shared/py/cpython@3.12.99) and cannot be removed
+    "###);
+    assert!(shared.join("py").join("cpython@3.12.99").is_file());
+}
+
+#[test]
+fn test_fetch_pin_file() {
+    let space = Space::new();
+    space.write(
+        "pins/.python-version",
+        "# pinned for the docker build\n3.12.8\n",
+    );
+    let output = space
+        .rye_cmd()
+        .arg("fetch")
+        .arg("--pin-file")
+        .arg("pins/.python-version")
+        .arg("--verbose")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("resolves to cpython@3.12.8"));
+    assert!(stdout.contains("Python version already downloaded. Skipping."));
+
+    // without a project --project does not fall back to the default toolchain
+    let status = space
+        .rye_cmd()
+        .arg("fetch")
+        .arg("--project")
+        .status()
+        .unwrap();
+    assert!(!status.success());
+}
+
+#[test]
+fn test_list_check_updates_skips_registered() {
+    let space = Space::new();
+    let home = space.project_path().join("home");
+    // an outdated toolchain downloaded by rye
+    #[cfg(unix)]
+    space.write("home/py/cpython@3.8.1/bin/python3", "");
+    #[cfg(windows)]
+    space.write("home/py/cpython@3.8.1/python.exe", "");
+    // an outdated toolchain registered from elsewhere
+    space.write(
+        "home/py/cpython@3.8.2",
+        format!("{}\n", get_bin().display()),
+    );
+
+    rye_cmd_snapshot!(space
+        .rye_cmd()
+        .env("RYE_HOME", &home)
+        .arg("toolchain")
+        .arg("list")
+        .arg("--check-updates"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    cpython@3.8.1 -> cpython@3.8.20
+
+    ----- stderr -----
+    "###);
+}
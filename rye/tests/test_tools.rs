@@ -47,6 +47,75 @@ fn test_basic_tool_behavior() {
      + pycowsay==0.0.0.2
     "###);
 
+    // the requested interpreter is recorded to rebuild the tool later
+    let marker: serde_json::Value = serde_json::from_slice(
+        &fs::read(
+            space
+                .rye_home()
+                .join("tools")
+                .join("pycowsay")
+                .join("rye-venv.json"),
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(marker["tool"]["requirement"], "pycowsay");
+    assert_eq!(marker["tool"]["python"], "cpython@3.11");
+
+    // the installed versions are locked and can be installed again
+    let lockfile = space
+        .rye_home()
+        .join("tools")
+        .join("pycowsay")
+        .join("requirements.lock");
+    let contents = fs::read_to_string(&lockfile).unwrap();
+    assert!(contents.starts_with("# generated by rye\n# tool: pycowsay\n"));
+    assert!(contents.contains("pycowsay==0.0.0.2"));
+    let status = space
+        .rye_cmd()
+        .env("UV_CACHE_DIR", cache_dir.path())
+        .arg("install")
+        .arg("--locked")
+        .arg("--force")
+        .arg("pycowsay")
+        .arg("-p")
+        .arg("cpython@3.11")
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(fs::read_to_string(&lockfile).unwrap(), contents);
+
+    // a different requirement does not silently install the locked one
+    let output = space
+        .rye_cmd()
+        .env("UV_CACHE_DIR", cache_dir.path())
+        .arg("install")
+        .arg("--locked")
+        .arg("--force")
+        .arg("pycowsay==0.0.0.1")
+        .arg("-p")
+        .arg("cpython@3.11")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("the lockfile of pycowsay was created for pycowsay"));
+    assert_eq!(fs::read_to_string(&lockfile).unwrap(), contents);
+
+    rye_cmd_snapshot!(
+        space.rye_cmd()
+            .env("UV_CACHE_DIR", cache_dir.path())
+            .arg("tools")
+            .arg("lock")
+            .arg("--all"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Locked pycowsay ([RYE_HOME]/tools/pycowsay/requirements.lock)
+
+    ----- stderr -----
+    "###);
+
     rye_cmd_snapshot!(
         space.rye_cmd()
             .env("UV_CACHE_DIR", cache_dir.path())
@@ -104,3 +173,232 @@ fn test_basic_tool_behavior() {
 
     assert!(!space.rye_home().join("tools").join("pycowsay").is_dir());
 }
+
+#[test]
+fn test_install_multiple_rejects_specifiers() {
+    let space = Space::new();
+
+    rye_cmd_snapshot!(
+        space.rye_cmd()
+            .arg("tools")
+            .arg("install")
+            .arg("pycowsay")
+            .arg("ruff")
+            .arg("--extra-requirement")
+            .arg("colorama"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: path/url/git/features/hash/extra-requirement is not compatible with passing multiple requirements: expected one requirement.
+    "###);
+}
+
+#[test]
+fn test_tool_shim_conflicts() {
+    let space = Space::new();
+    let cache_dir = TempDir::new_in(space.rye_home()).unwrap();
+
+    let shim_dir = space.rye_home().join("shims");
+    let shim = shim_dir.join("pycowsay").with_extension(EXE_EXTENSION);
+    let suffixed_shim = shim_dir
+        .join("pycowsay@pycowsay")
+        .with_extension(EXE_EXTENSION);
+    fs::remove_dir_all(space.rye_home().join("tools")).ok();
+    fs::remove_file(&suffixed_shim).ok();
+    fs::create_dir_all(&shim_dir).unwrap();
+    fs::write(&shim, "").unwrap();
+
+    rye_cmd_snapshot!(
+        space.rye_cmd()
+            .env("UV_CACHE_DIR", cache_dir.path())
+            .arg("tools")
+            .arg("install")
+            .arg("pycowsay")
+            .arg("-p")
+            .arg("cpython@3.11"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    Using Python 3.11.11 environment at: [RYE_HOME]/tools/pycowsay
+    Resolved 1 package in [EXECUTION_TIME]
+    Prepared 1 package in [EXECUTION_TIME]
+    Installed 1 package in [EXECUTION_TIME]
+     + pycowsay==0.0.0.2
+    error: script pycowsay of pycowsay conflicts with an existing shim provided by an unknown program. Use --suffix to install it as pycowsay@pycowsay instead
+    "###);
+    assert!(!space.rye_home().join("tools").join("pycowsay").is_dir());
+
+    rye_cmd_snapshot!(
+        space.rye_cmd()
+            .env("UV_CACHE_DIR", cache_dir.path())
+            .arg("tools")
+            .arg("install")
+            .arg("pycowsay")
+            .arg("--suffix")
+            .arg("-p")
+            .arg("cpython@3.11"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+
+    Installed scripts:
+      - pycowsay@pycowsay
+
+    ----- stderr -----
+    Using Python 3.11.11 environment at: [RYE_HOME]/tools/pycowsay
+    Resolved 1 package in [EXECUTION_TIME]
+    Installed 1 package in [EXECUTION_TIME]
+     + pycowsay==0.0.0.2
+    "###);
+
+    rye_cmd_snapshot!(
+        space.rye_cmd()
+            .arg("tools")
+            .arg("which")
+            .arg("pycowsay@pycowsay"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    pycowsay
+
+    ----- stderr -----
+    "###);
+
+    rye_cmd_snapshot!(
+        space.rye_cmd()
+            .arg("tools")
+            .arg("which")
+            .arg("pycowsay"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    unknown
+
+    ----- stderr -----
+    "###);
+
+    rye_cmd_snapshot!(
+        space.rye_cmd()
+            .arg("tools")
+            .arg("uninstall")
+            .arg("pycowsay"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    Uninstalled pycowsay
+
+    ----- stderr -----
+    "###);
+
+    assert!(!suffixed_shim.exists());
+    assert!(shim.is_file());
+    fs::remove_file(&shim).ok();
+}
+
+#[test]
+fn test_install_and_upgrade_multiple() {
+    let space = Space::new();
+    let cache_dir = TempDir::new_in(space.rye_home()).unwrap();
+
+    let shim_dir = space.rye_home().join("shims");
+    fs::remove_dir_all(space.rye_home().join("tools")).ok();
+    for script in ["pycowsay", "cowsay"] {
+        fs::remove_file(shim_dir.join(script).with_extension(EXE_EXTENSION)).ok();
+    }
+
+    let output = space
+        .rye_cmd()
+        .env("UV_CACHE_DIR", cache_dir.path())
+        .arg("tools")
+        .arg("install")
+        .arg("pycowsay")
+        .arg("cowsay==6.0")
+        .arg("-p")
+        .arg("cpython@3.11")
+        .arg("--jobs")
+        .arg("2")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "pycowsay  installed (pycowsay)",
+            "cowsay    installed (cowsay)"
+        ]
+    );
+    for script in ["pycowsay", "cowsay"] {
+        assert!(shim_dir
+            .join(script)
+            .with_extension(EXE_EXTENSION)
+            .is_file());
+    }
+
+    let lockfile = space
+        .rye_home()
+        .join("tools")
+        .join("cowsay")
+        .join("requirements.lock");
+    assert!(fs::read_to_string(&lockfile)
+        .unwrap()
+        .contains("cowsay==6.0"));
+
+    // the recorded requirement is kept, so the pinned tool stays on its version
+    let output = space
+        .rye_cmd()
+        .env("UV_CACHE_DIR", cache_dir.path())
+        .arg("tools")
+        .arg("upgrade")
+        .arg("--all")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(
+        lines,
+        [
+            "cowsay    upgraded (cowsay)",
+            "pycowsay  upgraded (pycowsay)"
+        ]
+    );
+    assert!(fs::read_to_string(&lockfile)
+        .unwrap()
+        .contains("cowsay==6.0"));
+    for script in ["pycowsay", "cowsay"] {
+        assert!(shim_dir
+            .join(script)
+            .with_extension(EXE_EXTENSION)
+            .is_file());
+    }
+
+    rye_cmd_snapshot!(
+        space.rye_cmd()
+            .arg("tools")
+            .arg("upgrade")
+            .arg("not-installed"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: not-installed is not installed
+    "###);
+
+    for tool in ["pycowsay", "cowsay"] {
+        let status = space
+            .rye_cmd()
+            .arg("tools")
+            .arg("uninstall")
+            .arg(tool)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+}
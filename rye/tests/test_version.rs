@@ -1,3 +1,5 @@
+use insta::{assert_snapshot, Settings};
+
 use crate::common::{rye_cmd_snapshot, Space};
 
 mod common;
@@ -47,3 +49,56 @@ fn test_version_bump() {
     ----- stderr -----
     "###);
 }
+
+#[test]
+fn test_version_changelog() {
+    let mut settings = Settings::clone_current();
+    settings.add_filter(r"\d{4}-\d{2}-\d{2}", "[DATE]");
+    let _guard = settings.bind_to_scope();
+
+    let space = Space::new();
+    space.init("my-project");
+    space.write(
+        "CHANGELOG.md",
+        "# Changelog\n\n## Unreleased\n\n* Added a feature\n\n## 0.1.0\n\n* Initial release\n",
+    );
+    rye_cmd_snapshot!(space.rye_cmd().arg("version").arg("--bump").arg("minor").arg("--changelog").arg("CHANGELOG.md"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    version bumped to 0.2.0
+
+    ----- stderr -----
+    "###);
+    assert_snapshot!(space.read_string("CHANGELOG.md"), @r###"
+    # Changelog
+
+    ## Unreleased
+
+    ## 0.2.0 - [DATE]
+
+    * Added a feature
+
+    ## 0.1.0
+
+    * Initial release
+    "###);
+
+    // an existing section leaves the version alone
+    rye_cmd_snapshot!(space.rye_cmd().arg("version").arg("0.1.0").arg("--changelog").arg("CHANGELOG.md"), @r###"
+    success: false
+    exit_code: 1
+    ----- stdout -----
+
+    ----- stderr -----
+    error: changelog already has a section for version 0.1.0
+    "###);
+    rye_cmd_snapshot!(space.rye_cmd().arg("version"), @r###"
+    success: true
+    exit_code: 0
+    ----- stdout -----
+    0.2.0
+
+    ----- stderr -----
+    "###);
+}